@@ -1,17 +1,18 @@
 use crate::{
     error::ContractError,
     storage::{
-        get_config, get_lp_vec, is_initialized, save_config, save_lp_vec,
-        save_lp_vec_with_tuple_as_key, set_initialized, Asset, Config, DataKey, LiquidityPoolInfo,
-        LpPortfolio, PairTupleKey, StakePortfolio, StakedResponse, UserPortfolio,
+        get_config, get_legacy_pool_for_pair, get_lp_vec, get_pools_for_pair, is_initialized,
+        remove_legacy_pool_for_pair, save_config, save_lp_vec, save_lp_vec_with_tuple_as_key,
+        set_initialized, Asset, Config, DataKey, LiquidityPoolInfo, LpPortfolio, PoolWasmHashes,
+        StakePortfolio, StakedResponse, UserPortfolio,
     },
-    utils::{deploy_and_initialize_multihop_contract, deploy_lp_contract},
+    utils::{deploy_and_initialize_multihop_contract, deploy_lp_contract, query_pool_type},
 };
-use phoenix::utils::{LiquidityPoolInitInfo, PoolType, StakeInitInfo, TokenInitInfo};
+use phoenix::utils::{LiquidityPoolInitInfo, PoolInitInfo, PoolType, StakeInitInfo, TokenInitInfo};
 use phoenix::validate_bps;
 use soroban_sdk::{
     contract, contractimpl, contractmeta, log, panic_with_error, vec, Address, BytesN, Env,
-    IntoVal, String, Symbol, Val, Vec,
+    IntoVal, InvokeError, String, Symbol, Val, Vec,
 };
 
 // Metadata that is added on to the WASM custom section
@@ -27,23 +28,20 @@ pub trait FactoryTrait {
         env: Env,
         admin: Address,
         multihop_wasm_hash: BytesN<32>,
-        lp_wasm_hash: BytesN<32>,
-        stable_wasm_hash: BytesN<32>,
+        pool_wasm_hashes: PoolWasmHashes,
         stake_wasm_hash: BytesN<32>,
         token_wasm_hash: BytesN<32>,
         whitelisted_accounts: Vec<Address>,
         lp_token_decimals: u32,
     );
 
-    #[allow(clippy::too_many_arguments)]
     fn create_liquidity_pool(
         env: Env,
         sender: Address,
         lp_init_info: LiquidityPoolInitInfo,
         share_token_name: String,
         share_token_symbol: String,
-        pool_type: PoolType,
-        amp: Option<u64>,
+        pool_init_info: PoolInitInfo,
         default_slippage_bps: i64,
     ) -> Address;
 
@@ -61,6 +59,19 @@ pub trait FactoryTrait {
         token_wasm_hash: Option<BytesN<32>>,
     );
 
+    // Pauses every pool created by the factory. Withdrawals from the pools stay available.
+    // Returns the pools that rejected the call, e.g. pools upgraded from a version that doesn't
+    // know its factory, those have to be paused by their admin
+    fn pause_all_pools(env: Env, sender: Address) -> Vec<Address>;
+
+    // Resumes every pool created by the factory. Returns the pools that rejected the call
+    fn unpause_all_pools(env: Env, sender: Address) -> Vec<Address>;
+
+    // Registers pools created before a pair could have several pools under the current storage
+    // layout, which keeps the pool type next to every pool of a pair. The previous layout didn't
+    // record the type, so it is read from the config of every pool. Run once after the upgrade
+    fn migrate_pools_for_pairs(env: Env, sender: Address, pools: Vec<Address>);
+
     fn query_pools(env: Env) -> Vec<Address>;
 
     fn query_pool_details(env: Env, pool_address: Address) -> LiquidityPoolInfo;
@@ -69,6 +80,12 @@ pub trait FactoryTrait {
 
     fn query_for_pool_by_token_pair(env: Env, token_a: Address, token_b: Address) -> Address;
 
+    fn query_pools_for_pair(
+        env: Env,
+        token_a: Address,
+        token_b: Address,
+    ) -> Vec<(PoolType, Address)>;
+
     fn get_admin(env: Env) -> Address;
 
     fn get_config(env: Env) -> Config;
@@ -83,8 +100,7 @@ impl FactoryTrait for Factory {
         env: Env,
         admin: Address,
         multihop_wasm_hash: BytesN<32>,
-        lp_wasm_hash: BytesN<32>,
-        stable_wasm_hash: BytesN<32>,
+        pool_wasm_hashes: PoolWasmHashes,
         stake_wasm_hash: BytesN<32>,
         token_wasm_hash: BytesN<32>,
         whitelisted_accounts: Vec<Address>,
@@ -113,8 +129,7 @@ impl FactoryTrait for Factory {
             Config {
                 admin: admin.clone(),
                 multihop_address,
-                lp_wasm_hash,
-                stable_wasm_hash,
+                pool_wasm_hashes,
                 stake_wasm_hash,
                 token_wasm_hash,
                 whitelisted_accounts,
@@ -128,19 +143,17 @@ impl FactoryTrait for Factory {
             .publish(("initialize", "LP factory contract"), admin);
     }
 
-    #[allow(clippy::too_many_arguments)]
     fn create_liquidity_pool(
         env: Env,
         sender: Address,
         lp_init_info: LiquidityPoolInitInfo,
         share_token_name: String,
         share_token_symbol: String,
-        pool_type: PoolType,
-        amp: Option<u64>,
+        pool_init_info: PoolInitInfo,
         default_slippage_bps: i64,
     ) -> Address {
         sender.require_auth();
-        validate_pool_info(&pool_type, &amp);
+        let pool_type = pool_init_info.pool_type();
 
         if !get_config(&env).whitelisted_accounts.contains(sender) {
             log!(
@@ -160,9 +173,12 @@ impl FactoryTrait for Factory {
         let stake_wasm_hash = config.stake_wasm_hash;
         let token_wasm_hash = config.token_wasm_hash;
 
-        let pool_hash = match pool_type {
-            PoolType::Xyk => config.lp_wasm_hash,
-            PoolType::Stable => config.stable_wasm_hash,
+        let pool_hash = config.pool_wasm_hashes.get(pool_type);
+
+        // the coins of a multi asset pool after token A and token B
+        let additional_tokens = match &pool_init_info {
+            PoolInitInfo::StableMulti(_, info) => info.additional_tokens.clone(),
+            _ => Vec::new(&env),
         };
 
         let lp_contract_address = deploy_lp_contract(
@@ -170,6 +186,8 @@ impl FactoryTrait for Factory {
             pool_hash,
             &lp_init_info.token_init_info.token_a,
             &lp_init_info.token_init_info.token_b,
+            &additional_tokens,
+            pool_type,
         );
 
         validate_bps!(
@@ -193,12 +211,24 @@ impl FactoryTrait for Factory {
         )
             .into_val(&env);
 
-        if let PoolType::Xyk = pool_type {
-            init_fn_args.push_back(default_slippage_bps.into_val(&env));
-        }
-
-        if let PoolType::Stable = pool_type {
-            init_fn_args.push_back(amp.unwrap().into_val(&env));
+        match pool_init_info {
+            PoolInitInfo::Xyk => {
+                init_fn_args.push_back(default_slippage_bps.into_val(&env));
+            }
+            PoolInitInfo::Stable(amp) => {
+                init_fn_args.push_back(amp.into_val(&env));
+            }
+            PoolInitInfo::Concentrated(info) => {
+                init_fn_args.push_back(info.into_val(&env));
+            }
+            PoolInitInfo::Weighted(info) => {
+                init_fn_args.push_back(default_slippage_bps.into_val(&env));
+                init_fn_args.push_back(info.into_val(&env));
+            }
+            PoolInitInfo::StableMulti(amp, info) => {
+                init_fn_args.push_back(amp.into_val(&env));
+                init_fn_args.push_back(info.into_val(&env));
+            }
         }
 
         env.invoke_contract::<Val>(&lp_contract_address, &init_fn, init_fn_args);
@@ -208,9 +238,23 @@ impl FactoryTrait for Factory {
         lp_vec.push_back(lp_contract_address.clone());
 
         save_lp_vec(&env, lp_vec);
-        let token_a = &lp_init_info.token_init_info.token_a;
-        let token_b = &lp_init_info.token_init_info.token_b;
-        save_lp_vec_with_tuple_as_key(&env, (token_a, token_b), &lp_contract_address);
+        let mut tokens = vec![
+            &env,
+            lp_init_info.token_init_info.token_a,
+            lp_init_info.token_init_info.token_b,
+        ];
+        tokens.append(&additional_tokens);
+        // a multi asset pool is found by every pair of its coins
+        for (index, token_a) in tokens.iter().enumerate() {
+            for token_b in tokens.iter().skip(index + 1) {
+                save_lp_vec_with_tuple_as_key(
+                    &env,
+                    (&token_a, &token_b),
+                    pool_type,
+                    &lp_contract_address,
+                );
+            }
+        }
 
         env.events()
             .publish(("create", "liquidity_pool"), &lp_contract_address);
@@ -272,7 +316,10 @@ impl FactoryTrait for Factory {
         save_config(
             &env,
             Config {
-                lp_wasm_hash: lp_wasm_hash.unwrap_or(config.lp_wasm_hash),
+                pool_wasm_hashes: PoolWasmHashes {
+                    xyk: lp_wasm_hash.unwrap_or(config.pool_wasm_hashes.xyk.clone()),
+                    ..config.pool_wasm_hashes.clone()
+                },
                 stake_wasm_hash: stake_wasm_hash.unwrap_or(config.stake_wasm_hash),
                 token_wasm_hash: token_wasm_hash.unwrap_or(config.token_wasm_hash),
                 ..config
@@ -280,6 +327,90 @@ impl FactoryTrait for Factory {
         );
     }
 
+    fn pause_all_pools(env: Env, sender: Address) -> Vec<Address> {
+        sender.require_auth();
+
+        if get_config(&env).admin != sender {
+            log!(&env, "Factory: Pause all pools: You are not authorized!");
+            panic_with_error!(&env, ContractError::NotAuthorized);
+        };
+
+        let failed_pools = invoke_on_all_pools(&env, "pause");
+
+        env.events().publish(("pause_all_pools", "sender"), sender);
+        env.events()
+            .publish(("pause_all_pools", "pools_count"), get_lp_vec(&env).len());
+        env.events()
+            .publish(("pause_all_pools", "failed_pools"), failed_pools.clone());
+
+        failed_pools
+    }
+
+    fn unpause_all_pools(env: Env, sender: Address) -> Vec<Address> {
+        sender.require_auth();
+
+        if get_config(&env).admin != sender {
+            log!(&env, "Factory: Unpause all pools: You are not authorized!");
+            panic_with_error!(&env, ContractError::NotAuthorized);
+        };
+
+        let failed_pools = invoke_on_all_pools(&env, "unpause");
+
+        env.events()
+            .publish(("unpause_all_pools", "sender"), sender);
+        env.events()
+            .publish(("unpause_all_pools", "pools_count"), get_lp_vec(&env).len());
+        env.events()
+            .publish(("unpause_all_pools", "failed_pools"), failed_pools.clone());
+
+        failed_pools
+    }
+
+    fn migrate_pools_for_pairs(env: Env, sender: Address, pools: Vec<Address>) {
+        sender.require_auth();
+
+        if get_config(&env).admin != sender {
+            log!(
+                &env,
+                "Factory: Migrate pools for pairs: You are not authorized!"
+            );
+            panic_with_error!(&env, ContractError::NotAuthorized);
+        };
+
+        let lp_vec = get_lp_vec(&env);
+        for pool in pools {
+            if !lp_vec.contains(&pool) {
+                log!(
+                    &env,
+                    "Factory: Migrate pools for pairs: Pool was not created by the factory"
+                );
+                panic_with_error!(&env, ContractError::LiquidityPoolNotFound);
+            }
+
+            let Some(pool_type) = query_pool_type(&env, &pool) else {
+                log!(
+                    &env,
+                    "Factory: Migrate pools for pairs: Pool config has no known pool type"
+                );
+                panic_with_error!(&env, ContractError::PoolTypeNotFound);
+            };
+
+            let info: LiquidityPoolInfo = env.invoke_contract(
+                &pool,
+                &Symbol::new(&env, "query_pool_info_for_factory"),
+                Vec::new(&env),
+            );
+            let token_a = info.pool_response.asset_a.address;
+            let token_b = info.pool_response.asset_b.address;
+
+            save_lp_vec_with_tuple_as_key(&env, (&token_a, &token_b), pool_type, &pool);
+            remove_legacy_pool_for_pair(&env, &token_a, &token_b);
+        }
+
+        env.events()
+            .publish(("migrate_pools_for_pairs", "sender"), sender);
+    }
+
     fn query_pools(env: Env) -> Vec<Address> {
         get_lp_vec(&env)
     }
@@ -310,21 +441,12 @@ impl FactoryTrait for Factory {
     }
 
     fn query_for_pool_by_token_pair(env: Env, token_a: Address, token_b: Address) -> Address {
-        let pool_result: Option<Address> = env.storage().persistent().get(&PairTupleKey {
-            token_a: token_a.clone(),
-            token_b: token_b.clone(),
-        });
-
-        if let Some(addr) = pool_result {
+        // when there is more than one pool for the pair, the first created one is returned
+        if let Some((_, addr)) = get_pools_for_pair(&env, &token_a, &token_b).first() {
             return addr;
         }
-
-        let reverted_pool_resul: Option<Address> = env.storage().persistent().get(&PairTupleKey {
-            token_a: token_b,
-            token_b: token_a,
-        });
-
-        if let Some(addr) = reverted_pool_resul {
+        // pairs of a factory that wasn't migrated yet still have their pool in the old layout
+        if let Some(addr) = get_legacy_pool_for_pair(&env, &token_a, &token_b) {
             return addr;
         }
 
@@ -335,6 +457,14 @@ impl FactoryTrait for Factory {
         panic_with_error!(&env, ContractError::LiquidityPoolNotFound);
     }
 
+    fn query_pools_for_pair(
+        env: Env,
+        token_a: Address,
+        token_b: Address,
+    ) -> Vec<(PoolType, Address)> {
+        get_pools_for_pair(&env, &token_a, &token_b)
+    }
+
     fn get_admin(env: Env) -> Address {
         get_config(&env).admin
     }
@@ -358,6 +488,20 @@ impl FactoryTrait for Factory {
                 Vec::new(&env),
             );
 
+            let pool_type = get_pools_for_pair(
+                &env,
+                &response.pool_response.asset_a.address,
+                &response.pool_response.asset_b.address,
+            )
+            .iter()
+            .find(|(_, pool)| *pool == address)
+            .map(|(pool_type, _)| pool_type);
+
+            // concentrated positions are not share tokens, so they are not part of the portfolio
+            if pool_type == Some(PoolType::Concentrated) {
+                continue;
+            }
+
             // get the lp share token balance for the user
             // if the user has any liquidity tokens in the pool add to the lp_portfolio
             let lp_share_balance: i128 = env.invoke_contract(
@@ -378,18 +522,23 @@ impl FactoryTrait for Factory {
             let total_lp_share_for_user = lp_share_balance + sum_of_lp_share_staked;
 
             // query the balance of the liquidity tokens
-            let (asset_a, asset_b) = env.invoke_contract::<(Asset, Asset)>(
-                &address,
-                &Symbol::new(&env, "query_share"),
-                vec![&env, total_lp_share_for_user.into_val(&env)],
-            );
+            let query_share_fn = Symbol::new(&env, "query_share");
+            let query_share_args = vec![&env, total_lp_share_for_user.into_val(&env)];
+            let assets = if pool_type == Some(PoolType::StableMulti) {
+                env.invoke_contract::<Vec<Asset>>(&address, &query_share_fn, query_share_args)
+            } else {
+                let (asset_a, asset_b) = env.invoke_contract::<(Asset, Asset)>(
+                    &address,
+                    &query_share_fn,
+                    query_share_args,
+                );
+                vec![&env, asset_a, asset_b]
+            };
 
             // we add only liquidity pools that the user has staked to to his portfolio
             if total_lp_share_for_user > 0 {
                 // add to the lp_portfolio
-                lp_portfolio.push_back(LpPortfolio {
-                    assets: (asset_a, asset_b),
-                });
+                lp_portfolio.push_back(LpPortfolio { assets });
             }
 
             // make a call towards the stake contract to check the staked amount
@@ -428,6 +577,25 @@ impl Factory {
     }
 }
 
+/// Calls `function` on every pool with the factory as the sender, the pools accept the factory
+/// that deployed them as a pauser. A pool rejecting the call doesn't stop the others, the
+/// rejecting pools are returned instead.
+fn invoke_on_all_pools(env: &Env, function: &str) -> Vec<Address> {
+    let function = Symbol::new(env, function);
+    let mut failed_pools = Vec::new(env);
+    for lp_contract_address in get_lp_vec(env).iter() {
+        let args: Vec<Val> = (env.current_contract_address(),).into_val(env);
+        if env
+            .try_invoke_contract::<Val, InvokeError>(&lp_contract_address, &function, args)
+            .is_err()
+        {
+            failed_pools.push_back(lp_contract_address);
+        }
+    }
+
+    failed_pools
+}
+
 fn validate_token_info(
     env: &Env,
     token_init_info: &TokenInitInfo,
@@ -458,16 +626,6 @@ fn validate_token_info(
     }
 }
 
-fn validate_pool_info(pool_type: &PoolType, amp: &Option<u64>) {
-    match pool_type {
-        PoolType::Xyk => (),
-        PoolType::Stable => assert!(
-            amp.is_some(),
-            "Factory: Create Liquidity Pool: Amp must be set for stable pool"
-        ),
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -542,20 +700,4 @@ mod tests {
         };
         validate_token_info(&env, &token_init_info, &stake_init_info);
     }
-
-    #[test]
-    fn validate_pool_info_works() {
-        let amp = Some(10);
-        let stable = PoolType::Stable;
-        let xyk = PoolType::Xyk;
-
-        validate_pool_info(&stable, &amp);
-        validate_pool_info(&xyk, &None::<u64>);
-    }
-
-    #[test]
-    #[should_panic(expected = "Factory: Create Liquidity Pool: Amp must be set for stable pool")]
-    fn validate_pool_info_panics() {
-        validate_pool_info(&PoolType::Stable, &None::<u64>);
-    }
 }
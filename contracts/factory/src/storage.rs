@@ -1,3 +1,4 @@
+use phoenix::utils::PoolType;
 use soroban_sdk::{contracttype, Address, BytesN, ConversionError, Env, TryFromVal, Val, Vec};
 
 #[derive(Clone, Copy)]
@@ -8,6 +9,8 @@ pub enum DataKey {
     Initialized = 3,
 }
 
+/// Key under which factories before several pools per pair stored the single pool of a pair.
+/// Those entries are moved to `PairKey` with `migrate_pools_for_pairs`.
 #[derive(Clone)]
 #[contracttype]
 pub struct PairTupleKey {
@@ -15,6 +18,13 @@ pub struct PairTupleKey {
     pub(crate) token_b: Address,
 }
 
+#[derive(Clone)]
+#[contracttype]
+pub enum PairKey {
+    /// The pools of every type registered for the two tokens
+    Pools(Address, Address),
+}
+
 impl TryFromVal<Env, DataKey> for Val {
     type Error = ConversionError;
 
@@ -28,14 +38,36 @@ impl TryFromVal<Env, DataKey> for Val {
 pub struct Config {
     pub admin: Address,
     pub multihop_address: Address,
-    pub lp_wasm_hash: BytesN<32>,
-    pub stable_wasm_hash: BytesN<32>,
+    pub pool_wasm_hashes: PoolWasmHashes,
     pub stake_wasm_hash: BytesN<32>,
     pub token_wasm_hash: BytesN<32>,
     pub whitelisted_accounts: Vec<Address>,
     pub lp_token_decimals: u32,
 }
 
+/// The wasm hashes of the pools deployed by the factory, one for every pool type
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PoolWasmHashes {
+    pub xyk: BytesN<32>,
+    pub stable: BytesN<32>,
+    pub concentrated: BytesN<32>,
+    pub weighted: BytesN<32>,
+    pub stable_multi: BytesN<32>,
+}
+
+impl PoolWasmHashes {
+    pub fn get(&self, pool_type: PoolType) -> BytesN<32> {
+        match pool_type {
+            PoolType::Xyk => self.xyk.clone(),
+            PoolType::Stable => self.stable.clone(),
+            PoolType::Concentrated => self.concentrated.clone(),
+            PoolType::Weighted => self.weighted.clone(),
+            PoolType::StableMulti => self.stable_multi.clone(),
+        }
+    }
+}
+
 #[contracttype]
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct UserPortfolio {
@@ -46,7 +78,8 @@ pub struct UserPortfolio {
 #[contracttype]
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct LpPortfolio {
-    pub assets: (Asset, Asset),
+    /// The share of every asset of the pool, two for all pools but the stable multi pools
+    pub assets: Vec<Asset>,
 }
 
 #[contracttype]
@@ -125,18 +158,83 @@ pub fn save_lp_vec(env: &Env, lp_info: Vec<Address>) {
     env.storage().persistent().set(&DataKey::LpVec, &lp_info);
 }
 
+/// Registers `lp_address` for the given pair. Every pair keeps at most one pool per pool type,
+/// so creating a new pool of an already registered type replaces the previous entry.
 pub fn save_lp_vec_with_tuple_as_key(
     env: &Env,
     tuple_pool: (&Address, &Address),
+    pool_type: PoolType,
     lp_address: &Address,
 ) {
-    env.storage().persistent().set(
-        &PairTupleKey {
-            token_a: tuple_pool.0.clone(),
-            token_b: tuple_pool.1.clone(),
-        },
-        &lp_address,
-    )
+    let key = PairKey::Pools(tuple_pool.0.clone(), tuple_pool.1.clone());
+    let mut pools: Vec<(PoolType, Address)> = env
+        .storage()
+        .persistent()
+        .get(&key)
+        .unwrap_or(Vec::new(env));
+
+    if let Some(index) = pools
+        .iter()
+        .position(|(saved_type, _)| saved_type == pool_type)
+    {
+        pools.remove(index as u32);
+    }
+    pools.push_back((pool_type, lp_address.clone()));
+
+    env.storage().persistent().set(&key, &pools)
+}
+
+/// Returns all pools registered for the pair, regardless of the order of the tokens.
+pub fn get_pools_for_pair(
+    env: &Env,
+    token_a: &Address,
+    token_b: &Address,
+) -> Vec<(PoolType, Address)> {
+    let pools: Option<Vec<(PoolType, Address)>> = env
+        .storage()
+        .persistent()
+        .get(&PairKey::Pools(token_a.clone(), token_b.clone()));
+
+    pools
+        .or_else(|| {
+            env.storage()
+                .persistent()
+                .get(&PairKey::Pools(token_b.clone(), token_a.clone()))
+        })
+        .unwrap_or(Vec::new(env))
+}
+
+/// Returns the pool stored for the pair by a factory before several pools per pair, if it was
+/// not migrated yet.
+pub fn get_legacy_pool_for_pair(
+    env: &Env,
+    token_a: &Address,
+    token_b: &Address,
+) -> Option<Address> {
+    env.storage()
+        .persistent()
+        .get(&PairTupleKey {
+            token_a: token_a.clone(),
+            token_b: token_b.clone(),
+        })
+        .or_else(|| {
+            env.storage().persistent().get(&PairTupleKey {
+                token_a: token_b.clone(),
+                token_b: token_a.clone(),
+            })
+        })
+}
+
+pub fn remove_legacy_pool_for_pair(env: &Env, token_a: &Address, token_b: &Address) {
+    let storage = env.storage().persistent();
+    storage.remove(&PairTupleKey {
+        token_a: token_a.clone(),
+        token_b: token_b.clone(),
+    });
+    storage.remove(&PairTupleKey {
+        token_a: token_b.clone(),
+        token_b: token_a.clone(),
+    });
 }
 
 pub fn is_initialized(e: &Env) -> bool {
@@ -1,8 +1,8 @@
 use soroban_sdk::{testutils::Address as _, vec, Address, Env};
 
 use self::setup::{
-    deploy_factory_contract, install_lp_contract, install_multihop_wasm, install_stable_lp,
-    install_stake_wasm, install_token_wasm,
+    deploy_factory_contract, install_multihop_wasm, install_pool_wasm_hashes, install_stake_wasm,
+    install_token_wasm,
 };
 
 mod config;
@@ -20,8 +20,7 @@ fn test_deploy_factory_twice_should_fail() {
 
     let auth_user = Address::generate(&env);
     let multihop_wasm_hash = install_multihop_wasm(&env);
-    let lp_wasm_hash = install_lp_contract(&env);
-    let stable_wasm_hash = install_stable_lp(&env);
+    let pool_wasm_hashes = install_pool_wasm_hashes(&env);
     let stake_wasm_hash = install_stake_wasm(&env);
     let token_wasm_hash = install_token_wasm(&env);
 
@@ -30,8 +29,7 @@ fn test_deploy_factory_twice_should_fail() {
     factory.initialize(
         &admin,
         &multihop_wasm_hash,
-        &lp_wasm_hash,
-        &stable_wasm_hash,
+        &pool_wasm_hashes,
         &stake_wasm_hash,
         &token_wasm_hash,
         &vec![&env, auth_user.clone()],
@@ -40,8 +38,7 @@ fn test_deploy_factory_twice_should_fail() {
     factory.initialize(
         &admin,
         &multihop_wasm_hash,
-        &lp_wasm_hash,
-        &stable_wasm_hash,
+        &pool_wasm_hashes,
         &stake_wasm_hash,
         &token_wasm_hash,
         &vec![&env, auth_user.clone()],
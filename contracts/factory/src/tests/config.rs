@@ -1,13 +1,15 @@
 use super::setup::{
-    deploy_factory_contract, install_lp_contract, install_multihop_wasm, install_stable_lp,
-    install_stake_wasm, install_token_wasm, lp_contract,
+    concentrated_lp, deploy_factory_contract, install_multihop_wasm, install_pool_wasm_hashes,
+    install_stake_wasm, install_token_wasm, lp_contract, stable_multi_lp, weighted_lp,
 };
 use crate::{
     contract::{Factory, FactoryClient},
     tests::setup::{generate_lp_init_info, install_and_deploy_token_contract, stable_lp},
 };
 
-use phoenix::utils::PoolType;
+use phoenix::utils::{
+    ConcentratedPoolInitInfo, PoolInitInfo, PoolType, StableMultiPoolInitInfo, WeightedPoolInitInfo,
+};
 use soroban_sdk::{
     testutils::{arbitrary::std, Address as _},
     vec, Address, Env, String,
@@ -16,6 +18,7 @@ use soroban_sdk::{
 #[test]
 fn factory_successfully_inits_itself() {
     let env = Env::default();
+    env.budget().reset_unlimited();
     let admin = Address::generate(&env);
 
     let factory = deploy_factory_contract(&env, Some(admin.clone()));
@@ -27,6 +30,7 @@ fn factory_successfully_inits_itself() {
 fn factory_successfully_inits_multihop() {
     let env = Env::default();
     env.mock_all_auths();
+    env.budget().reset_unlimited();
 
     let admin = Address::generate(&env);
 
@@ -72,8 +76,7 @@ fn factory_successfully_inits_lp() {
         &lp_init_info,
         &String::from_str(&env, "Pool"),
         &String::from_str(&env, "PHO/BTC"),
-        &PoolType::Xyk,
-        &None::<u64>,
+        &PoolInitInfo::Xyk,
         &100i64,
     );
     let lp_contract_addr = factory.query_pools().get(0).unwrap();
@@ -89,6 +92,8 @@ fn factory_successfully_inits_lp() {
             max_allowed_slippage_bps: 5_000,
             max_allowed_spread_bps: 500,
             max_referral_bps: 5_000,
+            protocol_fee_share_bps: 10_000,
+            flash_loan_fee_bps: 0,
             pool_type: lp_contract::PairType::Xyk,
             share_token: share_token_address,
             stake_contract: stake_token_address,
@@ -145,8 +150,7 @@ fn factory_successfully_inits_stable_pool() {
         &lp_init_info,
         &String::from_str(&env, "Pool Stable"),
         &String::from_str(&env, "EURC/USDC"),
-        &PoolType::Stable,
-        &Some(10),
+        &PoolInitInfo::Stable(10),
         &100i64,
     );
 
@@ -162,6 +166,9 @@ fn factory_successfully_inits_stable_pool() {
             fee_recipient: user,
             max_allowed_slippage_bps: 5_000,
             max_allowed_spread_bps: 500,
+            max_referral_bps: 5_000,
+            protocol_fee_share_bps: 10_000,
+            flash_loan_fee_bps: 0,
             pool_type: stable_lp::PairType::Stable,
             share_token: share_token_address,
             stake_contract: stake_token_address,
@@ -172,6 +179,214 @@ fn factory_successfully_inits_stable_pool() {
     );
 }
 
+#[test]
+fn factory_successfully_inits_concentrated_pool() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let user = Address::generate(&env);
+
+    let mut token1 = install_and_deploy_token_contract(
+        &env,
+        &token_admin.clone(),
+        &7,
+        &String::from_str(&env, "EURO Coin"),
+        &String::from_str(&env, "EURC"),
+    );
+    let mut token2 = install_and_deploy_token_contract(
+        &env,
+        &token_admin.clone(),
+        &7,
+        &String::from_str(&env, "USD Coin"),
+        &String::from_str(&env, "USDC"),
+    );
+
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+
+    let factory = deploy_factory_contract(&env, Some(admin.clone()));
+
+    let lp_init_info = generate_lp_init_info(
+        token1.address.clone(),
+        token2.address.clone(),
+        Address::generate(&env),
+        admin.clone(),
+        user.clone(),
+    );
+
+    let pool = factory.create_liquidity_pool(
+        &admin,
+        &lp_init_info,
+        &String::from_str(&env, "Pool Concentrated"),
+        &String::from_str(&env, "EURC/USDC"),
+        &PoolInitInfo::Concentrated(ConcentratedPoolInitInfo {
+            tick_spacing: 60,
+            initial_tick: -120,
+        }),
+        &100i64,
+    );
+
+    let concentrated_client = concentrated_lp::Client::new(&env, &pool);
+    assert_eq!(
+        concentrated_client.query_config(),
+        concentrated_lp::Config {
+            token_a: token1.address.clone(),
+            token_b: token2.address.clone(),
+            pool_type: concentrated_lp::PairType::Concentrated,
+            total_fee_bps: 0,
+            fee_recipient: user.clone(),
+            max_allowed_spread_bps: 500,
+            max_referral_bps: 5_000,
+            protocol_fee_share_bps: 0,
+            tick_spacing: 60,
+        }
+    );
+    assert_eq!(concentrated_client.query_pool_state().tick, -120);
+
+    assert_eq!(
+        factory.query_pools_for_pair(&token1.address, &token2.address),
+        vec![&env, (PoolType::Concentrated, pool.clone())]
+    );
+    assert_eq!(factory.query_pool_details(&pool).pool_address, pool);
+
+    // positions are not share tokens, so the pool is left out of the portfolio
+    let portfolio = factory.query_user_portfolio(&user, &true);
+    assert!(portfolio.lp_portfolio.is_empty());
+    assert!(portfolio.stake_portfolio.is_empty());
+}
+
+#[test]
+fn factory_successfully_inits_weighted_pool() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let user = Address::generate(&env);
+
+    let mut token1 = install_and_deploy_token_contract(
+        &env,
+        &token_admin.clone(),
+        &7,
+        &String::from_str(&env, "Phoenix"),
+        &String::from_str(&env, "PHO"),
+    );
+    let mut token2 = install_and_deploy_token_contract(
+        &env,
+        &token_admin.clone(),
+        &7,
+        &String::from_str(&env, "USD Coin"),
+        &String::from_str(&env, "USDC"),
+    );
+
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+
+    let factory = deploy_factory_contract(&env, Some(admin.clone()));
+
+    let lp_init_info = generate_lp_init_info(
+        token1.address.clone(),
+        token2.address.clone(),
+        Address::generate(&env),
+        admin.clone(),
+        user.clone(),
+    );
+
+    let pool = factory.create_liquidity_pool(
+        &admin,
+        &lp_init_info,
+        &String::from_str(&env, "Pool Weighted"),
+        &String::from_str(&env, "PHO/USDC"),
+        &PoolInitInfo::Weighted(WeightedPoolInitInfo {
+            weight_a_bps: 8_000,
+            weight_b_bps: 2_000,
+        }),
+        &100i64,
+    );
+
+    let weighted_client = weighted_lp::Client::new(&env, &pool);
+    let config = weighted_client.query_config();
+    assert_eq!(config.pool_type, weighted_lp::PairType::Weighted);
+    assert_eq!((config.weight_a_bps, config.weight_b_bps), (8_000, 2_000));
+    assert_eq!(config.default_slippage_bps, 100);
+    assert_eq!(config.fee_recipient, user);
+
+    assert_eq!(
+        factory.query_pools_for_pair(&token1.address, &token2.address),
+        vec![&env, (PoolType::Weighted, pool.clone())]
+    );
+    assert_eq!(factory.query_pool_details(&pool).pool_address, pool);
+}
+
+#[test]
+fn factory_successfully_inits_stable_multi_pool() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+
+    let mut tokens: std::vec::Vec<Address> = (0..3)
+        .map(|_| env.register_stellar_asset_contract(admin.clone()))
+        .collect();
+    tokens.sort();
+
+    let factory = deploy_factory_contract(&env, Some(admin.clone()));
+
+    let lp_init_info = generate_lp_init_info(
+        tokens[0].clone(),
+        tokens[1].clone(),
+        Address::generate(&env),
+        admin.clone(),
+        user.clone(),
+    );
+
+    let pool = factory.create_liquidity_pool(
+        &admin,
+        &lp_init_info,
+        &String::from_str(&env, "Pool Stable Multi"),
+        &String::from_str(&env, "USDC/EURC/USDT"),
+        &PoolInitInfo::StableMulti(
+            10,
+            StableMultiPoolInitInfo {
+                additional_tokens: vec![&env, tokens[2].clone()],
+            },
+        ),
+        &100i64,
+    );
+
+    let stable_multi_client = stable_multi_lp::Client::new(&env, &pool);
+    let config = stable_multi_client.query_config();
+    assert_eq!(config.pool_type, stable_multi_lp::PairType::StableMulti);
+    assert_eq!(
+        config.tokens,
+        vec![
+            &env,
+            tokens[0].clone(),
+            tokens[1].clone(),
+            tokens[2].clone()
+        ]
+    );
+    assert_eq!(config.fee_recipient, user);
+    assert_eq!(stable_multi_client.query_current_amp(), 10);
+
+    // the pool is found by every pair of its coins
+    for (token_a, token_b) in [(0, 1), (0, 2), (1, 2)] {
+        assert_eq!(
+            factory.query_pools_for_pair(&tokens[token_a], &tokens[token_b]),
+            vec![&env, (PoolType::StableMulti, pool.clone())]
+        );
+    }
+    assert_eq!(factory.query_pool_details(&pool).pool_address, pool);
+}
+
 #[test]
 #[should_panic(
     expected = "Factory: Create Liquidity Pool: You are not authorized to create liquidity pool!"
@@ -212,8 +427,7 @@ fn factory_fails_to_init_lp_when_authorized_address_not_present() {
         &lp_init_info,
         &String::from_str(&env, "Pool"),
         &String::from_str(&env, "PHO/BTC"),
-        &PoolType::Xyk,
-        &None::<u64>,
+        &PoolInitInfo::Xyk,
         &100i64,
     );
 }
@@ -233,16 +447,14 @@ fn factory_fails_to_init_lp_when_no_whitelisted_accounts() {
     let multihop_wasm_hash = install_multihop_wasm(&env);
     let whitelisted_accounts = vec![&env];
 
-    let lp_wasm_hash = install_lp_contract(&env);
-    let stable_wasm_hash = install_stable_lp(&env);
+    let pool_wasm_hashes = install_pool_wasm_hashes(&env);
     let stake_wasm_hash = install_stake_wasm(&env);
     let token_wasm_hash = install_token_wasm(&env);
 
     factory.initialize(
         &admin,
         &multihop_wasm_hash,
-        &lp_wasm_hash,
-        &stable_wasm_hash,
+        &pool_wasm_hashes,
         &stake_wasm_hash,
         &token_wasm_hash,
         &whitelisted_accounts,
@@ -254,6 +466,7 @@ fn factory_fails_to_init_lp_when_no_whitelisted_accounts() {
 fn successfully_updates_new_list_of_whitelisted_accounts() {
     let env = Env::default();
     env.mock_all_auths();
+    env.budget().reset_unlimited();
 
     let admin = Address::generate(&env);
     let first_wl_addr = Address::generate(&env);
@@ -283,6 +496,7 @@ fn successfully_updates_new_list_of_whitelisted_accounts() {
 fn doesn_not_change_whitelisted_accounts_when_removing_non_existent() {
     let env = Env::default();
     env.mock_all_auths();
+    env.budget().reset_unlimited();
 
     let admin = Address::generate(&env);
 
@@ -303,6 +517,7 @@ fn doesn_not_change_whitelisted_accounts_when_removing_non_existent() {
 fn fails_to_update_whitelisted_accounts_when_not_authorized() {
     let env = Env::default();
     env.mock_all_auths();
+    env.budget().reset_unlimited();
 
     let admin = Address::generate(&env);
     let first_wl_addr = Address::generate(&env);
@@ -322,6 +537,7 @@ fn fails_to_update_whitelisted_accounts_when_not_authorized() {
 fn test_add_vec_with_duplicates_should_be_handled_correctly() {
     let env = Env::default();
     env.mock_all_auths();
+    env.budget().reset_unlimited();
 
     let admin = Address::generate(&env);
     let first_wl_addr = Address::generate(&env);
@@ -357,8 +573,7 @@ fn test_add_vec_with_duplicates_should_be_handled_correctly() {
 }
 
 #[test]
-#[should_panic(expected = "Factory: Create Liquidity Pool: Amp must be set for stable pool")]
-fn factory_stable_pool_creation_should_fail_early_without_amp() {
+fn pause_and_unpause_all_pools() {
     let env = Env::default();
     env.mock_all_auths();
     env.budget().reset_unlimited();
@@ -381,13 +596,11 @@ fn factory_stable_pool_creation_should_fail_early_without_amp() {
         &String::from_str(&env, "USD Coin"),
         &String::from_str(&env, "USDC"),
     );
-
     if token2.address < token1.address {
         std::mem::swap(&mut token1, &mut token2);
     }
 
     let factory = deploy_factory_contract(&env, Some(admin.clone()));
-    assert_eq!(factory.get_admin(), admin);
 
     let lp_init_info = generate_lp_init_info(
         token1.address.clone(),
@@ -396,91 +609,61 @@ fn factory_stable_pool_creation_should_fail_early_without_amp() {
         admin.clone(),
         user.clone(),
     );
-
-    // we try to make a stable pool without setting the amp
-    factory.create_liquidity_pool(
+    let xyk_pool = factory.create_liquidity_pool(
+        &admin,
+        &lp_init_info,
+        &String::from_str(&env, "Pool"),
+        &String::from_str(&env, "EURC/USDC"),
+        &PoolInitInfo::Xyk,
+        &100i64,
+    );
+    let stable_pool = factory.create_liquidity_pool(
         &admin,
         &lp_init_info,
         &String::from_str(&env, "Pool Stable"),
-        &String::from_str(&env, "EUROC/USDC"),
-        &PoolType::Stable,
-        &None,
+        &String::from_str(&env, "EURC/USDC"),
+        &PoolInitInfo::Stable(10),
         &100i64,
     );
+
+    let xyk_client = lp_contract::Client::new(&env, &xyk_pool);
+    let stable_client = stable_lp::Client::new(&env, &stable_pool);
+    assert!(!xyk_client.query_paused());
+    assert!(!stable_client.query_paused());
+
+    assert!(factory.pause_all_pools(&admin).is_empty());
+
+    assert!(xyk_client.query_paused());
+    assert!(stable_client.query_paused());
+
+    assert!(factory.unpause_all_pools(&admin).is_empty());
+
+    assert!(!xyk_client.query_paused());
+    assert!(!stable_client.query_paused());
 }
 
 #[test]
-fn factory_create_xyk_pool_with_amp_parameter_should_still_succeed() {
+#[should_panic(expected = "Factory: Pause all pools: You are not authorized!")]
+fn pause_all_pools_by_non_admin_fails() {
     let env = Env::default();
     env.mock_all_auths();
     env.budget().reset_unlimited();
 
     let admin = Address::generate(&env);
-    let token_admin = Address::generate(&env);
-    let user = Address::generate(&env);
+    let factory = deploy_factory_contract(&env, Some(admin));
 
-    let mut token1 = install_and_deploy_token_contract(
-        &env,
-        &token_admin.clone(),
-        &7,
-        &String::from_str(&env, "Phoenix"),
-        &String::from_str(&env, "PHO"),
-    );
-    let mut token2 = install_and_deploy_token_contract(
-        &env,
-        &token_admin.clone(),
-        &7,
-        &String::from_str(&env, "USD Coin"),
-        &String::from_str(&env, "USDC"),
-    );
-
-    if token2.address < token1.address {
-        std::mem::swap(&mut token1, &mut token2);
-    }
-
-    let factory = deploy_factory_contract(&env, Some(admin.clone()));
-    assert_eq!(factory.get_admin(), admin);
-
-    let lp_init_info = generate_lp_init_info(
-        token1.address.clone(),
-        token2.address.clone(),
-        Address::generate(&env),
-        admin.clone(),
-        user.clone(),
-    );
-
-    // we want to make an XYK pool, but we accidentaly set the amp
-    // pool creation should still succeed
-    factory.create_liquidity_pool(
-        &admin,
-        &lp_init_info,
-        &String::from_str(&env, "Pool Stable"),
-        &String::from_str(&env, "EUROC/USDC"),
-        &PoolType::Xyk,
-        &Some(10),
-        &100i64,
-    );
+    factory.pause_all_pools(&Address::generate(&env));
+}
 
-    let lp_contract_addr = factory.query_pools().get(0).unwrap();
+#[test]
+#[should_panic(expected = "Factory: Unpause all pools: You are not authorized!")]
+fn unpause_all_pools_by_non_admin_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
 
-    let first_lp_contract = lp_contract::Client::new(&env, &lp_contract_addr);
-    let share_token_address = first_lp_contract.query_share_token_address();
-    let stake_token_address = first_lp_contract.query_stake_contract_address();
+    let admin = Address::generate(&env);
+    let factory = deploy_factory_contract(&env, Some(admin));
 
-    assert_eq!(
-        first_lp_contract.query_config(),
-        lp_contract::Config {
-            fee_recipient: user,
-            max_allowed_slippage_bps: 5_000,
-            max_allowed_spread_bps: 500,
-            max_referral_bps: 5_000,
-            pool_type: lp_contract::PairType::Xyk,
-            share_token: share_token_address,
-            stake_contract: stake_token_address,
-            token_a: token1.address,
-            token_b: token2.address,
-            total_fee_bps: 0,
-            default_slippage_bps: 100,
-        }
-    );
+    factory.unpause_all_pools(&Address::generate(&env));
 }
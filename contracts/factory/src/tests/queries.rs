@@ -1,8 +1,12 @@
 use super::setup::{deploy_factory_contract, generate_lp_init_info};
-use crate::storage::{Asset, LpPortfolio, Stake, StakePortfolio, UserPortfolio};
-use crate::tests::setup::{lp_contract, stake_contract, ONE_DAY};
+use crate::storage::{
+    Asset, LpPortfolio, PairKey, PairTupleKey, Stake, StakePortfolio, UserPortfolio,
+};
+use crate::tests::setup::{
+    install_and_deploy_token_contract, lp_contract, stake_contract, ONE_DAY,
+};
 use crate::token_contract;
-use phoenix::utils::{LiquidityPoolInitInfo, PoolType, StakeInitInfo, TokenInitInfo};
+use phoenix::utils::{LiquidityPoolInitInfo, PoolInitInfo, PoolType, StakeInitInfo, TokenInitInfo};
 use soroban_sdk::testutils::Ledger;
 use soroban_sdk::vec;
 use soroban_sdk::{
@@ -35,6 +39,8 @@ pub struct LiquidityPoolConfig {
     pub max_allowed_spread_bps: i64,
     pub max_referral_bps: i64,
     pub default_slippage_bps: i64,
+    pub protocol_fee_share_bps: i64,
+    pub flash_loan_fee_bps: i64,
 }
 
 #[test]
@@ -138,8 +144,7 @@ fn test_deploy_multiple_liquidity_pools() {
         &first_lp_init_info,
         &String::from_str(&env, "Pool"),
         &String::from_str(&env, "PHO/BTC"),
-        &PoolType::Xyk,
-        &None::<u64>,
+        &PoolInitInfo::Xyk,
         &100i64,
     );
     let second_lp_contract_addr = factory.create_liquidity_pool(
@@ -147,8 +152,7 @@ fn test_deploy_multiple_liquidity_pools() {
         &second_lp_init_info,
         &String::from_str(&env, "Pool #2"),
         &String::from_str(&env, "PHO/ETH"),
-        &PoolType::Xyk,
-        &None::<u64>,
+        &PoolInitInfo::Xyk,
         &100i64,
     );
     let third_lp_contract_addr = factory.create_liquidity_pool(
@@ -156,8 +160,7 @@ fn test_deploy_multiple_liquidity_pools() {
         &third_lp_init_info,
         &String::from_str(&env, "Pool #3"),
         &String::from_str(&env, "PHO/XLM"),
-        &PoolType::Xyk,
-        &None::<u64>,
+        &PoolInitInfo::Xyk,
         &100i64,
     );
 
@@ -347,8 +350,7 @@ fn test_queries_by_tuple() {
         &first_lp_init_info,
         &String::from_str(&env, "Pool"),
         &String::from_str(&env, "PHO/BTC"),
-        &PoolType::Xyk,
-        &None::<u64>,
+        &PoolInitInfo::Xyk,
         &100i64,
     );
     let second_lp_contract_addr = factory.create_liquidity_pool(
@@ -356,8 +358,7 @@ fn test_queries_by_tuple() {
         &second_lp_init_info,
         &String::from_str(&env, "Pool #2"),
         &String::from_str(&env, "PHO/ETH"),
-        &PoolType::Xyk,
-        &None::<u64>,
+        &PoolInitInfo::Xyk,
         &100i64,
     );
     let third_lp_contract_addr = factory.create_liquidity_pool(
@@ -365,8 +366,7 @@ fn test_queries_by_tuple() {
         &third_lp_init_info,
         &String::from_str(&env, "Pool #3"),
         &String::from_str(&env, "PHO/XLM"),
-        &PoolType::Xyk,
-        &None::<u64>,
+        &PoolInitInfo::Xyk,
         &100i64,
     );
 
@@ -410,10 +410,196 @@ fn test_queries_by_tuple() {
     assert_eq!(third_lp_address_by_tuple, third_lp_contract_addr);
 }
 
+#[test]
+fn test_query_pools_for_pair_with_xyk_and_stable_pool() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let user = Address::generate(&env);
+
+    let mut token1 = install_and_deploy_token_contract(
+        &env,
+        &token_admin,
+        &7,
+        &String::from_str(&env, "EURO Coin"),
+        &String::from_str(&env, "EURC"),
+    );
+    let mut token2 = install_and_deploy_token_contract(
+        &env,
+        &token_admin,
+        &7,
+        &String::from_str(&env, "USD Coin"),
+        &String::from_str(&env, "USDC"),
+    );
+
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+
+    let factory = deploy_factory_contract(&env, Some(admin.clone()));
+
+    let lp_init_info = generate_lp_init_info(
+        token1.address.clone(),
+        token2.address.clone(),
+        Address::generate(&env),
+        admin.clone(),
+        user.clone(),
+    );
+
+    let xyk_pool = factory.create_liquidity_pool(
+        &admin,
+        &lp_init_info,
+        &String::from_str(&env, "Pool"),
+        &String::from_str(&env, "EURC/USDC"),
+        &PoolInitInfo::Xyk,
+        &100i64,
+    );
+    let stable_pool = factory.create_liquidity_pool(
+        &admin,
+        &lp_init_info,
+        &String::from_str(&env, "Pool Stable"),
+        &String::from_str(&env, "EURC/USDC"),
+        &PoolInitInfo::Stable(10),
+        &100i64,
+    );
+
+    assert_ne!(xyk_pool, stable_pool);
+    assert_eq!(
+        factory.query_pools(),
+        vec![&env, xyk_pool.clone(), stable_pool.clone()]
+    );
+
+    let expected = vec![
+        &env,
+        (PoolType::Xyk, xyk_pool.clone()),
+        (PoolType::Stable, stable_pool),
+    ];
+    assert_eq!(
+        factory.query_pools_for_pair(&token1.address, &token2.address),
+        expected
+    );
+    assert_eq!(
+        factory.query_pools_for_pair(&token2.address, &token1.address),
+        expected
+    );
+    assert_eq!(
+        factory.query_for_pool_by_token_pair(&token2.address, &token1.address),
+        xyk_pool
+    );
+
+    assert_eq!(
+        factory.query_pools_for_pair(&Address::generate(&env), &Address::generate(&env)),
+        Vec::new(&env)
+    );
+}
+
+#[test]
+fn test_migrate_pools_for_pairs_from_single_pool_layout() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let user = Address::generate(&env);
+
+    let mut token1 = install_and_deploy_token_contract(
+        &env,
+        &token_admin,
+        &7,
+        &String::from_str(&env, "EURO Coin"),
+        &String::from_str(&env, "EURC"),
+    );
+    let mut token2 = install_and_deploy_token_contract(
+        &env,
+        &token_admin,
+        &7,
+        &String::from_str(&env, "USD Coin"),
+        &String::from_str(&env, "USDC"),
+    );
+
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+
+    let factory = deploy_factory_contract(&env, Some(admin.clone()));
+
+    let lp_init_info = generate_lp_init_info(
+        token1.address.clone(),
+        token2.address.clone(),
+        Address::generate(&env),
+        admin.clone(),
+        user.clone(),
+    );
+
+    let stable_pool = factory.create_liquidity_pool(
+        &admin,
+        &lp_init_info,
+        &String::from_str(&env, "Pool Stable"),
+        &String::from_str(&env, "EURC/USDC"),
+        &PoolInitInfo::Stable(10),
+        &100i64,
+    );
+
+    // factories before several pools per pair stored a single address for the pair
+    env.as_contract(&factory.address, || {
+        let storage = env.storage().persistent();
+        storage.remove(&PairKey::Pools(
+            token1.address.clone(),
+            token2.address.clone(),
+        ));
+        storage.set(
+            &PairTupleKey {
+                token_a: token1.address.clone(),
+                token_b: token2.address.clone(),
+            },
+            &stable_pool,
+        );
+    });
+
+    assert_eq!(
+        factory.query_pools_for_pair(&token1.address, &token2.address),
+        Vec::new(&env)
+    );
+    assert_eq!(
+        factory.query_for_pool_by_token_pair(&token2.address, &token1.address),
+        stable_pool
+    );
+
+    // the stored pool type comes from the pool itself
+    factory.migrate_pools_for_pairs(&admin, &vec![&env, stable_pool.clone()]);
+
+    assert_eq!(
+        factory.query_pools_for_pair(&token2.address, &token1.address),
+        vec![&env, (PoolType::Stable, stable_pool.clone())]
+    );
+    assert_eq!(
+        factory.query_for_pool_by_token_pair(&token1.address, &token2.address),
+        stable_pool
+    );
+    env.as_contract(&factory.address, || {
+        assert!(!env.storage().persistent().has(&PairTupleKey {
+            token_a: token1.address.clone(),
+            token_b: token2.address.clone(),
+        }));
+    });
+
+    assert!(factory
+        .try_migrate_pools_for_pairs(&user, &vec![&env, stable_pool])
+        .is_err());
+    assert!(factory
+        .try_migrate_pools_for_pairs(&admin, &vec![&env, Address::generate(&env)])
+        .is_err());
+}
+
 #[test]
 #[should_panic(expected = "Factory: query_for_pool_by_token_pair failed: No liquidity pool found")]
 fn test_queries_by_tuple_errors() {
     let env = Env::default();
+    env.budget().reset_unlimited();
     let admin = Address::generate(&env);
     let factory = deploy_factory_contract(&env, Some(admin.clone()));
 
@@ -450,10 +636,10 @@ fn test_query_user_portfolio_with_stake() {
         std::mem::swap(&mut token3, &mut token4);
     }
 
-    token1.mint(&user_1, &10_000i128);
-    token2.mint(&user_1, &10_000i128);
-    token3.mint(&user_2, &20_000i128);
-    token4.mint(&user_2, &20_000i128);
+    token1.mint(&user_1, &20_000i128);
+    token2.mint(&user_1, &20_000i128);
+    token3.mint(&user_2, &30_000i128);
+    token4.mint(&user_2, &30_000i128);
 
     let factory = deploy_factory_contract(&env, Some(admin.clone()));
 
@@ -470,8 +656,7 @@ fn test_query_user_portfolio_with_stake() {
         &first_lp_init_info,
         &String::from_str(&env, "Pool"),
         &String::from_str(&env, "PHO/BTC"),
-        &PoolType::Xyk,
-        &None::<u64>,
+        &PoolInitInfo::Xyk,
         &100i64,
     );
 
@@ -486,10 +671,10 @@ fn test_query_user_portfolio_with_stake() {
 
     first_lp_client.provide_liquidity(
         &user_1.clone(),
-        &Some(150),
-        &Some(100i128),
-        &Some(200),
-        &Some(100i128),
+        &Some(15_000),
+        &Some(10_000i128),
+        &Some(20_000),
+        &Some(10_000i128),
         &None::<i64>,
         &None::<u64>,
     );
@@ -502,16 +687,17 @@ fn test_query_user_portfolio_with_stake() {
             lp_portfolio: vec![
                 &env,
                 LpPortfolio {
-                    assets: (
+                    assets: vec![
+                        &env,
                         Asset {
                             address: token1.address.clone(),
-                            amount: 150i128,
+                            amount: 14_133i128,
                         },
                         Asset {
                             address: token2.address.clone(),
-                            amount: 200i128
+                            amount: 18_845i128
                         }
-                    )
+                    ]
                 }
             ],
             stake_portfolio: vec![&env,]
@@ -519,7 +705,7 @@ fn test_query_user_portfolio_with_stake() {
     );
 
     env.ledger().with_mut(|li| li.timestamp = ONE_DAY);
-    first_stake_client.bond(&user_1, &173i128);
+    first_stake_client.bond(&user_1, &16_320i128);
 
     // first user portfolio after staking
     let first_portfolio = factory.query_user_portfolio(&user_1, &true);
@@ -529,16 +715,17 @@ fn test_query_user_portfolio_with_stake() {
             lp_portfolio: vec![
                 &env,
                 LpPortfolio {
-                    assets: (
+                    assets: vec![
+                        &env,
                         Asset {
                             address: token1.address.clone(),
-                            amount: 150i128,
+                            amount: 14_133i128,
                         },
                         Asset {
                             address: token2.address.clone(),
-                            amount: 200i128
+                            amount: 18_845i128
                         }
-                    )
+                    ]
                 }
             ],
             stake_portfolio: vec![
@@ -548,7 +735,7 @@ fn test_query_user_portfolio_with_stake() {
                     stakes: vec![
                         &env,
                         Stake {
-                            stake: 173i128,
+                            stake: 16_320i128,
                             stake_timestamp: ONE_DAY
                         }
                     ]
@@ -570,8 +757,7 @@ fn test_query_user_portfolio_with_stake() {
         &second_lp_init_info,
         &String::from_str(&env, "Second Pool"),
         &String::from_str(&env, "PHO/ETH"),
-        &PoolType::Xyk,
-        &None::<u64>,
+        &PoolInitInfo::Xyk,
         &100i64,
     );
 
@@ -585,10 +771,10 @@ fn test_query_user_portfolio_with_stake() {
 
     second_lp_client.provide_liquidity(
         &user_2.clone(),
-        &Some(200),
-        &Some(100i128),
-        &Some(250),
-        &Some(100i128),
+        &Some(20_000),
+        &Some(10_000i128),
+        &Some(25_000),
+        &Some(10_000i128),
         &None::<i64>,
         &None::<u64>,
     );
@@ -600,23 +786,24 @@ fn test_query_user_portfolio_with_stake() {
             lp_portfolio: vec![
                 &env,
                 LpPortfolio {
-                    assets: (
+                    assets: vec![
+                        &env,
                         Asset {
                             address: token3.address.clone(),
-                            amount: 200i128,
+                            amount: 19_105i128,
                         },
                         Asset {
                             address: token4.address.clone(),
-                            amount: 250i128
+                            amount: 23_881i128
                         }
-                    )
+                    ]
                 }
             ],
             stake_portfolio: vec![&env,]
         }
     );
 
-    second_stake_client.bond(&user_2, &223i128);
+    second_stake_client.bond(&user_2, &21_360i128);
 
     let second_portfolio = factory.query_user_portfolio(&user_2, &true);
     assert_eq!(
@@ -625,16 +812,17 @@ fn test_query_user_portfolio_with_stake() {
             lp_portfolio: vec![
                 &env,
                 LpPortfolio {
-                    assets: (
+                    assets: vec![
+                        &env,
                         Asset {
                             address: token3.address.clone(),
-                            amount: 200i128,
+                            amount: 19_105i128,
                         },
                         Asset {
                             address: token4.address.clone(),
-                            amount: 250i128
+                            amount: 23_881i128
                         }
-                    )
+                    ]
                 }
             ],
             stake_portfolio: vec![
@@ -644,7 +832,7 @@ fn test_query_user_portfolio_with_stake() {
                     stakes: vec![
                         &env,
                         Stake {
-                            stake: 223i128,
+                            stake: 21_360i128,
                             stake_timestamp: ONE_DAY
                         }
                     ]
@@ -683,15 +871,15 @@ fn test_query_user_portfolio_with_multiple_users_staking_in_multiple_liquidity_p
         std::mem::swap(&mut token3, &mut token4);
     }
 
-    token1.mint(&user_1, &1_000i128);
-    token1.mint(&user_2, &2_000i128);
-    token2.mint(&user_1, &1_000i128);
-    token2.mint(&user_2, &2_000i128);
+    token1.mint(&user_1, &10_000i128);
+    token1.mint(&user_2, &20_000i128);
+    token2.mint(&user_1, &10_000i128);
+    token2.mint(&user_2, &20_000i128);
 
-    token3.mint(&user_1, &1_000i128);
-    token3.mint(&user_2, &2_000i128);
-    token4.mint(&user_1, &4_000i128);
-    token4.mint(&user_2, &8_000i128);
+    token3.mint(&user_1, &10_000i128);
+    token3.mint(&user_2, &20_000i128);
+    token4.mint(&user_1, &40_000i128);
+    token4.mint(&user_2, &80_000i128);
 
     let factory = deploy_factory_contract(&env, Some(admin.clone()));
 
@@ -709,8 +897,7 @@ fn test_query_user_portfolio_with_multiple_users_staking_in_multiple_liquidity_p
         &first_lp_init_info,
         &String::from_str(&env, "Pool"),
         &String::from_str(&env, "PHO/BTC"),
-        &PoolType::Xyk,
-        &None::<u64>,
+        &PoolInitInfo::Xyk,
         &100i64,
     );
 
@@ -737,8 +924,7 @@ fn test_query_user_portfolio_with_multiple_users_staking_in_multiple_liquidity_p
         &second_lp_init_info,
         &String::from_str(&env, "Second Pool"),
         &String::from_str(&env, "PHO/ETH"),
-        &PoolType::Xyk,
-        &None::<u64>,
+        &PoolInitInfo::Xyk,
         &100i64,
     );
 
@@ -755,20 +941,20 @@ fn test_query_user_portfolio_with_multiple_users_staking_in_multiple_liquidity_p
     // provides liquidity in 50/50 ratio
     first_lp_client.provide_liquidity(
         &user_1.clone(),
-        &Some(1_000i128),
-        &Some(900i128),
-        &Some(1_000i128),
-        &Some(900i128),
+        &Some(10_000i128),
+        &Some(9_000i128),
+        &Some(10_000i128),
+        &Some(9_000i128),
         &None::<i64>,
         &None::<u64>,
     );
 
     first_lp_client.provide_liquidity(
         &user_2.clone(),
-        &Some(2_000i128),
-        &Some(1_900i128),
-        &Some(2_000i128),
-        &Some(1_900i128),
+        &Some(20_000i128),
+        &Some(19_000i128),
+        &Some(20_000i128),
+        &Some(19_000i128),
         &None::<i64>,
         &None::<u64>,
     );
@@ -781,16 +967,17 @@ fn test_query_user_portfolio_with_multiple_users_staking_in_multiple_liquidity_p
             lp_portfolio: vec![
                 &env,
                 LpPortfolio {
-                    assets: (
+                    assets: vec![
+                        &env,
                         Asset {
                             address: token1.address.clone(),
-                            amount: 999i128,
+                            amount: 9_000i128,
                         },
                         Asset {
                             address: token2.address.clone(),
-                            amount: 999i128,
+                            amount: 9_000i128,
                         }
-                    )
+                    ]
                 }
             ],
             stake_portfolio: vec![&env,]
@@ -798,7 +985,7 @@ fn test_query_user_portfolio_with_multiple_users_staking_in_multiple_liquidity_p
     );
 
     env.ledger().with_mut(|li| li.timestamp = ONE_DAY);
-    first_stake_client.bond(&user_1, &1_000i128);
+    first_stake_client.bond(&user_1, &9_000i128);
 
     // first user portfolio in first pool after staking
     let first_user_first_portfolio = factory.query_user_portfolio(&user_1, &true);
@@ -808,16 +995,17 @@ fn test_query_user_portfolio_with_multiple_users_staking_in_multiple_liquidity_p
             lp_portfolio: vec![
                 &env,
                 LpPortfolio {
-                    assets: (
+                    assets: vec![
+                        &env,
                         Asset {
                             address: token1.address.clone(),
-                            amount: 999i128,
+                            amount: 9_000i128,
                         },
                         Asset {
                             address: token2.address.clone(),
-                            amount: 999i128
+                            amount: 9_000i128
                         }
-                    )
+                    ]
                 }
             ],
             stake_portfolio: vec![
@@ -827,7 +1015,7 @@ fn test_query_user_portfolio_with_multiple_users_staking_in_multiple_liquidity_p
                     stakes: vec![
                         &env,
                         Stake {
-                            stake: 1_000i128,
+                            stake: 9_000i128,
                             stake_timestamp: ONE_DAY
                         }
                     ]
@@ -844,16 +1032,17 @@ fn test_query_user_portfolio_with_multiple_users_staking_in_multiple_liquidity_p
             lp_portfolio: vec![
                 &env,
                 LpPortfolio {
-                    assets: (
+                    assets: vec![
+                        &env,
                         Asset {
                             address: token1.address.clone(),
-                            amount: 1_999i128,
+                            amount: 19_999i128,
                         },
                         Asset {
                             address: token2.address.clone(),
-                            amount: 1_999i128,
+                            amount: 19_999i128,
                         }
-                    )
+                    ]
                 }
             ],
             stake_portfolio: vec![&env,]
@@ -861,7 +1050,7 @@ fn test_query_user_portfolio_with_multiple_users_staking_in_multiple_liquidity_p
     );
 
     // this time we bond just 50% of the lp share token for 2nd user
-    first_stake_client.bond(&user_2, &1_000i128);
+    first_stake_client.bond(&user_2, &10_000i128);
 
     // second user portfolio in first pool after staking
     let second_user_first_portfolio = factory.query_user_portfolio(&user_2, &true);
@@ -871,16 +1060,17 @@ fn test_query_user_portfolio_with_multiple_users_staking_in_multiple_liquidity_p
             lp_portfolio: vec![
                 &env,
                 LpPortfolio {
-                    assets: (
+                    assets: vec![
+                        &env,
                         Asset {
                             address: token1.address.clone(),
-                            amount: 1_999i128,
+                            amount: 19_999i128,
                         },
                         Asset {
                             address: token2.address.clone(),
-                            amount: 1_999i128
+                            amount: 19_999i128
                         }
-                    )
+                    ]
                 }
             ],
             stake_portfolio: vec![
@@ -890,7 +1080,7 @@ fn test_query_user_portfolio_with_multiple_users_staking_in_multiple_liquidity_p
                     stakes: vec![
                         &env,
                         Stake {
-                            stake: 1_000i128,
+                            stake: 10_000i128,
                             stake_timestamp: ONE_DAY
                         }
                     ]
@@ -903,20 +1093,20 @@ fn test_query_user_portfolio_with_multiple_users_staking_in_multiple_liquidity_p
     // provides liquidity in 25/75 ratio
     second_lp_client.provide_liquidity(
         &user_1.clone(),
-        &Some(1_000i128),
-        &Some(900i128),
-        &Some(4_000i128),
-        &Some(3_900i128),
+        &Some(10_000i128),
+        &Some(9_000i128),
+        &Some(40_000i128),
+        &Some(39_000i128),
         &None::<i64>,
         &None::<u64>,
     );
 
     second_lp_client.provide_liquidity(
         &user_2.clone(),
-        &Some(2_000i128),
-        &Some(1_900i128),
-        &Some(8_000i128),
-        &Some(7_900i128),
+        &Some(20_000i128),
+        &Some(19_000i128),
+        &Some(79_999i128),
+        &Some(79_000i128),
         &None::<i64>,
         &None::<u64>,
     );
@@ -929,28 +1119,30 @@ fn test_query_user_portfolio_with_multiple_users_staking_in_multiple_liquidity_p
             lp_portfolio: vec![
                 &env,
                 LpPortfolio {
-                    assets: (
+                    assets: vec![
+                        &env,
                         Asset {
                             address: token1.address.clone(),
-                            amount: 999i128,
+                            amount: 9_000i128,
                         },
                         Asset {
                             address: token2.address.clone(),
-                            amount: 999i128,
+                            amount: 9_000i128,
                         }
-                    )
+                    ]
                 },
                 LpPortfolio {
-                    assets: (
+                    assets: vec![
+                        &env,
                         Asset {
                             address: token3.address.clone(),
-                            amount: 999i128,
+                            amount: 9_499i128,
                         },
                         Asset {
                             address: token4.address.clone(),
-                            amount: 3_999i128,
+                            amount: 38_000i128,
                         }
-                    )
+                    ]
                 },
             ],
             stake_portfolio: vec![
@@ -960,7 +1152,7 @@ fn test_query_user_portfolio_with_multiple_users_staking_in_multiple_liquidity_p
                     stakes: vec![
                         &env,
                         Stake {
-                            stake: 1_000i128,
+                            stake: 9_000i128,
                             stake_timestamp: ONE_DAY
                         }
                     ]
@@ -970,7 +1162,7 @@ fn test_query_user_portfolio_with_multiple_users_staking_in_multiple_liquidity_p
     );
 
     // after providing liquidity to 2nd pool user1 has 2_000 lp share tokens
-    second_stake_client.bond(&user_1, &2_000i128);
+    second_stake_client.bond(&user_1, &19_000i128);
 
     // first user portfolio with second pool after staking
     let first_user_first_portfolio = factory.query_user_portfolio(&user_1, &true);
@@ -980,28 +1172,30 @@ fn test_query_user_portfolio_with_multiple_users_staking_in_multiple_liquidity_p
             lp_portfolio: vec![
                 &env,
                 LpPortfolio {
-                    assets: (
+                    assets: vec![
+                        &env,
                         Asset {
                             address: token1.address.clone(),
-                            amount: 999i128,
+                            amount: 9_000i128,
                         },
                         Asset {
                             address: token2.address.clone(),
-                            amount: 999i128
+                            amount: 9_000i128
                         }
-                    )
+                    ]
                 },
                 LpPortfolio {
-                    assets: (
+                    assets: vec![
+                        &env,
                         Asset {
                             address: token3.address.clone(),
-                            amount: 999i128,
+                            amount: 9_499i128,
                         },
                         Asset {
                             address: token4.address.clone(),
-                            amount: 3_999i128,
+                            amount: 38_000i128,
                         }
-                    )
+                    ]
                 },
             ],
             stake_portfolio: vec![
@@ -1011,7 +1205,7 @@ fn test_query_user_portfolio_with_multiple_users_staking_in_multiple_liquidity_p
                     stakes: vec![
                         &env,
                         Stake {
-                            stake: 1_000i128,
+                            stake: 9_000i128,
                             stake_timestamp: ONE_DAY
                         }
                     ]
@@ -1021,7 +1215,7 @@ fn test_query_user_portfolio_with_multiple_users_staking_in_multiple_liquidity_p
                     stakes: vec![
                         &env,
                         Stake {
-                            stake: 2_000i128,
+                            stake: 19_000i128,
                             stake_timestamp: ONE_DAY
                         }
                     ]
@@ -1038,28 +1232,30 @@ fn test_query_user_portfolio_with_multiple_users_staking_in_multiple_liquidity_p
             lp_portfolio: vec![
                 &env,
                 LpPortfolio {
-                    assets: (
+                    assets: vec![
+                        &env,
                         Asset {
                             address: token1.address.clone(),
-                            amount: 1_999i128,
+                            amount: 19_999i128,
                         },
                         Asset {
                             address: token2.address.clone(),
-                            amount: 1_999i128,
+                            amount: 19_999i128,
                         }
-                    )
+                    ]
                 },
                 LpPortfolio {
-                    assets: (
+                    assets: vec![
+                        &env,
                         Asset {
                             address: token3.address.clone(),
-                            amount: 1_999i128,
+                            amount: 19_998i128,
                         },
                         Asset {
                             address: token4.address.clone(),
-                            amount: 7_999i128,
+                            amount: 79_997i128,
                         }
-                    )
+                    ]
                 },
             ],
             stake_portfolio: vec![
@@ -1069,7 +1265,7 @@ fn test_query_user_portfolio_with_multiple_users_staking_in_multiple_liquidity_p
                     stakes: vec![
                         &env,
                         Stake {
-                            stake: 1_000i128,
+                            stake: 10_000i128,
                             stake_timestamp: ONE_DAY
                         }
                     ]
@@ -1079,7 +1275,7 @@ fn test_query_user_portfolio_with_multiple_users_staking_in_multiple_liquidity_p
     );
 
     // this time we bond just 75% of the lp share token for 2nd user
-    second_stake_client.bond(&user_2, &3_000i128);
+    second_stake_client.bond(&user_2, &30_000i128);
 
     // second user portfolio with second pool after staking
     let second_user_second_portfolio = factory.query_user_portfolio(&user_2, &true);
@@ -1089,28 +1285,30 @@ fn test_query_user_portfolio_with_multiple_users_staking_in_multiple_liquidity_p
             lp_portfolio: vec![
                 &env,
                 LpPortfolio {
-                    assets: (
+                    assets: vec![
+                        &env,
                         Asset {
                             address: token1.address.clone(),
-                            amount: 1_999i128,
+                            amount: 19_999i128,
                         },
                         Asset {
                             address: token2.address.clone(),
-                            amount: 1_999i128
+                            amount: 19_999i128
                         }
-                    )
+                    ]
                 },
                 LpPortfolio {
-                    assets: (
+                    assets: vec![
+                        &env,
                         Asset {
                             address: token3.address.clone(),
-                            amount: 1_999i128,
+                            amount: 19_998i128,
                         },
                         Asset {
                             address: token4.address.clone(),
-                            amount: 7_999i128
+                            amount: 79_997i128
                         }
-                    )
+                    ]
                 }
             ],
             stake_portfolio: vec![
@@ -1120,7 +1318,7 @@ fn test_query_user_portfolio_with_multiple_users_staking_in_multiple_liquidity_p
                     stakes: vec![
                         &env,
                         Stake {
-                            stake: 1_000i128,
+                            stake: 10_000i128,
                             stake_timestamp: ONE_DAY
                         }
                     ]
@@ -1130,7 +1328,7 @@ fn test_query_user_portfolio_with_multiple_users_staking_in_multiple_liquidity_p
                     stakes: vec![
                         &env,
                         Stake {
-                            stake: 3_000i128,
+                            stake: 30_000i128,
                             stake_timestamp: ONE_DAY
                         }
                     ]
@@ -1147,28 +1345,30 @@ fn test_query_user_portfolio_with_multiple_users_staking_in_multiple_liquidity_p
             lp_portfolio: vec![
                 &env,
                 LpPortfolio {
-                    assets: (
+                    assets: vec![
+                        &env,
                         Asset {
                             address: token1.address.clone(),
-                            amount: 1_999i128,
+                            amount: 19_999i128,
                         },
                         Asset {
                             address: token2.address.clone(),
-                            amount: 1_999i128
+                            amount: 19_999i128
                         }
-                    )
+                    ]
                 },
                 LpPortfolio {
-                    assets: (
+                    assets: vec![
+                        &env,
                         Asset {
                             address: token3.address.clone(),
-                            amount: 1_999i128,
+                            amount: 19_998i128,
                         },
                         Asset {
                             address: token4.address.clone(),
-                            amount: 7_999i128
+                            amount: 79_997i128
                         }
-                    )
+                    ]
                 }
             ],
             stake_portfolio: vec![&env,]
@@ -1197,8 +1397,8 @@ fn test_query_user_portfolio_without_stake() {
         std::mem::swap(&mut token1, &mut token2);
     }
 
-    token1.mint(&user_1, &10_000i128);
-    token2.mint(&user_1, &10_000i128);
+    token1.mint(&user_1, &20_000i128);
+    token2.mint(&user_1, &20_000i128);
     let factory = deploy_factory_contract(&env, Some(admin.clone()));
 
     let first_token_init_info = TokenInitInfo {
@@ -1228,8 +1428,7 @@ fn test_query_user_portfolio_without_stake() {
         &first_lp_init_info,
         &String::from_str(&env, "Pool"),
         &String::from_str(&env, "PHO/BTC"),
-        &PoolType::Xyk,
-        &None::<u64>,
+        &PoolInitInfo::Xyk,
         &100i64,
     );
 
@@ -1237,10 +1436,10 @@ fn test_query_user_portfolio_without_stake() {
 
     first_lp_client.provide_liquidity(
         &user_1.clone(),
-        &Some(150i128),
-        &Some(100i128),
-        &Some(200i128),
-        &Some(100i128),
+        &Some(15_000i128),
+        &Some(10_000i128),
+        &Some(20_000i128),
+        &Some(10_000i128),
         &None::<i64>,
         &None::<u64>,
     );
@@ -1252,16 +1451,17 @@ fn test_query_user_portfolio_without_stake() {
             lp_portfolio: vec![
                 &env,
                 LpPortfolio {
-                    assets: (
+                    assets: vec![
+                        &env,
                         Asset {
                             address: token1.address,
-                            amount: 150i128
+                            amount: 14_133i128
                         },
                         Asset {
                             address: token2.address,
-                            amount: 200i128
+                            amount: 18_845i128
                         }
-                    )
+                    ]
                 }
             ],
             stake_portfolio: vec![&env]
@@ -1,8 +1,9 @@
 use crate::{
     contract::{Factory, FactoryClient},
+    storage::PoolWasmHashes,
     token_contract,
 };
-use phoenix::utils::{LiquidityPoolInitInfo, PoolType, StakeInitInfo, TokenInitInfo};
+use phoenix::utils::{LiquidityPoolInitInfo, StakeInitInfo, TokenInitInfo};
 use soroban_sdk::{testutils::Address as _, vec, xdr::ToXdr, Address, Bytes, BytesN, Env, String};
 pub const ONE_DAY: u64 = 86400;
 
@@ -20,6 +21,27 @@ pub mod stable_lp {
     );
 }
 
+#[allow(clippy::too_many_arguments)]
+pub mod concentrated_lp {
+    soroban_sdk::contractimport!(
+        file = "../../target/wasm32-unknown-unknown/release/phoenix_pool_concentrated.wasm"
+    );
+}
+
+#[allow(clippy::too_many_arguments)]
+pub mod weighted_lp {
+    soroban_sdk::contractimport!(
+        file = "../../target/wasm32-unknown-unknown/release/phoenix_pool_weighted.wasm"
+    );
+}
+
+#[allow(clippy::too_many_arguments)]
+pub mod stable_multi_lp {
+    soroban_sdk::contractimport!(
+        file = "../../target/wasm32-unknown-unknown/release/phoenix_pool_stable_multi.wasm"
+    );
+}
+
 #[allow(clippy::too_many_arguments)]
 pub mod stake_contract {
     soroban_sdk::contractimport!(
@@ -28,10 +50,12 @@ pub mod stake_contract {
 }
 
 pub fn install_multihop_wasm(env: &Env) -> BytesN<32> {
-    soroban_sdk::contractimport!(
-        file = "../../target/wasm32-unknown-unknown/release/phoenix_multihop.wasm"
-    );
-    env.deployer().upload_contract_wasm(WASM)
+    // only the wasm is needed, importing the whole contract would generate an unused client
+    let wasm = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../target/wasm32-unknown-unknown/release/phoenix_multihop.wasm"
+    ));
+    env.deployer().upload_contract_wasm(wasm.as_slice())
 }
 
 pub fn install_lp_contract(env: &Env) -> BytesN<32> {
@@ -42,6 +66,28 @@ pub fn install_stable_lp(env: &Env) -> BytesN<32> {
     env.deployer().upload_contract_wasm(stable_lp::WASM)
 }
 
+pub fn install_concentrated_lp(env: &Env) -> BytesN<32> {
+    env.deployer().upload_contract_wasm(concentrated_lp::WASM)
+}
+
+pub fn install_weighted_lp(env: &Env) -> BytesN<32> {
+    env.deployer().upload_contract_wasm(weighted_lp::WASM)
+}
+
+pub fn install_stable_multi_lp(env: &Env) -> BytesN<32> {
+    env.deployer().upload_contract_wasm(stable_multi_lp::WASM)
+}
+
+pub fn install_pool_wasm_hashes(env: &Env) -> PoolWasmHashes {
+    PoolWasmHashes {
+        xyk: install_lp_contract(env),
+        stable: install_stable_lp(env),
+        concentrated: install_concentrated_lp(env),
+        weighted: install_weighted_lp(env),
+        stable_multi: install_stable_multi_lp(env),
+    }
+}
+
 pub fn install_token_wasm(env: &Env) -> BytesN<32> {
     env.deployer().upload_contract_wasm(token_contract::WASM)
 }
@@ -63,16 +109,14 @@ pub fn deploy_factory_contract<'a>(
     let multihop_wasm_hash = install_multihop_wasm(env);
     let whitelisted_accounts = vec![env, admin.clone()];
 
-    let lp_wasm_hash = install_lp_contract(env);
-    let stable_wasm_hash = install_stable_lp(env);
+    let pool_wasm_hashes = install_pool_wasm_hashes(env);
     let stake_wasm_hash = install_stake_wasm(env);
     let token_wasm_hash = install_token_wasm(env);
 
     factory.initialize(
         &admin,
         &multihop_wasm_hash,
-        &lp_wasm_hash,
-        &stable_wasm_hash,
+        &pool_wasm_hashes,
         &stake_wasm_hash,
         &token_wasm_hash,
         &whitelisted_accounts,
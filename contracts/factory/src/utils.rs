@@ -1,14 +1,24 @@
-use soroban_sdk::{xdr::ToXdr, Address, Bytes, BytesN, Env, IntoVal, Symbol, Val, Vec};
+use phoenix::utils::PoolType;
+use soroban_sdk::{
+    xdr::ToXdr, Address, Bytes, BytesN, Env, IntoVal, Map, Symbol, TryFromVal, Val, Vec,
+};
 
 pub fn deploy_lp_contract(
     env: &Env,
     wasm_hash: BytesN<32>,
     token_a: &Address,
     token_b: &Address,
+    additional_tokens: &Vec<Address>,
+    pool_type: PoolType,
 ) -> Address {
     let mut salt = Bytes::new(env);
     salt.append(&token_a.to_xdr(env));
     salt.append(&token_b.to_xdr(env));
+    for token in additional_tokens.iter() {
+        salt.append(&token.to_xdr(env));
+    }
+    // the pool type is part of the salt, so the same pair can have one pool of each type
+    salt.append(&pool_type.to_xdr(env));
     let salt = env.crypto().sha256(&salt);
 
     env.deployer().with_current_contract(salt).deploy(wasm_hash)
@@ -34,3 +44,14 @@ pub fn deploy_and_initialize_multihop_contract(
 
     multihop_address
 }
+
+/// Reads the type of `pool` from the `pool_type` field of its config. Every pool keeps its type
+/// there with the discriminant of the matching `PoolType`, including the pools of earlier
+/// versions. Returns `None` if the config has no readable pool type.
+pub fn query_pool_type(env: &Env, pool: &Address) -> Option<PoolType> {
+    let config: Map<Symbol, Val> =
+        env.invoke_contract(pool, &Symbol::new(env, "query_config"), Vec::new(env));
+    config
+        .get(Symbol::new(env, "pool_type"))
+        .and_then(|pool_type| PoolType::try_from_val(env, &pool_type).ok())
+}
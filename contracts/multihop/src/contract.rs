@@ -3,15 +3,19 @@ use soroban_sdk::{
 };
 
 use crate::error::ContractError;
-use crate::factory_contract::PoolType;
-// FIXM: Disable Referral struct
-// use crate::lp_contract::Referral;
 use crate::storage::{
-    get_admin, get_factory, is_initialized, save_admin, save_factory, set_initialized,
-    SimulateReverseSwapResponse, SimulateSwapResponse, Swap,
+    get_admin, get_factory, get_guardian, is_initialized, is_paused, save_admin, save_factory,
+    save_guardian, set_initialized, set_paused, BestRouteResponse, MultihopSwapResponse, Referral,
+    SimulateReverseSwapResponse, SimulateSplitSwapResponse, SimulateSwapResponse, SplitRoute,
+    SplitSwapResponse, Swap, SwapHopResponse,
 };
-use crate::utils::{verify_reverse_swap, verify_swap};
-use crate::{factory_contract, stable_pool, token_contract, xyk_pool};
+use crate::utils::{
+    get_all_pools, get_pool_for_operation, required_offer_amount, search_best_route,
+    simulate_reverse_swap_in_pool, simulate_swap_in_pool, split_amount, swap_in_pool,
+    verify_reverse_swap, verify_split_routes, verify_split_routes_disjoint, verify_swap,
+    MAX_ROUTE_HOPS, MAX_ROUTE_SIMULATIONS,
+};
+use crate::{factory_contract, token_contract};
 
 // Metadata that is added on to the WASM custom section
 contractmeta!(
@@ -26,31 +30,89 @@ pub struct Multihop;
 pub trait MultihopTrait {
     fn initialize(env: Env, admin: Address, factory: Address);
 
+    // Returns the final ask amount together with a report of every hop.
+    // `min_return` protects the whole route and is checked against the output of the last hop,
+    // so the per-hop `ask_asset_min_amount` can be left empty.
+    #[allow(clippy::too_many_arguments)]
     fn swap(
         env: Env,
         recipient: Address,
-        // FIXM: Disable Referral struct
-        // referral: Option<Referral>,
+        referral: Option<Referral>,
         operations: Vec<Swap>,
         max_spread_bps: Option<i64>,
         amount: i128,
-        pool_type: PoolType,
+        min_return: Option<i128>,
         deadline: Option<u64>,
-    );
+    ) -> MultihopSwapResponse;
 
-    fn simulate_swap(
+    // Splits `amount` across `routes` proportionally to their weights and executes every route.
+    // All routes must share the same offer and ask asset and must not go through the same pool;
+    // `min_return` is checked against the combined output.
+    #[allow(clippy::too_many_arguments)]
+    fn swap_split(
         env: Env,
-        operations: Vec<Swap>,
+        recipient: Address,
+        referral: Option<Referral>,
+        routes: Vec<SplitRoute>,
+        max_spread_bps: Option<i64>,
         amount: i128,
-        pool_type: PoolType,
-    ) -> SimulateSwapResponse;
+        min_return: Option<i128>,
+        deadline: Option<u64>,
+    ) -> SplitSwapResponse;
+
+    // Executes `operations` so that `recipient` receives `ask_amount` of the last ask asset.
+    // The required input is computed by walking the path backwards and the call reverts if it
+    // exceeds `max_offer_amount`. Every intermediate hop must return at least the amount the
+    // next hop offers, the operations' own `ask_asset_min_amount` are ignored. Rounding dust of
+    // the intermediate hops stays with the recipient. Returns the amount of the first offer
+    // asset spent.
+    fn swap_exact_out(
+        env: Env,
+        recipient: Address,
+        operations: Vec<Swap>,
+        ask_amount: i128,
+        max_offer_amount: i128,
+        deadline: Option<u64>,
+    ) -> i128;
+
+    fn simulate_swap(env: Env, operations: Vec<Swap>, amount: i128) -> SimulateSwapResponse;
 
     fn simulate_reverse_swap(
         env: Env,
         operations: Vec<Swap>,
         amount: i128,
-        pool_type: PoolType,
     ) -> SimulateReverseSwapResponse;
+
+    // Simulates every route of a split swap independently, so routes sharing a pool are rejected
+    fn simulate_split_swap(
+        env: Env,
+        routes: Vec<SplitRoute>,
+        amount: i128,
+    ) -> SimulateSplitSwapResponse;
+
+    // Searches all pools registered in the factory for the path of at most `max_hops` swaps
+    // that yields the most `ask_asset` for `amount` of `offer_asset`. Panics when the search
+    // needs more than `MAX_ROUTE_SIMULATIONS` pool simulations
+    fn find_best_route(
+        env: Env,
+        offer_asset: Address,
+        ask_asset: Address,
+        amount: i128,
+        max_hops: u32,
+    ) -> BestRouteResponse;
+
+    // Sets the guardian, an address allowed to pause the contract next to the admin
+    fn set_guardian(env: Env, guardian: Address);
+
+    // Pauses all swaps. Callable by the admin or the guardian
+    fn pause(env: Env, sender: Address);
+
+    // Resumes swapping. Callable by the admin only
+    fn unpause(env: Env);
+
+    fn query_paused(env: Env) -> bool;
+
+    fn query_guardian(env: Env) -> Option<Address>;
 }
 
 #[contractimpl]
@@ -74,17 +136,19 @@ impl MultihopTrait for Multihop {
             .publish(("initialize", "Multihop factory with admin: "), admin);
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn swap(
         env: Env,
         recipient: Address,
-        // FIXM: Disable Referral struct
-        // referral: Option<Referral>,
+        referral: Option<Referral>,
         operations: Vec<Swap>,
         max_spread_bps: Option<i64>,
         amount: i128,
-        pool_type: PoolType,
+        min_return: Option<i128>,
         deadline: Option<u64>,
-    ) {
+    ) -> MultihopSwapResponse {
+        ensure_not_paused(&env);
+
         if operations.is_empty() {
             log!(&env, "Multihop: Swap: operations is empty!");
             panic_with_error!(&env, ContractError::OperationsEmpty);
@@ -93,51 +157,152 @@ impl MultihopTrait for Multihop {
 
         recipient.require_auth();
 
-        // first offer amount is an input from the user,
-        // subsequent are the results of the previous swap
-        let mut next_offer_amount: i128 = amount;
+        let factory_client = factory_contract::Client::new(&env, &get_factory(&env));
+        let response = execute_route(
+            &env,
+            &factory_client,
+            &recipient,
+            &referral,
+            &operations,
+            max_spread_bps,
+            amount,
+            deadline,
+        );
+
+        verify_min_return(&env, response.ask_amount, min_return);
+
+        env.events()
+            .publish(("multihop_swap", recipient), response.clone());
+
+        response
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn swap_split(
+        env: Env,
+        recipient: Address,
+        referral: Option<Referral>,
+        routes: Vec<SplitRoute>,
+        max_spread_bps: Option<i64>,
+        amount: i128,
+        min_return: Option<i128>,
+        deadline: Option<u64>,
+    ) -> SplitSwapResponse {
+        ensure_not_paused(&env);
+
+        let total_weight = verify_split_routes(&env, &routes);
+
+        recipient.require_auth();
 
         let factory_client = factory_contract::Client::new(&env, &get_factory(&env));
+        verify_split_routes_disjoint(&env, &factory_client, &routes);
 
-        operations.iter().for_each(|op| {
-            let liquidity_pool_addr: Address = factory_client
-                .query_for_pool_by_token_pair(&op.clone().offer_asset, &op.ask_asset.clone());
-
-            match pool_type {
-                PoolType::Xyk => {
-                    let lp_client = xyk_pool::Client::new(&env, &liquidity_pool_addr);
-                    // FIXM: Disable Referral struct
-                    next_offer_amount = lp_client.swap(
-                        &recipient,
-                        // &referral,
-                        &op.offer_asset,
-                        &next_offer_amount,
-                        &op.ask_asset_min_amount,
-                        &max_spread_bps,
-                        &deadline,
-                    );
-                }
-                PoolType::Stable => {
-                    let lp_client = stable_pool::Client::new(&env, &liquidity_pool_addr);
-                    next_offer_amount = lp_client.swap(
-                        &recipient,
-                        &op.offer_asset,
-                        &next_offer_amount,
-                        &op.ask_asset_min_amount,
-                        &max_spread_bps,
-                        &deadline,
-                    );
-                }
-            }
-        });
+        let mut response = SplitSwapResponse {
+            ask_amount: 0,
+            routes: vec![&env],
+        };
+        let mut remaining_amount = amount;
+        for (index, route) in routes.iter().enumerate() {
+            // the last route takes the remainder, so no rounding dust is left behind
+            let route_amount = if index as u32 == routes.len() - 1 {
+                remaining_amount
+            } else {
+                split_amount(amount, route.weight, total_weight)
+            };
+            remaining_amount -= route_amount;
+
+            let route_response = execute_route(
+                &env,
+                &factory_client,
+                &recipient,
+                &referral,
+                &route.operations,
+                max_spread_bps,
+                route_amount,
+                deadline,
+            );
+            response.ask_amount += route_response.ask_amount;
+            response.routes.push_back(route_response);
+        }
+
+        verify_min_return(&env, response.ask_amount, min_return);
+
+        env.events()
+            .publish(("multihop_split_swap", recipient), response.clone());
+
+        response
     }
 
-    fn simulate_swap(
+    fn swap_exact_out(
         env: Env,
+        recipient: Address,
         operations: Vec<Swap>,
-        amount: i128,
-        pool_type: PoolType,
-    ) -> SimulateSwapResponse {
+        ask_amount: i128,
+        max_offer_amount: i128,
+        deadline: Option<u64>,
+    ) -> i128 {
+        ensure_not_paused(&env);
+
+        if operations.is_empty() {
+            log!(&env, "Multihop: Swap exact out: operations is empty!");
+            panic_with_error!(&env, ContractError::OperationsEmpty);
+        }
+        verify_swap(&env, &operations);
+
+        recipient.require_auth();
+
+        let factory_client = factory_contract::Client::new(&env, &get_factory(&env));
+
+        // walk the path backwards to find how much each hop has to be offered
+        let mut offer_amounts: Vec<i128> = vec![&env];
+        let mut next_ask_amount = ask_amount;
+        for op in operations.iter().rev() {
+            let pool_address = get_pool_for_operation(&env, &factory_client, &op);
+            next_ask_amount = required_offer_amount(&env, &pool_address, &op, next_ask_amount);
+            offer_amounts.push_front(next_ask_amount);
+        }
+
+        let offer_amount = offer_amounts.first().unwrap();
+        if offer_amount > max_offer_amount {
+            log!(
+                &env,
+                "Multihop: Swap exact out: required offer amount {} exceeds the maximum of {}",
+                offer_amount,
+                max_offer_amount
+            );
+            panic_with_error!(&env, ContractError::MaxOfferAmountExceeded);
+        }
+
+        let last_index = operations.len() - 1;
+        for (index, op) in operations.iter().enumerate() {
+            let pool_address = get_pool_for_operation(&env, &factory_client, &op);
+            let offer_amount = offer_amounts.get(index as u32).unwrap();
+            // every hop must return at least what the next hop offers, so a shortfall is never
+            // covered from the recipient's own balance of the intermediate token, and the
+            // recipient must get at least the requested amount out of the last hop
+            let ask_asset_min_amount = if index as u32 == last_index {
+                Some(ask_amount)
+            } else {
+                Some(offer_amounts.get(index as u32 + 1).unwrap())
+            };
+
+            swap_in_pool(
+                &env,
+                &pool_address,
+                &op,
+                &recipient,
+                &None,
+                offer_amount,
+                ask_asset_min_amount,
+                None,
+                deadline,
+            );
+        }
+
+        offer_amount
+    }
+
+    fn simulate_swap(env: Env, operations: Vec<Swap>, amount: i128) -> SimulateSwapResponse {
         if operations.is_empty() {
             log!(&env, "Multihop: Simulate swap: operations empty");
             panic_with_error!(&env, ContractError::OperationsEmpty);
@@ -156,46 +321,22 @@ impl MultihopTrait for Multihop {
         let factory_client = factory_contract::Client::new(&env, &get_factory(&env));
 
         operations.iter().for_each(|op| {
-            let pool_addres: Address = factory_client
-                .query_for_pool_by_token_pair(&op.clone().offer_asset, &op.ask_asset.clone());
-
-            // due to different pool libraries we cannot use shorter match statement.
-            match pool_type {
-                PoolType::Xyk => {
-                    let lp_client = xyk_pool::Client::new(&env, &pool_addres);
-                    let simulated_swap =
-                        lp_client.simulate_swap(&op.offer_asset, &next_offer_amount);
-
-                    let token_symbol = token_contract::Client::new(&env, &op.offer_asset).symbol();
-
-                    simulate_swap_response
-                        .commission_amounts
-                        .push_back((token_symbol, simulated_swap.commission_amount));
-                    simulate_swap_response.ask_amount = simulated_swap.ask_amount;
-                    simulate_swap_response
-                        .spread_amount
-                        .push_back(simulated_swap.spread_amount);
-
-                    next_offer_amount = simulated_swap.ask_amount;
-                }
-                PoolType::Stable => {
-                    let lp_client = stable_pool::Client::new(&env, &pool_addres);
-                    let simulated_swap =
-                        lp_client.simulate_swap(&op.offer_asset, &next_offer_amount);
-
-                    let token_symbol = token_contract::Client::new(&env, &op.offer_asset).symbol();
-
-                    simulate_swap_response
-                        .commission_amounts
-                        .push_back((token_symbol, simulated_swap.commission_amount));
-                    simulate_swap_response.ask_amount = simulated_swap.ask_amount;
-                    simulate_swap_response
-                        .spread_amount
-                        .push_back(simulated_swap.spread_amount);
-
-                    next_offer_amount = simulated_swap.ask_amount;
-                }
-            }
+            let pool_address = get_pool_for_operation(&env, &factory_client, &op);
+            let (ask_amount, commission_amount, spread_amount) =
+                simulate_swap_in_pool(&env, &pool_address, &op, next_offer_amount)
+                    .unwrap_or_else(|error| panic_with_error!(&env, error));
+
+            let token_symbol = token_contract::Client::new(&env, &op.offer_asset).symbol();
+
+            simulate_swap_response
+                .commission_amounts
+                .push_back((token_symbol, commission_amount));
+            simulate_swap_response.ask_amount = ask_amount;
+            simulate_swap_response
+                .spread_amount
+                .push_back(spread_amount);
+
+            next_offer_amount = ask_amount;
         });
 
         simulate_swap_response
@@ -205,7 +346,6 @@ impl MultihopTrait for Multihop {
         env: Env,
         operations: Vec<Swap>,
         amount: i128,
-        pool_type: PoolType,
     ) -> SimulateReverseSwapResponse {
         if operations.is_empty() {
             log!(&env, "Multihop: Simulate reverse swap: operations empty");
@@ -225,50 +365,152 @@ impl MultihopTrait for Multihop {
         let factory_client = factory_contract::Client::new(&env, &get_factory(&env));
 
         operations.iter().for_each(|op| {
-            let pool_address: Address = factory_client
-                .query_for_pool_by_token_pair(&op.clone().offer_asset, &op.ask_asset.clone());
-
-            // due to different pool libraries we cannot use shorter match statement.
-            match pool_type {
-                PoolType::Xyk => {
-                    let lp_client = xyk_pool::Client::new(&env, &pool_address);
-                    let simulated_reverse_swap =
-                        lp_client.simulate_reverse_swap(&op.ask_asset, &next_ask_amount);
-
-                    let token_symbol = token_contract::Client::new(&env, &op.ask_asset).symbol();
-
-                    simulate_swap_response
-                        .commission_amounts
-                        .push_back((token_symbol, simulated_reverse_swap.commission_amount));
-                    simulate_swap_response.offer_amount = simulated_reverse_swap.offer_amount;
-                    simulate_swap_response
-                        .spread_amount
-                        .push_back(simulated_reverse_swap.spread_amount);
-
-                    next_ask_amount = simulated_reverse_swap.offer_amount;
-                }
-                PoolType::Stable => {
-                    let lp_client = stable_pool::Client::new(&env, &pool_address);
-                    let simulated_reverse_swap =
-                        lp_client.simulate_reverse_swap(&op.ask_asset, &next_ask_amount);
-
-                    let token_symbol = token_contract::Client::new(&env, &op.ask_asset).symbol();
-
-                    simulate_swap_response
-                        .commission_amounts
-                        .push_back((token_symbol, simulated_reverse_swap.commission_amount));
-                    simulate_swap_response.offer_amount = simulated_reverse_swap.offer_amount;
-                    simulate_swap_response
-                        .spread_amount
-                        .push_back(simulated_reverse_swap.spread_amount);
-
-                    next_ask_amount = simulated_reverse_swap.offer_amount;
-                }
-            }
+            let pool_address = get_pool_for_operation(&env, &factory_client, &op);
+            let (offer_amount, commission_amount, spread_amount) =
+                simulate_reverse_swap_in_pool(&env, &pool_address, &op, next_ask_amount)
+                    .unwrap_or_else(|error| panic_with_error!(&env, error));
+
+            let token_symbol = token_contract::Client::new(&env, &op.ask_asset).symbol();
+
+            simulate_swap_response
+                .commission_amounts
+                .push_back((token_symbol, commission_amount));
+            simulate_swap_response.offer_amount = offer_amount;
+            simulate_swap_response
+                .spread_amount
+                .push_back(spread_amount);
+
+            next_ask_amount = offer_amount;
         });
 
         simulate_swap_response
     }
+
+    fn simulate_split_swap(
+        env: Env,
+        routes: Vec<SplitRoute>,
+        amount: i128,
+    ) -> SimulateSplitSwapResponse {
+        let total_weight = verify_split_routes(&env, &routes);
+        let factory_client = factory_contract::Client::new(&env, &get_factory(&env));
+        verify_split_routes_disjoint(&env, &factory_client, &routes);
+
+        let mut response = SimulateSplitSwapResponse {
+            ask_amount: 0,
+            routes: vec![&env],
+        };
+        let mut remaining_amount = amount;
+        for (index, route) in routes.iter().enumerate() {
+            let route_amount = if index as u32 == routes.len() - 1 {
+                remaining_amount
+            } else {
+                split_amount(amount, route.weight, total_weight)
+            };
+            remaining_amount -= route_amount;
+
+            let simulated = Self::simulate_swap(env.clone(), route.operations, route_amount);
+            response.ask_amount += simulated.ask_amount;
+            response.routes.push_back(simulated);
+        }
+
+        response
+    }
+
+    fn find_best_route(
+        env: Env,
+        offer_asset: Address,
+        ask_asset: Address,
+        amount: i128,
+        max_hops: u32,
+    ) -> BestRouteResponse {
+        if max_hops == 0 || max_hops > MAX_ROUTE_HOPS {
+            log!(
+                &env,
+                "Multihop: Find best route: max_hops must be between 1 and {}",
+                MAX_ROUTE_HOPS
+            );
+            panic_with_error!(&env, ContractError::InvalidMaxHops);
+        }
+
+        if offer_asset == ask_asset {
+            log!(
+                &env,
+                "Multihop: Find best route: offer and ask asset must differ"
+            );
+            panic_with_error!(&env, ContractError::IncorrectAssetSwap);
+        }
+
+        let factory_client = factory_contract::Client::new(&env, &get_factory(&env));
+        let pools = get_all_pools(&env, &factory_client);
+
+        let mut best = None;
+        let mut simulations_left = MAX_ROUTE_SIMULATIONS;
+        search_best_route(
+            &env,
+            &pools,
+            &offer_asset,
+            &ask_asset,
+            amount,
+            max_hops,
+            &mut vec![&env],
+            &mut best,
+            &mut simulations_left,
+        );
+
+        match best {
+            Some((operations, ask_amount)) => BestRouteResponse {
+                operations,
+                ask_amount,
+            },
+            None => {
+                log!(&env, "Multihop: Find best route: no route found");
+                panic_with_error!(&env, ContractError::NoRouteFound);
+            }
+        }
+    }
+
+    fn set_guardian(env: Env, guardian: Address) {
+        let admin = get_admin(&env);
+        admin.require_auth();
+
+        save_guardian(&env, &guardian);
+
+        env.events().publish(("set_guardian", "guardian"), guardian);
+    }
+
+    fn pause(env: Env, sender: Address) {
+        sender.require_auth();
+
+        let is_guardian = get_guardian(&env).map_or(false, |guardian| guardian == sender);
+        if sender != get_admin(&env) && !is_guardian {
+            log!(
+                &env,
+                "Multihop: Pause: You are not authorized to pause the contract!"
+            );
+            panic_with_error!(&env, ContractError::Unauthorized);
+        }
+
+        set_paused(&env, true);
+
+        env.events().publish(("pause", "sender"), sender);
+    }
+
+    fn unpause(env: Env) {
+        let admin = get_admin(&env);
+        admin.require_auth();
+
+        set_paused(&env, false);
+
+        env.events().publish(("unpause", "sender"), admin);
+    }
+
+    fn query_paused(env: Env) -> bool {
+        is_paused(&env)
+    }
+
+    fn query_guardian(env: Env) -> Option<Address> {
+        get_guardian(&env)
+    }
 }
 
 #[contractimpl]
@@ -281,3 +523,76 @@ impl Multihop {
         env.deployer().update_current_contract_wasm(new_wasm_hash);
     }
 }
+
+fn ensure_not_paused(env: &Env) {
+    if is_paused(env) {
+        log!(env, "Multihop: The contract is paused!");
+        panic_with_error!(env, ContractError::ContractPaused);
+    }
+}
+
+/// Executes the hops of a single route, feeding the output of every hop into the next one.
+#[allow(clippy::too_many_arguments)]
+fn execute_route(
+    env: &Env,
+    factory_client: &factory_contract::Client,
+    recipient: &Address,
+    referral: &Option<Referral>,
+    operations: &Vec<Swap>,
+    max_spread_bps: Option<i64>,
+    amount: i128,
+    deadline: Option<u64>,
+) -> MultihopSwapResponse {
+    // first offer amount is an input from the user,
+    // subsequent are the results of the previous swap
+    let mut next_offer_amount: i128 = amount;
+    let mut hops: Vec<SwapHopResponse> = vec![env];
+
+    operations.iter().for_each(|op| {
+        let liquidity_pool_addr = get_pool_for_operation(env, factory_client, &op);
+        let offer_amount = next_offer_amount;
+
+        let (ask_amount, commission_amount, spread_amount) = swap_in_pool(
+            env,
+            &liquidity_pool_addr,
+            &op,
+            recipient,
+            referral,
+            offer_amount,
+            op.ask_asset_min_amount,
+            max_spread_bps,
+            deadline,
+        );
+
+        next_offer_amount = ask_amount;
+
+        hops.push_back(SwapHopResponse {
+            pool_address: liquidity_pool_addr,
+            offer_asset: op.offer_asset,
+            ask_asset: op.ask_asset,
+            offer_amount,
+            ask_amount,
+            commission_amount,
+            spread_amount,
+        });
+    });
+
+    MultihopSwapResponse {
+        ask_amount: next_offer_amount,
+        hops,
+    }
+}
+
+fn verify_min_return(env: &Env, ask_amount: i128, min_return: Option<i128>) {
+    if let Some(min_return) = min_return {
+        if ask_amount < min_return {
+            log!(
+                env,
+                "Multihop: Swap: route returned {} which is less than the minimum of {}",
+                ask_amount,
+                min_return
+            );
+            panic_with_error!(env, ContractError::MinReturnNotMet);
+        }
+    }
+}
@@ -8,4 +8,14 @@ pub enum ContractError {
     OperationsEmpty = 2,
     IncorrectAssetSwap = 3,
     AdminNotSet = 4,
+    LiquidityPoolNotFound = 5,
+    InvalidMaxHops = 6,
+    NoRouteFound = 7,
+    MaxOfferAmountExceeded = 8,
+    MinReturnNotMet = 9,
+    InvalidSplitRoutes = 10,
+    Unauthorized = 11,
+    ContractPaused = 12,
+    SplitRoutesSharePool = 13,
+    RouteSearchTooExpensive = 14,
 }
@@ -21,6 +21,27 @@ pub mod stable_pool {
     );
 }
 
+#[allow(clippy::too_many_arguments)]
+pub mod concentrated_pool {
+    soroban_sdk::contractimport!(
+        file = "../../target/wasm32-unknown-unknown/release/phoenix_pool_concentrated.wasm"
+    );
+}
+
+#[allow(clippy::too_many_arguments)]
+pub mod weighted_pool {
+    soroban_sdk::contractimport!(
+        file = "../../target/wasm32-unknown-unknown/release/phoenix_pool_weighted.wasm"
+    );
+}
+
+#[allow(clippy::too_many_arguments)]
+pub mod stable_multi_pool {
+    soroban_sdk::contractimport!(
+        file = "../../target/wasm32-unknown-unknown/release/phoenix_pool_stable_multi.wasm"
+    );
+}
+
 #[allow(clippy::too_many_arguments)]
 pub mod factory_contract {
     soroban_sdk::contractimport!(
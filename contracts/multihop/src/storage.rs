@@ -1,5 +1,7 @@
 use soroban_sdk::{contracttype, log, panic_with_error, Address, Env, String, Vec};
 
+use phoenix::utils::PoolType;
+
 use crate::error::ContractError;
 
 #[contracttype]
@@ -8,6 +10,17 @@ pub struct Swap {
     pub ask_asset: Address,
     pub offer_asset: Address,
     pub ask_asset_min_amount: Option<i128>,
+    /// Type of the pool used for this hop
+    pub pool_type: PoolType,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Referral {
+    /// Address of the referral
+    pub address: Address,
+    /// fee in bps, later parsed to percentage
+    pub fee_bps: i64,
 }
 
 #[derive(Clone)]
@@ -24,6 +37,8 @@ pub enum DataKey {
     FactoryKey,
     Admin,
     Initialized,
+    Paused,
+    Guardian,
 }
 
 #[contracttype]
@@ -53,6 +68,63 @@ pub struct SimulateReverseSwapResponse {
     pub spread_amount: Vec<i128>,
 }
 
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SwapHopResponse {
+    /// Address of the pool used for this hop
+    pub pool_address: Address,
+    pub offer_asset: Address,
+    pub ask_asset: Address,
+    /// The amount sold into the pool
+    pub offer_amount: i128,
+    /// The amount received from the pool, after commission and referral fee
+    pub ask_amount: i128,
+    /// The commission charged by the pool, as reported by its swap
+    pub commission_amount: i128,
+    pub spread_amount: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MultihopSwapResponse {
+    /// The amount of the last ask asset received by the recipient
+    pub ask_amount: i128,
+    pub hops: Vec<SwapHopResponse>,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SplitRoute {
+    pub operations: Vec<Swap>,
+    /// Share of the input sent through this route, relative to the sum of all route weights
+    pub weight: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SplitSwapResponse {
+    /// The combined amount of the ask asset received from all routes
+    pub ask_amount: i128,
+    pub routes: Vec<MultihopSwapResponse>,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SimulateSplitSwapResponse {
+    /// The combined amount of the ask asset expected from all routes
+    pub ask_amount: i128,
+    pub routes: Vec<SimulateSwapResponse>,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BestRouteResponse {
+    /// The operations of the route, ready to be passed to `swap`
+    pub operations: Vec<Swap>,
+    /// The expected amount of the ask asset, net of commission and spread
+    pub ask_amount: i128,
+}
+
 /// This struct is used to return a query result with the total amount of LP tokens and assets in a specific pool.
 #[contracttype]
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -86,6 +158,26 @@ pub fn get_admin(env: &Env) -> Address {
             panic_with_error!(&env, ContractError::AdminNotSet)
         })
 }
+
+pub fn save_guardian(env: &Env, guardian: &Address) {
+    env.storage().instance().set(&DataKey::Guardian, guardian);
+}
+
+pub fn get_guardian(env: &Env) -> Option<Address> {
+    env.storage().instance().get(&DataKey::Guardian)
+}
+
+pub fn set_paused(env: &Env, paused: bool) {
+    env.storage().instance().set(&DataKey::Paused, &paused);
+}
+
+pub fn is_paused(env: &Env) -> bool {
+    env.storage()
+        .instance()
+        .get(&DataKey::Paused)
+        .unwrap_or(false)
+}
+
 pub fn is_initialized(e: &Env) -> bool {
     e.storage()
         .persistent()
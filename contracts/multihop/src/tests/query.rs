@@ -1,10 +1,10 @@
-use crate::factory_contract::PoolType;
 use crate::storage::Swap;
 use crate::tests::setup::{
     create_token_contract_with_metadata, deploy_and_initialize_factory, deploy_and_initialize_pool,
-    deploy_multihop_contract, deploy_token_contract,
+    deploy_and_mint_tokens, deploy_multihop_contract, deploy_token_contract,
 };
 
+use phoenix::utils::PoolType;
 use soroban_sdk::{testutils::Address as _, vec, Address, Env, String};
 
 #[test]
@@ -61,11 +61,12 @@ fn simulate_swap_single_pool_no_fees() {
             offer_asset: token1.address.clone(),
             ask_asset: token2.address.clone(),
             ask_asset_min_amount: None::<i128>,
+            pool_type: PoolType::Xyk,
         },
     ];
 
     // Offering 1k token1 should result in 2k token2
-    let result = multihop.simulate_swap(&operation, &1_000, &PoolType::Xyk);
+    let result = multihop.simulate_swap(&operation, &1_000);
 
     assert_eq!(result.ask_amount, 2_000i128);
     assert_eq!(
@@ -75,8 +76,7 @@ fn simulate_swap_single_pool_no_fees() {
     assert_eq!(result.spread_amount, vec![&env, 0i128]);
 
     // simulate reverse swap for exact results
-    let reverse_simulated_swap =
-        multihop.simulate_reverse_swap(&operation, &2_000i128, &PoolType::Xyk);
+    let reverse_simulated_swap = multihop.simulate_reverse_swap(&operation, &2_000i128);
 
     assert_eq!(reverse_simulated_swap.offer_amount, 1_000i128);
     assert_eq!(
@@ -173,20 +173,22 @@ fn simulate_swap_three_equal_pools_no_fees() {
                 offer_asset: token1.address.clone(),
                 ask_asset: token2.address.clone(),
                 ask_asset_min_amount: None::<i128>,
+                pool_type: PoolType::Xyk,
             },
             Swap {
                 offer_asset: token2.address.clone(),
                 ask_asset: token3.address.clone(),
                 ask_asset_min_amount: None::<i128>,
+                pool_type: PoolType::Xyk,
             },
             Swap {
                 offer_asset: token3.address.clone(),
                 ask_asset: token4.address.clone(),
                 ask_asset_min_amount: None::<i128>,
+                pool_type: PoolType::Xyk,
             },
         ],
         &50i128,
-        &PoolType::Xyk,
     );
 
     assert_eq!(simulated_swap.ask_amount, 50i128);
@@ -212,20 +214,22 @@ fn simulate_swap_three_equal_pools_no_fees() {
                 offer_asset: token3.address.clone(),
                 ask_asset: token4.address.clone(),
                 ask_asset_min_amount: None::<i128>,
+                pool_type: PoolType::Xyk,
             },
             Swap {
                 offer_asset: token2.address.clone(),
                 ask_asset: token3.address.clone(),
                 ask_asset_min_amount: None::<i128>,
+                pool_type: PoolType::Xyk,
             },
             Swap {
                 offer_asset: token1.address.clone(),
                 ask_asset: token2.address.clone(),
                 ask_asset_min_amount: None::<i128>,
+                pool_type: PoolType::Xyk,
             },
         ],
         &50i128,
-        &PoolType::Xyk,
     );
 
     assert_eq!(reverse_simulated_swap.offer_amount, 50i128);
@@ -292,10 +296,11 @@ fn simulate_swap_single_pool_with_fees() {
             offer_asset: token1.address.clone(),
             ask_asset: token2.address.clone(),
             ask_asset_min_amount: None::<i128>,
+            pool_type: PoolType::Xyk,
         },
     ];
 
-    let simulated_swap = multihop.simulate_swap(&operation, &300i128, &PoolType::Xyk);
+    let simulated_swap = multihop.simulate_swap(&operation, &300i128);
 
     // 1000 tokens initially
     // swap 300 from token1 to token2 with 2000 bps (20%)
@@ -308,8 +313,7 @@ fn simulate_swap_single_pool_with_fees() {
     assert_eq!(simulated_swap.spread_amount, vec![&env, 0i128]);
 
     // simulate reverse swap returns same result
-    let reverse_simulated_swap =
-        multihop.simulate_reverse_swap(&operation, &240i128, &PoolType::Xyk);
+    let reverse_simulated_swap = multihop.simulate_reverse_swap(&operation, &240i128);
 
     assert_eq!(reverse_simulated_swap.offer_amount, 300i128);
     assert_eq!(
@@ -405,20 +409,22 @@ fn simulate_swap_three_different_pools_no_fees() {
                 offer_asset: token1.address.clone(),
                 ask_asset: token2.address.clone(),
                 ask_asset_min_amount: None::<i128>,
+                pool_type: PoolType::Xyk,
             },
             Swap {
                 offer_asset: token2.address.clone(),
                 ask_asset: token3.address.clone(),
                 ask_asset_min_amount: None::<i128>,
+                pool_type: PoolType::Xyk,
             },
             Swap {
                 offer_asset: token3.address.clone(),
                 ask_asset: token4.address.clone(),
                 ask_asset_min_amount: None::<i128>,
+                pool_type: PoolType::Xyk,
             },
         ],
         &5_000i128,
-        &PoolType::Xyk,
     );
 
     // constant product formula starts to with which amoutns such as 5k
@@ -446,20 +452,22 @@ fn simulate_swap_three_different_pools_no_fees() {
                 offer_asset: token3.address.clone(),
                 ask_asset: token4.address.clone(),
                 ask_asset_min_amount: None::<i128>,
+                pool_type: PoolType::Xyk,
             },
             Swap {
                 offer_asset: token2.address.clone(),
                 ask_asset: token3.address.clone(),
                 ask_asset_min_amount: None::<i128>,
+                pool_type: PoolType::Xyk,
             },
             Swap {
                 offer_asset: token1.address.clone(),
                 ask_asset: token2.address.clone(),
                 ask_asset_min_amount: None::<i128>,
+                pool_type: PoolType::Xyk,
             },
         ],
         &4_956i128,
-        &PoolType::Xyk,
     );
 
     assert_eq!(reverse_simulated_swap.offer_amount, 5_000i128);
@@ -566,20 +574,22 @@ fn simulate_swap_three_different_pools_with_fees() {
                 offer_asset: token1.address.clone(),
                 ask_asset: token2.address.clone(),
                 ask_asset_min_amount: None::<i128>,
+                pool_type: PoolType::Xyk,
             },
             Swap {
                 offer_asset: token2.address.clone(),
                 ask_asset: token3.address.clone(),
                 ask_asset_min_amount: None::<i128>,
+                pool_type: PoolType::Xyk,
             },
             Swap {
                 offer_asset: token3.address.clone(),
                 ask_asset: token4.address.clone(),
                 ask_asset_min_amount: None::<i128>,
+                pool_type: PoolType::Xyk,
             },
         ],
         &10_000i128,
-        &PoolType::Xyk,
     );
 
     // cp = offer_pool * ask_pool
@@ -629,20 +639,22 @@ fn simulate_swap_three_different_pools_with_fees() {
                 offer_asset: token3.address.clone(),
                 ask_asset: token4.address.clone(),
                 ask_asset_min_amount: None::<i128>,
+                pool_type: PoolType::Xyk,
             },
             Swap {
                 offer_asset: token2.address.clone(),
                 ask_asset: token3.address.clone(),
                 ask_asset_min_amount: None::<i128>,
+                pool_type: PoolType::Xyk,
             },
             Swap {
                 offer_asset: token1.address.clone(),
                 ask_asset: token2.address.clone(),
                 ask_asset_min_amount: None::<i128>,
+                pool_type: PoolType::Xyk,
             },
         ],
         &203_143i128,
-        &PoolType::Xyk,
     );
 
     // one difference due to rounding
@@ -678,7 +690,7 @@ fn query_simulate_swap_panics_with_no_operations() {
 
     let swap_vec = vec![&env];
 
-    multihop.simulate_swap(&swap_vec, &50i128, &PoolType::Xyk);
+    multihop.simulate_swap(&swap_vec, &50i128);
 }
 
 #[test]
@@ -697,5 +709,205 @@ fn query_simulate_reverse_swap_panics_with_no_operations() {
 
     let swap_vec = vec![&env];
 
-    multihop.simulate_reverse_swap(&swap_vec, &50i128, &PoolType::Xyk);
+    multihop.simulate_reverse_swap(&swap_vec, &50i128);
+}
+
+#[test]
+fn find_best_route_prefers_deeper_multi_hop_path() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+
+    let token1 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+    let token2 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+    let token3 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+
+    let factory_client = deploy_and_initialize_factory(&env, admin.clone());
+
+    // shallow direct pool
+    deploy_and_initialize_pool(
+        &env,
+        &factory_client,
+        admin.clone(),
+        token1.address.clone(),
+        10_000,
+        token2.address.clone(),
+        10_000,
+        None,
+        PoolType::Xyk,
+    );
+    // deep pools through token3
+    deploy_and_initialize_pool(
+        &env,
+        &factory_client,
+        admin.clone(),
+        token1.address.clone(),
+        1_000_000,
+        token3.address.clone(),
+        1_000_000,
+        None,
+        PoolType::Xyk,
+    );
+    deploy_and_initialize_pool(
+        &env,
+        &factory_client,
+        admin.clone(),
+        token3.address.clone(),
+        1_000_000,
+        token2.address.clone(),
+        1_000_000,
+        None,
+        PoolType::Stable,
+    );
+
+    let multihop = deploy_multihop_contract(&env, admin.clone(), &factory_client.address);
+
+    let best_route = multihop.find_best_route(&token1.address, &token2.address, &1_000i128, &2u32);
+
+    assert_eq!(
+        best_route.operations,
+        vec![
+            &env,
+            Swap {
+                offer_asset: token1.address.clone(),
+                ask_asset: token3.address.clone(),
+                ask_asset_min_amount: None::<i128>,
+                pool_type: PoolType::Xyk,
+            },
+            Swap {
+                offer_asset: token3.address.clone(),
+                ask_asset: token2.address.clone(),
+                ask_asset_min_amount: None::<i128>,
+                pool_type: PoolType::Stable,
+            },
+        ]
+    );
+    assert_eq!(
+        best_route.ask_amount,
+        multihop
+            .simulate_swap(&best_route.operations, &1_000i128)
+            .ask_amount
+    );
+
+    // with a single hop allowed only the shallow direct pool is left
+    let direct_route =
+        multihop.find_best_route(&token1.address, &token2.address, &1_000i128, &1u32);
+
+    assert_eq!(
+        direct_route.operations,
+        vec![
+            &env,
+            Swap {
+                offer_asset: token1.address.clone(),
+                ask_asset: token2.address.clone(),
+                ask_asset_min_amount: None::<i128>,
+                pool_type: PoolType::Xyk,
+            },
+        ]
+    );
+    assert_eq!(direct_route.ask_amount, 910i128);
+    assert!(best_route.ask_amount > direct_route.ask_amount);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #7)")]
+fn find_best_route_panics_when_no_route_exists() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+
+    let token1 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+    let token2 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+    let token3 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+
+    let factory_client = deploy_and_initialize_factory(&env, admin.clone());
+
+    deploy_and_initialize_pool(
+        &env,
+        &factory_client,
+        admin.clone(),
+        token1.address.clone(),
+        1_000_000,
+        token2.address.clone(),
+        1_000_000,
+        None,
+        PoolType::Xyk,
+    );
+
+    let multihop = deploy_multihop_contract(&env, admin.clone(), &factory_client.address);
+
+    multihop.find_best_route(&token1.address, &token3.address, &1_000i128, &3u32);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #6)")]
+fn find_best_route_panics_with_zero_max_hops() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let factory = Address::generate(&env);
+
+    let multihop = deploy_multihop_contract(&env, admin, &factory);
+
+    multihop.find_best_route(
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &1_000i128,
+        &0u32,
+    );
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #14)")]
+fn find_best_route_panics_when_search_exceeds_simulation_cap() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+
+    let factory_client = deploy_and_initialize_factory(&env, admin.clone());
+
+    // six tokens with a pool between every pair take over a hundred simulations at four hops
+    let mut tokens = vec![&env];
+    for _ in 0..6 {
+        tokens.push_back(deploy_and_mint_tokens(&env, &admin, 10_000_000i128).address);
+    }
+    for (i, token_a) in tokens.iter().enumerate() {
+        for token_b in tokens.iter().skip(i + 1) {
+            deploy_and_initialize_pool(
+                &env,
+                &factory_client,
+                admin.clone(),
+                token_a.clone(),
+                100_000,
+                token_b,
+                100_000,
+                None,
+                PoolType::Xyk,
+            );
+        }
+    }
+
+    let multihop = deploy_multihop_contract(&env, admin.clone(), &factory_client.address);
+
+    // fewer hops keep the same graph within the cap
+    multihop.find_best_route(
+        &tokens.get_unchecked(0),
+        &tokens.get_unchecked(5),
+        &1_000i128,
+        &2u32,
+    );
+
+    multihop.find_best_route(
+        &tokens.get_unchecked(0),
+        &tokens.get_unchecked(5),
+        &1_000i128,
+        &4u32,
+    );
 }
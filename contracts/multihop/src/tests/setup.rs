@@ -1,6 +1,10 @@
 use crate::contract::{Multihop, MultihopClient};
-use crate::factory_contract::{LiquidityPoolInitInfo, PoolType, StakeInitInfo, TokenInitInfo};
-use crate::{factory_contract, stable_pool, token_contract, xyk_pool};
+use crate::factory_contract::{LiquidityPoolInitInfo, StakeInitInfo, TokenInitInfo};
+use crate::{
+    concentrated_pool, factory_contract, stable_multi_pool, stable_pool, token_contract,
+    weighted_pool, xyk_pool,
+};
+use phoenix::utils::PoolType;
 
 use soroban_sdk::{
     testutils::{arbitrary::std, Address as _},
@@ -30,6 +34,18 @@ pub fn install_stable_lp_contract(env: &Env) -> BytesN<32> {
     env.deployer().upload_contract_wasm(stable_pool::WASM)
 }
 
+pub fn install_concentrated_lp_contract(env: &Env) -> BytesN<32> {
+    env.deployer().upload_contract_wasm(concentrated_pool::WASM)
+}
+
+pub fn install_weighted_lp_contract(env: &Env) -> BytesN<32> {
+    env.deployer().upload_contract_wasm(weighted_pool::WASM)
+}
+
+pub fn install_stable_multi_lp_contract(env: &Env) -> BytesN<32> {
+    env.deployer().upload_contract_wasm(stable_multi_pool::WASM)
+}
+
 pub fn install_token_wasm(env: &Env) -> BytesN<32> {
     env.deployer().upload_contract_wasm(token_contract::WASM)
 }
@@ -47,10 +63,12 @@ pub fn install_stake_wasm(env: &Env) -> BytesN<32> {
 }
 
 pub fn install_multihop_wasm(env: &Env) -> BytesN<32> {
-    soroban_sdk::contractimport!(
-        file = "../../target/wasm32-unknown-unknown/release/phoenix_multihop.wasm"
-    );
-    env.deployer().upload_contract_wasm(WASM)
+    // only the wasm is needed, importing the whole contract would generate an unused client
+    let wasm = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../target/wasm32-unknown-unknown/release/phoenix_multihop.wasm"
+    ));
+    env.deployer().upload_contract_wasm(wasm.as_slice())
 }
 
 pub fn deploy_factory_contract(e: &Env, admin: Address) -> Address {
@@ -90,16 +108,20 @@ pub fn deploy_and_initialize_factory(env: &Env, admin: Address) -> factory_contr
     let multihop_wasm_hash = install_multihop_wasm(env);
     let whitelisted_accounts = vec![env, admin.clone()];
 
-    let lp_wasm_hash = install_lp_contract(env);
-    let stable_wasm_hash = install_stable_lp_contract(env);
+    let pool_wasm_hashes = factory_contract::PoolWasmHashes {
+        xyk: install_lp_contract(env),
+        stable: install_stable_lp_contract(env),
+        concentrated: install_concentrated_lp_contract(env),
+        weighted: install_weighted_lp_contract(env),
+        stable_multi: install_stable_multi_lp_contract(env),
+    };
     let stake_wasm_hash = install_stake_wasm(env);
     let token_wasm_hash = install_token_wasm(env);
 
     factory_client.initialize(
         &admin.clone(),
         &multihop_wasm_hash,
-        &lp_wasm_hash,
-        &stable_wasm_hash,
+        &pool_wasm_hashes,
         &stake_wasm_hash,
         &token_wasm_hash,
         &whitelisted_accounts,
@@ -147,9 +169,24 @@ pub fn deploy_and_initialize_pool(
         stake_init_info,
     };
 
-    let amp = match pool_type {
-        PoolType::Stable => Some(10u64),
-        PoolType::Xyk => None,
+    let pool_init_info = match pool_type {
+        PoolType::Stable => factory_contract::PoolInitInfo::Stable(10),
+        PoolType::Xyk => factory_contract::PoolInitInfo::Xyk,
+        PoolType::Concentrated => factory_contract::PoolInitInfo::Concentrated(
+            factory_contract::ConcentratedPoolInitInfo {
+                tick_spacing: 10,
+                initial_tick: 0,
+            },
+        ),
+        PoolType::Weighted => {
+            factory_contract::PoolInitInfo::Weighted(factory_contract::WeightedPoolInitInfo {
+                weight_a_bps: 5_000,
+                weight_b_bps: 5_000,
+            })
+        }
+        PoolType::StableMulti => {
+            panic!("Stable multi pools are set up with deploy_and_initialize_stable_multi_pool")
+        }
     };
 
     let lp = factory.create_liquidity_pool(
@@ -157,8 +194,7 @@ pub fn deploy_and_initialize_pool(
         &lp_init_info,
         &String::from_str(env, "Pool"),
         &String::from_str(env, "PHO/XLM"),
-        &pool_type,
-        &amp,
+        &pool_init_info,
         &100i64,
     );
 
@@ -182,8 +218,97 @@ pub fn deploy_and_initialize_pool(
                 &token_a_amount,
                 &token_b_amount,
                 &None,
+                &None,
+                &None::<u64>,
+            );
+        }
+        PoolType::Concentrated => {
+            // a wide range around the initial price, so the pool behaves close to a full range one
+            let lp_client = concentrated_pool::Client::new(env, &lp);
+            lp_client.provide_liquidity(
+                &admin.clone(),
+                &-60_000,
+                &60_000,
+                &token_a_amount,
+                &token_b_amount,
+                &0,
+                &0,
+                &None::<u64>,
+            );
+        }
+        PoolType::Weighted => {
+            let lp_client = weighted_pool::Client::new(env, &lp);
+            lp_client.provide_liquidity(
+                &admin.clone(),
+                &Some(token_a_amount),
+                &None,
+                &Some(token_b_amount),
+                &None,
+                &None::<i64>,
                 &None::<u64>,
             );
         }
+        PoolType::StableMulti => unreachable!(),
     }
 }
+
+/// Creates a stable multi pool of the given tokens and provides the given amounts of them
+pub fn deploy_and_initialize_stable_multi_pool(
+    env: &Env,
+    factory: &factory_contract::Client,
+    admin: Address,
+    tokens_and_amounts: &[(Address, i128)],
+    fees: Option<i64>,
+) -> Address {
+    let mut tokens_and_amounts = tokens_and_amounts.to_vec();
+    tokens_and_amounts.sort();
+
+    let mut additional_tokens = vec![env];
+    let mut amounts = vec![env];
+    for (token, amount) in &tokens_and_amounts {
+        amounts.push_back(*amount);
+        additional_tokens.push_back(token.clone());
+    }
+    let token_init_info = TokenInitInfo {
+        token_a: additional_tokens.pop_front_unchecked(),
+        token_b: additional_tokens.pop_front_unchecked(),
+    };
+    let stake_init_info = StakeInitInfo {
+        min_bond: 10i128,
+        min_reward: 5i128,
+        manager: Address::generate(env),
+        max_complexity: 10u32,
+    };
+
+    let lp_init_info = LiquidityPoolInitInfo {
+        admin: admin.clone(),
+        fee_recipient: admin.clone(),
+        max_allowed_slippage_bps: 5000,
+        max_allowed_spread_bps: 500,
+        swap_fee_bps: fees.unwrap_or(0i64),
+        max_referral_bps: 5_000,
+        token_init_info,
+        stake_init_info,
+    };
+
+    let lp = factory.create_liquidity_pool(
+        &admin.clone(),
+        &lp_init_info,
+        &String::from_str(env, "Pool"),
+        &String::from_str(env, "PHO/XLM/USDC"),
+        &factory_contract::PoolInitInfo::StableMulti(
+            10u64,
+            factory_contract::StableMultiPoolInitInfo { additional_tokens },
+        ),
+        &100i64,
+    );
+
+    stable_multi_pool::Client::new(env, &lp).provide_liquidity(
+        &admin,
+        &amounts,
+        &None,
+        &None,
+        &None::<u64>,
+    );
+    lp
+}
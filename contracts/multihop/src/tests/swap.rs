@@ -1,14 +1,16 @@
-use crate::factory_contract::PoolType;
-// FIXM: Disable Referral struct
-// use crate::lp_contract::Referral;
-use crate::storage::Swap;
+use crate::storage::{MultihopSwapResponse, Referral, SplitRoute, Swap};
 use crate::tests::setup::{
-    deploy_and_initialize_factory, deploy_and_initialize_pool, deploy_and_mint_tokens,
-    deploy_multihop_contract, deploy_token_contract,
+    deploy_and_initialize_factory, deploy_and_initialize_pool,
+    deploy_and_initialize_stable_multi_pool, deploy_and_mint_tokens, deploy_multihop_contract,
+    deploy_token_contract,
 };
 
+use phoenix::utils::PoolType;
 use soroban_sdk::contracterror;
-use soroban_sdk::{testutils::Address as _, vec, Address, Env};
+use soroban_sdk::{
+    testutils::{Address as _, Events},
+    vec, Address, Env, IntoVal, TryFromVal,
+};
 
 #[contracterror]
 #[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
@@ -79,28 +81,30 @@ fn swap_three_equal_pools_no_fees() {
         offer_asset: token1.address.clone(),
         ask_asset: token2.address.clone(),
         ask_asset_min_amount: None::<i128>,
+        pool_type: PoolType::Xyk,
     };
     let swap2 = Swap {
         offer_asset: token2.address.clone(),
         ask_asset: token3.address.clone(),
         ask_asset_min_amount: None::<i128>,
+        pool_type: PoolType::Xyk,
     };
     let swap3 = Swap {
         offer_asset: token3.address.clone(),
         ask_asset: token4.address.clone(),
         ask_asset_min_amount: None::<i128>,
+        pool_type: PoolType::Xyk,
     };
 
     let operations = vec![&env, swap1, swap2, swap3];
 
-    // FIXM: Disable Referral struct
-    // multihop.swap(&recipient, &None, &operations, &None, &None, &50i128);
     multihop.swap(
         &recipient,
+        &None,
         &operations,
         &None,
         &50i128,
-        &PoolType::Xyk,
+        &None,
         &None::<u64>,
     );
 
@@ -109,8 +113,6 @@ fn swap_three_equal_pools_no_fees() {
     assert_eq!(token4.balance(&recipient), 50i128);
 }
 
-// FIXM: Disable Referral struct
-#[ignore]
 #[test]
 fn swap_three_equal_pools_no_fees_referral_fee() {
     let env = Env::default();
@@ -173,40 +175,35 @@ fn swap_three_equal_pools_no_fees_referral_fee() {
         offer_asset: token1.address.clone(),
         ask_asset: token2.address.clone(),
         ask_asset_min_amount: None::<i128>,
+        pool_type: PoolType::Xyk,
     };
     let swap2 = Swap {
         offer_asset: token2.address.clone(),
         ask_asset: token3.address.clone(),
         ask_asset_min_amount: None::<i128>,
+        pool_type: PoolType::Xyk,
     };
     let swap3 = Swap {
         offer_asset: token3.address.clone(),
         ask_asset: token4.address.clone(),
         ask_asset_min_amount: None::<i128>,
+        pool_type: PoolType::Xyk,
     };
 
     let operations = vec![&env, swap1, swap2, swap3];
     let referral_addr = Address::generate(&env);
-    // FIXM: Disable Referral struct
-    // let referral = Referral {
-    //     address: referral_addr.clone(),
-    //     fee: 1_000,
-    // };
-
-    // multihop.swap(
-    //     &recipient,
-    //     &Some(referral),
-    //     &operations,
-    //     &None,
-    //     &None,
-    //     &50i128,
-    // );
+    let referral = Referral {
+        address: referral_addr.clone(),
+        fee_bps: 1_000,
+    };
+
     multihop.swap(
         &recipient,
+        &Some(referral),
         &operations,
         &None,
         &50i128,
-        &PoolType::Xyk,
+        &None,
         &None::<u64>,
     );
 
@@ -258,18 +255,18 @@ fn swap_single_pool_no_fees() {
         offer_asset: token1.address.clone(),
         ask_asset: token2.address.clone(),
         ask_asset_min_amount: None::<i128>,
+        pool_type: PoolType::Xyk,
     };
 
     let operations = vec![&env, swap1];
 
-    // FIXM: Disable Referral struct
-    // multihop.swap(&recipient, /*&None,*/ &operations, &None, &None, &50i128);
     multihop.swap(
         &recipient,
+        &None,
         &operations,
         &None,
         &1_000,
-        &PoolType::Xyk,
+        &None,
         &None::<u64>,
     );
 
@@ -313,18 +310,18 @@ fn swap_should_fail_when_spread_exceeds_the_limit() {
         offer_asset: token1.address.clone(),
         ask_asset: token2.address.clone(),
         ask_asset_min_amount: None::<i128>,
+        pool_type: PoolType::Xyk,
     };
 
     let operations = vec![&env, swap1];
 
-    // FIXM: Disable Referral struct
-    // multihop.swap(&recipient, &None, &operations, &None, &Some(50), &50);
     multihop.swap(
         &recipient,
+        &None,
         &operations,
         &Some(50),
         &50,
-        &PoolType::Xyk,
+        &None,
         &None::<u64>,
     );
 }
@@ -366,18 +363,18 @@ fn swap_single_pool_with_fees() {
         offer_asset: token1.address.clone(),
         ask_asset: token2.address.clone(),
         ask_asset_min_amount: None::<i128>,
+        pool_type: PoolType::Xyk,
     };
 
     let operations = vec![&env, swap1];
 
-    // FIXM: Disable Referral struct
-    // multihop.swap(&recipient, &None, &operations, &None, &None, &300i128);
     multihop.swap(
         &recipient,
+        &None,
         &operations,
         &None,
         &300i128,
-        &PoolType::Xyk,
+        &None,
         &None::<u64>,
     );
 
@@ -452,28 +449,30 @@ fn swap_three_different_pools_no_fees() {
         offer_asset: token1.address.clone(),
         ask_asset: token2.address.clone(),
         ask_asset_min_amount: None::<i128>,
+        pool_type: PoolType::Xyk,
     };
     let swap2 = Swap {
         offer_asset: token2.address.clone(),
         ask_asset: token3.address.clone(),
         ask_asset_min_amount: None::<i128>,
+        pool_type: PoolType::Xyk,
     };
     let swap3 = Swap {
         offer_asset: token3.address.clone(),
         ask_asset: token4.address.clone(),
         ask_asset_min_amount: None::<i128>,
+        pool_type: PoolType::Xyk,
     };
 
     let operations = vec![&env, swap1, swap2, swap3];
 
-    // FIXM: Disable Referral struct
-    // multihop.swap(&recipient, &None, &operations, &None, &None, &5_000i128);
     multihop.swap(
         &recipient,
+        &None,
         &operations,
         &None,
         &5_000i128,
-        &PoolType::Xyk,
+        &None,
         &None::<u64>,
     );
 
@@ -547,28 +546,30 @@ fn swap_three_different_pools_with_fees() {
         offer_asset: token1.address.clone(),
         ask_asset: token2.address.clone(),
         ask_asset_min_amount: None::<i128>,
+        pool_type: PoolType::Xyk,
     };
     let swap2 = Swap {
         offer_asset: token2.address.clone(),
         ask_asset: token3.address.clone(),
         ask_asset_min_amount: None::<i128>,
+        pool_type: PoolType::Xyk,
     };
     let swap3 = Swap {
         offer_asset: token3.address.clone(),
         ask_asset: token4.address.clone(),
         ask_asset_min_amount: None::<i128>,
+        pool_type: PoolType::Xyk,
     };
 
     let operations = vec![&env, swap1, swap2, swap3];
 
-    // FIXM: Disable Referral struct
-    // multihop.swap(&recipient, &None, &operations, &None, &None, &10_000i128);
     multihop.swap(
         &recipient,
+        &None,
         &operations,
         &None,
         &10_000i128,
-        &PoolType::Xyk,
+        &None,
         &None::<u64>,
     );
 
@@ -613,14 +614,13 @@ fn swap_panics_with_no_operations() {
 
     let swap_vec = vec![&env];
 
-    // FIXM: Disable Referral struct
-    // multihop.swap(&recipient, &None, &swap_vec, &None, &None, &50i128);
     multihop.swap(
         &recipient,
+        &None,
         &swap_vec,
         &None,
         &50i128,
-        &PoolType::Xyk,
+        &None,
         &None::<u64>,
     );
 }
@@ -682,26 +682,30 @@ fn test_v_phx_vul_013_add_belief_price_for_every_swap() {
         offer_asset: token1.address.clone(),
         ask_asset: token2.address.clone(),
         ask_asset_min_amount: Some(1_050),
+        pool_type: PoolType::Xyk,
     };
     let swap2 = Swap {
         offer_asset: token2.address.clone(),
         ask_asset: token3.address.clone(),
         ask_asset_min_amount: Some(2_100),
+        pool_type: PoolType::Xyk,
     };
     let swap3 = Swap {
         offer_asset: token3.address.clone(),
         ask_asset: token4.address.clone(),
         ask_asset_min_amount: Some(3_150),
+        pool_type: PoolType::Xyk,
     };
 
     let operations = vec![&env, swap1, swap2, swap3];
 
     multihop.swap(
         &recipient,
+        &None,
         &operations,
         &None,
         &5_000i128,
-        &PoolType::Xyk,
+        &None,
         &None::<u64>,
     );
 
@@ -753,16 +757,18 @@ fn test_swap_with_ask_asset_min_amount() {
         offer_asset: token1.address.clone(),
         ask_asset: token2.address.clone(),
         ask_asset_min_amount: Some(1_000),
+        pool_type: PoolType::Xyk,
     };
 
     let operations = vec![&env, swap1];
 
     multihop.swap(
         &recipient,
+        &None,
         &operations,
         &None,
         &1_000,
-        &PoolType::Xyk,
+        &None,
         &None::<u64>,
     );
 
@@ -773,14 +779,16 @@ fn test_swap_with_ask_asset_min_amount() {
         offer_asset: token1.address.clone(),
         ask_asset: token2.address.clone(),
         ask_asset_min_amount: Some(10_000),
+        pool_type: PoolType::Xyk,
     };
     let operations = vec![&env, greedy_swap];
     multihop.swap(
         &recipient,
+        &None,
         &operations,
         &None,
         &1_000,
-        &PoolType::Xyk,
+        &None,
         &None::<u64>,
     );
 }
@@ -848,29 +856,1456 @@ fn swap_three_equal_stable_pool() {
         offer_asset: token1.address.clone(),
         ask_asset: token2.address.clone(),
         ask_asset_min_amount: None::<i128>,
+        pool_type: PoolType::Stable,
     };
     let swap2 = Swap {
         offer_asset: token2.address.clone(),
         ask_asset: token3.address.clone(),
         ask_asset_min_amount: None::<i128>,
+        pool_type: PoolType::Stable,
     };
     let swap3 = Swap {
         offer_asset: token3.address.clone(),
         ask_asset: token4.address.clone(),
         ask_asset_min_amount: None::<i128>,
+        pool_type: PoolType::Stable,
     };
 
     let operations = vec![&env, swap1, swap2, swap3];
 
     multihop.swap(
         &recipient,
+        &None,
         &operations,
         &None,
         &50i128,
-        &PoolType::Stable,
+        &None,
         &None::<u64>,
     );
 
     assert_eq!(token1.balance(&recipient), 0i128);
     assert_eq!(token4.balance(&recipient), 50i128);
 }
+
+#[test]
+fn swap_single_stable_pool_with_referral_fee() {
+    let env = Env::default();
+    let admin = Address::generate(&env);
+
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let token1 = deploy_and_mint_tokens(&env, &admin, 1_001_000i128);
+    let token2 = deploy_and_mint_tokens(&env, &admin, 1_001_000i128);
+
+    let factory_client = deploy_and_initialize_factory(&env, admin.clone());
+
+    deploy_and_initialize_pool(
+        &env,
+        &factory_client,
+        admin.clone(),
+        token1.address.clone(),
+        1_000_000,
+        token2.address.clone(),
+        1_000_000,
+        None,
+        PoolType::Stable,
+    );
+
+    let multihop = deploy_multihop_contract(&env, admin.clone(), &factory_client.address);
+    let recipient = Address::generate(&env);
+    token1.mint(&recipient, &1_000i128);
+
+    let swap1 = Swap {
+        offer_asset: token1.address.clone(),
+        ask_asset: token2.address.clone(),
+        ask_asset_min_amount: None::<i128>,
+        pool_type: PoolType::Stable,
+    };
+
+    let operations = vec![&env, swap1];
+
+    let referral_addr = Address::generate(&env);
+    let referral = Referral {
+        address: referral_addr.clone(),
+        fee_bps: 1_000,
+    };
+
+    multihop.swap(
+        &recipient,
+        &Some(referral),
+        &operations,
+        &None,
+        &1_000,
+        &None,
+        &None::<u64>,
+    );
+
+    assert_eq!(token1.balance(&recipient), 0i128);
+    // 10% of the returned amount goes to the referral
+    assert_eq!(token2.balance(&recipient), 900i128);
+    assert_eq!(token2.balance(&referral_addr), 100i128);
+}
+
+#[test]
+fn swap_through_mixed_pool_types() {
+    let env = Env::default();
+    let admin = Address::generate(&env);
+
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let token1 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+    let token2 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+    let token3 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+    let token4 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+
+    let factory_client = deploy_and_initialize_factory(&env, admin.clone());
+
+    // XYK -> Stable -> XYK
+    deploy_and_initialize_pool(
+        &env,
+        &factory_client,
+        admin.clone(),
+        token1.address.clone(),
+        1_000_000,
+        token2.address.clone(),
+        1_000_000,
+        None,
+        PoolType::Xyk,
+    );
+    deploy_and_initialize_pool(
+        &env,
+        &factory_client,
+        admin.clone(),
+        token2.address.clone(),
+        1_000_000,
+        token3.address.clone(),
+        1_000_000,
+        None,
+        PoolType::Stable,
+    );
+    deploy_and_initialize_pool(
+        &env,
+        &factory_client,
+        admin.clone(),
+        token3.address.clone(),
+        1_000_000,
+        token4.address.clone(),
+        1_000_000,
+        None,
+        PoolType::Xyk,
+    );
+
+    let multihop = deploy_multihop_contract(&env, admin.clone(), &factory_client.address);
+    let recipient = Address::generate(&env);
+    token1.mint(&recipient, &1_000i128);
+
+    let swap1 = Swap {
+        offer_asset: token1.address.clone(),
+        ask_asset: token2.address.clone(),
+        ask_asset_min_amount: None::<i128>,
+        pool_type: PoolType::Xyk,
+    };
+    let swap2 = Swap {
+        offer_asset: token2.address.clone(),
+        ask_asset: token3.address.clone(),
+        ask_asset_min_amount: None::<i128>,
+        pool_type: PoolType::Stable,
+    };
+    let swap3 = Swap {
+        offer_asset: token3.address.clone(),
+        ask_asset: token4.address.clone(),
+        ask_asset_min_amount: None::<i128>,
+        pool_type: PoolType::Xyk,
+    };
+
+    let operations = vec![&env, swap1, swap2, swap3];
+
+    let simulated = multihop.simulate_swap(&operations, &1_000i128);
+
+    multihop.swap(
+        &recipient,
+        &None,
+        &operations,
+        &None,
+        &1_000i128,
+        &None,
+        &None::<u64>,
+    );
+
+    assert_eq!(token1.balance(&recipient), 0i128);
+    assert_eq!(token4.balance(&recipient), simulated.ask_amount);
+    assert_eq!(simulated.spread_amount.len(), 3);
+}
+
+#[test]
+fn swap_through_a_concentrated_pool() {
+    let env = Env::default();
+    let admin = Address::generate(&env);
+
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let token1 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+    let token2 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+    let token3 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+
+    let factory_client = deploy_and_initialize_factory(&env, admin.clone());
+
+    // XYK -> Concentrated
+    deploy_and_initialize_pool(
+        &env,
+        &factory_client,
+        admin.clone(),
+        token1.address.clone(),
+        1_000_000,
+        token2.address.clone(),
+        1_000_000,
+        None,
+        PoolType::Xyk,
+    );
+    deploy_and_initialize_pool(
+        &env,
+        &factory_client,
+        admin.clone(),
+        token2.address.clone(),
+        1_000_000,
+        token3.address.clone(),
+        1_000_000,
+        Some(100),
+        PoolType::Concentrated,
+    );
+
+    let multihop = deploy_multihop_contract(&env, admin.clone(), &factory_client.address);
+    let recipient = Address::generate(&env);
+    token1.mint(&recipient, &1_000i128);
+
+    let swap1 = Swap {
+        offer_asset: token1.address.clone(),
+        ask_asset: token2.address.clone(),
+        ask_asset_min_amount: None::<i128>,
+        pool_type: PoolType::Xyk,
+    };
+    let swap2 = Swap {
+        offer_asset: token2.address.clone(),
+        ask_asset: token3.address.clone(),
+        ask_asset_min_amount: None::<i128>,
+        pool_type: PoolType::Concentrated,
+    };
+
+    let operations = vec![&env, swap1, swap2];
+
+    let simulated = multihop.simulate_swap(&operations, &1_000i128);
+    assert!(simulated.ask_amount > 0);
+
+    multihop.swap(
+        &recipient,
+        &None,
+        &operations,
+        &None,
+        &1_000i128,
+        &None,
+        &None::<u64>,
+    );
+
+    assert_eq!(token1.balance(&recipient), 0i128);
+    assert_eq!(token3.balance(&recipient), simulated.ask_amount);
+    assert_eq!(simulated.commission_amounts.len(), 2);
+
+    // and back, asking for an exact amount out of the concentrated pool first
+    let reverse_operations = vec![
+        &env,
+        Swap {
+            offer_asset: token3.address.clone(),
+            ask_asset: token2.address.clone(),
+            ask_asset_min_amount: None::<i128>,
+            pool_type: PoolType::Concentrated,
+        },
+    ];
+    let offer_amount = multihop
+        .simulate_reverse_swap(&reverse_operations, &500i128)
+        .offer_amount;
+    assert!(offer_amount <= simulated.ask_amount);
+
+    multihop.swap(
+        &recipient,
+        &None,
+        &reverse_operations,
+        &None,
+        &offer_amount,
+        &None,
+        &None::<u64>,
+    );
+    assert!(token2.balance(&recipient) >= 500);
+}
+
+#[test]
+fn swap_through_a_weighted_pool() {
+    let env = Env::default();
+    let admin = Address::generate(&env);
+
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let token1 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+    let token2 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+    let token3 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+
+    let factory_client = deploy_and_initialize_factory(&env, admin.clone());
+
+    // XYK -> Weighted
+    deploy_and_initialize_pool(
+        &env,
+        &factory_client,
+        admin.clone(),
+        token1.address.clone(),
+        1_000_000,
+        token2.address.clone(),
+        1_000_000,
+        None,
+        PoolType::Xyk,
+    );
+    deploy_and_initialize_pool(
+        &env,
+        &factory_client,
+        admin.clone(),
+        token2.address.clone(),
+        1_000_000,
+        token3.address.clone(),
+        1_000_000,
+        Some(100),
+        PoolType::Weighted,
+    );
+
+    let multihop = deploy_multihop_contract(&env, admin.clone(), &factory_client.address);
+    let recipient = Address::generate(&env);
+    token1.mint(&recipient, &1_000i128);
+
+    let swap1 = Swap {
+        offer_asset: token1.address.clone(),
+        ask_asset: token2.address.clone(),
+        ask_asset_min_amount: None::<i128>,
+        pool_type: PoolType::Xyk,
+    };
+    let swap2 = Swap {
+        offer_asset: token2.address.clone(),
+        ask_asset: token3.address.clone(),
+        ask_asset_min_amount: None::<i128>,
+        pool_type: PoolType::Weighted,
+    };
+
+    let operations = vec![&env, swap1, swap2];
+
+    let simulated = multihop.simulate_swap(&operations, &1_000i128);
+    assert!(simulated.ask_amount > 0);
+
+    multihop.swap(
+        &recipient,
+        &None,
+        &operations,
+        &None,
+        &1_000i128,
+        &None,
+        &None::<u64>,
+    );
+
+    assert_eq!(token1.balance(&recipient), 0i128);
+    assert_eq!(token3.balance(&recipient), simulated.ask_amount);
+    assert_eq!(simulated.commission_amounts.len(), 2);
+
+    // and back, asking for an exact amount out of the weighted pool first
+    let reverse_operations = vec![
+        &env,
+        Swap {
+            offer_asset: token3.address.clone(),
+            ask_asset: token2.address.clone(),
+            ask_asset_min_amount: None::<i128>,
+            pool_type: PoolType::Weighted,
+        },
+    ];
+    let offer_amount = multihop
+        .simulate_reverse_swap(&reverse_operations, &500i128)
+        .offer_amount;
+    assert!(offer_amount <= simulated.ask_amount);
+
+    multihop.swap(
+        &recipient,
+        &None,
+        &reverse_operations,
+        &None,
+        &offer_amount,
+        &None,
+        &None::<u64>,
+    );
+    assert!(token2.balance(&recipient) >= 500);
+}
+
+#[test]
+fn swap_through_a_stable_multi_pool() {
+    let env = Env::default();
+    let admin = Address::generate(&env);
+
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let token1 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+    let token2 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+    let token3 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+    let token4 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+
+    let factory_client = deploy_and_initialize_factory(&env, admin.clone());
+
+    // Stable multi of tokens 1, 2 and 3 -> XYK
+    let stable_multi_pool = deploy_and_initialize_stable_multi_pool(
+        &env,
+        &factory_client,
+        admin.clone(),
+        &[
+            (token1.address.clone(), 1_000_000),
+            (token2.address.clone(), 1_000_000),
+            (token3.address.clone(), 1_000_000),
+        ],
+        None,
+    );
+    deploy_and_initialize_pool(
+        &env,
+        &factory_client,
+        admin.clone(),
+        token3.address.clone(),
+        1_000_000,
+        token4.address.clone(),
+        1_000_000,
+        None,
+        PoolType::Xyk,
+    );
+
+    let multihop = deploy_multihop_contract(&env, admin.clone(), &factory_client.address);
+    let recipient = Address::generate(&env);
+    token1.mint(&recipient, &1_000i128);
+
+    let operations = vec![
+        &env,
+        Swap {
+            offer_asset: token1.address.clone(),
+            ask_asset: token3.address.clone(),
+            ask_asset_min_amount: None::<i128>,
+            pool_type: PoolType::StableMulti,
+        },
+        Swap {
+            offer_asset: token3.address.clone(),
+            ask_asset: token4.address.clone(),
+            ask_asset_min_amount: None::<i128>,
+            pool_type: PoolType::Xyk,
+        },
+    ];
+
+    let simulated = multihop.simulate_swap(&operations, &1_000i128);
+    assert!(simulated.ask_amount > 0);
+
+    let response = multihop.swap(
+        &recipient,
+        &None,
+        &operations,
+        &None,
+        &1_000i128,
+        &None,
+        &None::<u64>,
+    );
+
+    assert_eq!(token1.balance(&recipient), 0i128);
+    assert_eq!(token4.balance(&recipient), simulated.ask_amount);
+    assert_eq!(
+        response.hops.get(0).unwrap().pool_address,
+        stable_multi_pool
+    );
+
+    // the route search finds the pool for a pair that is not its first two coins
+    let best_route = multihop.find_best_route(&token1.address, &token4.address, &1_000i128, &2u32);
+    assert_eq!(best_route.operations, operations);
+}
+
+#[test]
+fn swap_picks_the_pool_matching_the_operation_pool_type() {
+    let env = Env::default();
+    let admin = Address::generate(&env);
+
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let token1 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+    let token2 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+
+    let factory_client = deploy_and_initialize_factory(&env, admin.clone());
+
+    // both an XYK and a stable pool exist for the same pair
+    deploy_and_initialize_pool(
+        &env,
+        &factory_client,
+        admin.clone(),
+        token1.address.clone(),
+        1_000_000,
+        token2.address.clone(),
+        1_000_000,
+        None,
+        PoolType::Xyk,
+    );
+    deploy_and_initialize_pool(
+        &env,
+        &factory_client,
+        admin.clone(),
+        token1.address.clone(),
+        1_000_000,
+        token2.address.clone(),
+        1_000_000,
+        None,
+        PoolType::Stable,
+    );
+
+    let pools = factory_client.query_pools_for_pair(&token1.address, &token2.address);
+    assert_eq!(pools.len(), 2);
+    let (_, xyk_pool_addr) = pools.get(0).unwrap();
+    let (_, stable_pool_addr) = pools.get(1).unwrap();
+
+    let multihop = deploy_multihop_contract(&env, admin.clone(), &factory_client.address);
+    let recipient = Address::generate(&env);
+    token1.mint(&recipient, &1_000i128);
+
+    let operations = vec![
+        &env,
+        Swap {
+            offer_asset: token1.address.clone(),
+            ask_asset: token2.address.clone(),
+            ask_asset_min_amount: None::<i128>,
+            pool_type: PoolType::Stable,
+        },
+    ];
+
+    multihop.swap(
+        &recipient,
+        &None,
+        &operations,
+        &None,
+        &1_000i128,
+        &None,
+        &None::<u64>,
+    );
+
+    assert_eq!(token1.balance(&recipient), 0i128);
+    assert_eq!(token1.balance(&stable_pool_addr), 1_001_000i128);
+    assert_eq!(token1.balance(&xyk_pool_addr), 1_000_000i128);
+    assert_eq!(token2.balance(&xyk_pool_addr), 1_000_000i128);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #5)")]
+fn swap_panics_when_pair_has_no_pool_of_the_requested_type() {
+    let env = Env::default();
+    let admin = Address::generate(&env);
+
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let token1 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+    let token2 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+
+    let factory_client = deploy_and_initialize_factory(&env, admin.clone());
+
+    deploy_and_initialize_pool(
+        &env,
+        &factory_client,
+        admin.clone(),
+        token1.address.clone(),
+        1_000_000,
+        token2.address.clone(),
+        1_000_000,
+        None,
+        PoolType::Xyk,
+    );
+
+    let multihop = deploy_multihop_contract(&env, admin.clone(), &factory_client.address);
+    let recipient = Address::generate(&env);
+    token1.mint(&recipient, &1_000i128);
+
+    let operations = vec![
+        &env,
+        Swap {
+            offer_asset: token1.address.clone(),
+            ask_asset: token2.address.clone(),
+            ask_asset_min_amount: None::<i128>,
+            pool_type: PoolType::Stable,
+        },
+    ];
+
+    multihop.swap(
+        &recipient,
+        &None,
+        &operations,
+        &None,
+        &1_000i128,
+        &None,
+        &None::<u64>,
+    );
+}
+
+#[test]
+fn swap_exact_out_through_three_pools_with_fees() {
+    let env = Env::default();
+    let admin = Address::generate(&env);
+
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let token1 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+    let token2 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+    let token3 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+    let token4 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+
+    let factory_client = deploy_and_initialize_factory(&env, admin.clone());
+
+    deploy_and_initialize_pool(
+        &env,
+        &factory_client,
+        admin.clone(),
+        token1.address.clone(),
+        1_000_000,
+        token2.address.clone(),
+        2_000_000,
+        Some(100),
+        PoolType::Xyk,
+    );
+    deploy_and_initialize_pool(
+        &env,
+        &factory_client,
+        admin.clone(),
+        token2.address.clone(),
+        1_000_000,
+        token3.address.clone(),
+        1_000_000,
+        Some(100),
+        PoolType::Stable,
+    );
+    deploy_and_initialize_pool(
+        &env,
+        &factory_client,
+        admin.clone(),
+        token3.address.clone(),
+        3_000_000,
+        token4.address.clone(),
+        1_000_000,
+        Some(100),
+        PoolType::Xyk,
+    );
+
+    let multihop = deploy_multihop_contract(&env, admin.clone(), &factory_client.address);
+    let recipient = Address::generate(&env);
+    token1.mint(&recipient, &10_000i128);
+
+    let swap1 = Swap {
+        offer_asset: token1.address.clone(),
+        ask_asset: token2.address.clone(),
+        ask_asset_min_amount: None::<i128>,
+        pool_type: PoolType::Xyk,
+    };
+    let swap2 = Swap {
+        offer_asset: token2.address.clone(),
+        ask_asset: token3.address.clone(),
+        ask_asset_min_amount: None::<i128>,
+        pool_type: PoolType::Stable,
+    };
+    let swap3 = Swap {
+        offer_asset: token3.address.clone(),
+        ask_asset: token4.address.clone(),
+        ask_asset_min_amount: None::<i128>,
+        pool_type: PoolType::Xyk,
+    };
+
+    let operations = vec![&env, swap1.clone(), swap2.clone(), swap3.clone()];
+    let simulated = multihop.simulate_reverse_swap(&vec![&env, swap3, swap2, swap1], &1_000i128);
+
+    let spent = multihop.swap_exact_out(
+        &recipient,
+        &operations,
+        &1_000i128,
+        &10_000i128,
+        &None::<u64>,
+    );
+
+    // the reverse simulation rounds down by one unit, which swap_exact_out compensates for
+    assert_eq!(simulated.offer_amount, 1_548i128);
+    assert_eq!(spent, 1_549i128);
+    assert_eq!(token1.balance(&recipient), 10_000i128 - spent);
+    assert_eq!(token4.balance(&recipient), 1_000i128);
+}
+
+#[test]
+fn swap_exact_out_does_not_spend_recipients_intermediate_tokens() {
+    let env = Env::default();
+    let admin = Address::generate(&env);
+
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let token1 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+    let token2 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+    let token3 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+
+    let factory_client = deploy_and_initialize_factory(&env, admin.clone());
+
+    deploy_and_initialize_pool(
+        &env,
+        &factory_client,
+        admin.clone(),
+        token1.address.clone(),
+        100_000,
+        token2.address.clone(),
+        100_000,
+        None,
+        PoolType::Xyk,
+    );
+    deploy_and_initialize_pool(
+        &env,
+        &factory_client,
+        admin.clone(),
+        token1.address.clone(),
+        1_000_000,
+        token2.address.clone(),
+        1_000_000,
+        None,
+        PoolType::Stable,
+    );
+    deploy_and_initialize_pool(
+        &env,
+        &factory_client,
+        admin.clone(),
+        token2.address.clone(),
+        1_000_000,
+        token3.address.clone(),
+        1_000_000,
+        None,
+        PoolType::Xyk,
+    );
+
+    let multihop = deploy_multihop_contract(&env, admin.clone(), &factory_client.address);
+    let recipient = Address::generate(&env);
+    token1.mint(&recipient, &100_000i128);
+    // the recipient already holds the intermediate token
+    token2.mint(&recipient, &50_000i128);
+
+    // the third hop goes through the XYK pool again after the first hop moved its price, so it
+    // returns less than the offer amounts computed up front on the untouched pools
+    let operations = vec![
+        &env,
+        Swap {
+            offer_asset: token1.address.clone(),
+            ask_asset: token2.address.clone(),
+            ask_asset_min_amount: None::<i128>,
+            pool_type: PoolType::Xyk,
+        },
+        Swap {
+            offer_asset: token2.address.clone(),
+            ask_asset: token1.address.clone(),
+            ask_asset_min_amount: None::<i128>,
+            pool_type: PoolType::Stable,
+        },
+        Swap {
+            offer_asset: token1.address.clone(),
+            ask_asset: token2.address.clone(),
+            ask_asset_min_amount: None::<i128>,
+            pool_type: PoolType::Xyk,
+        },
+        Swap {
+            offer_asset: token2.address.clone(),
+            ask_asset: token3.address.clone(),
+            ask_asset_min_amount: None::<i128>,
+            pool_type: PoolType::Xyk,
+        },
+    ];
+
+    // the third hop falls short of what the last hop offers and fails the XYK pool's
+    // `SwapMinReceivedBiggerThanReturn` check
+    assert_eq!(
+        multihop.try_swap_exact_out(
+            &recipient,
+            &operations,
+            &1_000i128,
+            &100_000i128,
+            &None::<u64>,
+        ),
+        Err(Ok(soroban_sdk::Error::from_contract_error(21)))
+    );
+
+    assert_eq!(token1.balance(&recipient), 100_000i128);
+    assert_eq!(token2.balance(&recipient), 50_000i128);
+    assert_eq!(token3.balance(&recipient), 0i128);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #8)")]
+fn swap_exact_out_panics_when_offer_exceeds_max_offer_amount() {
+    let env = Env::default();
+    let admin = Address::generate(&env);
+
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let token1 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+    let token2 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+
+    let factory_client = deploy_and_initialize_factory(&env, admin.clone());
+
+    deploy_and_initialize_pool(
+        &env,
+        &factory_client,
+        admin.clone(),
+        token1.address.clone(),
+        1_000_000,
+        token2.address.clone(),
+        1_000_000,
+        Some(100),
+        PoolType::Xyk,
+    );
+
+    let multihop = deploy_multihop_contract(&env, admin.clone(), &factory_client.address);
+    let recipient = Address::generate(&env);
+    token1.mint(&recipient, &10_000i128);
+
+    let operations = vec![
+        &env,
+        Swap {
+            offer_asset: token1.address.clone(),
+            ask_asset: token2.address.clone(),
+            ask_asset_min_amount: None::<i128>,
+            pool_type: PoolType::Xyk,
+        },
+    ];
+
+    // with a 1% fee receiving 1_000 costs more than 1_000
+    multihop.swap_exact_out(
+        &recipient,
+        &operations,
+        &1_000i128,
+        &1_000i128,
+        &None::<u64>,
+    );
+}
+
+#[test]
+fn swap_returns_per_hop_report_and_emits_event() {
+    let env = Env::default();
+    let admin = Address::generate(&env);
+
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let token1 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+    let token2 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+    let token3 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+
+    let factory_client = deploy_and_initialize_factory(&env, admin.clone());
+
+    deploy_and_initialize_pool(
+        &env,
+        &factory_client,
+        admin.clone(),
+        token1.address.clone(),
+        1_000_000,
+        token2.address.clone(),
+        1_000_000,
+        Some(100),
+        PoolType::Xyk,
+    );
+    deploy_and_initialize_pool(
+        &env,
+        &factory_client,
+        admin.clone(),
+        token2.address.clone(),
+        1_000_000,
+        token3.address.clone(),
+        1_000_000,
+        Some(100),
+        PoolType::Stable,
+    );
+
+    let multihop = deploy_multihop_contract(&env, admin.clone(), &factory_client.address);
+    let recipient = Address::generate(&env);
+    token1.mint(&recipient, &10_000i128);
+
+    let operations = vec![
+        &env,
+        Swap {
+            offer_asset: token1.address.clone(),
+            ask_asset: token2.address.clone(),
+            ask_asset_min_amount: None::<i128>,
+            pool_type: PoolType::Xyk,
+        },
+        Swap {
+            offer_asset: token2.address.clone(),
+            ask_asset: token3.address.clone(),
+            ask_asset_min_amount: None::<i128>,
+            pool_type: PoolType::Stable,
+        },
+    ];
+
+    let simulated = multihop.simulate_swap(&operations, &10_000i128);
+    let pools = (
+        factory_client.query_for_pool_by_token_pair(&token1.address, &token2.address),
+        factory_client.query_for_pool_by_token_pair(&token2.address, &token3.address),
+    );
+
+    let response = multihop.swap(
+        &recipient,
+        &None,
+        &operations,
+        &None,
+        &10_000i128,
+        &None,
+        &None::<u64>,
+    );
+
+    let event = env.events().all().last().unwrap();
+    assert_eq!(event.0, multihop.address);
+    assert_eq!(event.1, ("multihop_swap", recipient.clone()).into_val(&env));
+    assert_eq!(
+        MultihopSwapResponse::try_from_val(&env, &event.2).unwrap(),
+        response
+    );
+
+    let first_hop = response.hops.get(0).unwrap();
+    let second_hop = response.hops.get(1).unwrap();
+
+    assert_eq!(response.hops.len(), 2);
+    assert_eq!(response.ask_amount, simulated.ask_amount);
+    assert_eq!(response.ask_amount, token3.balance(&recipient));
+
+    assert_eq!(first_hop.pool_address, pools.0);
+    assert_eq!(first_hop.offer_asset, token1.address);
+    assert_eq!(first_hop.ask_asset, token2.address);
+    assert_eq!(first_hop.offer_amount, 10_000i128);
+    assert_eq!(
+        first_hop.commission_amount,
+        simulated.commission_amounts.get(0).unwrap().1
+    );
+    assert_eq!(
+        first_hop.spread_amount,
+        simulated.spread_amount.get(0).unwrap()
+    );
+
+    assert_eq!(second_hop.pool_address, pools.1);
+    assert_eq!(second_hop.offer_amount, first_hop.ask_amount);
+    assert_eq!(second_hop.ask_amount, response.ask_amount);
+    assert_eq!(
+        second_hop.commission_amount,
+        simulated.commission_amounts.get(1).unwrap().1
+    );
+    assert_eq!(
+        second_hop.spread_amount,
+        simulated.spread_amount.get(1).unwrap()
+    );
+}
+
+#[test]
+fn swap_with_route_min_return_and_no_per_hop_minimums() {
+    let env = Env::default();
+    let admin = Address::generate(&env);
+
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let token1 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+    let token2 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+    let token3 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+
+    let factory_client = deploy_and_initialize_factory(&env, admin.clone());
+
+    deploy_and_initialize_pool(
+        &env,
+        &factory_client,
+        admin.clone(),
+        token1.address.clone(),
+        1_000_000,
+        token2.address.clone(),
+        1_000_000,
+        Some(100),
+        PoolType::Xyk,
+    );
+    deploy_and_initialize_pool(
+        &env,
+        &factory_client,
+        admin.clone(),
+        token2.address.clone(),
+        1_000_000,
+        token3.address.clone(),
+        1_000_000,
+        Some(100),
+        PoolType::Xyk,
+    );
+
+    let multihop = deploy_multihop_contract(&env, admin.clone(), &factory_client.address);
+    let recipient = Address::generate(&env);
+    token1.mint(&recipient, &1_000i128);
+
+    let operations = vec![
+        &env,
+        Swap {
+            offer_asset: token1.address.clone(),
+            ask_asset: token2.address.clone(),
+            ask_asset_min_amount: None::<i128>,
+            pool_type: PoolType::Xyk,
+        },
+        Swap {
+            offer_asset: token2.address.clone(),
+            ask_asset: token3.address.clone(),
+            ask_asset_min_amount: None::<i128>,
+            pool_type: PoolType::Xyk,
+        },
+    ];
+
+    let simulated = multihop.simulate_swap(&operations, &1_000i128);
+
+    let response = multihop.swap(
+        &recipient,
+        &None,
+        &operations,
+        &None,
+        &1_000i128,
+        &Some(simulated.ask_amount),
+        &None::<u64>,
+    );
+
+    assert_eq!(response.ask_amount, simulated.ask_amount);
+    assert_eq!(token3.balance(&recipient), simulated.ask_amount);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #9)")]
+fn swap_panics_when_route_returns_less_than_min_return() {
+    let env = Env::default();
+    let admin = Address::generate(&env);
+
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let token1 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+    let token2 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+    let token3 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+
+    let factory_client = deploy_and_initialize_factory(&env, admin.clone());
+
+    deploy_and_initialize_pool(
+        &env,
+        &factory_client,
+        admin.clone(),
+        token1.address.clone(),
+        1_000_000,
+        token2.address.clone(),
+        1_000_000,
+        Some(100),
+        PoolType::Xyk,
+    );
+    deploy_and_initialize_pool(
+        &env,
+        &factory_client,
+        admin.clone(),
+        token2.address.clone(),
+        1_000_000,
+        token3.address.clone(),
+        1_000_000,
+        Some(100),
+        PoolType::Stable,
+    );
+
+    let multihop = deploy_multihop_contract(&env, admin.clone(), &factory_client.address);
+    let recipient = Address::generate(&env);
+    token1.mint(&recipient, &1_000i128);
+
+    let operations = vec![
+        &env,
+        Swap {
+            offer_asset: token1.address.clone(),
+            ask_asset: token2.address.clone(),
+            ask_asset_min_amount: None::<i128>,
+            pool_type: PoolType::Xyk,
+        },
+        Swap {
+            offer_asset: token2.address.clone(),
+            ask_asset: token3.address.clone(),
+            ask_asset_min_amount: None::<i128>,
+            pool_type: PoolType::Stable,
+        },
+    ];
+
+    let simulated = multihop.simulate_swap(&operations, &1_000i128);
+
+    multihop.swap(
+        &recipient,
+        &None,
+        &operations,
+        &None,
+        &1_000i128,
+        &Some(simulated.ask_amount + 1),
+        &None::<u64>,
+    );
+}
+
+#[test]
+fn swap_split_between_xyk_and_stable_pool_of_the_same_pair() {
+    let env = Env::default();
+    let admin = Address::generate(&env);
+
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let token1 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+    let token2 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+
+    let factory_client = deploy_and_initialize_factory(&env, admin.clone());
+
+    deploy_and_initialize_pool(
+        &env,
+        &factory_client,
+        admin.clone(),
+        token1.address.clone(),
+        1_000_000,
+        token2.address.clone(),
+        1_000_000,
+        None,
+        PoolType::Xyk,
+    );
+    deploy_and_initialize_pool(
+        &env,
+        &factory_client,
+        admin.clone(),
+        token1.address.clone(),
+        1_000_000,
+        token2.address.clone(),
+        1_000_000,
+        None,
+        PoolType::Stable,
+    );
+
+    let multihop = deploy_multihop_contract(&env, admin.clone(), &factory_client.address);
+    let recipient = Address::generate(&env);
+    token1.mint(&recipient, &100_000i128);
+
+    let xyk_operations = vec![
+        &env,
+        Swap {
+            offer_asset: token1.address.clone(),
+            ask_asset: token2.address.clone(),
+            ask_asset_min_amount: None::<i128>,
+            pool_type: PoolType::Xyk,
+        },
+    ];
+    let stable_operations = vec![
+        &env,
+        Swap {
+            offer_asset: token1.address.clone(),
+            ask_asset: token2.address.clone(),
+            ask_asset_min_amount: None::<i128>,
+            pool_type: PoolType::Stable,
+        },
+    ];
+    let routes = vec![
+        &env,
+        SplitRoute {
+            operations: xyk_operations.clone(),
+            weight: 1,
+        },
+        SplitRoute {
+            operations: stable_operations,
+            weight: 3,
+        },
+    ];
+
+    let single_route = multihop.simulate_swap(&xyk_operations, &100_000i128);
+    let simulated = multihop.simulate_split_swap(&routes, &100_000i128);
+
+    assert_eq!(simulated.routes.len(), 2);
+    assert_eq!(
+        simulated.ask_amount,
+        simulated.routes.get(0).unwrap().ask_amount + simulated.routes.get(1).unwrap().ask_amount
+    );
+    // spreading the order over both pools reduces the price impact
+    assert!(simulated.ask_amount > single_route.ask_amount);
+
+    let response = multihop.swap_split(
+        &recipient,
+        &None,
+        &routes,
+        &None,
+        &100_000i128,
+        &Some(simulated.ask_amount),
+        &None::<u64>,
+    );
+
+    let xyk_route = response.routes.get(0).unwrap();
+    let stable_route = response.routes.get(1).unwrap();
+
+    assert_eq!(xyk_route.hops.get(0).unwrap().offer_amount, 25_000i128);
+    assert_eq!(stable_route.hops.get(0).unwrap().offer_amount, 75_000i128);
+    assert_eq!(response.ask_amount, simulated.ask_amount);
+    assert_eq!(token1.balance(&recipient), 0i128);
+    assert_eq!(token2.balance(&recipient), response.ask_amount);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #10)")]
+fn swap_split_panics_with_zero_weight_route() {
+    let env = Env::default();
+    let admin = Address::generate(&env);
+
+    env.mock_all_auths();
+
+    let token1 = Address::generate(&env);
+    let token2 = Address::generate(&env);
+    let factory = Address::generate(&env);
+
+    let multihop = deploy_multihop_contract(&env, admin, &factory);
+    let recipient = Address::generate(&env);
+
+    let operations = vec![
+        &env,
+        Swap {
+            offer_asset: token1,
+            ask_asset: token2,
+            ask_asset_min_amount: None::<i128>,
+            pool_type: PoolType::Xyk,
+        },
+    ];
+    let routes = vec![
+        &env,
+        SplitRoute {
+            operations: operations.clone(),
+            weight: 1,
+        },
+        SplitRoute {
+            operations,
+            weight: 0,
+        },
+    ];
+
+    multihop.swap_split(
+        &recipient,
+        &None,
+        &routes,
+        &None,
+        &1_000i128,
+        &None,
+        &None::<u64>,
+    );
+}
+
+#[test]
+fn split_routes_sharing_a_pool_are_rejected() {
+    let env = Env::default();
+    let admin = Address::generate(&env);
+
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let token1 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+    let token2 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+    let token3 = deploy_and_mint_tokens(&env, &admin, 10_000_000i128);
+
+    let factory_client = deploy_and_initialize_factory(&env, admin.clone());
+    for (token_a, token_b) in [(&token1, &token2), (&token1, &token3), (&token3, &token2)] {
+        deploy_and_initialize_pool(
+            &env,
+            &factory_client,
+            admin.clone(),
+            token_a.address.clone(),
+            1_000_000,
+            token_b.address.clone(),
+            1_000_000,
+            None,
+            PoolType::Xyk,
+        );
+    }
+
+    let multihop = deploy_multihop_contract(&env, admin.clone(), &factory_client.address);
+    let recipient = Address::generate(&env);
+    token1.mint(&recipient, &100_000i128);
+
+    let direct_operations = vec![
+        &env,
+        Swap {
+            offer_asset: token1.address.clone(),
+            ask_asset: token2.address.clone(),
+            ask_asset_min_amount: None::<i128>,
+            pool_type: PoolType::Xyk,
+        },
+    ];
+    let indirect_operations = vec![
+        &env,
+        Swap {
+            offer_asset: token1.address.clone(),
+            ask_asset: token3.address.clone(),
+            ask_asset_min_amount: None::<i128>,
+            pool_type: PoolType::Xyk,
+        },
+        Swap {
+            offer_asset: token3.address.clone(),
+            ask_asset: token2.address.clone(),
+            ask_asset_min_amount: None::<i128>,
+            pool_type: PoolType::Xyk,
+        },
+    ];
+
+    // routes through different pools are fine
+    let routes = vec![
+        &env,
+        SplitRoute {
+            operations: direct_operations.clone(),
+            weight: 1,
+        },
+        SplitRoute {
+            operations: indirect_operations,
+            weight: 1,
+        },
+    ];
+    multihop.simulate_split_swap(&routes, &100_000i128);
+
+    // the second route would be priced against reserves the first route already moved
+    let routes = vec![
+        &env,
+        SplitRoute {
+            operations: direct_operations.clone(),
+            weight: 1,
+        },
+        SplitRoute {
+            operations: direct_operations,
+            weight: 1,
+        },
+    ];
+    assert_eq!(
+        multihop.try_simulate_split_swap(&routes, &100_000i128),
+        Err(Ok(soroban_sdk::Error::from_contract_error(
+            crate::error::ContractError::SplitRoutesSharePool as u32
+        )))
+    );
+    assert_eq!(
+        multihop.try_swap_split(
+            &recipient,
+            &None,
+            &routes,
+            &None,
+            &100_000i128,
+            &None,
+            &None::<u64>,
+        ),
+        Err(Ok(soroban_sdk::Error::from_contract_error(
+            crate::error::ContractError::SplitRoutesSharePool as u32
+        )))
+    );
+    assert_eq!(token1.balance(&recipient), 100_000i128);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #12)")]
+fn swap_panics_when_paused() {
+    let env = Env::default();
+    let admin = Address::generate(&env);
+
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let token1 = deploy_and_mint_tokens(&env, &admin, 1_001_000i128);
+    let token2 = deploy_and_mint_tokens(&env, &admin, 1_001_000i128);
+
+    let factory_client = deploy_and_initialize_factory(&env, admin.clone());
+    deploy_and_initialize_pool(
+        &env,
+        &factory_client,
+        admin.clone(),
+        token1.address.clone(),
+        1_000_000,
+        token2.address.clone(),
+        1_000_000,
+        None,
+        PoolType::Xyk,
+    );
+
+    let multihop = deploy_multihop_contract(&env, admin.clone(), &factory_client.address);
+    let guardian = Address::generate(&env);
+    multihop.set_guardian(&guardian);
+    multihop.pause(&guardian);
+    assert!(multihop.query_paused());
+
+    let recipient = Address::generate(&env);
+    token1.mint(&recipient, &1_000i128);
+    let operations = vec![
+        &env,
+        Swap {
+            offer_asset: token1.address.clone(),
+            ask_asset: token2.address.clone(),
+            ask_asset_min_amount: None::<i128>,
+            pool_type: PoolType::Xyk,
+        },
+    ];
+
+    multihop.swap(
+        &recipient,
+        &None,
+        &operations,
+        &None,
+        &1_000,
+        &None,
+        &None::<u64>,
+    );
+}
+
+#[test]
+fn swap_works_again_after_unpause() {
+    let env = Env::default();
+    let admin = Address::generate(&env);
+
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let token1 = deploy_and_mint_tokens(&env, &admin, 1_001_000i128);
+    let token2 = deploy_and_mint_tokens(&env, &admin, 1_001_000i128);
+
+    let factory_client = deploy_and_initialize_factory(&env, admin.clone());
+    deploy_and_initialize_pool(
+        &env,
+        &factory_client,
+        admin.clone(),
+        token1.address.clone(),
+        1_000_000,
+        token2.address.clone(),
+        1_000_000,
+        None,
+        PoolType::Xyk,
+    );
+
+    let multihop = deploy_multihop_contract(&env, admin.clone(), &factory_client.address);
+    multihop.pause(&admin);
+    multihop.unpause();
+    assert!(!multihop.query_paused());
+    assert_eq!(multihop.query_guardian(), None);
+
+    let recipient = Address::generate(&env);
+    token1.mint(&recipient, &1_000i128);
+    let operations = vec![
+        &env,
+        Swap {
+            offer_asset: token1.address.clone(),
+            ask_asset: token2.address.clone(),
+            ask_asset_min_amount: None::<i128>,
+            pool_type: PoolType::Xyk,
+        },
+    ];
+
+    multihop.swap(
+        &recipient,
+        &None,
+        &operations,
+        &None,
+        &1_000,
+        &None,
+        &None::<u64>,
+    );
+
+    assert_eq!(token2.balance(&recipient), 1_000i128);
+}
@@ -1,6 +1,28 @@
-use soroban_sdk::{log, panic_with_error, Env, Vec};
+use phoenix::utils::PoolType;
+use soroban_sdk::{
+    log, panic_with_error, vec,
+    xdr::{ScErrorCode, ScErrorType},
+    Address, Env, Error, InvokeError, Vec,
+};
 
-use crate::{error::ContractError, storage::Swap};
+use crate::{
+    concentrated_pool,
+    error::ContractError,
+    factory_contract, stable_multi_pool, stable_pool,
+    storage::{Referral, SplitRoute, Swap},
+    weighted_pool, xyk_pool,
+};
+
+/// Upper bound for the `max_hops` accepted by `find_best_route`, as the number of candidate
+/// paths grows exponentially with it.
+pub const MAX_ROUTE_HOPS: u32 = 4;
+
+/// Upper bound for the pool simulations a single `find_best_route` call may run, so that a
+/// densely connected pool graph fails explicitly instead of exhausting the budget.
+pub const MAX_ROUTE_SIMULATIONS: u32 = 64;
+
+/// How many times `required_offer_amount` may bump a reverse simulated amount by one unit.
+const MAX_ROUNDING_ADJUSTMENTS: u32 = 10;
 
 pub fn verify_swap(env: &Env, operations: &Vec<Swap>) {
     for (current, next) in operations.iter().zip(operations.iter().skip(1)) {
@@ -23,10 +45,473 @@ pub fn verify_reverse_swap(env: &Env, operations: &Vec<Swap>) {
     }
 }
 
+/// Checks that every route is a valid, non-empty swap path with a non-zero weight and that all
+/// routes trade the same offer asset for the same ask asset. Returns the sum of the weights.
+pub fn verify_split_routes(env: &Env, routes: &Vec<SplitRoute>) -> u64 {
+    let Some(first_route) = routes.first() else {
+        log!(&env, "Multihop: Verify Split Routes: no routes provided");
+        panic_with_error!(&env, ContractError::OperationsEmpty);
+    };
+    let (Some(first_op), Some(last_op)) = (
+        first_route.operations.first(),
+        first_route.operations.last(),
+    ) else {
+        log!(
+            &env,
+            "Multihop: Verify Split Routes: route without operations"
+        );
+        panic_with_error!(&env, ContractError::OperationsEmpty);
+    };
+
+    let mut total_weight: u64 = 0;
+    for route in routes.iter() {
+        let (Some(route_first_op), Some(route_last_op)) =
+            (route.operations.first(), route.operations.last())
+        else {
+            log!(
+                &env,
+                "Multihop: Verify Split Routes: route without operations"
+            );
+            panic_with_error!(&env, ContractError::OperationsEmpty);
+        };
+        verify_swap(env, &route.operations);
+
+        if route_first_op.offer_asset != first_op.offer_asset
+            || route_last_op.ask_asset != last_op.ask_asset
+        {
+            log!(
+                &env,
+                "Multihop: Verify Split Routes: all routes must share the offer and ask asset"
+            );
+            panic_with_error!(&env, ContractError::InvalidSplitRoutes);
+        }
+
+        if route.weight == 0 {
+            log!(
+                &env,
+                "Multihop: Verify Split Routes: route weight must be positive"
+            );
+            panic_with_error!(&env, ContractError::InvalidSplitRoutes);
+        }
+        total_weight += route.weight as u64;
+    }
+
+    total_weight
+}
+
+/// Checks that no pool is used by more than one route of a split swap. Every route is priced
+/// against the reserves before the swap, which does not hold when an earlier route already
+/// moved the reserves of a shared pool.
+pub fn verify_split_routes_disjoint(
+    env: &Env,
+    factory_client: &factory_contract::Client,
+    routes: &Vec<SplitRoute>,
+) {
+    let mut used_pools: Vec<Address> = vec![env];
+    for route in routes.iter() {
+        let mut route_pools: Vec<Address> = vec![env];
+        for operation in route.operations.iter() {
+            let pool = get_pool_for_operation(env, factory_client, &operation);
+            if used_pools.contains(&pool) {
+                log!(
+                    &env,
+                    "Multihop: Verify Split Routes: routes must not share a pool"
+                );
+                panic_with_error!(&env, ContractError::SplitRoutesSharePool);
+            }
+            route_pools.push_back(pool);
+        }
+        used_pools.append(&route_pools);
+    }
+}
+
+/// Share of `amount` allocated to a route of `weight` out of `total_weight`, rounded down.
+pub fn split_amount(amount: i128, weight: u32, total_weight: u64) -> i128 {
+    amount * weight as i128 / total_weight as i128
+}
+
+/// Looks up the pool of the operation's `pool_type` for the operation's token pair.
+pub fn get_pool_for_operation(
+    env: &Env,
+    factory_client: &factory_contract::Client,
+    operation: &Swap,
+) -> Address {
+    let pool = factory_client
+        .query_pools_for_pair(&operation.offer_asset, &operation.ask_asset)
+        .iter()
+        .find(|(pool_type, _)| *pool_type as u32 == operation.pool_type as u32);
+
+    match pool {
+        Some((_, address)) => address,
+        None => {
+            log!(
+                &env,
+                "Multihop: Get pool for operation: no pool of the requested type for the pair"
+            );
+            panic_with_error!(&env, ContractError::LiquidityPoolNotFound);
+        }
+    }
+}
+
+/// Collects every pool registered in the factory as `(pool_address, pool_type, token_a, token_b)`.
+/// A stable multi pool is listed once for every pair of its coins.
+pub fn get_all_pools(
+    env: &Env,
+    factory_client: &factory_contract::Client,
+) -> Vec<(Address, PoolType, Address, Address)> {
+    let mut pools = vec![env];
+
+    for pool in factory_client.query_all_pools_details().iter() {
+        let token_a = pool.pool_response.asset_a.address;
+        let token_b = pool.pool_response.asset_b.address;
+
+        let pool_type = factory_client
+            .query_pools_for_pair(&token_a, &token_b)
+            .iter()
+            .find(|(_, address)| *address == pool.pool_address)
+            .map(|(pool_type, _)| match pool_type {
+                factory_contract::PoolType::Xyk => PoolType::Xyk,
+                factory_contract::PoolType::Stable => PoolType::Stable,
+                factory_contract::PoolType::Concentrated => PoolType::Concentrated,
+                factory_contract::PoolType::Weighted => PoolType::Weighted,
+                factory_contract::PoolType::StableMulti => PoolType::StableMulti,
+            });
+
+        match pool_type {
+            Some(PoolType::StableMulti) => {
+                let assets = stable_multi_pool::Client::new(env, &pool.pool_address)
+                    .query_pool_info()
+                    .assets;
+                for (index, asset_a) in assets.iter().enumerate() {
+                    for asset_b in assets.iter().skip(index + 1) {
+                        pools.push_back((
+                            pool.pool_address.clone(),
+                            PoolType::StableMulti,
+                            asset_a.address.clone(),
+                            asset_b.address,
+                        ));
+                    }
+                }
+            }
+            Some(pool_type) => pools.push_back((pool.pool_address, pool_type, token_a, token_b)),
+            None => (),
+        }
+    }
+
+    pools
+}
+
+/// Swaps `offer_amount` of `operation.offer_asset` in the pool behind `pool_address` and returns
+/// the ask, commission and spread amounts of the hop.
+#[allow(clippy::too_many_arguments)]
+pub fn swap_in_pool(
+    env: &Env,
+    pool_address: &Address,
+    operation: &Swap,
+    recipient: &Address,
+    referral: &Option<Referral>,
+    offer_amount: i128,
+    ask_asset_min_amount: Option<i128>,
+    max_spread_bps: Option<i64>,
+    deadline: Option<u64>,
+) -> (i128, i128, i128) {
+    // due to different pool libraries we cannot use shorter match statement.
+    match operation.pool_type {
+        PoolType::Xyk => {
+            let response = xyk_pool::Client::new(env, pool_address).swap(
+                recipient,
+                &referral.clone().map(|r| xyk_pool::Referral {
+                    address: r.address,
+                    fee_bps: r.fee_bps,
+                }),
+                &operation.offer_asset,
+                &offer_amount,
+                &ask_asset_min_amount,
+                &max_spread_bps,
+                &deadline,
+            );
+            (
+                response.ask_amount,
+                response.commission_amount,
+                response.spread_amount,
+            )
+        }
+        PoolType::Stable => {
+            let response = stable_pool::Client::new(env, pool_address).swap(
+                recipient,
+                &referral.clone().map(|r| stable_pool::Referral {
+                    address: r.address,
+                    fee_bps: r.fee_bps,
+                }),
+                &operation.offer_asset,
+                &offer_amount,
+                &ask_asset_min_amount,
+                &max_spread_bps,
+                &deadline,
+            );
+            (
+                response.ask_amount,
+                response.commission_amount,
+                response.spread_amount,
+            )
+        }
+        PoolType::Concentrated => {
+            let response = concentrated_pool::Client::new(env, pool_address).swap(
+                recipient,
+                &referral.clone().map(|r| concentrated_pool::Referral {
+                    address: r.address,
+                    fee_bps: r.fee_bps,
+                }),
+                &operation.offer_asset,
+                &offer_amount,
+                &ask_asset_min_amount,
+                &max_spread_bps,
+                &deadline,
+            );
+            (
+                response.ask_amount,
+                response.commission_amount,
+                response.spread_amount,
+            )
+        }
+        PoolType::Weighted => {
+            let response = weighted_pool::Client::new(env, pool_address).swap(
+                recipient,
+                &referral.clone().map(|r| weighted_pool::Referral {
+                    address: r.address,
+                    fee_bps: r.fee_bps,
+                }),
+                &operation.offer_asset,
+                &offer_amount,
+                &ask_asset_min_amount,
+                &max_spread_bps,
+                &deadline,
+            );
+            (
+                response.ask_amount,
+                response.commission_amount,
+                response.spread_amount,
+            )
+        }
+        PoolType::StableMulti => {
+            let response = stable_multi_pool::Client::new(env, pool_address).swap(
+                recipient,
+                &referral.clone().map(|r| stable_multi_pool::Referral {
+                    address: r.address,
+                    fee_bps: r.fee_bps,
+                }),
+                &operation.offer_asset,
+                &operation.ask_asset,
+                &offer_amount,
+                &ask_asset_min_amount,
+                &max_spread_bps,
+                &deadline,
+            );
+            (
+                response.ask_amount,
+                response.commission_amount,
+                response.spread_amount,
+            )
+        }
+    }
+}
+
+/// Simulates offering `offer_amount` to the pool behind `pool_address` and returns the ask,
+/// commission and spread amounts, or the error the pool failed with.
+pub fn simulate_swap_in_pool(
+    env: &Env,
+    pool_address: &Address,
+    operation: &Swap,
+    offer_amount: i128,
+) -> Result<(i128, i128, i128), Error> {
+    // due to different pool libraries we cannot use shorter match statement.
+    match operation.pool_type {
+        PoolType::Xyk => xyk_pool::Client::new(env, pool_address)
+            .try_simulate_swap(&operation.offer_asset, &offer_amount)
+            .map(|response| response.map(|r| (r.ask_amount, r.commission_amount, r.spread_amount))),
+        PoolType::Stable => stable_pool::Client::new(env, pool_address)
+            .try_simulate_swap(&operation.offer_asset, &offer_amount)
+            .map(|response| response.map(|r| (r.ask_amount, r.commission_amount, r.spread_amount))),
+        PoolType::Concentrated => concentrated_pool::Client::new(env, pool_address)
+            .try_simulate_swap(&operation.offer_asset, &offer_amount)
+            .map(|response| response.map(|r| (r.ask_amount, r.commission_amount, r.spread_amount))),
+        PoolType::Weighted => weighted_pool::Client::new(env, pool_address)
+            .try_simulate_swap(&operation.offer_asset, &offer_amount)
+            .map(|response| response.map(|r| (r.ask_amount, r.commission_amount, r.spread_amount))),
+        PoolType::StableMulti => stable_multi_pool::Client::new(env, pool_address)
+            .try_simulate_swap(&operation.offer_asset, &operation.ask_asset, &offer_amount)
+            .map(|response| response.map(|r| (r.ask_amount, r.commission_amount, r.spread_amount))),
+    }
+    .map_or_else(
+        |error| Err(invoke_error(error)),
+        |response| response.map_err(Error::from),
+    )
+}
+
+/// Simulates asking `ask_amount` from the pool behind `pool_address` and returns the offer,
+/// commission and spread amounts, or the error the pool failed with.
+pub fn simulate_reverse_swap_in_pool(
+    env: &Env,
+    pool_address: &Address,
+    operation: &Swap,
+    ask_amount: i128,
+) -> Result<(i128, i128, i128), Error> {
+    // due to different pool libraries we cannot use shorter match statement.
+    match operation.pool_type {
+        PoolType::Xyk => xyk_pool::Client::new(env, pool_address)
+            .try_simulate_reverse_swap(&operation.ask_asset, &ask_amount)
+            .map(|response| {
+                response.map(|r| (r.offer_amount, r.commission_amount, r.spread_amount))
+            }),
+        PoolType::Stable => stable_pool::Client::new(env, pool_address)
+            .try_simulate_reverse_swap(&operation.ask_asset, &ask_amount)
+            .map(|response| {
+                response.map(|r| (r.offer_amount, r.commission_amount, r.spread_amount))
+            }),
+        PoolType::Concentrated => concentrated_pool::Client::new(env, pool_address)
+            .try_simulate_reverse_swap(&operation.ask_asset, &ask_amount)
+            .map(|response| {
+                response.map(|r| (r.offer_amount, r.commission_amount, r.spread_amount))
+            }),
+        PoolType::Weighted => weighted_pool::Client::new(env, pool_address)
+            .try_simulate_reverse_swap(&operation.ask_asset, &ask_amount)
+            .map(|response| {
+                response.map(|r| (r.offer_amount, r.commission_amount, r.spread_amount))
+            }),
+        PoolType::StableMulti => stable_multi_pool::Client::new(env, pool_address)
+            .try_simulate_reverse_swap(&operation.offer_asset, &operation.ask_asset, &ask_amount)
+            .map(|response| {
+                response.map(|r| (r.offer_amount, r.commission_amount, r.spread_amount))
+            }),
+    }
+    .map_or_else(
+        |error| Err(invoke_error(error)),
+        |response| response.map_err(Error::from),
+    )
+}
+
+/// Turns the error of a failed pool invocation back into the error the pool raised.
+fn invoke_error(error: Result<Error, InvokeError>) -> Error {
+    match error {
+        Ok(error) => error,
+        Err(InvokeError::Contract(code)) => Error::from_contract_error(code),
+        Err(InvokeError::Abort) => {
+            Error::from_type_and_code(ScErrorType::Context, ScErrorCode::InvalidAction)
+        }
+    }
+}
+
+/// Amount of `operation.offer_asset` needed to get at least `ask_amount` out of the pool.
+/// The reverse simulation rounds, so the result is bumped until a forward simulation confirms it.
+pub fn required_offer_amount(
+    env: &Env,
+    pool_address: &Address,
+    operation: &Swap,
+    ask_amount: i128,
+) -> i128 {
+    let (mut offer_amount, _, _) =
+        simulate_reverse_swap_in_pool(env, pool_address, operation, ask_amount)
+            .unwrap_or_else(|error| panic_with_error!(env, error));
+
+    for _ in 0..MAX_ROUNDING_ADJUSTMENTS {
+        let simulated = simulate_swap_in_pool(env, pool_address, operation, offer_amount);
+        if simulated.map_or(false, |(simulated, _, _)| simulated >= ask_amount) {
+            break;
+        }
+        offer_amount += 1;
+    }
+
+    offer_amount
+}
+
+/// Depth-first search over all paths of at most `hops_left` hops that never revisit an asset,
+/// keeping the path with the highest ask amount in `best`. Every simulated hop consumes one of
+/// `simulations_left`; running out aborts the search.
+#[allow(clippy::too_many_arguments)]
+pub fn search_best_route(
+    env: &Env,
+    pools: &Vec<(Address, PoolType, Address, Address)>,
+    offer_asset: &Address,
+    ask_asset: &Address,
+    amount: i128,
+    hops_left: u32,
+    path: &mut Vec<Swap>,
+    best: &mut Option<(Vec<Swap>, i128)>,
+    simulations_left: &mut u32,
+) {
+    for (pool_address, pool_type, token_a, token_b) in pools.iter() {
+        let next_asset = if *offer_asset == token_a {
+            token_b
+        } else if *offer_asset == token_b {
+            token_a
+        } else {
+            continue;
+        };
+
+        if path.iter().any(|op| op.offer_asset == next_asset) {
+            continue;
+        }
+
+        if *simulations_left == 0 {
+            log!(
+                env,
+                "Multihop: Find best route: search exceeds {} pool simulations, lower max_hops",
+                MAX_ROUTE_SIMULATIONS
+            );
+            panic_with_error!(env, ContractError::RouteSearchTooExpensive);
+        }
+        *simulations_left -= 1;
+
+        let operation = Swap {
+            offer_asset: offer_asset.clone(),
+            ask_asset: next_asset.clone(),
+            ask_asset_min_amount: None,
+            pool_type,
+        };
+        // pools that cannot serve the hop, e.g. for lack of liquidity, are skipped
+        let Some(next_amount) = simulate_swap_in_pool(env, &pool_address, &operation, amount)
+            .ok()
+            .map(|(ask_amount, _, _)| ask_amount)
+            .filter(|ask_amount| *ask_amount > 0)
+        else {
+            continue;
+        };
+
+        path.push_back(operation);
+
+        if next_asset == *ask_asset {
+            if best
+                .as_ref()
+                .map_or(true, |(_, best_amount)| next_amount > *best_amount)
+            {
+                *best = Some((path.clone(), next_amount));
+            }
+        } else if hops_left > 1 {
+            search_best_route(
+                env,
+                pools,
+                &next_asset,
+                ask_asset,
+                next_amount,
+                hops_left - 1,
+                path,
+                best,
+                simulations_left,
+            );
+        }
+
+        path.pop_back();
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{storage::Swap, utils::verify_swap};
+    use crate::{
+        storage::{SplitRoute, Swap},
+        utils::verify_swap,
+    };
+    use phoenix::utils::PoolType;
 
     use soroban_sdk::{testutils::Address as _, vec, Address, Env};
 
@@ -43,16 +528,19 @@ mod tests {
             offer_asset: token1.clone(),
             ask_asset: token2.clone(),
             ask_asset_min_amount: None::<i128>,
+            pool_type: PoolType::Xyk,
         };
         let swap2 = Swap {
             offer_asset: token2.clone(),
             ask_asset: token3.clone(),
             ask_asset_min_amount: None::<i128>,
+            pool_type: PoolType::Xyk,
         };
         let swap3 = Swap {
             offer_asset: token3.clone(),
             ask_asset: token4.clone(),
             ask_asset_min_amount: None::<i128>,
+            pool_type: PoolType::Xyk,
         };
 
         let operations = vec![&env, swap1, swap2, swap3];
@@ -73,16 +561,19 @@ mod tests {
             offer_asset: token3.clone(),
             ask_asset: token4.clone(),
             ask_asset_min_amount: None::<i128>,
+            pool_type: PoolType::Xyk,
         };
         let swap2 = Swap {
             offer_asset: token2.clone(),
             ask_asset: token3.clone(),
             ask_asset_min_amount: None::<i128>,
+            pool_type: PoolType::Xyk,
         };
         let swap3 = Swap {
             offer_asset: token1.clone(),
             ask_asset: token2.clone(),
             ask_asset_min_amount: None::<i128>,
+            pool_type: PoolType::Xyk,
         };
 
         let operations = vec![&env, swap1, swap2, swap3];
@@ -104,11 +595,13 @@ mod tests {
             offer_asset: token1.clone(),
             ask_asset: token2.clone(),
             ask_asset_min_amount: None::<i128>,
+            pool_type: PoolType::Xyk,
         };
         let swap2 = Swap {
             offer_asset: token3.clone(),
             ask_asset: token4.clone(),
             ask_asset_min_amount: None::<i128>,
+            pool_type: PoolType::Xyk,
         };
 
         let operations = vec![&env, swap1, swap2];
@@ -130,15 +623,64 @@ mod tests {
             offer_asset: token1.clone(),
             ask_asset: token2.clone(),
             ask_asset_min_amount: None::<i128>,
+            pool_type: PoolType::Xyk,
         };
         let swap2 = Swap {
             offer_asset: token3.clone(),
             ask_asset: token4.clone(),
             ask_asset_min_amount: None::<i128>,
+            pool_type: PoolType::Xyk,
         };
 
         let operations = vec![&env, swap1, swap2];
 
         verify_reverse_swap(&env, &operations);
     }
+
+    #[test]
+    fn split_amount_is_proportional_to_weight() {
+        assert_eq!(split_amount(1_000, 1, 4), 250);
+        assert_eq!(split_amount(1_000, 3, 4), 750);
+        // rounded down, the remainder is left for the last route
+        assert_eq!(split_amount(1_000, 1, 3), 333);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Multihop: Verify Split Routes: all routes must share the offer and ask asset"
+    )]
+    fn verify_split_routes_should_fail_when_ask_assets_differ() {
+        let env = Env::default();
+
+        let token1 = Address::generate(&env);
+        let token2 = Address::generate(&env);
+        let token3 = Address::generate(&env);
+
+        let route1 = SplitRoute {
+            operations: vec![
+                &env,
+                Swap {
+                    offer_asset: token1.clone(),
+                    ask_asset: token2.clone(),
+                    ask_asset_min_amount: None::<i128>,
+                    pool_type: PoolType::Xyk,
+                },
+            ],
+            weight: 1,
+        };
+        let route2 = SplitRoute {
+            operations: vec![
+                &env,
+                Swap {
+                    offer_asset: token1.clone(),
+                    ask_asset: token3.clone(),
+                    ask_asset_min_amount: None::<i128>,
+                    pool_type: PoolType::Xyk,
+                },
+            ],
+            weight: 1,
+        };
+
+        verify_split_routes(&env, &vec![&env, route1, route2]);
+    }
 }
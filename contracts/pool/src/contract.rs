@@ -1,6 +1,6 @@
 use soroban_sdk::{
-    contract, contractimpl, contractmeta, log, panic_with_error, Address, BytesN, Env, IntoVal,
-    String,
+    contract, contractimpl, contractmeta, log, panic_with_error, Address, Bytes, BytesN, Env,
+    IntoVal, String, Symbol,
 };
 
 use num_integer::Roots;
@@ -11,17 +11,20 @@ use crate::{
     storage::{
         get_config, save_config,
         utils::{self, get_admin, is_initialized, set_initialized},
-        Asset, ComputeSwap, Config, LiquidityPoolInfo, PairType, PoolResponse,
-        SimulateReverseSwapResponse, SimulateSwapResponse,
+        Asset, ComputeSwap, Config, LiquidityPoolInfo, PairType, PoolResponse, Referral,
+        SimulateProvideLiquidityResponse, SimulateReverseSwapResponse, SimulateSwapResponse,
+        SimulateWithdrawSingleResponse, SwapResponse,
     },
     token_contract,
 };
 use phoenix::{
-    utils::{is_approx_ratio, LiquidityPoolInitInfo},
-    validate_bps, validate_int_parameters,
+    oracle::compute_twap, utils::LiquidityPoolInitInfo, validate_bps, validate_int_parameters,
 };
 use soroban_decimal::Decimal;
 
+// Amount of LP shares locked in the pool on the first deposit
+const MINIMUM_LIQUIDITY_AMOUNT: i128 = 1000;
+
 // Metadata that is added on to the WASM custom section
 contractmeta!(
     key = "Description",
@@ -66,19 +69,20 @@ pub trait LiquidityPoolTrait {
     // `offer_asset` is the asset that the user would like to swap for the other token in the pool.
     // `offer_amount` is the amount being sold, with `max_spread_bps` being a safety to make sure you receive at least that amount.
     // swap will transfer the selling token "to" to this contract, and then the contract will transfer the buying token to `sender`.
-    // Returns the amount of the token being bought.
+    // An optional `referral` receives `fee_bps` of the bought amount, capped by the pool's `max_referral_bps`.
+    // Returns the amount of the token being bought with the commission, fees and spread of the swap.
+    #[allow(clippy::too_many_arguments)]
     fn swap(
         env: Env,
         sender: Address,
-        // FIXM: Disable Referral struct
-        // referral: Option<Referral>,
+        referral: Option<Referral>,
         offer_asset: Address,
         offer_amount: i128,
         // Minimum amount of the ask token user expects to receive
         ask_asset_min_amount: Option<i128>,
         max_spread_bps: Option<i64>,
         deadline: Option<u64>,
-    ) -> i128;
+    ) -> SwapResponse;
 
     // transfers share_amount of pool share tokens to this contract, burns all pools share tokens in this contracts, and sends the
     // corresponding amount of token_a and token_b to "to".
@@ -92,8 +96,55 @@ pub trait LiquidityPoolTrait {
         deadline: Option<u64>,
     ) -> (i128, i128);
 
+    // Same as `provide_liquidity`, but the minted pool shares are bonded right away in the pool's
+    // stake contract on behalf of `depositor`.
+    // Returns the amount of shares bonded
+    #[allow(clippy::too_many_arguments)]
+    fn provide_liquidity_and_stake(
+        env: Env,
+        depositor: Address,
+        desired_a: Option<i128>,
+        min_a: Option<i128>,
+        desired_b: Option<i128>,
+        min_b: Option<i128>,
+        custom_slippage_bps: Option<i64>,
+        deadline: Option<u64>,
+    ) -> i128;
+
+    // Unbonds the stake of `recipient` made at `stake_timestamp` from the pool's stake contract and
+    // withdraws the liquidity for the unbonded shares in the same call.
+    // Returns amount of both tokens withdrawn
+    fn unstake_and_withdraw(
+        env: Env,
+        recipient: Address,
+        stake_amount: i128,
+        stake_timestamp: u64,
+        min_a: i128,
+        min_b: i128,
+        deadline: Option<u64>,
+    ) -> (i128, i128);
+
+    // Burns `share_amount` of pool shares and pays out only `ask_asset`. The other token of the
+    // withdrawn share is swapped for `ask_asset` against the reserves left after the withdrawal.
+    // Returns the amount of `ask_asset` paid out
+    fn withdraw_liquidity_single(
+        env: Env,
+        recipient: Address,
+        share_amount: i128,
+        ask_asset: Address,
+        min_out: i128,
+        deadline: Option<u64>,
+    ) -> i128;
+
+    // Lends `amount` of `token` from the pool reserves to `receiver` for the duration of the call.
+    // The pool calls `on_flash_loan(token, amount, fee, data)` on the receiver contract, which
+    // must transfer `amount` plus the flash loan fee back to the pool before it returns.
+    // Returns the fee paid
+    fn flash_loan(env: Env, receiver: Address, token: Address, amount: i128, data: Bytes) -> i128;
+
     // Allows admin address set during initialization to change some parameters of the
     // configuration
+    #[allow(clippy::too_many_arguments)]
     fn update_config(
         env: Env,
         new_admin: Option<Address>,
@@ -102,8 +153,28 @@ pub trait LiquidityPoolTrait {
         max_allowed_slippage_bps: Option<i64>,
         max_allowed_spread_bps: Option<i64>,
         max_referral_bps: Option<i64>,
+        protocol_fee_share_bps: Option<i64>,
+        flash_loan_fee_bps: Option<i64>,
     );
 
+    // Sets the guardian, an address allowed to pause the pool next to the admin and the factory
+    fn set_guardian(env: Env, guardian: Address);
+
+    // Pauses swaps, deposits, single token withdrawals and flash loans. Proportional withdrawals
+    // stay available. Callable by the admin, the guardian or the factory
+    fn pause(env: Env, sender: Address);
+
+    // Resumes the pool. Callable by the admin or the factory
+    fn unpause(env: Env, sender: Address);
+
+    // Sets the stored reserves to the actual token balances of the pool, so tokens sent to the
+    // pool directly accrue to the liquidity providers. Callable by the admin only
+    fn sync(env: Env);
+
+    // Sends the tokens held by the pool above the stored reserves to `recipient`.
+    // Callable by the admin only. Returns the amounts of token A and token B sent
+    fn skim(env: Env, recipient: Address) -> (i128, i128);
+
     // Migration entrypoint
     fn upgrade(e: Env, new_wasm_hash: BytesN<32>);
 
@@ -133,9 +204,41 @@ pub trait LiquidityPoolTrait {
         ask_amount: i128,
     ) -> SimulateReverseSwapResponse;
 
+    // Simulate depositing both tokens, returning the amounts taken in the ratio of the pool and the
+    // shares minted. Deposits of a single token, which swap a part of it first, are not covered
+    fn simulate_provide_liquidity(
+        env: Env,
+        desired_a: i128,
+        desired_b: i128,
+        custom_slippage_bps: Option<i64>,
+    ) -> SimulateProvideLiquidityResponse;
+
+    // Simulate withdrawing `share_amount` of pool shares, returning the amounts of token A and
+    // token B paid out
+    fn simulate_withdraw_liquidity(env: Env, share_amount: i128) -> (i128, i128);
+
+    // Simulate withdrawing `share_amount` of pool shares into `ask_asset` only
+    fn simulate_withdraw_single(
+        env: Env,
+        share_amount: i128,
+        ask_asset: Address,
+    ) -> SimulateWithdrawSingleResponse;
+
     fn query_share(env: Env, amount: i128) -> (Asset, Asset);
 
     fn query_total_issued_lp(env: Env) -> i128;
+
+    // Time-weighted average price of `asset` over the last `window_seconds`, expressed in the
+    // other token of the pool with 18 decimal places
+    fn query_twap(env: Env, asset: Address, window_seconds: u64) -> i128;
+
+    fn query_paused(env: Env) -> bool;
+
+    fn query_guardian(env: Env) -> Option<Address>;
+
+    // Returns the actual token balances of the pool minus the stored reserves, for token A and
+    // token B. A positive value is an excess that can be skimmed, a negative one a shortfall
+    fn query_reserve_discrepancy(env: Env) -> (i128, i128);
 }
 
 #[contractimpl]
@@ -233,10 +336,15 @@ impl LiquidityPoolTrait for LiquidityPool {
             max_allowed_spread_bps,
             max_referral_bps,
             default_slippage_bps,
+            // the whole commission goes to the fee recipient until the admin sets a share
+            protocol_fee_share_bps: 10_000,
+            // flash loans pay the swap fee until the admin sets their own
+            flash_loan_fee_bps: swap_fee_bps,
         };
 
         save_config(&env, config);
         utils::save_admin(&env, admin);
+        utils::save_factory(&env, factory_addr);
         utils::save_total_shares(&env, 0);
         utils::save_pool_balance_a(&env, 0);
         utils::save_pool_balance_b(&env, 0);
@@ -258,6 +366,8 @@ impl LiquidityPoolTrait for LiquidityPool {
         custom_slippage_bps: Option<i64>,
         deadline: Option<u64>,
     ) {
+        ensure_not_paused(&env);
+
         if let Some(deadline) = deadline {
             if env.ledger().timestamp() > deadline {
                 log!(
@@ -273,20 +383,13 @@ impl LiquidityPoolTrait for LiquidityPool {
         // sender needs to authorize the deposit
         sender.require_auth();
 
+        utils::update_price_accumulators(&env);
+
         let config = get_config(&env);
         let pool_balance_a = utils::get_pool_balance_a(&env);
         let pool_balance_b = utils::get_pool_balance_b(&env);
 
-        // Check if custom_slippage_bps is more than max_allowed_slippage
-        if let Some(custom_slippage) = custom_slippage_bps {
-            if custom_slippage > config.max_allowed_slippage_bps {
-                log!(
-                    &env,
-                    "Pool: ProvideLiquidity: Custom slippage tolerance is more than max allowed slippage tolerance"
-                );
-                panic_with_error!(env, ContractError::ProvideLiquiditySlippageToleranceTooHigh);
-            }
-        }
+        let allowed_slippage = get_allowed_slippage(&env, &config, custom_slippage_bps);
         // Check if both tokens are provided, one token is provided, or none are provided
         let amounts = match (desired_a, desired_b) {
             // Both tokens are provided
@@ -300,59 +403,73 @@ impl LiquidityPoolTrait for LiquidityPool {
                     min_b,
                     pool_balance_a,
                     pool_balance_b,
-                    Decimal::bps(custom_slippage_bps.unwrap_or(config.default_slippage_bps)),
+                    allowed_slippage,
+                )
+            }
+            // Only token A is provided
+            (Some(a), None) if a > 0 => {
+                let a_for_swap = split_deposit_based_on_pool_ratio(
+                    &env,
+                    &config,
+                    pool_balance_a,
+                    pool_balance_b,
+                    a,
+                    &config.token_a,
+                );
+                let b_from_swap = do_swap(
+                    env.clone(),
+                    sender.clone(),
+                    None,
+                    config.token_a.clone(),
+                    a_for_swap,
+                    None,
+                    None,
+                )
+                .ask_amount;
+                // Deposit the rest of token A together with the token B received from the swap
+                utils::get_deposit_amounts(
+                    &env,
+                    a - a_for_swap,
+                    min_a,
+                    b_from_swap,
+                    min_b,
+                    utils::get_pool_balance_a(&env),
+                    utils::get_pool_balance_b(&env),
+                    allowed_slippage,
+                )
+            }
+            // Only token B is provided
+            (None, Some(b)) if b > 0 => {
+                let b_for_swap = split_deposit_based_on_pool_ratio(
+                    &env,
+                    &config,
+                    pool_balance_a,
+                    pool_balance_b,
+                    b,
+                    &config.token_b,
+                );
+                let a_from_swap = do_swap(
+                    env.clone(),
+                    sender.clone(),
+                    None,
+                    config.token_b.clone(),
+                    b_for_swap,
+                    None,
+                    None,
+                )
+                .ask_amount;
+                // Deposit the token A received from the swap together with the rest of token B
+                utils::get_deposit_amounts(
+                    &env,
+                    a_from_swap,
+                    min_a,
+                    b - b_for_swap,
+                    min_b,
+                    utils::get_pool_balance_a(&env),
+                    utils::get_pool_balance_b(&env),
+                    allowed_slippage,
                 )
             }
-            // TODO: https://github.com/Phoenix-Protocol-Group/phoenix-contracts/issues/204
-            // Providing liquidity with a single token is temporarily disabled
-            // // Only token A is provided
-            // (Some(a), None) if a > 0 => {
-            //     let (a_for_swap, b_from_swap) = split_deposit_based_on_pool_ratio(
-            //         &env,
-            //         &config,
-            //         pool_balance_a,
-            //         pool_balance_b,
-            //         a,
-            //         &config.token_a,
-            //     );
-            //     do_swap(
-            //         env.clone(),
-            //         sender.clone(),
-            //         // FIXM: Disable Referral struct
-            //         // None,
-            //         config.clone().token_a,
-            //         a_for_swap,
-            //         // expected amount of ask token from swap
-            //         Some(b_from_swap),
-            //         None,
-            //     );
-            //     // return: rest of Token A amount, simulated result of swap of portion A
-            //     (a - a_for_swap, b_from_swap)
-            // }
-            // // Only token B is provided
-            // (None, Some(b)) if b > 0 => {
-            //     let (b_for_swap, a_from_swap) = split_deposit_based_on_pool_ratio(
-            //         &env,
-            //         &config,
-            //         pool_balance_a,
-            //         pool_balance_b,
-            //         b,
-            //         &config.token_b,
-            //     );
-            //     do_swap(
-            //         env.clone(),
-            //         sender.clone(),
-            //         // FIXM: Disable Referral struct
-            //         // None,
-            //         config.clone().token_b,
-            //         b_for_swap,
-            //         // expected amount of ask token from swap
-            //         Some(a_from_swap),
-            //         None,
-            //     );
-            //     // return: simulated result of swap of portion B, rest of Token B amount
-            //     (a_from_swap, b - b_for_swap)
-            // }
             // None or invalid amounts are provided
             _ => {
                 log!(
@@ -372,29 +489,23 @@ impl LiquidityPoolTrait for LiquidityPool {
         token_a_client.transfer(&sender, &env.current_contract_address(), &(amounts.0));
         token_b_client.transfer(&sender, &env.current_contract_address(), &(amounts.1));
 
-        let pool_balance_a = utils::get_pool_balance_a(&env);
-        let pool_balance_b = utils::get_pool_balance_b(&env);
-
         // Now calculate how many new pool shares to mint
         let balance_a = utils::get_balance(&env, &config.token_a);
         let balance_b = utils::get_balance(&env, &config.token_b);
-        let total_shares = utils::get_total_shares(&env);
+        let shares = compute_shares(&env, balance_a, balance_b);
 
-        let new_total_shares = if pool_balance_a > 0 && pool_balance_b > 0 {
-            let shares_a = (balance_a * total_shares) / pool_balance_a;
-            let shares_b = (balance_b * total_shares) / pool_balance_b;
-            shares_a.min(shares_b)
-        } else {
-            // In case of empty pool, just produce X*Y shares
-            (balance_a * balance_b).sqrt()
-        };
+        if utils::get_total_shares(&env) == 0 {
+            // the first shares are locked in the pool forever, so the value of a single share can
+            // not be inflated far enough to round the following deposits down to nothing
+            utils::mint_shares(
+                &env,
+                &config.share_token,
+                &env.current_contract_address(),
+                MINIMUM_LIQUIDITY_AMOUNT,
+            );
+        }
 
-        utils::mint_shares(
-            &env,
-            &config.share_token,
-            &sender,
-            new_total_shares - total_shares,
-        );
+        utils::mint_shares(&env, &config.share_token, &sender, shares);
         utils::save_pool_balance_a(&env, balance_a);
         utils::save_pool_balance_b(&env, balance_b);
 
@@ -410,17 +521,19 @@ impl LiquidityPoolTrait for LiquidityPool {
             .publish(("provide_liquidity", "token_b-amount"), amounts.1);
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn swap(
         env: Env,
         sender: Address,
-        // FIXM: Disable Referral struct
-        // referral: Option<Referral>,
+        referral: Option<Referral>,
         offer_asset: Address,
         offer_amount: i128,
         ask_asset_min_amount: Option<i128>,
         max_spread_bps: Option<i64>,
         deadline: Option<u64>,
-    ) -> i128 {
+    ) -> SwapResponse {
+        ensure_not_paused(&env);
+
         if let Some(deadline) = deadline {
             if env.ledger().timestamp() > deadline {
                 log!(env, "Pool: Swap: Transaction executed after deadline!");
@@ -435,7 +548,7 @@ impl LiquidityPoolTrait for LiquidityPool {
         do_swap(
             env,
             sender,
-            // referral,
+            referral,
             offer_asset,
             offer_amount,
             ask_asset_min_amount,
@@ -465,34 +578,116 @@ impl LiquidityPoolTrait for LiquidityPool {
 
         sender.require_auth();
 
+        do_withdraw_liquidity(env, sender, share_amount, min_a, min_b)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn provide_liquidity_and_stake(
+        env: Env,
+        sender: Address,
+        desired_a: Option<i128>,
+        min_a: Option<i128>,
+        desired_b: Option<i128>,
+        min_b: Option<i128>,
+        custom_slippage_bps: Option<i64>,
+        deadline: Option<u64>,
+    ) -> i128 {
         let config = get_config(&env);
 
+        // shares are always minted to the sender, so the difference is what this deposit minted
         let share_token_client = token_contract::Client::new(&env, &config.share_token);
-        share_token_client.transfer(&sender, &env.current_contract_address(), &share_amount);
+        let shares_before = share_token_client.balance(&sender);
+
+        Self::provide_liquidity(
+            env.clone(),
+            sender.clone(),
+            desired_a,
+            min_a,
+            desired_b,
+            min_b,
+            custom_slippage_bps,
+            deadline,
+        );
 
-        let pool_balance_a = utils::get_pool_balance_a(&env);
-        let pool_balance_b = utils::get_pool_balance_b(&env);
+        let minted_shares = share_token_client.balance(&sender) - shares_before;
+        stake_contract::Client::new(&env, &config.stake_contract).bond(&sender, &minted_shares);
 
-        let total_shares = utils::get_total_shares(&env);
+        minted_shares
+    }
 
-        if total_shares == 0i128 {
-            log!(&env, "Pool: WithdrawLiquidity: Critical error - Total shares are equal to zero before withdrawal!");
-            panic_with_error!(env, ContractError::TotalSharesEqualZero);
+    fn unstake_and_withdraw(
+        env: Env,
+        sender: Address,
+        stake_amount: i128,
+        stake_timestamp: u64,
+        min_a: i128,
+        min_b: i128,
+        deadline: Option<u64>,
+    ) -> (i128, i128) {
+        if let Some(deadline) = deadline {
+            if env.ledger().timestamp() > deadline {
+                log!(
+                    env,
+                    "Pool: Unstake and Withdraw: Transaction executed after deadline!"
+                );
+                panic_with_error!(env, ContractError::TransactionAfterTimestampDeadline)
+            }
+        }
+
+        validate_int_parameters!(stake_amount, min_a, min_b);
+
+        sender.require_auth();
+
+        let config = get_config(&env);
+        stake_contract::Client::new(&env, &config.stake_contract).unbond(
+            &sender,
+            &stake_amount,
+            &stake_timestamp,
+        );
+
+        do_withdraw_liquidity(env, sender, stake_amount, min_a, min_b)
+    }
+
+    fn withdraw_liquidity_single(
+        env: Env,
+        sender: Address,
+        share_amount: i128,
+        ask_asset: Address,
+        min_out: i128,
+        deadline: Option<u64>,
+    ) -> i128 {
+        ensure_not_paused(&env);
+
+        if let Some(deadline) = deadline {
+            if env.ledger().timestamp() > deadline {
+                log!(
+                    env,
+                    "Pool: Withdraw Liquidity Single: Transaction executed after deadline!"
+                );
+                panic_with_error!(env, ContractError::TransactionAfterTimestampDeadline)
+            }
         }
 
-        let share_ratio = Decimal::from_ratio(share_amount, total_shares);
+        validate_int_parameters!(share_amount, min_out);
+
+        sender.require_auth();
+
+        utils::update_price_accumulators(&env);
+
+        let config = get_config(&env);
+
+        let share_token_client = token_contract::Client::new(&env, &config.share_token);
+        share_token_client.transfer(&sender, &env.current_contract_address(), &share_amount);
 
-        let return_amount_a = pool_balance_a * share_ratio;
-        let return_amount_b = pool_balance_b * share_ratio;
+        let (ask_share, swap) = compute_withdraw_single(&env, &config, share_amount, &ask_asset);
+        let return_amount = ask_share + swap.return_amount;
 
-        if return_amount_a < min_a || return_amount_b < min_b {
+        if return_amount < min_out {
             log!(
                 &env,
-                "Pool: WithdrawLiquidity: Minimum amount of token_a or token_b is not satisfied! min_a: {}, min_b: {}, return_amount_a: {}, return_amount_b: {}",
-                min_a,
-                min_b,
-                return_amount_a,
-                return_amount_b
+                "Pool: WithdrawLiquiditySingle: Minimum amount is not satisfied! min_out: {}, return_amount: {}",
+                min_out,
+                return_amount
             );
             panic_with_error!(
                 env,
@@ -500,35 +695,115 @@ impl LiquidityPoolTrait for LiquidityPool {
             );
         }
 
-        // burn shares
         utils::burn_shares(&env, &config.share_token, share_amount);
-        // transfer tokens from sender to contract
-        token_contract::Client::new(&env, &config.token_a).transfer(
-            &env.current_contract_address(),
-            &sender,
-            &return_amount_a,
-        );
-        token_contract::Client::new(&env, &config.token_b).transfer(
+
+        let ask_token_client = token_contract::Client::new(&env, &ask_asset);
+        ask_token_client.transfer(&env.current_contract_address(), &sender, &return_amount);
+
+        // the protocol share of the swap commission goes to fee recipient, the rest stays in the pool
+        let protocol_fee_amount = swap.commission_amount * config.protocol_fee_share();
+        ask_token_client.transfer(
             &env.current_contract_address(),
-            &sender,
-            &return_amount_b,
+            &config.fee_recipient,
+            &protocol_fee_amount,
         );
-        // update pool balances
-        utils::save_pool_balance_a(&env, pool_balance_a - return_amount_a);
-        utils::save_pool_balance_b(&env, pool_balance_b - return_amount_b);
+
+        // the other token is withdrawn and swapped back in, so only the ask side changes
+        if ask_asset == config.token_a {
+            let pool_balance_a = utils::get_pool_balance_a(&env);
+            utils::save_pool_balance_a(&env, pool_balance_a - return_amount - protocol_fee_amount);
+        } else {
+            let pool_balance_b = utils::get_pool_balance_b(&env);
+            utils::save_pool_balance_b(&env, pool_balance_b - return_amount - protocol_fee_amount);
+        }
 
         env.events()
-            .publish(("withdraw_liquidity", "sender"), sender);
+            .publish(("withdraw_liquidity_single", "sender"), sender);
         env.events()
-            .publish(("withdraw_liquidity", "shares_amount"), share_amount);
+            .publish(("withdraw_liquidity_single", "shares_amount"), share_amount);
         env.events()
-            .publish(("withdraw_liquidity", "return_amount_a"), return_amount_a);
+            .publish(("withdraw_liquidity_single", "ask_asset"), ask_asset);
+        env.events().publish(
+            ("withdraw_liquidity_single", "return_amount"),
+            return_amount,
+        );
+        env.events().publish(
+            ("withdraw_liquidity_single", "commission_amount"),
+            swap.commission_amount,
+        );
+
+        return_amount
+    }
+
+    fn flash_loan(env: Env, receiver: Address, token: Address, amount: i128, data: Bytes) -> i128 {
+        ensure_not_paused(&env);
+
+        validate_int_parameters!(amount);
+
+        receiver.require_auth();
+
+        let config = get_config(&env);
+        let pool_balance = if token == config.token_a {
+            utils::get_pool_balance_a(&env)
+        } else if token == config.token_b {
+            utils::get_pool_balance_b(&env)
+        } else {
+            log!(&env, "Pool: FlashLoan: Token not found in Pool");
+            panic_with_error!(&env, ContractError::AssetNotInPool);
+        };
+        if amount > pool_balance {
+            log!(
+                &env,
+                "Pool: FlashLoan: Amount exceeds the reserves! amount: {}, reserve: {}",
+                amount,
+                pool_balance
+            );
+            panic_with_error!(&env, ContractError::FlashLoanExceedsReserves);
+        }
+
+        utils::update_price_accumulators(&env);
+
+        let fee_amount = amount * config.flash_loan_fee();
+
+        let token_client = token_contract::Client::new(&env, &token);
+        let balance_before = token_client.balance(&env.current_contract_address());
+        token_client.transfer(&env.current_contract_address(), &receiver, &amount);
+
+        // the pool can not be re-entered while the callback runs, so the reserves stay untouched
+        env.invoke_contract::<()>(
+            &receiver,
+            &Symbol::new(&env, "on_flash_loan"),
+            (token.clone(), amount, fee_amount, data).into_val(&env),
+        );
+
+        let balance_after = token_client.balance(&env.current_contract_address());
+        if balance_after < balance_before + fee_amount {
+            log!(
+                &env,
+                "Pool: FlashLoan: Loan was not repaid! expected: {}, balance: {}",
+                balance_before + fee_amount,
+                balance_after
+            );
+            panic_with_error!(&env, ContractError::FlashLoanNotRepaid);
+        }
+
+        // the fee stays in the pool and accrues to the liquidity providers
+        if token == config.token_a {
+            utils::save_pool_balance_a(&env, pool_balance + fee_amount);
+        } else {
+            utils::save_pool_balance_b(&env, pool_balance + fee_amount);
+        }
+
+        env.events().publish(("flash_loan", "receiver"), receiver);
+        env.events().publish(("flash_loan", "token"), token);
+        env.events().publish(("flash_loan", "amount"), amount);
         env.events()
-            .publish(("withdraw_liquidity", "return_amount_b"), return_amount_b);
+            .publish(("flash_loan", "fee_amount"), fee_amount);
 
-        (return_amount_a, return_amount_b)
+        fee_amount
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn update_config(
         env: Env,
         new_admin: Option<Address>,
@@ -537,6 +812,8 @@ impl LiquidityPoolTrait for LiquidityPool {
         max_allowed_slippage_bps: Option<i64>,
         max_allowed_spread_bps: Option<i64>,
         max_referral_bps: Option<i64>,
+        protocol_fee_share_bps: Option<i64>,
+        flash_loan_fee_bps: Option<i64>,
     ) {
         let admin: Address = utils::get_admin(&env);
         admin.require_auth();
@@ -565,10 +842,117 @@ impl LiquidityPoolTrait for LiquidityPool {
         if let Some(max_referral_bps) = max_referral_bps {
             config.max_referral_bps = max_referral_bps;
         }
+        if let Some(protocol_fee_share_bps) = protocol_fee_share_bps {
+            if !(0..=10_000).contains(&protocol_fee_share_bps) {
+                log!(&env, "Pool: UpdateConfig: Invalid protocol_fee_share_bps");
+                panic_with_error!(&env, ContractError::InvalidBps);
+            }
+            config.protocol_fee_share_bps = protocol_fee_share_bps;
+        }
+        if let Some(flash_loan_fee_bps) = flash_loan_fee_bps {
+            if !(0..=10_000).contains(&flash_loan_fee_bps) {
+                log!(&env, "Pool: UpdateConfig: Invalid flash_loan_fee_bps");
+                panic_with_error!(&env, ContractError::InvalidBps);
+            }
+            config.flash_loan_fee_bps = flash_loan_fee_bps;
+        }
 
         save_config(&env, config);
     }
 
+    fn set_guardian(env: Env, guardian: Address) {
+        let admin: Address = utils::get_admin(&env);
+        admin.require_auth();
+
+        utils::save_guardian(&env, guardian.clone());
+
+        env.events().publish(("set_guardian", "guardian"), guardian);
+    }
+
+    fn pause(env: Env, sender: Address) {
+        sender.require_auth();
+
+        let is_guardian = utils::get_guardian(&env).map_or(false, |guardian| guardian == sender);
+        let is_factory = utils::get_factory(&env).map_or(false, |factory| factory == sender);
+        if sender != utils::get_admin(&env) && !is_factory && !is_guardian {
+            log!(
+                &env,
+                "Pool: Pause: You are not authorized to pause the pool!"
+            );
+            panic_with_error!(&env, ContractError::Unauthorized);
+        }
+
+        utils::set_paused(&env, true);
+
+        env.events().publish(("pause", "sender"), sender);
+    }
+
+    fn unpause(env: Env, sender: Address) {
+        sender.require_auth();
+
+        let is_factory = utils::get_factory(&env).map_or(false, |factory| factory == sender);
+        if sender != utils::get_admin(&env) && !is_factory {
+            log!(
+                &env,
+                "Pool: Unpause: You are not authorized to unpause the pool!"
+            );
+            panic_with_error!(&env, ContractError::Unauthorized);
+        }
+
+        utils::set_paused(&env, false);
+
+        env.events().publish(("unpause", "sender"), sender);
+    }
+
+    fn sync(env: Env) {
+        let admin: Address = utils::get_admin(&env);
+        admin.require_auth();
+
+        utils::update_price_accumulators(&env);
+
+        let config = get_config(&env);
+        let balance_a = utils::get_balance(&env, &config.token_a);
+        let balance_b = utils::get_balance(&env, &config.token_b);
+
+        utils::save_pool_balance_a(&env, balance_a);
+        utils::save_pool_balance_b(&env, balance_b);
+
+        env.events().publish(("sync", "reserve_a"), balance_a);
+        env.events().publish(("sync", "reserve_b"), balance_b);
+    }
+
+    fn skim(env: Env, recipient: Address) -> (i128, i128) {
+        let admin: Address = utils::get_admin(&env);
+        admin.require_auth();
+
+        let config = get_config(&env);
+        let excess_a =
+            (utils::get_balance(&env, &config.token_a) - utils::get_pool_balance_a(&env)).max(0);
+        let excess_b =
+            (utils::get_balance(&env, &config.token_b) - utils::get_pool_balance_b(&env)).max(0);
+
+        if excess_a > 0 {
+            token_contract::Client::new(&env, &config.token_a).transfer(
+                &env.current_contract_address(),
+                &recipient,
+                &excess_a,
+            );
+        }
+        if excess_b > 0 {
+            token_contract::Client::new(&env, &config.token_b).transfer(
+                &env.current_contract_address(),
+                &recipient,
+                &excess_b,
+            );
+        }
+
+        env.events().publish(("skim", "recipient"), recipient);
+        env.events().publish(("skim", "amount_a"), excess_a);
+        env.events().publish(("skim", "amount_b"), excess_b);
+
+        (excess_a, excess_b)
+    }
+
     fn upgrade(env: Env, new_wasm_hash: BytesN<32>) {
         let admin: Address = utils::get_admin(&env);
         admin.require_auth();
@@ -665,6 +1049,7 @@ impl LiquidityPoolTrait for LiquidityPool {
         SimulateSwapResponse {
             ask_amount: compute_swap.return_amount,
             commission_amount: compute_swap.commission_amount,
+            protocol_fee_amount: compute_swap.commission_amount * config.protocol_fee_share(),
             spread_amount: compute_swap.spread_amount,
             total_return,
         }
@@ -702,6 +1087,55 @@ impl LiquidityPoolTrait for LiquidityPool {
         }
     }
 
+    fn simulate_provide_liquidity(
+        env: Env,
+        desired_a: i128,
+        desired_b: i128,
+        custom_slippage_bps: Option<i64>,
+    ) -> SimulateProvideLiquidityResponse {
+        let config = get_config(&env);
+        let pool_balance_a = utils::get_pool_balance_a(&env);
+        let pool_balance_b = utils::get_pool_balance_b(&env);
+
+        let (amount_a, amount_b) = utils::get_deposit_amounts(
+            &env,
+            desired_a,
+            None,
+            desired_b,
+            None,
+            pool_balance_a,
+            pool_balance_b,
+            get_allowed_slippage(&env, &config, custom_slippage_bps),
+        );
+
+        SimulateProvideLiquidityResponse {
+            amount_a,
+            amount_b,
+            shares: compute_shares(&env, pool_balance_a + amount_a, pool_balance_b + amount_b),
+        }
+    }
+
+    fn simulate_withdraw_liquidity(env: Env, share_amount: i128) -> (i128, i128) {
+        compute_withdraw_amounts(&env, share_amount)
+    }
+
+    fn simulate_withdraw_single(
+        env: Env,
+        share_amount: i128,
+        ask_asset: Address,
+    ) -> SimulateWithdrawSingleResponse {
+        let config = get_config(&env);
+
+        let (ask_share, swap) = compute_withdraw_single(&env, &config, share_amount, &ask_asset);
+
+        SimulateWithdrawSingleResponse {
+            ask_amount: ask_share + swap.return_amount,
+            commission_amount: swap.commission_amount,
+            protocol_fee_amount: swap.commission_amount * config.protocol_fee_share(),
+            spread_amount: swap.spread_amount,
+        }
+    }
+
     fn query_share(env: Env, amount: i128) -> (Asset, Asset) {
         let pool_info = Self::query_pool_info(env);
         let total_share = pool_info.asset_lp_share.amount;
@@ -730,6 +1164,54 @@ impl LiquidityPoolTrait for LiquidityPool {
     fn query_total_issued_lp(env: Env) -> i128 {
         utils::get_total_shares(&env)
     }
+
+    fn query_twap(env: Env, asset: Address, window_seconds: u64) -> i128 {
+        let config = get_config(&env);
+        let price_of_a = if asset == config.token_a {
+            true
+        } else if asset == config.token_b {
+            false
+        } else {
+            log!(&env, "Pool: Query TWAP: Asset not found in the pool");
+            panic_with_error!(&env, ContractError::AssetNotInPool);
+        };
+
+        let accumulator = utils::get_price_accumulator(&env);
+        let current =
+            accumulator.accumulate(env.ledger().timestamp(), utils::get_spot_prices(&env));
+
+        compute_twap(
+            &utils::get_observations(&env),
+            &accumulator,
+            &current,
+            window_seconds,
+            price_of_a,
+        )
+        .unwrap_or_else(|| {
+            log!(
+                &env,
+                "Pool: Query TWAP: Not enough price history for the requested window"
+            );
+            panic_with_error!(&env, ContractError::TwapWindowNotCovered);
+        })
+    }
+
+    fn query_paused(env: Env) -> bool {
+        utils::is_paused(&env)
+    }
+
+    fn query_guardian(env: Env) -> Option<Address> {
+        utils::get_guardian(&env)
+    }
+
+    fn query_reserve_discrepancy(env: Env) -> (i128, i128) {
+        let config = get_config(&env);
+
+        (
+            utils::get_balance(&env, &config.token_a) - utils::get_pool_balance_a(&env),
+            utils::get_balance(&env, &config.token_b) - utils::get_pool_balance_b(&env),
+        )
+    }
 }
 
 #[contractimpl]
@@ -743,23 +1225,216 @@ impl LiquidityPool {
     }
 }
 
+fn ensure_not_paused(env: &Env) {
+    if utils::is_paused(env) {
+        log!(env, "Pool: The pool is paused!");
+        panic_with_error!(env, ContractError::ContractPaused);
+    }
+}
+
+/// Burns `share_amount` of the sender's pool shares and sends back the corresponding part of
+/// both reserves. The caller is responsible for authorizing the sender.
+fn do_withdraw_liquidity(
+    env: Env,
+    sender: Address,
+    share_amount: i128,
+    min_a: i128,
+    min_b: i128,
+) -> (i128, i128) {
+    utils::update_price_accumulators(&env);
+
+    let config = get_config(&env);
+
+    let share_token_client = token_contract::Client::new(&env, &config.share_token);
+    share_token_client.transfer(&sender, &env.current_contract_address(), &share_amount);
+
+    let pool_balance_a = utils::get_pool_balance_a(&env);
+    let pool_balance_b = utils::get_pool_balance_b(&env);
+
+    let (return_amount_a, return_amount_b) = compute_withdraw_amounts(&env, share_amount);
+
+    if return_amount_a < min_a || return_amount_b < min_b {
+        log!(
+            &env,
+            "Pool: WithdrawLiquidity: Minimum amount of token_a or token_b is not satisfied! min_a: {}, min_b: {}, return_amount_a: {}, return_amount_b: {}",
+            min_a,
+            min_b,
+            return_amount_a,
+            return_amount_b
+        );
+        panic_with_error!(
+            env,
+            ContractError::WithdrawLiquidityMinimumAmountOfAOrBIsNotSatisfied
+        );
+    }
+
+    // burn shares
+    utils::burn_shares(&env, &config.share_token, share_amount);
+    // transfer tokens from sender to contract
+    token_contract::Client::new(&env, &config.token_a).transfer(
+        &env.current_contract_address(),
+        &sender,
+        &return_amount_a,
+    );
+    token_contract::Client::new(&env, &config.token_b).transfer(
+        &env.current_contract_address(),
+        &sender,
+        &return_amount_b,
+    );
+    // update pool balances
+    utils::save_pool_balance_a(&env, pool_balance_a - return_amount_a);
+    utils::save_pool_balance_b(&env, pool_balance_b - return_amount_b);
+
+    env.events()
+        .publish(("withdraw_liquidity", "sender"), sender);
+    env.events()
+        .publish(("withdraw_liquidity", "shares_amount"), share_amount);
+    env.events()
+        .publish(("withdraw_liquidity", "return_amount_a"), return_amount_a);
+    env.events()
+        .publish(("withdraw_liquidity", "return_amount_b"), return_amount_b);
+
+    (return_amount_a, return_amount_b)
+}
+
+/// The slippage tolerance of a deposit, `custom_slippage_bps` if given, else the default of the
+/// pool.
+fn get_allowed_slippage(env: &Env, config: &Config, custom_slippage_bps: Option<i64>) -> Decimal {
+    // Check if custom_slippage_bps is more than max_allowed_slippage
+    if let Some(custom_slippage) = custom_slippage_bps {
+        if custom_slippage > config.max_allowed_slippage_bps {
+            log!(
+                env,
+                "Pool: ProvideLiquidity: Custom slippage tolerance is more than max allowed slippage tolerance"
+            );
+            panic_with_error!(env, ContractError::ProvideLiquiditySlippageToleranceTooHigh);
+        }
+    }
+
+    Decimal::bps(custom_slippage_bps.unwrap_or(config.default_slippage_bps))
+}
+
+/// The pool shares minted to a depositor for raising the reserves to `balance_a` and `balance_b`.
+/// On the first deposit `MINIMUM_LIQUIDITY_AMOUNT` of the shares stay locked in the pool and are
+/// not part of the result.
+fn compute_shares(env: &Env, balance_a: i128, balance_b: i128) -> i128 {
+    let pool_balance_a = utils::get_pool_balance_a(env);
+    let pool_balance_b = utils::get_pool_balance_b(env);
+    let total_shares = utils::get_total_shares(env);
+
+    let new_total_shares = if pool_balance_a > 0 && pool_balance_b > 0 {
+        let shares_a = (balance_a * total_shares) / pool_balance_a;
+        let shares_b = (balance_b * total_shares) / pool_balance_b;
+        shares_a.min(shares_b)
+    } else {
+        // In case of empty pool, just produce X*Y shares
+        (balance_a * balance_b).sqrt()
+    };
+
+    if total_shares == 0 {
+        if new_total_shares <= MINIMUM_LIQUIDITY_AMOUNT {
+            log!(
+                env,
+                "Pool: ProvideLiquidity: Liquidity amount is too low - shares: {}",
+                new_total_shares
+            );
+            panic_with_error!(env, ContractError::LowLiquidity);
+        }
+        return new_total_shares - MINIMUM_LIQUIDITY_AMOUNT;
+    }
+
+    new_total_shares - total_shares
+}
+
+/// The amounts of token A and token B paid out for `share_amount` of pool shares.
+fn compute_withdraw_amounts(env: &Env, share_amount: i128) -> (i128, i128) {
+    let total_shares = utils::get_total_shares(env);
+
+    if total_shares == 0i128 {
+        log!(env, "Pool: WithdrawLiquidity: Critical error - Total shares are equal to zero before withdrawal!");
+        panic_with_error!(env, ContractError::TotalSharesEqualZero);
+    }
+
+    let share_ratio = Decimal::from_ratio(share_amount, total_shares);
+
+    (
+        utils::get_pool_balance_a(env) * share_ratio,
+        utils::get_pool_balance_b(env) * share_ratio,
+    )
+}
+
+/// Splits a withdrawal of `share_amount` into the proportional amount of `ask_asset` and the
+/// swap of the proportional amount of the other token, made against the reserves that are
+/// left in the pool after the withdrawal.
+/// # Returns
+/// * The proportional amount of `ask_asset` and the result of the swap.
+fn compute_withdraw_single(
+    env: &Env,
+    config: &Config,
+    share_amount: i128,
+    ask_asset: &Address,
+) -> (i128, ComputeSwap) {
+    let pool_balance_a = utils::get_pool_balance_a(env);
+    let pool_balance_b = utils::get_pool_balance_b(env);
+
+    let (pool_balance_ask, pool_balance_offer) = if ask_asset == &config.token_a {
+        (pool_balance_a, pool_balance_b)
+    } else if ask_asset == &config.token_b {
+        (pool_balance_b, pool_balance_a)
+    } else {
+        log!(env, "Pool: Token to withdraw not found in Pool");
+        panic_with_error!(env, ContractError::AssetNotInPool);
+    };
+
+    let total_shares = utils::get_total_shares(env);
+    if total_shares == 0i128 {
+        log!(env, "Pool: WithdrawLiquiditySingle: Critical error - Total shares are equal to zero before withdrawal!");
+        panic_with_error!(env, ContractError::TotalSharesEqualZero);
+    }
+
+    let share_ratio = Decimal::from_ratio(share_amount, total_shares);
+    let ask_share = pool_balance_ask * share_ratio;
+    let offer_share = pool_balance_offer * share_ratio;
+
+    if pool_balance_ask - ask_share <= 0 || pool_balance_offer - offer_share <= 0 {
+        log!(
+            env,
+            "Pool: WithdrawLiquiditySingle: Not enough liquidity left to swap against!"
+        );
+        panic_with_error!(env, ContractError::WithdrawSingleEmptiesPool);
+    }
+
+    let swap = compute_swap(
+        pool_balance_offer - offer_share,
+        pool_balance_ask - ask_share,
+        offer_share,
+        config.protocol_fee_rate(),
+        0i64,
+    );
+
+    (ask_share, swap)
+}
+
 fn do_swap(
     env: Env,
     sender: Address,
-    // FIXM: Disable Referral struct
-    // referral: Option<Referral>,
+    referral: Option<Referral>,
     offer_asset: Address,
     offer_amount: i128,
     ask_asset_min_amount: Option<i128>,
     max_spread: Option<i64>,
-) -> i128 {
+) -> SwapResponse {
     let config = get_config(&env);
-    // FIXM: Disable Referral struct
-    // if let Some(referral) = &referral {
-    //     if referral.fee > config.max_referral_bps {
-    //         panic!("Pool: Swap: Trying to swap with more than the allowed referral fee");
-    //     }
-    // }
+
+    if let Some(referral) = &referral {
+        if !(0..=config.max_referral_bps).contains(&referral.fee_bps) {
+            log!(
+                &env,
+                "Pool: Swap: Trying to swap with more than the allowed referral fee"
+            );
+            panic_with_error!(&env, ContractError::ReferralFeeTooHigh);
+        }
+    }
 
     if let Some(max_spread) = max_spread {
         if !(0..=config.max_allowed_spread_bps).contains(&max_spread) {
@@ -770,6 +1445,8 @@ fn do_swap(
 
     let max_spread = Decimal::bps(max_spread.map_or_else(|| config.max_allowed_spread_bps, |x| x));
 
+    utils::update_price_accumulators(&env);
+
     let pool_balance_a = utils::get_pool_balance_a(&env);
     let pool_balance_b = utils::get_pool_balance_b(&env);
 
@@ -782,12 +1459,10 @@ fn do_swap(
         panic_with_error!(env, ContractError::AssetNotInPool);
     };
 
-    // FIXM: Disable Referral struct
-    // let referral_fee_bps = match referral {
-    //     Some(ref referral) => referral.clone().fee,
-    //     None => 0,
-    // };
-    let referral_fee_bps = 0;
+    let referral_fee_bps = match referral {
+        Some(ref referral) => referral.fee_bps,
+        None => 0,
+    };
 
     // 1. We calculate the referral_fee below. If none referral fee will be 0
     let compute_swap: ComputeSwap = compute_swap(
@@ -840,25 +1515,25 @@ fn do_swap(
         &compute_swap.return_amount,
     );
 
-    // send commission to fee recipient
+    // send the protocol share of the commission to fee recipient, the rest stays in the pool
+    let protocol_fee_amount = compute_swap.commission_amount * config.protocol_fee_share();
     token_contract::Client::new(&env, &buy_token).transfer(
         &env.current_contract_address(),
         &config.fee_recipient,
-        &compute_swap.commission_amount,
+        &protocol_fee_amount,
     );
 
-    // 2. If referral is present and return amount is larger than 0 we send referral fee commision
-    //    to fee recipient
-    // FIXM: Disable Referral struct
-    // if let Some(Referral { address, fee }) = referral {
-    //     if fee > 0 {
-    //         token_contract::Client::new(&env, &buy_token).transfer(
-    //             &env.current_contract_address(),
-    //             &address,
-    //             &compute_swap.referral_fee_amount,
-    //         );
-    //     }
-    // }
+    // 2. If referral is present and referral fee is larger than 0 we send referral fee commision
+    //    to the referral address
+    if let Some(Referral { address, .. }) = referral {
+        if compute_swap.referral_fee_amount > 0 {
+            token_contract::Client::new(&env, &buy_token).transfer(
+                &env.current_contract_address(),
+                &address,
+                &compute_swap.referral_fee_amount,
+            );
+        }
+    }
 
     // user is offering to sell A, so they will receive B
     // A balance is bigger, B balance is smaller
@@ -866,14 +1541,14 @@ fn do_swap(
         (
             pool_balance_a + offer_amount,
             pool_balance_b
-                - compute_swap.commission_amount
+                - protocol_fee_amount
                 - compute_swap.referral_fee_amount
                 - compute_swap.return_amount,
         )
     } else {
         (
             pool_balance_a
-                - compute_swap.commission_amount
+                - protocol_fee_amount
                 - compute_swap.referral_fee_amount
                 - compute_swap.return_amount,
             pool_balance_b + offer_amount,
@@ -894,21 +1569,37 @@ fn do_swap(
         ("swap", "referral_fee_amount"),
         compute_swap.referral_fee_amount,
     );
-    compute_swap.return_amount
+    env.events()
+        .publish(("swap", "protocol_fee_amount"), protocol_fee_amount);
+    env.events().publish(
+        ("swap", "lp_fee_amount"),
+        compute_swap.commission_amount - protocol_fee_amount,
+    );
+
+    SwapResponse {
+        ask_amount: compute_swap.return_amount,
+        commission_amount: compute_swap.commission_amount,
+        protocol_fee_amount,
+        referral_fee_amount: compute_swap.referral_fee_amount,
+        spread_amount: compute_swap.spread_amount,
+    }
 }
 
 /// This function divides the deposit in such a way that when swapping it for the other token,
-/// the resulting amounts of tokens maintain the current pool's ratio.
+/// the resulting amounts of tokens maintain the pool's ratio after the swap.
 /// * `a_pool` - The current amount of Token A in the liquidity pool.
 /// * `b_pool` - The current amount of Token B in the liquidity pool.
 /// * `deposit` - The total amount of tokens that the user wants to deposit into the liquidity pool.
-/// * `sell_a` - A boolean that indicates whether the deposit is in Token A (if true) or in Token B (if false).
+/// * `offer_asset` - The token in which the deposit is made.
 /// # Returns
-/// * A tuple `(final_offer_amount, final_ask_amount)`, where `final_offer_amount` is the amount of deposit tokens
-///   to be swapped, and `final_ask_amount` is the amount of the other tokens that will be received in return.
+/// * The amount of deposit tokens to be swapped for the other token.
 ///
-// TODO: https://github.com/Phoenix-Protocol-Group/phoenix-contracts/issues/204
-#[allow(dead_code)]
+/// Swapping `s` out of a deposit `d` into a pool holding `x` of the offered token leaves the
+/// remaining `d - s` in the same ratio as the pool when
+/// `(1 - f + f_lp) * s^2 + ((2 - f) * x - f_lp * d) * s - x * d = 0`, where `f` is the total fee
+/// rate and `f_lp` the part of it that stays in the pool (the protocol share of the commission
+/// leaves the pool). The positive root is computed in the form `2 * c / (b + sqrt(b^2 + 4 * a * c))`,
+/// which stays accurate for deposits much smaller than the pool.
 fn split_deposit_based_on_pool_ratio(
     env: &Env,
     config: &Config,
@@ -916,7 +1607,7 @@ fn split_deposit_based_on_pool_ratio(
     b_pool: i128,
     deposit: i128,
     offer_asset: &Address,
-) -> (i128, i128) {
+) -> i128 {
     // check if offer_asset is one of the two tokens in the pool
     if offer_asset != &config.token_a && offer_asset != &config.token_b {
         log!(&env, "Pool: Token offered to swap not found in Pool");
@@ -935,58 +1626,57 @@ fn split_deposit_based_on_pool_ratio(
         );
     }
 
-    // Calculate the current ratio in the pool
-    let target_ratio = Decimal::from_ratio(b_pool, a_pool);
-    // Define boundaries for binary search algorithm
-    let mut low = 0;
-    let mut high = deposit;
-
-    // Tolerance is the smallest difference in deposit that we care about
-    let tolerance = 500;
+    let offer_pool = if offer_asset == &config.token_a {
+        a_pool
+    } else {
+        b_pool
+    };
 
-    let mut final_offer_amount = deposit; // amount of deposit tokens to be swapped
-    let mut final_ask_amount = 0; // amount of other tokens to be received
+    let fee = config.protocol_fee_rate();
+    // the part of the commission that stays in the pool
+    let lp_fee = fee * (Decimal::one() - config.protocol_fee_share());
+    let two_minus_fee = Decimal::percent(200) - fee;
+    let quadratic = Decimal::one() - fee + lp_fee;
+
+    // the equation is solved for the ratio of the smaller of deposit and pool to the bigger one,
+    // so that none of the intermediate values can overflow
+    let offer_ratio = if deposit <= offer_pool {
+        // r = d / x, s = 2 * d / (b + sqrt(b^2 + 4 * a * r)) with b = (2 - f) - r * f_lp
+        let ratio = Decimal::from_ratio(deposit, offer_pool);
+        let linear = two_minus_fee - ratio * lp_fee;
+        let denominator =
+            linear + decimal_sqrt(linear * linear + Decimal::percent(400) * quadratic * ratio);
+        Decimal::percent(200) / denominator
+    } else {
+        // p = x / d, s / d = 2 * p / (b + sqrt(b^2 + 4 * a * p)) with b = p * (2 - f) - f_lp
+        let ratio = Decimal::from_ratio(offer_pool, deposit);
+        let linear = ratio * two_minus_fee - lp_fee;
+        let denominator =
+            linear + decimal_sqrt(linear * linear + Decimal::percent(400) * quadratic * ratio);
+        Decimal::percent(200) * ratio / denominator
+    };
 
-    while high - low > tolerance {
-        let mid = (low + high) / 2; // Calculate middle point
+    // never swap more than the whole deposit because of rounding
+    let offer_amount = (deposit * offer_ratio).min(deposit);
+    if offer_amount == 0 {
+        log!(
+            env,
+            "Pool: split_deposit_based_on_pool_ratio: Deposit is too small to be split!"
+        );
+        panic_with_error!(env, ContractError::SplitDepositTooSmall);
+    }
 
-        // Simulate swap to get amount of other tokens to be received for `mid` amount of deposit tokens
-        let SimulateSwapResponse {
-            ask_amount,
-            spread_amount: _,
-            commission_amount: _,
-            total_return: _,
-        } = LiquidityPool::simulate_swap(env.clone(), offer_asset.clone(), mid);
-
-        // Update final amounts
-        final_offer_amount = mid;
-        final_ask_amount = ask_amount;
-
-        // Calculate the ratio that would result from swapping `mid` deposit tokens
-        let ratio = if offer_asset == &config.token_a {
-            Decimal::from_ratio(ask_amount, deposit - mid)
-        } else {
-            Decimal::from_ratio(deposit - mid, ask_amount)
-        };
+    offer_amount
+}
 
-        // If the resulting ratio is approximately equal (1%) to the target ratio, break the loop
-        if is_approx_ratio(ratio, target_ratio, Decimal::percent(1)) {
-            break;
-        }
-        // Update boundaries for the next iteration of the binary search
-        if ratio > target_ratio {
-            if offer_asset == &config.token_a {
-                high = mid;
-            } else {
-                low = mid;
-            }
-        } else if offer_asset == &config.token_a {
-            low = mid;
-        } else {
-            high = mid;
-        };
+/// Square root of a non-negative decimal, accurate to the last decimal place when the value is
+/// small and to 9 decimal places otherwise.
+fn decimal_sqrt(value: Decimal) -> Decimal {
+    let atomics = value.atomics();
+    match atomics.checked_mul(Decimal::one().atomics()) {
+        Some(scaled) => Decimal::new(scaled.sqrt()),
+        None => Decimal::new(atomics.sqrt() * 1_000_000_000),
     }
-    (final_offer_amount, final_ask_amount)
 }
 
 /// This function asserts that the slippage does not exceed the provided tolerance.
@@ -1306,10 +1996,179 @@ mod tests {
             max_allowed_spread_bps: 100i64,
             max_referral_bps: 1_000i64,
             default_slippage_bps: 100i64,
+            protocol_fee_share_bps: 10_000,
+            flash_loan_fee_bps: 0i64,
         };
         split_deposit_based_on_pool_ratio(&env, config, 100, 100, 100, &Address::generate(&env));
     }
 
+    fn split_config(env: &Env, total_fee_bps: i64) -> Config {
+        Config {
+            token_a: Address::generate(env),
+            token_b: Address::generate(env),
+            share_token: Address::generate(env),
+            stake_contract: Address::generate(env),
+            pool_type: PairType::Xyk,
+            total_fee_bps,
+            fee_recipient: Address::generate(env),
+            max_allowed_slippage_bps: 100i64,
+            max_allowed_spread_bps: 100i64,
+            max_referral_bps: 1_000i64,
+            default_slippage_bps: 100i64,
+            protocol_fee_share_bps: 10_000,
+            flash_loan_fee_bps: total_fee_bps,
+        }
+    }
+
+    #[test]
+    fn split_deposit_keeps_pool_ratio_without_fees() {
+        let env = Env::default();
+        let config = split_config(&env, 0);
+
+        let offer = split_deposit_based_on_pool_ratio(
+            &env,
+            &config,
+            10_000_000,
+            10_000_000,
+            100_000,
+            &config.token_a,
+        );
+        // exact root is 49_875.62...
+        assert_eq!(offer, 49_875);
+
+        let swap = compute_swap(10_000_000, 10_000_000, offer, Decimal::zero(), 0);
+        // what is left from the deposit is in the same ratio as the pool after the swap
+        let left = Decimal::from_ratio(100_000 - offer, swap.return_amount);
+        let pool = Decimal::from_ratio(10_000_000 + offer, 10_000_000 - swap.return_amount);
+        assert!(left.abs_diff(pool) < Decimal::bps(1));
+    }
+
+    #[test]
+    fn split_deposit_swaps_more_with_higher_fees() {
+        let env = Env::default();
+        let no_fee = split_config(&env, 0);
+        let with_fee = split_config(&env, 1_000);
+
+        let offer_no_fee = split_deposit_based_on_pool_ratio(
+            &env,
+            &no_fee,
+            3_000_000,
+            1_000_000,
+            90_000,
+            &no_fee.token_b,
+        );
+        let offer_with_fee = split_deposit_based_on_pool_ratio(
+            &env,
+            &with_fee,
+            3_000_000,
+            1_000_000,
+            90_000,
+            &with_fee.token_b,
+        );
+        assert!(offer_with_fee > offer_no_fee);
+
+        let swap = compute_swap(
+            1_000_000,
+            3_000_000,
+            offer_with_fee,
+            Decimal::percent(10),
+            0,
+        );
+        let left = Decimal::from_ratio(swap.return_amount, 90_000 - offer_with_fee);
+        let pool = Decimal::from_ratio(
+            3_000_000 - swap.return_amount - swap.commission_amount,
+            1_000_000 + offer_with_fee,
+        );
+        assert!(left.abs_diff(pool) < Decimal::bps(1) * pool);
+    }
+
+    #[test]
+    fn split_deposit_accounts_for_the_commission_kept_in_the_pool() {
+        let env = Env::default();
+        let mut config = split_config(&env, 1_000);
+        config.protocol_fee_share_bps = 0;
+
+        let offer = split_deposit_based_on_pool_ratio(
+            &env,
+            &config,
+            3_000_000,
+            1_000_000,
+            90_000,
+            &config.token_b,
+        );
+
+        let swap = compute_swap(1_000_000, 3_000_000, offer, Decimal::percent(10), 0);
+        // the whole commission stays in the pool
+        let left = Decimal::from_ratio(swap.return_amount, 90_000 - offer);
+        let pool = Decimal::from_ratio(3_000_000 - swap.return_amount, 1_000_000 + offer);
+        assert!(left.abs_diff(pool) < Decimal::bps(1) * pool);
+
+        // same for a deposit bigger than the pool
+        let offer = split_deposit_based_on_pool_ratio(
+            &env,
+            &config,
+            3_000_000,
+            1_000_000,
+            5_000_000,
+            &config.token_b,
+        );
+
+        let swap = compute_swap(1_000_000, 3_000_000, offer, Decimal::percent(10), 0);
+        let left = Decimal::from_ratio(swap.return_amount, 5_000_000 - offer);
+        let pool = Decimal::from_ratio(3_000_000 - swap.return_amount, 1_000_000 + offer);
+        assert!(left.abs_diff(pool) < Decimal::bps(1) * pool);
+    }
+
+    #[test]
+    fn split_deposit_with_full_commission_swaps_everything() {
+        let env = Env::default();
+        let config = split_config(&env, 10_000);
+
+        let offer =
+            split_deposit_based_on_pool_ratio(&env, &config, 1_000, 1_000, 500, &config.token_a);
+        assert_eq!(offer, 500);
+    }
+
+    #[test]
+    fn split_deposit_into_tiny_pool() {
+        let env = Env::default();
+        let config = split_config(&env, 0);
+
+        // deposit is many orders of magnitude bigger than the pool
+        let offer = split_deposit_based_on_pool_ratio(
+            &env,
+            &config,
+            1,
+            1,
+            1_000_000_000_000,
+            &config.token_a,
+        );
+        // root of s^2 + 2s - 10^12 = 0
+        assert_eq!(offer, 999_999);
+    }
+
+    #[should_panic(
+        expected = "Pool: split_deposit_based_on_pool_ratio: Deposit is too small to be split!"
+    )]
+    #[test]
+    fn should_panic_when_deposit_is_too_small_to_split() {
+        let env = Env::default();
+        let config = split_config(&env, 0);
+
+        split_deposit_based_on_pool_ratio(&env, &config, 1_000_000, 1_000_000, 1, &config.token_a);
+    }
+
+    #[should_panic(
+        expected = "Pool: split_deposit_based_on_pool_ratio: Both pools and deposit must be a positive!"
+    )]
+    #[test]
+    fn should_panic_when_splitting_into_empty_pool() {
+        let env = Env::default();
+        let config = split_config(&env, 0);
+
+        split_deposit_based_on_pool_ratio(&env, &config, 0, 0, 1_000, &config.token_a);
+    }
+
     #[test]
     fn assert_slippage_tolerance_with_none_as_tolerance() {
         let env = Env::default();
@@ -30,4 +30,13 @@ pub enum ContractError {
 
     SwapMinReceivedBiggerThanReturn = 21,
     TransactionAfterTimestampDeadline = 22,
+    SplitDepositTooSmall = 23,
+    ReferralFeeTooHigh = 24,
+    TwapWindowNotCovered = 25,
+    WithdrawSingleEmptiesPool = 26,
+    FlashLoanExceedsReserves = 27,
+    FlashLoanNotRepaid = 28,
+    Unauthorized = 29,
+    ContractPaused = 30,
+    LowLiquidity = 31,
 }
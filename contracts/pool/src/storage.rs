@@ -1,9 +1,10 @@
 use soroban_sdk::{
     contracttype, log, panic_with_error, symbol_short, xdr::ToXdr, Address, Bytes, BytesN,
-    ConversionError, Env, Symbol, TryFromVal, Val,
+    ConversionError, Env, Symbol, TryFromVal, Val, Vec, U256,
 };
 
 use crate::{error::ContractError, token_contract};
+use phoenix::oracle::{record_observation, saturating_price, Observation};
 use soroban_decimal::Decimal;
 
 #[derive(Clone, Copy)]
@@ -14,6 +15,13 @@ pub enum DataKey {
     ReserveB = 2,
     Admin = 3,
     Initialized = 4,
+    PriceAccumulator = 5,
+    Observations = 6,
+    Paused = 7,
+    Guardian = 8,
+    Factory = 9,
+    ProtocolFeeShareBps = 10,
+    FlashLoanFeeBps = 11,
 }
 
 impl TryFromVal<Env, DataKey> for Val {
@@ -51,6 +59,12 @@ pub struct Config {
     pub max_referral_bps: i64,
     /// Default value that will be used whenever the user hasn't specified their preferred slippage
     pub default_slippage_bps: i64,
+    /// The share (in bps) of the swap commission that is sent to the fee recipient.
+    /// The rest of the commission stays in the pool and accrues to the liquidity providers
+    pub protocol_fee_share_bps: i64,
+    /// The fee (in bps) charged on the borrowed amount of a flash loan.
+    /// The whole fee stays in the pool and accrues to the liquidity providers
+    pub flash_loan_fee_bps: i64,
 }
 const CONFIG: Symbol = symbol_short!("CONFIG");
 
@@ -59,17 +73,86 @@ impl Config {
         Decimal::bps(self.total_fee_bps)
     }
 
+    pub fn protocol_fee_share(&self) -> Decimal {
+        Decimal::bps(self.protocol_fee_share_bps)
+    }
+
+    pub fn flash_loan_fee(&self) -> Decimal {
+        Decimal::bps(self.flash_loan_fee_bps)
+    }
+
     pub fn max_allowed_slippage(&self) -> Decimal {
         Decimal::bps(self.max_allowed_slippage_bps)
     }
 }
 
+/// The part of `Config` stored under `CONFIG`, in the layout of the pools deployed before
+/// `Config` was extended. Parameters added later are stored under their own keys, so pools
+/// upgraded to this version can still read their configuration.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StoredConfig {
+    pub token_a: Address,
+    pub token_b: Address,
+    pub share_token: Address,
+    pub stake_contract: Address,
+    pub pool_type: PairType,
+    pub total_fee_bps: i64,
+    pub fee_recipient: Address,
+    pub max_allowed_slippage_bps: i64,
+    pub max_allowed_spread_bps: i64,
+    pub max_referral_bps: i64,
+    pub default_slippage_bps: i64,
+}
+
 pub fn get_config(env: &Env) -> Config {
-    env.storage().persistent().get(&CONFIG).unwrap()
+    let storage = env.storage().persistent();
+    let stored: StoredConfig = storage.get(&CONFIG).unwrap();
+
+    Config {
+        // pools upgraded from before these parameters existed keep sending the whole
+        // commission to the fee recipient, and charge the swap fee on flash loans
+        protocol_fee_share_bps: storage.get(&DataKey::ProtocolFeeShareBps).unwrap_or(10_000),
+        flash_loan_fee_bps: storage
+            .get(&DataKey::FlashLoanFeeBps)
+            .unwrap_or(stored.total_fee_bps),
+        token_a: stored.token_a,
+        token_b: stored.token_b,
+        share_token: stored.share_token,
+        stake_contract: stored.stake_contract,
+        pool_type: stored.pool_type,
+        total_fee_bps: stored.total_fee_bps,
+        fee_recipient: stored.fee_recipient,
+        max_allowed_slippage_bps: stored.max_allowed_slippage_bps,
+        max_allowed_spread_bps: stored.max_allowed_spread_bps,
+        max_referral_bps: stored.max_referral_bps,
+        default_slippage_bps: stored.default_slippage_bps,
+    }
 }
 
 pub fn save_config(env: &Env, config: Config) {
-    env.storage().persistent().set(&CONFIG, &config);
+    let storage = env.storage().persistent();
+    storage.set(
+        &DataKey::ProtocolFeeShareBps,
+        &config.protocol_fee_share_bps,
+    );
+    storage.set(&DataKey::FlashLoanFeeBps, &config.flash_loan_fee_bps);
+    storage.set(
+        &CONFIG,
+        &StoredConfig {
+            token_a: config.token_a,
+            token_b: config.token_b,
+            share_token: config.share_token,
+            stake_contract: config.stake_contract,
+            pool_type: config.pool_type,
+            total_fee_bps: config.total_fee_bps,
+            fee_recipient: config.fee_recipient,
+            max_allowed_slippage_bps: config.max_allowed_slippage_bps,
+            max_allowed_spread_bps: config.max_allowed_spread_bps,
+            max_referral_bps: config.max_referral_bps,
+            default_slippage_bps: config.default_slippage_bps,
+        },
+    );
 }
 
 #[contracttype]
@@ -102,7 +185,7 @@ pub struct Referral {
     /// Address of the referral
     pub address: Address,
     /// fee in bps, later parsed to percentage
-    pub fee: i64,
+    pub fee_bps: i64,
 }
 
 /// This struct is used to return a query result with the total amount of LP tokens and assets in a specific pool.
@@ -127,11 +210,26 @@ pub struct LiquidityPoolInfo {
     pub total_fee_bps: i64,
 }
 
+/// The amounts of an executed swap, as returned by `swap`.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SwapResponse {
+    /// The amount sent to the sender, after commission and referral fee
+    pub ask_amount: i128,
+    pub commission_amount: i128,
+    /// The part of the commission sent to the fee recipient, the rest stays in the pool
+    pub protocol_fee_amount: i128,
+    pub referral_fee_amount: i128,
+    pub spread_amount: i128,
+}
+
 #[contracttype]
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct SimulateSwapResponse {
     pub ask_amount: i128,
     pub commission_amount: i128,
+    /// The part of the commission sent to the fee recipient, the rest stays in the pool
+    pub protocol_fee_amount: i128,
     pub spread_amount: i128,
     pub total_return: i128,
 }
@@ -144,6 +242,30 @@ pub struct SimulateReverseSwapResponse {
     pub spread_amount: i128,
 }
 
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SimulateWithdrawSingleResponse {
+    /// The total amount of the ask token paid out, after the commission of the internal swap
+    pub ask_amount: i128,
+    /// The commission charged on the internal swap of the other token
+    pub commission_amount: i128,
+    /// The part of the commission sent to the fee recipient, the rest stays in the pool
+    pub protocol_fee_amount: i128,
+    /// The spread of the internal swap of the other token
+    pub spread_amount: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SimulateProvideLiquidityResponse {
+    /// The amount of token A taken from the depositor
+    pub amount_a: i128,
+    /// The amount of token B taken from the depositor
+    pub amount_b: i128,
+    /// The pool shares minted to the depositor
+    pub shares: i128,
+}
+
 pub mod utils {
     use super::*;
 
@@ -175,6 +297,18 @@ pub mod utils {
         e.storage().persistent().set(&DataKey::Admin, &address)
     }
 
+    pub fn save_factory(e: &Env, address: Address) {
+        e.storage().persistent().set(&DataKey::Factory, &address)
+    }
+
+    pub fn save_guardian(e: &Env, address: Address) {
+        e.storage().persistent().set(&DataKey::Guardian, &address)
+    }
+
+    pub fn set_paused(e: &Env, paused: bool) {
+        e.storage().persistent().set(&DataKey::Paused, &paused)
+    }
+
     pub fn save_total_shares(e: &Env, amount: i128) {
         e.storage().persistent().set(&DataKey::TotalShares, &amount)
     }
@@ -208,6 +342,22 @@ pub mod utils {
         e.storage().persistent().get(&DataKey::Admin).unwrap()
     }
 
+    /// `None` for pools upgraded from a version that didn't store the factory
+    pub fn get_factory(e: &Env) -> Option<Address> {
+        e.storage().persistent().get(&DataKey::Factory)
+    }
+
+    pub fn get_guardian(e: &Env) -> Option<Address> {
+        e.storage().persistent().get(&DataKey::Guardian)
+    }
+
+    pub fn is_paused(e: &Env) -> bool {
+        e.storage()
+            .persistent()
+            .get(&DataKey::Paused)
+            .unwrap_or(false)
+    }
+
     pub fn get_total_shares(e: &Env) -> i128 {
         e.storage().persistent().get(&DataKey::TotalShares).unwrap()
     }
@@ -219,6 +369,55 @@ pub mod utils {
         e.storage().persistent().get(&DataKey::ReserveB).unwrap()
     }
 
+    pub fn get_price_accumulator(e: &Env) -> Observation {
+        e.storage()
+            .persistent()
+            .get(&DataKey::PriceAccumulator)
+            .unwrap_or_else(|| Observation::new(e.ledger().timestamp()))
+    }
+
+    pub fn get_observations(e: &Env) -> Vec<Observation> {
+        e.storage()
+            .persistent()
+            .get(&DataKey::Observations)
+            .unwrap_or(Vec::new(e))
+    }
+
+    /// Price of token A in token B and of token B in token A, `None` while the pool is empty.
+    pub fn get_spot_prices(e: &Env) -> Option<(Decimal, Decimal)> {
+        let pool_balance_a = get_pool_balance_a(e);
+        let pool_balance_b = get_pool_balance_b(e);
+        if pool_balance_a == 0 || pool_balance_b == 0 {
+            return None;
+        }
+
+        // saturates instead of panicking for huge reserves, every operation of the pool
+        // updates the accumulators first
+        let balance_a = U256::from_u128(e, pool_balance_a as u128);
+        let balance_b = U256::from_u128(e, pool_balance_b as u128);
+        Some((
+            saturating_price(e, &balance_b, &balance_a),
+            saturating_price(e, &balance_a, &balance_b),
+        ))
+    }
+
+    /// Accumulates the current price up to now. Has to be called before the reserves change,
+    /// so a trade only moves the TWAP once time has passed.
+    pub fn update_price_accumulators(e: &Env) {
+        let accumulator =
+            get_price_accumulator(e).accumulate(e.ledger().timestamp(), get_spot_prices(e));
+        e.storage()
+            .persistent()
+            .set(&DataKey::PriceAccumulator, &accumulator);
+
+        let mut observations = get_observations(e);
+        if record_observation(&mut observations, &accumulator) {
+            e.storage()
+                .persistent()
+                .set(&DataKey::Observations, &observations);
+        }
+    }
+
     pub fn get_balance(e: &Env, contract: &Address) -> i128 {
         token_contract::Client::new(e, contract).balance(&e.current_contract_address())
     }
@@ -608,6 +807,8 @@ mod tests {
             max_allowed_spread_bps: 10_i64,
             max_referral_bps: 10i64,
             default_slippage_bps: 100i64,
+            protocol_fee_share_bps: 10_000,
+            flash_loan_fee_bps: 10i64,
         };
 
         let result = config.max_allowed_slippage();
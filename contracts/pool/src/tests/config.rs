@@ -8,7 +8,7 @@ use super::setup::{
 };
 use crate::{
     contract::{LiquidityPool, LiquidityPoolClient},
-    storage::{Asset, Config, PairType, PoolResponse},
+    storage::{Asset, Config, DataKey, PairType, PoolResponse},
 };
 
 #[should_panic(
@@ -114,6 +114,8 @@ fn update_config() {
             max_allowed_spread_bps: 200,
             max_referral_bps: 5_000,
             default_slippage_bps: 100i64,
+            protocol_fee_share_bps: 10_000,
+            flash_loan_fee_bps: 0,
         }
     );
 
@@ -125,6 +127,8 @@ fn update_config() {
         &None,
         &None,
         &Some(1_000i64),
+        &None,
+        &None,
     );
     assert_eq!(
         pool.query_config(),
@@ -140,11 +144,22 @@ fn update_config() {
             max_allowed_spread_bps: 200,
             max_referral_bps: 1_000,
             default_slippage_bps: 100i64,
+            protocol_fee_share_bps: 10_000,
+            flash_loan_fee_bps: 0,
         }
     );
 
     // update slippage and spread
-    pool.update_config(&None, &None, &None, &None, &Some(5_000i64), &Some(500));
+    pool.update_config(
+        &None,
+        &None,
+        &None,
+        &None,
+        &Some(5_000i64),
+        &Some(500),
+        &None,
+        &None,
+    );
     assert_eq!(
         pool.query_config(),
         Config {
@@ -159,8 +174,27 @@ fn update_config() {
             max_allowed_spread_bps: 5_000,
             max_referral_bps: 500,
             default_slippage_bps: 100i64,
+            protocol_fee_share_bps: 10_000,
+            flash_loan_fee_bps: 0,
         }
     );
+
+    // keep a part of the commission in the pool
+    pool.update_config(
+        &None,
+        &None,
+        &None,
+        &None,
+        &None,
+        &None,
+        &Some(2_500i64),
+        &None,
+    );
+    assert_eq!(pool.query_config().protocol_fee_share_bps, 2_500);
+
+    // charge flash loans a fee of their own
+    pool.update_config(&None, &None, &None, &None, &None, &None, &None, &Some(9i64));
+    assert_eq!(pool.query_config().flash_loan_fee_bps, 9);
 }
 
 #[test]
@@ -201,6 +235,8 @@ fn update_config_unauthorized() {
         &None,
         &None,
         &None,
+        &None,
+        &None,
     );
 }
 
@@ -237,13 +273,31 @@ fn update_config_update_admin() {
     );
 
     // update admin to new admin
-    pool.update_config(&Some(admin2.clone()), &None, &None, &None, &None, &None);
+    pool.update_config(
+        &Some(admin2.clone()),
+        &None,
+        &None,
+        &None,
+        &None,
+        &None,
+        &None,
+        &None,
+    );
 
     let share_token_address = pool.query_share_token_address();
     let stake_token_address = pool.query_stake_contract_address();
 
     // now update succeeds
-    pool.update_config(&Some(admin2.clone()), &None, &None, &None, &None, &None);
+    pool.update_config(
+        &Some(admin2.clone()),
+        &None,
+        &None,
+        &None,
+        &None,
+        &None,
+        &None,
+        &None,
+    );
     assert_eq!(
         pool.query_config(),
         Config {
@@ -258,6 +312,8 @@ fn update_config_update_admin() {
             max_allowed_spread_bps: 200,
             max_referral_bps: 5_000,
             default_slippage_bps: 100i64,
+            protocol_fee_share_bps: 10_000,
+            flash_loan_fee_bps: 0,
         }
     );
 }
@@ -302,6 +358,44 @@ fn update_config_too_high_fees() {
         &None,
         &None,
         &None,
+        &None,
+        &None,
+    );
+}
+
+#[test]
+#[should_panic(expected = "Pool: UpdateConfig: Invalid protocol_fee_share_bps")]
+fn update_config_too_high_protocol_fee_share() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let mut token1 = deploy_token_contract(&env, &Address::generate(&env));
+    let mut token2 = deploy_token_contract(&env, &Address::generate(&env));
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+
+    let pool = deploy_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        500,
+        200,
+        Address::generate(&env),
+        Address::generate(&env),
+    );
+
+    pool.update_config(
+        &None,
+        &None,
+        &None,
+        &None,
+        &None,
+        &None,
+        &Some(10_001i64),
+        &None,
     );
 }
 
@@ -363,3 +457,329 @@ fn update_liquidity_pool_works() {
     );
     assert_eq!(result.total_fee_bps, 0);
 }
+
+#[test]
+fn pause_blocks_swaps_but_not_withdrawals() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let guardian = Address::generate(&env);
+    let user = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let pool = deploy_liquidity_pool_contract(
+        &env,
+        Some(admin.clone()),
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+    );
+
+    token1.mint(&user, &10_000);
+    token2.mint(&user, &10_000);
+    pool.provide_liquidity(
+        &user,
+        &Some(10_000),
+        &None,
+        &Some(10_000),
+        &None,
+        &None,
+        &None::<u64>,
+    );
+
+    assert!(!pool.query_paused());
+    pool.set_guardian(&guardian);
+    assert_eq!(pool.query_guardian(), Some(guardian.clone()));
+
+    pool.pause(&guardian);
+    assert!(pool.query_paused());
+
+    // liquidity providers can always leave
+    pool.withdraw_liquidity(&user, &5_000, &1, &1, &None::<u64>);
+    assert_eq!(token1.balance(&user), 5_000);
+    assert_eq!(token2.balance(&user), 5_000);
+
+    pool.unpause(&admin);
+    assert!(!pool.query_paused());
+    pool.swap(
+        &user,
+        &None,
+        &token1.address,
+        &10,
+        &None,
+        &Some(1_000),
+        &None::<u64>,
+    );
+    assert_eq!(token1.balance(&user), 4_990);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #30)")]
+fn swap_when_paused_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let factory = Address::generate(&env);
+    let user = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let pool = deploy_liquidity_pool_contract(
+        &env,
+        Some(admin.clone()),
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        factory.clone(),
+    );
+
+    token1.mint(&user, &10_010);
+    token2.mint(&user, &10_000);
+    pool.provide_liquidity(
+        &user,
+        &Some(10_000),
+        &None,
+        &Some(10_000),
+        &None,
+        &None,
+        &None::<u64>,
+    );
+
+    // the factory that deployed the pool may pause it as well
+    pool.pause(&factory);
+
+    pool.swap(
+        &user,
+        &None,
+        &token1.address,
+        &10,
+        &None,
+        &Some(1_000),
+        &None::<u64>,
+    );
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #30)")]
+fn provide_liquidity_when_paused_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let pool = deploy_liquidity_pool_contract(
+        &env,
+        Some(admin.clone()),
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+    );
+
+    pool.pause(&admin);
+
+    token1.mint(&user, &1_000);
+    token2.mint(&user, &1_000);
+    pool.provide_liquidity(
+        &user,
+        &Some(1_000),
+        &None,
+        &Some(1_000),
+        &None,
+        &None,
+        &None::<u64>,
+    );
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #29)")]
+fn pause_by_stranger_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let pool = deploy_liquidity_pool_contract(
+        &env,
+        Some(admin.clone()),
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+    );
+
+    pool.pause(&Address::generate(&env));
+}
+
+#[test]
+fn pause_and_unpause_without_stored_factory() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let factory = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let pool = deploy_liquidity_pool_contract(
+        &env,
+        Some(admin.clone()),
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        factory.clone(),
+    );
+
+    // pools upgraded from a version without the factory key don't know their factory
+    env.as_contract(&pool.address, || {
+        env.storage().persistent().remove(&DataKey::Factory)
+    });
+
+    pool.pause(&admin);
+    assert!(pool.query_paused());
+    pool.unpause(&admin);
+    assert!(!pool.query_paused());
+
+    assert!(pool.try_pause(&factory).is_err());
+}
+
+#[test]
+fn factory_can_unpause() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let factory = Address::generate(&env);
+    let guardian = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let pool = deploy_liquidity_pool_contract(
+        &env,
+        Some(admin.clone()),
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        factory.clone(),
+    );
+    pool.set_guardian(&guardian);
+
+    pool.pause(&guardian);
+    // the guardian may only pause
+    assert!(pool.try_unpause(&guardian).is_err());
+
+    pool.unpause(&factory);
+    assert!(!pool.query_paused());
+}
+
+#[test]
+fn config_of_upgraded_pool_falls_back_to_defaults() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let pool = deploy_liquidity_pool_contract(
+        &env,
+        Some(admin.clone()),
+        (&token1.address, &token2.address),
+        30i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+    );
+
+    // pools deployed before these parameters existed only have the baseline config stored
+    env.as_contract(&pool.address, || {
+        let storage = env.storage().persistent();
+        storage.remove(&DataKey::ProtocolFeeShareBps);
+        storage.remove(&DataKey::FlashLoanFeeBps);
+    });
+
+    let config = pool.query_config();
+    assert_eq!(config.total_fee_bps, 30);
+    assert_eq!(config.protocol_fee_share_bps, 10_000);
+    assert_eq!(config.flash_loan_fee_bps, 30);
+
+    token1.mint(&user, &2_000_000);
+    token2.mint(&user, &1_000_000);
+    pool.provide_liquidity(
+        &user,
+        &Some(1_000_000),
+        &None,
+        &Some(1_000_000),
+        &None,
+        &None::<i64>,
+        &None::<u64>,
+    );
+    pool.swap(
+        &user,
+        &None,
+        &token1.address,
+        &1_000,
+        &None,
+        &Some(1_000),
+        &None::<u64>,
+    );
+    assert_eq!(token1.balance(&user), 999_000);
+}
@@ -0,0 +1,256 @@
+extern crate std;
+
+use soroban_sdk::{
+    contract, contractimpl, symbol_short, testutils::Address as _, Address, Bytes, Env, Symbol,
+};
+
+use super::setup::{deploy_liquidity_pool_contract, deploy_token_contract};
+use crate::token_contract;
+
+const POOL: Symbol = symbol_short!("POOL");
+const REPAY: Symbol = symbol_short!("REPAY");
+
+/// Borrows from the pool and either pays the loan back with the fee or keeps it.
+#[contract]
+pub struct MockFlashLoanReceiver;
+
+#[contractimpl]
+impl MockFlashLoanReceiver {
+    pub fn initialize(env: Env, pool: Address, repay: bool) {
+        env.storage().instance().set(&POOL, &pool);
+        env.storage().instance().set(&REPAY, &repay);
+    }
+
+    pub fn on_flash_loan(env: Env, token: Address, amount: i128, fee: i128, _data: Bytes) {
+        let repay: bool = env.storage().instance().get(&REPAY).unwrap();
+        if !repay {
+            return;
+        }
+
+        // pays back as much of the loan and the fee as it holds
+        let pool: Address = env.storage().instance().get(&POOL).unwrap();
+        let token_client = token_contract::Client::new(&env, &token);
+        let balance = token_client.balance(&env.current_contract_address());
+        token_client.transfer(
+            &env.current_contract_address(),
+            &pool,
+            &(amount + fee).min(balance),
+        );
+    }
+}
+
+fn deploy_receiver<'a>(env: &Env, pool: &Address, repay: bool) -> MockFlashLoanReceiverClient<'a> {
+    let receiver =
+        MockFlashLoanReceiverClient::new(env, &env.register_contract(None, MockFlashLoanReceiver));
+    receiver.initialize(pool, &repay);
+    receiver
+}
+
+#[test]
+fn flash_loan_is_repaid_with_fee() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let swap_fees = 30i64;
+    let pool = deploy_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        swap_fees,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+    );
+
+    token1.mint(&user, &1_000_000);
+    token2.mint(&user, &1_000_000);
+    pool.provide_liquidity(
+        &user,
+        &Some(1_000_000),
+        &None,
+        &Some(1_000_000),
+        &None,
+        &None,
+        &None::<u64>,
+    );
+    let shares = pool.query_total_issued_lp();
+
+    let receiver = deploy_receiver(&env, &pool.address, true);
+    // the receiver has just enough on its own to pay the fee
+    token1.mint(&receiver.address, &300);
+
+    // flash loans pay the swap fee by default
+    let fee = pool.flash_loan(
+        &receiver.address,
+        &token1.address,
+        &100_000,
+        &Bytes::new(&env),
+    );
+
+    assert_eq!(fee, 300);
+    assert_eq!(token1.balance(&receiver.address), 0);
+    assert_eq!(token1.balance(&pool.address), 1_000_300);
+
+    // the fee accrues to the liquidity providers
+    let pool_info = pool.query_pool_info();
+    assert_eq!(pool_info.asset_a.amount, 1_000_300);
+    assert_eq!(pool_info.asset_b.amount, 1_000_000);
+    assert_eq!(pool_info.asset_lp_share.amount, shares);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #28)")]
+fn flash_loan_not_repaid_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let pool = deploy_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        30i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+    );
+
+    token1.mint(&user, &1_000_000);
+    token2.mint(&user, &1_000_000);
+    pool.provide_liquidity(
+        &user,
+        &Some(1_000_000),
+        &None,
+        &Some(1_000_000),
+        &None,
+        &None,
+        &None::<u64>,
+    );
+
+    let receiver = deploy_receiver(&env, &pool.address, false);
+
+    pool.flash_loan(
+        &receiver.address,
+        &token2.address,
+        &100_000,
+        &Bytes::new(&env),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #28)")]
+fn flash_loan_repaid_without_fee_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let pool = deploy_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        30i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+    );
+
+    token1.mint(&user, &1_000_000);
+    token2.mint(&user, &1_000_000);
+    pool.provide_liquidity(
+        &user,
+        &Some(1_000_000),
+        &None,
+        &Some(1_000_000),
+        &None,
+        &None,
+        &None::<u64>,
+    );
+
+    // the receiver can repay the principal, but not the fee
+    let receiver = deploy_receiver(&env, &pool.address, true);
+    pool.flash_loan(
+        &receiver.address,
+        &token1.address,
+        &100_000,
+        &Bytes::new(&env),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #27)")]
+fn flash_loan_above_reserves_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let pool = deploy_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        30i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+    );
+
+    token1.mint(&user, &1_000_000);
+    token2.mint(&user, &1_000_000);
+    pool.provide_liquidity(
+        &user,
+        &Some(1_000_000),
+        &None,
+        &Some(1_000_000),
+        &None,
+        &None,
+        &None::<u64>,
+    );
+
+    let receiver = deploy_receiver(&env, &pool.address, true);
+    pool.flash_loan(
+        &receiver.address,
+        &token1.address,
+        &1_000_001,
+        &Bytes::new(&env),
+    );
+}
@@ -10,7 +10,9 @@ use soroban_sdk::{
 
 use super::setup::{deploy_liquidity_pool_contract, deploy_token_contract};
 use crate::{
-    storage::{Asset, PoolResponse},
+    storage::{
+        Asset, PoolResponse, SimulateProvideLiquidityResponse, SimulateWithdrawSingleResponse,
+    },
     token_contract,
 };
 
@@ -49,18 +51,18 @@ fn provide_liqudity() {
     let share_token_address = pool.query_share_token_address();
     let token_share = token_contract::Client::new(&env, &share_token_address);
 
-    token1.mint(&user1, &1000);
-    assert_eq!(token1.balance(&user1), 1000);
+    token1.mint(&user1, &100_000);
+    assert_eq!(token1.balance(&user1), 100_000);
 
-    token2.mint(&user1, &1000);
-    assert_eq!(token2.balance(&user1), 1000);
+    token2.mint(&user1, &100_000);
+    assert_eq!(token2.balance(&user1), 100_000);
 
     pool.provide_liquidity(
         &user1,
-        &Some(100),
-        &Some(100),
-        &Some(100),
-        &Some(100),
+        &Some(10_000),
+        &Some(10_000),
+        &Some(10_000),
+        &Some(10_000),
         &None,
         &None::<u64>,
     );
@@ -75,10 +77,10 @@ fn provide_liqudity() {
                     Symbol::new(&env, "provide_liquidity"),
                     (
                         &user1,
-                        Some(100i128),
-                        Some(100i128),
-                        Some(100i128),
-                        Some(100i128),
+                        Some(10_000i128),
+                        Some(10_000i128),
+                        Some(10_000i128),
+                        Some(10_000i128),
                         None::<i64>,
                         None::<u64>
                     )
@@ -89,7 +91,7 @@ fn provide_liqudity() {
                         function: AuthorizedFunction::Contract((
                             token1.address.clone(),
                             symbol_short!("transfer"),
-                            (&user1, &pool.address, 100_i128).into_val(&env)
+                            (&user1, &pool.address, 10_000_i128).into_val(&env)
                         )),
                         sub_invocations: std::vec![],
                     },
@@ -97,7 +99,7 @@ fn provide_liqudity() {
                         function: AuthorizedFunction::Contract((
                             token2.address.clone(),
                             symbol_short!("transfer"),
-                            (&user1, &pool.address, 100_i128).into_val(&env)
+                            (&user1, &pool.address, 10_000_i128).into_val(&env)
                         )),
                         sub_invocations: std::vec![],
                     },
@@ -106,12 +108,12 @@ fn provide_liqudity() {
         ),]
     );
 
-    assert_eq!(token_share.balance(&user1), 100);
-    assert_eq!(token_share.balance(&pool.address), 0);
-    assert_eq!(token1.balance(&user1), 900);
-    assert_eq!(token1.balance(&pool.address), 100);
-    assert_eq!(token2.balance(&user1), 900);
-    assert_eq!(token2.balance(&pool.address), 100);
+    assert_eq!(token_share.balance(&user1), 9_000);
+    assert_eq!(token_share.balance(&pool.address), 1_000); // locked on the first deposit
+    assert_eq!(token1.balance(&user1), 90_000);
+    assert_eq!(token1.balance(&pool.address), 10_000);
+    assert_eq!(token2.balance(&user1), 90_000);
+    assert_eq!(token2.balance(&pool.address), 10_000);
 
     let result = pool.query_pool_info();
     assert_eq!(
@@ -119,20 +121,20 @@ fn provide_liqudity() {
         PoolResponse {
             asset_a: Asset {
                 address: token1.address,
-                amount: 100i128
+                amount: 10_000i128
             },
             asset_b: Asset {
                 address: token2.address,
-                amount: 100i128
+                amount: 10_000i128
             },
             asset_lp_share: Asset {
                 address: share_token_address,
-                amount: 100i128
+                amount: 10_000i128
             },
             stake_address: result.clone().stake_address,
         }
     );
-    assert_eq!(pool.query_total_issued_lp(), 100);
+    assert_eq!(pool.query_total_issued_lp(), 10_000);
 }
 
 #[test]
@@ -170,26 +172,26 @@ fn withdraw_liquidity() {
     let share_token_address = pool.query_share_token_address();
     let token_share = token_contract::Client::new(&env, &share_token_address);
 
-    token1.mint(&user1, &100);
-    token2.mint(&user1, &100);
+    token1.mint(&user1, &10_000);
+    token2.mint(&user1, &10_000);
     pool.provide_liquidity(
         &user1,
-        &Some(100),
-        &Some(100),
-        &Some(100),
-        &Some(100),
+        &Some(10_000),
+        &Some(10_000),
+        &Some(10_000),
+        &Some(10_000),
         &None,
         &None::<u64>,
     );
 
     assert_eq!(token1.balance(&user1), 0);
-    assert_eq!(token1.balance(&pool.address), 100);
+    assert_eq!(token1.balance(&pool.address), 10_000);
     assert_eq!(token2.balance(&user1), 0);
-    assert_eq!(token2.balance(&pool.address), 100);
+    assert_eq!(token2.balance(&pool.address), 10_000);
 
-    let share_amount = 50;
-    let min_a = 50;
-    let min_b = 50;
+    let share_amount = 5_000;
+    let min_a = 5_000;
+    let min_b = 5_000;
     pool.withdraw_liquidity(&user1, &share_amount, &min_a, &min_b, &None::<u64>);
     // assert_eq!(
     //     env.auths(),
@@ -209,12 +211,12 @@ fn withdraw_liquidity() {
     //     ]
     // );
 
-    assert_eq!(token_share.balance(&user1), 50);
-    assert_eq!(token_share.balance(&pool.address), 0); // sanity check
-    assert_eq!(token1.balance(&user1), 50);
-    assert_eq!(token1.balance(&pool.address), 50);
-    assert_eq!(token2.balance(&user1), 50);
-    assert_eq!(token2.balance(&pool.address), 50);
+    assert_eq!(token_share.balance(&user1), 4_000);
+    assert_eq!(token_share.balance(&pool.address), 1_000); // locked on the first deposit
+    assert_eq!(token1.balance(&user1), 5_000);
+    assert_eq!(token1.balance(&pool.address), 5_000);
+    assert_eq!(token2.balance(&user1), 5_000);
+    assert_eq!(token2.balance(&pool.address), 5_000);
 
     let result = pool.query_pool_info();
     assert_eq!(
@@ -222,372 +224,38 @@ fn withdraw_liquidity() {
         PoolResponse {
             asset_a: Asset {
                 address: token1.address.clone(),
-                amount: 50i128,
+                amount: 5_000i128,
             },
             asset_b: Asset {
                 address: token2.address.clone(),
-                amount: 50i128,
+                amount: 5_000i128,
             },
             asset_lp_share: Asset {
                 address: share_token_address,
-                amount: 50i128,
+                amount: 5_000i128,
             },
             stake_address: result.clone().stake_address,
         }
     );
 
-    // clear the pool
-    pool.withdraw_liquidity(&user1, &share_amount, &min_a, &min_b, &None::<u64>);
+    // withdraw the rest, the locked liquidity stays in the pool
+    pool.withdraw_liquidity(&user1, &4_000, &4_000, &4_000, &None::<u64>);
     assert_eq!(token_share.balance(&user1), 0);
-    assert_eq!(token_share.balance(&pool.address), 0); // sanity check
-    assert_eq!(token1.balance(&user1), 100);
-    assert_eq!(token1.balance(&pool.address), 0);
-    assert_eq!(token2.balance(&user1), 100);
-    assert_eq!(token2.balance(&pool.address), 0);
+    assert_eq!(token_share.balance(&pool.address), 1_000);
+    assert_eq!(token1.balance(&user1), 9_000);
+    assert_eq!(token1.balance(&pool.address), 1_000);
+    assert_eq!(token2.balance(&user1), 9_000);
+    assert_eq!(token2.balance(&pool.address), 1_000);
 }
 
-// TODO: https://github.com/Phoenix-Protocol-Group/phoenix-contracts/issues/204
-// #[test]
-// #[should_panic = "Pool: split_deposit_based_on_pool_ratio: Both pools and deposit must be a positive!"]
-// fn provide_liqudity_single_asset_on_empty_pool() {
-//     let env = Env::default();
-//     env.mock_all_auths();
-//
-//     let mut admin1 = Address::generate(&env);
-//     let mut admin2 = Address::generate(&env);
-//
-//     let mut token1 = deploy_token_contract(&env, &admin1);
-//     let mut token2 = deploy_token_contract(&env, &admin2);
-//     if token2.address < token1.address {
-//         std::mem::swap(&mut token1, &mut token2);
-//         std::mem::swap(&mut admin1, &mut admin2);
-//     }
-//     let user1 = Address::generate(&env);
-//     let stake_manager = Address::generate(&env);
-//     let stake_owner = Address::generate(&env);
-//
-//     let swap_fees = 0i64;
-//     let pool = deploy_liquidity_pool_contract(
-//         &env,
-//         None,
-//         (&token1.address, &token2.address),
-//         swap_fees,
-//         None,
-//         None,
-//         None,
-//         stake_manager,
-//         stake_owner,
-//     );
-//
-//     token1.mint(&user1, &1_000_000);
-//
-//     // providing liquidity with single asset is not allowed on an empty pool
-//     pool.provide_liquidity(
-//         &user1,
-//         &Some(1_000_000),
-//         &Some(1_000_000),
-//         &None,
-//         &None,
-//         &None,
-//     );
-// }
-//
-// #[test]
-// fn provide_liqudity_single_asset_equal() {
-//     let env = Env::default();
-//     env.mock_all_auths();
-//     env.budget().reset_unlimited();
-//
-//     let mut admin1 = Address::generate(&env);
-//     let mut admin2 = Address::generate(&env);
-//
-//     let mut token1 = deploy_token_contract(&env, &admin1);
-//     let mut token2 = deploy_token_contract(&env, &admin2);
-//     if token2.address < token1.address {
-//         std::mem::swap(&mut token1, &mut token2);
-//         std::mem::swap(&mut admin1, &mut admin2);
-//     }
-//     let user1 = Address::generate(&env);
-//     let stake_manager = Address::generate(&env);
-//     let stake_owner = Address::generate(&env);
-//
-//     let swap_fees = 0i64;
-//     let pool = deploy_liquidity_pool_contract(
-//         &env,
-//         None,
-//         (&token1.address, &token2.address),
-//         swap_fees,
-//         None,
-//         None,
-//         None,
-//         stake_manager,
-//         stake_owner,
-//     );
-//
-//     token1.mint(&user1, &10_000_000);
-//     token2.mint(&user1, &10_000_000);
-//
-//     // providing liquidity with equal assets
-//     pool.provide_liquidity(
-//         &user1,
-//         &Some(10_000_000),
-//         &Some(10_000_000),
-//         &Some(10_000_000),
-//         &Some(10_000_000),
-//         &None,
-//     );
-//     assert_eq!(token1.balance(&pool.address), 10_000_000);
-//     assert_eq!(token2.balance(&pool.address), 10_000_000);
-//
-//     token1.mint(&user1, &100_000);
-//
-//     // Providing 100k of token1 to 1:1 pool will perform swap which will create imbalance
-//     pool.provide_liquidity(
-//         &user1,
-//         &Some(100_000),
-//         &Some(50_000),
-//         &None,
-//         &Some(49_000),
-//         &None,
-//     );
-//     // before swap : A(10_000_000), B(10_000_000)
-//     // since pool is equal divides 50/50 sum for swap
-//     // swap 50k A for B = 49752
-//     // after swap : A(10_050_000), B(9_950_248)
-//     // after providing liquidity
-//     // A(1_100_000), B(1_000_000)
-//
-//     assert_eq!(token1.balance(&pool.address), 10_100_000);
-//     // because of lack of fees, first swap took from pool b exact amount
-//     // that was provided to the pool in the next step
-//     assert_eq!(token2.balance(&pool.address), 10_000_000);
-//     assert_eq!(token1.balance(&user1), 0);
-//     assert_eq!(token2.balance(&user1), 0);
-// }
-//
-// #[test]
-// fn provide_liqudity_single_asset_equal_with_fees() {
-//     let env = Env::default();
-//     env.mock_all_auths();
-//     env.budget().reset_unlimited();
-//
-//     let mut admin1 = Address::generate(&env);
-//     let mut admin2 = Address::generate(&env);
-//
-//     let mut token1 = deploy_token_contract(&env, &admin1);
-//     let mut token2 = deploy_token_contract(&env, &admin2);
-//     if token2.address < token1.address {
-//         std::mem::swap(&mut token1, &mut token2);
-//         std::mem::swap(&mut admin1, &mut admin2);
-//     }
-//     let user1 = Address::generate(&env);
-//     let stake_manager = Address::generate(&env);
-//     let stake_owner = Address::generate(&env);
-//
-//     let swap_fees = 1_000i64; // 10% bps
-//     let pool = deploy_liquidity_pool_contract(
-//         &env,
-//         None,
-//         (&token1.address, &token2.address),
-//         swap_fees,
-//         None,
-//         None,
-//         None,
-//         stake_manager,
-//         stake_owner,
-//     );
-//
-//     let initial_pool_liquidity = 10_000_000;
-//     token1.mint(&user1, &initial_pool_liquidity);
-//     token2.mint(&user1, &initial_pool_liquidity);
-//
-//     // providing liquidity with equal assets
-//     pool.provide_liquidity(
-//         &user1,
-//         &Some(initial_pool_liquidity),
-//         &Some(initial_pool_liquidity),
-//         &Some(initial_pool_liquidity),
-//         &Some(initial_pool_liquidity),
-//         &None,
-//     );
-//     assert_eq!(token1.balance(&pool.address), initial_pool_liquidity);
-//     assert_eq!(token2.balance(&pool.address), initial_pool_liquidity);
-//
-//     let token_a_amount = 100_000;
-//     token1.mint(&user1, &token_a_amount);
-//     // Providing 100k of token1 to 1:1 pool will perform swap which will create imbalance
-//     pool.provide_liquidity(
-//         &user1,
-//         &Some(token_a_amount),
-//         &Some(50_000),
-//         &None,
-//         &Some(49_000),
-//         &None,
-//     );
-//     // before swap : A(10_000_000), B(10_000_000)
-//     // algorithm splits 100k in such way, so that after swapping (with 10% fee)
-//     // it will provide liquidity maintining 1:1 ratio
-//     // split is 47_266 token A and 47213 token B (52_734 of token A was swapped to B)
-//     // after swap : A(10_052_734), B(9_947_542)
-//     // after providing liquidity
-//     // A(1_100_000), B(9_994_755)
-//
-//     // return_amount: i128 = ask_pool - (cp / (offer_pool + offer_amount))
-//     let return_amount = 52_458; // that's how many tokens B would be received from 52_734 tokens A
-//     let fees = Decimal::percent(10);
-//     assert_eq!(
-//         token1.balance(&pool.address),
-//         initial_pool_liquidity + token_a_amount
-//     );
-//     assert_eq!(
-//         token2.balance(&pool.address),
-//         initial_pool_liquidity - return_amount * fees
-//     );
-//     assert_eq!(token1.balance(&user1), 0);
-//     assert_eq!(token2.balance(&user1), 0);
-// }
-//
-// #[test]
-// fn provide_liqudity_single_asset_one_third() {
-//     let env = Env::default();
-//     env.mock_all_auths();
-//     env.budget().reset_unlimited();
-//
-//     let mut admin1 = Address::generate(&env);
-//     let mut admin2 = Address::generate(&env);
-//
-//     let mut token1 = deploy_token_contract(&env, &admin1);
-//     let mut token2 = deploy_token_contract(&env, &admin2);
-//     if token2.address < token1.address {
-//         std::mem::swap(&mut token1, &mut token2);
-//         std::mem::swap(&mut admin1, &mut admin2);
-//     }
-//     let user1 = Address::generate(&env);
-//     let stake_manager = Address::generate(&env);
-//     let stake_owner = Address::generate(&env);
-//
-//     let swap_fees = 0i64;
-//     let pool = deploy_liquidity_pool_contract(
-//         &env,
-//         None,
-//         (&token1.address, &token2.address),
-//         swap_fees,
-//         None,
-//         None,
-//         None,
-//         stake_manager,
-//         stake_owner,
-//     );
-//
-//     token1.mint(&user1, &10_000_000);
-//     token2.mint(&user1, &30_000_000);
-//
-//     // providing liquidity in 1:3 ratio
-//     pool.provide_liquidity(
-//         &user1,
-//         &Some(10_000_000),
-//         &Some(10_000_000),
-//         &Some(30_000_000),
-//         &Some(30_000_000),
-//         &None,
-//     );
-//     assert_eq!(token1.balance(&pool.address), 10_000_000);
-//     assert_eq!(token2.balance(&pool.address), 30_000_000);
-//
-//     token2.mint(&user1, &100_000);
-//     // Providing 100k of token2 to 1:3 pool will perform swap which will create imbalance
-//     let slippage_tolerance_bps = 300; // 3%
-//     pool.provide_liquidity(
-//         &user1,
-//         &None,
-//         &None,
-//         &Some(100_000),
-//         &None,
-//         &Some(slippage_tolerance_bps),
-//     );
-//     // before swap : A(10_000_000), B(30_000_000)
-//     // since pool is 1/3 divides 75k/25k sum for swap
-//     // swap 25k B for A = 8327
-//     // after swap : A(9_991_673), B(30_025_000)
-//     // after providing liquidity
-//     // A(10_000_000), B(30_100_000)
-//
-//     assert_eq!(token1.balance(&pool.address), 10_000_000);
-//     assert_eq!(token2.balance(&pool.address), 30_100_000);
-// }
-//
-// #[test]
-// fn provide_liqudity_single_asset_one_third_with_fees() {
-//     let env = Env::default();
-//     env.mock_all_auths();
-//     env.budget().reset_unlimited();
-//
-//     let mut admin1 = Address::generate(&env);
-//     let mut admin2 = Address::generate(&env);
-//
-//     let mut token1 = deploy_token_contract(&env, &admin1);
-//     let mut token2 = deploy_token_contract(&env, &admin2);
-//     if token2.address < token1.address {
-//         std::mem::swap(&mut token1, &mut token2);
-//         std::mem::swap(&mut admin1, &mut admin2);
-//     }
-//     let user1 = Address::generate(&env);
-//     let stake_manager = Address::generate(&env);
-//     let stake_owner = Address::generate(&env);
-//
-//     let swap_fees = 1_000i64; // 10% bps
-//     let pool = deploy_liquidity_pool_contract(
-//         &env,
-//         None,
-//         (&token1.address, &token2.address),
-//         swap_fees,
-//         None,
-//         None,
-//         None,
-//         stake_manager,
-//         stake_owner,
-//     );
-//
-//     token1.mint(&user1, &10_000_000);
-//     token2.mint(&user1, &30_000_000);
-//
-//     // providing liquidity in 1:3 ratio
-//     pool.provide_liquidity(
-//         &user1,
-//         &Some(10_000_000),
-//         &Some(10_000_000),
-//         &Some(30_000_000),
-//         &Some(30_000_000),
-//         &None,
-//     );
-//     assert_eq!(token1.balance(&pool.address), 10_000_000);
-//     assert_eq!(token2.balance(&pool.address), 30_000_000);
-//
-//     token2.mint(&user1, &100_000);
-//     // providing liquidity with a single asset - token2
-//     pool.provide_liquidity(&user1, &None, &None, &Some(100_000), &None, &None);
-//     // before swap : A(10_000_000), B(30_000_000)
-//     // since pool is 1/3 algorithm will split it around 15794/52734
-//     // swap 47_226k B for A = 17_548 (-10% fee = 15_793)
-//     // after swap : A(9_982_452), B(30_052_734)
-//     // after providing liquidity
-//     // A(10_000_000), B(30_100_000)
-//
-//     // return_amount: i128 = ask_pool - (cp / (offer_pool + offer_amount))
-//     let return_amount = 17_548;
-//     let fees = Decimal::percent(10);
-//     assert_eq!(
-//         token1.balance(&pool.address),
-//         10_000_000 - return_amount * fees
-//     );
-//     assert_eq!(token2.balance(&pool.address), 30_100_000);
-// }
-
 #[test]
-#[should_panic(expected = "The value 10001 is out of range. Must be between 0 and 10000 bps.")]
-fn provide_liqudity_too_high_fees() {
+#[should_panic(
+    expected = "Pool: split_deposit_based_on_pool_ratio: Both pools and deposit must be a positive!"
+)]
+fn provide_liqudity_single_asset_on_empty_pool() {
     let env = Env::default();
     env.mock_all_auths();
+    env.budget().reset_unlimited();
 
     let mut admin1 = Address::generate(&env);
     let mut admin2 = Address::generate(&env);
@@ -598,12 +266,12 @@ fn provide_liqudity_too_high_fees() {
         std::mem::swap(&mut token1, &mut token2);
         std::mem::swap(&mut admin1, &mut admin2);
     }
-    let swap_fees = 10_001i64;
-
+    let user1 = Address::generate(&env);
     let stake_manager = Address::generate(&env);
     let stake_owner = Address::generate(&env);
 
-    deploy_liquidity_pool_contract(
+    let swap_fees = 0i64;
+    let pool = deploy_liquidity_pool_contract(
         &env,
         None,
         (&token1.address, &token2.address),
@@ -614,15 +282,26 @@ fn provide_liqudity_too_high_fees() {
         stake_manager,
         stake_owner,
     );
+
+    token1.mint(&user1, &1_000_000);
+
+    // providing liquidity with single asset is not allowed on an empty pool
+    pool.provide_liquidity(
+        &user1,
+        &Some(1_000_000),
+        &None,
+        &None,
+        &None,
+        &None,
+        &None::<u64>,
+    );
 }
 
 #[test]
-#[should_panic(
-    expected = "Pool: ProvideLiquidity: Both tokens must be provided and must be bigger then 0!"
-)]
-fn swap_with_no_amounts() {
+fn provide_liqudity_single_asset_equal() {
     let env = Env::default();
     env.mock_all_auths();
+    env.budget().reset_unlimited();
 
     let mut admin1 = Address::generate(&env);
     let mut admin2 = Address::generate(&env);
@@ -650,19 +329,52 @@ fn swap_with_no_amounts() {
         stake_owner,
     );
 
-    token1.mint(&user1, &1_001_000);
-    token2.mint(&user1, &1_001_000);
-    // providing all amounts as None
-    pool.provide_liquidity(&user1, &None, &None, &None, &None, &None, &None::<u64>);
+    token1.mint(&user1, &10_000_000);
+    token2.mint(&user1, &10_000_000);
+    pool.provide_liquidity(
+        &user1,
+        &Some(10_000_000),
+        &None,
+        &Some(10_000_000),
+        &None,
+        &None,
+        &None::<u64>,
+    );
+    assert_eq!(token1.balance(&pool.address), 10_000_000);
+    assert_eq!(token2.balance(&pool.address), 10_000_000);
+    let token_share = token_contract::Client::new(&env, &pool.query_share_token_address());
+    let shares_before = token_share.balance(&user1);
+
+    token1.mint(&user1, &100_000);
+
+    // Providing 100k of token1 to 1:1 pool will swap part of it and deposit the rest
+    pool.provide_liquidity(
+        &user1,
+        &Some(100_000),
+        &None,
+        &None,
+        &Some(49_000),
+        &None,
+        &None::<u64>,
+    );
+    // before swap : A(10_000_000), B(10_000_000)
+    // swap 49_875 A for 49_628 B
+    // after swap : A(10_049_875), B(9_950_372)
+    // deposit 50_124 A and 49_628 B, 1 unit of A is left due to rounding
+    assert_eq!(token1.balance(&pool.address), 10_099_999);
+    // because of lack of fees, first swap took from pool b exact amount
+    // that was provided to the pool in the next step
+    assert_eq!(token2.balance(&pool.address), 10_000_000);
+    assert_eq!(token1.balance(&user1), 1);
+    assert_eq!(token2.balance(&user1), 0);
+    assert!(token_share.balance(&user1) > shares_before);
 }
 
 #[test]
-#[should_panic(
-    expected = "Pool: WithdrawLiquidity: Minimum amount of token_a or token_b is not satisfied!"
-)]
-fn withdraw_liqudity_below_min() {
+fn provide_liqudity_single_asset_equal_with_fees() {
     let env = Env::default();
     env.mock_all_auths();
+    env.budget().reset_unlimited();
 
     let mut admin1 = Address::generate(&env);
     let mut admin2 = Address::generate(&env);
@@ -676,40 +388,57 @@ fn withdraw_liqudity_below_min() {
     let user1 = Address::generate(&env);
     let stake_manager = Address::generate(&env);
     let stake_owner = Address::generate(&env);
+    let fee_recipient = Address::generate(&env);
 
-    let swap_fees = 0i64;
+    let swap_fees = 1_000i64; // 10% bps;
     let pool = deploy_liquidity_pool_contract(
         &env,
         None,
         (&token1.address, &token2.address),
         swap_fees,
-        None,
+        fee_recipient.clone(),
         None,
         None,
         stake_manager,
         stake_owner,
     );
 
-    token1.mint(&user1, &100);
-    token2.mint(&user1, &100);
-    // providing liquidity in a 1:1 ratio
+    token1.mint(&user1, &10_000_000);
+    token2.mint(&user1, &10_000_000);
     pool.provide_liquidity(
         &user1,
-        &Some(100),
-        &Some(100),
-        &Some(100),
-        &Some(100),
+        &Some(10_000_000),
+        &None,
+        &Some(10_000_000),
+        &None,
         &None,
         &None::<u64>,
     );
 
-    let share_amount = 50;
-    // Expecting min_a and/or min_b as huge bigger then available
-    pool.withdraw_liquidity(&user1, &share_amount, &3000, &3000, &None::<u64>);
+    token1.mint(&user1, &100_000);
+    // Providing 100k of token1 to 1:1 pool will perform swap which will create imbalance
+    pool.provide_liquidity(
+        &user1,
+        &Some(100_000),
+        &None,
+        &None,
+        &None,
+        &None,
+        &None::<u64>,
+    );
+    // with fees a bigger part of the deposit is swapped: 52_501 A for 47_005 B,
+    // 5_222 B of commission goes to the fee recipient
+    // after swap : A(10_052_501), B(9_947_773)
+    // deposit 47_499 A and 47_004 B
+    assert_eq!(token2.balance(&fee_recipient), 5_222);
+    assert_eq!(token1.balance(&pool.address), 10_100_000);
+    assert_eq!(token2.balance(&pool.address), 9_994_777);
+    assert_eq!(token1.balance(&user1), 0);
+    assert_eq!(token2.balance(&user1), 1);
 }
 
 #[test]
-fn query_share_valid_liquidity() {
+fn provide_liqudity_single_asset_one_third() {
     let env = Env::default();
     env.mock_all_auths();
     env.budget().reset_unlimited();
@@ -724,16 +453,15 @@ fn query_share_valid_liquidity() {
         std::mem::swap(&mut admin1, &mut admin2);
     }
     let user1 = Address::generate(&env);
-    let user2 = Address::generate(&env);
-    let user3 = Address::generate(&env);
     let stake_manager = Address::generate(&env);
     let stake_owner = Address::generate(&env);
 
+    let swap_fees = 0i64;
     let pool = deploy_liquidity_pool_contract(
         &env,
         None,
         (&token1.address, &token2.address),
-        0i64,
+        swap_fees,
         None,
         None,
         None,
@@ -741,250 +469,211 @@ fn query_share_valid_liquidity() {
         stake_owner,
     );
 
-    let share_token_address = pool.query_share_token_address();
-    let token_share = token_contract::Client::new(&env, &share_token_address.clone());
-
-    token1.mint(&user1, &150);
-    token1.mint(&user2, &250);
-    token1.mint(&user3, &350);
-
-    token2.mint(&user1, &200);
-    token2.mint(&user2, &300);
-    token2.mint(&user3, &400);
-
-    // all users provide liquidity in a 3:4 ratio
+    token1.mint(&user1, &10_000_000);
+    token2.mint(&user1, &30_000_000);
     pool.provide_liquidity(
         &user1,
-        &Some(150),
-        &Some(10),
-        &Some(200),
-        &Some(10),
+        &Some(10_000_000),
+        &None,
+        &Some(30_000_000),
+        &None,
         &None,
         &None::<u64>,
     );
+
+    token2.mint(&user1, &300_000);
+    // Providing 300k of token2 to 1:3 pool
     pool.provide_liquidity(
-        &user2,
-        &Some(150),
-        &Some(50),
-        &Some(200),
-        &Some(50),
+        &user1,
+        &None,
+        &None,
+        &Some(300_000),
+        &None,
         &None,
         &None::<u64>,
     );
+    // swap 149_626 B for 49_628 A
+    // after swap : A(9_950_372), B(30_149_626)
+    // deposit 49_628 A and 150_372 B
+    assert_eq!(token1.balance(&pool.address), 10_000_000);
+    assert_eq!(token2.balance(&pool.address), 30_299_998);
+    assert_eq!(token1.balance(&user1), 0);
+    assert_eq!(token2.balance(&user1), 2);
+}
+
+#[test]
+fn provide_liqudity_single_asset_one_third_with_fees() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let mut admin1 = Address::generate(&env);
+    let mut admin2 = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin1);
+    let mut token2 = deploy_token_contract(&env, &admin2);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+        std::mem::swap(&mut admin1, &mut admin2);
+    }
+    let user1 = Address::generate(&env);
+    let stake_manager = Address::generate(&env);
+    let stake_owner = Address::generate(&env);
+
+    let swap_fees = 1_000i64; // 10% bps;
+    let pool = deploy_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        swap_fees,
+        None,
+        None,
+        None,
+        stake_manager,
+        stake_owner,
+    );
+
+    token1.mint(&user1, &10_000_000);
+    token2.mint(&user1, &30_000_000);
     pool.provide_liquidity(
-        &user3,
-        &Some(150),
-        &Some(100),
-        &Some(200),
-        &Some(100),
+        &user1,
+        &Some(10_000_000),
+        &None,
+        &Some(30_000_000),
+        &None,
         &None,
         &None::<u64>,
     );
 
-    // user1 assertions
-    let lp_share_balance_user1 = token_share.balance(&user1);
-    let query_share_result_user1 = pool.query_share(&lp_share_balance_user1);
-    assert_eq!(
-        query_share_result_user1,
-        (
-            Asset {
-                address: token1.address.clone(),
-                amount: 149
-            },
-            Asset {
-                address: token2.address.clone(),
-                amount: 199
-            }
-        )
+    token2.mint(&user1, &300_000);
+    pool.provide_liquidity(
+        &user1,
+        &None,
+        &None,
+        &Some(300_000),
+        &None,
+        &None,
+        &None::<u64>,
     );
+    // swap 157_503 B for 47_005 A, 5_222 A of commission leaves the pool
+    // after swap : A(9_947_773), B(30_157_503)
+    // deposit 47_004 A and 142_497 B
+    assert_eq!(token1.balance(&pool.address), 9_994_777);
+    assert_eq!(token2.balance(&pool.address), 30_300_000);
+    assert_eq!(token1.balance(&user1), 1);
+    assert_eq!(token2.balance(&user1), 0);
+}
 
-    let pool_info_before_withdrawal = pool.query_pool_info();
-    assert_eq!(
-        pool_info_before_withdrawal,
-        PoolResponse {
-            asset_a: Asset {
-                address: token1.address.clone(),
-                amount: 450
-            },
-            asset_b: Asset {
-                address: token2.address.clone(),
-                amount: 600
-            },
-            asset_lp_share: Asset {
-                address: share_token_address.clone(),
-                amount: 519
-            },
-            stake_address: pool_info_before_withdrawal.clone().stake_address,
-        }
+#[test]
+#[should_panic(expected = "HostError: Error(Contract, #1)")]
+fn provide_liqudity_single_asset_tiny_pool_spread_too_high() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let mut admin1 = Address::generate(&env);
+    let mut admin2 = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin1);
+    let mut token2 = deploy_token_contract(&env, &admin2);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+        std::mem::swap(&mut admin1, &mut admin2);
+    }
+    let user1 = Address::generate(&env);
+    let stake_manager = Address::generate(&env);
+    let stake_owner = Address::generate(&env);
+
+    let swap_fees = 0i64;
+    let pool = deploy_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        swap_fees,
+        None,
+        None,
+        None,
+        stake_manager,
+        stake_owner,
     );
 
-    pool.withdraw_liquidity(
+    token1.mint(&user1, &1_001);
+    token2.mint(&user1, &1_001);
+    pool.provide_liquidity(
         &user1,
-        &lp_share_balance_user1,
-        &100i128,
-        &100i128,
+        &Some(1_001),
+        &None,
+        &Some(1_001),
+        &None,
+        &None,
         &None::<u64>,
     );
-    let pool_info_after_withdrawal = pool.query_pool_info();
-    assert_eq!(
-        pool_info_after_withdrawal,
-        PoolResponse {
-            asset_a: Asset {
-                address: token1.address.clone(),
-                amount: 301
-            },
-            asset_b: Asset {
-                address: token2.address.clone(),
-                amount: 401
-            },
-            asset_lp_share: Asset {
-                address: share_token_address.clone(),
-                amount: 346
-            },
-            stake_address: pool_info_after_withdrawal.clone().stake_address,
-        }
+
+    token1.mint(&user1, &1_000);
+    // deposit as big as the pool itself moves the price far beyond the max allowed spread
+    pool.provide_liquidity(
+        &user1,
+        &Some(1_000),
+        &None,
+        &None,
+        &None,
+        &None,
+        &None::<u64>,
     );
+}
 
-    let lp_share_balance_after_withdraw_user1: i128 = token_share.balance(&user1);
-    assert_eq!(lp_share_balance_after_withdraw_user1, 0);
+#[test]
+#[should_panic(expected = "HostError: Error(Contract, #11)")]
+fn provide_liqudity_single_asset_tiny_pool_default_slippage() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
 
-    let query_share_result_user1 = pool.query_share(&lp_share_balance_after_withdraw_user1);
-    assert_eq!(
-        query_share_result_user1,
-        (
-            Asset {
-                address: token1.address.clone(),
-                amount: 0
-            },
-            Asset {
-                address: token2.address.clone(),
-                amount: 0
-            }
-        )
-    );
-
-    // user2 assertions
-    let lp_share_balance_user2 = token_share.balance(&user2);
-    let query_share_result_user2 = pool.query_share(&lp_share_balance_user2);
-    assert_eq!(
-        query_share_result_user2,
-        (
-            Asset {
-                address: token1.address.clone(),
-                amount: 150
-            },
-            Asset {
-                address: token2.address.clone(),
-                amount: 200
-            }
-        )
-    );
-
-    pool.withdraw_liquidity(
-        &user2,
-        &lp_share_balance_user2,
-        &150i128,
-        &200i128,
-        &None::<u64>,
-    );
-    let pool_info_after_withdrawal = pool.query_pool_info();
-    assert_eq!(
-        pool_info_after_withdrawal,
-        PoolResponse {
-            asset_a: Asset {
-                address: token1.address.clone(),
-                amount: 151
-            },
-            asset_b: Asset {
-                address: token2.address.clone(),
-                amount: 201
-            },
-            asset_lp_share: Asset {
-                address: share_token_address.clone(),
-                amount: 173
-            },
-            stake_address: pool_info_after_withdrawal.clone().stake_address,
-        }
-    );
-
-    let lp_share_balance_after_withdraw_user2: i128 = token_share.balance(&user2);
-    assert_eq!(lp_share_balance_after_withdraw_user2, 0);
+    let mut admin1 = Address::generate(&env);
+    let mut admin2 = Address::generate(&env);
 
-    let query_share_result_user2 = pool.query_share(&lp_share_balance_after_withdraw_user2);
-    assert_eq!(
-        query_share_result_user2,
-        (
-            Asset {
-                address: token1.address.clone(),
-                amount: 0
-            },
-            Asset {
-                address: token2.address.clone(),
-                amount: 0
-            }
-        )
-    );
+    let mut token1 = deploy_token_contract(&env, &admin1);
+    let mut token2 = deploy_token_contract(&env, &admin2);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+        std::mem::swap(&mut admin1, &mut admin2);
+    }
+    let user1 = Address::generate(&env);
+    let stake_manager = Address::generate(&env);
+    let stake_owner = Address::generate(&env);
 
-    // user3 assertions
-    let lp_share_balance_user3 = token_share.balance(&user3);
-    let query_share_result_user3 = pool.query_share(&lp_share_balance_user3);
-    assert_eq!(
-        query_share_result_user3,
-        (
-            Asset {
-                address: token1.address.clone(),
-                amount: 151
-            },
-            Asset {
-                address: token2.address.clone(),
-                amount: 201
-            }
-        )
+    let swap_fees = 0i64;
+    let pool = deploy_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        swap_fees,
+        None,
+        None,
+        None,
+        stake_manager,
+        stake_owner,
     );
 
-    // user3 has 173 shares, we are withdrawing 73
-    pool.withdraw_liquidity(&user3, &73, &1i128, &1i128, &None::<u64>);
-    let pool_info_after_withdrawal = pool.query_pool_info();
-    assert_eq!(
-        pool_info_after_withdrawal,
-        PoolResponse {
-            asset_a: Asset {
-                address: token1.address.clone(),
-                amount: 88
-            },
-            asset_b: Asset {
-                address: token2.address.clone(),
-                amount: 117
-            },
-            asset_lp_share: Asset {
-                address: share_token_address.clone(),
-                amount: 100
-            },
-            stake_address: pool_info_after_withdrawal.clone().stake_address,
-        }
+    token1.mint(&user1, &1_001);
+    token2.mint(&user1, &1_001);
+    pool.provide_liquidity(
+        &user1,
+        &Some(1_001),
+        &None,
+        &Some(1_001),
+        &None,
+        &None,
+        &None::<u64>,
     );
 
-    let lp_share_balance_after_withdraw_user3: i128 = token_share.balance(&user3);
-    assert_eq!(lp_share_balance_after_withdraw_user3, 100);
-
-    let query_share_result_user3 = pool.query_share(&lp_share_balance_after_withdraw_user3);
-    assert_eq!(
-        query_share_result_user3,
-        (
-            Asset {
-                address: token1.address.clone(),
-                amount: 88
-            },
-            Asset {
-                address: token2.address.clone(),
-                amount: 117
-            }
-        )
-    );
+    token1.mint(&user1, &100);
+    // rounding in a tiny pool is bigger than the default slippage tolerance of 1%
+    pool.provide_liquidity(&user1, &Some(100), &None, &None, &None, &None, &None::<u64>);
 }
 
 #[test]
-fn query_share_empty_pool() {
+fn provide_liqudity_single_asset_tiny_pool_custom_slippage() {
     let env = Env::default();
     env.mock_all_auths();
     env.budget().reset_unlimited();
@@ -1002,11 +691,12 @@ fn query_share_empty_pool() {
     let stake_manager = Address::generate(&env);
     let stake_owner = Address::generate(&env);
 
+    let swap_fees = 0i64;
     let pool = deploy_liquidity_pool_contract(
         &env,
         None,
         (&token1.address, &token2.address),
-        0i64,
+        swap_fees,
         None,
         None,
         None,
@@ -1014,31 +704,40 @@ fn query_share_empty_pool() {
         stake_owner,
     );
 
-    let share_token_address = pool.query_share_token_address();
-    let token_share = token_contract::Client::new(&env, &share_token_address);
+    token1.mint(&user1, &1_001);
+    token2.mint(&user1, &1_001);
+    pool.provide_liquidity(
+        &user1,
+        &Some(1_001),
+        &None,
+        &Some(1_001),
+        &None,
+        &None,
+        &None::<u64>,
+    );
 
-    let lp_share_balance = token_share.balance(&user1);
-    let query_share_result = pool.query_share(&lp_share_balance);
-    assert_eq!(
-        query_share_result,
-        (
-            Asset {
-                address: token1.address,
-                amount: 0
-            },
-            Asset {
-                address: token2.address,
-                amount: 0
-            }
-        )
+    token1.mint(&user1, &100);
+    pool.provide_liquidity(
+        &user1,
+        &Some(100),
+        &None,
+        &None,
+        &None,
+        &Some(300),
+        &None::<u64>,
     );
+    // swap 48 A for 46 B
+    // after swap : A(1_049), B(955)
+    // deposit 50 A and 46 B
+    assert_eq!(token1.balance(&pool.address), 1_099);
+    assert_eq!(token2.balance(&pool.address), 1_001);
+    assert_eq!(token1.balance(&user1), 2);
+    assert_eq!(token2.balance(&user1), 0);
 }
 
-#[should_panic(
-    expected = "Pool: ProvideLiquidity: Custom slippage tolerance is more than max allowed slippage tolerance"
-)]
 #[test]
-fn provide_liquidity_slippage_tolerance_too_high() {
+#[should_panic(expected = "HostError: Error(Contract, #14)")]
+fn provide_liqudity_single_asset_full_commission() {
     let env = Env::default();
     env.mock_all_auths();
     env.budget().reset_unlimited();
@@ -1052,35 +751,53 @@ fn provide_liquidity_slippage_tolerance_too_high() {
         std::mem::swap(&mut token1, &mut token2);
         std::mem::swap(&mut admin1, &mut admin2);
     }
+    let user1 = Address::generate(&env);
+    let stake_manager = Address::generate(&env);
+    let stake_owner = Address::generate(&env);
 
+    let swap_fees = 10_000i64;
     let pool = deploy_liquidity_pool_contract(
         &env,
         None,
         (&token1.address, &token2.address),
-        0i64,
+        swap_fees,
         None,
         None,
         None,
-        Address::generate(&env),
-        Address::generate(&env),
+        stake_manager,
+        stake_owner,
     );
 
+    token1.mint(&user1, &1_000_000);
+    token2.mint(&user1, &1_000_000);
     pool.provide_liquidity(
-        &Address::generate(&env),
-        &Some(100),
-        &Some(100),
-        &Some(100),
-        &Some(100),
-        &Some(10001),
+        &user1,
+        &Some(1_000_000),
+        &None,
+        &Some(1_000_000),
+        &None,
+        &None,
+        &None::<u64>,
+    );
+
+    token1.mint(&user1, &10_000);
+    // with 100% commission the whole deposit is swapped and nothing is returned
+    pool.provide_liquidity(
+        &user1,
+        &Some(10_000),
+        &None,
+        &None,
+        &None,
+        &None,
         &None::<u64>,
     );
 }
 
 #[test]
-fn test_query_info_for_factory_works() {
+#[should_panic(expected = "The value 10001 is out of range. Must be between 0 and 10000 bps.")]
+fn provide_liqudity_too_high_fees() {
     let env = Env::default();
     env.mock_all_auths();
-    env.budget().reset_unlimited();
 
     let mut admin1 = Address::generate(&env);
     let mut admin2 = Address::generate(&env);
@@ -1091,50 +808,31 @@ fn test_query_info_for_factory_works() {
         std::mem::swap(&mut token1, &mut token2);
         std::mem::swap(&mut admin1, &mut admin2);
     }
-    let user1 = Address::generate(&env);
+    let swap_fees = 10_001i64;
+
     let stake_manager = Address::generate(&env);
     let stake_owner = Address::generate(&env);
-    let swap_fees = 0i64;
-    let pool = deploy_liquidity_pool_contract(
+
+    deploy_liquidity_pool_contract(
         &env,
-        Some(admin1.clone()),
+        None,
         (&token1.address, &token2.address),
         swap_fees,
-        user1.clone(),
-        500,
-        200,
+        None,
+        None,
+        None,
         stake_manager,
         stake_owner,
     );
-
-    let result = pool.query_pool_info_for_factory();
-    // not using result only because we have to take the current contract address, which is not known during the test
-    assert_eq!(
-        result.pool_response,
-        PoolResponse {
-            asset_a: Asset {
-                address: token1.address,
-                amount: 0
-            },
-            asset_b: Asset {
-                address: token2.address,
-                amount: 0
-            },
-            asset_lp_share: Asset {
-                address: pool.query_share_token_address(),
-                amount: 0
-            },
-            stake_address: pool.query_stake_contract_address(),
-        }
-    );
-    assert_eq!(result.total_fee_bps, 0);
 }
 
 #[test]
-fn provide_liqudity_with_deadline_should_work() {
+#[should_panic(
+    expected = "Pool: ProvideLiquidity: Both tokens must be provided and must be bigger then 0!"
+)]
+fn swap_with_no_amounts() {
     let env = Env::default();
     env.mock_all_auths();
-    env.budget().reset_unlimited();
 
     let mut admin1 = Address::generate(&env);
     let mut admin2 = Address::generate(&env);
@@ -1162,61 +860,19 @@ fn provide_liqudity_with_deadline_should_work() {
         stake_owner,
     );
 
-    let share_token_address = pool.query_share_token_address();
-    let token_share = token_contract::Client::new(&env, &share_token_address);
-
-    token1.mint(&user1, &1000);
-    assert_eq!(token1.balance(&user1), 1000);
-
-    token2.mint(&user1, &1000);
-    assert_eq!(token2.balance(&user1), 1000);
-
-    env.ledger().with_mut(|li| li.timestamp = 99);
-    pool.provide_liquidity(
-        &user1,
-        &Some(100),
-        &Some(100),
-        &Some(100),
-        &Some(100),
-        &None,
-        &Some(100),
-    );
-
-    assert_eq!(token_share.balance(&user1), 100);
-    assert_eq!(token_share.balance(&pool.address), 0);
-    assert_eq!(token1.balance(&user1), 900);
-    assert_eq!(token1.balance(&pool.address), 100);
-    assert_eq!(token2.balance(&user1), 900);
-    assert_eq!(token2.balance(&pool.address), 100);
-
-    let result = pool.query_pool_info();
-    assert_eq!(
-        result,
-        PoolResponse {
-            asset_a: Asset {
-                address: token1.address,
-                amount: 100i128
-            },
-            asset_b: Asset {
-                address: token2.address,
-                amount: 100i128
-            },
-            asset_lp_share: Asset {
-                address: share_token_address,
-                amount: 100i128
-            },
-            stake_address: result.clone().stake_address,
-        }
-    );
-    assert_eq!(pool.query_total_issued_lp(), 100);
-}
+    token1.mint(&user1, &1_001_000);
+    token2.mint(&user1, &1_001_000);
+    // providing all amounts as None
+    pool.provide_liquidity(&user1, &None, &None, &None, &None, &None, &None::<u64>);
+}
 
 #[test]
-#[should_panic(expected = "Pool: Provide Liquidity: Transaction executed after deadline!")]
-fn provide_liqudity_past_deadline_should_panic() {
+#[should_panic(
+    expected = "Pool: WithdrawLiquidity: Minimum amount of token_a or token_b is not satisfied!"
+)]
+fn withdraw_liqudity_below_min() {
     let env = Env::default();
     env.mock_all_auths();
-    env.budget().reset_unlimited();
 
     let mut admin1 = Address::generate(&env);
     let mut admin2 = Address::generate(&env);
@@ -1244,26 +900,26 @@ fn provide_liqudity_past_deadline_should_panic() {
         stake_owner,
     );
 
-    token1.mint(&user1, &1000);
-    assert_eq!(token1.balance(&user1), 1000);
-
-    token2.mint(&user1, &1000);
-    assert_eq!(token2.balance(&user1), 1000);
-
-    env.ledger().with_mut(|li| li.timestamp = 100);
+    token1.mint(&user1, &10_000);
+    token2.mint(&user1, &10_000);
+    // providing liquidity in a 1:1 ratio
     pool.provide_liquidity(
         &user1,
-        &Some(100),
-        &Some(100),
-        &Some(100),
-        &Some(100),
+        &Some(10_000),
+        &Some(10_000),
+        &Some(10_000),
+        &Some(10_000),
         &None,
-        &Some(99),
+        &None::<u64>,
     );
+
+    let share_amount = 5_000;
+    // Expecting min_a and/or min_b as huge bigger then available
+    pool.withdraw_liquidity(&user1, &share_amount, &30_000, &30_000, &None::<u64>);
 }
 
 #[test]
-fn withdraw_liquidity_with_deadline_should_work() {
+fn query_share_valid_liquidity() {
     let env = Env::default();
     env.mock_all_auths();
     env.budget().reset_unlimited();
@@ -1278,15 +934,16 @@ fn withdraw_liquidity_with_deadline_should_work() {
         std::mem::swap(&mut admin1, &mut admin2);
     }
     let user1 = Address::generate(&env);
+    let user2 = Address::generate(&env);
+    let user3 = Address::generate(&env);
     let stake_manager = Address::generate(&env);
     let stake_owner = Address::generate(&env);
 
-    let swap_fees = 0i64;
     let pool = deploy_liquidity_pool_contract(
         &env,
         None,
         (&token1.address, &token2.address),
-        swap_fees,
+        0i64,
         None,
         None,
         None,
@@ -1295,121 +952,1148 @@ fn withdraw_liquidity_with_deadline_should_work() {
     );
 
     let share_token_address = pool.query_share_token_address();
-    let token_share = token_contract::Client::new(&env, &share_token_address);
+    let token_share = token_contract::Client::new(&env, &share_token_address.clone());
 
-    token1.mint(&user1, &100);
-    token2.mint(&user1, &100);
+    token1.mint(&user1, &15_000);
+    token1.mint(&user2, &25_000);
+    token1.mint(&user3, &35_000);
+
+    token2.mint(&user1, &20_000);
+    token2.mint(&user2, &30_000);
+    token2.mint(&user3, &40_000);
+
+    // all users provide liquidity in a 3:4 ratio
     pool.provide_liquidity(
         &user1,
-        &Some(100),
-        &Some(100),
-        &Some(100),
-        &Some(100),
+        &Some(15_000),
+        &Some(1_000),
+        &Some(20_000),
+        &Some(1_000),
+        &None,
+        &None::<u64>,
+    );
+    pool.provide_liquidity(
+        &user2,
+        &Some(15_000),
+        &Some(5_000),
+        &Some(20_000),
+        &Some(5_000),
+        &None,
+        &None::<u64>,
+    );
+    pool.provide_liquidity(
+        &user3,
+        &Some(15_000),
+        &Some(10_000),
+        &Some(20_000),
+        &Some(10_000),
         &None,
         &None::<u64>,
     );
 
-    assert_eq!(token1.balance(&user1), 0);
-    assert_eq!(token1.balance(&pool.address), 100);
-    assert_eq!(token2.balance(&user1), 0);
-    assert_eq!(token2.balance(&pool.address), 100);
-
-    let share_amount = 50;
-    let min_a = 50;
-    let min_b = 50;
-    env.ledger().with_mut(|li| li.timestamp = 49);
-    pool.withdraw_liquidity(&user1, &share_amount, &min_a, &min_b, &Some(50));
-
-    assert_eq!(token_share.balance(&user1), 50);
-    assert_eq!(token_share.balance(&pool.address), 0);
-    assert_eq!(token1.balance(&user1), 50);
-    assert_eq!(token1.balance(&pool.address), 50);
-    assert_eq!(token2.balance(&user1), 50);
-    assert_eq!(token2.balance(&pool.address), 50);
+    // user1 assertions
+    let lp_share_balance_user1 = token_share.balance(&user1);
+    let query_share_result_user1 = pool.query_share(&lp_share_balance_user1);
+    assert_eq!(
+        query_share_result_user1,
+        (
+            Asset {
+                address: token1.address.clone(),
+                amount: 14_133
+            },
+            Asset {
+                address: token2.address.clone(),
+                amount: 18_845
+            }
+        )
+    );
 
-    let result = pool.query_pool_info();
+    let pool_info_before_withdrawal = pool.query_pool_info();
     assert_eq!(
-        result,
+        pool_info_before_withdrawal,
         PoolResponse {
             asset_a: Asset {
                 address: token1.address.clone(),
-                amount: 50i128,
+                amount: 45_000
             },
             asset_b: Asset {
                 address: token2.address.clone(),
-                amount: 50i128,
+                amount: 60_000
             },
             asset_lp_share: Asset {
-                address: share_token_address,
-                amount: 50i128,
+                address: share_token_address.clone(),
+                amount: 51_960
             },
-            stake_address: result.clone().stake_address,
+            stake_address: pool_info_before_withdrawal.clone().stake_address,
         }
     );
 
-    env.ledger().with_mut(|li| li.timestamp = 99);
-    pool.withdraw_liquidity(&user1, &share_amount, &min_a, &min_b, &Some(100));
-    assert_eq!(token_share.balance(&user1), 0);
-    assert_eq!(token_share.balance(&pool.address), 0); // sanity check
-    assert_eq!(token1.balance(&user1), 100);
-    assert_eq!(token1.balance(&pool.address), 0);
-    assert_eq!(token2.balance(&user1), 100);
-    assert_eq!(token2.balance(&pool.address), 0);
-}
-
-#[test]
-#[should_panic(expected = "Pool: Withdraw Liquidity: Transaction executed after deadline!")]
-fn withdraw_liquidity_past_deadline_should_panic() {
-    let env = Env::default();
-    env.mock_all_auths();
-    env.budget().reset_unlimited();
+    pool.withdraw_liquidity(
+        &user1,
+        &lp_share_balance_user1,
+        &10_000i128,
+        &10_000i128,
+        &None::<u64>,
+    );
+    let pool_info_after_withdrawal = pool.query_pool_info();
+    assert_eq!(
+        pool_info_after_withdrawal,
+        PoolResponse {
+            asset_a: Asset {
+                address: token1.address.clone(),
+                amount: 30_867
+            },
+            asset_b: Asset {
+                address: token2.address.clone(),
+                amount: 41_155
+            },
+            asset_lp_share: Asset {
+                address: share_token_address.clone(),
+                amount: 35_640
+            },
+            stake_address: pool_info_after_withdrawal.clone().stake_address,
+        }
+    );
 
-    let mut admin1 = Address::generate(&env);
-    let mut admin2 = Address::generate(&env);
+    let lp_share_balance_after_withdraw_user1: i128 = token_share.balance(&user1);
+    assert_eq!(lp_share_balance_after_withdraw_user1, 0);
 
-    let mut token1 = deploy_token_contract(&env, &admin1);
-    let mut token2 = deploy_token_contract(&env, &admin2);
-    if token2.address < token1.address {
-        std::mem::swap(&mut token1, &mut token2);
-        std::mem::swap(&mut admin1, &mut admin2);
-    }
-    let user1 = Address::generate(&env);
-    let stake_manager = Address::generate(&env);
-    let stake_owner = Address::generate(&env);
+    let query_share_result_user1 = pool.query_share(&lp_share_balance_after_withdraw_user1);
+    assert_eq!(
+        query_share_result_user1,
+        (
+            Asset {
+                address: token1.address.clone(),
+                amount: 0
+            },
+            Asset {
+                address: token2.address.clone(),
+                amount: 0
+            }
+        )
+    );
 
-    let swap_fees = 0i64;
-    let pool = deploy_liquidity_pool_contract(
-        &env,
-        None,
-        (&token1.address, &token2.address),
-        swap_fees,
-        None,
-        None,
-        None,
-        stake_manager,
-        stake_owner,
+    // user2 assertions
+    let lp_share_balance_user2 = token_share.balance(&user2);
+    let query_share_result_user2 = pool.query_share(&lp_share_balance_user2);
+    assert_eq!(
+        query_share_result_user2,
+        (
+            Asset {
+                address: token1.address.clone(),
+                amount: 15_000
+            },
+            Asset {
+                address: token2.address.clone(),
+                amount: 20_000
+            }
+        )
     );
 
-    token1.mint(&user1, &100);
-    token2.mint(&user1, &100);
-    pool.provide_liquidity(
-        &user1,
-        &Some(100),
-        &Some(100),
-        &Some(100),
-        &Some(100),
-        &None,
+    pool.withdraw_liquidity(
+        &user2,
+        &lp_share_balance_user2,
+        &15_000i128,
+        &20_000i128,
         &None::<u64>,
     );
+    let pool_info_after_withdrawal = pool.query_pool_info();
+    assert_eq!(
+        pool_info_after_withdrawal,
+        PoolResponse {
+            asset_a: Asset {
+                address: token1.address.clone(),
+                amount: 15_867
+            },
+            asset_b: Asset {
+                address: token2.address.clone(),
+                amount: 21_155
+            },
+            asset_lp_share: Asset {
+                address: share_token_address.clone(),
+                amount: 18_320
+            },
+            stake_address: pool_info_after_withdrawal.clone().stake_address,
+        }
+    );
 
-    assert_eq!(token1.balance(&user1), 0);
-    assert_eq!(token1.balance(&pool.address), 100);
-    assert_eq!(token2.balance(&user1), 0);
-    assert_eq!(token2.balance(&pool.address), 100);
+    let lp_share_balance_after_withdraw_user2: i128 = token_share.balance(&user2);
+    assert_eq!(lp_share_balance_after_withdraw_user2, 0);
 
-    let share_amount = 50;
-    let min_a = 50;
-    let min_b = 50;
-    env.ledger().with_mut(|li| li.timestamp = 50);
-    pool.withdraw_liquidity(&user1, &share_amount, &min_a, &min_b, &Some(49));
+    let query_share_result_user2 = pool.query_share(&lp_share_balance_after_withdraw_user2);
+    assert_eq!(
+        query_share_result_user2,
+        (
+            Asset {
+                address: token1.address.clone(),
+                amount: 0
+            },
+            Asset {
+                address: token2.address.clone(),
+                amount: 0
+            }
+        )
+    );
+
+    // user3 assertions
+    let lp_share_balance_user3 = token_share.balance(&user3);
+    let query_share_result_user3 = pool.query_share(&lp_share_balance_user3);
+    assert_eq!(
+        query_share_result_user3,
+        (
+            Asset {
+                address: token1.address.clone(),
+                amount: 15_000
+            },
+            Asset {
+                address: token2.address.clone(),
+                amount: 20_000
+            }
+        )
+    );
+
+    // user3 has 17_320 shares, we are withdrawing 7_320
+    pool.withdraw_liquidity(&user3, &7_320, &1i128, &1i128, &None::<u64>);
+    let pool_info_after_withdrawal = pool.query_pool_info();
+    assert_eq!(
+        pool_info_after_withdrawal,
+        PoolResponse {
+            asset_a: Asset {
+                address: token1.address.clone(),
+                amount: 9_528
+            },
+            asset_b: Asset {
+                address: token2.address.clone(),
+                amount: 12_703
+            },
+            asset_lp_share: Asset {
+                address: share_token_address.clone(),
+                amount: 11_000
+            },
+            stake_address: pool_info_after_withdrawal.clone().stake_address,
+        }
+    );
+
+    let lp_share_balance_after_withdraw_user3: i128 = token_share.balance(&user3);
+    assert_eq!(lp_share_balance_after_withdraw_user3, 10_000);
+
+    let query_share_result_user3 = pool.query_share(&lp_share_balance_after_withdraw_user3);
+    assert_eq!(
+        query_share_result_user3,
+        (
+            Asset {
+                address: token1.address.clone(),
+                amount: 8_661
+            },
+            Asset {
+                address: token2.address.clone(),
+                amount: 11_548
+            }
+        )
+    );
+}
+
+#[test]
+fn query_share_empty_pool() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let mut admin1 = Address::generate(&env);
+    let mut admin2 = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin1);
+    let mut token2 = deploy_token_contract(&env, &admin2);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+        std::mem::swap(&mut admin1, &mut admin2);
+    }
+    let user1 = Address::generate(&env);
+    let stake_manager = Address::generate(&env);
+    let stake_owner = Address::generate(&env);
+
+    let pool = deploy_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        stake_manager,
+        stake_owner,
+    );
+
+    let share_token_address = pool.query_share_token_address();
+    let token_share = token_contract::Client::new(&env, &share_token_address);
+
+    let lp_share_balance = token_share.balance(&user1);
+    let query_share_result = pool.query_share(&lp_share_balance);
+    assert_eq!(
+        query_share_result,
+        (
+            Asset {
+                address: token1.address,
+                amount: 0
+            },
+            Asset {
+                address: token2.address,
+                amount: 0
+            }
+        )
+    );
+}
+
+#[should_panic(
+    expected = "Pool: ProvideLiquidity: Custom slippage tolerance is more than max allowed slippage tolerance"
+)]
+#[test]
+fn provide_liquidity_slippage_tolerance_too_high() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let mut admin1 = Address::generate(&env);
+    let mut admin2 = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin1);
+    let mut token2 = deploy_token_contract(&env, &admin2);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+        std::mem::swap(&mut admin1, &mut admin2);
+    }
+
+    let pool = deploy_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+    );
+
+    pool.provide_liquidity(
+        &Address::generate(&env),
+        &Some(100),
+        &Some(100),
+        &Some(100),
+        &Some(100),
+        &Some(10001),
+        &None::<u64>,
+    );
+}
+
+#[test]
+fn test_query_info_for_factory_works() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let mut admin1 = Address::generate(&env);
+    let mut admin2 = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin1);
+    let mut token2 = deploy_token_contract(&env, &admin2);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+        std::mem::swap(&mut admin1, &mut admin2);
+    }
+    let user1 = Address::generate(&env);
+    let stake_manager = Address::generate(&env);
+    let stake_owner = Address::generate(&env);
+    let swap_fees = 0i64;
+    let pool = deploy_liquidity_pool_contract(
+        &env,
+        Some(admin1.clone()),
+        (&token1.address, &token2.address),
+        swap_fees,
+        user1.clone(),
+        500,
+        200,
+        stake_manager,
+        stake_owner,
+    );
+
+    let result = pool.query_pool_info_for_factory();
+    // not using result only because we have to take the current contract address, which is not known during the test
+    assert_eq!(
+        result.pool_response,
+        PoolResponse {
+            asset_a: Asset {
+                address: token1.address,
+                amount: 0
+            },
+            asset_b: Asset {
+                address: token2.address,
+                amount: 0
+            },
+            asset_lp_share: Asset {
+                address: pool.query_share_token_address(),
+                amount: 0
+            },
+            stake_address: pool.query_stake_contract_address(),
+        }
+    );
+    assert_eq!(result.total_fee_bps, 0);
+}
+
+#[test]
+fn provide_liqudity_with_deadline_should_work() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let mut admin1 = Address::generate(&env);
+    let mut admin2 = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin1);
+    let mut token2 = deploy_token_contract(&env, &admin2);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+        std::mem::swap(&mut admin1, &mut admin2);
+    }
+    let user1 = Address::generate(&env);
+    let stake_manager = Address::generate(&env);
+    let stake_owner = Address::generate(&env);
+
+    let swap_fees = 0i64;
+    let pool = deploy_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        swap_fees,
+        None,
+        None,
+        None,
+        stake_manager,
+        stake_owner,
+    );
+
+    let share_token_address = pool.query_share_token_address();
+    let token_share = token_contract::Client::new(&env, &share_token_address);
+
+    token1.mint(&user1, &100_000);
+    assert_eq!(token1.balance(&user1), 100_000);
+
+    token2.mint(&user1, &100_000);
+    assert_eq!(token2.balance(&user1), 100_000);
+
+    env.ledger().with_mut(|li| li.timestamp = 99);
+    pool.provide_liquidity(
+        &user1,
+        &Some(10_000),
+        &Some(10_000),
+        &Some(10_000),
+        &Some(10_000),
+        &None,
+        &Some(100),
+    );
+
+    assert_eq!(token_share.balance(&user1), 9_000);
+    assert_eq!(token_share.balance(&pool.address), 1_000);
+    assert_eq!(token1.balance(&user1), 90_000);
+    assert_eq!(token1.balance(&pool.address), 10_000);
+    assert_eq!(token2.balance(&user1), 90_000);
+    assert_eq!(token2.balance(&pool.address), 10_000);
+
+    let result = pool.query_pool_info();
+    assert_eq!(
+        result,
+        PoolResponse {
+            asset_a: Asset {
+                address: token1.address,
+                amount: 10_000i128
+            },
+            asset_b: Asset {
+                address: token2.address,
+                amount: 10_000i128
+            },
+            asset_lp_share: Asset {
+                address: share_token_address,
+                amount: 10_000i128
+            },
+            stake_address: result.clone().stake_address,
+        }
+    );
+    assert_eq!(pool.query_total_issued_lp(), 10_000);
+}
+
+#[test]
+#[should_panic(expected = "Pool: Provide Liquidity: Transaction executed after deadline!")]
+fn provide_liqudity_past_deadline_should_panic() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let mut admin1 = Address::generate(&env);
+    let mut admin2 = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin1);
+    let mut token2 = deploy_token_contract(&env, &admin2);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+        std::mem::swap(&mut admin1, &mut admin2);
+    }
+    let user1 = Address::generate(&env);
+    let stake_manager = Address::generate(&env);
+    let stake_owner = Address::generate(&env);
+
+    let swap_fees = 0i64;
+    let pool = deploy_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        swap_fees,
+        None,
+        None,
+        None,
+        stake_manager,
+        stake_owner,
+    );
+
+    token1.mint(&user1, &1000);
+    assert_eq!(token1.balance(&user1), 1000);
+
+    token2.mint(&user1, &1000);
+    assert_eq!(token2.balance(&user1), 1000);
+
+    env.ledger().with_mut(|li| li.timestamp = 100);
+    pool.provide_liquidity(
+        &user1,
+        &Some(100),
+        &Some(100),
+        &Some(100),
+        &Some(100),
+        &None,
+        &Some(99),
+    );
+}
+
+#[test]
+fn withdraw_liquidity_with_deadline_should_work() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let mut admin1 = Address::generate(&env);
+    let mut admin2 = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin1);
+    let mut token2 = deploy_token_contract(&env, &admin2);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+        std::mem::swap(&mut admin1, &mut admin2);
+    }
+    let user1 = Address::generate(&env);
+    let stake_manager = Address::generate(&env);
+    let stake_owner = Address::generate(&env);
+
+    let swap_fees = 0i64;
+    let pool = deploy_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        swap_fees,
+        None,
+        None,
+        None,
+        stake_manager,
+        stake_owner,
+    );
+
+    let share_token_address = pool.query_share_token_address();
+    let token_share = token_contract::Client::new(&env, &share_token_address);
+
+    token1.mint(&user1, &10_000);
+    token2.mint(&user1, &10_000);
+    pool.provide_liquidity(
+        &user1,
+        &Some(10_000),
+        &Some(10_000),
+        &Some(10_000),
+        &Some(10_000),
+        &None,
+        &None::<u64>,
+    );
+
+    assert_eq!(token1.balance(&user1), 0);
+    assert_eq!(token1.balance(&pool.address), 10_000);
+    assert_eq!(token2.balance(&user1), 0);
+    assert_eq!(token2.balance(&pool.address), 10_000);
+
+    let share_amount = 5_000;
+    let min_a = 5_000;
+    let min_b = 5_000;
+    env.ledger().with_mut(|li| li.timestamp = 49);
+    pool.withdraw_liquidity(&user1, &share_amount, &min_a, &min_b, &Some(50));
+
+    assert_eq!(token_share.balance(&user1), 4_000);
+    assert_eq!(token_share.balance(&pool.address), 1_000);
+    assert_eq!(token1.balance(&user1), 5_000);
+    assert_eq!(token1.balance(&pool.address), 5_000);
+    assert_eq!(token2.balance(&user1), 5_000);
+    assert_eq!(token2.balance(&pool.address), 5_000);
+
+    let result = pool.query_pool_info();
+    assert_eq!(
+        result,
+        PoolResponse {
+            asset_a: Asset {
+                address: token1.address.clone(),
+                amount: 5_000i128,
+            },
+            asset_b: Asset {
+                address: token2.address.clone(),
+                amount: 5_000i128,
+            },
+            asset_lp_share: Asset {
+                address: share_token_address,
+                amount: 5_000i128,
+            },
+            stake_address: result.clone().stake_address,
+        }
+    );
+
+    env.ledger().with_mut(|li| li.timestamp = 99);
+    pool.withdraw_liquidity(&user1, &4_000, &4_000, &4_000, &Some(100));
+    assert_eq!(token_share.balance(&user1), 0);
+    assert_eq!(token_share.balance(&pool.address), 1_000); // locked on the first deposit
+    assert_eq!(token1.balance(&user1), 9_000);
+    assert_eq!(token1.balance(&pool.address), 1_000);
+    assert_eq!(token2.balance(&user1), 9_000);
+    assert_eq!(token2.balance(&pool.address), 1_000);
+}
+
+#[test]
+#[should_panic(expected = "Pool: Withdraw Liquidity: Transaction executed after deadline!")]
+fn withdraw_liquidity_past_deadline_should_panic() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let mut admin1 = Address::generate(&env);
+    let mut admin2 = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin1);
+    let mut token2 = deploy_token_contract(&env, &admin2);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+        std::mem::swap(&mut admin1, &mut admin2);
+    }
+    let user1 = Address::generate(&env);
+    let stake_manager = Address::generate(&env);
+    let stake_owner = Address::generate(&env);
+
+    let swap_fees = 0i64;
+    let pool = deploy_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        swap_fees,
+        None,
+        None,
+        None,
+        stake_manager,
+        stake_owner,
+    );
+
+    token1.mint(&user1, &10_000);
+    token2.mint(&user1, &10_000);
+    pool.provide_liquidity(
+        &user1,
+        &Some(10_000),
+        &Some(10_000),
+        &Some(10_000),
+        &Some(10_000),
+        &None,
+        &None::<u64>,
+    );
+
+    assert_eq!(token1.balance(&user1), 0);
+    assert_eq!(token1.balance(&pool.address), 10_000);
+    assert_eq!(token2.balance(&user1), 0);
+    assert_eq!(token2.balance(&pool.address), 10_000);
+
+    let share_amount = 5_000;
+    let min_a = 5_000;
+    let min_b = 5_000;
+    env.ledger().with_mut(|li| li.timestamp = 50);
+    pool.withdraw_liquidity(&user1, &share_amount, &min_a, &min_b, &Some(49));
+}
+
+#[test]
+fn withdraw_liquidity_single_pays_out_one_token() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let mut token1 = deploy_token_contract(&env, &Address::generate(&env));
+    let mut token2 = deploy_token_contract(&env, &Address::generate(&env));
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let user1 = Address::generate(&env);
+    let fee_recipient = Address::generate(&env);
+
+    let swap_fees = 1_000i64; // 10% bps
+    let pool = deploy_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        swap_fees,
+        fee_recipient.clone(),
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+    );
+
+    token1.mint(&user1, &1_000_000);
+    token2.mint(&user1, &1_000_000);
+    pool.provide_liquidity(
+        &user1,
+        &Some(1_000_000),
+        &None,
+        &Some(1_000_000),
+        &None,
+        &None,
+        &None::<u64>,
+    );
+
+    // 10% of the shares are worth 100_000 of each token. The 100_000 of token2 are swapped
+    // against the remaining 900_000 / 900_000 reserves for 90_000 token1, minus 10% commission
+    let expected = SimulateWithdrawSingleResponse {
+        ask_amount: 100_000 + 81_000,
+        commission_amount: 9_000,
+        protocol_fee_amount: 9_000,
+        spread_amount: 10_000,
+    };
+    assert_eq!(
+        pool.simulate_withdraw_single(&100_000, &token1.address),
+        expected
+    );
+
+    let return_amount =
+        pool.withdraw_liquidity_single(&user1, &100_000, &token1.address, &181_000, &None::<u64>);
+
+    assert_eq!(return_amount, expected.ask_amount);
+    assert_eq!(token1.balance(&user1), 181_000);
+    assert_eq!(token2.balance(&user1), 0);
+    assert_eq!(token1.balance(&fee_recipient), 9_000);
+    let share_token = token_contract::Client::new(&env, &pool.query_share_token_address());
+    assert_eq!(share_token.balance(&user1), 899_000);
+
+    let result = pool.query_pool_info();
+    assert_eq!(result.asset_a.amount, 1_000_000 - 181_000 - 9_000);
+    assert_eq!(result.asset_b.amount, 1_000_000);
+    assert_eq!(result.asset_lp_share.amount, 900_000);
+    assert_eq!(token1.balance(&pool.address), result.asset_a.amount);
+}
+
+#[test]
+#[should_panic(expected = "Pool: WithdrawLiquiditySingle: Minimum amount is not satisfied!")]
+fn withdraw_liquidity_single_below_min() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let mut token1 = deploy_token_contract(&env, &Address::generate(&env));
+    let mut token2 = deploy_token_contract(&env, &Address::generate(&env));
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let user1 = Address::generate(&env);
+
+    let pool = deploy_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+    );
+
+    token1.mint(&user1, &1_000_000);
+    token2.mint(&user1, &1_000_000);
+    pool.provide_liquidity(
+        &user1,
+        &Some(1_000_000),
+        &None,
+        &Some(1_000_000),
+        &None,
+        &None,
+        &None::<u64>,
+    );
+
+    // without fees the withdrawal pays out 100_000 + 90_000
+    pool.withdraw_liquidity_single(&user1, &100_000, &token2.address, &190_001, &None::<u64>);
+}
+
+#[test]
+fn withdraw_liquidity_single_of_all_shares_leaves_locked_liquidity() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let mut token1 = deploy_token_contract(&env, &Address::generate(&env));
+    let mut token2 = deploy_token_contract(&env, &Address::generate(&env));
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let user1 = Address::generate(&env);
+
+    let pool = deploy_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+    );
+
+    token1.mint(&user1, &10_000);
+    token2.mint(&user1, &10_000);
+    pool.provide_liquidity(
+        &user1,
+        &Some(10_000),
+        &None,
+        &Some(10_000),
+        &None,
+        &None,
+        &None::<u64>,
+    );
+
+    // the shares locked on the first deposit keep liquidity to swap token2 against
+    let return_amount =
+        pool.withdraw_liquidity_single(&user1, &9_000, &token1.address, &1, &None::<u64>);
+    // 9_000 token1 for the shares and 900 for swapping 9_000 token2 against the rest
+    assert_eq!(return_amount, 9_900);
+    assert_eq!(token1.balance(&user1), 9_900);
+    assert_eq!(token1.balance(&pool.address), 100);
+    assert_eq!(token2.balance(&pool.address), 10_000);
+    assert_eq!(pool.query_total_issued_lp(), 1_000);
+}
+
+#[test]
+fn skim_sends_out_tokens_sent_to_the_pool_directly() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let recipient = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let pool = deploy_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+    );
+
+    token1.mint(&user, &10_000);
+    token2.mint(&user, &10_000);
+    pool.provide_liquidity(
+        &user,
+        &Some(10_000),
+        &None,
+        &Some(10_000),
+        &None,
+        &None,
+        &None::<u64>,
+    );
+    assert_eq!(pool.query_reserve_discrepancy(), (0, 0));
+
+    // tokens sent with a plain transfer do not show up in the reserves
+    token1.mint(&pool.address, &100);
+    assert_eq!(pool.query_reserve_discrepancy(), (100, 0));
+
+    assert_eq!(pool.skim(&recipient), (100, 0));
+    assert_eq!(token1.balance(&recipient), 100);
+    assert_eq!(token1.balance(&pool.address), 10_000);
+    assert_eq!(pool.query_reserve_discrepancy(), (0, 0));
+}
+
+#[test]
+fn sync_adds_tokens_sent_to_the_pool_directly_to_the_reserves() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let pool = deploy_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+    );
+
+    token1.mint(&user, &10_000);
+    token2.mint(&user, &10_000);
+    pool.provide_liquidity(
+        &user,
+        &Some(10_000),
+        &None,
+        &Some(10_000),
+        &None,
+        &None,
+        &None::<u64>,
+    );
+
+    token2.mint(&pool.address, &100);
+    assert_eq!(pool.query_reserve_discrepancy(), (0, 100));
+
+    pool.sync();
+
+    assert_eq!(pool.query_reserve_discrepancy(), (0, 0));
+    let pool_info = pool.query_pool_info();
+    assert_eq!(pool_info.asset_a.amount, 10_000);
+    assert_eq!(pool_info.asset_b.amount, 10_100);
+    // nothing left to skim
+    assert_eq!(pool.skim(&user), (0, 0));
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #31)")]
+fn provide_liquidity_below_minimum_liquidity_on_empty_pool() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let mut token1 = deploy_token_contract(&env, &Address::generate(&env));
+    let mut token2 = deploy_token_contract(&env, &Address::generate(&env));
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let user = Address::generate(&env);
+
+    let pool = deploy_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+    );
+
+    // sqrt(1_000 * 1_000) shares would all be locked
+    token1.mint(&user, &1_000);
+    token2.mint(&user, &1_000);
+    pool.provide_liquidity(
+        &user,
+        &Some(1_000),
+        &None,
+        &Some(1_000),
+        &None,
+        &None,
+        &None::<u64>,
+    );
+}
+
+#[test]
+fn first_depositor_can_not_inflate_the_share_price() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let mut token1 = deploy_token_contract(&env, &Address::generate(&env));
+    let mut token2 = deploy_token_contract(&env, &Address::generate(&env));
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let attacker = Address::generate(&env);
+    let victim = Address::generate(&env);
+
+    let pool = deploy_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+    );
+    let share_token = token_contract::Client::new(&env, &pool.query_share_token_address());
+
+    let donation = 1_000_000;
+    token1.mint(&attacker, &(1_001 + donation + 1_000));
+    token2.mint(&attacker, &(1_001 + donation + 1_000));
+
+    // the smallest possible first deposit leaves a single share to the attacker
+    pool.provide_liquidity(
+        &attacker,
+        &Some(1_001),
+        &None,
+        &Some(1_001),
+        &None,
+        &None,
+        &None::<u64>,
+    );
+    assert_eq!(share_token.balance(&attacker), 1);
+    assert_eq!(share_token.balance(&pool.address), 1_000);
+
+    // donate to the pool and pull the donation into the reserves with tiny deposits, so the
+    // few existing shares are backed by all of it
+    token1.transfer(&attacker, &pool.address, &donation);
+    pool.provide_liquidity(
+        &attacker,
+        &Some(1),
+        &None,
+        &Some(1),
+        &None,
+        &None,
+        &None::<u64>,
+    );
+    token2.transfer(&attacker, &pool.address, &donation);
+    pool.provide_liquidity(
+        &attacker,
+        &Some(1_000),
+        &None,
+        &Some(1),
+        &None,
+        &None,
+        &None::<u64>,
+    );
+    assert_eq!(share_token.balance(&attacker), 2);
+    assert_eq!(pool.query_total_issued_lp(), 1_002);
+    let info = pool.query_pool_info();
+    assert_eq!(info.asset_a.amount, 1_002_001);
+    assert_eq!(info.asset_b.amount, 1_001_003);
+
+    // without the locked shares this deposit would be rounded down to no shares at all
+    token1.mint(&victim, &100_000);
+    token2.mint(&victim, &100_000);
+    pool.provide_liquidity(
+        &victim,
+        &Some(100_000),
+        &None,
+        &Some(100_000),
+        &None,
+        &None,
+        &None::<u64>,
+    );
+    let victim_shares = share_token.balance(&victim);
+    assert_eq!(victim_shares, 99);
+
+    // the victim loses less than one share worth to rounding
+    pool.withdraw_liquidity(&victim, &victim_shares, &1, &1, &None::<u64>);
+    assert_eq!(token1.balance(&victim), 99_090);
+    assert_eq!(token2.balance(&victim), 99_091);
+
+    // while the attacker lost almost the whole donation to the locked shares
+    let attacker_shares = share_token.balance(&attacker);
+    pool.withdraw_liquidity(&attacker, &attacker_shares, &1, &1, &None::<u64>);
+    assert_eq!(token1.balance(&attacker), 2_001);
+    assert_eq!(token2.balance(&attacker), 2_997);
+}
+
+#[test]
+fn simulate_provide_and_withdraw_liquidity() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let mut token1 = deploy_token_contract(&env, &Address::generate(&env));
+    let mut token2 = deploy_token_contract(&env, &Address::generate(&env));
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let user = Address::generate(&env);
+
+    let pool = deploy_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+    );
+    let share_token = token_contract::Client::new(&env, &pool.query_share_token_address());
+
+    token1.mint(&user, &2_000_000);
+    token2.mint(&user, &2_000_000);
+
+    // the first deposit keeps the locked shares to the pool
+    assert_eq!(
+        pool.simulate_provide_liquidity(&1_000_000, &500_000, &None),
+        SimulateProvideLiquidityResponse {
+            amount_a: 1_000_000,
+            amount_b: 500_000,
+            shares: 706_106,
+        }
+    );
+    pool.provide_liquidity(
+        &user,
+        &Some(1_000_000),
+        &None,
+        &Some(500_000),
+        &None,
+        &None,
+        &None::<u64>,
+    );
+    assert_eq!(share_token.balance(&user), 706_106);
+
+    // a later deposit is adjusted to the ratio of the pool, within the slippage tolerance
+    let simulated = pool.simulate_provide_liquidity(&200_000, &110_000, &Some(1_000));
+    assert_eq!(simulated.amount_a, 200_000);
+    assert_eq!(simulated.amount_b, 100_000);
+    pool.provide_liquidity(
+        &user,
+        &Some(200_000),
+        &None,
+        &Some(110_000),
+        &None,
+        &Some(1_000),
+        &None::<u64>,
+    );
+    assert_eq!(token1.balance(&user), 2_000_000 - 1_200_000);
+    assert_eq!(token2.balance(&user), 2_000_000 - 600_000);
+    assert_eq!(share_token.balance(&user), 706_106 + simulated.shares);
+
+    // the simulation doesn't change the pool
+    let simulated = pool.simulate_withdraw_liquidity(&300_000);
+    assert_eq!(pool.query_pool_info().asset_a.amount, 1_200_000);
+    assert_eq!(
+        pool.withdraw_liquidity(&user, &300_000, &1, &1, &None::<u64>),
+        simulated
+    );
 }
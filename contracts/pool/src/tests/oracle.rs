@@ -0,0 +1,221 @@
+extern crate std;
+use pretty_assertions::assert_eq;
+use soroban_sdk::{
+    testutils::{Address as _, Ledger},
+    Address, Env,
+};
+
+use super::setup::{deploy_liquidity_pool_contract, deploy_token_contract};
+use crate::storage::Referral;
+
+#[test]
+fn twap_follows_reserves_over_time() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let mut token1 = deploy_token_contract(&env, &Address::generate(&env));
+    let mut token2 = deploy_token_contract(&env, &Address::generate(&env));
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let user = Address::generate(&env);
+
+    let pool = deploy_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        5_000i64,
+        Address::generate(&env),
+        Address::generate(&env),
+    );
+
+    token1.mint(&user, &2_000_000);
+    token2.mint(&user, &2_000_000);
+
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+    pool.provide_liquidity(
+        &user,
+        &Some(1_000_000),
+        &None,
+        &Some(1_000_000),
+        &None,
+        &None,
+        &None::<u64>,
+    );
+
+    // after 10 minutes at a price of 1, move the reserves to 1_250_000 / 800_000
+    env.ledger().with_mut(|li| li.timestamp = 1_600);
+    pool.swap(
+        &user,
+        &None::<Referral>,
+        &token1.address,
+        &250_000,
+        &None,
+        &Some(5_000),
+        &None::<u64>,
+    );
+
+    env.ledger().with_mut(|li| li.timestamp = 2_200);
+
+    // the price of token1 is 0.64 token2 since the swap
+    assert_eq!(
+        pool.query_twap(&token1.address, &600),
+        640_000_000_000_000_000
+    );
+    assert_eq!(
+        pool.query_twap(&token2.address, &600),
+        1_562_500_000_000_000_000
+    );
+    // 600 seconds at 1 and 600 seconds at 0.64
+    assert_eq!(
+        pool.query_twap(&token1.address, &1_200),
+        820_000_000_000_000_000
+    );
+
+    // a trade in the current ledger does not move the average yet
+    pool.swap(
+        &user,
+        &None::<Referral>,
+        &token1.address,
+        &500_000,
+        &None,
+        &Some(5_000),
+        &None::<u64>,
+    );
+    assert_eq!(
+        pool.query_twap(&token1.address, &600),
+        640_000_000_000_000_000
+    );
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #25)")]
+fn twap_fails_when_window_exceeds_history() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let mut token1 = deploy_token_contract(&env, &Address::generate(&env));
+    let mut token2 = deploy_token_contract(&env, &Address::generate(&env));
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let user = Address::generate(&env);
+
+    let pool = deploy_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+    );
+
+    token1.mint(&user, &1_000_000);
+    token2.mint(&user, &1_000_000);
+
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+    pool.provide_liquidity(
+        &user,
+        &Some(1_000_000),
+        &None,
+        &Some(1_000_000),
+        &None,
+        &None,
+        &None::<u64>,
+    );
+
+    env.ledger().with_mut(|li| li.timestamp = 1_500);
+    pool.query_twap(&token1.address, &600);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #16)")]
+fn twap_fails_for_asset_not_in_pool() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let mut token1 = deploy_token_contract(&env, &Address::generate(&env));
+    let mut token2 = deploy_token_contract(&env, &Address::generate(&env));
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+
+    let pool = deploy_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+    );
+
+    pool.query_twap(&Address::generate(&env), &600);
+}
+
+#[test]
+fn extreme_price_does_not_block_withdrawals() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let mut token1 = deploy_token_contract(&env, &Address::generate(&env));
+    let mut token2 = deploy_token_contract(&env, &Address::generate(&env));
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let user = Address::generate(&env);
+
+    let pool = deploy_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+    );
+    let share_token = crate::token_contract::Client::new(&env, &pool.query_share_token_address());
+
+    // one token1 is worth 10^15 token2, e.g. a 7 decimals token paired with a cheap 18 decimals
+    // one, so the price times the elapsed seconds overflows an i128 within a few days
+    let amount_a = 100_000;
+    let amount_b = 100_000_000_000_000_000_000;
+    token1.mint(&user, &amount_a);
+    token2.mint(&user, &amount_b);
+
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+    pool.provide_liquidity(
+        &user,
+        &Some(amount_a),
+        &None,
+        &Some(amount_b),
+        &None,
+        &None,
+        &None::<u64>,
+    );
+
+    // a year of that price wraps the accumulators around
+    env.ledger()
+        .with_mut(|li| li.timestamp = 1_000 + 365 * 24 * 60 * 60);
+    pool.query_twap(&token1.address, &600);
+
+    let shares = share_token.balance(&user);
+    let (return_a, return_b) = pool.withdraw_liquidity(&user, &shares, &1, &1, &None::<u64>);
+    assert_eq!(token1.balance(&user), return_a);
+    assert_eq!(token2.balance(&user), return_b);
+    assert!(return_a > amount_a * 99 / 100);
+}
@@ -1,11 +1,15 @@
 extern crate std;
 use phoenix::utils::{LiquidityPoolInitInfo, StakeInitInfo, TokenInitInfo};
-use soroban_sdk::{testutils::Address as _, Address, Env, String};
+use soroban_sdk::{
+    testutils::{Address as _, Ledger},
+    vec, Address, Env, String,
+};
 
 use super::setup::{deploy_liquidity_pool_contract, deploy_token_contract};
 use crate::{
     stake_contract,
     storage::{Config, PairType},
+    token_contract,
 };
 
 use crate::contract::{LiquidityPool, LiquidityPoolClient};
@@ -60,6 +64,8 @@ fn confirm_stake_contract_deployment() {
             max_allowed_spread_bps: 200,
             max_referral_bps: 5_000,
             default_slippage_bps: 100i64,
+            protocol_fee_share_bps: 10_000,
+            flash_loan_fee_bps: 0,
         }
     );
 
@@ -149,3 +155,127 @@ fn second_pool_deployment_should_fail() {
         &100i64,
     );
 }
+
+#[test]
+fn provide_liquidity_and_stake_bonds_the_minted_shares() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let mut token1 = deploy_token_contract(&env, &Address::generate(&env));
+    let mut token2 = deploy_token_contract(&env, &Address::generate(&env));
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let user = Address::generate(&env);
+
+    let pool = deploy_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+    );
+    let share_token = token_contract::Client::new(&env, &pool.query_share_token_address());
+    let stake = stake_contract::Client::new(&env, &pool.query_stake_contract_address());
+
+    token1.mint(&user, &10_000);
+    token2.mint(&user, &10_000);
+
+    env.ledger().with_mut(|li| li.timestamp = 100);
+    let bonded = pool.provide_liquidity_and_stake(
+        &user,
+        &Some(10_000),
+        &None,
+        &Some(10_000),
+        &None,
+        &None,
+        &None::<u64>,
+    );
+
+    assert_eq!(bonded, 9_000);
+    assert_eq!(share_token.balance(&user), 0);
+    assert_eq!(share_token.balance(&stake.address), 9_000);
+    assert_eq!(
+        stake.query_staked(&user),
+        stake_contract::StakedResponse {
+            stakes: vec![
+                &env,
+                stake_contract::Stake {
+                    stake: 9_000,
+                    stake_timestamp: 100,
+                }
+            ],
+            total_stake: 9_000,
+        }
+    );
+}
+
+#[test]
+fn unstake_and_withdraw_unbonds_and_burns_the_shares() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let mut token1 = deploy_token_contract(&env, &Address::generate(&env));
+    let mut token2 = deploy_token_contract(&env, &Address::generate(&env));
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let user = Address::generate(&env);
+
+    let pool = deploy_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+    );
+    let share_token = token_contract::Client::new(&env, &pool.query_share_token_address());
+    let stake = stake_contract::Client::new(&env, &pool.query_stake_contract_address());
+
+    token1.mint(&user, &15_000);
+    token2.mint(&user, &15_000);
+
+    env.ledger().with_mut(|li| li.timestamp = 100);
+    pool.provide_liquidity_and_stake(
+        &user,
+        &Some(10_000),
+        &None,
+        &Some(10_000),
+        &None,
+        &None,
+        &None::<u64>,
+    );
+    env.ledger().with_mut(|li| li.timestamp = 200);
+    pool.provide_liquidity_and_stake(
+        &user,
+        &Some(5_000),
+        &None,
+        &Some(5_000),
+        &None,
+        &None,
+        &None::<u64>,
+    );
+
+    // unbond the second stake only
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+    let withdrawn = pool.unstake_and_withdraw(&user, &5_000, &200, &4_999, &4_999, &None::<u64>);
+
+    // a third of the reserves, rounded down
+    assert_eq!(withdrawn, (4_999, 4_999));
+    assert_eq!(token1.balance(&user), 4_999);
+    assert_eq!(token2.balance(&user), 4_999);
+    assert_eq!(share_token.balance(&user), 0);
+    assert_eq!(share_token.balance(&stake.address), 9_000);
+    assert_eq!(pool.query_total_issued_lp(), 10_000);
+    assert_eq!(stake.query_staked(&user).total_stake, 9_000);
+}
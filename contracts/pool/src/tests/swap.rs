@@ -8,7 +8,13 @@ use soroban_sdk::{
 use test_case::test_case;
 
 use super::setup::{deploy_liquidity_pool_contract, deploy_token_contract};
-use crate::storage::{Asset, PoolResponse, SimulateReverseSwapResponse, SimulateSwapResponse};
+use crate::{
+    storage::{
+        Asset, PoolResponse, Referral, SimulateReverseSwapResponse, SimulateSwapResponse,
+        SwapResponse,
+    },
+    token_contract,
+};
 use soroban_decimal::Decimal;
 
 #[test]
@@ -59,8 +65,7 @@ fn simple_swap() {
     let spread = 100i64; // 1% maximum spread allowed
     pool.swap(
         &user1,
-        // FIXM: Disable Referral struct
-        // &None::<Referral>,
+        &None::<Referral>,
         &token1.address,
         &1,
         &None,
@@ -77,8 +82,7 @@ fn simple_swap() {
                     symbol_short!("swap"),
                     (
                         &user1,
-                        // FIXM: Disable Referral struct
-                        // None::<Referral>,
+                        None::<Referral>,
                         token1.address.clone(),
                         1_i128,
                         None::<i64>,
@@ -125,16 +129,17 @@ fn simple_swap() {
     assert_eq!(token2.balance(&user1), 1001); // 1 from the swap
 
     // this time 100 units
-    let output_amount = pool.swap(
-        &user1,
-        // FIXM: Disable Referral struct
-        // &None::<Referral>,
-        &token2.address,
-        &1_000,
-        &None,
-        &Some(spread),
-        &None::<u64>,
-    );
+    let output_amount = pool
+        .swap(
+            &user1,
+            &None::<Referral>,
+            &token2.address,
+            &1_000,
+            &None,
+            &Some(spread),
+            &None::<u64>,
+        )
+        .ask_amount;
     let result = pool.query_pool_info();
     assert_eq!(
         result,
@@ -159,8 +164,6 @@ fn simple_swap() {
     assert_eq!(token2.balance(&user1), 1001 - 1000); // user1 sold 1k of token B on second swap
 }
 
-// FIXM: Disable Referral struct
-#[ignore]
 #[test]
 fn simple_swap_with_referral_fee() {
     let env = Env::default();
@@ -210,15 +213,14 @@ fn simple_swap_with_referral_fee() {
     let spread = 100i64; // 1% maximum spread allowed
 
     // selling with 10% fee for the big guy
-    // FIXM: Disable Referral struct
-    // let referral = Referral {
-    //     address: referral_addr.clone(),
-    //     fee: 1_000,
-    // };
+    let referral = Referral {
+        address: referral_addr.clone(),
+        fee_bps: 1_000,
+    };
 
     pool.swap(
         &user1,
-        //     &Some(referral.clone()),
+        &Some(referral.clone()),
         &token1.address,
         &1,
         &None,
@@ -252,16 +254,25 @@ fn simple_swap_with_referral_fee() {
 
     assert_eq!(token1.balance(&user1), 999); // -1 from the swap
     assert_eq!(token2.balance(&user1), 1001); // 1 from the swap
-    let output_amount = pool.swap(
+    let response = pool.swap(
         &user1,
-        // FIXM: Disable Referral struct
-        // &Some(referral),
+        &Some(referral),
         &token2.address,
         &1_000,
         &None,
         &Some(spread),
         &None::<u64>,
     );
+    assert_eq!(
+        response,
+        SwapResponse {
+            ask_amount: 900,
+            commission_amount: 0,
+            protocol_fee_amount: 0,
+            referral_fee_amount: 100,
+            spread_amount: 0,
+        }
+    );
     let result = pool.query_pool_info();
     assert_eq!(
         result,
@@ -281,17 +292,13 @@ fn simple_swap_with_referral_fee() {
             stake_address: result.clone().stake_address,
         }
     );
-    assert_eq!(output_amount, 900);
-    assert_eq!(token1.balance(&user1), 1899); // 999 + 1_000 as a result of swap
-                                              // FIXM: Disable Referral struct
-                                              // assert_eq!(token1.balance(&referral_addr), 100);
+    assert_eq!(token1.balance(&user1), 1899); // 999 + 900 as a result of swap
+    assert_eq!(token1.balance(&referral_addr), 100);
     assert_eq!(token2.balance(&user1), 1001 - 1000); // user1 sold 1k of token B on second swap
 }
 
-// FIXM: Disable Referral struct
-#[ignore]
 #[test]
-#[should_panic(expected = "Pool: Swap: Trying to swap with more than the allowed referral fee")]
+#[should_panic(expected = "HostError: Error(Contract, #24)")]
 fn test_swap_should_fail_when_referral_fee_is_larger_than_allowed() {
     let env = Env::default();
     env.mock_all_auths();
@@ -336,17 +343,16 @@ fn test_swap_should_fail_when_referral_fee_is_larger_than_allowed() {
 
     let spread = 100i64; // 1% maximum spread allowed
 
-    // FIXM: Disable Referral struct
-    // let referral = Referral {
-    //     address: Address::random(&env),
-    //     // in tests/setup.rs we hardcoded the max referral fee
-    //     // to 5_000 bps (50%), here we try to set it to 10_000 bps (100%)
-    //     fee: 10_000,
-    // };
+    let referral = Referral {
+        address: Address::generate(&env),
+        // in tests/setup.rs we hardcoded the max referral fee
+        // to 5_000 bps (50%), here we try to set it to 10_000 bps (100%)
+        fee_bps: 10_000,
+    };
 
     pool.swap(
         &user1,
-        //     &Some(referral),
+        &Some(referral),
         &token1.address,
         &1,
         &None,
@@ -400,8 +406,15 @@ fn swap_should_panic_with_bad_max_spread() {
     );
 
     // selling just one token with 1% max spread allowed and 50 bps max spread
-    // FIXM: Disable Referral struct
-    pool.swap(&user1, &token1.address, &50, &None, &Some(50), &None::<u64>);
+    pool.swap(
+        &user1,
+        &None,
+        &token1.address,
+        &50,
+        &None,
+        &Some(50),
+        &None::<u64>,
+    );
 }
 
 #[test]
@@ -456,8 +469,7 @@ fn swap_with_high_fee() {
     // let's swap 100_000 units of Token 1 in 1:1 pool with 10% protocol fee
     pool.swap(
         &user1,
-        // FIXM: Disable Referral struct
-        // &None,
+        &None,
         &token1.address,
         &100_000,
         &None,
@@ -495,6 +507,99 @@ fn swap_with_high_fee() {
     assert_eq!(token2.balance(&fee_recipient), fees);
 }
 
+#[test]
+fn swap_splits_commission_between_protocol_and_liquidity_providers() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let mut token1 = deploy_token_contract(&env, &Address::generate(&env));
+    let mut token2 = deploy_token_contract(&env, &Address::generate(&env));
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let user1 = Address::generate(&env);
+
+    let swap_fees = 1_000i64; // 10% bps
+    let fee_recipient = Address::generate(&env);
+    let pool = deploy_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        swap_fees,
+        fee_recipient.clone(),
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+    );
+    // 40% of the commission goes to the fee recipient, 60% stays in the pool
+    pool.update_config(
+        &None,
+        &None,
+        &None,
+        &None,
+        &None,
+        &None,
+        &Some(4_000i64),
+        &None,
+    );
+
+    let initial_liquidity = 1_000_000i128;
+    token1.mint(&user1, &(initial_liquidity + 100_000));
+    token2.mint(&user1, &initial_liquidity);
+    pool.provide_liquidity(
+        &user1,
+        &Some(initial_liquidity),
+        &Some(initial_liquidity),
+        &Some(initial_liquidity),
+        &Some(initial_liquidity),
+        &None,
+        &None::<u64>,
+    );
+
+    let simulation = pool.simulate_swap(&token1.address, &100_000);
+    // same swap as in `swap_with_high_fee`
+    let output_amount = 90_910i128;
+    let fees = Decimal::percent(10) * output_amount;
+    assert_eq!(simulation.commission_amount, fees);
+    assert_eq!(simulation.protocol_fee_amount, fees * 4 / 10);
+
+    pool.swap(
+        &user1,
+        &None,
+        &token1.address,
+        &100_000,
+        &None,
+        &Some(1_000),
+        &None::<u64>,
+    );
+
+    assert_eq!(token2.balance(&user1), output_amount - fees);
+    assert_eq!(
+        token2.balance(&fee_recipient),
+        simulation.protocol_fee_amount
+    );
+    // the liquidity providers' part of the commission stays in the reserves
+    let lp_fee_amount = fees - simulation.protocol_fee_amount;
+    let result = pool.query_pool_info();
+    assert_eq!(
+        result.asset_b.amount,
+        initial_liquidity - output_amount + lp_fee_amount
+    );
+    assert_eq!(token2.balance(&pool.address), result.asset_b.amount);
+
+    // and is paid out when withdrawing, except for the part owned by the locked shares
+    let share_token = token_contract::Client::new(&env, &pool.query_share_token_address());
+    let shares = share_token.balance(&user1);
+    pool.withdraw_liquidity(&user1, &shares, &1, &1, &None::<u64>);
+    assert_eq!(token2.balance(&pool.address), 915);
+    assert_eq!(
+        token2.balance(&user1),
+        initial_liquidity - fees + lp_fee_amount - 915
+    );
+}
+
 #[test]
 fn swap_simulation_even_pool() {
     let env = Env::default();
@@ -553,6 +658,7 @@ fn swap_simulation_even_pool() {
             // spread_amount: Decimal::from_ratio(100_000, 1_000_000) * output_amount, // since it's 10% of the pool
             spread_amount: 9090, // rounding error, one less then ^
             commission_amount: fees,
+            protocol_fee_amount: fees,
             total_return: offer_amount,
         }
     );
@@ -581,6 +687,7 @@ fn swap_simulation_even_pool() {
             spread_amount: 9090, // spread amount is basically 10%, since it's basically 10% of the
             // first token
             commission_amount: fees,
+            protocol_fee_amount: fees,
             total_return: offer_amount,
         }
     );
@@ -657,6 +764,7 @@ fn swap_simulation_one_third_pool() {
             ask_amount: output_amount - fees,
             spread_amount: Decimal::from_ratio(offer_amount, 1_000_000) * output_amount, // since it's 10% of the pool
             commission_amount: fees,
+            protocol_fee_amount: fees,
             total_return: 300_000,
         }
     );
@@ -687,6 +795,7 @@ fn swap_simulation_one_third_pool() {
             // spread_amount: Decimal::from_ratio(100_000i128, 3_000_000i128) * output_amount,
             spread_amount: 1074, // rounding error, one less then ^
             commission_amount: fees,
+            protocol_fee_amount: fees,
             total_return: 33_333,
         }
     );
@@ -765,6 +874,7 @@ fn test_swap_fee_variants(swap_fees: i64, commission_fee: i128) {
             ask_amount: output_amount - fees,
             spread_amount: 8979975,
             commission_amount: fees,
+            protocol_fee_amount: fees,
             total_return: 1000000000,
         }
     );
@@ -831,8 +941,7 @@ fn test_v_phx_vul_021_should_panic_when_max_spread_invalid_range(max_spread: Opt
 
     pool.swap(
         &user1,
-        // FIXM: Disable Referral struct
-        // &None::<Referral>,
+        &None::<Referral>,
         &token1.address,
         &1,
         &None,
@@ -874,8 +983,7 @@ fn test_v_phx_vul_017_should_panic_when_swapping_non_existing_token_in_pool() {
     // Swap fails because we provide incorrect token as offer token.
     pool.swap(
         &user1,
-        // FIXM: Disable Referral struct
-        // &None::<Referral>,
+        &None::<Referral>,
         &bad_token.address,
         &1,
         &None,
@@ -914,12 +1022,7 @@ fn test_v_phx_vul_017_should_panic_when_simulating_swap_for_non_existing_token_i
         Address::generate(&env),
     );
     // Simulate swap fails because we provide incorrect token as offer token.
-    pool.simulate_swap(
-        // FIXM: Disable Referral struct
-        // &None::<Referral>,
-        &bad_token.address,
-        &1,
-    );
+    pool.simulate_swap(&bad_token.address, &1);
 }
 
 #[test]
@@ -952,12 +1055,7 @@ fn test_v_phx_vul_017_should_panic_when_simulating_reverse_swap_for_non_existing
         Address::generate(&env),
     );
     // Simulate swap fails because we provide incorrect token as offer token.
-    pool.simulate_reverse_swap(
-        // FIXM: Disable Referral struct
-        // &None::<Referral>,
-        &bad_token.address,
-        &1,
-    );
+    pool.simulate_reverse_swap(&bad_token.address, &1);
 }
 
 #[test]
@@ -1001,6 +1099,7 @@ fn test_should_swap_with_valid_ask_asset_min_amount() {
 
     pool.swap(
         &user,
+        &None,
         &token1.address,
         &10,
         &Some(10),
@@ -1012,6 +1111,7 @@ fn test_should_swap_with_valid_ask_asset_min_amount() {
 
     pool.swap(
         &user,
+        &None,
         &token2.address,
         &5_000i128,
         &Some(4_900i128),
@@ -1067,6 +1167,7 @@ fn test_should_fail_when_invalid_ask_asset_min_amount() {
     let spread = 100i64; // 1% maximum spread allowed
     pool.swap(
         &user,
+        &None,
         &token1.address,
         &1,
         &Some(10),
@@ -1127,8 +1228,7 @@ fn simple_swap_with_deadline_success() {
     // we set the deadline to be at latest 100 and we execute swap at 99
     pool.swap(
         &user1,
-        // FIXM: Disable Referral struct
-        // &None::<Referral>,
+        &None::<Referral>,
         &token1.address,
         &1,
         &None,
@@ -1160,16 +1260,17 @@ fn simple_swap_with_deadline_success() {
     assert_eq!(token2.balance(&user1), 1001); // 1 from the swap
 
     env.ledger().with_mut(|li| li.timestamp = 149);
-    let output_amount = pool.swap(
-        &user1,
-        // FIXM: Disable Referral struct
-        // &None::<Referral>,
-        &token2.address,
-        &1_000,
-        &None,
-        &Some(spread),
-        &Some(150),
-    );
+    let output_amount = pool
+        .swap(
+            &user1,
+            &None::<Referral>,
+            &token2.address,
+            &1_000,
+            &None,
+            &Some(spread),
+            &Some(150),
+        )
+        .ask_amount;
     let result = pool.query_pool_info();
     assert_eq!(
         result,
@@ -1246,8 +1347,7 @@ fn simple_swap_with_should_fail_when_after_the_deadline() {
     // this will panic, because our deadline is before the current timestamp
     pool.swap(
         &user1,
-        // FIXM: Disable Referral struct
-        // &None::<Referral>,
+        &None::<Referral>,
         &token1.address,
         &1,
         &None,
@@ -0,0 +1,1500 @@
+use phoenix::utils::{ConcentratedPoolInitInfo, LiquidityPoolInitInfo};
+use soroban_sdk::{
+    contract, contractimpl, contractmeta, log, panic_with_error, Address, BytesN, Env, String,
+};
+
+use crate::{
+    error::ContractError,
+    math::{
+        amount_a_delta, amount_b_delta, fee_growth, fees_for_growth, liquidity_for_amounts,
+        quote_at_sqrt_price, sqrt_price_after_a_in, sqrt_price_after_a_out, sqrt_price_after_b_in,
+        sqrt_price_after_b_out, sqrt_price_at_tick, tick_at_sqrt_price, MAX_TICK, MIN_TICK,
+    },
+    storage::{
+        get_config, get_pool_state, save_config, save_pool_state, utils, Asset,
+        ConcentratedLiquidityPoolInfo, Config, PairType, PoolResponse, PoolState, Position,
+        PositionResponse, Referral, SimulateReverseSwapResponse, SimulateSwapResponse,
+        SwapResponse, TickInfo,
+    },
+    tick_bitmap::{flip_tick, next_initialized_tick},
+    token_contract,
+};
+use phoenix::{validate_bps, validate_int_parameters};
+use soroban_decimal::Decimal;
+
+// Positions must hold at least this much liquidity, so the ticks of a pool can not be flooded
+// with dust positions
+const MIN_POSITION_LIQUIDITY: u128 = 100_000;
+
+// Metadata that is added on to the WASM custom section
+contractmeta!(
+    key = "Description",
+    val = "Phoenix Protocol Concentrated Liquidity Pool"
+);
+
+#[contract]
+pub struct ConcentratedLiquidityPool;
+
+#[allow(dead_code)]
+pub trait ConcentratedLiquidityPoolTrait {
+    // Sets the tokens, the tick spacing and the starting price of this pool.
+    // The stake and token WASM hashes and the share token parameters are accepted so the factory
+    // can deploy every pool type the same way, but positions are not fungible and no share
+    // token nor stake contract is deployed
+    #[allow(clippy::too_many_arguments)]
+    fn initialize(
+        env: Env,
+        stake_wasm_hash: BytesN<32>,
+        token_wasm_hash: BytesN<32>,
+        lp_init_info: LiquidityPoolInitInfo,
+        factory_addr: Address,
+        share_token_decimals: u32,
+        share_token_name: String,
+        share_token_symbol: String,
+        init_info: ConcentratedPoolInitInfo,
+    );
+
+    // Adds liquidity to the position of `sender` between `tick_lower` and `tick_upper`.
+    // The liquidity is the largest one `desired_a` and `desired_b` can back at the current price,
+    // a range above the price only takes token A and a range below it only token B.
+    // Returns the liquidity added to the position
+    #[allow(clippy::too_many_arguments)]
+    fn provide_liquidity(
+        env: Env,
+        sender: Address,
+        tick_lower: i32,
+        tick_upper: i32,
+        desired_a: i128,
+        desired_b: i128,
+        min_a: i128,
+        min_b: i128,
+        deadline: Option<u64>,
+    ) -> u128;
+
+    // Removes `liquidity` from the position of `sender` between `tick_lower` and `tick_upper` and
+    // sends the tokens backing it to `sender`. The fees earned by the position stay owed to it
+    // until they are collected.
+    // Returns amount of both tokens withdrawn
+    #[allow(clippy::too_many_arguments)]
+    fn withdraw_liquidity(
+        env: Env,
+        sender: Address,
+        tick_lower: i32,
+        tick_upper: i32,
+        liquidity: u128,
+        min_a: i128,
+        min_b: i128,
+        deadline: Option<u64>,
+    ) -> (i128, i128);
+
+    // Sends all the fees earned by the position of `sender` between `tick_lower` and `tick_upper`
+    // to `sender`.
+    // Returns amount of both tokens collected
+    fn collect_fees(env: Env, sender: Address, tick_lower: i32, tick_upper: i32) -> (i128, i128);
+
+    // `offer_asset` is the asset that the user would like to swap for the other token in the pool.
+    // `offer_amount` is the amount being sold, with `max_spread_bps` being a safety to make sure you receive at least that amount.
+    // swap will transfer the selling token "to" to this contract, and then the contract will transfer the buying token to `sender`.
+    // The swap crosses as many ticks as needed and the commission of every part of it accrues to
+    // the positions in range at that price.
+    // An optional `referral` receives `fee_bps` of the bought amount, capped by the pool's `max_referral_bps`.
+    // Returns the amount of the token being bought with the commission, fees and spread of the swap.
+    #[allow(clippy::too_many_arguments)]
+    fn swap(
+        env: Env,
+        sender: Address,
+        referral: Option<Referral>,
+        offer_asset: Address,
+        offer_amount: i128,
+        ask_asset_min_amount: Option<i128>,
+        max_spread_bps: Option<i64>,
+        deadline: Option<u64>,
+    ) -> SwapResponse;
+
+    // Allows admin address set during initialization to change some parameters of the
+    // configuration
+    fn update_config(
+        env: Env,
+        sender: Address,
+        new_admin: Option<Address>,
+        total_fee_bps: Option<i64>,
+        fee_recipient: Option<Address>,
+        max_allowed_spread_bps: Option<i64>,
+        protocol_fee_share_bps: Option<i64>,
+    );
+
+    // Sets the guardian, an address allowed to pause the pool next to the admin and the factory
+    fn set_guardian(env: Env, guardian: Address);
+
+    // Pauses swaps and deposits. Withdrawals and fee collection stay available.
+    // Callable by the admin, the guardian or the factory
+    fn pause(env: Env, sender: Address);
+
+    // Resumes the pool. Callable by the admin or the factory
+    fn unpause(env: Env, sender: Address);
+
+    // Migration entrypoint
+    fn upgrade(e: Env, new_wasm_hash: BytesN<32>);
+
+    // QUERIES
+
+    // Returns the configuration structure containing the addresses
+    fn query_config(env: Env) -> Config;
+
+    // Returns the assets in the pool and the liquidity currently in range
+    fn query_pool_info(env: Env) -> PoolResponse;
+
+    fn query_pool_info_for_factory(env: Env) -> ConcentratedLiquidityPoolInfo;
+
+    // Returns the current price, tick, liquidity in range and fee growth of the pool
+    fn query_pool_state(env: Env) -> PoolState;
+
+    // Returns the liquidity of a position, what it is worth at the current price and the fees it
+    // has earned so far
+    fn query_position(
+        env: Env,
+        owner: Address,
+        tick_lower: i32,
+        tick_upper: i32,
+    ) -> PositionResponse;
+
+    // Simulate swap transaction
+    fn simulate_swap(env: Env, offer_asset: Address, offer_amount: i128) -> SimulateSwapResponse;
+
+    // Simulate reverse swap transaction
+    fn simulate_reverse_swap(
+        env: Env,
+        ask_asset: Address,
+        ask_amount: i128,
+    ) -> SimulateReverseSwapResponse;
+
+    fn query_paused(env: Env) -> bool;
+
+    fn query_guardian(env: Env) -> Option<Address>;
+}
+
+#[contractimpl]
+impl ConcentratedLiquidityPoolTrait for ConcentratedLiquidityPool {
+    #[allow(clippy::too_many_arguments)]
+    fn initialize(
+        env: Env,
+        _stake_wasm_hash: BytesN<32>,
+        _token_wasm_hash: BytesN<32>,
+        lp_init_info: LiquidityPoolInitInfo,
+        factory_addr: Address,
+        _share_token_decimals: u32,
+        _share_token_name: String,
+        _share_token_symbol: String,
+        init_info: ConcentratedPoolInitInfo,
+    ) {
+        if utils::is_initialized(&env) {
+            log!(
+                &env,
+                "Pool Concentrated: Initialize: initializing contract twice is not allowed"
+            );
+            panic_with_error!(&env, ContractError::AlreadyInitialized);
+        }
+
+        let admin = lp_init_info.admin;
+        let swap_fee_bps = lp_init_info.swap_fee_bps;
+        let fee_recipient = lp_init_info.fee_recipient;
+        let max_allowed_spread_bps = lp_init_info.max_allowed_spread_bps;
+        let max_referral_bps = lp_init_info.max_referral_bps;
+        let token_init_info = lp_init_info.token_init_info;
+
+        validate_bps!(swap_fee_bps, max_allowed_spread_bps, max_referral_bps);
+        utils::set_initialized(&env);
+
+        // Token info
+        let token_a = token_init_info.token_a;
+        let token_b = token_init_info.token_b;
+
+        // Token order validation to make sure only one instance of a pool can exist
+        if token_a >= token_b {
+            log!(
+                &env,
+                "Pool Concentrated: Initialize: First token must be alphabetically smaller than second token"
+            );
+            panic_with_error!(&env, ContractError::TokenABiggerThanTokenB);
+        }
+
+        if init_info.tick_spacing == 0 || init_info.tick_spacing > MAX_TICK as u32 {
+            log!(&env, "Pool Concentrated: Initialize: Invalid tick spacing");
+            panic_with_error!(&env, ContractError::InvalidTickSpacing);
+        }
+
+        if !(MIN_TICK..MAX_TICK).contains(&init_info.initial_tick) {
+            log!(
+                &env,
+                "Pool Concentrated: Initialize: Initial tick is out of bounds"
+            );
+            panic_with_error!(&env, ContractError::InvalidTickRange);
+        }
+
+        let config = Config {
+            token_a: token_a.clone(),
+            token_b: token_b.clone(),
+            pool_type: PairType::Concentrated,
+            total_fee_bps: swap_fee_bps,
+            fee_recipient,
+            max_allowed_spread_bps,
+            max_referral_bps,
+            // the whole commission accrues to the positions until the admin sets a share
+            protocol_fee_share_bps: 0,
+            tick_spacing: init_info.tick_spacing,
+        };
+        save_config(&env, config);
+        save_pool_state(
+            &env,
+            &PoolState {
+                sqrt_price: sqrt_price_at_tick(&env, init_info.initial_tick),
+                tick: init_info.initial_tick,
+                liquidity: 0,
+                fee_growth_global_a: 0,
+                fee_growth_global_b: 0,
+            },
+        );
+        utils::save_admin(&env, admin);
+        utils::save_factory(&env, factory_addr);
+        utils::save_pool_balance_a(&env, 0);
+        utils::save_pool_balance_b(&env, 0);
+
+        env.events()
+            .publish(("initialize", "Concentrated LP token_a"), token_a);
+        env.events()
+            .publish(("initialize", "Concentrated LP token_b"), token_b);
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn provide_liquidity(
+        env: Env,
+        sender: Address,
+        tick_lower: i32,
+        tick_upper: i32,
+        desired_a: i128,
+        desired_b: i128,
+        min_a: i128,
+        min_b: i128,
+        deadline: Option<u64>,
+    ) -> u128 {
+        ensure_not_paused(&env);
+
+        if let Some(deadline) = deadline {
+            if env.ledger().timestamp() > deadline {
+                log!(
+                    env,
+                    "Pool Concentrated: Provide Liquidity: Transaction executed after deadline!"
+                );
+                panic_with_error!(env, ContractError::TransactionAfterTimestampDeadline)
+            }
+        }
+
+        if desired_a < 0 || desired_b < 0 {
+            log!(
+                &env,
+                "Pool Concentrated: ProvideLiquidity: Desired amounts can not be negative"
+            );
+            panic_with_error!(&env, ContractError::NegativeAmount);
+        }
+
+        // sender needs to authorize the deposit
+        sender.require_auth();
+
+        let config = get_config(&env);
+        validate_ticks(&env, &config, tick_lower, tick_upper);
+
+        let mut state = get_pool_state(&env);
+        let liquidity = liquidity_for_amounts(
+            &env,
+            state.sqrt_price,
+            sqrt_price_at_tick(&env, tick_lower),
+            sqrt_price_at_tick(&env, tick_upper),
+            desired_a as u128,
+            desired_b as u128,
+        );
+        if liquidity == 0 {
+            log!(
+                &env,
+                "Pool Concentrated: ProvideLiquidity: The amounts do not back any liquidity in the range"
+            );
+            panic_with_error!(&env, ContractError::ZeroLiquidity);
+        }
+
+        let position_liquidity = utils::get_position(&env, &sender, tick_lower, tick_upper)
+            .map_or(0, |position| position.liquidity);
+        if position_liquidity + liquidity < MIN_POSITION_LIQUIDITY {
+            log!(
+                &env,
+                "Pool Concentrated: ProvideLiquidity: The position liquidity is below the minimum of {}",
+                MIN_POSITION_LIQUIDITY
+            );
+            panic_with_error!(&env, ContractError::PositionLiquidityBelowMinimum);
+        }
+
+        let (amount_a, amount_b) =
+            position_amounts(&env, &state, tick_lower, tick_upper, liquidity, true);
+        if amount_a < min_a || amount_b < min_b {
+            log!(
+                &env,
+                "Pool Concentrated: ProvideLiquidity: Minimum amount of token A or B is not satisfied"
+            );
+            panic_with_error!(
+                &env,
+                ContractError::ProvideLiquidityMinimumAmountOfAOrBIsNotSatisfied
+            );
+        }
+
+        modify_position(
+            &env,
+            &config,
+            &mut state,
+            &sender,
+            tick_lower,
+            tick_upper,
+            to_liquidity_delta(liquidity),
+        );
+
+        // Move tokens from client's wallet to the contract
+        if amount_a > 0 {
+            token_contract::Client::new(&env, &config.token_a).transfer(
+                &sender,
+                &env.current_contract_address(),
+                &amount_a,
+            );
+        }
+        if amount_b > 0 {
+            token_contract::Client::new(&env, &config.token_b).transfer(
+                &sender,
+                &env.current_contract_address(),
+                &amount_b,
+            );
+        }
+
+        utils::save_pool_balance_a(&env, utils::get_pool_balance_a(&env) + amount_a);
+        utils::save_pool_balance_b(&env, utils::get_pool_balance_b(&env) + amount_b);
+        save_pool_state(&env, &state);
+
+        env.events()
+            .publish(("provide_liquidity", "sender"), sender);
+        env.events()
+            .publish(("provide_liquidity", "tick_lower"), tick_lower);
+        env.events()
+            .publish(("provide_liquidity", "tick_upper"), tick_upper);
+        env.events()
+            .publish(("provide_liquidity", "liquidity"), liquidity);
+        env.events()
+            .publish(("provide_liquidity", "token_a-amount"), amount_a);
+        env.events()
+            .publish(("provide_liquidity", "token_b-amount"), amount_b);
+
+        liquidity
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn withdraw_liquidity(
+        env: Env,
+        sender: Address,
+        tick_lower: i32,
+        tick_upper: i32,
+        liquidity: u128,
+        min_a: i128,
+        min_b: i128,
+        deadline: Option<u64>,
+    ) -> (i128, i128) {
+        if let Some(deadline) = deadline {
+            if env.ledger().timestamp() > deadline {
+                log!(
+                    env,
+                    "Pool Concentrated: Withdraw Liquidity: Transaction executed after deadline!"
+                );
+                panic_with_error!(env, ContractError::TransactionAfterTimestampDeadline)
+            }
+        }
+
+        sender.require_auth();
+
+        let position = get_existing_position(&env, &sender, tick_lower, tick_upper);
+        if liquidity == 0 || liquidity > position.liquidity {
+            log!(
+                &env,
+                "Pool Concentrated: WithdrawLiquidity: The position does not have enough liquidity"
+            );
+            panic_with_error!(&env, ContractError::InsufficientPositionLiquidity);
+        }
+
+        // a position is either closed or keeps at least the minimum liquidity
+        let remaining_liquidity = position.liquidity - liquidity;
+        if remaining_liquidity != 0 && remaining_liquidity < MIN_POSITION_LIQUIDITY {
+            log!(
+                &env,
+                "Pool Concentrated: WithdrawLiquidity: The remaining position liquidity is below the minimum of {}",
+                MIN_POSITION_LIQUIDITY
+            );
+            panic_with_error!(&env, ContractError::PositionLiquidityBelowMinimum);
+        }
+
+        let config = get_config(&env);
+        let mut state = get_pool_state(&env);
+
+        let (amount_a, amount_b) =
+            position_amounts(&env, &state, tick_lower, tick_upper, liquidity, false);
+        if amount_a < min_a || amount_b < min_b {
+            log!(
+                &env,
+                "Pool Concentrated: WithdrawLiquidity: Minimum amount of token A or B is not satisfied"
+            );
+            panic_with_error!(
+                &env,
+                ContractError::WithdrawLiquidityMinimumAmountOfAOrBIsNotSatisfied
+            );
+        }
+
+        modify_position(
+            &env,
+            &config,
+            &mut state,
+            &sender,
+            tick_lower,
+            tick_upper,
+            -to_liquidity_delta(liquidity),
+        );
+
+        if amount_a > 0 {
+            token_contract::Client::new(&env, &config.token_a).transfer(
+                &env.current_contract_address(),
+                &sender,
+                &amount_a,
+            );
+        }
+        if amount_b > 0 {
+            token_contract::Client::new(&env, &config.token_b).transfer(
+                &env.current_contract_address(),
+                &sender,
+                &amount_b,
+            );
+        }
+
+        utils::save_pool_balance_a(&env, utils::get_pool_balance_a(&env) - amount_a);
+        utils::save_pool_balance_b(&env, utils::get_pool_balance_b(&env) - amount_b);
+        save_pool_state(&env, &state);
+
+        env.events()
+            .publish(("withdraw_liquidity", "sender"), sender);
+        env.events()
+            .publish(("withdraw_liquidity", "liquidity"), liquidity);
+        env.events()
+            .publish(("withdraw_liquidity", "return_amount_a"), amount_a);
+        env.events()
+            .publish(("withdraw_liquidity", "return_amount_b"), amount_b);
+
+        (amount_a, amount_b)
+    }
+
+    fn collect_fees(env: Env, sender: Address, tick_lower: i32, tick_upper: i32) -> (i128, i128) {
+        sender.require_auth();
+
+        get_existing_position(&env, &sender, tick_lower, tick_upper);
+
+        let config = get_config(&env);
+        let mut state = get_pool_state(&env);
+
+        // brings the fees owed to the position up to date before paying them out
+        let mut position = modify_position(
+            &env, &config, &mut state, &sender, tick_lower, tick_upper, 0,
+        );
+        let fees_a = position.fees_owed_a;
+        let fees_b = position.fees_owed_b;
+        position.fees_owed_a = 0;
+        position.fees_owed_b = 0;
+        save_or_remove_position(&env, &sender, tick_lower, tick_upper, &position);
+
+        if fees_a > 0 {
+            token_contract::Client::new(&env, &config.token_a).transfer(
+                &env.current_contract_address(),
+                &sender,
+                &fees_a,
+            );
+        }
+        if fees_b > 0 {
+            token_contract::Client::new(&env, &config.token_b).transfer(
+                &env.current_contract_address(),
+                &sender,
+                &fees_b,
+            );
+        }
+
+        utils::save_pool_balance_a(&env, utils::get_pool_balance_a(&env) - fees_a);
+        utils::save_pool_balance_b(&env, utils::get_pool_balance_b(&env) - fees_b);
+
+        env.events().publish(("collect_fees", "sender"), sender);
+        env.events().publish(("collect_fees", "amount_a"), fees_a);
+        env.events().publish(("collect_fees", "amount_b"), fees_b);
+
+        (fees_a, fees_b)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn swap(
+        env: Env,
+        sender: Address,
+        referral: Option<Referral>,
+        offer_asset: Address,
+        offer_amount: i128,
+        ask_asset_min_amount: Option<i128>,
+        max_spread_bps: Option<i64>,
+        deadline: Option<u64>,
+    ) -> SwapResponse {
+        ensure_not_paused(&env);
+
+        if let Some(deadline) = deadline {
+            if env.ledger().timestamp() > deadline {
+                log!(
+                    env,
+                    "Pool Concentrated: Swap: Transaction executed after deadline!"
+                );
+                panic_with_error!(env, ContractError::TransactionAfterTimestampDeadline)
+            }
+        }
+
+        validate_int_parameters!(offer_amount);
+
+        sender.require_auth();
+
+        do_swap(
+            env,
+            sender,
+            referral,
+            offer_asset,
+            offer_amount,
+            ask_asset_min_amount,
+            max_spread_bps,
+        )
+    }
+
+    fn update_config(
+        env: Env,
+        sender: Address,
+        new_admin: Option<Address>,
+        total_fee_bps: Option<i64>,
+        fee_recipient: Option<Address>,
+        max_allowed_spread_bps: Option<i64>,
+        protocol_fee_share_bps: Option<i64>,
+    ) {
+        if sender != utils::get_admin(&env) {
+            log!(&env, "Pool Concentrated: UpdateConfig: Unauthorized");
+            panic_with_error!(&env, ContractError::Unauthorized);
+        }
+        sender.require_auth();
+
+        let mut config = get_config(&env);
+
+        if let Some(new_admin) = new_admin {
+            utils::save_admin(&env, new_admin);
+        }
+        if let Some(total_fee_bps) = total_fee_bps {
+            if !(0..=10_000).contains(&total_fee_bps) {
+                log!(
+                    &env,
+                    "Pool Concentrated: UpdateConfig: Invalid total_fee_bps"
+                );
+                panic_with_error!(&env, ContractError::InvalidBps);
+            }
+            config.total_fee_bps = total_fee_bps;
+        }
+        if let Some(fee_recipient) = fee_recipient {
+            config.fee_recipient = fee_recipient;
+        }
+        if let Some(max_allowed_spread_bps) = max_allowed_spread_bps {
+            config.max_allowed_spread_bps = max_allowed_spread_bps;
+        }
+        if let Some(protocol_fee_share_bps) = protocol_fee_share_bps {
+            if !(0..=10_000).contains(&protocol_fee_share_bps) {
+                log!(
+                    &env,
+                    "Pool Concentrated: UpdateConfig: Invalid protocol_fee_share_bps"
+                );
+                panic_with_error!(&env, ContractError::InvalidBps);
+            }
+            config.protocol_fee_share_bps = protocol_fee_share_bps;
+        }
+
+        save_config(&env, config);
+    }
+
+    fn set_guardian(env: Env, guardian: Address) {
+        let admin: Address = utils::get_admin(&env);
+        admin.require_auth();
+
+        utils::save_guardian(&env, guardian.clone());
+
+        env.events().publish(("set_guardian", "guardian"), guardian);
+    }
+
+    fn pause(env: Env, sender: Address) {
+        sender.require_auth();
+
+        let is_guardian = utils::get_guardian(&env).map_or(false, |guardian| guardian == sender);
+        let is_factory = utils::get_factory(&env).map_or(false, |factory| factory == sender);
+        if sender != utils::get_admin(&env) && !is_factory && !is_guardian {
+            log!(
+                &env,
+                "Pool Concentrated: Pause: You are not authorized to pause the pool!"
+            );
+            panic_with_error!(&env, ContractError::Unauthorized);
+        }
+
+        utils::set_paused(&env, true);
+
+        env.events().publish(("pause", "sender"), sender);
+    }
+
+    fn unpause(env: Env, sender: Address) {
+        sender.require_auth();
+
+        let is_factory = utils::get_factory(&env).map_or(false, |factory| factory == sender);
+        if sender != utils::get_admin(&env) && !is_factory {
+            log!(
+                &env,
+                "Pool Concentrated: Unpause: You are not authorized to unpause the pool!"
+            );
+            panic_with_error!(&env, ContractError::Unauthorized);
+        }
+
+        utils::set_paused(&env, false);
+
+        env.events().publish(("unpause", "sender"), sender);
+    }
+
+    fn upgrade(env: Env, new_wasm_hash: BytesN<32>) {
+        let admin: Address = utils::get_admin(&env);
+        admin.require_auth();
+
+        env.deployer().update_current_contract_wasm(new_wasm_hash);
+    }
+
+    // Queries
+
+    fn query_config(env: Env) -> Config {
+        get_config(&env)
+    }
+
+    fn query_pool_info(env: Env) -> PoolResponse {
+        let config = get_config(&env);
+
+        PoolResponse {
+            asset_a: Asset {
+                address: config.token_a,
+                amount: utils::get_pool_balance_a(&env),
+            },
+            asset_b: Asset {
+                address: config.token_b,
+                amount: utils::get_pool_balance_b(&env),
+            },
+            asset_lp_share: Asset {
+                address: env.current_contract_address(),
+                amount: to_liquidity_delta(get_pool_state(&env).liquidity),
+            },
+            stake_address: env.current_contract_address(),
+        }
+    }
+
+    fn query_pool_info_for_factory(env: Env) -> ConcentratedLiquidityPoolInfo {
+        let total_fee_bps = get_config(&env).total_fee_bps;
+
+        ConcentratedLiquidityPoolInfo {
+            pool_address: env.current_contract_address(),
+            pool_response: Self::query_pool_info(env),
+            total_fee_bps,
+        }
+    }
+
+    fn query_pool_state(env: Env) -> PoolState {
+        get_pool_state(&env)
+    }
+
+    fn query_position(
+        env: Env,
+        owner: Address,
+        tick_lower: i32,
+        tick_upper: i32,
+    ) -> PositionResponse {
+        let position = get_existing_position(&env, &owner, tick_lower, tick_upper);
+        let state = get_pool_state(&env);
+
+        let (mut fees_owed_a, mut fees_owed_b) = (position.fees_owed_a, position.fees_owed_b);
+        if position.liquidity > 0 {
+            let (inside_a, inside_b) = fee_growth_inside(&env, &state, tick_lower, tick_upper);
+            fees_owed_a += fees_for_growth(
+                &env,
+                inside_a.wrapping_sub(position.fee_growth_inside_a),
+                position.liquidity,
+            ) as i128;
+            fees_owed_b += fees_for_growth(
+                &env,
+                inside_b.wrapping_sub(position.fee_growth_inside_b),
+                position.liquidity,
+            ) as i128;
+        }
+
+        let (amount_a, amount_b) = position_amounts(
+            &env,
+            &state,
+            tick_lower,
+            tick_upper,
+            position.liquidity,
+            false,
+        );
+
+        PositionResponse {
+            liquidity: position.liquidity,
+            amount_a,
+            amount_b,
+            fees_owed_a,
+            fees_owed_b,
+        }
+    }
+
+    fn simulate_swap(env: Env, offer_asset: Address, offer_amount: i128) -> SimulateSwapResponse {
+        let config = get_config(&env);
+
+        let a_to_b = if offer_asset == config.token_a {
+            true
+        } else if offer_asset == config.token_b {
+            false
+        } else {
+            log!(
+                &env,
+                "Pool Concentrated: Token offered to swap not found in Pool"
+            );
+            panic_with_error!(env, ContractError::AssetNotInPool);
+        };
+
+        let mut state = get_pool_state(&env);
+        let (ask_amount, spread_amount, commission_amount, protocol_fee_amount) =
+            compute_swap(&env, &config, &mut state, a_to_b, offer_amount, false);
+
+        let total_return = ask_amount + commission_amount + spread_amount;
+
+        SimulateSwapResponse {
+            ask_amount,
+            spread_amount,
+            commission_amount,
+            protocol_fee_amount,
+            total_return,
+        }
+    }
+
+    fn simulate_reverse_swap(
+        env: Env,
+        ask_asset: Address,
+        ask_amount: i128,
+    ) -> SimulateReverseSwapResponse {
+        let config = get_config(&env);
+
+        let a_to_b = if ask_asset == config.token_b {
+            true
+        } else if ask_asset == config.token_a {
+            false
+        } else {
+            log!(
+                &env,
+                "Pool Concentrated: Token offered to swap not found in Pool"
+            );
+            panic_with_error!(env, ContractError::AssetNotInPool);
+        };
+
+        let mut state = get_pool_state(&env);
+        let (offer_amount, spread_amount, commission_amount) =
+            compute_offer_amount(&env, &config, &mut state, a_to_b, ask_amount);
+
+        SimulateReverseSwapResponse {
+            offer_amount,
+            spread_amount,
+            commission_amount,
+        }
+    }
+
+    fn query_paused(env: Env) -> bool {
+        utils::is_paused(&env)
+    }
+
+    fn query_guardian(env: Env) -> Option<Address> {
+        utils::get_guardian(&env)
+    }
+}
+
+fn ensure_not_paused(env: &Env) {
+    if utils::is_paused(env) {
+        log!(env, "Pool Concentrated: The pool is paused!");
+        panic_with_error!(env, ContractError::ContractPaused);
+    }
+}
+
+fn validate_ticks(env: &Env, config: &Config, tick_lower: i32, tick_upper: i32) {
+    let tick_spacing = config.tick_spacing as i32;
+    if tick_lower >= tick_upper
+        || tick_lower < MIN_TICK
+        || tick_upper > MAX_TICK
+        || tick_lower % tick_spacing != 0
+        || tick_upper % tick_spacing != 0
+    {
+        log!(
+            env,
+            "Pool Concentrated: Position ticks must be ordered, in bounds and a multiple of the tick spacing"
+        );
+        panic_with_error!(env, ContractError::InvalidTickRange);
+    }
+}
+
+fn get_existing_position(env: &Env, owner: &Address, tick_lower: i32, tick_upper: i32) -> Position {
+    utils::get_position(env, owner, tick_lower, tick_upper).unwrap_or_else(|| {
+        log!(env, "Pool Concentrated: Position not found");
+        panic_with_error!(env, ContractError::PositionNotFound);
+    })
+}
+
+fn save_or_remove_position(
+    env: &Env,
+    owner: &Address,
+    tick_lower: i32,
+    tick_upper: i32,
+    position: &Position,
+) {
+    if position.liquidity == 0 && position.fees_owed_a == 0 && position.fees_owed_b == 0 {
+        utils::remove_position(env, owner, tick_lower, tick_upper);
+    } else {
+        utils::save_position(env, owner, tick_lower, tick_upper, position);
+    }
+}
+
+fn to_liquidity_delta(liquidity: u128) -> i128 {
+    i128::try_from(liquidity).expect("Pool Concentrated: liquidity does not fit in i128")
+}
+
+fn add_liquidity_delta(liquidity: u128, liquidity_delta: i128) -> u128 {
+    liquidity
+        .checked_add_signed(liquidity_delta)
+        .expect("Pool Concentrated: liquidity overflow")
+}
+
+/// Adds `liquidity_delta` to the position of `owner` between `tick_lower` and `tick_upper`,
+/// together with the ticks it starts and ends at and, when the position is in range, the
+/// liquidity of the pool. The fees earned by the position since its last update are added to
+/// its owed fees before its liquidity changes.
+///
+/// Saves the position and the ticks, the caller is responsible for saving the pool state.
+fn modify_position(
+    env: &Env,
+    config: &Config,
+    state: &mut PoolState,
+    owner: &Address,
+    tick_lower: i32,
+    tick_upper: i32,
+    liquidity_delta: i128,
+) -> Position {
+    let mut position = utils::get_position(env, owner, tick_lower, tick_upper).unwrap_or_default();
+
+    let (mut cleared_lower, mut cleared_upper) = (false, false);
+    if liquidity_delta != 0 {
+        cleared_lower = update_tick(env, config, state, tick_lower, liquidity_delta, false);
+        cleared_upper = update_tick(env, config, state, tick_upper, liquidity_delta, true);
+    }
+
+    if position.liquidity > 0 || liquidity_delta > 0 {
+        let (inside_a, inside_b) = fee_growth_inside(env, state, tick_lower, tick_upper);
+        position.fees_owed_a += fees_for_growth(
+            env,
+            inside_a.wrapping_sub(position.fee_growth_inside_a),
+            position.liquidity,
+        ) as i128;
+        position.fees_owed_b += fees_for_growth(
+            env,
+            inside_b.wrapping_sub(position.fee_growth_inside_b),
+            position.liquidity,
+        ) as i128;
+        position.fee_growth_inside_a = inside_a;
+        position.fee_growth_inside_b = inside_b;
+    }
+
+    position.liquidity = add_liquidity_delta(position.liquidity, liquidity_delta);
+    if liquidity_delta != 0 && tick_lower <= state.tick && state.tick < tick_upper {
+        state.liquidity = add_liquidity_delta(state.liquidity, liquidity_delta);
+    }
+
+    // ticks no position starts or ends at anymore are dropped only after the fees inside the
+    // range were read from them
+    if cleared_lower {
+        clear_tick(env, config, tick_lower);
+    }
+    if cleared_upper {
+        clear_tick(env, config, tick_upper);
+    }
+
+    save_or_remove_position(env, owner, tick_lower, tick_upper, &position);
+
+    position
+}
+
+/// Adds `liquidity_delta` to a position boundary. Returns true when no position starts or ends
+/// at the tick anymore.
+fn update_tick(
+    env: &Env,
+    config: &Config,
+    state: &PoolState,
+    tick: i32,
+    liquidity_delta: i128,
+    is_upper: bool,
+) -> bool {
+    let mut info = utils::get_tick_info(env, tick).unwrap_or_default();
+
+    if info.liquidity_gross == 0 {
+        // by convention all the fees so far were earned below the tick
+        if tick <= state.tick {
+            info.fee_growth_outside_a = state.fee_growth_global_a;
+            info.fee_growth_outside_b = state.fee_growth_global_b;
+        }
+
+        flip_tick(env, tick, config.tick_spacing);
+    }
+
+    info.liquidity_gross = add_liquidity_delta(info.liquidity_gross, liquidity_delta);
+    info.liquidity_net = if is_upper {
+        info.liquidity_net - liquidity_delta
+    } else {
+        info.liquidity_net + liquidity_delta
+    };
+    utils::save_tick_info(env, tick, &info);
+
+    info.liquidity_gross == 0
+}
+
+fn clear_tick(env: &Env, config: &Config, tick: i32) {
+    utils::remove_tick_info(env, tick);
+    flip_tick(env, tick, config.tick_spacing);
+}
+
+/// Fee growth of both tokens between `tick_lower` and `tick_upper`, from the global fee growth
+/// minus the growth below the lower tick and above the upper one.
+fn fee_growth_inside(
+    env: &Env,
+    state: &PoolState,
+    tick_lower: i32,
+    tick_upper: i32,
+) -> (u128, u128) {
+    let lower: TickInfo = utils::get_tick_info(env, tick_lower).unwrap_or_default();
+    let upper: TickInfo = utils::get_tick_info(env, tick_upper).unwrap_or_default();
+
+    let (below_a, below_b) = if state.tick >= tick_lower {
+        (lower.fee_growth_outside_a, lower.fee_growth_outside_b)
+    } else {
+        (
+            state
+                .fee_growth_global_a
+                .wrapping_sub(lower.fee_growth_outside_a),
+            state
+                .fee_growth_global_b
+                .wrapping_sub(lower.fee_growth_outside_b),
+        )
+    };
+    let (above_a, above_b) = if state.tick < tick_upper {
+        (upper.fee_growth_outside_a, upper.fee_growth_outside_b)
+    } else {
+        (
+            state
+                .fee_growth_global_a
+                .wrapping_sub(upper.fee_growth_outside_a),
+            state
+                .fee_growth_global_b
+                .wrapping_sub(upper.fee_growth_outside_b),
+        )
+    };
+
+    (
+        state
+            .fee_growth_global_a
+            .wrapping_sub(below_a)
+            .wrapping_sub(above_a),
+        state
+            .fee_growth_global_b
+            .wrapping_sub(below_b)
+            .wrapping_sub(above_b),
+    )
+}
+
+/// The amounts of token A and token B backing `liquidity` between `tick_lower` and `tick_upper`
+/// at the current price.
+fn position_amounts(
+    env: &Env,
+    state: &PoolState,
+    tick_lower: i32,
+    tick_upper: i32,
+    liquidity: u128,
+    round_up: bool,
+) -> (i128, i128) {
+    let sqrt_lower = sqrt_price_at_tick(env, tick_lower);
+    let sqrt_upper = sqrt_price_at_tick(env, tick_upper);
+
+    let (amount_a, amount_b) = if state.tick < tick_lower {
+        (
+            amount_a_delta(env, liquidity, sqrt_lower, sqrt_upper, round_up),
+            0,
+        )
+    } else if state.tick < tick_upper {
+        (
+            amount_a_delta(env, liquidity, state.sqrt_price, sqrt_upper, round_up),
+            amount_b_delta(env, liquidity, sqrt_lower, state.sqrt_price, round_up),
+        )
+    } else {
+        (
+            0,
+            amount_b_delta(env, liquidity, sqrt_lower, sqrt_upper, round_up),
+        )
+    };
+
+    (amount_a as i128, amount_b as i128)
+}
+
+/// Moves the price across `tick`, flipping the fee growth outside of it and adding or removing
+/// the liquidity of the positions starting or ending there.
+fn cross_tick(env: &Env, state: &mut PoolState, tick: i32, a_to_b: bool, commit: bool) {
+    let mut info = utils::get_tick_info(env, tick).unwrap_or_default();
+    info.fee_growth_outside_a = state
+        .fee_growth_global_a
+        .wrapping_sub(info.fee_growth_outside_a);
+    info.fee_growth_outside_b = state
+        .fee_growth_global_b
+        .wrapping_sub(info.fee_growth_outside_b);
+    if commit {
+        utils::save_tick_info(env, tick, &info);
+    }
+
+    if a_to_b {
+        state.liquidity = add_liquidity_delta(state.liquidity, -info.liquidity_net);
+        state.tick = tick - 1;
+    } else {
+        state.liquidity = add_liquidity_delta(state.liquidity, info.liquidity_net);
+        state.tick = tick;
+    }
+}
+
+fn do_swap(
+    env: Env,
+    sender: Address,
+    referral: Option<Referral>,
+    offer_asset: Address,
+    offer_amount: i128,
+    ask_asset_min_amount: Option<i128>,
+    max_spread: Option<i64>,
+) -> SwapResponse {
+    let config = get_config(&env);
+
+    let (a_to_b, sell_token, buy_token) = if offer_asset == config.token_a {
+        (true, config.token_a.clone(), config.token_b.clone())
+    } else if offer_asset == config.token_b {
+        (false, config.token_b.clone(), config.token_a.clone())
+    } else {
+        log!(
+            &env,
+            "Pool Concentrated: do swap: Trying to swap wrong asset. Aborting.."
+        );
+        panic_with_error!(&env, ContractError::AssetNotInPool);
+    };
+
+    if let Some(referral) = &referral {
+        if !(0..=config.max_referral_bps).contains(&referral.fee_bps) {
+            log!(
+                &env,
+                "Pool Concentrated: Swap: Trying to swap with more than the allowed referral fee"
+            );
+            panic_with_error!(&env, ContractError::ReferralFeeTooHigh);
+        }
+    }
+
+    if let Some(max_spread) = max_spread {
+        if !(0..=config.max_allowed_spread_bps).contains(&max_spread) {
+            log!(
+                &env,
+                "Pool Concentrated: do swap: max spread is out of bounds"
+            );
+            panic_with_error!(&env, ContractError::InvalidBps);
+        }
+    }
+
+    let max_spread = Decimal::bps(max_spread.map_or_else(|| config.max_allowed_spread_bps, |x| x));
+
+    let mut state = get_pool_state(&env);
+    let (return_amount, spread_amount, commission_amount, protocol_fee_amount) =
+        compute_swap(&env, &config, &mut state, a_to_b, offer_amount, true);
+
+    // referral fee is taken from the return amount, the same way as the commission
+    let referral_fee_amount = match referral {
+        Some(ref referral) => return_amount * Decimal::bps(referral.fee_bps),
+        None => 0,
+    };
+    let return_amount = return_amount - referral_fee_amount;
+
+    if let Some(ask_asset_min_amount) = ask_asset_min_amount {
+        if ask_asset_min_amount > return_amount {
+            log!(
+                &env,
+                "Pool Concentrated: do_swap: Return amount is smaller then expected minimum amount"
+            );
+            panic_with_error!(&env, ContractError::SwapMinReceivedBiggerThanReturn);
+        }
+    }
+
+    assert_max_spread(
+        &env,
+        max_spread,
+        return_amount + commission_amount + referral_fee_amount,
+        spread_amount,
+    );
+
+    // transfer tokens to swap
+    token_contract::Client::new(&env, &sell_token).transfer(
+        &sender,
+        &env.current_contract_address(),
+        &offer_amount,
+    );
+
+    // return swapped tokens to user
+    token_contract::Client::new(&env, &buy_token).transfer(
+        &env.current_contract_address(),
+        &sender,
+        &return_amount,
+    );
+
+    // send the protocol share of the commission to fee recipient, the rest stays in the pool
+    if protocol_fee_amount > 0 {
+        token_contract::Client::new(&env, &buy_token).transfer(
+            &env.current_contract_address(),
+            &config.fee_recipient,
+            &protocol_fee_amount,
+        );
+    }
+
+    // send referral fee to the referral address
+    if let Some(Referral { address, .. }) = referral {
+        if referral_fee_amount > 0 {
+            token_contract::Client::new(&env, &buy_token).transfer(
+                &env.current_contract_address(),
+                &address,
+                &referral_fee_amount,
+            );
+        }
+    }
+
+    let pool_balance_a = utils::get_pool_balance_a(&env);
+    let pool_balance_b = utils::get_pool_balance_b(&env);
+    let paid_out = return_amount + protocol_fee_amount + referral_fee_amount;
+    if a_to_b {
+        utils::save_pool_balance_a(&env, pool_balance_a + offer_amount);
+        utils::save_pool_balance_b(&env, pool_balance_b - paid_out);
+    } else {
+        utils::save_pool_balance_a(&env, pool_balance_a - paid_out);
+        utils::save_pool_balance_b(&env, pool_balance_b + offer_amount);
+    }
+    save_pool_state(&env, &state);
+
+    env.events().publish(("swap", "sender"), sender);
+    env.events().publish(("swap", "sell_token"), sell_token);
+    env.events().publish(("swap", "offer_amount"), offer_amount);
+    env.events().publish(("swap", "buy_token"), buy_token);
+    env.events()
+        .publish(("swap", "return_amount"), return_amount);
+    env.events()
+        .publish(("swap", "spread_amount"), spread_amount);
+    env.events()
+        .publish(("swap", "referral_fee_amount"), referral_fee_amount);
+    env.events()
+        .publish(("swap", "protocol_fee_amount"), protocol_fee_amount);
+    env.events().publish(
+        ("swap", "lp_fee_amount"),
+        commission_amount - protocol_fee_amount,
+    );
+    env.events().publish(("swap", "tick"), state.tick);
+
+    SwapResponse {
+        ask_amount: return_amount,
+        commission_amount,
+        protocol_fee_amount,
+        referral_fee_amount,
+        spread_amount,
+    }
+}
+
+/// This function asserts that the spread (slippage) does not exceed a given maximum.
+/// * `max_spread` - The maximum allowed spread (slippage) as a fraction of the return amount.
+/// * `return_amount` - The amount of tokens that the user receives in return.
+/// * `spread_amount` - The spread (slippage) amount, i.e., the difference between the expected and actual return.
+/// # Returns
+/// * An error if the spread exceeds the maximum allowed, otherwise Ok.
+pub fn assert_max_spread(env: &Env, max_spread: Decimal, return_amount: i128, spread_amount: i128) {
+    if return_amount == 0 {
+        return;
+    }
+
+    // Calculate the spread ratio, the fraction of the return that is due to spread
+    let spread_ratio = Decimal::from_ratio(spread_amount, return_amount);
+
+    if spread_ratio > max_spread {
+        log!(env, "Pool Concentrated: Spread exceeds maximum allowed");
+        panic_with_error!(env, ContractError::SpreadExceedsLimit);
+    }
+}
+
+/// Computes the result of a swap of `offer_amount`, moving `state` through the ticks to the price
+/// after the swap.
+///
+/// Every step between two ticks uses the liquidity in range there, and the commission on the
+/// amount bought in the step accrues to that liquidity. Tick crossings are only saved when
+/// `commit` is set, so the same computation serves the simulation.
+///
+/// Returns a tuple containing the following values:
+/// - The resulting amount of ask assets after the swap minus the commission amount.
+/// - The spread amount, representing the difference between the amount at the starting price
+///   and the actual swap amount.
+/// - The commission amount, representing the fees charged for the swap.
+/// - The part of the commission sent to the fee recipient.
+fn compute_swap(
+    env: &Env,
+    config: &Config,
+    state: &mut PoolState,
+    a_to_b: bool,
+    offer_amount: i128,
+    commit: bool,
+) -> (i128, i128, i128, i128) {
+    let start_sqrt_price = state.sqrt_price;
+    let commission_rate = config.protocol_fee_rate();
+    let protocol_fee_share = config.protocol_fee_share();
+
+    let mut remaining = offer_amount as u128;
+    let mut total_return = 0i128;
+    let mut commission_amount = 0i128;
+    let mut protocol_fee_amount = 0i128;
+
+    while remaining > 0 {
+        let next_tick = next_initialized_tick(env, state.tick, config.tick_spacing, a_to_b);
+        let target_tick = next_tick.unwrap_or(if a_to_b { MIN_TICK } else { MAX_TICK });
+        let target_sqrt_price = sqrt_price_at_tick(env, target_tick);
+
+        let mut step_return = 0u128;
+        let mut reached_target = true;
+        if state.liquidity > 0 {
+            let max_offer = if a_to_b {
+                amount_a_delta(
+                    env,
+                    state.liquidity,
+                    target_sqrt_price,
+                    state.sqrt_price,
+                    true,
+                )
+            } else {
+                amount_b_delta(
+                    env,
+                    state.liquidity,
+                    state.sqrt_price,
+                    target_sqrt_price,
+                    true,
+                )
+            };
+
+            if remaining >= max_offer {
+                remaining -= max_offer;
+                step_return = if a_to_b {
+                    amount_b_delta(
+                        env,
+                        state.liquidity,
+                        target_sqrt_price,
+                        state.sqrt_price,
+                        false,
+                    )
+                } else {
+                    amount_a_delta(
+                        env,
+                        state.liquidity,
+                        state.sqrt_price,
+                        target_sqrt_price,
+                        false,
+                    )
+                };
+            } else {
+                let sqrt_price = if a_to_b {
+                    sqrt_price_after_a_in(env, state.sqrt_price, state.liquidity, remaining)
+                } else {
+                    sqrt_price_after_b_in(env, state.sqrt_price, state.liquidity, remaining)
+                };
+                step_return = if a_to_b {
+                    amount_b_delta(env, state.liquidity, sqrt_price, state.sqrt_price, false)
+                } else {
+                    amount_a_delta(env, state.liquidity, state.sqrt_price, sqrt_price, false)
+                };
+                state.tick = if a_to_b {
+                    tick_at_sqrt_price(env, sqrt_price, target_tick, state.tick)
+                } else {
+                    tick_at_sqrt_price(env, sqrt_price, state.tick, target_tick - 1)
+                };
+                state.sqrt_price = sqrt_price;
+                remaining = 0;
+                reached_target = false;
+            }
+        }
+
+        if step_return > 0 {
+            let step_commission = step_return as i128 * commission_rate;
+            let step_protocol_fee = step_commission * protocol_fee_share;
+            let lp_fee_growth = fee_growth(
+                env,
+                (step_commission - step_protocol_fee) as u128,
+                state.liquidity,
+            );
+            if a_to_b {
+                state.fee_growth_global_b = state.fee_growth_global_b.wrapping_add(lp_fee_growth);
+            } else {
+                state.fee_growth_global_a = state.fee_growth_global_a.wrapping_add(lp_fee_growth);
+            }
+
+            total_return += step_return as i128;
+            commission_amount += step_commission;
+            protocol_fee_amount += step_protocol_fee;
+        }
+
+        if reached_target {
+            match next_tick {
+                Some(tick) => {
+                    state.sqrt_price = target_sqrt_price;
+                    cross_tick(env, state, tick, a_to_b, commit);
+                }
+                None if remaining > 0 => {
+                    log!(
+                        env,
+                        "Pool Concentrated: Swap: Not enough liquidity to fill the swap"
+                    );
+                    panic_with_error!(env, ContractError::NotEnoughLiquidity);
+                }
+                None => {
+                    state.sqrt_price = target_sqrt_price;
+                    state.tick = target_tick;
+                }
+            }
+        }
+    }
+
+    let expected_return =
+        quote_at_sqrt_price(env, start_sqrt_price, offer_amount as u128, a_to_b) as i128;
+    let spread_amount = (expected_return - total_return).max(0);
+
+    (
+        total_return - commission_amount,
+        spread_amount,
+        commission_amount,
+        protocol_fee_amount,
+    )
+}
+
+/// Returns the amount of offer assets needed to get `ask_amount` of ask assets after the
+/// commission, walking through the ticks the same way as `compute_swap`.
+///
+/// Returns a tuple containing the offer amount, the spread amount and the commission amount.
+fn compute_offer_amount(
+    env: &Env,
+    config: &Config,
+    state: &mut PoolState,
+    a_to_b: bool,
+    ask_amount: i128,
+) -> (i128, i128, i128) {
+    let start_sqrt_price = state.sqrt_price;
+    let commission_rate = config.protocol_fee_rate();
+
+    let one_minus_commission = Decimal::one() - commission_rate;
+    let inv_one_minus_commission = Decimal::one() / one_minus_commission;
+    let ask_before_commission = ask_amount * inv_one_minus_commission;
+
+    let mut remaining = ask_before_commission as u128;
+    let mut offer_amount = 0u128;
+
+    while remaining > 0 {
+        let next_tick = next_initialized_tick(env, state.tick, config.tick_spacing, a_to_b);
+        let target_tick = next_tick.unwrap_or(if a_to_b { MIN_TICK } else { MAX_TICK });
+        let target_sqrt_price = sqrt_price_at_tick(env, target_tick);
+
+        let mut reached_target = true;
+        if state.liquidity > 0 {
+            let max_return = if a_to_b {
+                amount_b_delta(
+                    env,
+                    state.liquidity,
+                    target_sqrt_price,
+                    state.sqrt_price,
+                    false,
+                )
+            } else {
+                amount_a_delta(
+                    env,
+                    state.liquidity,
+                    state.sqrt_price,
+                    target_sqrt_price,
+                    false,
+                )
+            };
+
+            if remaining >= max_return {
+                remaining -= max_return;
+                offer_amount += if a_to_b {
+                    amount_a_delta(
+                        env,
+                        state.liquidity,
+                        target_sqrt_price,
+                        state.sqrt_price,
+                        true,
+                    )
+                } else {
+                    amount_b_delta(
+                        env,
+                        state.liquidity,
+                        state.sqrt_price,
+                        target_sqrt_price,
+                        true,
+                    )
+                };
+            } else {
+                let sqrt_price = if a_to_b {
+                    sqrt_price_after_b_out(env, state.sqrt_price, state.liquidity, remaining)
+                } else {
+                    sqrt_price_after_a_out(env, state.sqrt_price, state.liquidity, remaining)
+                };
+                offer_amount += if a_to_b {
+                    amount_a_delta(env, state.liquidity, sqrt_price, state.sqrt_price, true)
+                } else {
+                    amount_b_delta(env, state.liquidity, state.sqrt_price, sqrt_price, true)
+                };
+                state.sqrt_price = sqrt_price;
+                remaining = 0;
+                reached_target = false;
+            }
+        }
+
+        if reached_target {
+            match next_tick {
+                Some(tick) => {
+                    state.sqrt_price = target_sqrt_price;
+                    cross_tick(env, state, tick, a_to_b, false);
+                }
+                None if remaining > 0 => {
+                    log!(
+                        env,
+                        "Pool Concentrated: Simulate Reverse Swap: Not enough liquidity to fill the swap"
+                    );
+                    panic_with_error!(env, ContractError::NotEnoughLiquidity);
+                }
+                None => state.sqrt_price = target_sqrt_price,
+            }
+        }
+    }
+
+    let offer_value = quote_at_sqrt_price(env, start_sqrt_price, offer_amount, a_to_b) as i128;
+    let spread_amount = (offer_value - ask_before_commission).max(0);
+    let commission_amount = ask_before_commission * commission_rate;
+
+    (offer_amount as i128, spread_amount, commission_amount)
+}
@@ -0,0 +1,27 @@
+use soroban_sdk::contracterror;
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum ContractError {
+    SpreadExceedsLimit = 1,
+    AlreadyInitialized = 2,
+    TokenABiggerThanTokenB = 3,
+    InvalidBps = 4,
+    Unauthorized = 5,
+    AssetNotInPool = 6,
+    SwapMinReceivedBiggerThanReturn = 7,
+    TransactionAfterTimestampDeadline = 8,
+    ReferralFeeTooHigh = 9,
+    ContractPaused = 10,
+    InvalidTickSpacing = 11,
+    InvalidTickRange = 12,
+    ZeroLiquidity = 13,
+    NotEnoughLiquidity = 14,
+    PositionNotFound = 15,
+    InsufficientPositionLiquidity = 16,
+    ProvideLiquidityMinimumAmountOfAOrBIsNotSatisfied = 17,
+    WithdrawLiquidityMinimumAmountOfAOrBIsNotSatisfied = 18,
+    NegativeAmount = 19,
+    PositionLiquidityBelowMinimum = 20,
+}
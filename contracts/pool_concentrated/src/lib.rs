@@ -0,0 +1,18 @@
+#![no_std]
+mod contract;
+mod error;
+mod math;
+mod storage;
+mod tick_bitmap;
+
+pub mod token_contract {
+    // The import will code generate:
+    // - A ContractClient type that can be used to invoke functions on the contract.
+    // - Any types in the contract that were annotated with #[contracttype].
+    soroban_sdk::contractimport!(
+        file = "../../target/wasm32-unknown-unknown/release/soroban_token_contract.wasm"
+    );
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,351 @@
+use soroban_sdk::{Env, U256};
+
+/// The lowest tick a position can start at. The price of token A in token B at a tick is
+/// 1.0001^tick, so the ticks cover prices from about 1e-12 to 1e12.
+pub const MIN_TICK: i32 = -276_324;
+/// The highest tick a position can end at.
+pub const MAX_TICK: i32 = 276_324;
+
+/// 1 / sqrt(1.0001)^(2^i) as Q128 numbers, one for every bit of the absolute tick up to MAX_TICK.
+const TICK_RATIOS: [u128; 19] = [
+    0xfffcb933bd6fad37aa2d162d1a594001,
+    0xfff97272373d413259a46990580e2139,
+    0xfff2e50f5f656932ef12357cf3c7fdcb,
+    0xffe5caca7e10e4e61c3624eaa0941ccf,
+    0xffcb9843d60f6159c9db58835c926643,
+    0xff973b41fa98c081472e6896dfb254bf,
+    0xff2ea16466c96a3843ec78b326b52860,
+    0xfe5dee046a99a2a811c461f1969c3052,
+    0xfcbe86c7900a88aedcffc83b479aa3a3,
+    0xf987a7253ac413176f2b074cf7815e53,
+    0xf3392b0822b70005940c7a398e4b70f2,
+    0xe7159475a2c29b7443b29c7fa6e889d8,
+    0xd097f3bdfd2022b8845ad8f792aa5825,
+    0xa9f746462d870fdf8a65dc1f90e061e4,
+    0x70d869a156d2a1b890bb3df62baf32f6,
+    0x31be135f97d08fd981231505542fcfa5,
+    0x9aa508b5b7a84e1c677de54f3e99bc8,
+    0x5d6af8dedb81196699c329225ee604,
+    0x2216e584f5fa1ea926041bedfe97,
+];
+
+fn to_u128(value: &U256) -> u128 {
+    value
+        .to_u128()
+        .expect("Pool Concentrated: math: conversion to u128 failed")
+}
+
+fn div_ceil(env: &Env, numerator: &U256, denominator: &U256) -> U256 {
+    let quotient = numerator.div(denominator);
+    if numerator.rem_euclid(denominator) > U256::from_u32(env, 0) {
+        quotient.add(&U256::from_u32(env, 1))
+    } else {
+        quotient
+    }
+}
+
+/// Square root of the price at `tick` (sqrt(1.0001^tick)) as a Q64.64 number.
+///
+/// The inverse of the price is multiplied together from one precomputed factor per bit of the
+/// absolute tick, and inverted back for positive ticks.
+pub fn sqrt_price_at_tick(env: &Env, tick: i32) -> u128 {
+    let abs_tick = tick.unsigned_abs();
+    let mut ratio = U256::from_u32(env, 1).shl(128);
+    for (bit, factor) in TICK_RATIOS.iter().enumerate() {
+        if abs_tick & (1 << bit) != 0 {
+            ratio = ratio.mul(&U256::from_u128(env, *factor)).shr(128);
+        }
+    }
+
+    let sqrt_price = if tick > 0 {
+        div_ceil(env, &U256::from_u32(env, 1).shl(192), &ratio)
+    } else {
+        ratio.shr(64)
+    };
+
+    to_u128(&sqrt_price)
+}
+
+/// The greatest tick between `lower` and `upper` (inclusive) whose square root price is not
+/// above `sqrt_price`. `sqrt_price` must not be below the square root price at `lower`.
+pub fn tick_at_sqrt_price(env: &Env, sqrt_price: u128, lower: i32, upper: i32) -> i32 {
+    let (mut lower, mut upper) = (lower, upper);
+    while lower < upper {
+        let middle = lower + (upper - lower + 1) / 2;
+        if sqrt_price_at_tick(env, middle) <= sqrt_price {
+            lower = middle;
+        } else {
+            upper = middle - 1;
+        }
+    }
+
+    lower
+}
+
+/// Amount of token A backing `liquidity` between two square root prices:
+/// liquidity * (sqrt_upper - sqrt_lower) / (sqrt_lower * sqrt_upper)
+pub fn amount_a_delta(
+    env: &Env,
+    liquidity: u128,
+    sqrt_lower: u128,
+    sqrt_upper: u128,
+    round_up: bool,
+) -> u128 {
+    let liquidity = U256::from_u128(env, liquidity).shl(64);
+    let sqrt_lower = U256::from_u128(env, sqrt_lower);
+    let sqrt_upper = U256::from_u128(env, sqrt_upper);
+
+    let (first, second) = if round_up {
+        (
+            div_ceil(env, &liquidity, &sqrt_lower),
+            liquidity.div(&sqrt_upper),
+        )
+    } else {
+        (
+            liquidity.div(&sqrt_lower),
+            div_ceil(env, &liquidity, &sqrt_upper),
+        )
+    };
+
+    if first <= second {
+        return 0;
+    }
+    to_u128(&first.sub(&second))
+}
+
+/// Amount of token B backing `liquidity` between two square root prices:
+/// liquidity * (sqrt_upper - sqrt_lower)
+pub fn amount_b_delta(
+    env: &Env,
+    liquidity: u128,
+    sqrt_lower: u128,
+    sqrt_upper: u128,
+    round_up: bool,
+) -> u128 {
+    let amount =
+        U256::from_u128(env, liquidity).mul(&U256::from_u128(env, sqrt_upper - sqrt_lower));
+    let q64 = U256::from_u32(env, 1).shl(64);
+
+    let amount = if round_up {
+        div_ceil(env, &amount, &q64)
+    } else {
+        amount.div(&q64)
+    };
+    to_u128(&amount)
+}
+
+/// Square root price after `amount` of token A is added to `liquidity`, which moves the price down.
+/// Rounded up, so the price never moves further than the amount pays for.
+pub fn sqrt_price_after_a_in(env: &Env, sqrt_price: u128, liquidity: u128, amount: u128) -> u128 {
+    let liquidity = U256::from_u128(env, liquidity).shl(64);
+    let denominator = liquidity
+        .div(&U256::from_u128(env, sqrt_price))
+        .add(&U256::from_u128(env, amount));
+
+    to_u128(&div_ceil(env, &liquidity, &denominator))
+}
+
+/// Square root price after `amount` of token B is added to `liquidity`, which moves the price up.
+/// Rounded down, so the price never moves further than the amount pays for.
+pub fn sqrt_price_after_b_in(env: &Env, sqrt_price: u128, liquidity: u128, amount: u128) -> u128 {
+    let step = U256::from_u128(env, amount)
+        .shl(64)
+        .div(&U256::from_u128(env, liquidity));
+
+    sqrt_price + to_u128(&step)
+}
+
+/// Square root price after `amount` of token A is taken out of `liquidity`, which moves the price
+/// up. Rounded up, so the amount paid in for it is never too small.
+pub fn sqrt_price_after_a_out(env: &Env, sqrt_price: u128, liquidity: u128, amount: u128) -> u128 {
+    let liquidity = U256::from_u128(env, liquidity).shl(64);
+    let denominator = liquidity
+        .div(&U256::from_u128(env, sqrt_price))
+        .sub(&U256::from_u128(env, amount));
+
+    to_u128(&div_ceil(env, &liquidity, &denominator))
+}
+
+/// Square root price after `amount` of token B is taken out of `liquidity`, which moves the price
+/// down. Rounded down, so the amount paid in for it is never too small.
+pub fn sqrt_price_after_b_out(env: &Env, sqrt_price: u128, liquidity: u128, amount: u128) -> u128 {
+    let step = div_ceil(
+        env,
+        &U256::from_u128(env, amount).shl(64),
+        &U256::from_u128(env, liquidity),
+    );
+
+    sqrt_price - to_u128(&step)
+}
+
+/// The largest liquidity between `sqrt_lower` and `sqrt_upper` that can be backed by `amount_a`
+/// and `amount_b` at the current `sqrt_price`.
+pub fn liquidity_for_amounts(
+    env: &Env,
+    sqrt_price: u128,
+    sqrt_lower: u128,
+    sqrt_upper: u128,
+    amount_a: u128,
+    amount_b: u128,
+) -> u128 {
+    if sqrt_price <= sqrt_lower {
+        liquidity_for_amount_a(env, sqrt_lower, sqrt_upper, amount_a)
+    } else if sqrt_price < sqrt_upper {
+        liquidity_for_amount_a(env, sqrt_price, sqrt_upper, amount_a).min(liquidity_for_amount_b(
+            env, sqrt_lower, sqrt_price, amount_b,
+        ))
+    } else {
+        liquidity_for_amount_b(env, sqrt_lower, sqrt_upper, amount_b)
+    }
+}
+
+fn liquidity_for_amount_a(env: &Env, sqrt_lower: u128, sqrt_upper: u128, amount: u128) -> u128 {
+    let intermediate = U256::from_u128(env, sqrt_lower)
+        .mul(&U256::from_u128(env, sqrt_upper))
+        .shr(64);
+
+    to_u128(
+        &U256::from_u128(env, amount)
+            .mul(&intermediate)
+            .div(&U256::from_u128(env, sqrt_upper - sqrt_lower)),
+    )
+}
+
+fn liquidity_for_amount_b(env: &Env, sqrt_lower: u128, sqrt_upper: u128, amount: u128) -> u128 {
+    to_u128(
+        &U256::from_u128(env, amount)
+            .shl(64)
+            .div(&U256::from_u128(env, sqrt_upper - sqrt_lower)),
+    )
+}
+
+/// Value of `amount` of the offered token in the other token at `sqrt_price`.
+pub fn quote_at_sqrt_price(env: &Env, sqrt_price: u128, amount: u128, a_to_b: bool) -> u128 {
+    let sqrt_price = U256::from_u128(env, sqrt_price);
+    let amount = U256::from_u128(env, amount);
+
+    let quote = if a_to_b {
+        amount.mul(&sqrt_price).shr(64).mul(&sqrt_price).shr(64)
+    } else {
+        amount.shl(64).div(&sqrt_price).shl(64).div(&sqrt_price)
+    };
+    to_u128(&quote)
+}
+
+/// Fee growth per unit of liquidity for `fee` earned by `liquidity`, as a Q64.64 number.
+/// Fee growth only matters as a difference between two readings, so it wraps around.
+pub fn fee_growth(env: &Env, fee: u128, liquidity: u128) -> u128 {
+    let growth = U256::from_u128(env, fee)
+        .shl(64)
+        .div(&U256::from_u128(env, liquidity))
+        .rem_euclid(&U256::from_u32(env, 1).shl(128));
+
+    to_u128(&growth)
+}
+
+/// Fees earned by `liquidity` for a fee growth of `growth`.
+pub fn fees_for_growth(env: &Env, growth: u128, liquidity: u128) -> u128 {
+    to_u128(
+        &U256::from_u128(env, growth)
+            .mul(&U256::from_u128(env, liquidity))
+            .shr(64),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+
+    const Q64: f64 = 18_446_744_073_709_551_616.0;
+
+    fn assert_sqrt_price(env: &Env, tick: i32) {
+        let expected = 1.0001f64.powf(tick as f64 / 2.0) * Q64;
+        let actual = sqrt_price_at_tick(env, tick) as f64;
+        assert!(
+            ((actual - expected) / expected).abs() < 1e-9,
+            "tick {tick}: {actual} != {expected}"
+        );
+    }
+
+    #[test]
+    fn sqrt_price_at_tick_matches_the_price_formula() {
+        let env = Env::default();
+        env.budget().reset_unlimited();
+
+        assert_eq!(sqrt_price_at_tick(&env, 0), 1 << 64);
+        for tick in [
+            1, -1, 10, -10, 887, -887, 46_054, -46_054, MAX_TICK, MIN_TICK,
+        ] {
+            assert_sqrt_price(&env, tick);
+        }
+    }
+
+    #[test]
+    fn tick_at_sqrt_price_is_the_inverse() {
+        let env = Env::default();
+        env.budget().reset_unlimited();
+
+        for tick in [-100_000, -61, 0, 1, 60, 100_000] {
+            let sqrt_price = sqrt_price_at_tick(&env, tick);
+            assert_eq!(
+                tick_at_sqrt_price(&env, sqrt_price, MIN_TICK, MAX_TICK),
+                tick
+            );
+            assert_eq!(
+                tick_at_sqrt_price(&env, sqrt_price + 1, MIN_TICK, MAX_TICK),
+                tick
+            );
+            assert_eq!(
+                tick_at_sqrt_price(&env, sqrt_price - 1, MIN_TICK, MAX_TICK),
+                tick - 1
+            );
+        }
+    }
+
+    #[test]
+    fn amounts_round_trip_through_liquidity() {
+        let env = Env::default();
+        env.budget().reset_unlimited();
+
+        let sqrt_lower = sqrt_price_at_tick(&env, -600);
+        let sqrt_upper = sqrt_price_at_tick(&env, 600);
+        let sqrt_price = sqrt_price_at_tick(&env, 0);
+
+        let liquidity = liquidity_for_amounts(
+            &env, sqrt_price, sqrt_lower, sqrt_upper, 1_000_000, 1_000_000,
+        );
+        let amount_a = amount_a_delta(&env, liquidity, sqrt_price, sqrt_upper, true);
+        let amount_b = amount_b_delta(&env, liquidity, sqrt_lower, sqrt_price, true);
+
+        // the range is symmetric around the price, so both tokens are used up
+        assert!((999_998..=1_000_000).contains(&amount_a));
+        assert!((999_998..=1_000_000).contains(&amount_b));
+    }
+
+    #[test]
+    fn swap_steps_never_pay_out_more_than_they_take() {
+        let env = Env::default();
+        env.budget().reset_unlimited();
+
+        let liquidity = 1_000_000_000u128;
+        let sqrt_price = sqrt_price_at_tick(&env, 0);
+
+        let after_a = sqrt_price_after_a_in(&env, sqrt_price, liquidity, 1_000);
+        assert!(after_a < sqrt_price);
+        let out_b = amount_b_delta(&env, liquidity, after_a, sqrt_price, false);
+        assert!(out_b < 1_000);
+
+        let after_b = sqrt_price_after_b_in(&env, sqrt_price, liquidity, 1_000);
+        assert!(after_b > sqrt_price);
+        let out_a = amount_a_delta(&env, liquidity, sqrt_price, after_b, false);
+        assert!(out_a < 1_000);
+
+        let before_b_out = sqrt_price_after_b_out(&env, sqrt_price, liquidity, 999);
+        assert!(amount_a_delta(&env, liquidity, before_b_out, sqrt_price, true) >= 999);
+
+        let before_a_out = sqrt_price_after_a_out(&env, sqrt_price, liquidity, 999);
+        assert!(amount_b_delta(&env, liquidity, sqrt_price, before_a_out, true) >= 999);
+    }
+}
@@ -0,0 +1,373 @@
+use soroban_sdk::{
+    contracttype, symbol_short, Address, ConversionError, Env, Map, Symbol, TryFromVal, Val,
+};
+
+use soroban_decimal::Decimal;
+
+#[derive(Clone, Copy)]
+#[repr(u32)]
+pub enum DataKey {
+    ReserveA = 0,
+    ReserveB = 1,
+    Admin = 2,
+    Initialized = 3,
+    PoolState = 4,
+    TickBitmap = 5,
+    TickInfo = 6,
+    Position = 7,
+    Paused = 8,
+    Guardian = 9,
+    Factory = 10,
+    TickBitmapIndex = 11,
+}
+
+impl TryFromVal<Env, DataKey> for Val {
+    type Error = ConversionError;
+
+    fn try_from_val(_env: &Env, v: &DataKey) -> Result<Self, Self::Error> {
+        Ok((*v as u32).into())
+    }
+}
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum PairType {
+    Xyk = 0,
+    Stable = 1,
+    Concentrated = 2,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Config {
+    pub token_a: Address,
+    pub token_b: Address,
+    pub pool_type: PairType,
+    /// The total fees (in bps) charged by a pool of this type.
+    /// In relation to the returned amount of tokens
+    pub total_fee_bps: i64,
+    pub fee_recipient: Address,
+    /// The maximum amount of spread (in bps) that is tolerated during swap
+    pub max_allowed_spread_bps: i64,
+    /// The maximum allowed percentage (in bps) for referral fee
+    pub max_referral_bps: i64,
+    /// The share (in bps) of the swap commission that is sent to the fee recipient.
+    /// The rest of the commission accrues to the positions in range during the swap
+    pub protocol_fee_share_bps: i64,
+    /// Only ticks that are a multiple of the spacing can be used as position boundaries
+    pub tick_spacing: u32,
+}
+const CONFIG: Symbol = symbol_short!("CONFIG");
+
+impl Config {
+    pub fn protocol_fee_rate(&self) -> Decimal {
+        Decimal::bps(self.total_fee_bps)
+    }
+
+    pub fn protocol_fee_share(&self) -> Decimal {
+        Decimal::bps(self.protocol_fee_share_bps)
+    }
+}
+
+pub fn get_config(env: &Env) -> Config {
+    env.storage().instance().get(&CONFIG).unwrap()
+}
+
+pub fn save_config(env: &Env, config: Config) {
+    env.storage().instance().set(&CONFIG, &config);
+}
+
+/// The price and the liquidity currently in range
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PoolState {
+    /// Square root of the price of token A in token B, as a Q64.64 number
+    pub sqrt_price: u128,
+    /// The greatest tick whose price is not above the current price
+    pub tick: i32,
+    /// The sum of the liquidity of all positions whose range contains the current tick
+    pub liquidity: u128,
+    /// The LP fees earned in token A per unit of liquidity since the pool was created, as a
+    /// Q64.64 number
+    pub fee_growth_global_a: u128,
+    /// The LP fees earned in token B per unit of liquidity since the pool was created, as a
+    /// Q64.64 number
+    pub fee_growth_global_b: u128,
+}
+
+pub fn get_pool_state(env: &Env) -> PoolState {
+    env.storage().instance().get(&DataKey::PoolState).unwrap()
+}
+
+pub fn save_pool_state(env: &Env, state: &PoolState) {
+    env.storage().instance().set(&DataKey::PoolState, state);
+}
+
+/// A tick used as the boundary of at least one position
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TickInfo {
+    /// The total liquidity of the positions starting or ending at this tick
+    pub liquidity_gross: u128,
+    /// The liquidity added to the pool when the price crosses this tick upwards
+    pub liquidity_net: i128,
+    /// Fee growth of token A on the other side of this tick from the current price
+    pub fee_growth_outside_a: u128,
+    /// Fee growth of token B on the other side of this tick from the current price
+    pub fee_growth_outside_b: u128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Position {
+    pub liquidity: u128,
+    /// Fee growth of token A inside the range at the last update of the position
+    pub fee_growth_inside_a: u128,
+    /// Fee growth of token B inside the range at the last update of the position
+    pub fee_growth_inside_b: u128,
+    /// Fees of token A earned by the position and not collected yet
+    pub fees_owed_a: i128,
+    /// Fees of token B earned by the position and not collected yet
+    pub fees_owed_b: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Asset {
+    /// Address of the asset
+    pub address: Address,
+    /// The total amount of those tokens in the pool
+    pub amount: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Referral {
+    /// Address of the referral
+    pub address: Address,
+    /// fee in bps, later parsed to percentage
+    pub fee_bps: i64,
+}
+
+/// This struct is used to return a query result with the liquidity and assets in a specific pool.
+/// Positions are not fungible, so there is no share token nor stake contract: `asset_lp_share`
+/// holds the pool address with the liquidity currently in range, and `stake_address` is the pool
+/// address, which keeps the layout the factory and multihop expect from every pool.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PoolResponse {
+    /// The asset A in the pool together with asset amounts
+    pub asset_a: Asset,
+    /// The asset B in the pool together with asset amounts
+    pub asset_b: Asset,
+    /// The liquidity currently in range
+    pub asset_lp_share: Asset,
+    /// The address of the pool itself
+    pub stake_address: Address,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConcentratedLiquidityPoolInfo {
+    pub pool_address: Address,
+    pub pool_response: PoolResponse,
+    pub total_fee_bps: i64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PositionResponse {
+    pub liquidity: u128,
+    /// The amount of token A the liquidity of the position is worth at the current price
+    pub amount_a: i128,
+    /// The amount of token B the liquidity of the position is worth at the current price
+    pub amount_b: i128,
+    /// Fees of token A earned by the position and not collected yet
+    pub fees_owed_a: i128,
+    /// Fees of token B earned by the position and not collected yet
+    pub fees_owed_b: i128,
+}
+
+/// The amounts of an executed swap, as returned by `swap`.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SwapResponse {
+    /// The amount sent to the sender, after commission and referral fee
+    pub ask_amount: i128,
+    pub commission_amount: i128,
+    /// The part of the commission sent to the fee recipient, the rest accrues to the positions
+    pub protocol_fee_amount: i128,
+    pub referral_fee_amount: i128,
+    pub spread_amount: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SimulateSwapResponse {
+    pub ask_amount: i128,
+    pub commission_amount: i128,
+    /// The part of the commission sent to the fee recipient, the rest accrues to the positions
+    pub protocol_fee_amount: i128,
+    pub spread_amount: i128,
+    pub total_return: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SimulateReverseSwapResponse {
+    pub offer_amount: i128,
+    pub commission_amount: i128,
+    pub spread_amount: i128,
+}
+
+pub mod utils {
+    use super::*;
+
+    pub fn save_admin(e: &Env, address: Address) {
+        e.storage().instance().set(&DataKey::Admin, &address)
+    }
+
+    pub fn save_factory(e: &Env, address: Address) {
+        e.storage().instance().set(&DataKey::Factory, &address)
+    }
+
+    pub fn save_guardian(e: &Env, address: Address) {
+        e.storage().instance().set(&DataKey::Guardian, &address)
+    }
+
+    pub fn set_paused(e: &Env, paused: bool) {
+        e.storage().instance().set(&DataKey::Paused, &paused)
+    }
+
+    pub fn save_pool_balance_a(e: &Env, amount: i128) {
+        e.storage().instance().set(&DataKey::ReserveA, &amount)
+    }
+
+    pub fn save_pool_balance_b(e: &Env, amount: i128) {
+        e.storage().instance().set(&DataKey::ReserveB, &amount)
+    }
+
+    // ticks and positions grow with the number of users, so they are kept in persistent
+    // storage instead of being loaded together with the instance on every call
+
+    /// One bit for each of 128 consecutive ticks divided by the tick spacing, set for the ticks
+    /// used as position boundaries, see `tick_bitmap`
+    pub fn get_tick_bitmap_word(e: &Env, word_pos: i32) -> u128 {
+        e.storage()
+            .persistent()
+            .get(&(DataKey::TickBitmap, word_pos))
+            .unwrap_or(0)
+    }
+
+    pub fn save_tick_bitmap_word(e: &Env, word_pos: i32, word: u128) {
+        if word == 0 {
+            e.storage()
+                .persistent()
+                .remove(&(DataKey::TickBitmap, word_pos))
+        } else {
+            e.storage()
+                .persistent()
+                .set(&(DataKey::TickBitmap, word_pos), &word)
+        }
+    }
+
+    /// One bit for each tick bitmap word with any tick set, keyed by the word position divided
+    /// by 128. The tick range bounds it to a few dozen entries whatever the number of positions
+    pub fn get_tick_bitmap_index(e: &Env) -> Map<i32, u128> {
+        e.storage()
+            .persistent()
+            .get(&DataKey::TickBitmapIndex)
+            .unwrap_or(Map::new(e))
+    }
+
+    pub fn save_tick_bitmap_index(e: &Env, index: &Map<i32, u128>) {
+        e.storage()
+            .persistent()
+            .set(&DataKey::TickBitmapIndex, index)
+    }
+
+    pub fn get_tick_info(e: &Env, tick: i32) -> Option<TickInfo> {
+        e.storage().persistent().get(&(DataKey::TickInfo, tick))
+    }
+
+    pub fn save_tick_info(e: &Env, tick: i32, info: &TickInfo) {
+        e.storage()
+            .persistent()
+            .set(&(DataKey::TickInfo, tick), info)
+    }
+
+    pub fn remove_tick_info(e: &Env, tick: i32) {
+        e.storage().persistent().remove(&(DataKey::TickInfo, tick))
+    }
+
+    pub fn get_position(
+        e: &Env,
+        owner: &Address,
+        tick_lower: i32,
+        tick_upper: i32,
+    ) -> Option<Position> {
+        e.storage()
+            .persistent()
+            .get(&(DataKey::Position, owner, tick_lower, tick_upper))
+    }
+
+    pub fn save_position(
+        e: &Env,
+        owner: &Address,
+        tick_lower: i32,
+        tick_upper: i32,
+        position: &Position,
+    ) {
+        e.storage().persistent().set(
+            &(DataKey::Position, owner, tick_lower, tick_upper),
+            position,
+        )
+    }
+
+    pub fn remove_position(e: &Env, owner: &Address, tick_lower: i32, tick_upper: i32) {
+        e.storage()
+            .persistent()
+            .remove(&(DataKey::Position, owner, tick_lower, tick_upper))
+    }
+
+    // queries
+    pub fn get_admin(e: &Env) -> Address {
+        e.storage().instance().get(&DataKey::Admin).unwrap()
+    }
+
+    /// `None` for pools upgraded from a version that didn't store the factory
+    pub fn get_factory(e: &Env) -> Option<Address> {
+        e.storage().instance().get(&DataKey::Factory)
+    }
+
+    pub fn get_guardian(e: &Env) -> Option<Address> {
+        e.storage().instance().get(&DataKey::Guardian)
+    }
+
+    pub fn is_paused(e: &Env) -> bool {
+        e.storage()
+            .instance()
+            .get(&DataKey::Paused)
+            .unwrap_or(false)
+    }
+
+    pub fn get_pool_balance_a(e: &Env) -> i128 {
+        e.storage().instance().get(&DataKey::ReserveA).unwrap()
+    }
+
+    pub fn get_pool_balance_b(e: &Env) -> i128 {
+        e.storage().instance().get(&DataKey::ReserveB).unwrap()
+    }
+
+    pub fn is_initialized(e: &Env) -> bool {
+        e.storage()
+            .persistent()
+            .get(&DataKey::Initialized)
+            .unwrap_or(false)
+    }
+
+    pub fn set_initialized(e: &Env) {
+        e.storage().persistent().set(&DataKey::Initialized, &true);
+    }
+}
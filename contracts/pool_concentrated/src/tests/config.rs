@@ -0,0 +1,234 @@
+use soroban_sdk::{testutils::Address as _, Address, BytesN, Env, String};
+
+use super::setup::{deploy_concentrated_liquidity_pool_contract, deploy_token_pair};
+use crate::storage::{Config, PairType};
+use phoenix::utils::{
+    ConcentratedPoolInitInfo, LiquidityPoolInitInfo, StakeInitInfo, TokenInitInfo,
+};
+
+#[test]
+fn update_config() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let fee_recipient = Address::generate(&env);
+    let (token1, token2) = deploy_token_pair(&env, &admin);
+    let pool = deploy_concentrated_liquidity_pool_contract(
+        &env,
+        admin.clone(),
+        (&token1.address, &token2.address),
+        30,
+        fee_recipient.clone(),
+        60,
+        -120,
+    );
+
+    assert_eq!(
+        pool.query_config(),
+        Config {
+            token_a: token1.address.clone(),
+            token_b: token2.address.clone(),
+            pool_type: PairType::Concentrated,
+            total_fee_bps: 30,
+            fee_recipient: fee_recipient.clone(),
+            max_allowed_spread_bps: 1_000,
+            max_referral_bps: 5_000,
+            protocol_fee_share_bps: 0,
+            tick_spacing: 60,
+        }
+    );
+    assert_eq!(pool.query_pool_state().tick, -120);
+
+    let new_fee_recipient = Address::generate(&env);
+    pool.update_config(
+        &admin,
+        &None,
+        &Some(100),
+        &Some(new_fee_recipient.clone()),
+        &Some(500),
+        &Some(2_000),
+    );
+
+    let config = pool.query_config();
+    assert_eq!(config.total_fee_bps, 100);
+    assert_eq!(config.fee_recipient, new_fee_recipient);
+    assert_eq!(config.max_allowed_spread_bps, 500);
+    assert_eq!(config.protocol_fee_share_bps, 2_000);
+}
+
+#[test]
+#[should_panic(expected = "Pool Concentrated: UpdateConfig: Unauthorized")]
+fn update_config_unauthorized() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let (token1, token2) = deploy_token_pair(&env, &admin);
+    let pool = deploy_concentrated_liquidity_pool_contract(
+        &env,
+        admin.clone(),
+        (&token1.address, &token2.address),
+        30,
+        None,
+        60,
+        0,
+    );
+
+    pool.update_config(
+        &Address::generate(&env),
+        &None,
+        &Some(100),
+        &None,
+        &None,
+        &None,
+    );
+}
+
+#[test]
+#[should_panic(expected = "Pool Concentrated: Initialize: Invalid tick spacing")]
+fn initialize_with_zero_tick_spacing_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let (token1, token2) = deploy_token_pair(&env, &admin);
+    deploy_concentrated_liquidity_pool_contract(
+        &env,
+        admin.clone(),
+        (&token1.address, &token2.address),
+        30,
+        None,
+        0,
+        0,
+    );
+}
+
+#[test]
+#[should_panic(
+    expected = "Pool Concentrated: Initialize: First token must be alphabetically smaller than second token"
+)]
+fn initialize_with_unordered_tokens_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let (token1, token2) = deploy_token_pair(&env, &admin);
+    deploy_concentrated_liquidity_pool_contract(
+        &env,
+        admin.clone(),
+        (&token2.address, &token1.address),
+        30,
+        None,
+        60,
+        0,
+    );
+}
+
+#[test]
+#[should_panic(
+    expected = "Pool Concentrated: Initialize: initializing contract twice is not allowed"
+)]
+fn initialize_twice_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let (token1, token2) = deploy_token_pair(&env, &admin);
+    let pool = deploy_concentrated_liquidity_pool_contract(
+        &env,
+        admin.clone(),
+        (&token1.address, &token2.address),
+        30,
+        None,
+        60,
+        0,
+    );
+
+    let lp_init_info = LiquidityPoolInitInfo {
+        admin: admin.clone(),
+        swap_fee_bps: 30,
+        fee_recipient: admin.clone(),
+        max_allowed_slippage_bps: 5_000,
+        max_allowed_spread_bps: 1_000,
+        max_referral_bps: 5_000,
+        token_init_info: TokenInitInfo {
+            token_a: token1.address.clone(),
+            token_b: token2.address.clone(),
+        },
+        stake_init_info: StakeInitInfo {
+            min_bond: 10i128,
+            min_reward: 5i128,
+            manager: admin.clone(),
+            max_complexity: 10u32,
+        },
+    };
+    pool.initialize(
+        &BytesN::from_array(&env, &[0u8; 32]),
+        &BytesN::from_array(&env, &[0u8; 32]),
+        &lp_init_info,
+        &admin,
+        &7,
+        &String::from_str(&env, "LP_SHARE_TOKEN"),
+        &String::from_str(&env, "PHOBTCLP"),
+        &ConcentratedPoolInitInfo {
+            tick_spacing: 60,
+            initial_tick: 0,
+        },
+    );
+}
+
+#[test]
+fn pause_blocks_swaps_but_not_withdrawals() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let guardian = Address::generate(&env);
+    let (token1, token2) = deploy_token_pair(&env, &admin);
+    let pool = deploy_concentrated_liquidity_pool_contract(
+        &env,
+        admin.clone(),
+        (&token1.address, &token2.address),
+        30,
+        None,
+        10,
+        0,
+    );
+
+    let user1 = Address::generate(&env);
+    token1.mint(&user1, &1_000_000);
+    token2.mint(&user1, &1_000_000);
+    let liquidity = pool.provide_liquidity(
+        &user1,
+        &-600,
+        &600,
+        &1_000_000,
+        &1_000_000,
+        &0,
+        &0,
+        &None::<u64>,
+    );
+
+    pool.set_guardian(&guardian);
+    pool.pause(&guardian);
+    assert!(pool.query_paused());
+
+    assert!(pool
+        .try_swap(&user1, &None, &token1.address, &1_000, &None, &None, &None)
+        .is_err());
+    assert!(pool
+        .try_provide_liquidity(&user1, &-600, &600, &1_000, &1_000, &0, &0, &None)
+        .is_err());
+
+    pool.withdraw_liquidity(&user1, &-600, &600, &liquidity, &0, &0, &None);
+
+    pool.unpause(&admin);
+    assert!(!pool.query_paused());
+}
@@ -0,0 +1,472 @@
+use soroban_sdk::{testutils::Address as _, Address, Env};
+
+use super::setup::{deploy_concentrated_liquidity_pool_contract, deploy_token_pair};
+use crate::storage::PoolState;
+
+#[test]
+fn provide_liquidity_in_range_takes_both_tokens() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let (token1, token2) = deploy_token_pair(&env, &admin);
+    let pool = deploy_concentrated_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0,
+        None,
+        10,
+        0,
+    );
+
+    let user1 = Address::generate(&env);
+    token1.mint(&user1, &1_000_000);
+    token2.mint(&user1, &1_000_000);
+
+    let liquidity = pool.provide_liquidity(
+        &user1,
+        &-600,
+        &600,
+        &1_000_000,
+        &1_000_000,
+        &990_000,
+        &990_000,
+        &None::<u64>,
+    );
+
+    // the range is symmetric around the price, so both tokens are used up
+    assert!(token1.balance(&user1) <= 1);
+    assert!(token2.balance(&user1) <= 1);
+    let position = pool.query_position(&user1, &-600, &600);
+    assert_eq!(position.liquidity, liquidity);
+    assert_eq!(position.fees_owed_a, 0);
+    assert_eq!(position.fees_owed_b, 0);
+
+    let state = pool.query_pool_state();
+    assert_eq!(state.liquidity, liquidity);
+    assert_eq!(state.tick, 0);
+
+    let pool_info = pool.query_pool_info();
+    assert_eq!(pool_info.asset_a.amount, token1.balance(&pool.address));
+    assert_eq!(pool_info.asset_b.amount, token2.balance(&pool.address));
+    assert_eq!(pool_info.asset_lp_share.amount, liquidity as i128);
+}
+
+#[test]
+fn provide_liquidity_out_of_range_takes_a_single_token() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let (token1, token2) = deploy_token_pair(&env, &admin);
+    let pool = deploy_concentrated_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0,
+        None,
+        60,
+        0,
+    );
+
+    let user1 = Address::generate(&env);
+    token1.mint(&user1, &2_000_000);
+    token2.mint(&user1, &2_000_000);
+
+    // above the current price the position only holds token A
+    pool.provide_liquidity(
+        &user1,
+        &600,
+        &1200,
+        &1_000_000,
+        &1_000_000,
+        &0,
+        &0,
+        &None::<u64>,
+    );
+    assert!(token1.balance(&user1) <= 1_000_001);
+    assert_eq!(token2.balance(&user1), 2_000_000);
+
+    // below the current price the position only holds token B
+    pool.provide_liquidity(
+        &user1,
+        &-1200,
+        &-600,
+        &1_000_000,
+        &1_000_000,
+        &0,
+        &0,
+        &None::<u64>,
+    );
+    assert!(token1.balance(&user1) <= 1_000_001);
+    assert!(token2.balance(&user1) <= 1_000_001);
+
+    // neither position is in range
+    assert_eq!(pool.query_pool_state().liquidity, 0);
+}
+
+#[test]
+fn withdraw_liquidity_returns_tokens_and_removes_position() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let (token1, token2) = deploy_token_pair(&env, &admin);
+    let pool = deploy_concentrated_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0,
+        None,
+        10,
+        0,
+    );
+
+    let user1 = Address::generate(&env);
+    token1.mint(&user1, &1_000_000);
+    token2.mint(&user1, &1_000_000);
+
+    let liquidity = pool.provide_liquidity(
+        &user1,
+        &-600,
+        &600,
+        &1_000_000,
+        &1_000_000,
+        &0,
+        &0,
+        &None::<u64>,
+    );
+
+    let (amount_a, amount_b) =
+        pool.withdraw_liquidity(&user1, &-600, &600, &(liquidity / 2), &0, &0, &None);
+    assert!(amount_a > 499_000 && amount_b > 499_000);
+    assert_eq!(
+        pool.query_position(&user1, &-600, &600).liquidity,
+        liquidity - liquidity / 2
+    );
+
+    pool.withdraw_liquidity(
+        &user1,
+        &-600,
+        &600,
+        &(liquidity - liquidity / 2),
+        &0,
+        &0,
+        &None,
+    );
+
+    // rounding is always in favour of the pool
+    assert!(token1.balance(&user1) >= 999_997);
+    assert!(token2.balance(&user1) >= 999_997);
+    assert!(pool.try_query_position(&user1, &-600, &600).is_err());
+    assert_eq!(
+        pool.query_pool_state(),
+        PoolState {
+            sqrt_price: 1 << 64,
+            tick: 0,
+            liquidity: 0,
+            fee_growth_global_a: 0,
+            fee_growth_global_b: 0,
+        }
+    );
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #16)")]
+fn withdraw_more_than_position_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let (token1, token2) = deploy_token_pair(&env, &admin);
+    let pool = deploy_concentrated_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0,
+        None,
+        10,
+        0,
+    );
+
+    let user1 = Address::generate(&env);
+    token1.mint(&user1, &1_000_000);
+    token2.mint(&user1, &1_000_000);
+
+    let liquidity = pool.provide_liquidity(
+        &user1,
+        &-600,
+        &600,
+        &1_000_000,
+        &1_000_000,
+        &0,
+        &0,
+        &None::<u64>,
+    );
+
+    pool.withdraw_liquidity(&user1, &-600, &600, &(liquidity + 1), &0, &0, &None);
+}
+
+#[test]
+#[should_panic(
+    expected = "Pool Concentrated: Position ticks must be ordered, in bounds and a multiple of the tick spacing"
+)]
+fn provide_liquidity_off_tick_spacing_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let (token1, token2) = deploy_token_pair(&env, &admin);
+    let pool = deploy_concentrated_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0,
+        None,
+        60,
+        0,
+    );
+
+    let user1 = Address::generate(&env);
+    token1.mint(&user1, &1_000_000);
+    token2.mint(&user1, &1_000_000);
+
+    pool.provide_liquidity(
+        &user1,
+        &-600,
+        &610,
+        &1_000_000,
+        &1_000_000,
+        &0,
+        &0,
+        &None::<u64>,
+    );
+}
+
+#[test]
+fn fees_accrue_only_to_positions_in_range() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let (token1, token2) = deploy_token_pair(&env, &admin);
+    let pool = deploy_concentrated_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        100,
+        None,
+        10,
+        0,
+    );
+
+    let in_range = Address::generate(&env);
+    let twice_in_range = Address::generate(&env);
+    let out_of_range = Address::generate(&env);
+    for user in [&in_range, &twice_in_range, &out_of_range] {
+        token1.mint(user, &2_000_000);
+        token2.mint(user, &2_000_000);
+    }
+
+    pool.provide_liquidity(
+        &in_range,
+        &-600,
+        &600,
+        &1_000_000,
+        &1_000_000,
+        &0,
+        &0,
+        &None::<u64>,
+    );
+    pool.provide_liquidity(
+        &twice_in_range,
+        &-600,
+        &600,
+        &2_000_000,
+        &2_000_000,
+        &0,
+        &0,
+        &None::<u64>,
+    );
+    pool.provide_liquidity(
+        &out_of_range,
+        &1_000,
+        &2_000,
+        &1_000_000,
+        &1_000_000,
+        &0,
+        &0,
+        &None::<u64>,
+    );
+
+    let trader = Address::generate(&env);
+    token1.mint(&trader, &10_000);
+    let commission = pool
+        .simulate_swap(&token1.address, &10_000)
+        .commission_amount;
+    pool.swap(
+        &trader,
+        &None,
+        &token1.address,
+        &10_000,
+        &None,
+        &None,
+        &None,
+    );
+
+    // the whole commission of the token B bought accrues to the two positions in range, split by
+    // their liquidity
+    let fees = pool.query_position(&in_range, &-600, &600);
+    let twice_fees = pool.query_position(&twice_in_range, &-600, &600);
+    assert_eq!(fees.fees_owed_a, 0);
+    assert!(fees.fees_owed_b > 0);
+    assert!((twice_fees.fees_owed_b - 2 * fees.fees_owed_b).abs() <= 2);
+    let total_fees = fees.fees_owed_b + twice_fees.fees_owed_b;
+    assert!(total_fees <= commission && total_fees >= commission - 2);
+    let out_of_range_fees = pool.query_position(&out_of_range, &1_000, &2_000);
+    assert_eq!(
+        (out_of_range_fees.fees_owed_a, out_of_range_fees.fees_owed_b),
+        (0, 0)
+    );
+
+    let balance_before = token2.balance(&in_range);
+    assert_eq!(
+        pool.collect_fees(&in_range, &-600, &600),
+        (0, fees.fees_owed_b)
+    );
+    assert_eq!(token2.balance(&in_range), balance_before + fees.fees_owed_b);
+    assert_eq!(pool.query_position(&in_range, &-600, &600).fees_owed_b, 0);
+
+    // collecting again pays nothing
+    assert_eq!(pool.collect_fees(&in_range, &-600, &600), (0, 0));
+}
+
+#[test]
+fn fees_stay_owed_after_the_liquidity_is_withdrawn() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let (token1, token2) = deploy_token_pair(&env, &admin);
+    let pool = deploy_concentrated_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        100,
+        None,
+        10,
+        0,
+    );
+
+    let user1 = Address::generate(&env);
+    token1.mint(&user1, &1_000_000);
+    token2.mint(&user1, &1_000_000);
+    let liquidity = pool.provide_liquidity(
+        &user1,
+        &-600,
+        &600,
+        &1_000_000,
+        &1_000_000,
+        &0,
+        &0,
+        &None::<u64>,
+    );
+
+    let trader = Address::generate(&env);
+    token2.mint(&trader, &10_000);
+    pool.swap(
+        &trader,
+        &None,
+        &token2.address,
+        &10_000,
+        &None,
+        &None,
+        &None,
+    );
+
+    pool.withdraw_liquidity(&user1, &-600, &600, &liquidity, &0, &0, &None);
+
+    let position = pool.query_position(&user1, &-600, &600);
+    assert_eq!(position.liquidity, 0);
+    assert!(position.fees_owed_a > 0);
+
+    let (fees_a, fees_b) = pool.collect_fees(&user1, &-600, &600);
+    assert_eq!((fees_a, fees_b), (position.fees_owed_a, 0));
+
+    // the position is gone once everything is paid out, and the pool keeps only dust
+    assert!(pool.try_query_position(&user1, &-600, &600).is_err());
+    assert!(token1.balance(&pool.address) <= 3);
+    assert!(token2.balance(&pool.address) <= 3);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #20)")]
+fn provide_liquidity_below_the_minimum_position_liquidity_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let (token1, token2) = deploy_token_pair(&env, &admin);
+    let pool = deploy_concentrated_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0,
+        None,
+        10,
+        0,
+    );
+
+    let user1 = Address::generate(&env);
+    token1.mint(&user1, &10);
+    token2.mint(&user1, &10);
+
+    // a dust position on a single tick spacing
+    pool.provide_liquidity(&user1, &0, &10, &10, &10, &0, &0, &None::<u64>);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #20)")]
+fn withdraw_leaving_less_than_the_minimum_position_liquidity_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let (token1, token2) = deploy_token_pair(&env, &admin);
+    let pool = deploy_concentrated_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0,
+        None,
+        10,
+        0,
+    );
+
+    let user1 = Address::generate(&env);
+    token1.mint(&user1, &1_000_000);
+    token2.mint(&user1, &1_000_000);
+
+    let liquidity = pool.provide_liquidity(
+        &user1,
+        &-600,
+        &600,
+        &1_000_000,
+        &1_000_000,
+        &0,
+        &0,
+        &None::<u64>,
+    );
+
+    pool.withdraw_liquidity(&user1, &-600, &600, &(liquidity - 1), &0, &0, &None);
+}
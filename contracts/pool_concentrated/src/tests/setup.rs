@@ -0,0 +1,82 @@
+use soroban_sdk::{testutils::Address as _, Address, BytesN, Env, String};
+
+use crate::{
+    contract::{ConcentratedLiquidityPool, ConcentratedLiquidityPoolClient},
+    token_contract,
+};
+
+use phoenix::utils::{
+    ConcentratedPoolInitInfo, LiquidityPoolInitInfo, StakeInitInfo, TokenInitInfo,
+};
+
+pub fn deploy_token_contract<'a>(env: &Env, admin: &Address) -> token_contract::Client<'a> {
+    token_contract::Client::new(env, &env.register_stellar_asset_contract(admin.clone()))
+}
+
+/// Deploys two tokens, ordered the way the pool expects them
+pub fn deploy_token_pair<'a>(
+    env: &Env,
+    admin: &Address,
+) -> (token_contract::Client<'a>, token_contract::Client<'a>) {
+    let token1 = deploy_token_contract(env, admin);
+    let token2 = deploy_token_contract(env, admin);
+    if token2.address < token1.address {
+        (token2, token1)
+    } else {
+        (token1, token2)
+    }
+}
+
+pub fn deploy_concentrated_liquidity_pool_contract<'a>(
+    env: &Env,
+    admin: impl Into<Option<Address>>,
+    token_a_b: (&Address, &Address),
+    swap_fees: i64,
+    fee_recipient: impl Into<Option<Address>>,
+    tick_spacing: u32,
+    initial_tick: i32,
+) -> ConcentratedLiquidityPoolClient<'a> {
+    let admin = admin.into().unwrap_or(Address::generate(env));
+    let pool = ConcentratedLiquidityPoolClient::new(
+        env,
+        &env.register_contract(None, ConcentratedLiquidityPool {}),
+    );
+    let fee_recipient = fee_recipient
+        .into()
+        .unwrap_or_else(|| Address::generate(env));
+
+    let lp_init_info = LiquidityPoolInitInfo {
+        admin,
+        swap_fee_bps: swap_fees,
+        fee_recipient,
+        max_allowed_slippage_bps: 5_000,
+        max_allowed_spread_bps: 1_000,
+        max_referral_bps: 5_000,
+        token_init_info: TokenInitInfo {
+            token_a: token_a_b.0.clone(),
+            token_b: token_a_b.1.clone(),
+        },
+        stake_init_info: StakeInitInfo {
+            min_bond: 10i128,
+            min_reward: 5i128,
+            manager: Address::generate(env),
+            max_complexity: 10u32,
+        },
+    };
+
+    pool.initialize(
+        // no share token nor stake contract is deployed
+        &BytesN::from_array(env, &[0u8; 32]),
+        &BytesN::from_array(env, &[0u8; 32]),
+        &lp_init_info,
+        &Address::generate(env),
+        &7,
+        &String::from_str(env, "LP_SHARE_TOKEN"),
+        &String::from_str(env, "PHOBTCLP"),
+        &ConcentratedPoolInitInfo {
+            tick_spacing,
+            initial_tick,
+        },
+    );
+    pool
+}
@@ -0,0 +1,463 @@
+use soroban_sdk::{testutils::Address as _, Address, Env};
+
+use super::setup::{deploy_concentrated_liquidity_pool_contract, deploy_token_pair};
+use crate::storage::{Referral, SimulateSwapResponse};
+
+#[test]
+fn simple_swap_within_a_range() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let (token1, token2) = deploy_token_pair(&env, &admin);
+    let pool = deploy_concentrated_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0,
+        None,
+        10,
+        0,
+    );
+
+    let user1 = Address::generate(&env);
+    token1.mint(&user1, &1_001_000);
+    token2.mint(&user1, &1_000_000);
+    pool.provide_liquidity(
+        &user1,
+        &-600,
+        &600,
+        &1_000_000,
+        &1_000_000,
+        &0,
+        &0,
+        &None::<u64>,
+    );
+    let liquidity = pool.query_pool_state().liquidity;
+
+    let simulated = pool.simulate_swap(&token1.address, &1_000);
+    let balance_before = token2.balance(&user1);
+    let return_amount = pool
+        .swap(
+            &user1,
+            &None,
+            &token1.address,
+            &1_000,
+            &None,
+            &Some(100),
+            &None::<u64>,
+        )
+        .ask_amount;
+
+    assert_eq!(return_amount, simulated.ask_amount);
+    assert_eq!(token2.balance(&user1), balance_before + return_amount);
+    // the liquidity is about 20 times deeper than in a full range pool of the same deposit
+    assert!(return_amount > 995 && return_amount < 1_000);
+    assert_eq!(
+        simulated,
+        SimulateSwapResponse {
+            ask_amount: return_amount,
+            commission_amount: 0,
+            protocol_fee_amount: 0,
+            spread_amount: 1_000 - return_amount,
+            total_return: 1_000,
+        }
+    );
+
+    // selling token A moves the price down within the range
+    let state = pool.query_pool_state();
+    assert!(state.sqrt_price < 1 << 64);
+    assert_eq!(state.tick, -1);
+    assert_eq!(state.liquidity, liquidity);
+
+    let pool_info = pool.query_pool_info();
+    assert_eq!(pool_info.asset_a.amount, token1.balance(&pool.address));
+    assert_eq!(pool_info.asset_b.amount, token2.balance(&pool.address));
+}
+
+#[test]
+fn swap_crosses_ticks_in_both_directions() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let (token1, token2) = deploy_token_pair(&env, &admin);
+    let pool = deploy_concentrated_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0,
+        None,
+        10,
+        0,
+    );
+
+    let user1 = Address::generate(&env);
+    token1.mint(&user1, &11_000_000);
+    token2.mint(&user1, &11_000_000);
+    let narrow = pool.provide_liquidity(
+        &user1,
+        &-100,
+        &100,
+        &1_000_000,
+        &1_000_000,
+        &0,
+        &0,
+        &None::<u64>,
+    );
+    let wide = pool.provide_liquidity(
+        &user1,
+        &-6_000,
+        &6_000,
+        &10_000_000,
+        &10_000_000,
+        &0,
+        &0,
+        &None::<u64>,
+    );
+    assert_eq!(pool.query_pool_state().liquidity, narrow + wide);
+
+    // enough token A to push the price out of the narrow range
+    let trader = Address::generate(&env);
+    token1.mint(&trader, &1_300_000);
+    let return_amount = pool
+        .swap(
+            &trader,
+            &None,
+            &token1.address,
+            &1_300_000,
+            &None,
+            &Some(1_000),
+            &None::<u64>,
+        )
+        .ask_amount;
+    assert_eq!(token2.balance(&trader), return_amount);
+
+    let state = pool.query_pool_state();
+    assert!(state.tick < -100);
+    assert_eq!(state.liquidity, wide);
+
+    // and all the way back up
+    token2.mint(&trader, &2_800_000);
+    pool.swap(
+        &trader,
+        &None,
+        &token2.address,
+        &2_800_000,
+        &None,
+        &Some(1_000),
+        &None::<u64>,
+    );
+
+    let state = pool.query_pool_state();
+    assert!(state.tick > 100);
+    assert_eq!(state.liquidity, wide);
+
+    let pool_info = pool.query_pool_info();
+    assert_eq!(pool_info.asset_a.amount, token1.balance(&pool.address));
+    assert_eq!(pool_info.asset_b.amount, token2.balance(&pool.address));
+}
+
+#[test]
+#[should_panic(expected = "Pool Concentrated: Swap: Not enough liquidity to fill the swap")]
+fn swap_beyond_the_available_liquidity_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let (token1, token2) = deploy_token_pair(&env, &admin);
+    let pool = deploy_concentrated_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0,
+        None,
+        10,
+        0,
+    );
+
+    let user1 = Address::generate(&env);
+    token1.mint(&user1, &1_000_000);
+    token2.mint(&user1, &1_000_000);
+    pool.provide_liquidity(
+        &user1,
+        &-100,
+        &100,
+        &1_000_000,
+        &1_000_000,
+        &0,
+        &0,
+        &None::<u64>,
+    );
+
+    // the range only holds 1_000_000 of token B
+    let trader = Address::generate(&env);
+    token1.mint(&trader, &3_000_000);
+    pool.swap(
+        &trader,
+        &None,
+        &token1.address,
+        &3_000_000,
+        &None,
+        &None,
+        &None::<u64>,
+    );
+}
+
+#[test]
+fn simulate_reverse_swap_covers_the_ask_amount() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let (token1, token2) = deploy_token_pair(&env, &admin);
+    let pool = deploy_concentrated_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        30,
+        None,
+        10,
+        0,
+    );
+
+    let user1 = Address::generate(&env);
+    token1.mint(&user1, &1_010_000);
+    token2.mint(&user1, &1_010_000);
+    pool.provide_liquidity(
+        &user1,
+        &-100,
+        &100,
+        &1_000_000,
+        &1_000_000,
+        &0,
+        &0,
+        &None::<u64>,
+    );
+    pool.provide_liquidity(
+        &user1,
+        &-1_000,
+        &1_000,
+        &10_000,
+        &10_000,
+        &0,
+        &0,
+        &None::<u64>,
+    );
+
+    for (ask_asset, offer_asset, ask_amount) in [
+        (&token2.address, &token1.address, 5_000),
+        (&token1.address, &token2.address, 5_000),
+        // crosses out of the narrow range
+        (&token2.address, &token1.address, 1_002_000),
+    ] {
+        let reverse = pool.simulate_reverse_swap(ask_asset, &ask_amount);
+        let forward = pool.simulate_swap(offer_asset, &reverse.offer_amount);
+        assert!(forward.ask_amount >= ask_amount);
+        assert!(forward.ask_amount - ask_amount <= 2);
+    }
+}
+
+#[test]
+fn swap_fees_are_split_with_the_fee_recipient_and_referral() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let fee_recipient = Address::generate(&env);
+    let (token1, token2) = deploy_token_pair(&env, &admin);
+    let pool = deploy_concentrated_liquidity_pool_contract(
+        &env,
+        admin.clone(),
+        (&token1.address, &token2.address),
+        1_000,
+        fee_recipient.clone(),
+        10,
+        0,
+    );
+    // 40% of the commission goes to the fee recipient
+    pool.update_config(&admin, &None, &None, &None, &None, &Some(4_000));
+
+    let user1 = Address::generate(&env);
+    token1.mint(&user1, &10_000_000);
+    token2.mint(&user1, &10_000_000);
+    pool.provide_liquidity(
+        &user1,
+        &-6_000,
+        &6_000,
+        &10_000_000,
+        &10_000_000,
+        &0,
+        &0,
+        &None::<u64>,
+    );
+
+    let simulated = pool.simulate_swap(&token1.address, &10_000);
+
+    let trader = Address::generate(&env);
+    let referral = Address::generate(&env);
+    token1.mint(&trader, &10_000);
+    let return_amount = pool
+        .swap(
+            &trader,
+            &Some(Referral {
+                address: referral.clone(),
+                fee_bps: 1_000,
+            }),
+            &token1.address,
+            &10_000,
+            &None,
+            &None,
+            &None::<u64>,
+        )
+        .ask_amount;
+
+    // 10% commission, 40% of it to the fee recipient, then 10% of the rest to the referral
+    assert_eq!(simulated.commission_amount, 999);
+    assert_eq!(simulated.protocol_fee_amount, 399);
+    assert_eq!(token2.balance(&fee_recipient), 399);
+    assert_eq!(token2.balance(&referral), simulated.ask_amount / 10);
+    assert_eq!(
+        return_amount,
+        simulated.ask_amount - simulated.ask_amount / 10
+    );
+    assert_eq!(token2.balance(&trader), return_amount);
+
+    // the rest of the commission stays with the position
+    let position = pool.query_position(&user1, &-6_000, &6_000);
+    assert!(position.fees_owed_b <= 600 && position.fees_owed_b >= 598);
+}
+
+#[test]
+#[should_panic(expected = "Pool Concentrated: Spread exceeds maximum allowed")]
+fn swap_with_high_spread_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let (token1, token2) = deploy_token_pair(&env, &admin);
+    let pool = deploy_concentrated_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0,
+        None,
+        10,
+        0,
+    );
+
+    let user1 = Address::generate(&env);
+    token1.mint(&user1, &1_000_000);
+    token2.mint(&user1, &1_000_000);
+    pool.provide_liquidity(
+        &user1,
+        &-6_000,
+        &6_000,
+        &1_000_000,
+        &1_000_000,
+        &0,
+        &0,
+        &None::<u64>,
+    );
+
+    let trader = Address::generate(&env);
+    token1.mint(&trader, &500_000);
+    pool.swap(
+        &trader,
+        &None,
+        &token1.address,
+        &500_000,
+        &None,
+        &Some(100),
+        &None::<u64>,
+    );
+}
+
+#[test]
+fn swap_crosses_positions_spread_over_the_tick_range() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let (token1, token2) = deploy_token_pair(&env, &admin);
+    let pool = deploy_concentrated_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0,
+        None,
+        1,
+        0,
+    );
+
+    // single tick positions above the price, each in its own tick bitmap word
+    let user1 = Address::generate(&env);
+    token1.mint(&user1, &8_000_008);
+    for k in 1..=8 {
+        pool.provide_liquidity(
+            &user1,
+            &(k * 130),
+            &(k * 130 + 1),
+            &1_000_000,
+            &0,
+            &0,
+            &0,
+            &None::<u64>,
+        );
+    }
+
+    let trader = Address::generate(&env);
+    token2.mint(&trader, &4_200_000);
+    let simulated = pool.simulate_swap(&token2.address, &4_200_000);
+    let return_amount = pool
+        .swap(
+            &trader,
+            &None,
+            &token2.address,
+            &4_200_000,
+            &None,
+            &None,
+            &None::<u64>,
+        )
+        .ask_amount;
+    assert_eq!(return_amount, simulated.ask_amount);
+    assert_eq!(token1.balance(&trader), return_amount);
+
+    // the swap skipped the empty words between the positions, emptied the first four and ends
+    // inside the fifth
+    let state = pool.query_pool_state();
+    assert!(state.tick > 4 * 130 && state.tick <= 5 * 130);
+    assert_eq!(
+        pool.query_position(&user1, &(4 * 130), &(4 * 130 + 1))
+            .amount_a,
+        0
+    );
+    assert!(
+        pool.query_position(&user1, &(5 * 130), &(5 * 130 + 1))
+            .amount_a
+            > 0
+    );
+
+    // and the same positions are crossed on the way back down into the first one
+    pool.swap(
+        &trader,
+        &None,
+        &token1.address,
+        &return_amount,
+        &None,
+        &None,
+        &None::<u64>,
+    );
+    assert_eq!(pool.query_pool_state().tick, 130);
+
+    let pool_info = pool.query_pool_info();
+    assert_eq!(pool_info.asset_a.amount, token1.balance(&pool.address));
+    assert_eq!(pool_info.asset_b.amount, token2.balance(&pool.address));
+}
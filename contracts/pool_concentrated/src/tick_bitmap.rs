@@ -0,0 +1,192 @@
+use soroban_sdk::Env;
+
+use crate::storage::utils;
+
+/// The ticks used as position boundaries are kept as bits of 128 bit words like in the Uniswap v3
+/// tick bitmap, so adding or removing a tick and finding the next one only touch a few small
+/// entries however many positions the pool has. Ticks are divided by the tick spacing first, the
+/// word of a tick is that quotient divided by 128 and its bit is the remainder. An index of the
+/// non-empty words, built the same way on top of the word positions, lets a swap skip ranges
+/// without any tick in a single lookup.
+const WORD_BITS: i32 = 128;
+
+fn word_and_bit(position: i32) -> (i32, u32) {
+    (
+        position.div_euclid(WORD_BITS),
+        position.rem_euclid(WORD_BITS) as u32,
+    )
+}
+
+/// The closest set bit of `word` at or below `bit` when `lte`, at or above it otherwise.
+fn closest_bit(word: u128, bit: u32, lte: bool) -> Option<u32> {
+    let masked = if lte {
+        word & (u128::MAX >> (127 - bit))
+    } else {
+        word & (u128::MAX << bit)
+    };
+
+    if masked == 0 {
+        None
+    } else if lte {
+        Some(127 - masked.leading_zeros())
+    } else {
+        Some(masked.trailing_zeros())
+    }
+}
+
+/// The lowest set bit of a non-empty `word` when `lowest`, the highest one otherwise.
+fn edge_bit(word: u128, lowest: bool) -> u32 {
+    if lowest {
+        word.trailing_zeros()
+    } else {
+        127 - word.leading_zeros()
+    }
+}
+
+/// Sets the bit of `tick` when it becomes a position boundary and clears it when it no longer
+/// is one. `tick` must be a multiple of `tick_spacing`.
+pub fn flip_tick(env: &Env, tick: i32, tick_spacing: u32) {
+    let (word_pos, bit) = word_and_bit(tick / tick_spacing as i32);
+    let old_word = utils::get_tick_bitmap_word(env, word_pos);
+    let new_word = old_word ^ (1u128 << bit);
+    utils::save_tick_bitmap_word(env, word_pos, new_word);
+
+    // the index only changes when the word gets its first tick or loses its last one
+    if (old_word == 0) != (new_word == 0) {
+        let (index_key, index_bit) = word_and_bit(word_pos);
+        let mut index = utils::get_tick_bitmap_index(env);
+        let entry = index.get(index_key).unwrap_or(0) ^ (1u128 << index_bit);
+        if entry == 0 {
+            index.remove(index_key);
+        } else {
+            index.set(index_key, entry);
+        }
+        utils::save_tick_bitmap_index(env, &index);
+    }
+}
+
+/// The closest non-empty word strictly below `word_pos` when `lte`, strictly above it otherwise.
+fn closest_word(env: &Env, word_pos: i32, lte: bool) -> Option<i32> {
+    let index = utils::get_tick_bitmap_index(env);
+    let (index_key, index_bit) = word_and_bit(word_pos);
+
+    // the rest of the index entry of the word first
+    let rest = match (lte, index_bit) {
+        (true, 0) | (false, 127) => None,
+        (true, bit) => closest_bit(index.get(index_key).unwrap_or(0), bit - 1, true),
+        (false, bit) => closest_bit(index.get(index_key).unwrap_or(0), bit + 1, false),
+    };
+    if let Some(bit) = rest {
+        return Some(index_key * WORD_BITS + bit as i32);
+    }
+
+    // then the closest other entry, the keys of the index are sorted
+    let entry = if lte {
+        index.iter().filter(|(key, _)| *key < index_key).last()
+    } else {
+        index.iter().find(|(key, _)| *key > index_key)
+    };
+    entry.map(|(key, entry)| key * WORD_BITS + edge_bit(entry, !lte) as i32)
+}
+
+/// The next tick used as a position boundary in the direction of the swap: the greatest one not
+/// above `tick` when the price goes down, the smallest one above it otherwise.
+pub fn next_initialized_tick(env: &Env, tick: i32, tick_spacing: u32, a_to_b: bool) -> Option<i32> {
+    let tick_spacing = tick_spacing as i32;
+    let position = if a_to_b {
+        tick.div_euclid(tick_spacing)
+    } else {
+        tick.div_euclid(tick_spacing) + 1
+    };
+
+    let (word_pos, bit) = word_and_bit(position);
+    let word = utils::get_tick_bitmap_word(env, word_pos);
+    let (word_pos, bit) = match closest_bit(word, bit, a_to_b) {
+        Some(bit) => (word_pos, bit),
+        None => {
+            let word_pos = closest_word(env, word_pos, a_to_b)?;
+            let word = utils::get_tick_bitmap_word(env, word_pos);
+            (word_pos, edge_bit(word, !a_to_b))
+        }
+    };
+
+    Some((word_pos * WORD_BITS + bit as i32) * tick_spacing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contract::ConcentratedLiquidityPool;
+
+    fn with_pool(test: impl FnOnce(&Env)) {
+        let env = Env::default();
+        let pool = env.register_contract(None, ConcentratedLiquidityPool {});
+        env.as_contract(&pool, || test(&env));
+    }
+
+    #[test]
+    fn closest_bit_searches_in_both_directions() {
+        let word = (1u128 << 3) | (1u128 << 64) | (1u128 << 127);
+
+        assert_eq!(closest_bit(word, 2, true), None);
+        assert_eq!(closest_bit(word, 3, true), Some(3));
+        assert_eq!(closest_bit(word, 63, true), Some(3));
+        assert_eq!(closest_bit(word, 127, true), Some(127));
+        assert_eq!(closest_bit(word, 0, false), Some(3));
+        assert_eq!(closest_bit(word, 65, false), Some(127));
+        assert_eq!(closest_bit(1, 1, false), None);
+    }
+
+    #[test]
+    fn next_initialized_tick_without_ticks_is_none() {
+        with_pool(|env| {
+            assert_eq!(next_initialized_tick(env, 0, 10, true), None);
+            assert_eq!(next_initialized_tick(env, 0, 10, false), None);
+        });
+    }
+
+    #[test]
+    fn next_initialized_tick_finds_ticks_in_other_words() {
+        with_pool(|env| {
+            // in the same word, in the neighbouring word, across the index and negative
+            for tick in [-276_320, -1_290, -10, 0, 600, 1_280, 1_290, 276_320] {
+                flip_tick(env, tick, 10);
+            }
+
+            assert_eq!(next_initialized_tick(env, 0, 10, true), Some(0));
+            assert_eq!(next_initialized_tick(env, -1, 10, true), Some(-10));
+            assert_eq!(next_initialized_tick(env, -11, 10, true), Some(-1_290));
+            assert_eq!(next_initialized_tick(env, -1_291, 10, true), Some(-276_320));
+            assert_eq!(next_initialized_tick(env, -276_321, 10, true), None);
+
+            assert_eq!(
+                next_initialized_tick(env, -276_321, 10, false),
+                Some(-276_320)
+            );
+            assert_eq!(next_initialized_tick(env, -10, 10, false), Some(0));
+            assert_eq!(next_initialized_tick(env, 0, 10, false), Some(600));
+            assert_eq!(next_initialized_tick(env, 605, 10, false), Some(1_280));
+            assert_eq!(next_initialized_tick(env, 1_280, 10, false), Some(1_290));
+            assert_eq!(next_initialized_tick(env, 1_290, 10, false), Some(276_320));
+            assert_eq!(next_initialized_tick(env, 276_320, 10, false), None);
+            assert_eq!(next_initialized_tick(env, 276_320, 10, true), Some(276_320));
+        });
+    }
+
+    #[test]
+    fn flipping_a_tick_twice_removes_it() {
+        with_pool(|env| {
+            flip_tick(env, -1_290, 10);
+            flip_tick(env, 1_290, 10);
+            flip_tick(env, 1_290, 10);
+
+            assert_eq!(next_initialized_tick(env, 0, 10, false), None);
+            assert_eq!(next_initialized_tick(env, 0, 10, true), Some(-1_290));
+            assert_eq!(utils::get_tick_bitmap_index(env).len(), 1);
+
+            flip_tick(env, -1_290, 10);
+            assert_eq!(next_initialized_tick(env, 0, 10, true), None);
+            assert!(utils::get_tick_bitmap_index(env).is_empty());
+        });
+    }
+}
@@ -1,26 +1,34 @@
 use phoenix::utils::LiquidityPoolInitInfo;
 use soroban_sdk::{
-    contract, contractimpl, contractmeta, log, panic_with_error, Address, BytesN, Env, IntoVal,
-    String,
+    contract, contractimpl, contractmeta, log, panic_with_error, Address, Bytes, BytesN, Env,
+    IntoVal, InvokeError, String, Symbol, Vec, U256,
 };
 
 use crate::{
     error::ContractError,
-    math::{calc_y, compute_current_amp, compute_d, scale_value, AMP_PRECISION},
+    math::{
+        calc_y, calc_y_for_d, compute_current_amp, compute_d, compute_spot_price, AMP_PRECISION,
+        MAX_AMP_CHANGE, MIN_AMP_CHANGING_TIME,
+    },
     stake_contract,
     storage::{
-        get_amp, get_config, get_greatest_precision, get_precisions, save_amp, save_config,
-        save_greatest_precision, utils,
+        get_amp, get_config, get_greatest_precision, get_precisions, get_rate_provider, save_amp,
+        save_config, save_greatest_precision, save_rate_provider, utils,
         utils::{get_admin, is_initialized, set_initialized},
-        AmplifierParameters, Asset, Config, PairType, PoolResponse, SimulateReverseSwapResponse,
-        SimulateSwapResponse, StableLiquidityPoolInfo,
+        AmplifierParameters, Asset, Config, PairType, PoolResponse, Referral,
+        SimulateProvideLiquidityResponse, SimulateReverseSwapResponse, SimulateSwapResponse,
+        SimulateWithdrawImbalancedResponse, SimulateWithdrawSingleResponse,
+        StableLiquidityPoolInfo, SwapResponse,
     },
     token_contract, DECIMAL_PRECISION,
 };
-use phoenix::{validate_bps, validate_int_parameters};
+use phoenix::{
+    oracle::{compute_twap, record_observation, saturating_price},
+    validate_bps, validate_int_parameters,
+};
 use soroban_decimal::Decimal;
 
-// Minimum amount of initial LP shares to mint
+// Amount of LP shares locked in the pool on the first deposit
 const MINIMUM_LIQUIDITY_AMOUNT: i128 = 1000;
 const MAX_AMP: u64 = 1_000_000;
 
@@ -53,28 +61,34 @@ pub trait StableLiquidityPoolTrait {
     // Deposits token_a and token_b. Also mints pool shares for the "to" Identifier. The amount minted
     // is determined based on the difference between the reserves stored by this contract, and
     // the actual balance of token_a and token_b for this contract.
+    // The part of the deposit deviating from the ratio of the pool is charged an imbalance fee of
+    // half the swap commission. Fails if fewer than `min_shares_out` shares would be minted.
     fn provide_liquidity(
         env: Env,
         depositor: Address,
         desired_a: i128,
         desired_b: i128,
         custom_slippage_bps: Option<i64>,
+        min_shares_out: Option<i128>,
         deadline: Option<u64>,
     );
 
     // `offer_asset` is the asset that the user would like to swap for the other token in the pool.
     // `offer_amount` is the amount being sold, with `max_spread_bps` being a safety to make sure you receive at least that amount.
     // swap will transfer the selling token "to" to this contract, and then the contract will transfer the buying token to `sender`.
-    // Returns the amount of the token being bought.
+    // An optional `referral` receives `fee_bps` of the bought amount, capped by the pool's `max_referral_bps`.
+    // Returns the amount of the token being bought with the commission, fees and spread of the swap.
+    #[allow(clippy::too_many_arguments)]
     fn swap(
         env: Env,
         sender: Address,
+        referral: Option<Referral>,
         offer_asset: Address,
         offer_amount: i128,
         ask_asset_min_amount: Option<i128>,
         max_spread_bps: Option<i64>,
         deadline: Option<u64>,
-    ) -> i128;
+    ) -> SwapResponse;
 
     // transfers share_amount of pool share tokens to this contract, burns all pools share tokens in this contracts, and sends the
     // corresponding amount of token_a and token_b to "to".
@@ -88,8 +102,66 @@ pub trait StableLiquidityPoolTrait {
         deadline: Option<u64>,
     ) -> (i128, i128);
 
+    // Same as `provide_liquidity`, but the minted pool shares are bonded right away in the pool's
+    // stake contract on behalf of `depositor`.
+    // Returns the amount of shares bonded
+    fn provide_liquidity_and_stake(
+        env: Env,
+        depositor: Address,
+        desired_a: i128,
+        desired_b: i128,
+        custom_slippage_bps: Option<i64>,
+        min_shares_out: Option<i128>,
+        deadline: Option<u64>,
+    ) -> i128;
+
+    // Unbonds the stake of `recipient` made at `stake_timestamp` from the pool's stake contract and
+    // withdraws the liquidity for the unbonded shares in the same call.
+    // Returns amount of both tokens withdrawn
+    fn unstake_and_withdraw(
+        env: Env,
+        recipient: Address,
+        stake_amount: i128,
+        stake_timestamp: u64,
+        min_a: i128,
+        min_b: i128,
+        deadline: Option<u64>,
+    ) -> (i128, i128);
+
+    // Burns `share_amount` of pool shares and pays out only `ask_asset`, computed from the
+    // invariant the same way as Curve's `remove_liquidity_one_coin`.
+    // Returns the amount of `ask_asset` paid out
+    fn withdraw_liquidity_single(
+        env: Env,
+        recipient: Address,
+        share_amount: i128,
+        ask_asset: Address,
+        min_out: i128,
+        deadline: Option<u64>,
+    ) -> i128;
+
+    // Withdraws exactly `amount_a` of token A and `amount_b` of token B, burning the pool shares
+    // required by the invariant including the imbalance fee, like the stableswap
+    // `remove_liquidity_imbalance`. Fails if more than `max_shares_burn` shares would be burned.
+    // Returns the amount of shares burned
+    fn withdraw_liquidity_imbalanced(
+        env: Env,
+        recipient: Address,
+        amount_a: i128,
+        amount_b: i128,
+        max_shares_burn: i128,
+        deadline: Option<u64>,
+    ) -> i128;
+
+    // Lends `amount` of `token` from the pool reserves to `receiver` for the duration of the call.
+    // The pool calls `on_flash_loan(token, amount, fee, data)` on the receiver contract, which
+    // must transfer `amount` plus the flash loan fee back to the pool before it returns.
+    // Returns the fee paid
+    fn flash_loan(env: Env, receiver: Address, token: Address, amount: i128, data: Bytes) -> i128;
+
     // Allows admin address set during initialization to change some parameters of the
     // configuration
+    #[allow(clippy::too_many_arguments)]
     fn update_config(
         env: Env,
         sender: Address,
@@ -98,8 +170,43 @@ pub trait StableLiquidityPoolTrait {
         fee_recipient: Option<Address>,
         max_allowed_slippage_bps: Option<i64>,
         max_allowed_spread_bps: Option<i64>,
+        max_referral_bps: Option<i64>,
+        protocol_fee_share_bps: Option<i64>,
+        flash_loan_fee_bps: Option<i64>,
     );
 
+    // Allows admin to start gradually changing the amplification parameter (AMP) from its
+    // current value to `next_amp`, which will be reached at `next_amp_time`
+    fn ramp_amp(env: Env, sender: Address, next_amp: u64, next_amp_time: u64);
+
+    // Allows admin to stop an ongoing AMP ramp, freezing AMP at its current value
+    fn stop_ramp_amp(env: Env, sender: Address);
+
+    // Sets the rate provider of `token`, or removes it with `None`. The rate provider is a
+    // contract with a `query_rate() -> i128` entrypoint, returning the value of one token with
+    // 18 decimals, e.g. the value of a yield-bearing token in its underlying asset. The reserves
+    // are multiplied by the rate in all of the pool math. Only possible while the pool holds no
+    // liquidity or is paused, e.g. to replace a failing rate provider
+    fn set_rate_provider(env: Env, sender: Address, token: Address, rate_provider: Option<Address>);
+
+    // Sets the guardian, an address allowed to pause the pool next to the admin and the factory
+    fn set_guardian(env: Env, guardian: Address);
+
+    // Pauses swaps, deposits, single token withdrawals and flash loans. Proportional withdrawals
+    // stay available. Callable by the admin, the guardian or the factory
+    fn pause(env: Env, sender: Address);
+
+    // Resumes the pool. Callable by the admin or the factory
+    fn unpause(env: Env, sender: Address);
+
+    // Sets the stored reserves to the actual token balances of the pool, so tokens sent to the
+    // pool directly accrue to the liquidity providers. Callable by the admin only
+    fn sync(env: Env);
+
+    // Sends the tokens held by the pool above the stored reserves to `recipient`.
+    // Callable by the admin only. Returns the amounts of token A and token B sent
+    fn skim(env: Env, recipient: Address) -> (i128, i128);
+
     // Migration entrypoint
     fn upgrade(e: Env, new_wasm_hash: BytesN<32>);
 
@@ -129,9 +236,59 @@ pub trait StableLiquidityPoolTrait {
         ask_amount: i128,
     ) -> SimulateReverseSwapResponse;
 
+    // Simulate depositing `desired_a` and `desired_b`, returning the shares minted and the
+    // imbalance fees charged. The desired amounts are always deposited as they are, while
+    // `custom_slippage_bps` is validated the same way as in `provide_liquidity`
+    fn simulate_provide_liquidity(
+        env: Env,
+        desired_a: i128,
+        desired_b: i128,
+        custom_slippage_bps: Option<i64>,
+    ) -> SimulateProvideLiquidityResponse;
+
+    // Simulate withdrawing `share_amount` of pool shares, returning the amounts of token A and
+    // token B paid out
+    fn simulate_withdraw_liquidity(env: Env, share_amount: i128) -> (i128, i128);
+
+    // Simulate withdrawing exactly `amount_a` and `amount_b`, returning the shares burned and the
+    // imbalance fees charged
+    fn simulate_withdraw_imbalanced(
+        env: Env,
+        amount_a: i128,
+        amount_b: i128,
+    ) -> SimulateWithdrawImbalancedResponse;
+
+    // Simulate withdrawing `share_amount` of pool shares into `ask_asset` only
+    fn simulate_withdraw_single(
+        env: Env,
+        share_amount: i128,
+        ask_asset: Address,
+    ) -> SimulateWithdrawSingleResponse;
+
     fn query_share(env: Env, amount: i128) -> (Asset, Asset);
 
     fn query_total_issued_lp(env: Env) -> i128;
+
+    // Time-weighted average price of `asset` over the last `window_seconds`, expressed in the
+    // other token of the pool with 18 decimal places
+    fn query_twap(env: Env, asset: Address, window_seconds: u64) -> i128;
+
+    // Returns the amplification parameter (AMP) currently in effect
+    fn query_current_amp(env: Env) -> u64;
+
+    fn query_paused(env: Env) -> bool;
+
+    fn query_guardian(env: Env) -> Option<Address>;
+
+    // Returns the actual token balances of the pool minus the stored reserves, for token A and
+    // token B. A positive value is an excess that can be skimmed, a negative one a shortfall
+    fn query_reserve_discrepancy(env: Env) -> (i128, i128);
+
+    // Returns the rate providers of token A and token B
+    fn query_rate_providers(env: Env) -> (Option<Address>, Option<Address>);
+
+    // Returns the current rates of token A and token B with 18 decimals
+    fn query_rates(env: Env) -> (i128, i128);
 }
 
 #[contractimpl]
@@ -161,13 +318,15 @@ impl StableLiquidityPoolTrait for StableLiquidityPool {
         let fee_recipient = lp_init_info.fee_recipient;
         let max_allowed_slippage_bps = lp_init_info.max_allowed_slippage_bps;
         let max_allowed_spread_bps = lp_init_info.max_allowed_spread_bps;
+        let max_referral_bps = lp_init_info.max_referral_bps;
         let token_init_info = lp_init_info.token_init_info;
         let stake_init_info = lp_init_info.stake_init_info;
 
         validate_bps!(
             swap_fee_bps,
             max_allowed_slippage_bps,
-            max_allowed_spread_bps
+            max_allowed_spread_bps,
+            max_referral_bps
         );
         set_initialized(&env);
 
@@ -225,6 +384,11 @@ impl StableLiquidityPoolTrait for StableLiquidityPool {
             fee_recipient,
             max_allowed_slippage_bps,
             max_allowed_spread_bps,
+            max_referral_bps,
+            // the whole commission goes to the fee recipient until the admin sets a share
+            protocol_fee_share_bps: 10_000,
+            // flash loans pay the swap fee until the admin sets their own
+            flash_loan_fee_bps: swap_fee_bps,
         };
         save_config(&env, config);
         let current_time = env.ledger().timestamp();
@@ -242,6 +406,7 @@ impl StableLiquidityPoolTrait for StableLiquidityPool {
             },
         );
         utils::save_admin(&env, admin);
+        utils::save_factory(&env, factory_addr);
         utils::save_total_shares(&env, 0);
         utils::save_pool_balance_a(&env, 0);
         utils::save_pool_balance_b(&env, 0);
@@ -258,8 +423,11 @@ impl StableLiquidityPoolTrait for StableLiquidityPool {
         desired_a: i128,
         desired_b: i128,
         custom_slippage_bps: Option<i64>,
+        min_shares_out: Option<i128>,
         deadline: Option<u64>,
     ) {
+        ensure_not_paused(&env);
+
         if let Some(deadline) = deadline {
             if env.ledger().timestamp() > deadline {
                 log!(
@@ -284,90 +452,65 @@ impl StableLiquidityPoolTrait for StableLiquidityPool {
         // sender needs to authorize the deposit
         sender.require_auth();
 
+        update_price_accumulators(&env);
+
         let config = get_config(&env);
-        let greatest_precision = get_greatest_precision(&env);
-        let old_balance_a = utils::get_pool_balance_a(&env);
-        let old_balance_b = utils::get_pool_balance_b(&env);
+        validate_custom_slippage(&env, &config, custom_slippage_bps);
 
-        // Check if custom_slippage_bps is more than max_allowed_slippage
-        if let Some(custom_slippage) = custom_slippage_bps {
-            if custom_slippage > config.max_allowed_slippage_bps {
+        let deposit = compute_provide_liquidity(&env, &config, desired_a, desired_b);
+
+        if let Some(min_shares_out) = min_shares_out {
+            if deposit.shares < min_shares_out {
                 log!(
                     &env,
-                    "Pool Stable: ProvideLiquidity: Custom slippage tolerance is more than max allowed slippage tolerance"
+                    "Pool Stable: ProvideLiquidity: Shares minted are less than the minimum requested"
                 );
-                panic_with_error!(env, ContractError::ProvideLiquiditySlippageToleranceTooHigh);
+                panic_with_error!(env, ContractError::ProvideLiquiditySharesBelowMinimum);
             }
         }
 
-        let amp_parameters = get_amp(&env);
-        let amp = compute_current_amp(&env, &amp_parameters);
+        if utils::get_total_shares(&env) == 0 {
+            // the first shares are locked in the pool forever, so the value of a single share can
+            // not be inflated far enough to round the following deposits down to nothing
+            utils::mint_shares(
+                &env,
+                &config.share_token,
+                &env.current_contract_address(),
+                MINIMUM_LIQUIDITY_AMOUNT,
+            );
+        }
 
         let token_a_client = token_contract::Client::new(&env, &config.token_a);
-        let token_a_decimals = token_a_client.decimals();
         let token_b_client = token_contract::Client::new(&env, &config.token_b);
-        let token_b_decimals = token_b_client.decimals();
-
-        // Invariant (D) after deposit added
-        let new_balance_a = desired_a as u128 + old_balance_a as u128;
-        let new_balance_b = desired_b as u128 + old_balance_b as u128;
-
-        let new_invariant = compute_d(
-            &env,
-            amp as u128,
-            &[
-                scale_value(new_balance_a, token_a_decimals, DECIMAL_PRECISION),
-                scale_value(new_balance_b, token_b_decimals, DECIMAL_PRECISION),
-            ],
-        );
-
-        let total_shares = utils::get_total_shares(&env);
-        let shares = if total_shares == 0 {
-            let divisor = 10u128.pow(DECIMAL_PRECISION - greatest_precision);
-            let share = (new_invariant
-                .to_u128()
-                .expect("Pool stable: provide_liquidity: conversion to u128 failed")
-                / divisor)
-                - MINIMUM_LIQUIDITY_AMOUNT as u128;
-            if share == 0 {
-                log!(
-                    &env,
-                    "Pool Stable: ProvideLiquidity: Liquidity amount is too low"
-                );
-                panic_with_error!(&env, ContractError::LowLiquidity);
-            }
-
-            share
-        } else {
-            let initial_invariant = compute_d(
-                &env,
-                amp as u128,
-                &[
-                    scale_value(old_balance_a as u128, token_a_decimals, DECIMAL_PRECISION),
-                    scale_value(old_balance_b as u128, token_b_decimals, DECIMAL_PRECISION),
-                ],
-            )
-            .to_u128()
-            .expect("Pool stable: provide_liquidity: conversion to u128 failed");
-            // Calculate the proportion of the change in invariant
-            (total_shares
-                * (Decimal::new(
-                    (new_invariant
-                        .to_u128()
-                        .expect("Pool stable: provide_liquidity: conversion to u128 failed")
-                        - initial_invariant) as i128,
-                ) / Decimal::new(initial_invariant as i128))) as u128
-        };
 
         // Move tokens from client's wallet to the contract
         token_a_client.transfer(&sender, &env.current_contract_address(), &(desired_a));
         token_b_client.transfer(&sender, &env.current_contract_address(), &(desired_b));
 
+        // the protocol part of the imbalance fees goes to the fee recipient, the rest stays in
+        // the pool
+        let protocol_fee_a = deposit.fee_a * config.protocol_fee_share();
+        let protocol_fee_b = deposit.fee_b * config.protocol_fee_share();
+        if protocol_fee_a > 0 {
+            token_a_client.transfer(
+                &env.current_contract_address(),
+                &config.fee_recipient,
+                &protocol_fee_a,
+            );
+        }
+        if protocol_fee_b > 0 {
+            token_b_client.transfer(
+                &env.current_contract_address(),
+                &config.fee_recipient,
+                &protocol_fee_b,
+            );
+        }
+
         // Now calculate how many new pool shares to mint
         let balance_a = utils::get_balance(&env, &config.token_a);
         let balance_b = utils::get_balance(&env, &config.token_b);
 
-        utils::mint_shares(&env, &config.share_token, &sender, shares as i128);
+        utils::mint_shares(&env, &config.share_token, &sender, deposit.shares);
         utils::save_pool_balance_a(&env, balance_a);
         utils::save_pool_balance_b(&env, balance_b);
 
@@ -381,17 +524,25 @@ impl StableLiquidityPoolTrait for StableLiquidityPool {
             .publish(("provide_liquidity", "token_b"), &config.token_b);
         env.events()
             .publish(("provide_liquidity", "token_b-amount"), desired_b);
+        env.events()
+            .publish(("provide_liquidity", "token_a-fee"), deposit.fee_a);
+        env.events()
+            .publish(("provide_liquidity", "token_b-fee"), deposit.fee_b);
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn swap(
         env: Env,
         sender: Address,
+        referral: Option<Referral>,
         offer_asset: Address,
         offer_amount: i128,
         ask_asset_min_amount: Option<i128>,
         max_spread_bps: Option<i64>,
         deadline: Option<u64>,
-    ) -> i128 {
+    ) -> SwapResponse {
+        ensure_not_paused(&env);
+
         if let Some(deadline) = deadline {
             if env.ledger().timestamp() > deadline {
                 log!(
@@ -409,6 +560,7 @@ impl StableLiquidityPoolTrait for StableLiquidityPool {
         do_swap(
             env,
             sender,
+            referral,
             offer_asset,
             offer_amount,
             ask_asset_min_amount,
@@ -438,34 +590,112 @@ impl StableLiquidityPoolTrait for StableLiquidityPool {
 
         sender.require_auth();
 
+        do_withdraw_liquidity(env, sender, share_amount, min_a, min_b)
+    }
+
+    fn provide_liquidity_and_stake(
+        env: Env,
+        sender: Address,
+        desired_a: i128,
+        desired_b: i128,
+        custom_slippage_bps: Option<i64>,
+        min_shares_out: Option<i128>,
+        deadline: Option<u64>,
+    ) -> i128 {
         let config = get_config(&env);
 
+        // shares are always minted to the sender, so the difference is what this deposit minted
         let share_token_client = token_contract::Client::new(&env, &config.share_token);
-        share_token_client.transfer(&sender, &env.current_contract_address(), &share_amount);
+        let shares_before = share_token_client.balance(&sender);
+
+        Self::provide_liquidity(
+            env.clone(),
+            sender.clone(),
+            desired_a,
+            desired_b,
+            custom_slippage_bps,
+            min_shares_out,
+            deadline,
+        );
 
-        let pool_balance_a = utils::get_pool_balance_a(&env);
-        let pool_balance_b = utils::get_pool_balance_b(&env);
+        let minted_shares = share_token_client.balance(&sender) - shares_before;
+        stake_contract::Client::new(&env, &config.stake_contract).bond(&sender, &minted_shares);
+
+        minted_shares
+    }
+
+    fn unstake_and_withdraw(
+        env: Env,
+        sender: Address,
+        stake_amount: i128,
+        stake_timestamp: u64,
+        min_a: i128,
+        min_b: i128,
+        deadline: Option<u64>,
+    ) -> (i128, i128) {
+        if let Some(deadline) = deadline {
+            if env.ledger().timestamp() > deadline {
+                log!(
+                    env,
+                    "Pool Stable: Unstake and Withdraw: Transaction executed after deadline!"
+                );
+                panic_with_error!(env, ContractError::TransactionAfterTimestampDeadline)
+            }
+        }
+
+        validate_int_parameters!(stake_amount, min_a, min_b);
+
+        sender.require_auth();
+
+        let config = get_config(&env);
+        stake_contract::Client::new(&env, &config.stake_contract).unbond(
+            &sender,
+            &stake_amount,
+            &stake_timestamp,
+        );
+
+        do_withdraw_liquidity(env, sender, stake_amount, min_a, min_b)
+    }
 
-        let total_shares = utils::get_total_shares(&env);
+    fn withdraw_liquidity_single(
+        env: Env,
+        sender: Address,
+        share_amount: i128,
+        ask_asset: Address,
+        min_out: i128,
+        deadline: Option<u64>,
+    ) -> i128 {
+        ensure_not_paused(&env);
 
-        if total_shares == 0i128 {
-            log!(&env, "Pool Stable: WithdrawLiquidity: Critical error - Total shares are equal to zero before withdrawal!");
-            panic_with_error!(env, ContractError::TotalSharesEqualZero);
+        if let Some(deadline) = deadline {
+            if env.ledger().timestamp() > deadline {
+                log!(
+                    env,
+                    "Pool Stable: Withdraw Liquidity Single: Transaction executed after deadline!"
+                );
+                panic_with_error!(env, ContractError::TransactionAfterTimestampDeadline)
+            }
         }
 
-        let share_ratio = Decimal::from_ratio(share_amount, total_shares);
+        validate_int_parameters!(share_amount, min_out);
+
+        sender.require_auth();
+
+        update_price_accumulators(&env);
+
+        let config = get_config(&env);
 
-        let return_amount_a = pool_balance_a * share_ratio;
-        let return_amount_b = pool_balance_b * share_ratio;
+        let share_token_client = token_contract::Client::new(&env, &config.share_token);
+        share_token_client.transfer(&sender, &env.current_contract_address(), &share_amount);
 
-        if return_amount_a < min_a || return_amount_b < min_b {
+        let withdrawal = compute_withdraw_single(&env, &config, share_amount, &ask_asset);
+
+        if withdrawal.ask_amount < min_out {
             log!(
                 &env,
-                "Pool Stable: WithdrawLiquidity: Minimum amount of token_a or token_b is not satisfied! min_a: {}, min_b: {}, return_amount_a: {}, return_amount_b: {}",
-                min_a,
-                min_b,
-                return_amount_a,
-                return_amount_b
+                "Pool Stable: WithdrawLiquiditySingle: Minimum amount is not satisfied! min_out: {}, return_amount: {}",
+                min_out,
+                withdrawal.ask_amount
             );
             panic_with_error!(
                 env,
@@ -473,35 +703,217 @@ impl StableLiquidityPoolTrait for StableLiquidityPool {
             );
         }
 
-        // burn shares
         utils::burn_shares(&env, &config.share_token, share_amount);
-        // transfer tokens from sender to contract
-        token_contract::Client::new(&env, &config.token_a).transfer(
+
+        let ask_token_client = token_contract::Client::new(&env, &ask_asset);
+        ask_token_client.transfer(
             &env.current_contract_address(),
             &sender,
-            &return_amount_a,
+            &withdrawal.ask_amount,
         );
-        token_contract::Client::new(&env, &config.token_b).transfer(
+        // the protocol share of the commission goes to fee recipient, the rest stays in the pool
+        ask_token_client.transfer(
             &env.current_contract_address(),
-            &sender,
-            &return_amount_b,
+            &config.fee_recipient,
+            &withdrawal.protocol_fee_amount,
         );
-        // update pool balances
-        utils::save_pool_balance_a(&env, pool_balance_a - return_amount_a);
-        utils::save_pool_balance_b(&env, pool_balance_b - return_amount_b);
+
+        // only the ask token leaves the pool
+        let paid_out = withdrawal.ask_amount + withdrawal.protocol_fee_amount;
+        if ask_asset == config.token_a {
+            utils::save_pool_balance_a(&env, utils::get_pool_balance_a(&env) - paid_out);
+        } else {
+            utils::save_pool_balance_b(&env, utils::get_pool_balance_b(&env) - paid_out);
+        }
 
         env.events()
-            .publish(("withdraw_liquidity", "sender"), sender);
+            .publish(("withdraw_liquidity_single", "sender"), sender);
         env.events()
-            .publish(("withdraw_liquidity", "shares_amount"), share_amount);
+            .publish(("withdraw_liquidity_single", "shares_amount"), share_amount);
+        env.events()
+            .publish(("withdraw_liquidity_single", "ask_asset"), ask_asset);
+        env.events().publish(
+            ("withdraw_liquidity_single", "return_amount"),
+            withdrawal.ask_amount,
+        );
+        env.events().publish(
+            ("withdraw_liquidity_single", "commission_amount"),
+            withdrawal.commission_amount,
+        );
+
+        withdrawal.ask_amount
+    }
+
+    fn withdraw_liquidity_imbalanced(
+        env: Env,
+        sender: Address,
+        amount_a: i128,
+        amount_b: i128,
+        max_shares_burn: i128,
+        deadline: Option<u64>,
+    ) -> i128 {
+        ensure_not_paused(&env);
+
+        if let Some(deadline) = deadline {
+            if env.ledger().timestamp() > deadline {
+                log!(
+                    env,
+                    "Pool Stable: Withdraw Liquidity Imbalanced: Transaction executed after deadline!"
+                );
+                panic_with_error!(env, ContractError::TransactionAfterTimestampDeadline)
+            }
+        }
+
+        validate_int_parameters!(max_shares_burn);
+
+        sender.require_auth();
+
+        update_price_accumulators(&env);
+
+        let config = get_config(&env);
+
+        let withdrawal = compute_withdraw_imbalanced(&env, &config, amount_a, amount_b);
+
+        if withdrawal.shares > max_shares_burn {
+            log!(
+                &env,
+                "Pool Stable: WithdrawLiquidityImbalanced: Shares to burn are more than the maximum! max_shares_burn: {}, shares: {}",
+                max_shares_burn,
+                withdrawal.shares
+            );
+            panic_with_error!(env, ContractError::WithdrawImbalancedSharesAboveMaximum);
+        }
+
+        let share_token_client = token_contract::Client::new(&env, &config.share_token);
+        share_token_client.transfer(&sender, &env.current_contract_address(), &withdrawal.shares);
+        utils::burn_shares(&env, &config.share_token, withdrawal.shares);
+
+        // the protocol part of the imbalance fees goes to the fee recipient, the rest stays in
+        // the pool
+        let protocol_fee_a = withdrawal.fee_a * config.protocol_fee_share();
+        let protocol_fee_b = withdrawal.fee_b * config.protocol_fee_share();
+        let token_a_client = token_contract::Client::new(&env, &config.token_a);
+        let token_b_client = token_contract::Client::new(&env, &config.token_b);
+        for (client, amount, protocol_fee) in [
+            (&token_a_client, amount_a, protocol_fee_a),
+            (&token_b_client, amount_b, protocol_fee_b),
+        ] {
+            if amount > 0 {
+                client.transfer(&env.current_contract_address(), &sender, &amount);
+            }
+            if protocol_fee > 0 {
+                client.transfer(
+                    &env.current_contract_address(),
+                    &config.fee_recipient,
+                    &protocol_fee,
+                );
+            }
+        }
+
+        utils::save_pool_balance_a(
+            &env,
+            utils::get_pool_balance_a(&env) - amount_a - protocol_fee_a,
+        );
+        utils::save_pool_balance_b(
+            &env,
+            utils::get_pool_balance_b(&env) - amount_b - protocol_fee_b,
+        );
+
         env.events()
-            .publish(("withdraw_liquidity", "return_amount_a"), return_amount_a);
+            .publish(("withdraw_liquidity_imbalanced", "sender"), sender);
+        env.events().publish(
+            ("withdraw_liquidity_imbalanced", "shares_amount"),
+            withdrawal.shares,
+        );
+        env.events().publish(
+            ("withdraw_liquidity_imbalanced", "token_a-amount"),
+            amount_a,
+        );
+        env.events().publish(
+            ("withdraw_liquidity_imbalanced", "token_b-amount"),
+            amount_b,
+        );
+        env.events().publish(
+            ("withdraw_liquidity_imbalanced", "token_a-fee"),
+            withdrawal.fee_a,
+        );
+        env.events().publish(
+            ("withdraw_liquidity_imbalanced", "token_b-fee"),
+            withdrawal.fee_b,
+        );
+
+        withdrawal.shares
+    }
+
+    fn flash_loan(env: Env, receiver: Address, token: Address, amount: i128, data: Bytes) -> i128 {
+        ensure_not_paused(&env);
+
+        validate_int_parameters!(amount);
+
+        receiver.require_auth();
+
+        let config = get_config(&env);
+        let pool_balance = if token == config.token_a {
+            utils::get_pool_balance_a(&env)
+        } else if token == config.token_b {
+            utils::get_pool_balance_b(&env)
+        } else {
+            log!(&env, "Pool Stable: FlashLoan: Token not found in Pool");
+            panic_with_error!(&env, ContractError::AssetNotInPool);
+        };
+        if amount > pool_balance {
+            log!(
+                &env,
+                "Pool Stable: FlashLoan: Amount exceeds the reserves! amount: {}, reserve: {}",
+                amount,
+                pool_balance
+            );
+            panic_with_error!(&env, ContractError::FlashLoanExceedsReserves);
+        }
+
+        update_price_accumulators(&env);
+
+        let fee_amount = amount * config.flash_loan_fee();
+
+        let token_client = token_contract::Client::new(&env, &token);
+        let balance_before = token_client.balance(&env.current_contract_address());
+        token_client.transfer(&env.current_contract_address(), &receiver, &amount);
+
+        // the pool can not be re-entered while the callback runs, so the reserves stay untouched
+        env.invoke_contract::<()>(
+            &receiver,
+            &Symbol::new(&env, "on_flash_loan"),
+            (token.clone(), amount, fee_amount, data).into_val(&env),
+        );
+
+        let balance_after = token_client.balance(&env.current_contract_address());
+        if balance_after < balance_before + fee_amount {
+            log!(
+                &env,
+                "Pool Stable: FlashLoan: Loan was not repaid! expected: {}, balance: {}",
+                balance_before + fee_amount,
+                balance_after
+            );
+            panic_with_error!(&env, ContractError::FlashLoanNotRepaid);
+        }
+
+        // the fee stays in the pool and accrues to the liquidity providers
+        if token == config.token_a {
+            utils::save_pool_balance_a(&env, pool_balance + fee_amount);
+        } else {
+            utils::save_pool_balance_b(&env, pool_balance + fee_amount);
+        }
+
+        env.events().publish(("flash_loan", "receiver"), receiver);
+        env.events().publish(("flash_loan", "token"), token);
+        env.events().publish(("flash_loan", "amount"), amount);
         env.events()
-            .publish(("withdraw_liquidity", "return_amount_b"), return_amount_b);
+            .publish(("flash_loan", "fee_amount"), fee_amount);
 
-        (return_amount_a, return_amount_b)
+        fee_amount
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn update_config(
         env: Env,
         sender: Address,
@@ -510,7 +922,12 @@ impl StableLiquidityPoolTrait for StableLiquidityPool {
         fee_recipient: Option<Address>,
         max_allowed_slippage_bps: Option<i64>,
         max_allowed_spread_bps: Option<i64>,
+        max_referral_bps: Option<i64>,
+        protocol_fee_share_bps: Option<i64>,
+        flash_loan_fee_bps: Option<i64>,
     ) {
+        sender.require_auth();
+
         if sender != utils::get_admin(&env) {
             log!(&env, "Pool Stable: UpdateConfig: Unauthorized");
             panic_with_error!(&env, ContractError::Unauthorized);
@@ -537,13 +954,267 @@ impl StableLiquidityPoolTrait for StableLiquidityPool {
         if let Some(max_allowed_spread_bps) = max_allowed_spread_bps {
             config.max_allowed_spread_bps = max_allowed_spread_bps;
         }
+        if let Some(max_referral_bps) = max_referral_bps {
+            if !(0..=10_000).contains(&max_referral_bps) {
+                log!(&env, "Pool Stable: UpdateConfig: Invalid max_referral_bps");
+                panic_with_error!(&env, ContractError::InvalidBps);
+            }
+            config.max_referral_bps = max_referral_bps;
+        }
+        if let Some(protocol_fee_share_bps) = protocol_fee_share_bps {
+            if !(0..=10_000).contains(&protocol_fee_share_bps) {
+                log!(
+                    &env,
+                    "Pool Stable: UpdateConfig: Invalid protocol_fee_share_bps"
+                );
+                panic_with_error!(&env, ContractError::InvalidBps);
+            }
+            config.protocol_fee_share_bps = protocol_fee_share_bps;
+        }
+        if let Some(flash_loan_fee_bps) = flash_loan_fee_bps {
+            if !(0..=10_000).contains(&flash_loan_fee_bps) {
+                log!(
+                    &env,
+                    "Pool Stable: UpdateConfig: Invalid flash_loan_fee_bps"
+                );
+                panic_with_error!(&env, ContractError::InvalidBps);
+            }
+            config.flash_loan_fee_bps = flash_loan_fee_bps;
+        }
 
         save_config(&env, config);
     }
 
-    fn upgrade(env: Env, new_wasm_hash: BytesN<32>) {
-        let admin: Address = utils::get_admin(&env);
-        admin.require_auth();
+    fn ramp_amp(env: Env, sender: Address, next_amp: u64, next_amp_time: u64) {
+        sender.require_auth();
+
+        if sender != utils::get_admin(&env) {
+            log!(&env, "Pool Stable: RampAmp: Unauthorized");
+            panic_with_error!(&env, ContractError::Unauthorized);
+        }
+
+        if next_amp == 0 || next_amp > MAX_AMP {
+            log!(&env, "Pool Stable: RampAmp: AMP parameter is incorrect");
+            panic_with_error!(&env, ContractError::InvalidAMP);
+        }
+
+        let amp_params = get_amp(&env);
+        let current_time = env.ledger().timestamp();
+
+        if current_time < amp_params.init_amp_time + MIN_AMP_CHANGING_TIME {
+            log!(
+                &env,
+                "Pool Stable: RampAmp: AMP can't be changed more often than once per {} seconds",
+                MIN_AMP_CHANGING_TIME
+            );
+            panic_with_error!(&env, ContractError::AmpRampTooEarly);
+        }
+
+        if next_amp_time < current_time + MIN_AMP_CHANGING_TIME {
+            log!(
+                &env,
+                "Pool Stable: RampAmp: AMP ramp must last at least {} seconds",
+                MIN_AMP_CHANGING_TIME
+            );
+            panic_with_error!(&env, ContractError::AmpRampTimeTooShort);
+        }
+
+        let current_amp = compute_current_amp(&env, &amp_params);
+        let next_amp = next_amp * AMP_PRECISION;
+
+        if (next_amp > current_amp && next_amp > current_amp * MAX_AMP_CHANGE)
+            || (next_amp < current_amp && next_amp * MAX_AMP_CHANGE < current_amp)
+        {
+            log!(
+                &env,
+                "Pool Stable: RampAmp: AMP can't be changed by more than {} times at once",
+                MAX_AMP_CHANGE
+            );
+            panic_with_error!(&env, ContractError::AmpChangeTooBig);
+        }
+
+        save_amp(
+            &env,
+            AmplifierParameters {
+                init_amp: current_amp,
+                init_amp_time: current_time,
+                next_amp,
+                next_amp_time,
+            },
+        );
+
+        env.events()
+            .publish(("ramp_amp", "init_amp"), current_amp / AMP_PRECISION);
+        env.events()
+            .publish(("ramp_amp", "next_amp"), next_amp / AMP_PRECISION);
+        env.events()
+            .publish(("ramp_amp", "next_amp_time"), next_amp_time);
+    }
+
+    fn stop_ramp_amp(env: Env, sender: Address) {
+        sender.require_auth();
+
+        if sender != utils::get_admin(&env) {
+            log!(&env, "Pool Stable: StopRampAmp: Unauthorized");
+            panic_with_error!(&env, ContractError::Unauthorized);
+        }
+
+        let current_amp = compute_current_amp(&env, &get_amp(&env));
+        let current_time = env.ledger().timestamp();
+
+        save_amp(
+            &env,
+            AmplifierParameters {
+                init_amp: current_amp,
+                init_amp_time: current_time,
+                next_amp: current_amp,
+                next_amp_time: current_time,
+            },
+        );
+
+        env.events().publish(
+            ("stop_ramp_amp", "current_amp"),
+            current_amp / AMP_PRECISION,
+        );
+    }
+
+    fn set_rate_provider(
+        env: Env,
+        sender: Address,
+        token: Address,
+        rate_provider: Option<Address>,
+    ) {
+        sender.require_auth();
+
+        if sender != utils::get_admin(&env) {
+            log!(&env, "Pool Stable: SetRateProvider: Unauthorized");
+            panic_with_error!(&env, ContractError::Unauthorized);
+        }
+
+        let config = get_config(&env);
+        if token != config.token_a && token != config.token_b {
+            log!(
+                &env,
+                "Pool Stable: SetRateProvider: Token not found in Pool"
+            );
+            panic_with_error!(&env, ContractError::AssetNotInPool);
+        }
+
+        // the value of the shares would jump with the rate, so the rates are fixed once the pool
+        // has liquidity, unless the admin paused the pool to replace a failing rate provider
+        if utils::get_total_shares(&env) != 0 && !utils::is_paused(&env) {
+            log!(
+                &env,
+                "Pool Stable: SetRateProvider: Rate provider can't be changed with liquidity in an active pool"
+            );
+            panic_with_error!(&env, ContractError::RateProviderChangeWithLiquidity);
+        }
+
+        save_rate_provider(&env, &token, &rate_provider);
+        // fails early on a provider without a valid rate
+        get_rate(&env, &token);
+
+        env.events().publish(("set_rate_provider", "token"), token);
+        env.events()
+            .publish(("set_rate_provider", "rate_provider"), rate_provider);
+    }
+
+    fn set_guardian(env: Env, guardian: Address) {
+        let admin: Address = utils::get_admin(&env);
+        admin.require_auth();
+
+        utils::save_guardian(&env, guardian.clone());
+
+        env.events().publish(("set_guardian", "guardian"), guardian);
+    }
+
+    fn pause(env: Env, sender: Address) {
+        sender.require_auth();
+
+        let is_guardian = utils::get_guardian(&env).map_or(false, |guardian| guardian == sender);
+        let is_factory = utils::get_factory(&env).map_or(false, |factory| factory == sender);
+        if sender != utils::get_admin(&env) && !is_factory && !is_guardian {
+            log!(
+                &env,
+                "Pool Stable: Pause: You are not authorized to pause the pool!"
+            );
+            panic_with_error!(&env, ContractError::Unauthorized);
+        }
+
+        utils::set_paused(&env, true);
+
+        env.events().publish(("pause", "sender"), sender);
+    }
+
+    fn unpause(env: Env, sender: Address) {
+        sender.require_auth();
+
+        let is_factory = utils::get_factory(&env).map_or(false, |factory| factory == sender);
+        if sender != utils::get_admin(&env) && !is_factory {
+            log!(
+                &env,
+                "Pool Stable: Unpause: You are not authorized to unpause the pool!"
+            );
+            panic_with_error!(&env, ContractError::Unauthorized);
+        }
+
+        utils::set_paused(&env, false);
+
+        env.events().publish(("unpause", "sender"), sender);
+    }
+
+    fn sync(env: Env) {
+        let admin: Address = utils::get_admin(&env);
+        admin.require_auth();
+
+        update_price_accumulators(&env);
+
+        let config = get_config(&env);
+        let balance_a = utils::get_balance(&env, &config.token_a);
+        let balance_b = utils::get_balance(&env, &config.token_b);
+
+        utils::save_pool_balance_a(&env, balance_a);
+        utils::save_pool_balance_b(&env, balance_b);
+
+        env.events().publish(("sync", "reserve_a"), balance_a);
+        env.events().publish(("sync", "reserve_b"), balance_b);
+    }
+
+    fn skim(env: Env, recipient: Address) -> (i128, i128) {
+        let admin: Address = utils::get_admin(&env);
+        admin.require_auth();
+
+        let config = get_config(&env);
+        let excess_a =
+            (utils::get_balance(&env, &config.token_a) - utils::get_pool_balance_a(&env)).max(0);
+        let excess_b =
+            (utils::get_balance(&env, &config.token_b) - utils::get_pool_balance_b(&env)).max(0);
+
+        if excess_a > 0 {
+            token_contract::Client::new(&env, &config.token_a).transfer(
+                &env.current_contract_address(),
+                &recipient,
+                &excess_a,
+            );
+        }
+        if excess_b > 0 {
+            token_contract::Client::new(&env, &config.token_b).transfer(
+                &env.current_contract_address(),
+                &recipient,
+                &excess_b,
+            );
+        }
+
+        env.events().publish(("skim", "recipient"), recipient);
+        env.events().publish(("skim", "amount_a"), excess_a);
+        env.events().publish(("skim", "amount_b"), excess_b);
+
+        (excess_a, excess_b)
+    }
+
+    fn upgrade(env: Env, new_wasm_hash: BytesN<32>) {
+        let admin: Address = utils::get_admin(&env);
+        admin.require_auth();
 
         env.deployer().update_current_contract_wasm(new_wasm_hash);
     }
@@ -636,10 +1307,8 @@ impl StableLiquidityPoolTrait for StableLiquidityPool {
 
         let (ask_amount, spread_amount, commission_amount) = compute_swap(
             &env,
-            pool_balance_sell as u128,
-            get_precisions(&env, &sell_token),
-            pool_balance_buy as u128,
-            get_precisions(&env, &buy_token),
+            &PoolReserve::new(&env, &sell_token, pool_balance_sell as u128),
+            &PoolReserve::new(&env, &buy_token, pool_balance_buy as u128),
             offer_amount as u128,
             config.protocol_fee_rate(),
         );
@@ -650,6 +1319,7 @@ impl StableLiquidityPoolTrait for StableLiquidityPool {
             ask_amount,
             spread_amount,
             commission_amount,
+            protocol_fee_amount: commission_amount * config.protocol_fee_share(),
             total_return,
         }
     }
@@ -685,10 +1355,8 @@ impl StableLiquidityPoolTrait for StableLiquidityPool {
 
         let (offer_amount, spread_amount, commission_amount) = compute_offer_amount(
             &env,
-            pool_balance_sell as u128,
-            get_precisions(&env, &sell_token),
-            pool_balance_buy as u128,
-            get_precisions(&env, &buy_token),
+            &PoolReserve::new(&env, &sell_token, pool_balance_sell as u128),
+            &PoolReserve::new(&env, &buy_token, pool_balance_buy as u128),
             ask_amount as u128,
             config.protocol_fee_rate(),
         );
@@ -700,6 +1368,38 @@ impl StableLiquidityPoolTrait for StableLiquidityPool {
         }
     }
 
+    fn simulate_provide_liquidity(
+        env: Env,
+        desired_a: i128,
+        desired_b: i128,
+        custom_slippage_bps: Option<i64>,
+    ) -> SimulateProvideLiquidityResponse {
+        let config = get_config(&env);
+        validate_custom_slippage(&env, &config, custom_slippage_bps);
+
+        compute_provide_liquidity(&env, &config, desired_a, desired_b)
+    }
+
+    fn simulate_withdraw_liquidity(env: Env, share_amount: i128) -> (i128, i128) {
+        compute_withdraw_amounts(&env, share_amount)
+    }
+
+    fn simulate_withdraw_imbalanced(
+        env: Env,
+        amount_a: i128,
+        amount_b: i128,
+    ) -> SimulateWithdrawImbalancedResponse {
+        compute_withdraw_imbalanced(&env, &get_config(&env), amount_a, amount_b)
+    }
+
+    fn simulate_withdraw_single(
+        env: Env,
+        share_amount: i128,
+        ask_asset: Address,
+    ) -> SimulateWithdrawSingleResponse {
+        compute_withdraw_single(&env, &get_config(&env), share_amount, &ask_asset)
+    }
+
     fn query_share(env: Env, amount: i128) -> (Asset, Asset) {
         let pool_info = Self::query_pool_info(env);
         let total_share = pool_info.asset_lp_share.amount;
@@ -728,6 +1428,79 @@ impl StableLiquidityPoolTrait for StableLiquidityPool {
     fn query_total_issued_lp(env: Env) -> i128 {
         utils::get_total_shares(&env)
     }
+
+    fn query_twap(env: Env, asset: Address, window_seconds: u64) -> i128 {
+        let config = get_config(&env);
+        let price_of_a = if asset == config.token_a {
+            true
+        } else if asset == config.token_b {
+            false
+        } else {
+            log!(&env, "Pool Stable: Query TWAP: Asset not found in the pool");
+            panic_with_error!(&env, ContractError::AssetNotInPool);
+        };
+
+        let accumulator = utils::get_price_accumulator(&env);
+        let prices = get_spot_prices(&env).unwrap_or_else(|error| {
+            log!(&env, "Pool Stable: Query TWAP: Rate provider failed");
+            panic_with_error!(&env, error);
+        });
+        let current = accumulator.accumulate(env.ledger().timestamp(), prices);
+
+        compute_twap(
+            &utils::get_observations(&env),
+            &accumulator,
+            &current,
+            window_seconds,
+            price_of_a,
+        )
+        .unwrap_or_else(|| {
+            log!(
+                &env,
+                "Pool Stable: Query TWAP: Not enough price history for the requested window"
+            );
+            panic_with_error!(&env, ContractError::TwapWindowNotCovered);
+        })
+    }
+
+    fn query_paused(env: Env) -> bool {
+        utils::is_paused(&env)
+    }
+
+    fn query_guardian(env: Env) -> Option<Address> {
+        utils::get_guardian(&env)
+    }
+
+    fn query_reserve_discrepancy(env: Env) -> (i128, i128) {
+        let config = get_config(&env);
+
+        (
+            utils::get_balance(&env, &config.token_a) - utils::get_pool_balance_a(&env),
+            utils::get_balance(&env, &config.token_b) - utils::get_pool_balance_b(&env),
+        )
+    }
+
+    fn query_current_amp(env: Env) -> u64 {
+        compute_current_amp(&env, &get_amp(&env)) / AMP_PRECISION
+    }
+
+    fn query_rate_providers(env: Env) -> (Option<Address>, Option<Address>) {
+        let config = get_config(&env);
+
+        (
+            get_rate_provider(&env, &config.token_a),
+            get_rate_provider(&env, &config.token_b),
+        )
+    }
+
+    fn query_rates(env: Env) -> (i128, i128) {
+        let config = get_config(&env);
+
+        (
+            get_rate(&env, &config.token_a).atomics(),
+            get_rate(&env, &config.token_b).atomics(),
+        )
+    }
 }
 
 #[contractimpl]
@@ -741,14 +1514,432 @@ impl StableLiquidityPool {
     }
 }
 
+/// Computes the pool shares minted for depositing `desired_a` and `desired_b` and the imbalance
+/// fees charged on the deposit.
+///
+/// The shares are minted from the invariant of the balances without the imbalance fees, see
+/// `compute_imbalance`. The first deposit is not charged and leaves out the
+/// `MINIMUM_LIQUIDITY_AMOUNT` locked in the pool.
+fn compute_provide_liquidity(
+    env: &Env,
+    config: &Config,
+    desired_a: i128,
+    desired_b: i128,
+) -> SimulateProvideLiquidityResponse {
+    let old_balance_a = utils::get_pool_balance_a(env) as u128;
+    let old_balance_b = utils::get_pool_balance_b(env) as u128;
+    let reserve_a = PoolReserve::new(env, &config.token_a, old_balance_a);
+    let reserve_b = PoolReserve::new(env, &config.token_b, old_balance_b);
+    let amp = compute_current_amp(env, &get_amp(env)) as u128;
+
+    // Invariant (D) after deposit added
+    let new_balance_a = desired_a as u128 + old_balance_a;
+    let new_balance_b = desired_b as u128 + old_balance_b;
+    let new_invariant = compute_d(
+        env,
+        amp,
+        &[
+            reserve_a.to_invariant_units(env, new_balance_a),
+            reserve_b.to_invariant_units(env, new_balance_b),
+        ],
+    );
+
+    let total_shares = utils::get_total_shares(env);
+    if total_shares == 0 {
+        let divisor = 10u128.pow(DECIMAL_PRECISION - get_greatest_precision(env));
+        let share = new_invariant
+            .to_u128()
+            .expect("Pool stable: provide_liquidity: conversion to u128 failed")
+            / divisor;
+        if share <= MINIMUM_LIQUIDITY_AMOUNT as u128 {
+            log!(
+                env,
+                "Pool Stable: ProvideLiquidity: Liquidity amount is too low"
+            );
+            panic_with_error!(env, ContractError::LowLiquidity);
+        }
+
+        return SimulateProvideLiquidityResponse {
+            shares: share as i128 - MINIMUM_LIQUIDITY_AMOUNT,
+            fee_a: 0,
+            fee_b: 0,
+        };
+    }
+
+    let imbalance = compute_imbalance(
+        env,
+        config,
+        amp,
+        (&reserve_a, &reserve_b),
+        (new_balance_a, new_balance_b),
+        &new_invariant,
+    );
+
+    // Calculate the proportion of the change in invariant
+    let shares = total_shares
+        * (Decimal::new(
+            imbalance
+                .invariant_after_fees
+                .saturating_sub(imbalance.initial_invariant) as i128,
+        ) / Decimal::new(imbalance.initial_invariant as i128));
+
+    SimulateProvideLiquidityResponse {
+        shares,
+        fee_a: imbalance.fee_a as i128,
+        fee_b: imbalance.fee_b as i128,
+    }
+}
+
+/// Computes the pool shares burned for withdrawing exactly `amount_a` and `amount_b`, and the
+/// imbalance fees charged on the withdrawal, the same way as the stableswap
+/// `remove_liquidity_imbalance`. The shares are rounded up, in favour of the pool.
+fn compute_withdraw_imbalanced(
+    env: &Env,
+    config: &Config,
+    amount_a: i128,
+    amount_b: i128,
+) -> SimulateWithdrawImbalancedResponse {
+    let old_balance_a = utils::get_pool_balance_a(env);
+    let old_balance_b = utils::get_pool_balance_b(env);
+
+    if amount_a < 0
+        || amount_b < 0
+        || (amount_a == 0 && amount_b == 0)
+        || amount_a >= old_balance_a
+        || amount_b >= old_balance_b
+    {
+        log!(
+            env,
+            "Pool Stable: WithdrawLiquidityImbalanced: Amounts must not be negative, not both zero and less than the reserves"
+        );
+        panic_with_error!(env, ContractError::WithdrawImbalancedInvalidAmounts);
+    }
+
+    let total_shares = utils::get_total_shares(env);
+    if total_shares == 0i128 {
+        log!(env, "Pool Stable: WithdrawLiquidityImbalanced: Critical error - Total shares are equal to zero before withdrawal!");
+        panic_with_error!(env, ContractError::TotalSharesEqualZero);
+    }
+
+    let reserve_a = PoolReserve::new(env, &config.token_a, old_balance_a as u128);
+    let reserve_b = PoolReserve::new(env, &config.token_b, old_balance_b as u128);
+    let amp = compute_current_amp(env, &get_amp(env)) as u128;
+
+    let new_balance_a = (old_balance_a - amount_a) as u128;
+    let new_balance_b = (old_balance_b - amount_b) as u128;
+    let new_invariant = compute_d(
+        env,
+        amp,
+        &[
+            reserve_a.to_invariant_units(env, new_balance_a),
+            reserve_b.to_invariant_units(env, new_balance_b),
+        ],
+    );
+
+    let imbalance = compute_imbalance(
+        env,
+        config,
+        amp,
+        (&reserve_a, &reserve_b),
+        (new_balance_a, new_balance_b),
+        &new_invariant,
+    );
+
+    let shares = U256::from_u128(env, total_shares as u128)
+        .mul(&U256::from_u128(
+            env,
+            imbalance
+                .initial_invariant
+                .saturating_sub(imbalance.invariant_after_fees),
+        ))
+        .div(&U256::from_u128(env, imbalance.initial_invariant))
+        .to_u128()
+        .expect("Pool stable: withdraw_liquidity_imbalanced: conversion to u128 failed")
+        + 1;
+
+    SimulateWithdrawImbalancedResponse {
+        shares: shares as i128,
+        fee_a: imbalance.fee_a as i128,
+        fee_b: imbalance.fee_b as i128,
+    }
+}
+
+struct Imbalance {
+    initial_invariant: u128,
+    /// The invariant of the new balances without the fees
+    invariant_after_fees: u128,
+    fee_a: u128,
+    fee_b: u128,
+}
+
+/// Computes the imbalance fees for moving the reserves to `new_balances`, where `new_invariant`
+/// is the invariant of the new balances.
+///
+/// Depositing or withdrawing off the ratio of the pool amounts to a swap, so the difference
+/// between each new balance and the balance the pool would have after a balanced change of the
+/// same invariant is charged half of the swap commission, i.e. total fee * n / (4 * (n - 1))
+/// for n coins.
+fn compute_imbalance(
+    env: &Env,
+    config: &Config,
+    amp: u128,
+    reserves: (&PoolReserve, &PoolReserve),
+    new_balances: (u128, u128),
+    new_invariant: &U256,
+) -> Imbalance {
+    let (reserve_a, reserve_b) = reserves;
+    let (new_balance_a, new_balance_b) = new_balances;
+    let initial_invariant = compute_d(env, amp, &[reserve_a.scaled(env), reserve_b.scaled(env)]);
+
+    let imbalance_fee_rate = config.protocol_fee_rate() / 2;
+    let imbalance_fee = |old_balance: u128, new_balance: u128| -> u128 {
+        let ideal_balance = U256::from_u128(env, old_balance)
+            .mul(new_invariant)
+            .div(&initial_invariant)
+            .to_u128()
+            .expect("Pool stable: compute_imbalance: conversion to u128 failed");
+        (ideal_balance.abs_diff(new_balance) as i128 * imbalance_fee_rate) as u128
+    };
+    let fee_a = imbalance_fee(reserve_a.amount, new_balance_a);
+    let fee_b = imbalance_fee(reserve_b.amount, new_balance_b);
+
+    let invariant_after_fees = compute_d(
+        env,
+        amp,
+        &[
+            reserve_a.to_invariant_units(env, new_balance_a - fee_a),
+            reserve_b.to_invariant_units(env, new_balance_b - fee_b),
+        ],
+    )
+    .to_u128()
+    .expect("Pool stable: compute_imbalance: conversion to u128 failed");
+
+    Imbalance {
+        initial_invariant: initial_invariant
+            .to_u128()
+            .expect("Pool stable: compute_imbalance: conversion to u128 failed"),
+        invariant_after_fees,
+        fee_a,
+        fee_b,
+    }
+}
+
+/// Computes the amount of `ask_asset` paid out for `share_amount` of pool shares when the
+/// liquidity is withdrawn in that token only.
+///
+/// The invariant is reduced in proportion to the burned shares and the new balance of the ask
+/// token is solved for with the other balance unchanged. As in the stableswap
+/// `remove_liquidity_one_coin`, the imbalance fees of that change are taken off both balances
+/// and the ask balance is solved for again, the difference being the commission.
+fn compute_withdraw_single(
+    env: &Env,
+    config: &Config,
+    share_amount: i128,
+    ask_asset: &Address,
+) -> SimulateWithdrawSingleResponse {
+    let pool_balance_a = utils::get_pool_balance_a(env);
+    let pool_balance_b = utils::get_pool_balance_b(env);
+
+    let (ask_token, pool_balance_ask, offer_token, pool_balance_offer) =
+        if ask_asset == &config.token_a {
+            (
+                &config.token_a,
+                pool_balance_a,
+                &config.token_b,
+                pool_balance_b,
+            )
+        } else if ask_asset == &config.token_b {
+            (
+                &config.token_b,
+                pool_balance_b,
+                &config.token_a,
+                pool_balance_a,
+            )
+        } else {
+            log!(env, "Pool Stable: Token to withdraw not found in Pool");
+            panic_with_error!(env, ContractError::AssetNotInPool);
+        };
+
+    let total_shares = utils::get_total_shares(env);
+    if total_shares == 0i128 {
+        log!(env, "Pool Stable: WithdrawLiquiditySingle: Critical error - Total shares are equal to zero before withdrawal!");
+        panic_with_error!(env, ContractError::TotalSharesEqualZero);
+    }
+    if share_amount >= total_shares {
+        log!(
+            env,
+            "Pool Stable: WithdrawLiquiditySingle: Not enough liquidity left to withdraw against!"
+        );
+        panic_with_error!(env, ContractError::WithdrawSingleEmptiesPool);
+    }
+
+    let ask = PoolReserve::new(env, ask_token, pool_balance_ask as u128);
+    let offer = PoolReserve::new(env, offer_token, pool_balance_offer as u128);
+    let amp = compute_current_amp(env, &get_amp(env)) as u128;
+
+    let balances = [ask.scaled(env), offer.scaled(env)];
+    let d = compute_d(env, amp, &balances);
+    let new_d = d.sub(
+        &d.mul(&U256::from_u128(env, share_amount as u128))
+            .div(&U256::from_u128(env, total_shares as u128)),
+    );
+
+    let new_ask_balance = ask.to_token_units(
+        env,
+        calc_y_for_d(env, amp, &balances, 0, new_d.clone(), DECIMAL_PRECISION),
+    ) as i128;
+    // calc_y rounds down, keep the last unit in the pool
+    let total_ask_amount = (pool_balance_ask - new_ask_balance - 1).max(0);
+
+    // the imbalance fees are charged on the balances left in the pool, including the last unit
+    let imbalance = compute_imbalance(
+        env,
+        config,
+        amp,
+        (&ask, &offer),
+        (new_ask_balance as u128 + 1, pool_balance_offer as u128),
+        &new_d,
+    );
+    let balances_after_fees = [
+        ask.to_invariant_units(env, pool_balance_ask as u128 - imbalance.fee_a),
+        offer.to_invariant_units(env, pool_balance_offer as u128 - imbalance.fee_b),
+    ];
+    let new_ask_balance_after_fees = ask.to_token_units(
+        env,
+        calc_y_for_d(env, amp, &balances_after_fees, 0, new_d, DECIMAL_PRECISION),
+    ) as i128;
+    let ask_amount =
+        (pool_balance_ask - imbalance.fee_a as i128 - new_ask_balance_after_fees - 1).max(0);
+
+    let share_ratio = Decimal::from_ratio(share_amount, total_shares);
+    let exchanged_amount = (total_ask_amount - pool_balance_ask * share_ratio).max(0);
+    let offer_share = ask.to_token_units(
+        env,
+        offer.to_invariant_units(env, (pool_balance_offer * share_ratio) as u128),
+    ) as i128;
+
+    let commission_amount = (total_ask_amount - ask_amount).max(0);
+
+    SimulateWithdrawSingleResponse {
+        ask_amount: total_ask_amount - commission_amount,
+        commission_amount,
+        protocol_fee_amount: commission_amount * config.protocol_fee_share(),
+        // the exchange rate in a stable pool is considered to be the rate of the tokens
+        spread_amount: (offer_share - exchanged_amount).max(0),
+    }
+}
+
+fn ensure_not_paused(env: &Env) {
+    if utils::is_paused(env) {
+        log!(env, "Pool Stable: The pool is paused!");
+        panic_with_error!(env, ContractError::ContractPaused);
+    }
+}
+
+/// Panics if the slippage tolerance chosen by the depositor is above the maximum of the pool.
+fn validate_custom_slippage(env: &Env, config: &Config, custom_slippage_bps: Option<i64>) {
+    if let Some(custom_slippage) = custom_slippage_bps {
+        if custom_slippage > config.max_allowed_slippage_bps {
+            log!(
+                env,
+                "Pool Stable: ProvideLiquidity: Custom slippage tolerance is more than max allowed slippage tolerance"
+            );
+            panic_with_error!(env, ContractError::ProvideLiquiditySlippageToleranceTooHigh);
+        }
+    }
+}
+
+/// The amounts of token A and token B paid out for burning `share_amount` of pool shares.
+fn compute_withdraw_amounts(env: &Env, share_amount: i128) -> (i128, i128) {
+    let total_shares = utils::get_total_shares(env);
+
+    if total_shares == 0i128 {
+        log!(env, "Pool Stable: WithdrawLiquidity: Critical error - Total shares are equal to zero before withdrawal!");
+        panic_with_error!(env, ContractError::TotalSharesEqualZero);
+    }
+
+    let share_ratio = Decimal::from_ratio(share_amount, total_shares);
+
+    (
+        utils::get_pool_balance_a(env) * share_ratio,
+        utils::get_pool_balance_b(env) * share_ratio,
+    )
+}
+
+/// Burns `share_amount` of the sender's pool shares and sends back the corresponding part of
+/// both reserves. The caller is responsible for authorizing the sender.
+fn do_withdraw_liquidity(
+    env: Env,
+    sender: Address,
+    share_amount: i128,
+    min_a: i128,
+    min_b: i128,
+) -> (i128, i128) {
+    update_price_accumulators(&env);
+
+    let config = get_config(&env);
+
+    let share_token_client = token_contract::Client::new(&env, &config.share_token);
+    share_token_client.transfer(&sender, &env.current_contract_address(), &share_amount);
+
+    let pool_balance_a = utils::get_pool_balance_a(&env);
+    let pool_balance_b = utils::get_pool_balance_b(&env);
+
+    let (return_amount_a, return_amount_b) = compute_withdraw_amounts(&env, share_amount);
+
+    if return_amount_a < min_a || return_amount_b < min_b {
+        log!(
+    &env,
+    "Pool Stable: WithdrawLiquidity: Minimum amount of token_a or token_b is not satisfied! min_a: {}, min_b: {}, return_amount_a: {}, return_amount_b: {}",
+    min_a,
+    min_b,
+    return_amount_a,
+    return_amount_b
+);
+        panic_with_error!(
+            env,
+            ContractError::WithdrawLiquidityMinimumAmountOfAOrBIsNotSatisfied
+        );
+    }
+
+    // burn shares
+    utils::burn_shares(&env, &config.share_token, share_amount);
+    // transfer tokens from sender to contract
+    token_contract::Client::new(&env, &config.token_a).transfer(
+        &env.current_contract_address(),
+        &sender,
+        &return_amount_a,
+    );
+    token_contract::Client::new(&env, &config.token_b).transfer(
+        &env.current_contract_address(),
+        &sender,
+        &return_amount_b,
+    );
+    // update pool balances
+    utils::save_pool_balance_a(&env, pool_balance_a - return_amount_a);
+    utils::save_pool_balance_b(&env, pool_balance_b - return_amount_b);
+
+    env.events()
+        .publish(("withdraw_liquidity", "sender"), sender);
+    env.events()
+        .publish(("withdraw_liquidity", "shares_amount"), share_amount);
+    env.events()
+        .publish(("withdraw_liquidity", "return_amount_a"), return_amount_a);
+    env.events()
+        .publish(("withdraw_liquidity", "return_amount_b"), return_amount_b);
+
+    (return_amount_a, return_amount_b)
+}
+
 fn do_swap(
     env: Env,
     sender: Address,
+    referral: Option<Referral>,
     offer_asset: Address,
     offer_amount: i128,
     ask_asset_min_amount: Option<i128>,
     max_spread: Option<i64>,
-) -> i128 {
+) -> SwapResponse {
     let config = get_config(&env);
 
     if offer_asset != config.token_a && offer_asset != config.token_b {
@@ -759,6 +1950,16 @@ fn do_swap(
         panic_with_error!(&env, ContractError::IncorrectAssetSwap);
     }
 
+    if let Some(referral) = &referral {
+        if !(0..=config.max_referral_bps).contains(&referral.fee_bps) {
+            log!(
+                &env,
+                "Pool Stable: Swap: Trying to swap with more than the allowed referral fee"
+            );
+            panic_with_error!(&env, ContractError::ReferralFeeTooHigh);
+        }
+    }
+
     if let Some(max_spread) = max_spread {
         if !(0..=config.max_allowed_spread_bps).contains(&max_spread) {
             log!(&env, "Pool Stable: do swap: max spread is out of bounds");
@@ -768,6 +1969,8 @@ fn do_swap(
 
     let max_spread = Decimal::bps(max_spread.map_or_else(|| config.max_allowed_spread_bps, |x| x));
 
+    update_price_accumulators(&env);
+
     let pool_balance_a = utils::get_pool_balance_a(&env);
     let pool_balance_b = utils::get_pool_balance_b(&env);
 
@@ -793,14 +1996,19 @@ fn do_swap(
 
     let (return_amount, spread_amount, commission_amount) = compute_swap(
         &env,
-        pool_balance_sell as u128,
-        get_precisions(&env, &sell_token),
-        pool_balance_buy as u128,
-        get_precisions(&env, &buy_token),
+        &PoolReserve::new(&env, &sell_token, pool_balance_sell as u128),
+        &PoolReserve::new(&env, &buy_token, pool_balance_buy as u128),
         offer_amount as u128,
         config.protocol_fee_rate(),
     );
 
+    // referral fee is taken from the return amount, the same way as the commission
+    let referral_fee_amount = match referral {
+        Some(ref referral) => return_amount * Decimal::bps(referral.fee_bps),
+        None => 0,
+    };
+    let return_amount = return_amount - referral_fee_amount;
+
     if let Some(ask_asset_min_amount) = ask_asset_min_amount {
         if ask_asset_min_amount > return_amount {
             log!(
@@ -814,7 +2022,7 @@ fn do_swap(
     assert_max_spread(
         &env,
         max_spread,
-        return_amount + commission_amount,
+        return_amount + commission_amount + referral_fee_amount,
         spread_amount,
     );
 
@@ -832,23 +2040,35 @@ fn do_swap(
         &return_amount,
     );
 
-    // send commission to fee recipient
+    // send the protocol share of the commission to fee recipient, the rest stays in the pool
+    let protocol_fee_amount = commission_amount * config.protocol_fee_share();
     token_contract::Client::new(&env, &buy_token).transfer(
         &env.current_contract_address(),
         &config.fee_recipient,
-        &commission_amount,
+        &protocol_fee_amount,
     );
 
+    // send referral fee to the referral address
+    if let Some(Referral { address, .. }) = referral {
+        if referral_fee_amount > 0 {
+            token_contract::Client::new(&env, &buy_token).transfer(
+                &env.current_contract_address(),
+                &address,
+                &referral_fee_amount,
+            );
+        }
+    }
+
     // user is offering to sell A, so they will receive B
     // A balance is bigger, B balance is smaller
     let (balance_a, balance_b) = if offer_asset == config.token_a {
         (
             pool_balance_a + offer_amount,
-            pool_balance_b - commission_amount - return_amount,
+            pool_balance_b - protocol_fee_amount - referral_fee_amount - return_amount,
         )
     } else {
         (
-            pool_balance_a - commission_amount - return_amount,
+            pool_balance_a - protocol_fee_amount - referral_fee_amount - return_amount,
             pool_balance_b + offer_amount,
         )
     };
@@ -863,8 +2083,22 @@ fn do_swap(
         .publish(("swap", "return_amount"), return_amount);
     env.events()
         .publish(("swap", "spread_amount"), spread_amount);
+    env.events()
+        .publish(("swap", "referral_fee_amount"), referral_fee_amount);
+    env.events()
+        .publish(("swap", "protocol_fee_amount"), protocol_fee_amount);
+    env.events().publish(
+        ("swap", "lp_fee_amount"),
+        commission_amount - protocol_fee_amount,
+    );
 
-    return_amount
+    SwapResponse {
+        ask_amount: return_amount,
+        commission_amount,
+        protocol_fee_amount,
+        referral_fee_amount,
+        spread_amount,
+    }
 }
 
 /// This function asserts that the spread (slippage) does not exceed a given maximum.
@@ -883,11 +2117,89 @@ pub fn assert_max_spread(env: &Env, max_spread: Decimal, return_amount: i128, sp
     }
 }
 
+/// A reserve of the pool together with what is needed to express its amounts in the units of the
+/// invariant, which are 18 decimals multiplied by the rate of the token.
+pub struct PoolReserve {
+    pub amount: u128,
+    pub precision: u32,
+    pub rate: Decimal,
+}
+
+impl PoolReserve {
+    pub fn new(env: &Env, token: &Address, amount: u128) -> Self {
+        PoolReserve {
+            amount,
+            precision: get_precisions(env, token),
+            rate: get_rate(env, token),
+        }
+    }
+
+    /// Like `new`, but returns an error instead of panicking when the rate provider of the token
+    /// fails or returns an invalid rate.
+    pub fn try_new(env: &Env, token: &Address, amount: u128) -> Result<Self, ContractError> {
+        Ok(PoolReserve {
+            amount,
+            precision: get_precisions(env, token),
+            rate: try_get_rate(env, token)?,
+        })
+    }
+
+    /// The reserve in the units of the invariant
+    pub fn scaled(&self, env: &Env) -> u128 {
+        self.to_invariant_units(env, self.amount)
+    }
+
+    pub fn to_invariant_units(&self, env: &Env, amount: u128) -> u128 {
+        U256::from_u128(env, amount)
+            .mul(&U256::from_u128(env, self.rate.atomics() as u128))
+            .div(&U256::from_u128(env, 10u128.pow(self.precision)))
+            .to_u128()
+            .expect("Pool stable: to_invariant_units: conversion to u128 failed")
+    }
+
+    /// Rounds down
+    pub fn to_token_units(&self, env: &Env, value: u128) -> u128 {
+        U256::from_u128(env, value)
+            .mul(&U256::from_u128(env, 10u128.pow(self.precision)))
+            .div(&U256::from_u128(env, self.rate.atomics() as u128))
+            .to_u128()
+            .expect("Pool stable: to_token_units: conversion to u128 failed")
+    }
+}
+
+/// The current rate of `token` from its rate provider, one for tokens without a rate provider.
+pub fn get_rate(env: &Env, token: &Address) -> Decimal {
+    try_get_rate(env, token).unwrap_or_else(|error| {
+        log!(
+            env,
+            "Pool Stable: Rate provider failed or returned an invalid rate"
+        );
+        panic_with_error!(env, error);
+    })
+}
+
+/// The current rate of `token`, `ContractError::InvalidRate` if the rate provider fails or
+/// returns a rate that is not positive.
+pub fn try_get_rate(env: &Env, token: &Address) -> Result<Decimal, ContractError> {
+    let Some(rate_provider) = get_rate_provider(env, token) else {
+        return Ok(Decimal::one());
+    };
+
+    match env.try_invoke_contract::<i128, InvokeError>(
+        &rate_provider,
+        &Symbol::new(env, "query_rate"),
+        Vec::new(env),
+    ) {
+        Ok(Ok(rate)) if rate > 0 => Ok(Decimal::new(rate)),
+        _ => Err(ContractError::InvalidRate),
+    }
+}
+
 /// Computes the result of a swap operation.
 ///
 /// Arguments:
-/// - `offer_pool`: Total amount of offer assets in the pool.
-/// - `ask_pool`: Total amount of ask assets in the pool.
+/// - `offer`: The offer asset reserve of the pool.
+/// - `ask`: The ask asset reserve of the pool.
 /// - `offer_amount`: Amount of offer assets to swap.
 /// - `commission_rate`: Total amount of fees charged for the swap.
 ///
@@ -897,41 +2209,33 @@ pub fn assert_max_spread(env: &Env, max_spread: Decimal, return_amount: i128, sp
 /// - The commission amount, representing the fees charged for the swap.
 pub fn compute_swap(
     env: &Env,
-    offer_pool: u128,
-    offer_pool_precision: u32,
-    ask_pool: u128,
-    ask_pool_precision: u32,
+    offer: &PoolReserve,
+    ask: &PoolReserve,
     offer_amount: u128,
     commission_rate: Decimal,
 ) -> (i128, i128, i128) {
     let amp_parameters = get_amp(env);
     let amp = compute_current_amp(env, &amp_parameters);
 
-    let greatest_precision = get_greatest_precision(env);
-
-    let new_ask_pool = calc_y(
+    let new_ask_pool = ask.to_token_units(
         env,
-        amp as u128,
-        scale_value(
-            offer_pool + offer_amount,
-            greatest_precision,
+        calc_y(
+            env,
+            amp as u128,
+            &[offer.scaled(env), ask.scaled(env)],
+            0,
+            offer.to_invariant_units(env, offer.amount + offer_amount),
+            1,
             DECIMAL_PRECISION,
         ),
-        &[
-            scale_value(offer_pool, offer_pool_precision, DECIMAL_PRECISION),
-            scale_value(ask_pool, ask_pool_precision, DECIMAL_PRECISION),
-        ],
-        greatest_precision,
     );
 
-    let return_amount = ask_pool - new_ask_pool;
-    // We consider swap rate 1:1 in stable swap thus any difference is considered as spread.
-    let spread_amount = if offer_amount > return_amount {
-        offer_amount - return_amount
-    } else {
-        // saturating sub equivalent
-        0
-    };
+    let return_amount = ask.amount - new_ask_pool;
+    // We consider the swap rate to be the rate of the tokens in stable swap, thus any difference
+    // is considered as spread.
+    let spread_amount = ask
+        .to_token_units(env, offer.to_invariant_units(env, offer_amount))
+        .saturating_sub(return_amount);
     let commission_amount = return_amount as i128 * commission_rate;
     // Because of issue #211
     let return_amount = return_amount as i128 - commission_amount;
@@ -941,16 +2245,14 @@ pub fn compute_swap(
 
 /// Returns an amount of offer assets for a specified amount of ask assets.
 ///
-/// * **offer_pool** total amount of offer assets in the pool.
-/// * **ask_pool** total amount of ask assets in the pool.
+/// * **offer** the offer asset reserve of the pool.
+/// * **ask** the ask asset reserve of the pool.
 /// * **ask_amount** amount of ask assets to swap to.
 /// * **commission_rate** total amount of fees charged for the swap.
 pub fn compute_offer_amount(
     env: &Env,
-    offer_pool: u128,
-    offer_pool_precision: u32,
-    ask_pool: u128,
-    ask_pool_precision: u32,
+    offer: &PoolReserve,
+    ask: &PoolReserve,
     ask_amount: u128,
     commission_rate: Decimal,
 ) -> (i128, i128, i128) {
@@ -961,33 +2263,26 @@ pub fn compute_offer_amount(
     let inv_one_minus_commission = Decimal::one() / one_minus_commission;
     let before_commission = inv_one_minus_commission * ask_amount as i128;
 
-    let greatest_precision = get_greatest_precision(env);
-
-    let new_offer_pool = calc_y(
+    let new_offer_pool = offer.to_token_units(
         env,
-        amp as u128,
-        scale_value(
-            ask_pool - before_commission as u128,
-            greatest_precision,
+        calc_y(
+            env,
+            amp as u128,
+            &[offer.scaled(env), ask.scaled(env)],
+            1,
+            ask.to_invariant_units(env, ask.amount - before_commission as u128),
+            0,
             DECIMAL_PRECISION,
         ),
-        &[
-            scale_value(offer_pool, offer_pool_precision, DECIMAL_PRECISION),
-            scale_value(ask_pool, ask_pool_precision, DECIMAL_PRECISION),
-        ],
-        greatest_precision,
     );
 
-    let offer_amount = new_offer_pool - offer_pool;
+    let offer_amount = new_offer_pool - offer.amount;
 
     let ask_before_commission = ask_amount as i128 * inv_one_minus_commission;
-    // We consider swap rate 1:1 in stable swap thus any difference is considered as spread.
-    let spread_amount = if offer_amount > ask_amount {
-        offer_amount - ask_amount
-    } else {
-        // saturating sub equivalent
-        0
-    };
+    // We consider the swap rate to be the rate of the tokens in stable swap, thus any difference
+    // is considered as spread.
+    let spread_amount = offer_amount
+        .saturating_sub(offer.to_token_units(env, ask.to_invariant_units(env, ask_amount)));
 
     // Calculate the commission amount
     let commission_amount: i128 = ask_before_commission * commission_rate;
@@ -999,6 +2294,62 @@ pub fn compute_offer_amount(
     )
 }
 
+/// Marginal price of token A in token B and of token B in token A, in raw token units.
+/// `None` while the pool is empty, an error if a rate provider fails.
+fn get_spot_prices(env: &Env) -> Result<Option<(Decimal, Decimal)>, ContractError> {
+    let pool_balance_a = utils::get_pool_balance_a(env);
+    let pool_balance_b = utils::get_pool_balance_b(env);
+    if pool_balance_a == 0 || pool_balance_b == 0 {
+        return Ok(None);
+    }
+
+    let config = get_config(env);
+    let reserve_a = PoolReserve::try_new(env, &config.token_a, pool_balance_a as u128)?;
+    let reserve_b = PoolReserve::try_new(env, &config.token_b, pool_balance_b as u128)?;
+    let amp = compute_current_amp(env, &get_amp(env));
+
+    // the curve works on balances scaled to the same precision and rate
+    let normalized_price_a = compute_spot_price(
+        env,
+        amp as u128,
+        &[reserve_a.scaled(env), reserve_b.scaled(env)],
+    );
+
+    // price_a = normalized_price_a * rate_a / rate_b * 10^precision_b / 10^precision_a, in U256
+    // and saturated, since every operation of the pool updates the accumulators first
+    let numerator = U256::from_u128(env, normalized_price_a.atomics() as u128)
+        .mul(&U256::from_u128(env, reserve_a.rate.atomics() as u128))
+        .mul(&U256::from_u32(env, 10).pow(reserve_b.precision));
+    let denominator = U256::from_u128(env, Decimal::one().atomics() as u128)
+        .mul(&U256::from_u128(env, reserve_b.rate.atomics() as u128))
+        .mul(&U256::from_u32(env, 10).pow(reserve_a.precision));
+
+    Ok(Some((
+        saturating_price(env, &numerator, &denominator),
+        saturating_price(env, &denominator, &numerator),
+    )))
+}
+
+/// Accumulates the current price up to now. Has to be called before the reserves change,
+/// so a trade only moves the TWAP once time has passed.
+///
+/// Without a valid rate there is no price, the update is skipped instead of blocking the
+/// operation, so withdrawals stay available while a rate provider fails. The elapsed time is
+/// accumulated at the price of the next successful update.
+fn update_price_accumulators(env: &Env) {
+    let Ok(prices) = get_spot_prices(env) else {
+        return;
+    };
+    let accumulator =
+        utils::get_price_accumulator(env).accumulate(env.ledger().timestamp(), prices);
+    utils::save_price_accumulator(env, &accumulator);
+
+    let mut observations = utils::get_observations(env);
+    if record_observation(&mut observations, &accumulator) {
+        utils::save_observations(env, &observations);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
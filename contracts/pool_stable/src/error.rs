@@ -23,4 +23,18 @@ pub enum ContractError {
     DivisionByZero = 17,
     InvalidAMP = 18,
     TransactionAfterTimestampDeadline = 19,
+    AmpRampTooEarly = 20,
+    AmpRampTimeTooShort = 21,
+    AmpChangeTooBig = 22,
+    ReferralFeeTooHigh = 23,
+    TwapWindowNotCovered = 24,
+    WithdrawSingleEmptiesPool = 25,
+    FlashLoanExceedsReserves = 26,
+    FlashLoanNotRepaid = 27,
+    ContractPaused = 28,
+    InvalidRate = 29,
+    RateProviderChangeWithLiquidity = 30,
+    ProvideLiquiditySharesBelowMinimum = 31,
+    WithdrawImbalancedInvalidAmounts = 32,
+    WithdrawImbalancedSharesAboveMaximum = 33,
 }
@@ -1,39 +1,23 @@
+use soroban_decimal::Decimal;
 use soroban_sdk::{log, panic_with_error, Env, U256};
 
 use crate::{error::ContractError, storage::AmplifierParameters, DECIMAL_PRECISION};
 
-// TODO: Those parameters will be used for updating AMP function later
-#[allow(dead_code)]
+/// The maximum factor by which AMP can be changed in a single ramp.
 pub const MAX_AMP_CHANGE: u64 = 10;
-#[allow(dead_code)]
+/// The minimum duration (in seconds) of a ramp and between two consecutive ramps.
 pub const MIN_AMP_CHANGING_TIME: u64 = 86400;
 pub const AMP_PRECISION: u64 = 100;
+/// The maximum number of coins the invariant is computed for
+pub const MAX_COINS: usize = 8;
 
 /// The maximum number of calculation steps for Newton's method.
 const ITERATIONS: u8 = 64;
-/// N = 2
-const N_COINS: u128 = 2;
-/// N = 2 with DECIMAL_PRECISION (18) digits
-const N_COINS_PRECISION: u128 = 2000000000000000000;
 /// 1*10**18
 const DECIMAL_FRACTIONAL: u128 = 1_000_000_000_000_000_000;
 /// 1e-6
 const TOL: u128 = 1000000000000;
 
-pub fn scale_value(atomics: u128, decimal_places: u32, target_decimal_places: u32) -> u128 {
-    const TEN: u128 = 10;
-
-    if decimal_places < target_decimal_places {
-        let factor = TEN.pow(target_decimal_places - decimal_places);
-        atomics
-            .checked_mul(factor)
-            .expect("Multiplication overflow")
-    } else {
-        let factor = TEN.pow(decimal_places - target_decimal_places);
-        atomics.checked_div(factor).expect("Division overflow")
-    }
-}
-
 fn abs_diff(a: &U256, b: &U256) -> U256 {
     if a < b {
         b.sub(a)
@@ -67,17 +51,19 @@ pub(crate) fn compute_current_amp(env: &Env, amp_params: &AmplifierParameters) -
     }
 }
 
-/// Computes the stableswap invariant (D).
+/// Computes the stableswap invariant (D) of any number of coins.
 ///
 /// * **Equation**
 ///
 /// A * sum(x_i) * n**n + D = A * D * n**n + D**(n+1) / (n**n * prod(x_i))
 pub fn compute_d(env: &Env, amp: u128, pools: &[u128]) -> U256 {
-    let leverage = U256::from_u128(env, (amp / AMP_PRECISION as u128) * N_COINS_PRECISION);
-    let amount_a_times_coins = pools[0] * N_COINS;
-    let amount_b_times_coins = pools[1] * N_COINS;
+    let n_coins = pools.len() as u128;
+    let leverage = U256::from_u128(
+        env,
+        (amp / AMP_PRECISION as u128) * n_coins * DECIMAL_FRACTIONAL,
+    );
 
-    let sum_x = U256::from_u128(env, pools[0] + pools[1]); // sum(x_i), a.k.a S
+    let sum_x = U256::from_u128(env, pools.iter().sum()); // sum(x_i), a.k.a S
     let zero = U256::from_u128(env, 0u128);
     if sum_x == zero {
         return zero;
@@ -88,12 +74,14 @@ pub fn compute_d(env: &Env, amp: u128, pools: &[u128]) -> U256 {
 
     // Newton's method to approximate D
     for _ in 0..ITERATIONS {
-        let d_product = d.pow(3).div(&U256::from_u128(
-            env,
-            amount_a_times_coins * amount_b_times_coins,
-        ));
+        // D**(n+1) / (n**n * prod(x_i)), built up one coin at a time so it never overflows
+        let d_product = pools.iter().fold(d.clone(), |d_product, amount| {
+            d_product
+                .mul(&d)
+                .div(&U256::from_u128(env, amount * n_coins))
+        });
         d_previous = d.clone();
-        d = calculate_step(env, &d, &leverage, &sum_x, &d_product);
+        d = calculate_step(env, n_coins, &d, &leverage, &sum_x, &d_product);
         // Equality with the precision of 1e-6
         if abs_diff(&d, &d_previous) <= U256::from_u128(env, TOL) {
             return d;
@@ -114,6 +102,7 @@ pub fn compute_d(env: &Env, amp: u128, pools: &[u128]) -> U256 {
 /// d = (leverage * sum_x + d_product * n_coins) * initial_d / ((leverage - 1) * initial_d + (n_coins + 1) * d_product)
 fn calculate_step(
     env: &Env,
+    n_coins: u128,
     initial_d: &U256,
     leverage: &U256,
     sum_x: &U256,
@@ -121,7 +110,7 @@ fn calculate_step(
 ) -> U256 {
     // (leverage * sum_x + d_product * n_coins)
     let leverage_mul = leverage.mul(sum_x);
-    let d_p_mul = d_product.mul(&U256::from_u128(env, N_COINS));
+    let d_p_mul = d_product.mul(&U256::from_u128(env, n_coins));
 
     let l_val = leverage_mul.add(&d_p_mul).mul(initial_d);
 
@@ -129,7 +118,7 @@ fn calculate_step(
     let leverage_sub = leverage_mul.add(&leverage.sub(&U256::from_u128(env, 1)));
 
     // (n_coins + 1) * d_product
-    let n_coins_sum = d_product.mul(&(U256::from_u128(env, 3)));
+    let n_coins_sum = d_product.mul(&(U256::from_u128(env, n_coins + 1)));
 
     // Calculate the final step value
     let r_val = leverage_sub.add(&n_coins_sum);
@@ -137,40 +126,114 @@ fn calculate_step(
     l_val.div(&r_val)
 }
 
-/// Compute the swap amount `y` in proportion to `x`.
+/// Computes the marginal price of the first token in units of the second one, i.e. how much of
+/// the second token one unit of the first is worth at the current point of the curve.
+/// Both balances have to be scaled to `DECIMAL_PRECISION`.
+///
+/// * **Equation**
+///
+/// With Ann = A * n, u = x / D and v = y / D:
+///
+/// price = (4 * Ann * u**2 * v**2 + v) / (4 * Ann * u**2 * v**2 + u)
+pub(crate) fn compute_spot_price(env: &Env, amp: u128, pools: &[u128]) -> Decimal {
+    // u and v are expressed with 15 digits, so the products stay well within U256
+    let scale = U256::from_u128(env, 1_000_000_000_000_000);
+
+    let d = compute_d(env, amp, pools);
+    let u = U256::from_u128(env, pools[0]).mul(&scale).div(&d);
+    let v = U256::from_u128(env, pools[1]).mul(&scale).div(&d);
+
+    // Ann * AMP_PRECISION for the two coins, the other terms are multiplied by AMP_PRECISION to match
+    let leverage = U256::from_u128(env, amp * 2);
+    let amp_precision = U256::from_u128(env, AMP_PRECISION as u128);
+    let scale_cubed = scale.pow(3);
+
+    let common = U256::from_u128(env, 4)
+        .mul(&leverage)
+        .mul(&u.pow(2))
+        .mul(&v.pow(2));
+    let numerator = common.add(&v.mul(&scale_cubed).mul(&amp_precision));
+    let denominator = common.add(&u.mul(&scale_cubed).mul(&amp_precision));
+
+    let price = numerator
+        .div(&denominator.div(&U256::from_u128(env, DECIMAL_FRACTIONAL)))
+        .to_u128()
+        .and_then(|price| i128::try_from(price).ok())
+        .unwrap_or(i128::MAX);
+
+    Decimal::new(price)
+}
+
+/// Compute the new balance `y` of the coin at `ask_index` after the balance of the coin at
+/// `offer_index` changed to `new_amount`, keeping the invariant of the current balances `xp`.
+pub(crate) fn calc_y(
+    env: &Env,
+    amp: u128,
+    xp: &[u128],
+    offer_index: usize,
+    new_amount: u128,
+    ask_index: usize,
+    target_precision: u32,
+) -> u128 {
+    let d = compute_d(env, amp, xp);
+
+    let mut new_xp = [0u128; MAX_COINS];
+    new_xp[..xp.len()].copy_from_slice(xp);
+    new_xp[offer_index] = new_amount;
+
+    calc_y_for_d(
+        env,
+        amp,
+        &new_xp[..xp.len()],
+        ask_index,
+        d,
+        target_precision,
+    )
+}
+
+/// Compute the balance `y` of the coin at `ask_index` for which the other balances of `xp` are
+/// on the curve of the given invariant `d`. The current balance at `ask_index` is ignored. Used
+/// when the invariant itself changes, e.g. when liquidity is withdrawn in a single token.
 ///
 /// * **Solve for y**
 ///
 /// y**2 + y * (sum' - (A*n**n - 1) * D / (A * n**n)) = D ** (n + 1) / (n ** (2 * n) * prod' * A)
 ///
 /// y**2 + b*y = c
-pub(crate) fn calc_y(
+pub(crate) fn calc_y_for_d(
     env: &Env,
     amp: u128,
-    new_amount: u128,
     xp: &[u128],
+    ask_index: usize,
+    d: U256,
     target_precision: u32,
 ) -> u128 {
-    let n_coins = U256::from_u128(env, N_COINS);
-    let new_amount = U256::from_u128(env, new_amount);
-
-    let d = compute_d(env, amp, xp);
-    let leverage = U256::from_u128(env, amp * DECIMAL_FRACTIONAL * N_COINS);
+    let n_coins = U256::from_u128(env, xp.len() as u128);
+    let leverage = U256::from_u128(env, amp * DECIMAL_FRACTIONAL * xp.len() as u128);
     let amp_prec = U256::from_u128(env, AMP_PRECISION as u128 * DECIMAL_FRACTIONAL);
 
-    let c = d
-        .pow(3)
-        .mul(&amp_prec)
-        .div(&new_amount.mul(&n_coins.mul(&n_coins)).mul(&leverage));
+    // sum' and D**(n+1) / (n**(n-1) * prod'), without the coin being solved for
+    let mut sum = U256::from_u128(env, 0);
+    let mut c = d.clone();
+    for (index, amount) in xp.iter().enumerate() {
+        if index == ask_index {
+            continue;
+        }
+        let amount = U256::from_u128(env, *amount);
+        sum = sum.add(&amount);
+        c = c.mul(&d).div(&amount.mul(&n_coins));
+    }
 
-    let b = new_amount.add(&d.mul(&amp_prec).div(&leverage));
+    let c = c.mul(&d).mul(&amp_prec).div(&n_coins.mul(&leverage));
+    let b = sum.add(&d.mul(&amp_prec).div(&leverage));
 
     // Solve for y by approximating: y**2 + b*y = c
+    let two = U256::from_u128(env, 2);
     let mut y_prev;
     let mut y = d.clone();
     for _ in 0..ITERATIONS {
         y_prev = y.clone();
-        y = (y.pow(2).add(&c)).div(&(y.mul(&n_coins).add(&b).sub(&d)));
+        y = (y.pow(2).add(&c)).div(&(y.mul(&two).add(&b).sub(&d)));
         if abs_diff(&y, &y_prev) <= U256::from_u128(env, TOL) {
             let divisor = 10u128.pow(DECIMAL_PRECISION - target_precision);
             return y
@@ -1,9 +1,10 @@
 use soroban_sdk::{
     contracttype, symbol_short, xdr::ToXdr, Address, Bytes, BytesN, ConversionError, Env, Symbol,
-    TryFromVal, Val,
+    TryFromVal, Val, Vec,
 };
 
 use crate::token_contract;
+use phoenix::oracle::Observation;
 use soroban_decimal::Decimal;
 
 #[derive(Clone, Copy)]
@@ -17,6 +18,15 @@ pub enum DataKey {
     Amp = 5,
     MaxPrecision = 6,
     TokenPrecision = 7,
+    PriceAccumulator = 8,
+    Observations = 9,
+    Paused = 10,
+    Guardian = 11,
+    Factory = 12,
+    RateProvider = 13,
+    MaxReferralBps = 14,
+    ProtocolFeeShareBps = 15,
+    FlashLoanFeeBps = 16,
 }
 
 impl TryFromVal<Env, DataKey> for Val {
@@ -51,6 +61,14 @@ pub struct Config {
     pub max_allowed_slippage_bps: i64,
     /// The maximum amount of spread (in bps) that is tolerated during swap
     pub max_allowed_spread_bps: i64,
+    /// The maximum allowed percentage (in bps) for referral fee
+    pub max_referral_bps: i64,
+    /// The share (in bps) of the swap commission that is sent to the fee recipient.
+    /// The rest of the commission stays in the pool and accrues to the liquidity providers
+    pub protocol_fee_share_bps: i64,
+    /// The fee (in bps) charged on the borrowed amount of a flash loan.
+    /// The whole fee stays in the pool and accrues to the liquidity providers
+    pub flash_loan_fee_bps: i64,
 }
 const CONFIG: Symbol = symbol_short!("CONFIG");
 
@@ -59,17 +77,83 @@ impl Config {
         Decimal::bps(self.total_fee_bps)
     }
 
+    pub fn protocol_fee_share(&self) -> Decimal {
+        Decimal::bps(self.protocol_fee_share_bps)
+    }
+
+    pub fn flash_loan_fee(&self) -> Decimal {
+        Decimal::bps(self.flash_loan_fee_bps)
+    }
+
     pub fn max_allowed_slippage(&self) -> Decimal {
         Decimal::bps(self.max_allowed_slippage_bps)
     }
 }
 
+/// The part of `Config` stored under `CONFIG`, in the layout of the pools deployed before
+/// `Config` was extended. Parameters added later are stored under their own keys, so pools
+/// upgraded to this version can still read their configuration.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StoredConfig {
+    pub token_a: Address,
+    pub token_b: Address,
+    pub share_token: Address,
+    pub stake_contract: Address,
+    pub pool_type: PairType,
+    pub total_fee_bps: i64,
+    pub fee_recipient: Address,
+    pub max_allowed_slippage_bps: i64,
+    pub max_allowed_spread_bps: i64,
+}
+
 pub fn get_config(env: &Env) -> Config {
-    env.storage().instance().get(&CONFIG).unwrap()
+    let storage = env.storage().instance();
+    let stored: StoredConfig = storage.get(&CONFIG).unwrap();
+
+    Config {
+        // pools upgraded from before these parameters existed pay no referral fees until the
+        // admin sets a maximum, keep sending the whole commission to the fee recipient and
+        // charge the swap fee on flash loans
+        max_referral_bps: storage.get(&DataKey::MaxReferralBps).unwrap_or(0),
+        protocol_fee_share_bps: storage.get(&DataKey::ProtocolFeeShareBps).unwrap_or(10_000),
+        flash_loan_fee_bps: storage
+            .get(&DataKey::FlashLoanFeeBps)
+            .unwrap_or(stored.total_fee_bps),
+        token_a: stored.token_a,
+        token_b: stored.token_b,
+        share_token: stored.share_token,
+        stake_contract: stored.stake_contract,
+        pool_type: stored.pool_type,
+        total_fee_bps: stored.total_fee_bps,
+        fee_recipient: stored.fee_recipient,
+        max_allowed_slippage_bps: stored.max_allowed_slippage_bps,
+        max_allowed_spread_bps: stored.max_allowed_spread_bps,
+    }
 }
 
 pub fn save_config(env: &Env, config: Config) {
-    env.storage().instance().set(&CONFIG, &config);
+    let storage = env.storage().instance();
+    storage.set(&DataKey::MaxReferralBps, &config.max_referral_bps);
+    storage.set(
+        &DataKey::ProtocolFeeShareBps,
+        &config.protocol_fee_share_bps,
+    );
+    storage.set(&DataKey::FlashLoanFeeBps, &config.flash_loan_fee_bps);
+    storage.set(
+        &CONFIG,
+        &StoredConfig {
+            token_a: config.token_a,
+            token_b: config.token_b,
+            share_token: config.share_token,
+            stake_contract: config.stake_contract,
+            pool_type: config.pool_type,
+            total_fee_bps: config.total_fee_bps,
+            fee_recipient: config.fee_recipient,
+            max_allowed_slippage_bps: config.max_allowed_slippage_bps,
+            max_allowed_spread_bps: config.max_allowed_spread_bps,
+        },
+    );
 }
 
 pub fn get_greatest_precision(env: &Env) -> u32 {
@@ -86,6 +170,26 @@ pub fn get_precisions(env: &Env, token: &Address) -> u32 {
         .unwrap()
 }
 
+/// The contract returning the current rate of `token`, if it has one
+pub fn get_rate_provider(env: &Env, token: &Address) -> Option<Address> {
+    env.storage()
+        .instance()
+        .get(&(DataKey::RateProvider, token))
+}
+
+pub fn save_rate_provider(env: &Env, token: &Address, rate_provider: &Option<Address>) {
+    match rate_provider {
+        Some(rate_provider) => env
+            .storage()
+            .instance()
+            .set(&(DataKey::RateProvider, token), rate_provider),
+        None => env
+            .storage()
+            .instance()
+            .remove(&(DataKey::RateProvider, token)),
+    }
+}
+
 pub fn save_greatest_precision(env: &Env, token1: &Address, token2: &Address) {
     let precision1 = token_contract::Client::new(env, token1).decimals();
     let precision2 = token_contract::Client::new(env, token2).decimals();
@@ -131,6 +235,15 @@ pub struct Asset {
     pub amount: i128,
 }
 
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Referral {
+    /// Address of the referral
+    pub address: Address,
+    /// fee in bps, later parsed to percentage
+    pub fee_bps: i64,
+}
+
 /// This struct is used to return a query result with the total amount of LP tokens and assets in a specific pool.
 #[contracttype]
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -153,11 +266,26 @@ pub struct StableLiquidityPoolInfo {
     pub total_fee_bps: i64,
 }
 
+/// The amounts of an executed swap, as returned by `swap`.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SwapResponse {
+    /// The amount sent to the sender, after commission and referral fee
+    pub ask_amount: i128,
+    pub commission_amount: i128,
+    /// The part of the commission sent to the fee recipient, the rest stays in the pool
+    pub protocol_fee_amount: i128,
+    pub referral_fee_amount: i128,
+    pub spread_amount: i128,
+}
+
 #[contracttype]
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct SimulateSwapResponse {
     pub ask_amount: i128,
     pub commission_amount: i128,
+    /// The part of the commission sent to the fee recipient, the rest stays in the pool
+    pub protocol_fee_amount: i128,
     pub spread_amount: i128,
     pub total_return: i128,
 }
@@ -170,6 +298,42 @@ pub struct SimulateReverseSwapResponse {
     pub spread_amount: i128,
 }
 
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SimulateWithdrawSingleResponse {
+    /// The total amount of the ask token paid out, after the commission
+    pub ask_amount: i128,
+    /// The commission charged on the part paid out above the proportional share of the ask token
+    pub commission_amount: i128,
+    /// The part of the commission sent to the fee recipient, the rest stays in the pool
+    pub protocol_fee_amount: i128,
+    /// The difference between the proportional share of the other token and the amount of the
+    /// ask token it was exchanged for
+    pub spread_amount: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SimulateProvideLiquidityResponse {
+    /// The pool shares minted to the depositor
+    pub shares: i128,
+    /// The imbalance fee charged on token A, the protocol part of it is sent to the fee recipient
+    pub fee_a: i128,
+    /// The imbalance fee charged on token B, the protocol part of it is sent to the fee recipient
+    pub fee_b: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SimulateWithdrawImbalancedResponse {
+    /// The pool shares burned for the withdrawal
+    pub shares: i128,
+    /// The imbalance fee charged on token A, the protocol part of it is sent to the fee recipient
+    pub fee_a: i128,
+    /// The imbalance fee charged on token B, the protocol part of it is sent to the fee recipient
+    pub fee_b: i128,
+}
+
 pub mod utils {
     use super::*;
 
@@ -201,6 +365,18 @@ pub mod utils {
         e.storage().instance().set(&DataKey::Admin, &address)
     }
 
+    pub fn save_factory(e: &Env, address: Address) {
+        e.storage().instance().set(&DataKey::Factory, &address)
+    }
+
+    pub fn save_guardian(e: &Env, address: Address) {
+        e.storage().instance().set(&DataKey::Guardian, &address)
+    }
+
+    pub fn set_paused(e: &Env, paused: bool) {
+        e.storage().instance().set(&DataKey::Paused, &paused)
+    }
+
     pub fn save_total_shares(e: &Env, amount: i128) {
         e.storage().instance().set(&DataKey::TotalShares, &amount)
     }
@@ -234,6 +410,22 @@ pub mod utils {
         e.storage().instance().get(&DataKey::Admin).unwrap()
     }
 
+    /// `None` for pools upgraded from a version that didn't store the factory
+    pub fn get_factory(e: &Env) -> Option<Address> {
+        e.storage().instance().get(&DataKey::Factory)
+    }
+
+    pub fn get_guardian(e: &Env) -> Option<Address> {
+        e.storage().instance().get(&DataKey::Guardian)
+    }
+
+    pub fn is_paused(e: &Env) -> bool {
+        e.storage()
+            .instance()
+            .get(&DataKey::Paused)
+            .unwrap_or(false)
+    }
+
     pub fn get_total_shares(e: &Env) -> i128 {
         e.storage().instance().get(&DataKey::TotalShares).unwrap()
     }
@@ -245,6 +437,34 @@ pub mod utils {
         e.storage().instance().get(&DataKey::ReserveB).unwrap()
     }
 
+    // the price history is kept in persistent storage, so the ring buffer is not loaded
+    // together with the instance on every call
+    pub fn get_price_accumulator(e: &Env) -> Observation {
+        e.storage()
+            .persistent()
+            .get(&DataKey::PriceAccumulator)
+            .unwrap_or_else(|| Observation::new(e.ledger().timestamp()))
+    }
+
+    pub fn save_price_accumulator(e: &Env, accumulator: &Observation) {
+        e.storage()
+            .persistent()
+            .set(&DataKey::PriceAccumulator, accumulator)
+    }
+
+    pub fn get_observations(e: &Env) -> Vec<Observation> {
+        e.storage()
+            .persistent()
+            .get(&DataKey::Observations)
+            .unwrap_or(Vec::new(e))
+    }
+
+    pub fn save_observations(e: &Env, observations: &Vec<Observation>) {
+        e.storage()
+            .persistent()
+            .set(&DataKey::Observations, observations)
+    }
+
     pub fn get_balance(e: &Env, contract: &Address) -> i128 {
         token_contract::Client::new(e, contract).balance(&e.current_contract_address())
     }
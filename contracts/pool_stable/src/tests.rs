@@ -1,6 +1,9 @@
 mod config;
+mod flash_loan;
 mod liquidity;
+mod oracle;
 mod queries;
+mod rate_provider;
 mod setup;
 mod stake_deployment;
 mod swap;
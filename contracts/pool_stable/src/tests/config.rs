@@ -1,8 +1,11 @@
 extern crate std;
-use soroban_sdk::{testutils::Address as _, Address, Env};
+use soroban_sdk::{
+    testutils::{Address as _, Ledger},
+    Address, Env,
+};
 
 use super::setup::{deploy_stable_liquidity_pool_contract, deploy_token_contract};
-use crate::storage::{Config, PairType};
+use crate::storage::{Config, DataKey, PairType};
 
 #[test]
 fn update_config() {
@@ -51,6 +54,9 @@ fn update_config() {
             fee_recipient: user1,
             max_allowed_slippage_bps: 500,
             max_allowed_spread_bps: 200,
+            max_referral_bps: 5_000,
+            protocol_fee_share_bps: 10_000,
+            flash_loan_fee_bps: 0,
         }
     );
 
@@ -62,6 +68,9 @@ fn update_config() {
         &Some(admin2.clone()),
         &None,
         &None,
+        &None,
+        &None,
+        &None,
     );
     assert_eq!(
         pool.query_config(),
@@ -75,11 +84,24 @@ fn update_config() {
             fee_recipient: admin2.clone(),
             max_allowed_slippage_bps: 500,
             max_allowed_spread_bps: 200,
+            max_referral_bps: 5_000,
+            protocol_fee_share_bps: 10_000,
+            flash_loan_fee_bps: 0,
         }
     );
 
     // update slippage and spread
-    pool.update_config(&admin1, &None, &None, &None, &Some(5_000i64), &Some(500));
+    pool.update_config(
+        &admin1,
+        &None,
+        &None,
+        &None,
+        &Some(5_000i64),
+        &Some(500),
+        &None,
+        &None,
+        &None,
+    );
     assert_eq!(
         pool.query_config(),
         Config {
@@ -92,8 +114,39 @@ fn update_config() {
             fee_recipient: admin2,
             max_allowed_slippage_bps: 5_000,
             max_allowed_spread_bps: 500,
+            max_referral_bps: 5_000,
+            protocol_fee_share_bps: 10_000,
+            flash_loan_fee_bps: 0,
         }
     );
+
+    // keep a part of the commission in the pool
+    pool.update_config(
+        &admin1,
+        &None,
+        &None,
+        &None,
+        &None,
+        &None,
+        &None,
+        &Some(2_500i64),
+        &None,
+    );
+    assert_eq!(pool.query_config().protocol_fee_share_bps, 2_500);
+
+    // charge flash loans a fee of their own
+    pool.update_config(
+        &admin1,
+        &None,
+        &None,
+        &None,
+        &None,
+        &None,
+        &None,
+        &None,
+        &Some(9i64),
+    );
+    assert_eq!(pool.query_config().flash_loan_fee_bps, 9);
 }
 
 #[test]
@@ -135,9 +188,60 @@ fn update_config_unauthorized() {
         &Some(admin2.clone()),
         &None,
         &None,
+        &None,
+        &None,
+        &None,
     );
 }
 
+#[test]
+fn update_config_without_admin_auth_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let fee_recipient = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let pool = deploy_stable_liquidity_pool_contract(
+        &env,
+        Some(admin.clone()),
+        (&token1.address, &token2.address),
+        30i64,
+        fee_recipient.clone(),
+        500,
+        200,
+        Address::generate(&env),
+        Address::generate(&env),
+        None,
+    );
+
+    // passing the admin address is not enough without the admin's signature
+    env.set_auths(&[]);
+    let attacker = Address::generate(&env);
+    assert!(pool
+        .try_update_config(
+            &admin,
+            &None,
+            &Some(0i64),
+            &Some(attacker),
+            &None,
+            &None,
+            &None,
+            &Some(0i64),
+            &None,
+        )
+        .is_err());
+
+    let config = pool.query_config();
+    assert_eq!(config.total_fee_bps, 30);
+    assert_eq!(config.fee_recipient, fee_recipient);
+}
+
 #[test]
 fn update_config_update_admin() {
     let env = Env::default();
@@ -171,13 +275,33 @@ fn update_config_update_admin() {
     );
 
     // update admin to new admin
-    pool.update_config(&admin1, &Some(admin2.clone()), &None, &None, &None, &None);
+    pool.update_config(
+        &admin1,
+        &Some(admin2.clone()),
+        &None,
+        &None,
+        &None,
+        &None,
+        &None,
+        &None,
+        &None,
+    );
 
     let share_token_address = pool.query_share_token_address();
     let stake_token_address = pool.query_stake_contract_address();
 
     // now update succeeds
-    pool.update_config(&admin2, &None, &None, &None, &None, &Some(3_000_000));
+    pool.update_config(
+        &admin2,
+        &None,
+        &None,
+        &None,
+        &None,
+        &Some(3_000_000),
+        &None,
+        &None,
+        &None,
+    );
     assert_eq!(
         pool.query_config(),
         Config {
@@ -190,6 +314,9 @@ fn update_config_update_admin() {
             fee_recipient: user1,
             max_allowed_slippage_bps: 500,
             max_allowed_spread_bps: 3_000_000,
+            max_referral_bps: 5_000,
+            protocol_fee_share_bps: 10_000,
+            flash_loan_fee_bps: 0,
         }
     );
 }
@@ -234,6 +361,47 @@ fn update_config_too_high_fees() {
         &Some(admin2.clone()),
         &None,
         &None,
+        &None,
+        &None,
+        &None,
+    );
+}
+
+#[test]
+#[should_panic(expected = "Pool Stable: UpdateConfig: Invalid protocol_fee_share_bps")]
+fn update_config_too_high_protocol_fee_share() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let pool = deploy_stable_liquidity_pool_contract(
+        &env,
+        Some(admin.clone()),
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        500,
+        200,
+        Address::generate(&env),
+        Address::generate(&env),
+        None,
+    );
+
+    pool.update_config(
+        &admin,
+        &None,
+        &None,
+        &None,
+        &None,
+        &None,
+        &None,
+        &Some(10_001i64),
+        &None,
     );
 }
 
@@ -269,3 +437,443 @@ fn initialize_with_incorrect_amp() {
         0, // init AMP
     );
 }
+
+#[test]
+fn ramp_amp_changes_amp_gradually() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let pool = deploy_stable_liquidity_pool_contract(
+        &env,
+        Some(admin.clone()),
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+        10u64,
+    );
+    assert_eq!(pool.query_current_amp(), 10);
+
+    // ramp from 10 to 50 over the course of two days
+    env.ledger().with_mut(|li| li.timestamp = 86_400);
+    pool.ramp_amp(&admin, &50, &(86_400 * 3));
+    assert_eq!(pool.query_current_amp(), 10);
+
+    // halfway through the ramp
+    env.ledger().with_mut(|li| li.timestamp = 86_400 * 2);
+    assert_eq!(pool.query_current_amp(), 30);
+
+    // ramp is finished
+    env.ledger().with_mut(|li| li.timestamp = 86_400 * 4);
+    assert_eq!(pool.query_current_amp(), 50);
+
+    // and we can ramp down again
+    pool.ramp_amp(&admin, &20, &(86_400 * 6));
+    env.ledger().with_mut(|li| li.timestamp = 86_400 * 5);
+    assert_eq!(pool.query_current_amp(), 35);
+}
+
+#[test]
+fn stop_ramp_amp_freezes_current_amp() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let pool = deploy_stable_liquidity_pool_contract(
+        &env,
+        Some(admin.clone()),
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+        10u64,
+    );
+
+    env.ledger().with_mut(|li| li.timestamp = 86_400);
+    pool.ramp_amp(&admin, &100, &(86_400 * 4));
+
+    env.ledger().with_mut(|li| li.timestamp = 86_400 * 2);
+    assert_eq!(pool.query_current_amp(), 40);
+    pool.stop_ramp_amp(&admin);
+
+    env.ledger().with_mut(|li| li.timestamp = 86_400 * 5);
+    assert_eq!(pool.query_current_amp(), 40);
+}
+
+#[test]
+#[should_panic(expected = "Pool Stable: RampAmp: Unauthorized")]
+fn ramp_amp_unauthorized() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let pool = deploy_stable_liquidity_pool_contract(
+        &env,
+        Some(admin),
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+        10u64,
+    );
+
+    env.ledger().with_mut(|li| li.timestamp = 86_400);
+    pool.ramp_amp(&Address::generate(&env), &50, &(86_400 * 3));
+}
+
+#[test]
+#[should_panic(expected = "Pool Stable: RampAmp: AMP can't be changed more often than once per")]
+fn ramp_amp_too_early_after_initialization() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let pool = deploy_stable_liquidity_pool_contract(
+        &env,
+        Some(admin.clone()),
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+        10u64,
+    );
+
+    env.ledger().with_mut(|li| li.timestamp = 86_399);
+    pool.ramp_amp(&admin, &50, &(86_400 * 3));
+}
+
+#[test]
+#[should_panic(expected = "Pool Stable: RampAmp: AMP ramp must last at least")]
+fn ramp_amp_too_short() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let pool = deploy_stable_liquidity_pool_contract(
+        &env,
+        Some(admin.clone()),
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+        10u64,
+    );
+
+    env.ledger().with_mut(|li| li.timestamp = 86_400);
+    pool.ramp_amp(&admin, &50, &(86_400 * 2 - 1));
+}
+
+#[test]
+#[should_panic(expected = "Pool Stable: RampAmp: AMP can't be changed by more than")]
+fn ramp_amp_change_too_big() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let pool = deploy_stable_liquidity_pool_contract(
+        &env,
+        Some(admin.clone()),
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+        10u64,
+    );
+
+    env.ledger().with_mut(|li| li.timestamp = 86_400);
+    pool.ramp_amp(&admin, &101, &(86_400 * 3));
+}
+
+#[test]
+fn pause_blocks_swaps_but_not_withdrawals() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let guardian = Address::generate(&env);
+    let user = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let pool = deploy_stable_liquidity_pool_contract(
+        &env,
+        Some(admin.clone()),
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+        None,
+    );
+
+    token1.mint(&user, &10_000);
+    token2.mint(&user, &10_000);
+    pool.provide_liquidity(&user, &10_000, &10_000, &None, &None, &None::<u64>);
+
+    assert!(!pool.query_paused());
+    pool.set_guardian(&guardian);
+    assert_eq!(pool.query_guardian(), Some(guardian.clone()));
+
+    pool.pause(&guardian);
+    assert!(pool.query_paused());
+
+    // liquidity providers can always leave
+    pool.withdraw_liquidity(&user, &10_000, &1, &1, &None::<u64>);
+    assert_eq!(token1.balance(&user), 5_000);
+    assert_eq!(token2.balance(&user), 5_000);
+
+    pool.unpause(&admin);
+    assert!(!pool.query_paused());
+    pool.swap(
+        &user,
+        &None,
+        &token1.address,
+        &10,
+        &None,
+        &Some(1_000),
+        &None::<u64>,
+    );
+    assert_eq!(token1.balance(&user), 4_990);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #28)")]
+fn swap_when_paused_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let factory = Address::generate(&env);
+    let user = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let pool = deploy_stable_liquidity_pool_contract(
+        &env,
+        Some(admin.clone()),
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        factory.clone(),
+        None,
+    );
+
+    token1.mint(&user, &1_010);
+    token2.mint(&user, &1_000);
+    pool.provide_liquidity(&user, &1_000, &1_000, &None, &None, &None::<u64>);
+
+    // the factory that deployed the pool may pause it as well
+    pool.pause(&factory);
+
+    pool.swap(
+        &user,
+        &None,
+        &token1.address,
+        &10,
+        &None,
+        &Some(1_000),
+        &None::<u64>,
+    );
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #28)")]
+fn provide_liquidity_when_paused_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let pool = deploy_stable_liquidity_pool_contract(
+        &env,
+        Some(admin.clone()),
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+        None,
+    );
+
+    pool.pause(&admin);
+
+    token1.mint(&user, &1_000);
+    token2.mint(&user, &1_000);
+    pool.provide_liquidity(&user, &1_000, &1_000, &None, &None, &None::<u64>);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #11)")]
+fn pause_by_stranger_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let pool = deploy_stable_liquidity_pool_contract(
+        &env,
+        Some(admin.clone()),
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+        None,
+    );
+
+    pool.pause(&Address::generate(&env));
+}
+
+#[test]
+fn config_of_upgraded_pool_falls_back_to_defaults() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let pool = deploy_stable_liquidity_pool_contract(
+        &env,
+        Some(admin.clone()),
+        (&token1.address, &token2.address),
+        30i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+        None,
+    );
+
+    // pools deployed before these parameters existed only have the baseline config stored
+    env.as_contract(&pool.address, || {
+        let storage = env.storage().instance();
+        storage.remove(&DataKey::MaxReferralBps);
+        storage.remove(&DataKey::ProtocolFeeShareBps);
+        storage.remove(&DataKey::FlashLoanFeeBps);
+    });
+
+    let config = pool.query_config();
+    assert_eq!(config.total_fee_bps, 30);
+    assert_eq!(config.max_referral_bps, 0);
+    assert_eq!(config.protocol_fee_share_bps, 10_000);
+    assert_eq!(config.flash_loan_fee_bps, 30);
+
+    token1.mint(&user, &20_000);
+    token2.mint(&user, &10_000);
+    pool.provide_liquidity(&user, &10_000, &10_000, &None, &None, &None::<u64>);
+    pool.swap(
+        &user,
+        &None,
+        &token1.address,
+        &10,
+        &None,
+        &Some(1_000),
+        &None::<u64>,
+    );
+    assert_eq!(token1.balance(&user), 9_990);
+
+    pool.update_config(
+        &admin,
+        &None,
+        &None,
+        &None,
+        &None,
+        &None,
+        &Some(1_000),
+        &None,
+        &None,
+    );
+    assert_eq!(pool.query_config().max_referral_bps, 1_000);
+}
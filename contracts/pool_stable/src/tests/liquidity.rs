@@ -8,7 +8,10 @@ use soroban_sdk::{
 
 use super::setup::{deploy_stable_liquidity_pool_contract, deploy_token_contract};
 use crate::{
-    storage::{Asset, PoolResponse},
+    storage::{
+        Asset, PoolResponse, SimulateProvideLiquidityResponse, SimulateWithdrawImbalancedResponse,
+        SimulateWithdrawSingleResponse,
+    },
     token_contract,
 };
 
@@ -52,7 +55,7 @@ fn provide_liqudity() {
     assert_eq!(token2.balance(&user1), 1000);
 
     // tokens 1 & 2 have 7 decimal digits, meaning those values are 0.0001 of token
-    pool.provide_liquidity(&user1, &1000, &1000, &None, &None::<u64>);
+    pool.provide_liquidity(&user1, &1000, &1000, &None, &None, &None::<u64>);
 
     assert_eq!(
         env.auths(),
@@ -62,7 +65,15 @@ fn provide_liqudity() {
                 function: AuthorizedFunction::Contract((
                     pool.address.clone(),
                     Symbol::new(&env, "provide_liquidity"),
-                    (&user1, 1000i128, 1000i128, None::<i64>, None::<u64>).into_val(&env),
+                    (
+                        &user1,
+                        1000i128,
+                        1000i128,
+                        None::<i64>,
+                        None::<i128>,
+                        None::<u64>,
+                    )
+                        .into_val(&env),
                 )),
                 sub_invocations: std::vec![
                     AuthorizedInvocation {
@@ -87,7 +98,7 @@ fn provide_liqudity() {
     );
 
     assert_eq!(token_share.balance(&user1), 999);
-    assert_eq!(token_share.balance(&pool.address), 0);
+    assert_eq!(token_share.balance(&pool.address), 1_000); // locked on the first deposit
     assert_eq!(token1.balance(&user1), 0);
     assert_eq!(token1.balance(&pool.address), 1000);
     assert_eq!(token2.balance(&user1), 0);
@@ -107,13 +118,13 @@ fn provide_liqudity() {
             },
             asset_lp_share: Asset {
                 address: share_token_address,
-                amount: 999i128
+                amount: 1_999i128
             },
             stake_address: pool.query_stake_contract_address(),
         }
     );
 
-    assert_eq!(pool.query_total_issued_lp(), 999);
+    assert_eq!(pool.query_total_issued_lp(), 1_999);
 }
 
 #[test]
@@ -149,21 +160,21 @@ fn withdraw_liquidity() {
     let share_token_address = pool.query_share_token_address();
     let token_share = token_contract::Client::new(&env, &share_token_address);
 
-    token1.mint(&user1, &1000);
-    token2.mint(&user1, &1000);
-    // tokens 1 & 2 have 7 decimal digits, meaning those values are 0.0001 of token
-    pool.provide_liquidity(&user1, &1000, &1000, &None, &None::<u64>);
+    token1.mint(&user1, &10_000);
+    token2.mint(&user1, &10_000);
+    // tokens 1 & 2 have 7 decimal digits, meaning those values are 0.001 of token
+    pool.provide_liquidity(&user1, &10_000, &10_000, &None, &None, &None::<u64>);
 
-    assert_eq!(token_share.balance(&user1), 999);
-    assert_eq!(token_share.balance(&pool.address), 0);
+    assert_eq!(token_share.balance(&user1), 18_999);
+    assert_eq!(token_share.balance(&pool.address), 1_000); // locked on the first deposit
     assert_eq!(token1.balance(&user1), 0);
-    assert_eq!(token1.balance(&pool.address), 1000);
+    assert_eq!(token1.balance(&pool.address), 10_000);
     assert_eq!(token2.balance(&user1), 0);
-    assert_eq!(token2.balance(&pool.address), 1000);
+    assert_eq!(token2.balance(&pool.address), 10_000);
 
-    let share_amount = 500; // half of the shares
-    let min_a = 500;
-    let min_b = 500;
+    let share_amount = 10_000; // half of the shares
+    let min_a = 5_000;
+    let min_b = 5_000;
     pool.withdraw_liquidity(&user1, &share_amount, &min_a, &min_b, &None::<u64>);
     assert_eq!(
         env.auths(),
@@ -173,13 +184,13 @@ fn withdraw_liquidity() {
                 function: AuthorizedFunction::Contract((
                     pool.address.clone(),
                     Symbol::new(&env, "withdraw_liquidity"),
-                    (&user1, 500i128, 500i128, 500i128, None::<u64>).into_val(&env),
+                    (&user1, 10_000i128, 5_000i128, 5_000i128, None::<u64>).into_val(&env),
                 )),
                 sub_invocations: std::vec![AuthorizedInvocation {
                     function: AuthorizedFunction::Contract((
                         share_token_address.clone(),
                         symbol_short!("transfer"),
-                        (&user1, &pool.address, 500_i128).into_val(&env)
+                        (&user1, &pool.address, 10_000_i128).into_val(&env)
                     )),
                     sub_invocations: std::vec![],
                 },],
@@ -187,12 +198,12 @@ fn withdraw_liquidity() {
         ),]
     );
 
-    assert_eq!(token_share.balance(&user1), 499);
-    assert_eq!(token_share.balance(&pool.address), 0); // sanity check
-    assert_eq!(token1.balance(&user1), 500);
-    assert_eq!(token1.balance(&pool.address), 500);
-    assert_eq!(token2.balance(&user1), 500);
-    assert_eq!(token2.balance(&pool.address), 500);
+    assert_eq!(token_share.balance(&user1), 8_999);
+    assert_eq!(token_share.balance(&pool.address), 1_000);
+    assert_eq!(token1.balance(&user1), 5_000);
+    assert_eq!(token1.balance(&pool.address), 5_000);
+    assert_eq!(token2.balance(&user1), 5_000);
+    assert_eq!(token2.balance(&pool.address), 5_000);
 
     let result = pool.query_pool_info();
     assert_eq!(
@@ -200,28 +211,28 @@ fn withdraw_liquidity() {
         PoolResponse {
             asset_a: Asset {
                 address: token1.address.clone(),
-                amount: 500i128,
+                amount: 5_000i128,
             },
             asset_b: Asset {
                 address: token2.address.clone(),
-                amount: 500i128,
+                amount: 5_000i128,
             },
             asset_lp_share: Asset {
                 address: share_token_address,
-                amount: 499i128,
+                amount: 9_999i128,
             },
             stake_address: pool.query_stake_contract_address(),
         }
     );
 
-    // clear the pool
-    pool.withdraw_liquidity(&user1, &499, &500, &500, &None::<u64>);
+    // withdraw the rest, the locked liquidity stays in the pool
+    pool.withdraw_liquidity(&user1, &8_999, &4_499, &4_499, &None::<u64>);
     assert_eq!(token_share.balance(&user1), 0);
-    assert_eq!(token_share.balance(&pool.address), 0); // sanity check
-    assert_eq!(token1.balance(&user1), 1000);
-    assert_eq!(token1.balance(&pool.address), 0);
-    assert_eq!(token2.balance(&user1), 1000);
-    assert_eq!(token2.balance(&pool.address), 0);
+    assert_eq!(token_share.balance(&pool.address), 1_000);
+    assert_eq!(token1.balance(&user1), 9_499);
+    assert_eq!(token1.balance(&pool.address), 501);
+    assert_eq!(token2.balance(&user1), 9_499);
+    assert_eq!(token2.balance(&pool.address), 501);
 }
 
 // Single asset liquidity providing is now disabled
@@ -446,7 +457,7 @@ fn swap_with_no_amounts() {
     token1.mint(&user1, &1_001_000);
     token2.mint(&user1, &1_001_000);
     // providing all amounts as None
-    pool.provide_liquidity(&user1, &0i128, &0i128, &None, &None::<u64>);
+    pool.provide_liquidity(&user1, &0i128, &0i128, &None, &None, &None::<u64>);
 }
 
 #[test]
@@ -483,7 +494,7 @@ fn withdraw_liqudity_below_min() {
 
     token1.mint(&user1, &1000);
     token2.mint(&user1, &1000);
-    pool.provide_liquidity(&user1, &1000, &1000, &None, &None::<u64>);
+    pool.provide_liquidity(&user1, &1000, &1000, &None, &None, &None::<u64>);
 
     let share_amount = 500;
     // Expecting min_a and/or min_b as huge bigger then available
@@ -530,10 +541,10 @@ fn provide_liqudity_with_deadline_works() {
     assert_eq!(token2.balance(&user1), 1000);
 
     env.ledger().with_mut(|li| li.timestamp = 99);
-    pool.provide_liquidity(&user1, &1000, &1000, &None, &Some(100));
+    pool.provide_liquidity(&user1, &1000, &1000, &None, &None, &Some(100));
 
     assert_eq!(token_share.balance(&user1), 999);
-    assert_eq!(token_share.balance(&pool.address), 0);
+    assert_eq!(token_share.balance(&pool.address), 1_000); // locked on the first deposit
     assert_eq!(token1.balance(&user1), 0);
     assert_eq!(token1.balance(&pool.address), 1000);
     assert_eq!(token2.balance(&user1), 0);
@@ -553,13 +564,13 @@ fn provide_liqudity_with_deadline_works() {
             },
             asset_lp_share: Asset {
                 address: share_token_address,
-                amount: 999i128
+                amount: 1_999i128
             },
             stake_address: pool.query_stake_contract_address(),
         }
     );
 
-    assert_eq!(pool.query_total_issued_lp(), 999);
+    assert_eq!(pool.query_total_issued_lp(), 1_999);
 }
 
 #[test]
@@ -600,7 +611,7 @@ fn provide_liqudity_past_deadline_should_panic() {
     assert_eq!(token2.balance(&user1), 1000);
 
     env.ledger().with_mut(|li| li.timestamp = 100);
-    pool.provide_liquidity(&user1, &1000, &1000, &None, &Some(99));
+    pool.provide_liquidity(&user1, &1000, &1000, &None, &None, &Some(99));
 }
 
 #[test]
@@ -636,30 +647,30 @@ fn withdraw_liquidity_with_deadline_should_work() {
     let share_token_address = pool.query_share_token_address();
     let token_share = token_contract::Client::new(&env, &share_token_address);
 
-    token1.mint(&user1, &1000);
-    token2.mint(&user1, &1000);
+    token1.mint(&user1, &10_000);
+    token2.mint(&user1, &10_000);
 
-    pool.provide_liquidity(&user1, &1000, &1000, &None, &None::<u64>);
+    pool.provide_liquidity(&user1, &10_000, &10_000, &None, &None, &None::<u64>);
 
-    assert_eq!(token_share.balance(&user1), 999);
-    assert_eq!(token_share.balance(&pool.address), 0);
+    assert_eq!(token_share.balance(&user1), 18_999);
+    assert_eq!(token_share.balance(&pool.address), 1_000);
     assert_eq!(token1.balance(&user1), 0);
-    assert_eq!(token1.balance(&pool.address), 1000);
+    assert_eq!(token1.balance(&pool.address), 10_000);
     assert_eq!(token2.balance(&user1), 0);
-    assert_eq!(token2.balance(&pool.address), 1000);
+    assert_eq!(token2.balance(&pool.address), 10_000);
 
-    let share_amount = 500;
-    let min_a = 500;
-    let min_b = 500;
+    let share_amount = 10_000;
+    let min_a = 5_000;
+    let min_b = 5_000;
     env.ledger().with_mut(|li| li.timestamp = 49);
     pool.withdraw_liquidity(&user1, &share_amount, &min_a, &min_b, &Some(50));
 
-    assert_eq!(token_share.balance(&user1), 499);
-    assert_eq!(token_share.balance(&pool.address), 0);
-    assert_eq!(token1.balance(&user1), 500);
-    assert_eq!(token1.balance(&pool.address), 500);
-    assert_eq!(token2.balance(&user1), 500);
-    assert_eq!(token2.balance(&pool.address), 500);
+    assert_eq!(token_share.balance(&user1), 8_999);
+    assert_eq!(token_share.balance(&pool.address), 1_000);
+    assert_eq!(token1.balance(&user1), 5_000);
+    assert_eq!(token1.balance(&pool.address), 5_000);
+    assert_eq!(token2.balance(&user1), 5_000);
+    assert_eq!(token2.balance(&pool.address), 5_000);
 
     let result = pool.query_pool_info();
     assert_eq!(
@@ -667,29 +678,29 @@ fn withdraw_liquidity_with_deadline_should_work() {
         PoolResponse {
             asset_a: Asset {
                 address: token1.address.clone(),
-                amount: 500i128,
+                amount: 5_000i128,
             },
             asset_b: Asset {
                 address: token2.address.clone(),
-                amount: 500i128,
+                amount: 5_000i128,
             },
             asset_lp_share: Asset {
                 address: share_token_address,
-                amount: 499i128,
+                amount: 9_999i128,
             },
             stake_address: pool.query_stake_contract_address(),
         }
     );
 
-    // clear the pool
+    // withdraw the rest, the locked liquidity stays in the pool
     env.ledger().with_mut(|li| li.timestamp = 99);
-    pool.withdraw_liquidity(&user1, &499, &500, &500, &Some(100));
+    pool.withdraw_liquidity(&user1, &8_999, &4_499, &4_499, &Some(100));
     assert_eq!(token_share.balance(&user1), 0);
-    assert_eq!(token_share.balance(&pool.address), 0);
-    assert_eq!(token1.balance(&user1), 1000);
-    assert_eq!(token1.balance(&pool.address), 0);
-    assert_eq!(token2.balance(&user1), 1000);
-    assert_eq!(token2.balance(&pool.address), 0);
+    assert_eq!(token_share.balance(&pool.address), 1_000);
+    assert_eq!(token1.balance(&user1), 9_499);
+    assert_eq!(token1.balance(&pool.address), 501);
+    assert_eq!(token2.balance(&user1), 9_499);
+    assert_eq!(token2.balance(&pool.address), 501);
 }
 
 #[test]
@@ -726,21 +737,735 @@ fn withdraw_liquidity_past_deadline_should_panic() {
     let share_token_address = pool.query_share_token_address();
     let token_share = token_contract::Client::new(&env, &share_token_address);
 
-    token1.mint(&user1, &1000);
-    token2.mint(&user1, &1000);
+    token1.mint(&user1, &10_000);
+    token2.mint(&user1, &10_000);
 
-    pool.provide_liquidity(&user1, &1000, &1000, &None, &None::<u64>);
+    pool.provide_liquidity(&user1, &10_000, &10_000, &None, &None, &None::<u64>);
 
-    assert_eq!(token_share.balance(&user1), 999);
-    assert_eq!(token_share.balance(&pool.address), 0);
+    assert_eq!(token_share.balance(&user1), 18_999);
+    assert_eq!(token_share.balance(&pool.address), 1_000);
     assert_eq!(token1.balance(&user1), 0);
-    assert_eq!(token1.balance(&pool.address), 1000);
+    assert_eq!(token1.balance(&pool.address), 10_000);
     assert_eq!(token2.balance(&user1), 0);
-    assert_eq!(token2.balance(&pool.address), 1000);
+    assert_eq!(token2.balance(&pool.address), 10_000);
 
-    let share_amount = 500;
-    let min_a = 500;
-    let min_b = 500;
+    let share_amount = 10_000;
+    let min_a = 5_000;
+    let min_b = 5_000;
     env.ledger().with_mut(|li| li.timestamp = 50);
     pool.withdraw_liquidity(&user1, &share_amount, &min_a, &min_b, &Some(49));
 }
+
+#[test]
+fn withdraw_liquidity_single_pays_out_one_token() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let manager = Address::generate(&env);
+    let factory = Address::generate(&env);
+    let user1 = Address::generate(&env);
+    let fee_recipient = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let swap_fees = 1_000i64; // 10% bps
+    let pool = deploy_stable_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        swap_fees,
+        fee_recipient.clone(),
+        None,
+        None,
+        manager,
+        factory,
+        None,
+    );
+    let token_share = token_contract::Client::new(&env, &pool.query_share_token_address());
+
+    token1.mint(&user1, &1_000_000);
+    token2.mint(&user1, &1_000_000);
+    pool.provide_liquidity(&user1, &1_000_000, &1_000_000, &None, &None, &None::<u64>);
+    let total_shares = pool.query_total_issued_lp();
+
+    let share_amount = 100_000;
+    let simulated = pool.simulate_withdraw_single(&share_amount, &token1.address);
+    assert_eq!(
+        simulated,
+        SimulateWithdrawSingleResponse {
+            ask_amount: 94_681,
+            commission_amount: 4_945,
+            protocol_fee_amount: 4_945,
+            spread_amount: 374,
+        }
+    );
+    let returned =
+        pool.withdraw_liquidity_single(&user1, &share_amount, &token1.address, &1, &None::<u64>);
+
+    assert_eq!(returned, simulated.ask_amount);
+    assert_eq!(token1.balance(&user1), returned);
+    assert_eq!(token2.balance(&user1), 0);
+    assert_eq!(
+        token_share.balance(&user1),
+        total_shares - 1_000 - share_amount
+    );
+    assert_eq!(
+        token1.balance(&fee_recipient),
+        simulated.protocol_fee_amount
+    );
+
+    // more than the proportional share of both tokens minus the commission, but not free
+    let proportional = 1_000_000 * share_amount / total_shares;
+    assert!(returned > proportional);
+    assert!(returned < 2 * proportional);
+    assert!(simulated.commission_amount > 0);
+
+    let info = pool.query_pool_info();
+    assert_eq!(info.asset_a.amount, token1.balance(&pool.address));
+    assert_eq!(info.asset_b.amount, 1_000_000);
+    assert_eq!(info.asset_lp_share.amount, total_shares - share_amount);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #3)")]
+fn withdraw_liquidity_single_below_min() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let manager = Address::generate(&env);
+    let factory = Address::generate(&env);
+    let user1 = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let pool = deploy_stable_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        manager,
+        factory,
+        None,
+    );
+
+    token1.mint(&user1, &1_000_000);
+    token2.mint(&user1, &1_000_000);
+    pool.provide_liquidity(&user1, &1_000_000, &1_000_000, &None, &None, &None::<u64>);
+
+    let simulated = pool.simulate_withdraw_single(&100_000, &token2.address);
+    pool.withdraw_liquidity_single(
+        &user1,
+        &100_000,
+        &token2.address,
+        &(simulated.ask_amount + 1),
+        &None::<u64>,
+    );
+}
+
+#[test]
+fn withdraw_liquidity_single_of_all_shares_leaves_locked_liquidity() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let manager = Address::generate(&env);
+    let factory = Address::generate(&env);
+    let user1 = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let pool = deploy_stable_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        manager,
+        factory,
+        None,
+    );
+
+    token1.mint(&user1, &1_000_000);
+    token2.mint(&user1, &1_000_000);
+    pool.provide_liquidity(&user1, &1_000_000, &1_000_000, &None, &None, &None::<u64>);
+
+    // the shares locked on the first deposit keep the pool from being emptied
+    let share_amount =
+        token_contract::Client::new(&env, &pool.query_share_token_address()).balance(&user1);
+    let returned =
+        pool.withdraw_liquidity_single(&user1, &share_amount, &token1.address, &1, &None::<u64>);
+    assert_eq!(share_amount, 1_998_999);
+    assert_eq!(returned, 999_999);
+    assert_eq!(token1.balance(&pool.address), 1);
+    assert_eq!(token2.balance(&pool.address), 1_000_000);
+    assert_eq!(pool.query_total_issued_lp(), 1_000);
+}
+
+#[test]
+fn skim_sends_out_tokens_sent_to_the_pool_directly() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let recipient = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let pool = deploy_stable_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+        None,
+    );
+
+    token1.mint(&user, &1_000);
+    token2.mint(&user, &1_000);
+    pool.provide_liquidity(&user, &1_000, &1_000, &None, &None, &None::<u64>);
+    assert_eq!(pool.query_reserve_discrepancy(), (0, 0));
+
+    // tokens sent with a plain transfer do not show up in the reserves
+    token1.mint(&pool.address, &100);
+    assert_eq!(pool.query_reserve_discrepancy(), (100, 0));
+
+    assert_eq!(pool.skim(&recipient), (100, 0));
+    assert_eq!(token1.balance(&recipient), 100);
+    assert_eq!(token1.balance(&pool.address), 1_000);
+    assert_eq!(pool.query_reserve_discrepancy(), (0, 0));
+}
+
+#[test]
+fn sync_adds_tokens_sent_to_the_pool_directly_to_the_reserves() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let pool = deploy_stable_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+        None,
+    );
+
+    token1.mint(&user, &1_000);
+    token2.mint(&user, &1_000);
+    pool.provide_liquidity(&user, &1_000, &1_000, &None, &None, &None::<u64>);
+
+    token2.mint(&pool.address, &100);
+    assert_eq!(pool.query_reserve_discrepancy(), (0, 100));
+
+    pool.sync();
+
+    assert_eq!(pool.query_reserve_discrepancy(), (0, 0));
+    let pool_info = pool.query_pool_info();
+    assert_eq!(pool_info.asset_a.amount, 1_000);
+    assert_eq!(pool_info.asset_b.amount, 1_100);
+    // nothing left to skim
+    assert_eq!(pool.skim(&user), (0, 0));
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #10)")]
+fn provide_liquidity_below_minimum_liquidity_on_empty_pool() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let pool = deploy_stable_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+        None,
+    );
+
+    // an invariant of 1_000 would be locked in full
+    token1.mint(&user, &500);
+    token2.mint(&user, &500);
+    pool.provide_liquidity(&user, &500, &500, &None, &None, &None::<u64>);
+}
+
+#[test]
+fn first_depositor_can_not_inflate_the_share_price() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let attacker = Address::generate(&env);
+    let victim = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let pool = deploy_stable_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+        None,
+    );
+    let share_token = token_contract::Client::new(&env, &pool.query_share_token_address());
+
+    let donation = 1_000_000;
+    token1.mint(&attacker, &(501 + donation + 1));
+    token2.mint(&attacker, &(501 + donation + 1));
+
+    // the smallest possible first deposit leaves a single share to the attacker
+    pool.provide_liquidity(&attacker, &501, &501, &None, &None, &None::<u64>);
+    assert_eq!(share_token.balance(&attacker), 1);
+    assert_eq!(share_token.balance(&pool.address), 1_000);
+
+    // donate to the pool and pull the donation into the reserves with a tiny deposit, so the
+    // few existing shares are backed by all of it
+    token1.transfer(&attacker, &pool.address, &donation);
+    token2.transfer(&attacker, &pool.address, &donation);
+    pool.provide_liquidity(&attacker, &1, &1, &None, &None, &None::<u64>);
+    assert_eq!(share_token.balance(&attacker), 2);
+    assert_eq!(pool.query_total_issued_lp(), 1_002);
+    let info = pool.query_pool_info();
+    assert_eq!(info.asset_a.amount, 1_000_502);
+    assert_eq!(info.asset_b.amount, 1_000_502);
+
+    // without the locked shares this deposit would be rounded down to no shares at all
+    token1.mint(&victim, &100_000);
+    token2.mint(&victim, &100_000);
+    pool.provide_liquidity(&victim, &100_000, &100_000, &None, &None, &None::<u64>);
+    let victim_shares = share_token.balance(&victim);
+    assert_eq!(victim_shares, 100);
+
+    // the victim loses less than one share worth to rounding
+    pool.withdraw_liquidity(&victim, &victim_shares, &1, &1, &None::<u64>);
+    assert_eq!(token1.balance(&victim), 99_864);
+    assert_eq!(token2.balance(&victim), 99_864);
+
+    // while the attacker lost almost the whole donation to the locked shares
+    let attacker_shares = share_token.balance(&attacker);
+    pool.withdraw_liquidity(&attacker, &attacker_shares, &1, &1, &None::<u64>);
+    assert_eq!(token1.balance(&attacker), 1_997);
+    assert_eq!(token2.balance(&attacker), 1_997);
+}
+
+#[test]
+fn provide_liquidity_charges_imbalance_fee() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let user1 = Address::generate(&env);
+    let user2 = Address::generate(&env);
+    let fee_recipient = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let swap_fees = 1_000i64; // 10% bps
+    let pool = deploy_stable_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        swap_fees,
+        fee_recipient.clone(),
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+        None,
+    );
+    let token_share = token_contract::Client::new(&env, &pool.query_share_token_address());
+
+    token1.mint(&user1, &1_000_000);
+    token2.mint(&user1, &1_000_000);
+    pool.provide_liquidity(&user1, &1_000_000, &1_000_000, &None, &None, &None::<u64>);
+    let total_shares = pool.query_total_issued_lp();
+
+    // a deposit in the ratio of the pool is not charged
+    let balanced = pool.simulate_provide_liquidity(&100_000, &100_000, &None);
+    assert_eq!(balanced.fee_a, 0);
+    assert_eq!(balanced.fee_b, 0);
+    assert_eq!(balanced.shares, total_shares / 10);
+
+    let simulated = pool.simulate_provide_liquidity(&200_000, &1, &None);
+    assert_eq!(
+        simulated,
+        SimulateProvideLiquidityResponse {
+            shares: 190_001,
+            fee_a: 5_000,
+            fee_b: 4_999,
+        }
+    );
+
+    token1.mint(&user2, &200_000);
+    token2.mint(&user2, &1);
+    pool.provide_liquidity(
+        &user2,
+        &200_000,
+        &1,
+        &None,
+        &Some(simulated.shares),
+        &None::<u64>,
+    );
+    assert_eq!(token_share.balance(&user2), simulated.shares);
+    assert_eq!(token1.balance(&fee_recipient), simulated.fee_a);
+    assert_eq!(token2.balance(&fee_recipient), simulated.fee_b);
+
+    // depositing one side and withdrawing proportionally costs at least the imbalance fee
+    let (amount_a, amount_b) =
+        pool.withdraw_liquidity(&user2, &simulated.shares, &1, &1, &None::<u64>);
+    assert!(amount_a + amount_b < 200_001 - simulated.fee_a);
+
+    let info = pool.query_pool_info();
+    assert_eq!(info.asset_a.amount, token1.balance(&pool.address));
+    assert_eq!(info.asset_b.amount, token2.balance(&pool.address));
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #31)")]
+fn provide_liquidity_below_min_shares_out() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let user1 = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let pool = deploy_stable_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        1_000i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+        None,
+    );
+
+    token1.mint(&user1, &1_200_000);
+    token2.mint(&user1, &1_000_001);
+    pool.provide_liquidity(&user1, &1_000_000, &1_000_000, &None, &None, &None::<u64>);
+
+    let simulated = pool.simulate_provide_liquidity(&200_000, &1, &None);
+    pool.provide_liquidity(
+        &user1,
+        &200_000,
+        &1,
+        &None,
+        &Some(simulated.shares + 1),
+        &None::<u64>,
+    );
+}
+
+#[test]
+fn simulate_provide_liquidity_rejects_slippage_like_provide_liquidity() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let user1 = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &Address::generate(&env));
+    let mut token2 = deploy_token_contract(&env, &Address::generate(&env));
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let pool = deploy_stable_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        500,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+        None,
+    );
+
+    token1.mint(&user1, &1_000_000);
+    token2.mint(&user1, &1_000_000);
+
+    assert_eq!(
+        pool.try_simulate_provide_liquidity(&1_000_000, &1_000_000, &Some(501)),
+        Err(Ok(soroban_sdk::Error::from_contract_error(2)))
+    );
+    assert_eq!(
+        pool.try_provide_liquidity(
+            &user1,
+            &1_000_000,
+            &1_000_000,
+            &Some(501),
+            &None,
+            &None::<u64>
+        ),
+        Err(Ok(soroban_sdk::Error::from_contract_error(2)))
+    );
+
+    let simulated = pool.simulate_provide_liquidity(&1_000_000, &1_000_000, &Some(500));
+    pool.provide_liquidity(
+        &user1,
+        &1_000_000,
+        &1_000_000,
+        &Some(500),
+        &None,
+        &None::<u64>,
+    );
+    assert_eq!(pool.query_total_issued_lp() - 1_000, simulated.shares);
+}
+
+#[test]
+fn simulate_withdraw_liquidity_matches_withdrawal() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let user1 = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let pool = deploy_stable_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+        None,
+    );
+    let token_share = token_contract::Client::new(&env, &pool.query_share_token_address());
+
+    token1.mint(&user1, &1_000_000);
+    token2.mint(&user1, &1_100_000);
+    let simulated = pool.simulate_provide_liquidity(&1_000_000, &1_100_000, &None);
+    pool.provide_liquidity(&user1, &1_000_000, &1_100_000, &None, &None, &None::<u64>);
+    assert_eq!(token_share.balance(&user1), simulated.shares);
+
+    // rounded down in favor of the pool, just like the withdrawal
+    let (amount_a, amount_b) = pool.simulate_withdraw_liquidity(&333_333);
+    assert_eq!(
+        pool.withdraw_liquidity(&user1, &333_333, &amount_a, &amount_b, &None::<u64>),
+        (amount_a, amount_b)
+    );
+    assert_eq!(token1.balance(&user1), amount_a);
+    assert_eq!(token2.balance(&user1), amount_b);
+}
+
+#[test]
+fn withdraw_liquidity_imbalanced_pays_out_exact_amounts() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let user1 = Address::generate(&env);
+    let fee_recipient = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let swap_fees = 1_000i64; // 10% bps
+    let pool = deploy_stable_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        swap_fees,
+        fee_recipient.clone(),
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+        None,
+    );
+    let token_share = token_contract::Client::new(&env, &pool.query_share_token_address());
+
+    token1.mint(&user1, &1_000_000);
+    token2.mint(&user1, &1_000_000);
+    pool.provide_liquidity(&user1, &1_000_000, &1_000_000, &None, &None, &None::<u64>);
+    let total_shares = pool.query_total_issued_lp();
+    let user_shares = token_share.balance(&user1);
+
+    // a withdrawal in the ratio of the pool is not charged
+    let balanced = pool.simulate_withdraw_imbalanced(&100_000, &100_000);
+    assert_eq!(balanced.fee_a, 0);
+    assert_eq!(balanced.fee_b, 0);
+    assert_eq!(balanced.shares, total_shares / 10 + 1);
+
+    let simulated = pool.simulate_withdraw_imbalanced(&200_000, &0);
+    assert_eq!(
+        simulated,
+        SimulateWithdrawImbalancedResponse {
+            shares: 209_999,
+            fee_a: 4_999,
+            fee_b: 5_000,
+        }
+    );
+
+    let burned =
+        pool.withdraw_liquidity_imbalanced(&user1, &200_000, &0, &simulated.shares, &None::<u64>);
+    assert_eq!(burned, simulated.shares);
+    assert_eq!(token1.balance(&user1), 200_000);
+    assert_eq!(token2.balance(&user1), 0);
+    assert_eq!(token_share.balance(&user1), user_shares - burned);
+    assert_eq!(pool.query_total_issued_lp(), total_shares - burned);
+    assert_eq!(token1.balance(&fee_recipient), simulated.fee_a);
+    assert_eq!(token2.balance(&fee_recipient), simulated.fee_b);
+
+    // costs more shares than a proportional withdrawal of the same total amount
+    assert!(burned > balanced.shares);
+
+    let info = pool.query_pool_info();
+    assert_eq!(info.asset_a.amount, token1.balance(&pool.address));
+    assert_eq!(info.asset_b.amount, token2.balance(&pool.address));
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #33)")]
+fn withdraw_liquidity_imbalanced_above_max_shares_burn() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let user1 = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let pool = deploy_stable_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        1_000i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+        None,
+    );
+
+    token1.mint(&user1, &1_000_000);
+    token2.mint(&user1, &1_000_000);
+    pool.provide_liquidity(&user1, &1_000_000, &1_000_000, &None, &None, &None::<u64>);
+
+    let simulated = pool.simulate_withdraw_imbalanced(&200_000, &0);
+    pool.withdraw_liquidity_imbalanced(&user1, &200_000, &0, &(simulated.shares - 1), &None::<u64>);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #32)")]
+fn withdraw_liquidity_imbalanced_of_the_whole_reserve() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let user1 = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let pool = deploy_stable_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        1_000i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+        None,
+    );
+
+    token1.mint(&user1, &1_000_000);
+    token2.mint(&user1, &1_000_000);
+    pool.provide_liquidity(&user1, &1_000_000, &1_000_000, &None, &None, &None::<u64>);
+
+    pool.withdraw_liquidity_imbalanced(&user1, &1_000_000, &0, &i128::MAX, &None::<u64>);
+}
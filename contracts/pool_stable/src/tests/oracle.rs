@@ -0,0 +1,139 @@
+extern crate std;
+use soroban_sdk::{
+    testutils::{Address as _, Ledger},
+    Address, Env,
+};
+
+use super::setup::{deploy_stable_liquidity_pool_contract, deploy_token_contract};
+
+#[test]
+fn twap_follows_the_curve_price_over_time() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let mut token1 = deploy_token_contract(&env, &Address::generate(&env));
+    let mut token2 = deploy_token_contract(&env, &Address::generate(&env));
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let user = Address::generate(&env);
+
+    let pool = deploy_stable_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        5_000i64,
+        Address::generate(&env),
+        Address::generate(&env),
+        None,
+    );
+
+    token1.mint(&user, &2_000_000);
+    token2.mint(&user, &1_000_000);
+
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+    pool.provide_liquidity(&user, &1_000_000, &1_000_000, &None, &None, &None::<u64>);
+
+    // a balanced pool prices both tokens at exactly 1
+    env.ledger().with_mut(|li| li.timestamp = 1_600);
+    assert_eq!(
+        pool.query_twap(&token1.address, &600),
+        1_000_000_000_000_000_000
+    );
+
+    pool.swap(
+        &user,
+        &None,
+        &token1.address,
+        &200_000,
+        &None,
+        &Some(5_000),
+        &None::<u64>,
+    );
+
+    env.ledger().with_mut(|li| li.timestamp = 2_200);
+
+    // since the swap token1 is worth less than token2; with reserves of 1_200_000 / 805_713
+    // the marginal price of the curve is ~0.9421
+    let price_a = pool.query_twap(&token1.address, &600);
+    let price_b = pool.query_twap(&token2.address, &600);
+    assert!((price_a - 942_092_082_069_637_992).abs() < 1_000_000_000_000_000);
+    assert!(price_b > 1_000_000_000_000_000_000);
+    assert!((price_a * price_b / 1_000_000_000_000_000_000 - 1_000_000_000_000_000_000).abs() < 10);
+
+    // 600 seconds at 1 and 600 seconds at the new price
+    assert_eq!(
+        pool.query_twap(&token1.address, &1_200),
+        (1_000_000_000_000_000_000 + price_a) / 2
+    );
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #24)")]
+fn twap_fails_when_window_exceeds_history() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let mut token1 = deploy_token_contract(&env, &Address::generate(&env));
+    let mut token2 = deploy_token_contract(&env, &Address::generate(&env));
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let user = Address::generate(&env);
+
+    let pool = deploy_stable_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+        None,
+    );
+
+    token1.mint(&user, &1_000_000);
+    token2.mint(&user, &1_000_000);
+
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+    pool.provide_liquidity(&user, &1_000_000, &1_000_000, &None, &None, &None::<u64>);
+
+    env.ledger().with_mut(|li| li.timestamp = 1_500);
+    pool.query_twap(&token1.address, &600);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #6)")]
+fn twap_fails_for_asset_not_in_pool() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let mut token1 = deploy_token_contract(&env, &Address::generate(&env));
+    let mut token2 = deploy_token_contract(&env, &Address::generate(&env));
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+
+    let pool = deploy_stable_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+        None,
+    );
+
+    pool.query_twap(&Address::generate(&env), &600);
+}
@@ -43,33 +43,33 @@ fn query_share_valid_liquidity() {
     let share_token_address = pool.query_share_token_address();
     let token_share = token_contract::Client::new(&env, &share_token_address.clone());
 
-    token1.mint(&user1, &1500);
-    token1.mint(&user2, &2500);
-    token1.mint(&user3, &3500);
+    token1.mint(&user1, &15_000);
+    token1.mint(&user2, &25_000);
+    token1.mint(&user3, &35_000);
 
-    token2.mint(&user1, &2000);
-    token2.mint(&user2, &3000);
-    token2.mint(&user3, &4000);
+    token2.mint(&user1, &20_000);
+    token2.mint(&user2, &30_000);
+    token2.mint(&user3, &40_000);
 
     // all users provide liquidity in a 3:4 ratio
-    pool.provide_liquidity(&user1, &1500, &2000, &None, &None::<u64>);
-    pool.provide_liquidity(&user2, &1500, &2000, &None, &None::<u64>);
-    pool.provide_liquidity(&user3, &1500, &2000, &None, &None::<u64>);
+    pool.provide_liquidity(&user1, &15_000, &20_000, &None, &None, &None::<u64>);
+    pool.provide_liquidity(&user2, &15_000, &20_000, &None, &None, &None::<u64>);
+    pool.provide_liquidity(&user3, &15_000, &20_000, &None, &None, &None::<u64>);
 
     // user1 assertions
     let lp_share_balance_user1 = token_share.balance(&user1);
     let query_share_result_user1 = pool.query_share(&lp_share_balance_user1);
-    // rounding errors, again - 1 token is 0.0000001
+    // user1 does not own the shares locked on the first deposit
     assert_eq!(
         query_share_result_user1,
         (
             Asset {
                 address: token1.address.clone(),
-                amount: 1499
+                amount: 14_571
             },
             Asset {
                 address: token2.address.clone(),
-                amount: 1999
+                amount: 19_428
             }
         )
     );
@@ -80,15 +80,16 @@ fn query_share_valid_liquidity() {
         PoolResponse {
             asset_a: Asset {
                 address: token1.address.clone(),
-                amount: 4500
+                amount: 45_000
             },
             asset_b: Asset {
                 address: token2.address.clone(),
-                amount: 6000
+                amount: 60_000
             },
             asset_lp_share: Asset {
                 address: share_token_address.clone(),
-                amount: token_share.balance(&user1)
+                amount: token_share.balance(&pool.address)
+                    + token_share.balance(&user1)
                     + token_share.balance(&user2)
                     + token_share.balance(&user3)
             },
@@ -100,8 +101,8 @@ fn query_share_valid_liquidity() {
     pool.withdraw_liquidity(
         &user1,
         &lp_share_balance_user1,
-        &1000i128,
-        &1000i128,
+        &10_000i128,
+        &10_000i128,
         &None::<u64>,
     );
     let pool_info_after_withdrawal = pool.query_pool_info();
@@ -110,15 +111,17 @@ fn query_share_valid_liquidity() {
         PoolResponse {
             asset_a: Asset {
                 address: token1.address.clone(),
-                amount: 3001
+                amount: 30_429
             },
             asset_b: Asset {
                 address: token2.address.clone(),
-                amount: 4001
+                amount: 40_572
             },
             asset_lp_share: Asset {
                 address: share_token_address.clone(),
-                amount: token_share.balance(&user2) + token_share.balance(&user3)
+                amount: token_share.balance(&pool.address)
+                    + token_share.balance(&user2)
+                    + token_share.balance(&user3)
             },
             stake_address: pool_info_after_withdrawal.clone().stake_address,
         }
@@ -135,11 +138,11 @@ fn query_share_valid_liquidity() {
         (
             Asset {
                 address: token1.address.clone(),
-                amount: 1500
+                amount: 15_000
             },
             Asset {
                 address: token2.address.clone(),
-                amount: 2000
+                amount: 20_000
             }
         )
     );
@@ -148,8 +151,8 @@ fn query_share_valid_liquidity() {
     pool.withdraw_liquidity(
         &user2,
         &lp_share_balance_user2,
-        &1500i128,
-        &2000i128,
+        &15_000i128,
+        &20_000i128,
         &None::<u64>,
     );
     let pool_info_after_withdrawal = pool.query_pool_info();
@@ -158,15 +161,15 @@ fn query_share_valid_liquidity() {
         PoolResponse {
             asset_a: Asset {
                 address: token1.address.clone(),
-                amount: 1501
+                amount: 15_429
             },
             asset_b: Asset {
                 address: token2.address.clone(),
-                amount: 2001
+                amount: 20_572
             },
             asset_lp_share: Asset {
                 address: share_token_address.clone(),
-                amount: token_share.balance(&user3)
+                amount: token_share.balance(&pool.address) + token_share.balance(&user3)
             },
             stake_address: pool_info_after_withdrawal.clone().stake_address,
         }
@@ -183,39 +186,39 @@ fn query_share_valid_liquidity() {
         (
             Asset {
                 address: token1.address.clone(),
-                amount: 1501
+                amount: 15_000
             },
             Asset {
                 address: token2.address.clone(),
-                amount: 2001
+                amount: 20_000
             }
         )
     );
 
-    // user3 has 2499 shares, we are withdrawing 1499
-    pool.withdraw_liquidity(&user3, &1499, &1i128, &1i128, &None::<u64>);
+    // user3 has 34_999 shares, we are withdrawing 14_999
+    pool.withdraw_liquidity(&user3, &14_999, &1i128, &1i128, &None::<u64>);
     let pool_info_after_withdrawal = pool.query_pool_info();
     assert_eq!(
         pool_info_after_withdrawal,
         PoolResponse {
             asset_a: Asset {
                 address: token1.address.clone(),
-                amount: 601
+                amount: 9_001
             },
             asset_b: Asset {
                 address: token2.address.clone(),
-                amount: 801
+                amount: 12_001
             },
             asset_lp_share: Asset {
                 address: share_token_address.clone(),
-                amount: 1000
+                amount: 21_000
             },
             stake_address: pool_info_after_withdrawal.clone().stake_address,
         }
     );
 
     let lp_share_balance_after_withdraw_user3: i128 = token_share.balance(&user3);
-    assert_eq!(lp_share_balance_after_withdraw_user3, 1000);
+    assert_eq!(lp_share_balance_after_withdraw_user3, 20_000);
 
     let query_share_result_user3 = pool.query_share(&lp_share_balance_after_withdraw_user3);
     assert_eq!(
@@ -223,11 +226,11 @@ fn query_share_valid_liquidity() {
         (
             Asset {
                 address: token1.address.clone(),
-                amount: 601
+                amount: 8_572
             },
             Asset {
                 address: token2.address.clone(),
-                amount: 801
+                amount: 11_429
             }
         )
     );
@@ -0,0 +1,261 @@
+extern crate std;
+
+use soroban_sdk::{
+    contract, contractimpl, symbol_short,
+    testutils::{Address as _, Ledger},
+    Address, Env, Symbol,
+};
+
+use super::setup::{deploy_stable_liquidity_pool_contract, deploy_token_contract};
+use crate::{contract::StableLiquidityPoolClient, token_contract};
+
+const RATE: Symbol = symbol_short!("RATE");
+
+/// Returns whatever rate was set last, like the exchange rate of a liquid staking token.
+#[contract]
+pub struct MockRateProvider;
+
+#[contractimpl]
+impl MockRateProvider {
+    pub fn set_rate(env: Env, rate: i128) {
+        env.storage().instance().set(&RATE, &rate);
+    }
+
+    /// Makes `query_rate` revert
+    pub fn remove_rate(env: Env) {
+        env.storage().instance().remove(&RATE);
+    }
+
+    pub fn query_rate(env: Env) -> i128 {
+        env.storage().instance().get(&RATE).unwrap()
+    }
+}
+
+fn deploy_rate_provider<'a>(env: &Env, rate: i128) -> MockRateProviderClient<'a> {
+    let rate_provider =
+        MockRateProviderClient::new(env, &env.register_contract(None, MockRateProvider));
+    rate_provider.set_rate(&rate);
+    rate_provider
+}
+
+fn deploy_pool<'a>(
+    env: &Env,
+    admin: &Address,
+) -> (
+    StableLiquidityPoolClient<'a>,
+    token_contract::Client<'a>,
+    token_contract::Client<'a>,
+) {
+    let mut token1 = deploy_token_contract(env, admin);
+    let mut token2 = deploy_token_contract(env, admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let pool = deploy_stable_liquidity_pool_contract(
+        env,
+        Some(admin.clone()),
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        Address::generate(env),
+        Address::generate(env),
+        None,
+    );
+
+    (pool, token1, token2)
+}
+
+#[test]
+fn swap_uses_the_rate_of_the_tokens() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let (pool, token1, token2) = deploy_pool(&env, &admin);
+
+    // one token1 is worth two token2
+    let rate_provider = deploy_rate_provider(&env, 2_000_000_000_000_000_000);
+    pool.set_rate_provider(
+        &admin,
+        &token1.address,
+        &Some(rate_provider.address.clone()),
+    );
+    assert_eq!(
+        pool.query_rate_providers(),
+        (Some(rate_provider.address.clone()), None)
+    );
+    assert_eq!(
+        pool.query_rates(),
+        (2_000_000_000_000_000_000, 1_000_000_000_000_000_000)
+    );
+
+    token1.mint(&user, &1_001_000);
+    token2.mint(&user, &2_000_000);
+    pool.provide_liquidity(&user, &1_000_000, &2_000_000, &None, &None, &None::<u64>);
+
+    // a balanced pool trades at the rate, in both directions
+    assert_eq!(
+        pool.simulate_reverse_swap(&token2.address, &1_000)
+            .offer_amount,
+        2_000
+    );
+    let simulated = pool.simulate_swap(&token1.address, &1_000);
+    let output_amount = pool
+        .swap(
+            &user,
+            &None,
+            &token1.address,
+            &1_000,
+            &None,
+            &None,
+            &None::<u64>,
+        )
+        .ask_amount;
+    assert_eq!(output_amount, simulated.ask_amount);
+    assert_eq!(output_amount, 2_000);
+    assert_eq!(token1.balance(&user), 0);
+    assert_eq!(token2.balance(&user), 2_000);
+
+    // the rate grows by 1%, so token1 buys more token2 from the same reserves
+    rate_provider.set_rate(&2_020_000_000_000_000_000);
+    let simulated = pool.simulate_swap(&token1.address, &1_000);
+    assert!((2_001..2_020).contains(&simulated.ask_amount));
+}
+
+#[test]
+#[should_panic(expected = "Pool Stable: SetRateProvider: Unauthorized")]
+fn set_rate_provider_unauthorized() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let (pool, token1, _) = deploy_pool(&env, &admin);
+
+    let rate_provider = deploy_rate_provider(&env, 1_000_000_000_000_000_000);
+    pool.set_rate_provider(
+        &Address::generate(&env),
+        &token1.address,
+        &Some(rate_provider.address),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #30)")]
+fn set_rate_provider_fails_with_liquidity_in_the_pool() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let (pool, token1, token2) = deploy_pool(&env, &admin);
+
+    token1.mint(&user, &1_000_000);
+    token2.mint(&user, &1_000_000);
+    pool.provide_liquidity(&user, &1_000_000, &1_000_000, &None, &None, &None::<u64>);
+
+    let rate_provider = deploy_rate_provider(&env, 1_000_000_000_000_000_000);
+    pool.set_rate_provider(&admin, &token1.address, &Some(rate_provider.address));
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #29)")]
+fn set_rate_provider_with_invalid_rate() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let (pool, _, token2) = deploy_pool(&env, &admin);
+
+    let rate_provider = deploy_rate_provider(&env, 0);
+    pool.set_rate_provider(&admin, &token2.address, &Some(rate_provider.address));
+}
+
+#[test]
+fn failing_rate_provider_does_not_block_withdrawals() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let (pool, token1, token2) = deploy_pool(&env, &admin);
+    let share_token = token_contract::Client::new(&env, &pool.query_share_token_address());
+
+    let rate_provider = deploy_rate_provider(&env, 1_000_000_000_000_000_000);
+    pool.set_rate_provider(
+        &admin,
+        &token1.address,
+        &Some(rate_provider.address.clone()),
+    );
+
+    token1.mint(&user, &1_000_000);
+    token2.mint(&user, &1_000_000);
+    pool.provide_liquidity(&user, &1_000_000, &1_000_000, &None, &None, &None::<u64>);
+
+    rate_provider.remove_rate();
+    env.ledger().with_mut(|li| li.timestamp += 600);
+
+    // proportional withdrawals don't need the rate
+    pool.withdraw_liquidity(&user, &200_000, &1, &1, &None::<u64>);
+    assert_eq!(token1.balance(&user), 100_000);
+    assert_eq!(token2.balance(&user), 100_000);
+
+    // the admin pauses the pool to replace the failing rate provider
+    pool.pause(&admin);
+    let new_rate_provider = deploy_rate_provider(&env, 1_000_000_000_000_000_000);
+    pool.set_rate_provider(
+        &admin,
+        &token1.address,
+        &Some(new_rate_provider.address.clone()),
+    );
+    pool.unpause(&admin);
+    assert_eq!(
+        pool.query_rate_providers(),
+        (Some(new_rate_provider.address), None)
+    );
+
+    pool.swap(
+        &user,
+        &None,
+        &token1.address,
+        &1_000,
+        &None,
+        &None,
+        &None::<u64>,
+    );
+    let shares = share_token.balance(&user);
+    pool.withdraw_liquidity(&user, &shares, &1, &1, &None::<u64>);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #29)")]
+fn failing_rate_provider_blocks_deposits() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let (pool, token1, token2) = deploy_pool(&env, &admin);
+
+    let rate_provider = deploy_rate_provider(&env, 1_000_000_000_000_000_000);
+    pool.set_rate_provider(
+        &admin,
+        &token1.address,
+        &Some(rate_provider.address.clone()),
+    );
+
+    token1.mint(&user, &2_000_000);
+    token2.mint(&user, &2_000_000);
+    pool.provide_liquidity(&user, &1_000_000, &1_000_000, &None, &None, &None::<u64>);
+
+    rate_provider.remove_rate();
+    pool.provide_liquidity(&user, &1_000_000, &1_000_000, &None, &None, &None::<u64>);
+}
@@ -1,6 +1,9 @@
 extern crate std;
 use phoenix::utils::{LiquidityPoolInitInfo, StakeInitInfo, TokenInitInfo};
-use soroban_sdk::{testutils::Address as _, Address, Env, String};
+use soroban_sdk::{
+    testutils::{Address as _, Ledger},
+    vec, Address, Env, String,
+};
 
 use super::setup::{deploy_stable_liquidity_pool_contract, deploy_token_contract};
 use crate::contract::{StableLiquidityPool, StableLiquidityPoolClient};
@@ -8,6 +11,7 @@ use crate::tests::setup::{install_stake_wasm, install_token_wasm};
 use crate::{
     stake_contract,
     storage::{Config, PairType},
+    token_contract,
 };
 
 #[test]
@@ -57,6 +61,9 @@ fn confirm_stake_contract_deployment() {
             fee_recipient: user1,
             max_allowed_slippage_bps: 500,
             max_allowed_spread_bps: 200,
+            max_referral_bps: 5_000,
+            protocol_fee_share_bps: 10_000,
+            flash_loan_fee_bps: 0,
         }
     );
 
@@ -215,3 +222,111 @@ fn pool_stable_initialization_should_fail_with_token_a_bigger_than_token_b() {
         &amp,
     );
 }
+
+#[test]
+fn provide_liquidity_and_stake_bonds_the_minted_shares() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let mut token1 = deploy_token_contract(&env, &Address::generate(&env));
+    let mut token2 = deploy_token_contract(&env, &Address::generate(&env));
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let user = Address::generate(&env);
+
+    let pool = deploy_stable_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+        None,
+    );
+    let share_token = token_contract::Client::new(&env, &pool.query_share_token_address());
+    let stake = stake_contract::Client::new(&env, &pool.query_stake_contract_address());
+
+    token1.mint(&user, &100_000);
+    token2.mint(&user, &100_000);
+
+    env.ledger().with_mut(|li| li.timestamp = 100);
+    let bonded =
+        pool.provide_liquidity_and_stake(&user, &100_000, &100_000, &None, &None, &None::<u64>);
+
+    // everything but the shares locked on the first deposit
+    assert_eq!(bonded, pool.query_total_issued_lp() - 1_000);
+    assert_eq!(share_token.balance(&user), 0);
+    assert_eq!(share_token.balance(&stake.address), bonded);
+    assert_eq!(
+        stake.query_staked(&user),
+        stake_contract::StakedResponse {
+            stakes: vec![
+                &env,
+                stake_contract::Stake {
+                    stake: bonded,
+                    stake_timestamp: 100,
+                }
+            ],
+            total_stake: bonded,
+        }
+    );
+}
+
+#[test]
+fn unstake_and_withdraw_unbonds_and_burns_the_shares() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let mut token1 = deploy_token_contract(&env, &Address::generate(&env));
+    let mut token2 = deploy_token_contract(&env, &Address::generate(&env));
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let user = Address::generate(&env);
+
+    let pool = deploy_stable_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0i64,
+        None,
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+        None,
+    );
+    let share_token = token_contract::Client::new(&env, &pool.query_share_token_address());
+    let stake = stake_contract::Client::new(&env, &pool.query_stake_contract_address());
+
+    token1.mint(&user, &150_000);
+    token2.mint(&user, &150_000);
+
+    env.ledger().with_mut(|li| li.timestamp = 100);
+    let first =
+        pool.provide_liquidity_and_stake(&user, &100_000, &100_000, &None, &None, &None::<u64>);
+    env.ledger().with_mut(|li| li.timestamp = 200);
+    let second =
+        pool.provide_liquidity_and_stake(&user, &50_000, &50_000, &None, &None, &None::<u64>);
+
+    // unbond the second stake only
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+    let total_shares = pool.query_total_issued_lp();
+    let (amount_a, amount_b) =
+        pool.unstake_and_withdraw(&user, &second, &200, &49_000, &49_000, &None::<u64>);
+
+    assert_eq!(amount_a, 150_000 * second / total_shares);
+    assert_eq!(amount_b, 150_000 * second / total_shares);
+    assert_eq!(token1.balance(&user), amount_a);
+    assert_eq!(token2.balance(&user), amount_b);
+    assert_eq!(share_token.balance(&user), 0);
+    assert_eq!(share_token.balance(&stake.address), first);
+    assert_eq!(pool.query_total_issued_lp(), total_shares - second);
+    assert_eq!(stake.query_staked(&user).total_stake, first);
+}
@@ -4,7 +4,9 @@ use soroban_sdk::testutils::{AuthorizedFunction, AuthorizedInvocation, Ledger};
 use soroban_sdk::{symbol_short, testutils::Address as _, Address, Env, IntoVal};
 
 use super::setup::{deploy_stable_liquidity_pool_contract, deploy_token_contract};
-use crate::storage::{Asset, PoolResponse, SimulateReverseSwapResponse, SimulateSwapResponse};
+use crate::storage::{
+    Asset, PoolResponse, Referral, SimulateReverseSwapResponse, SimulateSwapResponse,
+};
 use soroban_decimal::Decimal;
 
 #[test]
@@ -39,13 +41,14 @@ fn simple_swap() {
 
     token1.mint(&user1, &1_001_000);
     token2.mint(&user1, &1_001_000);
-    pool.provide_liquidity(&user1, &1_000_000, &1_000_000, &None, &None::<u64>);
+    pool.provide_liquidity(&user1, &1_000_000, &1_000_000, &None, &None, &None::<u64>);
 
     // true means "selling A token"
     // selling just one token with 1% max spread allowed
     let spread = 100i64; // 1% maximum spread allowed
     pool.swap(
         &user1,
+        &None,
         &token1.address,
         &1,
         &None,
@@ -62,6 +65,7 @@ fn simple_swap() {
                     symbol_short!("swap"),
                     (
                         &user1,
+                        None::<Referral>,
                         token1.address.clone(),
                         1_i128,
                         None::<i64>,
@@ -99,7 +103,7 @@ fn simple_swap() {
             },
             asset_lp_share: Asset {
                 address: share_token_address.clone(),
-                amount: 1998999i128,
+                amount: 1999999i128,
             },
             stake_address: pool.query_stake_contract_address(),
         }
@@ -109,14 +113,17 @@ fn simple_swap() {
 
     // false means selling B token
     // this time 100 units
-    let output_amount = pool.swap(
-        &user1,
-        &token2.address,
-        &1_000,
-        &None,
-        &Some(spread),
-        &None::<u64>,
-    );
+    let output_amount = pool
+        .swap(
+            &user1,
+            &None,
+            &token2.address,
+            &1_000,
+            &None,
+            &Some(spread),
+            &None::<u64>,
+        )
+        .ask_amount;
     let result = pool.query_pool_info();
     assert_eq!(
         result,
@@ -131,7 +138,7 @@ fn simple_swap() {
             },
             asset_lp_share: Asset {
                 address: share_token_address,
-                amount: 1998999i128, // this has not changed
+                amount: 1999999i128, // this has not changed
             },
             stake_address: pool.query_stake_contract_address(),
         }
@@ -141,6 +148,124 @@ fn simple_swap() {
     assert_eq!(token2.balance(&user1), 1001 - 1000); // user1 sold 1k of token B on second swap
 }
 
+#[test]
+fn simple_swap_with_referral_fee() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let manager = Address::generate(&env);
+    let factory = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let user1 = Address::generate(&env);
+    let referral_addr = Address::generate(&env);
+    let swap_fees = 0i64;
+    let pool = deploy_stable_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        swap_fees,
+        None,
+        None,
+        None,
+        manager,
+        factory,
+        None,
+    );
+
+    token1.mint(&user1, &1_001_000);
+    token2.mint(&user1, &1_001_000);
+    pool.provide_liquidity(&user1, &1_000_000, &1_000_000, &None, &None, &None::<u64>);
+
+    // selling with 10% fee for the referral
+    let referral = Referral {
+        address: referral_addr.clone(),
+        fee_bps: 1_000,
+    };
+
+    let spread = 100i64; // 1% maximum spread allowed
+    let output_amount = pool
+        .swap(
+            &user1,
+            &Some(referral),
+            &token2.address,
+            &1_000,
+            &None,
+            &Some(spread),
+            &None::<u64>,
+        )
+        .ask_amount;
+
+    assert_eq!(output_amount, 900);
+    assert_eq!(token1.balance(&referral_addr), 100);
+    assert_eq!(token1.balance(&user1), 1_900);
+    assert_eq!(token2.balance(&user1), 0);
+
+    let result = pool.query_pool_info();
+    // the pool pays out both the return amount and the referral fee
+    assert_eq!(result.asset_a.amount, 1_000_000 - 1_000);
+    assert_eq!(result.asset_b.amount, 1_000_000 + 1_000);
+}
+
+#[test]
+#[should_panic(expected = "HostError: Error(Contract, #23)")]
+fn swap_should_fail_when_referral_fee_is_larger_than_allowed() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let manager = Address::generate(&env);
+    let factory = Address::generate(&env);
+
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let user1 = Address::generate(&env);
+    let swap_fees = 0i64;
+    let pool = deploy_stable_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        swap_fees,
+        None,
+        None,
+        None,
+        manager,
+        factory,
+        None,
+    );
+
+    token1.mint(&user1, &1_001_000);
+    token2.mint(&user1, &1_001_000);
+    pool.provide_liquidity(&user1, &1_000_000, &1_000_000, &None, &None, &None::<u64>);
+
+    // in tests/setup.rs we hardcoded the max referral fee
+    // to 5_000 bps (50%), here we try to set it to 10_000 bps (100%)
+    let referral = Referral {
+        address: Address::generate(&env),
+        fee_bps: 10_000,
+    };
+
+    pool.swap(
+        &user1,
+        &Some(referral),
+        &token1.address,
+        &1_000,
+        &None,
+        &None,
+        &None::<u64>,
+    );
+}
+
 #[test]
 fn swap_with_high_fee() {
     let env = Env::default();
@@ -182,6 +307,7 @@ fn swap_with_high_fee() {
         &initial_liquidity,
         &initial_liquidity,
         &None,
+        &None,
         &None::<u64>,
     );
 
@@ -190,6 +316,7 @@ fn swap_with_high_fee() {
     // let's swap 100_000 units of Token 1 in 1:1 pool with 10% protocol fee
     pool.swap(
         &user1,
+        &None,
         &token1.address,
         &100_000,
         &None,
@@ -213,7 +340,7 @@ fn swap_with_high_fee() {
             },
             asset_lp_share: Asset {
                 address: pool.query_share_token_address(),
-                amount: 1998999i128,
+                amount: 1999999i128,
             },
             stake_address: pool.query_stake_contract_address(),
         }
@@ -224,6 +351,91 @@ fn swap_with_high_fee() {
     assert_eq!(token2.balance(&fee_recipient), fees);
 }
 
+#[test]
+fn swap_splits_commission_between_protocol_and_liquidity_providers() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let mut token1 = deploy_token_contract(&env, &admin);
+    let mut token2 = deploy_token_contract(&env, &admin);
+    if token2.address < token1.address {
+        std::mem::swap(&mut token1, &mut token2);
+    }
+    let user1 = Address::generate(&env);
+
+    let swap_fees = 1_000i64; // 10% bps
+    let fee_recipient = Address::generate(&env);
+    let pool = deploy_stable_liquidity_pool_contract(
+        &env,
+        admin.clone(),
+        (&token1.address, &token2.address),
+        swap_fees,
+        fee_recipient.clone(),
+        None,
+        None,
+        Address::generate(&env),
+        Address::generate(&env),
+        None,
+    );
+    // 40% of the commission goes to the fee recipient, 60% stays in the pool
+    pool.update_config(
+        &admin,
+        &None,
+        &None,
+        &None,
+        &None,
+        &None,
+        &None,
+        &Some(4_000i64),
+        &None,
+    );
+
+    let initial_liquidity = 1_000_000i128;
+    token1.mint(&user1, &(initial_liquidity + 100_000));
+    token2.mint(&user1, &initial_liquidity);
+    pool.provide_liquidity(
+        &user1,
+        &initial_liquidity,
+        &initial_liquidity,
+        &None,
+        &None,
+        &None::<u64>,
+    );
+
+    let simulation = pool.simulate_swap(&token1.address, &100_000);
+    // same swap as in `swap_with_high_fee`
+    let output_amount = 98_582i128;
+    let fees = Decimal::percent(10) * output_amount;
+    assert_eq!(simulation.commission_amount, fees);
+    assert_eq!(simulation.protocol_fee_amount, fees * 4 / 10);
+
+    pool.swap(
+        &user1,
+        &None,
+        &token1.address,
+        &100_000,
+        &None,
+        &Some(1_000),
+        &None::<u64>,
+    );
+
+    assert_eq!(token2.balance(&user1), output_amount - fees);
+    assert_eq!(
+        token2.balance(&fee_recipient),
+        simulation.protocol_fee_amount
+    );
+    // the liquidity providers' part of the commission stays in the reserves
+    let lp_fee_amount = fees - simulation.protocol_fee_amount;
+    let result = pool.query_pool_info();
+    assert_eq!(
+        result.asset_b.amount,
+        initial_liquidity - output_amount + lp_fee_amount
+    );
+    assert_eq!(token2.balance(&pool.address), result.asset_b.amount);
+}
+
 #[test]
 fn swap_simulation_even_pool() {
     let env = Env::default();
@@ -263,6 +475,7 @@ fn swap_simulation_even_pool() {
         &initial_liquidity,
         &initial_liquidity,
         &None,
+        &None,
         &None::<u64>,
     );
 
@@ -280,6 +493,7 @@ fn swap_simulation_even_pool() {
             // spread_amount: any difference between the offer and return amounts since it's 1:1
             spread_amount: offer_amount - output_amount,
             commission_amount: fees,
+            protocol_fee_amount: fees,
             total_return: offer_amount,
         }
     );
@@ -308,6 +522,7 @@ fn swap_simulation_even_pool() {
             ask_amount: output_amount - fees,
             spread_amount: offer_amount - output_amount,
             commission_amount: fees,
+            protocol_fee_amount: fees,
             total_return: offer_amount,
         }
     );
@@ -359,13 +574,14 @@ fn simple_swap_with_deadline_should_work() {
     token1.mint(&user1, &1_001_000);
     token2.mint(&user1, &1_001_000);
     env.ledger().with_mut(|li| li.timestamp = 49);
-    pool.provide_liquidity(&user1, &1_000_000, &1_000_000, &None, &Some(50));
+    pool.provide_liquidity(&user1, &1_000_000, &1_000_000, &None, &None, &Some(50));
 
     let spread = 100i64;
     // making the swap at the final moment
     env.ledger().with_mut(|li| li.timestamp = 99);
     pool.swap(
         &user1,
+        &None,
         &token1.address,
         &1,
         &None,
@@ -388,7 +604,7 @@ fn simple_swap_with_deadline_should_work() {
             },
             asset_lp_share: Asset {
                 address: share_token_address.clone(),
-                amount: 1998999i128,
+                amount: 1999999i128,
             },
             stake_address: pool.query_stake_contract_address(),
         }
@@ -399,14 +615,17 @@ fn simple_swap_with_deadline_should_work() {
     // false means selling B token
     // this time 100 units
     env.ledger().with_mut(|li| li.timestamp = 149);
-    let output_amount = pool.swap(
-        &user1,
-        &token2.address,
-        &1_000,
-        &None,
-        &Some(spread),
-        &Some(150u64),
-    );
+    let output_amount = pool
+        .swap(
+            &user1,
+            &None,
+            &token2.address,
+            &1_000,
+            &None,
+            &Some(spread),
+            &Some(150u64),
+        )
+        .ask_amount;
     let result = pool.query_pool_info();
     assert_eq!(
         result,
@@ -421,7 +640,7 @@ fn simple_swap_with_deadline_should_work() {
             },
             asset_lp_share: Asset {
                 address: share_token_address,
-                amount: 1998999i128, // this has not changed
+                amount: 1999999i128, // this has not changed
             },
             stake_address: pool.query_stake_contract_address(),
         }
@@ -464,7 +683,7 @@ fn simple_swap_should_panic_after_deadline() {
 
     token1.mint(&user1, &1_001_000);
     token2.mint(&user1, &1_001_000);
-    pool.provide_liquidity(&user1, &1_000_000, &1_000_000, &None, &None::<u64>);
+    pool.provide_liquidity(&user1, &1_000_000, &1_000_000, &None, &None, &None::<u64>);
 
     // true means "selling A token"
     // selling just one token with 1% max spread allowed
@@ -473,6 +692,7 @@ fn simple_swap_should_panic_after_deadline() {
     env.ledger().with_mut(|li| li.timestamp = 100);
     pool.swap(
         &user1,
+        &None,
         &token1.address,
         &1,
         &None,
@@ -0,0 +1,1446 @@
+use phoenix::utils::{LiquidityPoolInitInfo, StableMultiPoolInitInfo};
+use soroban_sdk::{
+    contract, contractimpl, contractmeta, log, panic_with_error, vec, Address, BytesN, Env,
+    IntoVal, String, Vec, U256,
+};
+
+use crate::{
+    error::ContractError,
+    math::{
+        calc_y, compute_current_amp, compute_d, scale_value, AMP_PRECISION, MAX_AMP_CHANGE,
+        MAX_COINS, MIN_AMP_CHANGING_TIME,
+    },
+    stake_contract,
+    storage::{
+        get_amp, get_config, get_greatest_precision, get_precisions, save_amp, save_config,
+        save_precisions, utils,
+        utils::{get_admin, is_initialized, set_initialized},
+        AmplifierParameters, Asset, Config, PairType, PoolResponse, Referral,
+        SimulateProvideLiquidityResponse, SimulateReverseSwapResponse, SimulateSwapResponse,
+        StableLiquidityPoolInfo, StableMultiPoolResponse, SwapResponse,
+    },
+    token_contract, DECIMAL_PRECISION,
+};
+use phoenix::{validate_bps, validate_int_parameters};
+use soroban_decimal::Decimal;
+
+// Amount of LP shares locked in the pool on the first deposit
+const MINIMUM_LIQUIDITY_AMOUNT: i128 = 1000;
+const MAX_AMP: u64 = 1_000_000;
+// Two coin pools are served by the stable pool
+const MIN_COINS: u32 = 3;
+
+// Metadata that is added on to the WASM custom section
+contractmeta!(
+    key = "Description",
+    val = "Phoenix Protocol Multi Asset Stable Liquidity Pool"
+);
+
+#[contract]
+pub struct StableMultiLiquidityPool;
+
+#[allow(dead_code)]
+pub trait StableMultiLiquidityPoolTrait {
+    // Sets the token contract addresses for this pool
+    // token_wasm_hash is the WASM hash of the deployed token contract for the pool share token
+    // The coins of the pool are token A and token B of `lp_init_info`, followed by the
+    // `additional_tokens` of `init_info`
+    #[allow(clippy::too_many_arguments)]
+    fn initialize(
+        env: Env,
+        stake_wasm_hash: BytesN<32>,
+        token_wasm_hash: BytesN<32>,
+        lp_init_info: LiquidityPoolInitInfo,
+        factory_addr: Address,
+        share_token_decimal: u32,
+        share_token_name: String,
+        share_token_symbol: String,
+        amp: u64,
+        init_info: StableMultiPoolInitInfo,
+    );
+
+    // Deposits `desired_amounts` of every coin, in the order of the pool tokens, and mints pool
+    // shares for the depositor in proportion to the change of the invariant.
+    // The part of the deposit deviating from the ratio of the pool is charged an imbalance fee of
+    // half the swap commission spread over the coins, i.e. total fee * n / (4 * (n - 1)) for n
+    // coins. Fails if fewer than `min_shares_out` shares would be minted.
+    fn provide_liquidity(
+        env: Env,
+        depositor: Address,
+        desired_amounts: Vec<i128>,
+        custom_slippage_bps: Option<i64>,
+        min_shares_out: Option<i128>,
+        deadline: Option<u64>,
+    );
+
+    // `offer_asset` is the asset that the user would like to swap for `ask_asset`, both coins of the pool.
+    // `offer_amount` is the amount being sold, with `max_spread_bps` being a safety to make sure you receive at least that amount.
+    // swap will transfer the selling token "to" to this contract, and then the contract will transfer the buying token to `sender`.
+    // An optional `referral` receives `fee_bps` of the bought amount, capped by the pool's `max_referral_bps`.
+    // Returns the amount of the token being bought with the commission, fees and spread of the swap.
+    #[allow(clippy::too_many_arguments)]
+    fn swap(
+        env: Env,
+        sender: Address,
+        referral: Option<Referral>,
+        offer_asset: Address,
+        ask_asset: Address,
+        offer_amount: i128,
+        ask_asset_min_amount: Option<i128>,
+        max_spread_bps: Option<i64>,
+        deadline: Option<u64>,
+    ) -> SwapResponse;
+
+    // transfers share_amount of pool share tokens to this contract, burns all pools share tokens in this contracts, and sends the
+    // corresponding amount of every coin to "to".
+    // Returns the amounts withdrawn, in the order of the pool tokens
+    fn withdraw_liquidity(
+        env: Env,
+        recipient: Address,
+        share_amount: i128,
+        min_amounts: Vec<i128>,
+        deadline: Option<u64>,
+    ) -> Vec<i128>;
+
+    // Allows admin address set during initialization to change some parameters of the
+    // configuration
+    #[allow(clippy::too_many_arguments)]
+    fn update_config(
+        env: Env,
+        sender: Address,
+        new_admin: Option<Address>,
+        total_fee_bps: Option<i64>,
+        fee_recipient: Option<Address>,
+        max_allowed_slippage_bps: Option<i64>,
+        max_allowed_spread_bps: Option<i64>,
+        protocol_fee_share_bps: Option<i64>,
+    );
+
+    // Allows admin to start gradually changing the amplification parameter (AMP) from its
+    // current value to `next_amp`, which will be reached at `next_amp_time`
+    fn ramp_amp(env: Env, sender: Address, next_amp: u64, next_amp_time: u64);
+
+    // Allows admin to stop an ongoing AMP ramp, freezing AMP at its current value
+    fn stop_ramp_amp(env: Env, sender: Address);
+
+    // Sets the guardian, an address allowed to pause the pool next to the admin and the factory
+    fn set_guardian(env: Env, guardian: Address);
+
+    // Pauses swaps and deposits. Withdrawals stay available.
+    // Callable by the admin, the guardian or the factory
+    fn pause(env: Env, sender: Address);
+
+    // Resumes the pool. Callable by the admin or the factory
+    fn unpause(env: Env, sender: Address);
+
+    // Migration entrypoint
+    fn upgrade(e: Env, new_wasm_hash: BytesN<32>);
+
+    // QUERIES
+
+    // Returns the configuration structure containing the addresses
+    fn query_config(env: Env) -> Config;
+
+    // Returns the address for the pool share token
+    fn query_share_token_address(env: Env) -> Address;
+
+    // Returns the address for the pool stake contract
+    fn query_stake_contract_address(env: Env) -> Address;
+
+    // Returns the total amount of LP tokens and of every asset in the pool
+    fn query_pool_info(env: Env) -> StableMultiPoolResponse;
+
+    // Returns the pool info in the two asset format of the factory, with the first two coins of
+    // the pool. The whole basket is returned by `query_pool_info`
+    fn query_pool_info_for_factory(env: Env) -> StableLiquidityPoolInfo;
+
+    // Simulate swap transaction
+    fn simulate_swap(
+        env: Env,
+        offer_asset: Address,
+        ask_asset: Address,
+        offer_amount: i128,
+    ) -> SimulateSwapResponse;
+
+    // Simulate reverse swap transaction
+    fn simulate_reverse_swap(
+        env: Env,
+        offer_asset: Address,
+        ask_asset: Address,
+        ask_amount: i128,
+    ) -> SimulateReverseSwapResponse;
+
+    // Returns the amounts of every asset `amount` of pool shares is worth
+    // Simulate depositing `desired_amounts`, returning the shares minted and the imbalance fees
+    // charged on every coin. `custom_slippage_bps` is validated the same way as in
+    // `provide_liquidity`
+    fn simulate_provide_liquidity(
+        env: Env,
+        desired_amounts: Vec<i128>,
+        custom_slippage_bps: Option<i64>,
+    ) -> SimulateProvideLiquidityResponse;
+
+    fn query_share(env: Env, amount: i128) -> Vec<Asset>;
+
+    fn query_total_issued_lp(env: Env) -> i128;
+
+    // Returns the amplification parameter (AMP) currently in effect
+    fn query_current_amp(env: Env) -> u64;
+
+    fn query_paused(env: Env) -> bool;
+
+    fn query_guardian(env: Env) -> Option<Address>;
+}
+
+#[contractimpl]
+impl StableMultiLiquidityPoolTrait for StableMultiLiquidityPool {
+    #[allow(clippy::too_many_arguments)]
+    fn initialize(
+        env: Env,
+        stake_wasm_hash: BytesN<32>,
+        token_wasm_hash: BytesN<32>,
+        lp_init_info: LiquidityPoolInitInfo,
+        factory_addr: Address,
+        _share_token_decimal: u32,
+        share_token_name: String,
+        share_token_symbol: String,
+        amp: u64,
+        init_info: StableMultiPoolInitInfo,
+    ) {
+        if is_initialized(&env) {
+            log!(
+                &env,
+                "Pool Stable Multi: Initialize: initializing contract twice is not allowed"
+            );
+            panic_with_error!(&env, ContractError::AlreadyInitialized);
+        }
+
+        let admin = lp_init_info.admin;
+        let swap_fee_bps = lp_init_info.swap_fee_bps;
+        let fee_recipient = lp_init_info.fee_recipient;
+        let max_allowed_slippage_bps = lp_init_info.max_allowed_slippage_bps;
+        let max_allowed_spread_bps = lp_init_info.max_allowed_spread_bps;
+        let max_referral_bps = lp_init_info.max_referral_bps;
+        let token_init_info = lp_init_info.token_init_info;
+        let stake_init_info = lp_init_info.stake_init_info;
+
+        validate_bps!(
+            swap_fee_bps,
+            max_allowed_slippage_bps,
+            max_allowed_spread_bps,
+            max_referral_bps
+        );
+        set_initialized(&env);
+
+        // Token info
+        let mut tokens = vec![&env, token_init_info.token_a, token_init_info.token_b];
+        tokens.append(&init_info.additional_tokens);
+        // Contract info
+        let min_bond = stake_init_info.min_bond;
+        let min_reward = stake_init_info.min_reward;
+        let manager = stake_init_info.manager;
+
+        if tokens.len() < MIN_COINS || tokens.len() > MAX_COINS as u32 {
+            log!(
+                &env,
+                "Pool Stable Multi: Initialize: The pool must have between {} and {} tokens",
+                MIN_COINS,
+                MAX_COINS as u32
+            );
+            panic_with_error!(&env, ContractError::InvalidNumberOfTokens);
+        }
+
+        // Token order validation to make sure only one instance of a pool can exist
+        for (current, next) in tokens.iter().zip(tokens.iter().skip(1)) {
+            if current >= next {
+                log!(
+                    &env,
+                    "Pool Stable Multi: Initialize: Tokens must be sorted alphabetically and unique"
+                );
+                panic_with_error!(&env, ContractError::TokensNotSorted);
+            }
+        }
+
+        save_precisions(&env, &tokens);
+
+        // deploy token contract
+        let share_token_address = utils::deploy_token_contract(&env, token_wasm_hash, &tokens);
+        token_contract::Client::new(&env, &share_token_address).initialize(
+            // admin
+            &env.current_contract_address(),
+            // number of decimals on the share token
+            &get_greatest_precision(&env),
+            // name
+            &share_token_name.into_val(&env),
+            // symbol
+            &share_token_symbol.into_val(&env),
+        );
+
+        let stake_contract_address = utils::deploy_stake_contract(&env, stake_wasm_hash);
+        stake_contract::Client::new(&env, &stake_contract_address).initialize(
+            &admin,
+            &share_token_address,
+            &min_bond,
+            &min_reward,
+            &manager,
+            &factory_addr,
+            &stake_init_info.max_complexity,
+        );
+
+        let config = Config {
+            tokens: tokens.clone(),
+            share_token: share_token_address,
+            stake_contract: stake_contract_address,
+            pool_type: PairType::StableMulti,
+            total_fee_bps: swap_fee_bps,
+            fee_recipient,
+            max_allowed_slippage_bps,
+            max_allowed_spread_bps,
+            max_referral_bps,
+            // the whole commission goes to the fee recipient until the admin sets a share
+            protocol_fee_share_bps: 10_000,
+        };
+        save_config(&env, config);
+        let current_time = env.ledger().timestamp();
+        if amp == 0 || amp > MAX_AMP {
+            log!(
+                &env,
+                "Pool Stable Multi: Initialize: AMP parameter is incorrect"
+            );
+            panic_with_error!(&env, ContractError::InvalidAMP);
+        }
+        save_amp(
+            &env,
+            AmplifierParameters {
+                init_amp: amp * AMP_PRECISION,
+                init_amp_time: current_time,
+                next_amp: amp * AMP_PRECISION,
+                next_amp_time: current_time,
+            },
+        );
+        utils::save_admin(&env, admin);
+        utils::save_factory(&env, factory_addr);
+        utils::save_total_shares(&env, 0);
+        let mut balances = Vec::new(&env);
+        for _ in tokens.iter() {
+            balances.push_back(0i128);
+        }
+        utils::save_pool_balances(&env, &balances);
+
+        env.events()
+            .publish(("initialize", "Stable Multi LP tokens"), tokens);
+    }
+
+    fn provide_liquidity(
+        env: Env,
+        sender: Address,
+        desired_amounts: Vec<i128>,
+        custom_slippage_bps: Option<i64>,
+        min_shares_out: Option<i128>,
+        deadline: Option<u64>,
+    ) {
+        ensure_not_paused(&env);
+
+        if let Some(deadline) = deadline {
+            if env.ledger().timestamp() > deadline {
+                log!(
+                    env,
+                    "Pool Stable Multi: Provide Liquidity: Transaction executed after deadline!"
+                );
+                panic_with_error!(env, ContractError::TransactionAfterTimestampDeadline)
+            }
+        }
+
+        let config = get_config(&env);
+        ensure_deposit_amounts(&env, &config, &desired_amounts);
+
+        // sender needs to authorize the deposit
+        sender.require_auth();
+
+        validate_custom_slippage(&env, &config, custom_slippage_bps);
+
+        let deposit = compute_provide_liquidity(&env, &config, &desired_amounts);
+
+        if let Some(min_shares_out) = min_shares_out {
+            if deposit.shares < min_shares_out {
+                log!(
+                    &env,
+                    "Pool Stable Multi: ProvideLiquidity: Shares minted are less than the minimum requested"
+                );
+                panic_with_error!(env, ContractError::ProvideLiquiditySharesBelowMinimum);
+            }
+        }
+
+        if utils::get_total_shares(&env) == 0 {
+            // the first shares are locked in the pool forever, so the value of a single share can
+            // not be inflated far enough to round the following deposits down to nothing
+            utils::mint_shares(
+                &env,
+                &config.share_token,
+                &env.current_contract_address(),
+                MINIMUM_LIQUIDITY_AMOUNT,
+            );
+        }
+
+        // Move tokens from client's wallet to the contract, the protocol part of the imbalance
+        // fees goes to the fee recipient and the rest stays in the pool
+        let old_balances = utils::get_pool_balances(&env);
+        let mut new_balances = Vec::new(&env);
+        for (index, token) in config.tokens.iter().enumerate() {
+            let index = index as u32;
+            let desired = desired_amounts.get_unchecked(index);
+            let protocol_fee = deposit.fees.get_unchecked(index) * config.protocol_fee_share();
+
+            let token_client = token_contract::Client::new(&env, &token);
+            token_client.transfer(&sender, &env.current_contract_address(), &desired);
+            if protocol_fee > 0 {
+                token_client.transfer(
+                    &env.current_contract_address(),
+                    &config.fee_recipient,
+                    &protocol_fee,
+                );
+            }
+
+            new_balances.push_back(old_balances.get_unchecked(index) + desired - protocol_fee);
+        }
+
+        utils::mint_shares(&env, &config.share_token, &sender, deposit.shares);
+        utils::save_pool_balances(&env, &new_balances);
+
+        env.events()
+            .publish(("provide_liquidity", "sender"), sender);
+        env.events()
+            .publish(("provide_liquidity", "tokens"), config.tokens);
+        env.events()
+            .publish(("provide_liquidity", "amounts"), desired_amounts);
+        env.events()
+            .publish(("provide_liquidity", "fees"), deposit.fees);
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn swap(
+        env: Env,
+        sender: Address,
+        referral: Option<Referral>,
+        offer_asset: Address,
+        ask_asset: Address,
+        offer_amount: i128,
+        ask_asset_min_amount: Option<i128>,
+        max_spread_bps: Option<i64>,
+        deadline: Option<u64>,
+    ) -> SwapResponse {
+        ensure_not_paused(&env);
+
+        if let Some(deadline) = deadline {
+            if env.ledger().timestamp() > deadline {
+                log!(
+                    env,
+                    "Pool Stable Multi: Swap: Transaction executed after deadline!"
+                );
+                panic_with_error!(env, ContractError::TransactionAfterTimestampDeadline)
+            }
+        }
+
+        validate_int_parameters!(offer_amount);
+
+        sender.require_auth();
+
+        do_swap(
+            env,
+            sender,
+            referral,
+            offer_asset,
+            ask_asset,
+            offer_amount,
+            ask_asset_min_amount,
+            max_spread_bps,
+        )
+    }
+
+    fn withdraw_liquidity(
+        env: Env,
+        sender: Address,
+        share_amount: i128,
+        min_amounts: Vec<i128>,
+        deadline: Option<u64>,
+    ) -> Vec<i128> {
+        if let Some(deadline) = deadline {
+            if env.ledger().timestamp() > deadline {
+                log!(
+                    env,
+                    "Pool Stable Multi: Withdraw Liquidity: Transaction executed after deadline!"
+                );
+                panic_with_error!(env, ContractError::TransactionAfterTimestampDeadline)
+            }
+        }
+
+        validate_int_parameters!(share_amount);
+
+        let config = get_config(&env);
+        ensure_amount_per_token(&env, &config, &min_amounts);
+
+        sender.require_auth();
+
+        let share_token_client = token_contract::Client::new(&env, &config.share_token);
+        share_token_client.transfer(&sender, &env.current_contract_address(), &share_amount);
+
+        let total_shares = utils::get_total_shares(&env);
+        if total_shares == 0i128 {
+            log!(&env, "Pool Stable Multi: WithdrawLiquidity: Critical error - Total shares are equal to zero before withdrawal!");
+            panic_with_error!(env, ContractError::TotalSharesEqualZero);
+        }
+
+        let share_ratio = Decimal::from_ratio(share_amount, total_shares);
+
+        let pool_balances = utils::get_pool_balances(&env);
+        let mut return_amounts = Vec::new(&env);
+        let mut new_balances = Vec::new(&env);
+        for (pool_balance, min_amount) in pool_balances.iter().zip(min_amounts.iter()) {
+            let return_amount = pool_balance * share_ratio;
+            if return_amount < min_amount {
+                log!(
+                    &env,
+                    "Pool Stable Multi: WithdrawLiquidity: Minimum amount of a token is not satisfied! min_amount: {}, return_amount: {}",
+                    min_amount,
+                    return_amount
+                );
+                panic_with_error!(
+                    env,
+                    ContractError::WithdrawLiquidityMinimumAmountIsNotSatisfied
+                );
+            }
+            return_amounts.push_back(return_amount);
+            new_balances.push_back(pool_balance - return_amount);
+        }
+
+        // burn shares
+        utils::burn_shares(&env, &config.share_token, share_amount);
+        // transfer tokens from contract to sender
+        for (token, return_amount) in config.tokens.iter().zip(return_amounts.iter()) {
+            token_contract::Client::new(&env, &token).transfer(
+                &env.current_contract_address(),
+                &sender,
+                &return_amount,
+            );
+        }
+        // update pool balances
+        utils::save_pool_balances(&env, &new_balances);
+
+        env.events()
+            .publish(("withdraw_liquidity", "sender"), sender);
+        env.events()
+            .publish(("withdraw_liquidity", "shares_amount"), share_amount);
+        env.events().publish(
+            ("withdraw_liquidity", "return_amounts"),
+            return_amounts.clone(),
+        );
+
+        return_amounts
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn update_config(
+        env: Env,
+        sender: Address,
+        new_admin: Option<Address>,
+        total_fee_bps: Option<i64>,
+        fee_recipient: Option<Address>,
+        max_allowed_slippage_bps: Option<i64>,
+        max_allowed_spread_bps: Option<i64>,
+        protocol_fee_share_bps: Option<i64>,
+    ) {
+        sender.require_auth();
+
+        if sender != utils::get_admin(&env) {
+            log!(&env, "Pool Stable Multi: UpdateConfig: Unauthorized");
+            panic_with_error!(&env, ContractError::Unauthorized);
+        }
+
+        let mut config = get_config(&env);
+
+        if let Some(new_admin) = new_admin {
+            utils::save_admin(&env, new_admin);
+        }
+        if let Some(total_fee_bps) = total_fee_bps {
+            if !(0..=10_000).contains(&total_fee_bps) {
+                log!(
+                    &env,
+                    "Pool Stable Multi: UpdateConfig: Invalid total_fee_bps"
+                );
+                panic_with_error!(&env, ContractError::InvalidBps);
+            }
+            config.total_fee_bps = total_fee_bps;
+        }
+        if let Some(fee_recipient) = fee_recipient {
+            config.fee_recipient = fee_recipient;
+        }
+        if let Some(max_allowed_slippage_bps) = max_allowed_slippage_bps {
+            config.max_allowed_slippage_bps = max_allowed_slippage_bps;
+        }
+        if let Some(max_allowed_spread_bps) = max_allowed_spread_bps {
+            config.max_allowed_spread_bps = max_allowed_spread_bps;
+        }
+        if let Some(protocol_fee_share_bps) = protocol_fee_share_bps {
+            if !(0..=10_000).contains(&protocol_fee_share_bps) {
+                log!(
+                    &env,
+                    "Pool Stable Multi: UpdateConfig: Invalid protocol_fee_share_bps"
+                );
+                panic_with_error!(&env, ContractError::InvalidBps);
+            }
+            config.protocol_fee_share_bps = protocol_fee_share_bps;
+        }
+
+        save_config(&env, config);
+    }
+
+    fn ramp_amp(env: Env, sender: Address, next_amp: u64, next_amp_time: u64) {
+        sender.require_auth();
+
+        if sender != utils::get_admin(&env) {
+            log!(&env, "Pool Stable Multi: RampAmp: Unauthorized");
+            panic_with_error!(&env, ContractError::Unauthorized);
+        }
+
+        if next_amp == 0 || next_amp > MAX_AMP {
+            log!(
+                &env,
+                "Pool Stable Multi: RampAmp: AMP parameter is incorrect"
+            );
+            panic_with_error!(&env, ContractError::InvalidAMP);
+        }
+
+        let amp_params = get_amp(&env);
+        let current_time = env.ledger().timestamp();
+
+        if current_time < amp_params.init_amp_time + MIN_AMP_CHANGING_TIME {
+            log!(
+                &env,
+                "Pool Stable Multi: RampAmp: AMP can't be changed more often than once per {} seconds",
+                MIN_AMP_CHANGING_TIME
+            );
+            panic_with_error!(&env, ContractError::AmpRampTooEarly);
+        }
+
+        if next_amp_time < current_time + MIN_AMP_CHANGING_TIME {
+            log!(
+                &env,
+                "Pool Stable Multi: RampAmp: AMP ramp must last at least {} seconds",
+                MIN_AMP_CHANGING_TIME
+            );
+            panic_with_error!(&env, ContractError::AmpRampTimeTooShort);
+        }
+
+        let current_amp = compute_current_amp(&env, &amp_params);
+        let next_amp = next_amp * AMP_PRECISION;
+
+        if (next_amp > current_amp && next_amp > current_amp * MAX_AMP_CHANGE)
+            || (next_amp < current_amp && next_amp * MAX_AMP_CHANGE < current_amp)
+        {
+            log!(
+                &env,
+                "Pool Stable Multi: RampAmp: AMP can't be changed by more than {} times at once",
+                MAX_AMP_CHANGE
+            );
+            panic_with_error!(&env, ContractError::AmpChangeTooBig);
+        }
+
+        save_amp(
+            &env,
+            AmplifierParameters {
+                init_amp: current_amp,
+                init_amp_time: current_time,
+                next_amp,
+                next_amp_time,
+            },
+        );
+
+        env.events()
+            .publish(("ramp_amp", "init_amp"), current_amp / AMP_PRECISION);
+        env.events()
+            .publish(("ramp_amp", "next_amp"), next_amp / AMP_PRECISION);
+        env.events()
+            .publish(("ramp_amp", "next_amp_time"), next_amp_time);
+    }
+
+    fn stop_ramp_amp(env: Env, sender: Address) {
+        sender.require_auth();
+
+        if sender != utils::get_admin(&env) {
+            log!(&env, "Pool Stable Multi: StopRampAmp: Unauthorized");
+            panic_with_error!(&env, ContractError::Unauthorized);
+        }
+
+        let current_amp = compute_current_amp(&env, &get_amp(&env));
+        let current_time = env.ledger().timestamp();
+
+        save_amp(
+            &env,
+            AmplifierParameters {
+                init_amp: current_amp,
+                init_amp_time: current_time,
+                next_amp: current_amp,
+                next_amp_time: current_time,
+            },
+        );
+
+        env.events().publish(
+            ("stop_ramp_amp", "current_amp"),
+            current_amp / AMP_PRECISION,
+        );
+    }
+
+    fn set_guardian(env: Env, guardian: Address) {
+        let admin: Address = utils::get_admin(&env);
+        admin.require_auth();
+
+        utils::save_guardian(&env, guardian.clone());
+
+        env.events().publish(("set_guardian", "guardian"), guardian);
+    }
+
+    fn pause(env: Env, sender: Address) {
+        sender.require_auth();
+
+        let is_guardian = utils::get_guardian(&env).map_or(false, |guardian| guardian == sender);
+        let is_factory = utils::get_factory(&env).map_or(false, |factory| factory == sender);
+        if sender != utils::get_admin(&env) && !is_factory && !is_guardian {
+            log!(
+                &env,
+                "Pool Stable Multi: Pause: You are not authorized to pause the pool!"
+            );
+            panic_with_error!(&env, ContractError::Unauthorized);
+        }
+
+        utils::set_paused(&env, true);
+
+        env.events().publish(("pause", "sender"), sender);
+    }
+
+    fn unpause(env: Env, sender: Address) {
+        sender.require_auth();
+
+        let is_factory = utils::get_factory(&env).map_or(false, |factory| factory == sender);
+        if sender != utils::get_admin(&env) && !is_factory {
+            log!(
+                &env,
+                "Pool Stable Multi: Unpause: You are not authorized to unpause the pool!"
+            );
+            panic_with_error!(&env, ContractError::Unauthorized);
+        }
+
+        utils::set_paused(&env, false);
+
+        env.events().publish(("unpause", "sender"), sender);
+    }
+
+    fn upgrade(env: Env, new_wasm_hash: BytesN<32>) {
+        let admin: Address = utils::get_admin(&env);
+        admin.require_auth();
+
+        env.deployer().update_current_contract_wasm(new_wasm_hash);
+    }
+
+    // Queries
+
+    fn query_config(env: Env) -> Config {
+        get_config(&env)
+    }
+
+    fn query_share_token_address(env: Env) -> Address {
+        get_config(&env).share_token
+    }
+
+    fn query_stake_contract_address(env: Env) -> Address {
+        get_config(&env).stake_contract
+    }
+
+    fn query_pool_info(env: Env) -> StableMultiPoolResponse {
+        let config = get_config(&env);
+
+        let mut assets = Vec::new(&env);
+        for (address, amount) in config
+            .tokens
+            .iter()
+            .zip(utils::get_pool_balances(&env).iter())
+        {
+            assets.push_back(Asset { address, amount });
+        }
+
+        StableMultiPoolResponse {
+            assets,
+            asset_lp_share: Asset {
+                address: config.share_token,
+                amount: utils::get_total_shares(&env),
+            },
+            stake_address: config.stake_contract,
+        }
+    }
+
+    fn query_pool_info_for_factory(env: Env) -> StableLiquidityPoolInfo {
+        let config = get_config(&env);
+        let pool_info = Self::query_pool_info(env.clone());
+
+        StableLiquidityPoolInfo {
+            pool_address: env.current_contract_address(),
+            pool_response: PoolResponse {
+                asset_a: pool_info.assets.get(0).unwrap(),
+                asset_b: pool_info.assets.get(1).unwrap(),
+                asset_lp_share: pool_info.asset_lp_share,
+                stake_address: pool_info.stake_address,
+            },
+            total_fee_bps: config.total_fee_bps,
+        }
+    }
+
+    fn simulate_swap(
+        env: Env,
+        offer_asset: Address,
+        ask_asset: Address,
+        offer_amount: i128,
+    ) -> SimulateSwapResponse {
+        let config = get_config(&env);
+        let (offer_index, ask_index) = get_swap_indexes(&env, &config, &offer_asset, &ask_asset);
+
+        let (ask_amount, spread_amount, commission_amount) = compute_swap(
+            &env,
+            compute_current_amp(&env, &get_amp(&env)),
+            &utils::get_pool_balances(&env),
+            &get_precisions(&env),
+            offer_index,
+            ask_index,
+            offer_amount,
+            config.protocol_fee_rate(),
+        );
+
+        let total_return = ask_amount + commission_amount + spread_amount;
+
+        SimulateSwapResponse {
+            ask_amount,
+            spread_amount,
+            commission_amount,
+            protocol_fee_amount: commission_amount * config.protocol_fee_share(),
+            total_return,
+        }
+    }
+
+    fn simulate_reverse_swap(
+        env: Env,
+        offer_asset: Address,
+        ask_asset: Address,
+        ask_amount: i128,
+    ) -> SimulateReverseSwapResponse {
+        let config = get_config(&env);
+        let (offer_index, ask_index) = get_swap_indexes(&env, &config, &offer_asset, &ask_asset);
+
+        let (offer_amount, spread_amount, commission_amount) = compute_offer_amount(
+            &env,
+            compute_current_amp(&env, &get_amp(&env)),
+            &utils::get_pool_balances(&env),
+            &get_precisions(&env),
+            offer_index,
+            ask_index,
+            ask_amount,
+            config.protocol_fee_rate(),
+        );
+
+        SimulateReverseSwapResponse {
+            offer_amount,
+            spread_amount,
+            commission_amount,
+        }
+    }
+
+    fn simulate_provide_liquidity(
+        env: Env,
+        desired_amounts: Vec<i128>,
+        custom_slippage_bps: Option<i64>,
+    ) -> SimulateProvideLiquidityResponse {
+        let config = get_config(&env);
+        ensure_deposit_amounts(&env, &config, &desired_amounts);
+        validate_custom_slippage(&env, &config, custom_slippage_bps);
+
+        compute_provide_liquidity(&env, &config, &desired_amounts)
+    }
+
+    fn query_share(env: Env, amount: i128) -> Vec<Asset> {
+        let pool_info = Self::query_pool_info(env.clone());
+        let total_share = pool_info.asset_lp_share.amount;
+
+        let mut share_ratio = Decimal::zero();
+        if total_share != 0 {
+            share_ratio = Decimal::from_ratio(amount, total_share);
+        }
+
+        let mut shares = Vec::new(&env);
+        for asset in pool_info.assets.iter() {
+            shares.push_back(Asset {
+                address: asset.address,
+                amount: asset.amount * share_ratio,
+            });
+        }
+        shares
+    }
+
+    fn query_total_issued_lp(env: Env) -> i128 {
+        utils::get_total_shares(&env)
+    }
+
+    fn query_current_amp(env: Env) -> u64 {
+        compute_current_amp(&env, &get_amp(&env)) / AMP_PRECISION
+    }
+
+    fn query_paused(env: Env) -> bool {
+        utils::is_paused(&env)
+    }
+
+    fn query_guardian(env: Env) -> Option<Address> {
+        utils::get_guardian(&env)
+    }
+}
+
+#[contractimpl]
+impl StableMultiLiquidityPool {
+    #[allow(dead_code)]
+    pub fn update(env: Env, new_wasm_hash: BytesN<32>) {
+        let admin = get_admin(&env);
+        admin.require_auth();
+
+        env.deployer().update_current_contract_wasm(new_wasm_hash);
+    }
+}
+
+fn ensure_not_paused(env: &Env) {
+    if utils::is_paused(env) {
+        log!(env, "Pool Stable Multi: The pool is paused!");
+        panic_with_error!(env, ContractError::ContractPaused);
+    }
+}
+
+/// Liquidity operations take one amount per coin, in the order of the pool tokens.
+fn ensure_amount_per_token(env: &Env, config: &Config, amounts: &Vec<i128>) {
+    if amounts.len() != config.tokens.len() {
+        log!(
+            env,
+            "Pool Stable Multi: Expected an amount for each of the {} tokens of the pool",
+            config.tokens.len()
+        );
+        panic_with_error!(env, ContractError::InvalidNumberOfAmounts);
+    }
+}
+
+/// Returns the positions of the offer and ask asset in the pool tokens.
+/// Every coin of the pool must be deposited with a positive amount.
+fn ensure_deposit_amounts(env: &Env, config: &Config, desired_amounts: &Vec<i128>) {
+    ensure_amount_per_token(env, config, desired_amounts);
+
+    if desired_amounts.iter().any(|amount| amount <= 0) {
+        log!(
+            env,
+            "Pool Stable Multi: ProvideLiquidity: All tokens must be provided and must be bigger then 0!"
+        );
+        panic_with_error!(
+            env,
+            ContractError::ProvideLiquidityAllTokensMustBeMoreThanZero
+        );
+    }
+}
+
+fn validate_custom_slippage(env: &Env, config: &Config, custom_slippage_bps: Option<i64>) {
+    // Check if custom_slippage_bps is more than max_allowed_slippage
+    if let Some(custom_slippage) = custom_slippage_bps {
+        if custom_slippage > config.max_allowed_slippage_bps {
+            log!(
+                env,
+                "Pool Stable Multi: ProvideLiquidity: Custom slippage tolerance is more than max allowed slippage tolerance"
+            );
+            panic_with_error!(env, ContractError::ProvideLiquiditySlippageToleranceTooHigh);
+        }
+    }
+}
+
+/// Computes the pool shares minted for depositing `desired_amounts` and the imbalance fees
+/// charged on every coin.
+///
+/// Depositing off the ratio of the pool amounts to a swap, so the difference between each new
+/// balance and the balance the pool would have after a balanced deposit of the same invariant is
+/// charged half of the swap commission, i.e. total fee * n / (4 * (n - 1)) for n coins, and the
+/// shares are minted from the invariant of the balances after those fees.
+fn compute_provide_liquidity(
+    env: &Env,
+    config: &Config,
+    desired_amounts: &Vec<i128>,
+) -> SimulateProvideLiquidityResponse {
+    let amp = compute_current_amp(env, &get_amp(env)) as u128;
+    let precisions = get_precisions(env);
+    let old_balances = utils::get_pool_balances(env);
+    let n_coins = config.tokens.len();
+
+    let mut new_balances = Vec::new(env);
+    for (old_balance, desired) in old_balances.iter().zip(desired_amounts.iter()) {
+        new_balances.push_back(old_balance + desired);
+    }
+
+    // Invariant (D) after deposit added
+    let new_invariant = compute_d(
+        env,
+        amp,
+        &scale_balances(&new_balances, &precisions)[..n_coins as usize],
+    );
+
+    let total_shares = utils::get_total_shares(env);
+    if total_shares == 0 {
+        let divisor = 10u128.pow(DECIMAL_PRECISION - get_greatest_precision(env));
+        let share = new_invariant
+            .to_u128()
+            .expect("Pool Stable Multi: provide_liquidity: conversion to u128 failed")
+            / divisor;
+        if share <= MINIMUM_LIQUIDITY_AMOUNT as u128 {
+            log!(
+                env,
+                "Pool Stable Multi: ProvideLiquidity: Liquidity amount is too low"
+            );
+            panic_with_error!(env, ContractError::LowLiquidity);
+        }
+
+        let mut fees = Vec::new(env);
+        for _ in 0..n_coins {
+            fees.push_back(0);
+        }
+
+        return SimulateProvideLiquidityResponse {
+            shares: share as i128 - MINIMUM_LIQUIDITY_AMOUNT,
+            fees,
+        };
+    }
+
+    let initial_invariant = compute_d(
+        env,
+        amp,
+        &scale_balances(&old_balances, &precisions)[..n_coins as usize],
+    );
+
+    let imbalance_fee_rate =
+        config.protocol_fee_rate() * Decimal::from_ratio(n_coins, 4 * (n_coins - 1));
+    let mut fees = Vec::new(env);
+    let mut balances_after_fees = Vec::new(env);
+    for (old_balance, new_balance) in old_balances.iter().zip(new_balances.iter()) {
+        let ideal_balance = U256::from_u128(env, old_balance as u128)
+            .mul(&new_invariant)
+            .div(&initial_invariant)
+            .to_u128()
+            .expect("Pool Stable Multi: provide_liquidity: conversion to u128 failed");
+        let fee = ideal_balance.abs_diff(new_balance as u128) as i128 * imbalance_fee_rate;
+        fees.push_back(fee);
+        balances_after_fees.push_back(new_balance - fee);
+    }
+
+    let invariant_after_fees = compute_d(
+        env,
+        amp,
+        &scale_balances(&balances_after_fees, &precisions)[..n_coins as usize],
+    )
+    .to_u128()
+    .expect("Pool Stable Multi: provide_liquidity: conversion to u128 failed");
+    let initial_invariant = initial_invariant
+        .to_u128()
+        .expect("Pool Stable Multi: provide_liquidity: conversion to u128 failed");
+
+    // Calculate the proportion of the change in invariant
+    let shares = total_shares
+        * (Decimal::new(invariant_after_fees.saturating_sub(initial_invariant) as i128)
+            / Decimal::new(initial_invariant as i128));
+
+    SimulateProvideLiquidityResponse { shares, fees }
+}
+
+fn get_swap_indexes(
+    env: &Env,
+    config: &Config,
+    offer_asset: &Address,
+    ask_asset: &Address,
+) -> (u32, u32) {
+    if offer_asset == ask_asset {
+        log!(
+            env,
+            "Pool Stable Multi: Offer and ask asset of a swap must differ"
+        );
+        panic_with_error!(env, ContractError::IncorrectAssetSwap);
+    }
+
+    match (
+        config.tokens.first_index_of(offer_asset),
+        config.tokens.first_index_of(ask_asset),
+    ) {
+        (Some(offer_index), Some(ask_index)) => (offer_index, ask_index),
+        _ => {
+            log!(env, "Pool Stable Multi: Token to swap not found in Pool");
+            panic_with_error!(env, ContractError::AssetNotInPool);
+        }
+    }
+}
+
+/// Scales every balance to `DECIMAL_PRECISION`. Only the first `balances.len()` entries of the
+/// result are used.
+fn scale_balances(balances: &Vec<i128>, precisions: &Vec<u32>) -> [u128; MAX_COINS] {
+    let mut scaled = [0u128; MAX_COINS];
+    for (index, (balance, precision)) in balances.iter().zip(precisions.iter()).enumerate() {
+        scaled[index] = scale_value(balance as u128, precision, DECIMAL_PRECISION);
+    }
+    scaled
+}
+
+#[allow(clippy::too_many_arguments)]
+fn do_swap(
+    env: Env,
+    sender: Address,
+    referral: Option<Referral>,
+    offer_asset: Address,
+    ask_asset: Address,
+    offer_amount: i128,
+    ask_asset_min_amount: Option<i128>,
+    max_spread: Option<i64>,
+) -> SwapResponse {
+    let config = get_config(&env);
+
+    let (offer_index, ask_index) = get_swap_indexes(&env, &config, &offer_asset, &ask_asset);
+
+    if let Some(referral) = &referral {
+        if !(0..=config.max_referral_bps).contains(&referral.fee_bps) {
+            log!(
+                &env,
+                "Pool Stable Multi: Swap: Trying to swap with more than the allowed referral fee"
+            );
+            panic_with_error!(&env, ContractError::ReferralFeeTooHigh);
+        }
+    }
+
+    if let Some(max_spread) = max_spread {
+        if !(0..=config.max_allowed_spread_bps).contains(&max_spread) {
+            log!(
+                &env,
+                "Pool Stable Multi: do swap: max spread is out of bounds"
+            );
+            panic_with_error!(&env, ContractError::InvalidBps);
+        }
+    }
+
+    let max_spread = Decimal::bps(max_spread.map_or_else(|| config.max_allowed_spread_bps, |x| x));
+
+    let mut pool_balances = utils::get_pool_balances(&env);
+
+    let (return_amount, spread_amount, commission_amount) = compute_swap(
+        &env,
+        compute_current_amp(&env, &get_amp(&env)),
+        &pool_balances,
+        &get_precisions(&env),
+        offer_index,
+        ask_index,
+        offer_amount,
+        config.protocol_fee_rate(),
+    );
+
+    // referral fee is taken from the return amount, the same way as the commission
+    let referral_fee_amount = match referral {
+        Some(ref referral) => return_amount * Decimal::bps(referral.fee_bps),
+        None => 0,
+    };
+    let return_amount = return_amount - referral_fee_amount;
+
+    if let Some(ask_asset_min_amount) = ask_asset_min_amount {
+        if ask_asset_min_amount > return_amount {
+            log!(
+                &env,
+                "Pool Stable Multi: do_swap: Return amount is smaller then expected minimum amount"
+            );
+            panic_with_error!(&env, ContractError::SwapMinReceivedBiggerThanReturn);
+        }
+    }
+
+    assert_max_spread(
+        &env,
+        max_spread,
+        return_amount + commission_amount + referral_fee_amount,
+        spread_amount,
+    );
+
+    // transfer tokens to swap
+    token_contract::Client::new(&env, &offer_asset).transfer(
+        &sender,
+        &env.current_contract_address(),
+        &offer_amount,
+    );
+
+    let ask_token_client = token_contract::Client::new(&env, &ask_asset);
+    // return swapped tokens to user
+    ask_token_client.transfer(&env.current_contract_address(), &sender, &return_amount);
+
+    // send the protocol share of the commission to fee recipient, the rest stays in the pool
+    let protocol_fee_amount = commission_amount * config.protocol_fee_share();
+    ask_token_client.transfer(
+        &env.current_contract_address(),
+        &config.fee_recipient,
+        &protocol_fee_amount,
+    );
+
+    // send referral fee to the referral address
+    if let Some(Referral { address, .. }) = referral {
+        if referral_fee_amount > 0 {
+            ask_token_client.transfer(
+                &env.current_contract_address(),
+                &address,
+                &referral_fee_amount,
+            );
+        }
+    }
+
+    // only the offer and the ask reserve change
+    pool_balances.set(
+        offer_index,
+        pool_balances.get(offer_index).unwrap() + offer_amount,
+    );
+    pool_balances.set(
+        ask_index,
+        pool_balances.get(ask_index).unwrap()
+            - protocol_fee_amount
+            - referral_fee_amount
+            - return_amount,
+    );
+    utils::save_pool_balances(&env, &pool_balances);
+
+    env.events().publish(("swap", "sender"), sender);
+    env.events().publish(("swap", "sell_token"), offer_asset);
+    env.events().publish(("swap", "offer_amount"), offer_amount);
+    env.events().publish(("swap", "buy_token"), ask_asset);
+    env.events()
+        .publish(("swap", "return_amount"), return_amount);
+    env.events()
+        .publish(("swap", "spread_amount"), spread_amount);
+    env.events()
+        .publish(("swap", "referral_fee_amount"), referral_fee_amount);
+    env.events()
+        .publish(("swap", "protocol_fee_amount"), protocol_fee_amount);
+    env.events().publish(
+        ("swap", "lp_fee_amount"),
+        commission_amount - protocol_fee_amount,
+    );
+
+    SwapResponse {
+        ask_amount: return_amount,
+        commission_amount,
+        protocol_fee_amount,
+        referral_fee_amount,
+        spread_amount,
+    }
+}
+
+/// This function asserts that the spread (slippage) does not exceed a given maximum.
+/// * `max_spread` - The maximum allowed spread (slippage) as a fraction of the return amount.
+/// * `return_amount` - The amount of tokens that the user receives in return.
+/// * `spread_amount` - The spread (slippage) amount, i.e., the difference between the expected and actual return.
+/// # Returns
+/// * An error if the spread exceeds the maximum allowed, otherwise Ok.
+pub fn assert_max_spread(env: &Env, max_spread: Decimal, return_amount: i128, spread_amount: i128) {
+    // Calculate the spread ratio, the fraction of the return that is due to spread
+    let spread_ratio = Decimal::from_ratio(spread_amount, return_amount);
+
+    if spread_ratio > max_spread {
+        log!(env, "Pool Stable Multi: Spread exceeds maximum allowed");
+        panic_with_error!(env, ContractError::SpreadExceedsLimit);
+    }
+}
+
+/// Computes the result of swapping `offer_amount` of the coin at `offer_index` for the coin at
+/// `ask_index`.
+///
+/// Returns a tuple containing the following values:
+/// - The resulting amount of ask assets after the swap minus the commission amount.
+/// - The spread amount, representing the difference between the expected and actual swap amounts.
+/// - The commission amount, representing the fees charged for the swap.
+#[allow(clippy::too_many_arguments)]
+pub fn compute_swap(
+    env: &Env,
+    amp: u64,
+    pool_balances: &Vec<i128>,
+    precisions: &Vec<u32>,
+    offer_index: u32,
+    ask_index: u32,
+    offer_amount: i128,
+    commission_rate: Decimal,
+) -> (i128, i128, i128) {
+    let offer_pool = pool_balances.get(offer_index).unwrap();
+    let ask_pool = pool_balances.get(ask_index).unwrap();
+    let offer_precision = precisions.get(offer_index).unwrap();
+    let ask_precision = precisions.get(ask_index).unwrap();
+
+    let new_ask_pool = calc_y(
+        env,
+        amp as u128,
+        &scale_balances(pool_balances, precisions)[..pool_balances.len() as usize],
+        offer_index as usize,
+        scale_value(
+            (offer_pool + offer_amount) as u128,
+            offer_precision,
+            DECIMAL_PRECISION,
+        ),
+        ask_index as usize,
+        ask_precision,
+    ) as i128;
+
+    let return_amount = ask_pool - new_ask_pool;
+    // We consider swap rate 1:1 in stable swap thus any difference is considered as spread.
+    let offer_amount = scale_value(offer_amount as u128, offer_precision, ask_precision) as i128;
+    let spread_amount = (offer_amount - return_amount).max(0);
+    let commission_amount = return_amount * commission_rate;
+    let return_amount = return_amount - commission_amount;
+
+    (return_amount, spread_amount, commission_amount)
+}
+
+/// Returns an amount of the coin at `offer_index` needed for `ask_amount` of the coin at
+/// `ask_index`, together with the spread and the commission of the swap.
+#[allow(clippy::too_many_arguments)]
+pub fn compute_offer_amount(
+    env: &Env,
+    amp: u64,
+    pool_balances: &Vec<i128>,
+    precisions: &Vec<u32>,
+    offer_index: u32,
+    ask_index: u32,
+    ask_amount: i128,
+    commission_rate: Decimal,
+) -> (i128, i128, i128) {
+    let offer_pool = pool_balances.get(offer_index).unwrap();
+    let ask_pool = pool_balances.get(ask_index).unwrap();
+    let offer_precision = precisions.get(offer_index).unwrap();
+    let ask_precision = precisions.get(ask_index).unwrap();
+
+    let one_minus_commission = Decimal::one() - commission_rate;
+    let inv_one_minus_commission = Decimal::one() / one_minus_commission;
+    let ask_before_commission = ask_amount * inv_one_minus_commission;
+
+    let new_offer_pool = calc_y(
+        env,
+        amp as u128,
+        &scale_balances(pool_balances, precisions)[..pool_balances.len() as usize],
+        ask_index as usize,
+        scale_value(
+            (ask_pool - ask_before_commission) as u128,
+            ask_precision,
+            DECIMAL_PRECISION,
+        ),
+        offer_index as usize,
+        offer_precision,
+    ) as i128;
+
+    let offer_amount = new_offer_pool - offer_pool;
+
+    // We consider swap rate 1:1 in stable swap thus any difference is considered as spread.
+    let spread_amount = (scale_value(offer_amount as u128, offer_precision, ask_precision) as i128
+        - ask_amount)
+        .max(0);
+    let commission_amount = ask_before_commission * commission_rate;
+
+    (offer_amount, spread_amount, commission_amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use soroban_sdk::vec;
+
+    #[test]
+    fn test_assert_max_spread_success() {
+        let env = Env::default();
+        // max spread of 10%, return amount of 100k and 1 unit, spread amount of 1
+        assert_max_spread(&env, Decimal::percent(10), 100_001, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Spread exceeds maximum allowed")]
+    fn test_assert_max_spread_fail_max_spread_exceeded() {
+        let env = Env::default();
+        // the spread ratio is 35%, more than the allowed 10%
+        assert_max_spread(&env, Decimal::percent(10), 100, 35);
+    }
+
+    #[test]
+    fn compute_swap_of_a_balanced_pool_is_close_to_one_to_one() {
+        let env = Env::default();
+        let balances = vec![&env, 1_000_000_000i128, 1_000_000_000, 1_000_000_000];
+        let precisions = vec![&env, 7u32, 7, 7];
+
+        let (return_amount, spread_amount, commission_amount) = compute_swap(
+            &env,
+            100 * AMP_PRECISION,
+            &balances,
+            &precisions,
+            0,
+            2,
+            1_000_000,
+            Decimal::bps(100),
+        );
+
+        assert_eq!(return_amount + commission_amount + spread_amount, 1_000_000);
+        assert!(spread_amount < 100);
+        assert_eq!(commission_amount, (return_amount + commission_amount) / 100);
+    }
+
+    #[test]
+    fn compute_swap_scales_coins_with_different_decimals() {
+        let env = Env::default();
+        // 100 units of every coin, with 6, 7 and 18 decimals
+        let balances = vec![
+            &env,
+            100_000_000i128,
+            1_000_000_000,
+            100_000_000_000_000_000_000,
+        ];
+        let precisions = vec![&env, 6u32, 7, 18];
+
+        let (return_amount, spread_amount, _) = compute_swap(
+            &env,
+            100 * AMP_PRECISION,
+            &balances,
+            &precisions,
+            0,
+            2,
+            1_000_000,
+            Decimal::zero(),
+        );
+
+        // one unit of the first coin is worth about one unit of the last one
+        assert_eq!(return_amount + spread_amount, 1_000_000_000_000_000_000);
+        assert!(spread_amount < 1_000_000_000_000_000);
+    }
+
+    #[test]
+    fn compute_offer_amount_is_the_inverse_of_compute_swap() {
+        let env = Env::default();
+        let balances = vec![&env, 1_000_000_000i128, 2_000_000_000, 1_500_000_000];
+        let precisions = vec![&env, 7u32, 7, 7];
+
+        let (offer_amount, _, _) = compute_offer_amount(
+            &env,
+            50 * AMP_PRECISION,
+            &balances,
+            &precisions,
+            1,
+            2,
+            10_000_000,
+            Decimal::bps(30),
+        );
+        let (return_amount, _, _) = compute_swap(
+            &env,
+            50 * AMP_PRECISION,
+            &balances,
+            &precisions,
+            1,
+            2,
+            offer_amount,
+            Decimal::bps(30),
+        );
+
+        assert!((return_amount - 10_000_000).abs() <= 1);
+    }
+}
@@ -0,0 +1,32 @@
+use soroban_sdk::contracterror;
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum ContractError {
+    SpreadExceedsLimit = 1,
+    ProvideLiquiditySlippageToleranceTooHigh = 2,
+    WithdrawLiquidityMinimumAmountIsNotSatisfied = 3,
+    TotalSharesEqualZero = 4,
+    AssetNotInPool = 5,
+    AlreadyInitialized = 6,
+    TokensNotSorted = 7,
+    InvalidNumberOfTokens = 8,
+    InvalidBps = 9,
+    LowLiquidity = 10,
+    Unauthorized = 11,
+    IncorrectAssetSwap = 12,
+    NewtonMethodFailed = 13,
+    CalcYErr = 14,
+    SwapMinReceivedBiggerThanReturn = 15,
+    ProvideLiquidityAllTokensMustBeMoreThanZero = 16,
+    InvalidAMP = 17,
+    TransactionAfterTimestampDeadline = 18,
+    AmpRampTooEarly = 19,
+    AmpRampTimeTooShort = 20,
+    AmpChangeTooBig = 21,
+    ReferralFeeTooHigh = 22,
+    ContractPaused = 23,
+    InvalidNumberOfAmounts = 24,
+    ProvideLiquiditySharesBelowMinimum = 25,
+}
@@ -0,0 +1,356 @@
+use soroban_sdk::{
+    contracttype, symbol_short, xdr::ToXdr, Address, Bytes, BytesN, ConversionError, Env, Symbol,
+    TryFromVal, Val, Vec,
+};
+
+use crate::token_contract;
+use soroban_decimal::Decimal;
+
+#[derive(Clone, Copy)]
+#[repr(u32)]
+pub enum DataKey {
+    TotalShares = 0,
+    Reserves = 1,
+    Admin = 2,
+    Initialized = 3,
+    Amp = 4,
+    MaxPrecision = 5,
+    Precisions = 6,
+    Paused = 7,
+    Guardian = 8,
+    Factory = 9,
+}
+
+impl TryFromVal<Env, DataKey> for Val {
+    type Error = ConversionError;
+
+    fn try_from_val(_env: &Env, v: &DataKey) -> Result<Self, Self::Error> {
+        Ok((*v as u32).into())
+    }
+}
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum PairType {
+    Xyk = 0,
+    Stable = 1,
+    Concentrated = 2,
+    Weighted = 3,
+    StableMulti = 4,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Config {
+    /// The coins of the pool, sorted ascending
+    pub tokens: Vec<Address>,
+    pub share_token: Address,
+    pub stake_contract: Address,
+    pub pool_type: PairType,
+    /// The total fees (in bps) charged by a pool of this type.
+    /// In relation to the returned amount of tokens
+    pub total_fee_bps: i64,
+    pub fee_recipient: Address,
+    /// The maximum amount of slippage (in bps) that is tolerated during providing liquidity
+    pub max_allowed_slippage_bps: i64,
+    /// The maximum amount of spread (in bps) that is tolerated during swap
+    pub max_allowed_spread_bps: i64,
+    /// The maximum allowed percentage (in bps) for referral fee
+    pub max_referral_bps: i64,
+    /// The share (in bps) of the swap commission that is sent to the fee recipient.
+    /// The rest of the commission stays in the pool and accrues to the liquidity providers
+    pub protocol_fee_share_bps: i64,
+}
+const CONFIG: Symbol = symbol_short!("CONFIG");
+
+impl Config {
+    pub fn protocol_fee_rate(&self) -> Decimal {
+        Decimal::bps(self.total_fee_bps)
+    }
+
+    pub fn protocol_fee_share(&self) -> Decimal {
+        Decimal::bps(self.protocol_fee_share_bps)
+    }
+}
+
+pub fn get_config(env: &Env) -> Config {
+    env.storage().instance().get(&CONFIG).unwrap()
+}
+
+pub fn save_config(env: &Env, config: Config) {
+    env.storage().instance().set(&CONFIG, &config);
+}
+
+pub fn get_greatest_precision(env: &Env) -> u32 {
+    env.storage()
+        .instance()
+        .get(&DataKey::MaxPrecision)
+        .unwrap()
+}
+
+/// The decimals of every coin of the pool, in the order of `Config::tokens`
+pub fn get_precisions(env: &Env) -> Vec<u32> {
+    env.storage().instance().get(&DataKey::Precisions).unwrap()
+}
+
+pub fn save_precisions(env: &Env, tokens: &Vec<Address>) {
+    let mut precisions = Vec::new(env);
+    for token in tokens.iter() {
+        precisions.push_back(token_contract::Client::new(env, &token).decimals());
+    }
+    let max_precision = precisions.iter().max().unwrap_or(0);
+
+    env.storage()
+        .instance()
+        .set(&DataKey::MaxPrecision, &max_precision);
+    env.storage()
+        .instance()
+        .set(&DataKey::Precisions, &precisions);
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AmplifierParameters {
+    pub init_amp: u64,
+    pub init_amp_time: u64,
+    pub next_amp: u64,
+    pub next_amp_time: u64,
+}
+
+pub fn get_amp(env: &Env) -> AmplifierParameters {
+    env.storage().instance().get(&DataKey::Amp).unwrap()
+}
+
+pub fn save_amp(env: &Env, amp: AmplifierParameters) {
+    env.storage().instance().set(&DataKey::Amp, &amp);
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Asset {
+    /// Address of the asset
+    pub address: Address,
+    /// The total amount of those tokens in the pool
+    pub amount: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Referral {
+    /// Address of the referral
+    pub address: Address,
+    /// fee in bps, later parsed to percentage
+    pub fee_bps: i64,
+}
+
+/// This struct is used to return a query result with the total amount of LP tokens and assets in a specific pool.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StableMultiPoolResponse {
+    /// Every asset of the pool together with asset amounts
+    pub assets: Vec<Asset>,
+    /// The total amount of LP tokens currently issued
+    pub asset_lp_share: Asset,
+    /// The address of the Stake contract for the liquidity pool
+    pub stake_address: Address,
+}
+
+/// The two asset response the factory understands, see `query_pool_info_for_factory`
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PoolResponse {
+    /// The asset A in the pool together with asset amounts
+    pub asset_a: Asset,
+    /// The asset B in the pool together with asset amounts
+    pub asset_b: Asset,
+    /// The total amount of LP tokens currently issued
+    pub asset_lp_share: Asset,
+    /// The address of the Stake contract for the liquidity pool
+    pub stake_address: Address,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StableLiquidityPoolInfo {
+    pub pool_address: Address,
+    pub pool_response: PoolResponse,
+    pub total_fee_bps: i64,
+}
+
+/// The amounts of an executed swap, as returned by `swap`.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SwapResponse {
+    /// The amount sent to the sender, after commission and referral fee
+    pub ask_amount: i128,
+    pub commission_amount: i128,
+    /// The part of the commission sent to the fee recipient, the rest stays in the pool
+    pub protocol_fee_amount: i128,
+    pub referral_fee_amount: i128,
+    pub spread_amount: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SimulateSwapResponse {
+    pub ask_amount: i128,
+    pub commission_amount: i128,
+    /// The part of the commission sent to the fee recipient, the rest stays in the pool
+    pub protocol_fee_amount: i128,
+    pub spread_amount: i128,
+    pub total_return: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SimulateProvideLiquidityResponse {
+    /// The pool shares minted to the depositor
+    pub shares: i128,
+    /// The imbalance fee charged on every coin, in the order of the pool tokens. The protocol
+    /// part of it is sent to the fee recipient
+    pub fees: Vec<i128>,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SimulateReverseSwapResponse {
+    pub offer_amount: i128,
+    pub commission_amount: i128,
+    pub spread_amount: i128,
+}
+
+pub mod utils {
+    use super::*;
+
+    pub fn deploy_token_contract(
+        e: &Env,
+        token_wasm_hash: BytesN<32>,
+        tokens: &Vec<Address>,
+    ) -> Address {
+        let mut salt = Bytes::new(e);
+        for token in tokens.iter() {
+            salt.append(&token.to_xdr(e));
+        }
+        let salt = e.crypto().sha256(&salt);
+        e.deployer()
+            .with_current_contract(salt)
+            .deploy(token_wasm_hash)
+    }
+
+    pub fn deploy_stake_contract(e: &Env, stake_wasm_hash: BytesN<32>) -> Address {
+        let salt = Bytes::new(e);
+        let salt = e.crypto().sha256(&salt);
+
+        e.deployer()
+            .with_current_contract(salt)
+            .deploy(stake_wasm_hash)
+    }
+
+    pub fn save_admin(e: &Env, address: Address) {
+        e.storage().instance().set(&DataKey::Admin, &address)
+    }
+
+    pub fn save_factory(e: &Env, address: Address) {
+        e.storage().instance().set(&DataKey::Factory, &address)
+    }
+
+    pub fn save_guardian(e: &Env, address: Address) {
+        e.storage().instance().set(&DataKey::Guardian, &address)
+    }
+
+    pub fn set_paused(e: &Env, paused: bool) {
+        e.storage().instance().set(&DataKey::Paused, &paused)
+    }
+
+    pub fn save_total_shares(e: &Env, amount: i128) {
+        e.storage().instance().set(&DataKey::TotalShares, &amount)
+    }
+
+    /// The reserves of every coin of the pool, in the order of `Config::tokens`
+    pub fn save_pool_balances(e: &Env, amounts: &Vec<i128>) {
+        e.storage().instance().set(&DataKey::Reserves, amounts)
+    }
+
+    pub fn mint_shares(e: &Env, share_token: &Address, to: &Address, amount: i128) {
+        let total = get_total_shares(e);
+
+        token_contract::Client::new(e, share_token).mint(to, &amount);
+
+        save_total_shares(e, total + amount);
+    }
+
+    pub fn burn_shares(e: &Env, share_token: &Address, amount: i128) {
+        let total = get_total_shares(e);
+
+        token_contract::Client::new(e, share_token).burn(&e.current_contract_address(), &amount);
+
+        save_total_shares(e, total - amount);
+    }
+
+    // queries
+    pub fn get_admin(e: &Env) -> Address {
+        e.storage().instance().get(&DataKey::Admin).unwrap()
+    }
+
+    /// `None` for pools upgraded from a version that didn't store the factory
+    pub fn get_factory(e: &Env) -> Option<Address> {
+        e.storage().instance().get(&DataKey::Factory)
+    }
+
+    pub fn get_guardian(e: &Env) -> Option<Address> {
+        e.storage().instance().get(&DataKey::Guardian)
+    }
+
+    pub fn is_paused(e: &Env) -> bool {
+        e.storage()
+            .instance()
+            .get(&DataKey::Paused)
+            .unwrap_or(false)
+    }
+
+    pub fn get_total_shares(e: &Env) -> i128 {
+        e.storage().instance().get(&DataKey::TotalShares).unwrap()
+    }
+
+    pub fn get_pool_balances(e: &Env) -> Vec<i128> {
+        e.storage().instance().get(&DataKey::Reserves).unwrap()
+    }
+
+    pub fn is_initialized(e: &Env) -> bool {
+        e.storage()
+            .persistent()
+            .get(&DataKey::Initialized)
+            .unwrap_or(false)
+    }
+
+    pub fn set_initialized(e: &Env) {
+        e.storage().persistent().set(&DataKey::Initialized, &true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[should_panic]
+    fn test_get_admin_failure() {
+        let env = Env::default();
+        let _ = utils::get_admin(&env);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_get_total_shares_failure() {
+        let env = Env::default();
+        let _ = utils::get_total_shares(&env);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_get_pool_balances_failure() {
+        let env = Env::default();
+        let _ = utils::get_pool_balances(&env);
+    }
+}
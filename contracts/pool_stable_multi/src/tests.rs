@@ -0,0 +1,4 @@
+mod config;
+mod liquidity;
+mod setup;
+mod swap;
@@ -0,0 +1,197 @@
+extern crate std;
+
+use soroban_sdk::{testutils::Address as _, vec, Address, Env};
+
+use super::setup::{deploy_sorted_token_contracts, deploy_stable_multi_liquidity_pool_contract};
+use crate::storage::{Config, PairType};
+
+#[test]
+fn initialize_with_three_tokens() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let fee_recipient = Address::generate(&env);
+    let tokens = deploy_sorted_token_contracts(&env, &admin, 3);
+
+    let pool = deploy_stable_multi_liquidity_pool_contract(
+        &env,
+        admin,
+        &[&tokens[0].address, &tokens[1].address, &tokens[2].address],
+        30,
+        fee_recipient.clone(),
+        Address::generate(&env),
+        None,
+    );
+
+    assert_eq!(
+        pool.query_config(),
+        Config {
+            tokens: vec![
+                &env,
+                tokens[0].address.clone(),
+                tokens[1].address.clone(),
+                tokens[2].address.clone()
+            ],
+            share_token: pool.query_share_token_address(),
+            stake_contract: pool.query_stake_contract_address(),
+            pool_type: PairType::StableMulti,
+            total_fee_bps: 30,
+            fee_recipient,
+            max_allowed_slippage_bps: 5_000,
+            max_allowed_spread_bps: 1_000,
+            max_referral_bps: 5_000,
+            protocol_fee_share_bps: 10_000,
+        }
+    );
+    assert_eq!(pool.query_current_amp(), 6);
+    assert_eq!(pool.query_pool_info().assets.len(), 3);
+    assert_eq!(pool.query_total_issued_lp(), 0);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #8)")]
+fn initialize_with_two_tokens_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let tokens = deploy_sorted_token_contracts(&env, &admin, 2);
+
+    deploy_stable_multi_liquidity_pool_contract(
+        &env,
+        admin,
+        &[&tokens[0].address, &tokens[1].address],
+        0,
+        None,
+        Address::generate(&env),
+        None,
+    );
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #7)")]
+fn initialize_with_unsorted_tokens_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let tokens = deploy_sorted_token_contracts(&env, &admin, 3);
+
+    deploy_stable_multi_liquidity_pool_contract(
+        &env,
+        admin,
+        &[&tokens[0].address, &tokens[2].address, &tokens[1].address],
+        0,
+        None,
+        Address::generate(&env),
+        None,
+    );
+}
+
+#[test]
+#[should_panic(expected = "Pool Stable Multi: UpdateConfig: Unauthorized")]
+fn update_config_unauthorized() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let tokens = deploy_sorted_token_contracts(&env, &admin, 3);
+
+    let pool = deploy_stable_multi_liquidity_pool_contract(
+        &env,
+        admin,
+        &[&tokens[0].address, &tokens[1].address, &tokens[2].address],
+        0,
+        None,
+        Address::generate(&env),
+        None,
+    );
+
+    pool.update_config(
+        &Address::generate(&env),
+        &None,
+        &Some(500i64),
+        &None,
+        &None,
+        &None,
+        &None,
+    );
+}
+
+#[test]
+fn update_config() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let new_fee_recipient = Address::generate(&env);
+    let tokens = deploy_sorted_token_contracts(&env, &admin, 3);
+
+    let pool = deploy_stable_multi_liquidity_pool_contract(
+        &env,
+        admin.clone(),
+        &[&tokens[0].address, &tokens[1].address, &tokens[2].address],
+        0,
+        None,
+        Address::generate(&env),
+        None,
+    );
+
+    pool.update_config(
+        &admin,
+        &None,
+        &Some(500i64),
+        &Some(new_fee_recipient.clone()),
+        &None,
+        &Some(2_000i64),
+        &Some(5_000i64),
+    );
+
+    let config = pool.query_config();
+    assert_eq!(config.total_fee_bps, 500);
+    assert_eq!(config.fee_recipient, new_fee_recipient);
+    assert_eq!(config.max_allowed_spread_bps, 2_000);
+    assert_eq!(config.protocol_fee_share_bps, 5_000);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #23)")]
+fn provide_liquidity_when_paused_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let tokens = deploy_sorted_token_contracts(&env, &admin, 3);
+
+    let pool = deploy_stable_multi_liquidity_pool_contract(
+        &env,
+        admin.clone(),
+        &[&tokens[0].address, &tokens[1].address, &tokens[2].address],
+        0,
+        None,
+        Address::generate(&env),
+        None,
+    );
+    for token in &tokens {
+        token.mint(&user, &1_000_000);
+    }
+
+    pool.pause(&admin);
+    assert!(pool.query_paused());
+
+    pool.provide_liquidity(
+        &user,
+        &vec![&env, 1_000_000i128, 1_000_000, 1_000_000],
+        &None,
+        &None,
+        &None::<u64>,
+    );
+}
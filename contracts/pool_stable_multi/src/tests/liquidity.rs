@@ -0,0 +1,363 @@
+extern crate std;
+
+use soroban_sdk::{testutils::Address as _, vec, Address, Env};
+
+use super::setup::{deploy_sorted_token_contracts, deploy_stable_multi_liquidity_pool_contract};
+use crate::{
+    storage::{Asset, SimulateProvideLiquidityResponse},
+    token_contract,
+};
+
+#[test]
+fn provide_and_withdraw_liquidity() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let tokens = deploy_sorted_token_contracts(&env, &admin, 3);
+
+    let pool = deploy_stable_multi_liquidity_pool_contract(
+        &env,
+        None,
+        &[&tokens[0].address, &tokens[1].address, &tokens[2].address],
+        0,
+        None,
+        Address::generate(&env),
+        None,
+    );
+    let share_token = token_contract::Client::new(&env, &pool.query_share_token_address());
+
+    for token in &tokens {
+        token.mint(&user, &1_000_000);
+    }
+
+    pool.provide_liquidity(
+        &user,
+        &vec![&env, 1_000_000i128, 1_000_000, 1_000_000],
+        &None,
+        &None,
+        &None::<u64>,
+    );
+
+    // a balanced pool has an invariant equal to the sum of its coins, which the newton method
+    // rounds down by one, minus the locked shares
+    assert_eq!(share_token.balance(&user), 2_998_999);
+    assert_eq!(share_token.balance(&pool.address), 1_000);
+    assert_eq!(pool.query_total_issued_lp(), 2_999_999);
+    for token in &tokens {
+        assert_eq!(token.balance(&user), 0);
+        assert_eq!(token.balance(&pool.address), 1_000_000);
+    }
+
+    let withdrawn = pool.withdraw_liquidity(
+        &user,
+        &1_500_000,
+        &vec![&env, 500_000i128, 500_000, 500_000],
+        &None::<u64>,
+    );
+
+    assert_eq!(withdrawn, vec![&env, 500_000i128, 500_000, 500_000]);
+    assert_eq!(share_token.balance(&user), 1_498_999);
+    assert_eq!(pool.query_total_issued_lp(), 1_499_999);
+    for token in &tokens {
+        assert_eq!(token.balance(&user), 500_000);
+    }
+    assert_eq!(
+        pool.query_pool_info().assets,
+        vec![
+            &env,
+            Asset {
+                address: tokens[0].address.clone(),
+                amount: 500_000
+            },
+            Asset {
+                address: tokens[1].address.clone(),
+                amount: 500_000
+            },
+            Asset {
+                address: tokens[2].address.clone(),
+                amount: 500_000
+            },
+        ]
+    );
+}
+
+#[test]
+fn second_deposit_gets_shares_in_proportion_to_the_invariant() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let user1 = Address::generate(&env);
+    let user2 = Address::generate(&env);
+    let tokens = deploy_sorted_token_contracts(&env, &admin, 4);
+
+    let pool = deploy_stable_multi_liquidity_pool_contract(
+        &env,
+        None,
+        &[
+            &tokens[0].address,
+            &tokens[1].address,
+            &tokens[2].address,
+            &tokens[3].address,
+        ],
+        0,
+        None,
+        Address::generate(&env),
+        100,
+    );
+    let share_token = token_contract::Client::new(&env, &pool.query_share_token_address());
+
+    for token in &tokens {
+        token.mint(&user1, &1_000_000);
+        token.mint(&user2, &500_000);
+    }
+
+    pool.provide_liquidity(
+        &user1,
+        &vec![&env, 1_000_000i128, 1_000_000, 1_000_000, 1_000_000],
+        &None,
+        &None,
+        &None::<u64>,
+    );
+    pool.provide_liquidity(
+        &user2,
+        &vec![&env, 500_000i128, 500_000, 500_000, 500_000],
+        &None,
+        &None,
+        &None::<u64>,
+    );
+
+    // half of the coins of the pool get half of the shares
+    assert_eq!(share_token.balance(&user1), 3_998_999);
+    assert_eq!(share_token.balance(&user2), 1_999_999);
+
+    let shares = pool.query_share(&share_token.balance(&user2));
+    for share in shares.iter() {
+        assert!((share.amount - 500_000).abs() <= 1);
+    }
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #24)")]
+fn provide_liquidity_with_missing_amount_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let tokens = deploy_sorted_token_contracts(&env, &admin, 3);
+
+    let pool = deploy_stable_multi_liquidity_pool_contract(
+        &env,
+        None,
+        &[&tokens[0].address, &tokens[1].address, &tokens[2].address],
+        0,
+        None,
+        Address::generate(&env),
+        None,
+    );
+
+    pool.provide_liquidity(
+        &user,
+        &vec![&env, 1_000_000i128, 1_000_000],
+        &None,
+        &None,
+        &None::<u64>,
+    );
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #16)")]
+fn provide_liquidity_with_zero_amount_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let tokens = deploy_sorted_token_contracts(&env, &admin, 3);
+
+    let pool = deploy_stable_multi_liquidity_pool_contract(
+        &env,
+        None,
+        &[&tokens[0].address, &tokens[1].address, &tokens[2].address],
+        0,
+        None,
+        Address::generate(&env),
+        None,
+    );
+
+    pool.provide_liquidity(
+        &user,
+        &vec![&env, 1_000_000i128, 0, 1_000_000],
+        &None,
+        &None,
+        &None::<u64>,
+    );
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #3)")]
+fn withdraw_liquidity_below_minimum_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let tokens = deploy_sorted_token_contracts(&env, &admin, 3);
+
+    let pool = deploy_stable_multi_liquidity_pool_contract(
+        &env,
+        None,
+        &[&tokens[0].address, &tokens[1].address, &tokens[2].address],
+        0,
+        None,
+        Address::generate(&env),
+        None,
+    );
+    for token in &tokens {
+        token.mint(&user, &1_000_000);
+    }
+    pool.provide_liquidity(
+        &user,
+        &vec![&env, 1_000_000i128, 1_000_000, 1_000_000],
+        &None,
+        &None,
+        &None::<u64>,
+    );
+
+    pool.withdraw_liquidity(
+        &user,
+        &1_500_000,
+        &vec![&env, 500_000i128, 500_001, 500_000],
+        &None::<u64>,
+    );
+}
+
+#[test]
+fn provide_liquidity_charges_imbalance_fee() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let user1 = Address::generate(&env);
+    let user2 = Address::generate(&env);
+    let fee_recipient = Address::generate(&env);
+    let tokens = deploy_sorted_token_contracts(&env, &admin, 3);
+
+    let swap_fees = 1_000i64; // 10% bps
+    let pool = deploy_stable_multi_liquidity_pool_contract(
+        &env,
+        None,
+        &[&tokens[0].address, &tokens[1].address, &tokens[2].address],
+        swap_fees,
+        fee_recipient.clone(),
+        Address::generate(&env),
+        None,
+    );
+    let share_token = token_contract::Client::new(&env, &pool.query_share_token_address());
+
+    for token in &tokens {
+        token.mint(&user1, &1_000_000);
+    }
+    pool.provide_liquidity(
+        &user1,
+        &vec![&env, 1_000_000i128, 1_000_000, 1_000_000],
+        &None,
+        &None,
+        &None::<u64>,
+    );
+    let total_shares = pool.query_total_issued_lp();
+
+    // a deposit in the ratio of the pool is not charged
+    let balanced =
+        pool.simulate_provide_liquidity(&vec![&env, 100_000i128, 100_000, 100_000], &None);
+    assert_eq!(balanced.fees, vec![&env, 0i128, 0, 0]);
+    assert_eq!(balanced.shares, total_shares / 10);
+
+    let simulated = pool.simulate_provide_liquidity(&vec![&env, 300_000i128, 1, 1], &None);
+    assert_eq!(
+        simulated,
+        SimulateProvideLiquidityResponse {
+            shares: 285_003,
+            fees: vec![&env, 7_500i128, 3_749, 3_749],
+        }
+    );
+
+    tokens[0].mint(&user2, &300_000);
+    tokens[1].mint(&user2, &1);
+    tokens[2].mint(&user2, &1);
+    pool.provide_liquidity(
+        &user2,
+        &vec![&env, 300_000i128, 1, 1],
+        &None,
+        &Some(simulated.shares),
+        &None::<u64>,
+    );
+    assert_eq!(share_token.balance(&user2), simulated.shares);
+    for (token, fee) in tokens.iter().zip(simulated.fees.iter()) {
+        assert_eq!(token.balance(&fee_recipient), fee);
+    }
+
+    // depositing one coin and withdrawing proportionally costs at least the imbalance fee
+    let withdrawn = pool.withdraw_liquidity(
+        &user2,
+        &simulated.shares,
+        &vec![&env, 1i128, 1, 1],
+        &None::<u64>,
+    );
+    let fees: i128 = simulated.fees.iter().sum();
+    assert!(withdrawn.iter().sum::<i128>() < 300_002 - fees);
+
+    for (asset, token) in pool.query_pool_info().assets.iter().zip(tokens.iter()) {
+        assert_eq!(asset.amount, token.balance(&pool.address));
+    }
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #25)")]
+fn provide_liquidity_below_min_shares_out() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let tokens = deploy_sorted_token_contracts(&env, &admin, 3);
+
+    let pool = deploy_stable_multi_liquidity_pool_contract(
+        &env,
+        None,
+        &[&tokens[0].address, &tokens[1].address, &tokens[2].address],
+        1_000,
+        None,
+        Address::generate(&env),
+        None,
+    );
+    for token in &tokens {
+        token.mint(&user, &1_300_000);
+    }
+    pool.provide_liquidity(
+        &user,
+        &vec![&env, 1_000_000i128, 1_000_000, 1_000_000],
+        &None,
+        &None,
+        &None::<u64>,
+    );
+
+    let simulated = pool.simulate_provide_liquidity(&vec![&env, 300_000i128, 1, 1], &None);
+    pool.provide_liquidity(
+        &user,
+        &vec![&env, 300_000i128, 1, 1],
+        &None,
+        &Some(simulated.shares + 1),
+        &None::<u64>,
+    );
+}
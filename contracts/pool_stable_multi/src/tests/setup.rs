@@ -0,0 +1,105 @@
+extern crate std;
+
+use soroban_sdk::{testutils::Address as _, vec, Address, BytesN, Env, String, Vec};
+
+use crate::{
+    contract::{StableMultiLiquidityPool, StableMultiLiquidityPoolClient},
+    token_contract,
+};
+
+use phoenix::utils::{
+    LiquidityPoolInitInfo, StableMultiPoolInitInfo, StakeInitInfo, TokenInitInfo,
+};
+
+pub fn deploy_token_contract<'a>(env: &Env, admin: &Address) -> token_contract::Client<'a> {
+    token_contract::Client::new(env, &env.register_stellar_asset_contract(admin.clone()))
+}
+
+/// Deploys `count` tokens, sorted by their address the way the pool expects them
+pub fn deploy_sorted_token_contracts<'a>(
+    env: &Env,
+    admin: &Address,
+    count: usize,
+) -> std::vec::Vec<token_contract::Client<'a>> {
+    let mut tokens: std::vec::Vec<_> = (0..count)
+        .map(|_| deploy_token_contract(env, admin))
+        .collect();
+    tokens.sort_by(|a, b| a.address.cmp(&b.address));
+    tokens
+}
+
+pub fn install_token_wasm(env: &Env) -> BytesN<32> {
+    soroban_sdk::contractimport!(
+        file = "../../target/wasm32-unknown-unknown/release/soroban_token_contract.wasm"
+    );
+    env.deployer().upload_contract_wasm(WASM)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn install_stake_wasm(env: &Env) -> BytesN<32> {
+    soroban_sdk::contractimport!(
+        file = "../../target/wasm32-unknown-unknown/release/phoenix_stake.wasm"
+    );
+    env.deployer().upload_contract_wasm(WASM)
+}
+
+pub fn deploy_stable_multi_liquidity_pool_contract<'a>(
+    env: &Env,
+    admin: impl Into<Option<Address>>,
+    tokens: &[&Address],
+    swap_fees: i64,
+    fee_recipient: impl Into<Option<Address>>,
+    factory: Address,
+    init_amp: impl Into<Option<u64>>,
+) -> StableMultiLiquidityPoolClient<'a> {
+    let admin = admin.into().unwrap_or(Address::generate(env));
+    let pool = StableMultiLiquidityPoolClient::new(
+        env,
+        &env.register_contract(None, StableMultiLiquidityPool {}),
+    );
+    let fee_recipient = fee_recipient
+        .into()
+        .unwrap_or_else(|| Address::generate(env));
+
+    let token_init_info = TokenInitInfo {
+        token_a: tokens[0].clone(),
+        token_b: tokens[1].clone(),
+    };
+    let mut additional_tokens: Vec<Address> = vec![env];
+    for token in &tokens[2..] {
+        additional_tokens.push_back((*token).clone());
+    }
+    let stake_init_info = StakeInitInfo {
+        min_bond: 10i128,
+        min_reward: 5i128,
+        manager: Address::generate(env),
+        max_complexity: 10u32,
+    };
+
+    let token_wasm_hash = install_token_wasm(env);
+    let stake_wasm_hash = install_stake_wasm(env);
+
+    let lp_init_info = LiquidityPoolInitInfo {
+        admin,
+        swap_fee_bps: swap_fees,
+        fee_recipient,
+        max_allowed_slippage_bps: 5_000,
+        max_allowed_spread_bps: 1_000,
+        max_referral_bps: 5_000,
+        token_init_info,
+        stake_init_info,
+    };
+
+    pool.initialize(
+        &stake_wasm_hash,
+        &token_wasm_hash,
+        &lp_init_info,
+        &factory,
+        &10, // LP share decimals, unused
+        &String::from_str(env, "LP_SHARE_TOKEN"),
+        &String::from_str(env, "PHOSTABLELP"),
+        &init_amp.into().unwrap_or(6u64),
+        &StableMultiPoolInitInfo { additional_tokens },
+    );
+    pool
+}
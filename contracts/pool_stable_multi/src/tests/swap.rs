@@ -0,0 +1,207 @@
+extern crate std;
+
+use soroban_sdk::{testutils::Address as _, vec, Address, Env};
+
+use super::setup::{
+    deploy_sorted_token_contracts, deploy_stable_multi_liquidity_pool_contract,
+    deploy_token_contract,
+};
+
+#[test]
+fn swap_between_any_two_coins() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let fee_recipient = Address::generate(&env);
+    let tokens = deploy_sorted_token_contracts(&env, &admin, 3);
+
+    // 1% swap fee
+    let pool = deploy_stable_multi_liquidity_pool_contract(
+        &env,
+        None,
+        &[&tokens[0].address, &tokens[1].address, &tokens[2].address],
+        100,
+        fee_recipient.clone(),
+        Address::generate(&env),
+        100,
+    );
+
+    for token in &tokens {
+        token.mint(&user, &1_010_000_000);
+    }
+    pool.provide_liquidity(
+        &user,
+        &vec![&env, 1_000_000_000i128, 1_000_000_000, 1_000_000_000],
+        &None,
+        &None,
+        &None::<u64>,
+    );
+
+    let simulated = pool.simulate_swap(&tokens[2].address, &tokens[0].address, &10_000_000);
+    let return_amount = pool
+        .swap(
+            &user,
+            &None,
+            &tokens[2].address,
+            &tokens[0].address,
+            &10_000_000,
+            &None,
+            &None,
+            &None::<u64>,
+        )
+        .ask_amount;
+
+    assert_eq!(return_amount, simulated.ask_amount);
+    // close to 1:1 minus the 1% fee
+    assert!(return_amount > 9_890_000 && return_amount <= 9_900_000);
+    assert_eq!(
+        simulated.ask_amount + simulated.commission_amount + simulated.spread_amount,
+        10_000_000
+    );
+
+    assert_eq!(tokens[2].balance(&user), 0);
+    assert_eq!(tokens[0].balance(&user), 10_000_000 + return_amount);
+    assert_eq!(tokens[1].balance(&user), 10_000_000);
+    assert_eq!(
+        tokens[0].balance(&fee_recipient),
+        simulated.commission_amount
+    );
+
+    let assets = pool.query_pool_info().assets;
+    assert_eq!(
+        assets.get(0).unwrap().amount,
+        1_000_000_000 - return_amount - simulated.commission_amount
+    );
+    assert_eq!(assets.get(1).unwrap().amount, 1_000_000_000);
+    assert_eq!(assets.get(2).unwrap().amount, 1_010_000_000);
+    assert_eq!(
+        tokens[0].balance(&pool.address),
+        assets.get(0).unwrap().amount
+    );
+
+    // the coin that was not traded can still be swapped for the one that got scarce
+    let return_amount = pool
+        .swap(
+            &user,
+            &None,
+            &tokens[1].address,
+            &tokens[0].address,
+            &10_000_000,
+            &None,
+            &None,
+            &None::<u64>,
+        )
+        .ask_amount;
+    assert!(return_amount < 9_900_000);
+}
+
+#[test]
+fn simulate_reverse_swap_matches_swap() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let tokens = deploy_sorted_token_contracts(&env, &admin, 3);
+
+    let pool = deploy_stable_multi_liquidity_pool_contract(
+        &env,
+        None,
+        &[&tokens[0].address, &tokens[1].address, &tokens[2].address],
+        30,
+        None,
+        Address::generate(&env),
+        50,
+    );
+
+    for token in &tokens {
+        token.mint(&user, &2_000_000_000);
+    }
+    pool.provide_liquidity(
+        &user,
+        &vec![&env, 1_000_000_000i128, 2_000_000_000, 1_500_000_000],
+        &None,
+        &None,
+        &None::<u64>,
+    );
+
+    let reverse = pool.simulate_reverse_swap(&tokens[1].address, &tokens[2].address, &5_000_000);
+    let swap = pool.simulate_swap(
+        &tokens[1].address,
+        &tokens[2].address,
+        &reverse.offer_amount,
+    );
+
+    assert!((swap.ask_amount - 5_000_000).abs() <= 1);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #5)")]
+fn swap_with_asset_outside_of_the_pool_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let tokens = deploy_sorted_token_contracts(&env, &admin, 3);
+    let other_token = deploy_token_contract(&env, &admin);
+
+    let pool = deploy_stable_multi_liquidity_pool_contract(
+        &env,
+        None,
+        &[&tokens[0].address, &tokens[1].address, &tokens[2].address],
+        0,
+        None,
+        Address::generate(&env),
+        None,
+    );
+
+    pool.swap(
+        &user,
+        &None,
+        &other_token.address,
+        &tokens[0].address,
+        &1_000,
+        &None,
+        &None,
+        &None::<u64>,
+    );
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #12)")]
+fn swap_for_the_same_asset_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let tokens = deploy_sorted_token_contracts(&env, &admin, 3);
+
+    let pool = deploy_stable_multi_liquidity_pool_contract(
+        &env,
+        None,
+        &[&tokens[0].address, &tokens[1].address, &tokens[2].address],
+        0,
+        None,
+        Address::generate(&env),
+        None,
+    );
+
+    pool.swap(
+        &user,
+        &None,
+        &tokens[1].address,
+        &tokens[1].address,
+        &1_000,
+        &None,
+        &None,
+        &None::<u64>,
+    );
+}
@@ -0,0 +1,1266 @@
+use soroban_sdk::{
+    contract, contractimpl, contractmeta, log, panic_with_error, Address, BytesN, Env, IntoVal,
+    String,
+};
+
+use crate::{
+    error::ContractError,
+    math::{pow_up, weighted_geometric_mean},
+    stake_contract,
+    storage::{
+        get_config, save_config,
+        utils::{self, get_admin, is_initialized, set_initialized},
+        Asset, ComputeSwap, Config, LiquidityPoolInfo, PairType, PoolResponse, Referral,
+        SimulateReverseSwapResponse, SimulateSwapResponse, SwapResponse,
+    },
+    token_contract,
+};
+use phoenix::{
+    utils::{LiquidityPoolInitInfo, WeightedPoolInitInfo},
+    validate_bps, validate_int_parameters,
+};
+use soroban_decimal::Decimal;
+
+// Amount of LP shares locked in the pool on the first deposit
+const MINIMUM_LIQUIDITY_AMOUNT: i128 = 1000;
+
+// Metadata that is added on to the WASM custom section
+contractmeta!(
+    key = "Description",
+    val = "Phoenix Protocol Weighted Liquidity Pool"
+);
+
+#[contract]
+pub struct WeightedLiquidityPool;
+
+#[allow(dead_code)]
+pub trait WeightedLiquidityPoolTrait {
+    // Sets the token contract addresses for this pool
+    // token_wasm_hash is the WASM hash of the deployed token contract for the pool share token
+    // `init_info` holds the weights of the two tokens in the pool value
+    #[allow(clippy::too_many_arguments)]
+    fn initialize(
+        env: Env,
+        stake_wasm_hash: BytesN<32>,
+        token_wasm_hash: BytesN<32>,
+        lp_init_info: LiquidityPoolInitInfo,
+        factory_addr: Address,
+        share_token_decimals: u32,
+        share_token_name: String,
+        share_token_symbol: String,
+        default_slippage_bps: i64,
+        init_info: WeightedPoolInitInfo,
+    );
+
+    // Deposits token_a and token_b in the ratio of the pool reserves and mints pool shares for
+    // the depositor. The first deposit sets the ratio, and so the starting price, of the pool.
+    #[allow(clippy::too_many_arguments)]
+    fn provide_liquidity(
+        env: Env,
+        depositor: Address,
+        desired_a: Option<i128>,
+        min_a: Option<i128>,
+        desired_b: Option<i128>,
+        min_b: Option<i128>,
+        custom_slippage_bps: Option<i64>,
+        deadline: Option<u64>,
+    );
+
+    // `offer_asset` is the asset that the user would like to swap for the other token in the pool.
+    // `offer_amount` is the amount being sold, with `max_spread_bps` being a safety to make sure you receive at least that amount.
+    // swap will transfer the selling token "to" to this contract, and then the contract will transfer the buying token to `sender`.
+    // An optional `referral` receives `fee_bps` of the bought amount, capped by the pool's `max_referral_bps`.
+    // Returns the amount of the token being bought with the commission, fees and spread of the swap.
+    #[allow(clippy::too_many_arguments)]
+    fn swap(
+        env: Env,
+        sender: Address,
+        referral: Option<Referral>,
+        offer_asset: Address,
+        offer_amount: i128,
+        // Minimum amount of the ask token user expects to receive
+        ask_asset_min_amount: Option<i128>,
+        max_spread_bps: Option<i64>,
+        deadline: Option<u64>,
+    ) -> SwapResponse;
+
+    // transfers share_amount of pool share tokens to this contract, burns all pools share tokens in this contracts, and sends the
+    // corresponding amount of token_a and token_b to "to".
+    // Returns amount of both tokens withdrawn
+    fn withdraw_liquidity(
+        env: Env,
+        recipient: Address,
+        share_amount: i128,
+        min_a: i128,
+        min_b: i128,
+        deadline: Option<u64>,
+    ) -> (i128, i128);
+
+    // Allows admin address set during initialization to change some parameters of the
+    // configuration. The weights are fixed at initialization
+    #[allow(clippy::too_many_arguments)]
+    fn update_config(
+        env: Env,
+        new_admin: Option<Address>,
+        total_fee_bps: Option<i64>,
+        fee_recipient: Option<Address>,
+        max_allowed_slippage_bps: Option<i64>,
+        max_allowed_spread_bps: Option<i64>,
+        max_referral_bps: Option<i64>,
+        protocol_fee_share_bps: Option<i64>,
+    );
+
+    // Sets the guardian, an address allowed to pause the pool next to the admin and the factory
+    fn set_guardian(env: Env, guardian: Address);
+
+    // Pauses swaps and deposits. Withdrawals stay available.
+    // Callable by the admin, the guardian or the factory
+    fn pause(env: Env, sender: Address);
+
+    // Resumes the pool. Callable by the admin or the factory
+    fn unpause(env: Env, sender: Address);
+
+    // Migration entrypoint
+    fn upgrade(e: Env, new_wasm_hash: BytesN<32>);
+
+    // QUERIES
+
+    // Returns the configuration structure containing the addresses
+    fn query_config(env: Env) -> Config;
+
+    // Returns the address for the pool share token
+    fn query_share_token_address(env: Env) -> Address;
+
+    // Returns the address for the pool stake contract
+    fn query_stake_contract_address(env: Env) -> Address;
+
+    // Returns  the total amount of LP tokens and assets in a specific pool
+    fn query_pool_info(env: Env) -> PoolResponse;
+
+    fn query_pool_info_for_factory(env: Env) -> LiquidityPoolInfo;
+
+    // Simulate swap transaction
+    fn simulate_swap(env: Env, offer_asset: Address, sell_amount: i128) -> SimulateSwapResponse;
+
+    // Simulate reverse swap transaction
+    fn simulate_reverse_swap(
+        env: Env,
+        ask_asset: Address,
+        ask_amount: i128,
+    ) -> SimulateReverseSwapResponse;
+
+    fn query_share(env: Env, amount: i128) -> (Asset, Asset);
+
+    fn query_total_issued_lp(env: Env) -> i128;
+
+    fn query_paused(env: Env) -> bool;
+
+    fn query_guardian(env: Env) -> Option<Address>;
+}
+
+#[contractimpl]
+impl WeightedLiquidityPoolTrait for WeightedLiquidityPool {
+    #[allow(clippy::too_many_arguments)]
+    fn initialize(
+        env: Env,
+        stake_wasm_hash: BytesN<32>,
+        token_wasm_hash: BytesN<32>,
+        lp_init_info: LiquidityPoolInitInfo,
+        factory_addr: Address,
+        share_token_decimals: u32,
+        share_token_name: String,
+        share_token_symbol: String,
+        default_slippage_bps: i64,
+        init_info: WeightedPoolInitInfo,
+    ) {
+        if is_initialized(&env) {
+            log!(
+                &env,
+                "Pool Weighted: Initialize: initializing contract twice is not allowed"
+            );
+            panic_with_error!(&env, ContractError::AlreadyInitialized);
+        }
+
+        let admin = lp_init_info.admin;
+        let swap_fee_bps = lp_init_info.swap_fee_bps;
+        let fee_recipient = lp_init_info.fee_recipient;
+        let max_allowed_slippage_bps = lp_init_info.max_allowed_slippage_bps;
+        let max_allowed_spread_bps = lp_init_info.max_allowed_spread_bps;
+        let max_referral_bps = lp_init_info.max_referral_bps;
+        let token_init_info = lp_init_info.token_init_info;
+        let stake_init_info = lp_init_info.stake_init_info;
+
+        validate_bps!(
+            swap_fee_bps,
+            max_allowed_slippage_bps,
+            max_allowed_spread_bps,
+            max_referral_bps,
+            default_slippage_bps
+        );
+
+        if init_info.weight_a_bps <= 0
+            || init_info.weight_b_bps <= 0
+            || init_info.weight_a_bps + init_info.weight_b_bps != 10_000
+        {
+            log!(
+                &env,
+                "Pool Weighted: Initialize: Weights must be positive and add up to 10_000 bps"
+            );
+            panic_with_error!(&env, ContractError::InvalidWeights);
+        }
+
+        set_initialized(&env);
+
+        // Token info
+        let token_a = token_init_info.token_a;
+        let token_b = token_init_info.token_b;
+        // Stake info
+        let min_bond = stake_init_info.min_bond;
+        let min_reward = stake_init_info.min_reward;
+        let manager = stake_init_info.manager;
+
+        // Token order validation to make sure only one instance of a pool can exist
+        if token_a >= token_b {
+            log!(
+                &env,
+                "Pool Weighted: Initialize: First token must be alphabetically smaller than second token"
+            );
+            panic_with_error!(&env, ContractError::TokenABiggerThanTokenB);
+        }
+
+        // deploy token contract
+        let share_token_address =
+            utils::deploy_token_contract(&env, token_wasm_hash, &token_a, &token_b);
+        token_contract::Client::new(&env, &share_token_address).initialize(
+            // admin
+            &env.current_contract_address(),
+            // number of decimals on the share token
+            &share_token_decimals,
+            // name
+            &share_token_name.into_val(&env),
+            // symbol
+            &share_token_symbol.into_val(&env),
+        );
+
+        let stake_contract_address = utils::deploy_stake_contract(&env, stake_wasm_hash);
+        stake_contract::Client::new(&env, &stake_contract_address).initialize(
+            &admin,
+            &share_token_address,
+            &min_bond,
+            &min_reward,
+            &manager,
+            &factory_addr,
+            &stake_init_info.max_complexity,
+        );
+
+        let config = Config {
+            token_a: token_a.clone(),
+            token_b: token_b.clone(),
+            share_token: share_token_address,
+            stake_contract: stake_contract_address,
+            pool_type: PairType::Weighted,
+            total_fee_bps: swap_fee_bps,
+            fee_recipient,
+            max_allowed_slippage_bps,
+            max_allowed_spread_bps,
+            max_referral_bps,
+            default_slippage_bps,
+            // the whole commission goes to the fee recipient until the admin sets a share
+            protocol_fee_share_bps: 10_000,
+            weight_a_bps: init_info.weight_a_bps,
+            weight_b_bps: init_info.weight_b_bps,
+        };
+
+        save_config(&env, config);
+        utils::save_admin(&env, admin);
+        utils::save_factory(&env, factory_addr);
+        utils::save_total_shares(&env, 0);
+        utils::save_pool_balance_a(&env, 0);
+        utils::save_pool_balance_b(&env, 0);
+
+        env.events()
+            .publish(("initialize", "Weighted LP token_a"), token_a);
+        env.events()
+            .publish(("initialize", "Weighted LP token_b"), token_b);
+        env.events().publish(
+            ("initialize", "Weighted LP weight_a_bps"),
+            init_info.weight_a_bps,
+        );
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn provide_liquidity(
+        env: Env,
+        sender: Address,
+        desired_a: Option<i128>,
+        min_a: Option<i128>,
+        desired_b: Option<i128>,
+        min_b: Option<i128>,
+        custom_slippage_bps: Option<i64>,
+        deadline: Option<u64>,
+    ) {
+        ensure_not_paused(&env);
+
+        if let Some(deadline) = deadline {
+            if env.ledger().timestamp() > deadline {
+                log!(
+                    env,
+                    "Pool Weighted: Provide Liquidity: Transaction executed after deadline!"
+                );
+                panic_with_error!(env, ContractError::TransactionAfterTimestampDeadline)
+            }
+        }
+
+        validate_int_parameters!(desired_a, min_a, desired_b, min_b);
+
+        // sender needs to authorize the deposit
+        sender.require_auth();
+
+        let config = get_config(&env);
+        let pool_balance_a = utils::get_pool_balance_a(&env);
+        let pool_balance_b = utils::get_pool_balance_b(&env);
+
+        // Check if custom_slippage_bps is more than max_allowed_slippage
+        if let Some(custom_slippage) = custom_slippage_bps {
+            if custom_slippage > config.max_allowed_slippage_bps {
+                log!(
+                    &env,
+                    "Pool Weighted: ProvideLiquidity: Custom slippage tolerance is more than max allowed slippage tolerance"
+                );
+                panic_with_error!(env, ContractError::ProvideLiquiditySlippageToleranceTooHigh);
+            }
+        }
+
+        // the ratio of the reserves is the price scaled by the weights, so a deposit has to keep
+        // it and both tokens are required
+        let amounts = match (desired_a, desired_b) {
+            (Some(a), Some(b)) if a > 0 && b > 0 => utils::get_deposit_amounts(
+                &env,
+                a,
+                min_a,
+                b,
+                min_b,
+                pool_balance_a,
+                pool_balance_b,
+                Decimal::bps(custom_slippage_bps.unwrap_or(config.default_slippage_bps)),
+            ),
+            _ => {
+                log!(
+                    &env,
+                    "Pool Weighted: ProvideLiquidity: Both tokens must be provided and must be bigger then 0!"
+                );
+                panic_with_error!(
+                    env,
+                    ContractError::ProvideLiquidityBothTokensMustBeBiggerThenZero
+                );
+            }
+        };
+        let token_a_client = token_contract::Client::new(&env, &config.token_a);
+        let token_b_client = token_contract::Client::new(&env, &config.token_b);
+
+        // Move tokens from client's wallet to the contract
+        token_a_client.transfer(&sender, &env.current_contract_address(), &(amounts.0));
+        token_b_client.transfer(&sender, &env.current_contract_address(), &(amounts.1));
+
+        // Now calculate how many new pool shares to mint
+        let balance_a = pool_balance_a + amounts.0;
+        let balance_b = pool_balance_b + amounts.1;
+        let mut total_shares = utils::get_total_shares(&env);
+
+        let new_total_shares = if pool_balance_a > 0 && pool_balance_b > 0 {
+            let shares_a = (balance_a * total_shares) / pool_balance_a;
+            let shares_b = (balance_b * total_shares) / pool_balance_b;
+            shares_a.min(shares_b)
+        } else {
+            // In case of empty pool, produce the value of the weighted invariant
+            weighted_geometric_mean(
+                balance_a,
+                config.weight_a_bps,
+                balance_b,
+                config.weight_b_bps,
+            )
+        };
+
+        if total_shares == 0 {
+            if new_total_shares <= MINIMUM_LIQUIDITY_AMOUNT {
+                log!(
+                    &env,
+                    "Pool Weighted: ProvideLiquidity: Liquidity amount is too low - shares: {}",
+                    new_total_shares
+                );
+                panic_with_error!(&env, ContractError::LowLiquidity);
+            }
+            // the first shares are locked in the pool forever, so the value of a single share can
+            // not be inflated far enough to round the following deposits down to nothing
+            utils::mint_shares(
+                &env,
+                &config.share_token,
+                &env.current_contract_address(),
+                MINIMUM_LIQUIDITY_AMOUNT,
+            );
+            total_shares = MINIMUM_LIQUIDITY_AMOUNT;
+        }
+
+        utils::mint_shares(
+            &env,
+            &config.share_token,
+            &sender,
+            new_total_shares - total_shares,
+        );
+        utils::save_pool_balance_a(&env, balance_a);
+        utils::save_pool_balance_b(&env, balance_b);
+
+        env.events()
+            .publish(("provide_liquidity", "sender"), sender);
+        env.events()
+            .publish(("provide_liquidity", "token_a"), &config.token_a);
+        env.events()
+            .publish(("provide_liquidity", "token_a-amount"), amounts.0);
+        env.events()
+            .publish(("provide_liquidity", "token_b"), &config.token_b);
+        env.events()
+            .publish(("provide_liquidity", "token_b-amount"), amounts.1);
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn swap(
+        env: Env,
+        sender: Address,
+        referral: Option<Referral>,
+        offer_asset: Address,
+        offer_amount: i128,
+        ask_asset_min_amount: Option<i128>,
+        max_spread_bps: Option<i64>,
+        deadline: Option<u64>,
+    ) -> SwapResponse {
+        ensure_not_paused(&env);
+
+        if let Some(deadline) = deadline {
+            if env.ledger().timestamp() > deadline {
+                log!(
+                    env,
+                    "Pool Weighted: Swap: Transaction executed after deadline!"
+                );
+                panic_with_error!(env, ContractError::TransactionAfterTimestampDeadline)
+            }
+        }
+
+        validate_int_parameters!(offer_amount);
+
+        sender.require_auth();
+
+        do_swap(
+            env,
+            sender,
+            referral,
+            offer_asset,
+            offer_amount,
+            ask_asset_min_amount,
+            max_spread_bps,
+        )
+    }
+
+    fn withdraw_liquidity(
+        env: Env,
+        sender: Address,
+        share_amount: i128,
+        min_a: i128,
+        min_b: i128,
+        deadline: Option<u64>,
+    ) -> (i128, i128) {
+        if let Some(deadline) = deadline {
+            if env.ledger().timestamp() > deadline {
+                log!(
+                    env,
+                    "Pool Weighted: Withdraw Liquidity: Transaction executed after deadline!"
+                );
+                panic_with_error!(env, ContractError::TransactionAfterTimestampDeadline)
+            }
+        }
+
+        validate_int_parameters!(share_amount, min_a, min_b);
+
+        sender.require_auth();
+
+        let config = get_config(&env);
+
+        let share_token_client = token_contract::Client::new(&env, &config.share_token);
+        share_token_client.transfer(&sender, &env.current_contract_address(), &share_amount);
+
+        let pool_balance_a = utils::get_pool_balance_a(&env);
+        let pool_balance_b = utils::get_pool_balance_b(&env);
+
+        let total_shares = utils::get_total_shares(&env);
+
+        if total_shares == 0i128 {
+            log!(&env, "Pool Weighted: WithdrawLiquidity: Critical error - Total shares are equal to zero before withdrawal!");
+            panic_with_error!(env, ContractError::TotalSharesEqualZero);
+        }
+
+        let share_ratio = Decimal::from_ratio(share_amount, total_shares);
+
+        let return_amount_a = pool_balance_a * share_ratio;
+        let return_amount_b = pool_balance_b * share_ratio;
+
+        if return_amount_a < min_a || return_amount_b < min_b {
+            log!(
+                &env,
+                "Pool Weighted: WithdrawLiquidity: Minimum amount of token_a or token_b is not satisfied! min_a: {}, min_b: {}, return_amount_a: {}, return_amount_b: {}",
+                min_a,
+                min_b,
+                return_amount_a,
+                return_amount_b
+            );
+            panic_with_error!(
+                env,
+                ContractError::WithdrawLiquidityMinimumAmountOfAOrBIsNotSatisfied
+            );
+        }
+
+        // burn shares
+        utils::burn_shares(&env, &config.share_token, share_amount);
+        // transfer tokens from sender to contract
+        token_contract::Client::new(&env, &config.token_a).transfer(
+            &env.current_contract_address(),
+            &sender,
+            &return_amount_a,
+        );
+        token_contract::Client::new(&env, &config.token_b).transfer(
+            &env.current_contract_address(),
+            &sender,
+            &return_amount_b,
+        );
+        // update pool balances
+        utils::save_pool_balance_a(&env, pool_balance_a - return_amount_a);
+        utils::save_pool_balance_b(&env, pool_balance_b - return_amount_b);
+
+        env.events()
+            .publish(("withdraw_liquidity", "sender"), sender);
+        env.events()
+            .publish(("withdraw_liquidity", "shares_amount"), share_amount);
+        env.events()
+            .publish(("withdraw_liquidity", "return_amount_a"), return_amount_a);
+        env.events()
+            .publish(("withdraw_liquidity", "return_amount_b"), return_amount_b);
+
+        (return_amount_a, return_amount_b)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn update_config(
+        env: Env,
+        new_admin: Option<Address>,
+        total_fee_bps: Option<i64>,
+        fee_recipient: Option<Address>,
+        max_allowed_slippage_bps: Option<i64>,
+        max_allowed_spread_bps: Option<i64>,
+        max_referral_bps: Option<i64>,
+        protocol_fee_share_bps: Option<i64>,
+    ) {
+        let admin: Address = utils::get_admin(&env);
+        admin.require_auth();
+
+        let mut config = get_config(&env);
+
+        if let Some(new_admin) = new_admin {
+            utils::save_admin(&env, new_admin);
+        }
+        if let Some(total_fee_bps) = total_fee_bps {
+            if !(0..=10_000).contains(&total_fee_bps) {
+                log!(&env, "Pool Weighted: UpdateConfig: Invalid total_fee_bps");
+                panic_with_error!(&env, ContractError::InvalidBps);
+            }
+            config.total_fee_bps = total_fee_bps;
+        }
+        if let Some(fee_recipient) = fee_recipient {
+            config.fee_recipient = fee_recipient;
+        }
+        if let Some(max_allowed_slippage_bps) = max_allowed_slippage_bps {
+            config.max_allowed_slippage_bps = max_allowed_slippage_bps;
+        }
+        if let Some(max_allowed_spread_bps) = max_allowed_spread_bps {
+            config.max_allowed_spread_bps = max_allowed_spread_bps;
+        }
+        if let Some(max_referral_bps) = max_referral_bps {
+            config.max_referral_bps = max_referral_bps;
+        }
+        if let Some(protocol_fee_share_bps) = protocol_fee_share_bps {
+            if !(0..=10_000).contains(&protocol_fee_share_bps) {
+                log!(
+                    &env,
+                    "Pool Weighted: UpdateConfig: Invalid protocol_fee_share_bps"
+                );
+                panic_with_error!(&env, ContractError::InvalidBps);
+            }
+            config.protocol_fee_share_bps = protocol_fee_share_bps;
+        }
+
+        save_config(&env, config);
+    }
+
+    fn set_guardian(env: Env, guardian: Address) {
+        let admin: Address = utils::get_admin(&env);
+        admin.require_auth();
+
+        utils::save_guardian(&env, guardian.clone());
+
+        env.events().publish(("set_guardian", "guardian"), guardian);
+    }
+
+    fn pause(env: Env, sender: Address) {
+        sender.require_auth();
+
+        let is_guardian = utils::get_guardian(&env).map_or(false, |guardian| guardian == sender);
+        let is_factory = utils::get_factory(&env).map_or(false, |factory| factory == sender);
+        if sender != utils::get_admin(&env) && !is_factory && !is_guardian {
+            log!(
+                &env,
+                "Pool Weighted: Pause: You are not authorized to pause the pool!"
+            );
+            panic_with_error!(&env, ContractError::Unauthorized);
+        }
+
+        utils::set_paused(&env, true);
+
+        env.events().publish(("pause", "sender"), sender);
+    }
+
+    fn unpause(env: Env, sender: Address) {
+        sender.require_auth();
+
+        let is_factory = utils::get_factory(&env).map_or(false, |factory| factory == sender);
+        if sender != utils::get_admin(&env) && !is_factory {
+            log!(
+                &env,
+                "Pool Weighted: Unpause: You are not authorized to unpause the pool!"
+            );
+            panic_with_error!(&env, ContractError::Unauthorized);
+        }
+
+        utils::set_paused(&env, false);
+
+        env.events().publish(("unpause", "sender"), sender);
+    }
+
+    fn upgrade(env: Env, new_wasm_hash: BytesN<32>) {
+        let admin: Address = utils::get_admin(&env);
+        admin.require_auth();
+
+        env.deployer().update_current_contract_wasm(new_wasm_hash);
+    }
+
+    // Queries
+
+    fn query_config(env: Env) -> Config {
+        get_config(&env)
+    }
+
+    fn query_share_token_address(env: Env) -> Address {
+        get_config(&env).share_token
+    }
+
+    fn query_stake_contract_address(env: Env) -> Address {
+        get_config(&env).stake_contract
+    }
+
+    fn query_pool_info(env: Env) -> PoolResponse {
+        let config = get_config(&env);
+
+        PoolResponse {
+            asset_a: Asset {
+                address: config.token_a,
+                amount: utils::get_pool_balance_a(&env),
+            },
+            asset_b: Asset {
+                address: config.token_b,
+                amount: utils::get_pool_balance_b(&env),
+            },
+            asset_lp_share: Asset {
+                address: config.share_token,
+                amount: utils::get_total_shares(&env),
+            },
+            stake_address: config.stake_contract,
+        }
+    }
+
+    fn query_pool_info_for_factory(env: Env) -> LiquidityPoolInfo {
+        let total_fee_bps = get_config(&env).total_fee_bps;
+
+        LiquidityPoolInfo {
+            pool_address: env.current_contract_address(),
+            pool_response: Self::query_pool_info(env),
+            total_fee_bps,
+        }
+    }
+
+    fn simulate_swap(env: Env, offer_asset: Address, offer_amount: i128) -> SimulateSwapResponse {
+        let config = get_config(&env);
+
+        let (pool_balance_offer, pool_balance_ask, offer_weight, ask_weight) =
+            get_swap_side(&env, &config, &offer_asset);
+        ensure_pool_not_empty(&env, pool_balance_offer, pool_balance_ask);
+
+        let compute_swap: ComputeSwap = compute_swap(
+            &env,
+            pool_balance_offer,
+            pool_balance_ask,
+            offer_amount,
+            offer_weight,
+            ask_weight,
+            config.protocol_fee_rate(),
+            0i64,
+        );
+
+        let total_return = compute_swap.return_amount
+            + compute_swap.commission_amount
+            + compute_swap.spread_amount;
+
+        SimulateSwapResponse {
+            ask_amount: compute_swap.return_amount,
+            commission_amount: compute_swap.commission_amount,
+            protocol_fee_amount: compute_swap.commission_amount * config.protocol_fee_share(),
+            spread_amount: compute_swap.spread_amount,
+            total_return,
+        }
+    }
+
+    fn simulate_reverse_swap(
+        env: Env,
+        ask_asset: Address,
+        ask_amount: i128,
+    ) -> SimulateReverseSwapResponse {
+        let config = get_config(&env);
+
+        let (pool_balance_ask, pool_balance_offer, ask_weight, offer_weight) =
+            get_swap_side(&env, &config, &ask_asset);
+        ensure_pool_not_empty(&env, pool_balance_offer, pool_balance_ask);
+
+        let (offer_amount, spread_amount, commission_amount) = compute_offer_amount(
+            pool_balance_offer,
+            pool_balance_ask,
+            ask_amount,
+            offer_weight,
+            ask_weight,
+            config.protocol_fee_rate(),
+        )
+        .unwrap_or_else(|| {
+            log!(
+                &env,
+                "Pool Weighted: Simulate Reverse Swap: Not enough liquidity to pay out the ask amount"
+            );
+            panic_with_error!(&env, ContractError::NotEnoughLiquidity);
+        });
+
+        SimulateReverseSwapResponse {
+            offer_amount,
+            spread_amount,
+            commission_amount,
+        }
+    }
+
+    fn query_share(env: Env, amount: i128) -> (Asset, Asset) {
+        let pool_info = Self::query_pool_info(env);
+        let total_share = pool_info.asset_lp_share.amount;
+        let token_a_amount = pool_info.asset_a.amount;
+        let token_b_amount = pool_info.asset_b.amount;
+
+        let mut share_ratio = Decimal::zero();
+        if total_share != 0 {
+            share_ratio = Decimal::from_ratio(amount, total_share);
+        }
+
+        let amount_a = token_a_amount * share_ratio;
+        let amount_b = token_b_amount * share_ratio;
+        (
+            Asset {
+                address: pool_info.asset_a.address,
+                amount: amount_a,
+            },
+            Asset {
+                address: pool_info.asset_b.address,
+                amount: amount_b,
+            },
+        )
+    }
+
+    fn query_total_issued_lp(env: Env) -> i128 {
+        utils::get_total_shares(&env)
+    }
+
+    fn query_paused(env: Env) -> bool {
+        utils::is_paused(&env)
+    }
+
+    fn query_guardian(env: Env) -> Option<Address> {
+        utils::get_guardian(&env)
+    }
+}
+
+#[contractimpl]
+impl WeightedLiquidityPool {
+    #[allow(dead_code)]
+    pub fn update(env: Env, new_wasm_hash: BytesN<32>) {
+        let admin = get_admin(&env);
+        admin.require_auth();
+
+        env.deployer().update_current_contract_wasm(new_wasm_hash);
+    }
+}
+
+fn ensure_not_paused(env: &Env) {
+    if utils::is_paused(env) {
+        log!(env, "Pool Weighted: The pool is paused!");
+        panic_with_error!(env, ContractError::ContractPaused);
+    }
+}
+
+/// Panics if either reserve of a swap is empty, as the weighted math is undefined for it.
+fn ensure_pool_not_empty(env: &Env, pool_balance_offer: i128, pool_balance_ask: i128) {
+    if pool_balance_offer <= 0 || pool_balance_ask <= 0 {
+        log!(
+            env,
+            "Pool Weighted: Swap: The pool holds no liquidity to swap against"
+        );
+        panic_with_error!(env, ContractError::EmptyPool);
+    }
+}
+
+/// Returns the reserve and the weight of `asset` followed by the reserve and the weight of the
+/// other token of the pool.
+fn get_swap_side(env: &Env, config: &Config, asset: &Address) -> (i128, i128, i64, i64) {
+    let pool_balance_a = utils::get_pool_balance_a(env);
+    let pool_balance_b = utils::get_pool_balance_b(env);
+
+    if asset == &config.token_a {
+        (
+            pool_balance_a,
+            pool_balance_b,
+            config.weight_a_bps,
+            config.weight_b_bps,
+        )
+    } else if asset == &config.token_b {
+        (
+            pool_balance_b,
+            pool_balance_a,
+            config.weight_b_bps,
+            config.weight_a_bps,
+        )
+    } else {
+        log!(
+            env,
+            "Pool Weighted: Token offered to swap not found in Pool"
+        );
+        panic_with_error!(env, ContractError::AssetNotInPool);
+    }
+}
+
+fn do_swap(
+    env: Env,
+    sender: Address,
+    referral: Option<Referral>,
+    offer_asset: Address,
+    offer_amount: i128,
+    ask_asset_min_amount: Option<i128>,
+    max_spread: Option<i64>,
+) -> SwapResponse {
+    let config = get_config(&env);
+
+    if let Some(referral) = &referral {
+        if !(0..=config.max_referral_bps).contains(&referral.fee_bps) {
+            log!(
+                &env,
+                "Pool Weighted: Swap: Trying to swap with more than the allowed referral fee"
+            );
+            panic_with_error!(&env, ContractError::ReferralFeeTooHigh);
+        }
+    }
+
+    if let Some(max_spread) = max_spread {
+        if !(0..=config.max_allowed_spread_bps).contains(&max_spread) {
+            log!(&env, "Pool Weighted: do_swap: max spread is out of bounds");
+            panic_with_error!(&env, ContractError::InvalidBps);
+        }
+    }
+
+    let max_spread = Decimal::bps(max_spread.map_or_else(|| config.max_allowed_spread_bps, |x| x));
+
+    let (pool_balance_sell, pool_balance_buy, sell_weight, buy_weight) =
+        get_swap_side(&env, &config, &offer_asset);
+    ensure_pool_not_empty(&env, pool_balance_sell, pool_balance_buy);
+
+    let referral_fee_bps = match referral {
+        Some(ref referral) => referral.fee_bps,
+        None => 0,
+    };
+
+    let compute_swap: ComputeSwap = compute_swap(
+        &env,
+        pool_balance_sell,
+        pool_balance_buy,
+        offer_amount,
+        sell_weight,
+        buy_weight,
+        config.protocol_fee_rate(),
+        referral_fee_bps,
+    );
+
+    if let Some(ask_asset_min_amount) = ask_asset_min_amount {
+        if ask_asset_min_amount > compute_swap.return_amount {
+            log!(
+                &env,
+                "Pool Weighted: do_swap: Return amount is smaller then expected minimum amount"
+            );
+            panic_with_error!(&env, ContractError::SwapMinReceivedBiggerThanReturn);
+        }
+    }
+
+    let total_return_amount = compute_swap.return_amount
+        + compute_swap.commission_amount
+        + compute_swap.referral_fee_amount;
+
+    assert_max_spread(
+        &env,
+        max_spread,
+        total_return_amount,
+        compute_swap.spread_amount,
+    );
+
+    let (sell_token, buy_token) = if offer_asset == config.token_a {
+        (config.token_a.clone(), config.token_b.clone())
+    } else {
+        (config.token_b.clone(), config.token_a.clone())
+    };
+
+    // transfer tokens to swap
+    token_contract::Client::new(&env, &sell_token).transfer(
+        &sender,
+        &env.current_contract_address(),
+        &offer_amount,
+    );
+
+    // return swapped tokens to user
+    token_contract::Client::new(&env, &buy_token).transfer(
+        &env.current_contract_address(),
+        &sender,
+        &compute_swap.return_amount,
+    );
+
+    // send the protocol share of the commission to fee recipient, the rest stays in the pool
+    let protocol_fee_amount = compute_swap.commission_amount * config.protocol_fee_share();
+    token_contract::Client::new(&env, &buy_token).transfer(
+        &env.current_contract_address(),
+        &config.fee_recipient,
+        &protocol_fee_amount,
+    );
+
+    if let Some(Referral { address, .. }) = referral {
+        if compute_swap.referral_fee_amount > 0 {
+            token_contract::Client::new(&env, &buy_token).transfer(
+                &env.current_contract_address(),
+                &address,
+                &compute_swap.referral_fee_amount,
+            );
+        }
+    }
+
+    let pool_balance_sell = pool_balance_sell + offer_amount;
+    let pool_balance_buy = pool_balance_buy
+        - protocol_fee_amount
+        - compute_swap.referral_fee_amount
+        - compute_swap.return_amount;
+    if offer_asset == config.token_a {
+        utils::save_pool_balance_a(&env, pool_balance_sell);
+        utils::save_pool_balance_b(&env, pool_balance_buy);
+    } else {
+        utils::save_pool_balance_a(&env, pool_balance_buy);
+        utils::save_pool_balance_b(&env, pool_balance_sell);
+    }
+
+    env.events().publish(("swap", "sender"), sender);
+    env.events().publish(("swap", "sell_token"), sell_token);
+    env.events().publish(("swap", "offer_amount"), offer_amount);
+    env.events().publish(("swap", "buy_token"), buy_token);
+    env.events()
+        .publish(("swap", "return_amount"), compute_swap.return_amount);
+    env.events()
+        .publish(("swap", "spread_amount"), compute_swap.spread_amount);
+    env.events().publish(
+        ("swap", "referral_fee_amount"),
+        compute_swap.referral_fee_amount,
+    );
+    env.events()
+        .publish(("swap", "protocol_fee_amount"), protocol_fee_amount);
+    env.events().publish(
+        ("swap", "lp_fee_amount"),
+        compute_swap.commission_amount - protocol_fee_amount,
+    );
+
+    SwapResponse {
+        ask_amount: compute_swap.return_amount,
+        commission_amount: compute_swap.commission_amount,
+        protocol_fee_amount,
+        referral_fee_amount: compute_swap.referral_fee_amount,
+        spread_amount: compute_swap.spread_amount,
+    }
+}
+
+/// This function asserts that the spread (slippage) does not exceed a given maximum.
+/// * `max_spread` - The maximum allowed spread (slippage) as a fraction of the return amount.
+/// * `return_amount` - The amount of tokens that the user receives in return.
+/// * `spread_amount` - The spread (slippage) amount, i.e., the difference between the expected and actual return.
+/// # Returns
+/// * An error if the spread exceeds the maximum allowed, otherwise Ok.
+pub fn assert_max_spread(env: &Env, max_spread: Decimal, return_amount: i128, spread_amount: i128) {
+    // Calculate the spread ratio, the fraction of the return that is due to spread
+    let spread_ratio = Decimal::from_ratio(spread_amount, return_amount);
+
+    if spread_ratio > max_spread {
+        log!(env, "Pool Weighted: Spread exceeds maximum allowed");
+        panic_with_error!(env, ContractError::SpreadExceedsLimit);
+    }
+}
+
+/// The amount of ask assets the offer amount is worth at the spot price of the pool, which is
+/// `(ask_pool / ask_weight) / (offer_pool / offer_weight)` for a weighted pool.
+fn spot_return_amount(
+    offer_pool: i128,
+    ask_pool: i128,
+    offer_amount: i128,
+    offer_weight: i64,
+    ask_weight: i64,
+) -> i128 {
+    offer_amount * ask_pool * offer_weight as i128 / (offer_pool * ask_weight as i128)
+}
+
+/// Computes the result of a swap operation.
+///
+/// Arguments:
+/// - `offer_pool`: Total amount of offer assets in the pool.
+/// - `ask_pool`: Total amount of ask assets in the pool.
+/// - `offer_amount`: Amount of offer assets to swap.
+/// - `offer_weight`: Weight of the offer asset in bps.
+/// - `ask_weight`: Weight of the ask asset in bps.
+/// - `commission_rate`: Total amount of fees charged for the swap.
+/// - `referral_fee`: Amount of fee for the referral
+///
+/// The pool keeps `offer_pool^offer_weight * ask_pool^ask_weight` constant, so the swap returns
+/// `ask_pool * (1 - (offer_pool / (offer_pool + offer_amount))^(offer_weight / ask_weight))`.
+#[allow(clippy::too_many_arguments)]
+pub fn compute_swap(
+    env: &Env,
+    offer_pool: i128,
+    ask_pool: i128,
+    offer_amount: i128,
+    offer_weight: i64,
+    ask_weight: i64,
+    commission_rate: Decimal,
+    referral_fee: i64,
+) -> ComputeSwap {
+    let ratio = Decimal::from_ratio(offer_pool, offer_pool + offer_amount);
+    // the power is rounded up, so the rounding never takes value out of the pool. A ratio of at
+    // most one has a power that fits unless it rounds to zero
+    let power = pow_up(ratio, offer_weight as i128, ask_weight as i128).unwrap_or_else(|| {
+        log!(
+            env,
+            "Pool Weighted: Compute Swap: Swap amount is out of range for the pool"
+        );
+        panic_with_error!(env, ContractError::SwapComputationOverflow);
+    });
+    let return_amount: i128 = if power < Decimal::one() {
+        ask_pool * (Decimal::one() - power)
+    } else {
+        0
+    };
+
+    // Calculate the spread amount, representing the difference between the expected and actual swap amounts
+    let spread_amount: i128 =
+        (spot_return_amount(offer_pool, ask_pool, offer_amount, offer_weight, ask_weight)
+            - return_amount)
+            .max(0);
+
+    let commission_amount: i128 = return_amount * commission_rate;
+
+    // Deduct the commission (minus the part that goes to the protocol) from the return amount
+    let return_amount: i128 = return_amount - commission_amount;
+    let referral_fee_amount: i128 = return_amount * Decimal::bps(referral_fee);
+
+    let return_amount: i128 = return_amount - referral_fee_amount;
+
+    ComputeSwap {
+        return_amount,
+        spread_amount,
+        commission_amount,
+        referral_fee_amount,
+    }
+}
+
+/// Returns an amount of offer assets for a specified amount of ask assets, together with the
+/// spread and the commission, or `None` when the pool can not pay out the ask amount.
+///
+/// * **offer_pool** total amount of offer assets in the pool.
+/// * **ask_pool** total amount of ask assets in the pool.
+/// * **ask_amount** amount of ask assets to swap to.
+/// * **offer_weight** weight of the offer asset in bps.
+/// * **ask_weight** weight of the ask asset in bps.
+/// * **commission_rate** total amount of fees charged for the swap.
+pub fn compute_offer_amount(
+    offer_pool: i128,
+    ask_pool: i128,
+    ask_amount: i128,
+    offer_weight: i64,
+    ask_weight: i64,
+    commission_rate: Decimal,
+) -> Option<(i128, i128, i128)> {
+    // Calculate the inverse of one minus the commission rate
+    let inv_one_minus_commission = Decimal::one() / (Decimal::one() - commission_rate);
+    let ask_before_commission = ask_amount * inv_one_minus_commission;
+    if ask_before_commission >= ask_pool {
+        return None;
+    }
+
+    // offer_pool * ((ask_pool / (ask_pool - ask))^(ask_weight / offer_weight) - 1), rounded up
+    let ratio = Decimal::from_ratio(ask_pool, ask_pool - ask_before_commission);
+    let power = pow_up(ratio, ask_weight as i128, offer_weight as i128)?;
+    let offer_amount: i128 = offer_pool * (power - Decimal::one()) + 1;
+
+    // Calculate the spread amount, representing the difference between the expected and actual swap amounts
+    let spread_amount: i128 =
+        (spot_return_amount(offer_pool, ask_pool, offer_amount, offer_weight, ask_weight)
+            - ask_before_commission)
+            .max(0);
+
+    // Calculate the commission amount
+    let commission_amount: i128 = ask_before_commission * commission_rate;
+
+    Some((offer_amount, spread_amount, commission_amount))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compute_swap_with_equal_weights_matches_constant_product() {
+        let env = Env::default();
+        // 1_000_000 * 1_000_000 / 1_010_000 = 990_099.0099
+        let result = compute_swap(
+            &env,
+            1_000_000,
+            1_000_000,
+            10_000,
+            5_000,
+            5_000,
+            Decimal::zero(),
+            0,
+        );
+        assert_eq!(result.return_amount, 9_900);
+        assert_eq!(result.spread_amount, 100);
+    }
+
+    #[test]
+    fn compute_swap_follows_the_weights() {
+        let env = Env::default();
+        // an 80/20 pool of 4_000_000 A and 1_000_000 B prices A at 1 B
+        // 1_000_000 * (1 - (4_000_000 / 4_010_000)^4) = 9_937.8
+        let result = compute_swap(
+            &env,
+            4_000_000,
+            1_000_000,
+            10_000,
+            8_000,
+            2_000,
+            Decimal::zero(),
+            0,
+        );
+        assert_eq!(result.return_amount, 9_937);
+        assert_eq!(result.spread_amount, 63);
+
+        // the other way around, 4_000_000 * (1 - (1_000_000 / 1_010_000)^0.25) = 9_937.9
+        let result = compute_swap(
+            &env,
+            1_000_000,
+            4_000_000,
+            10_000,
+            2_000,
+            8_000,
+            Decimal::zero(),
+            0,
+        );
+        assert_eq!(result.return_amount, 9_937);
+        assert_eq!(result.spread_amount, 63);
+    }
+
+    #[test]
+    fn compute_swap_takes_commission_and_referral_from_the_return() {
+        let env = Env::default();
+        let result = compute_swap(
+            &env,
+            4_000_000,
+            1_000_000,
+            10_000,
+            8_000,
+            2_000,
+            Decimal::percent(10),
+            1_000,
+        );
+        assert_eq!(result.commission_amount, 993);
+        assert_eq!(result.referral_fee_amount, 894);
+        assert_eq!(result.return_amount, 9_937 - 993 - 894);
+    }
+
+    #[test]
+    fn compute_offer_amount_is_the_inverse_of_compute_swap() {
+        let env = Env::default();
+        for (offer_pool, ask_pool, offer_weight, ask_weight) in [
+            (4_000_000, 1_000_000, 8_000, 2_000),
+            (1_000_000, 4_000_000, 2_000, 8_000),
+            (1_000_000, 1_000_000, 5_000, 5_000),
+        ] {
+            let (offer_amount, _, commission_amount) = compute_offer_amount(
+                offer_pool,
+                ask_pool,
+                100_000,
+                offer_weight,
+                ask_weight,
+                Decimal::bps(30),
+            )
+            .unwrap();
+
+            let result = compute_swap(
+                &env,
+                offer_pool,
+                ask_pool,
+                offer_amount,
+                offer_weight,
+                ask_weight,
+                Decimal::bps(30),
+                0,
+            );
+            assert!(result.return_amount >= 100_000);
+            assert!(result.return_amount - 100_000 <= 2);
+            assert!((result.commission_amount - commission_amount).abs() <= 1);
+        }
+    }
+
+    #[test]
+    fn compute_offer_amount_beyond_the_pool_is_none() {
+        assert_eq!(
+            compute_offer_amount(
+                1_000_000,
+                1_000_000,
+                1_000_000,
+                5_000,
+                5_000,
+                Decimal::zero()
+            ),
+            None
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Pool Weighted: Spread exceeds maximum allowed")]
+    fn test_assert_max_spread_failure() {
+        let env = Env::default();
+        assert_max_spread(&env, Decimal::percent(10), 1_000, 200);
+    }
+}
@@ -0,0 +1,38 @@
+use soroban_sdk::contracterror;
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum ContractError {
+    SpreadExceedsLimit = 1,
+
+    ProvideLiquiditySlippageToleranceTooHigh = 2,
+    ProvideLiquidityBothTokensMustBeBiggerThenZero = 3,
+
+    WithdrawLiquidityMinimumAmountOfAOrBIsNotSatisfied = 4,
+
+    GetDepositAmountsMinABiggerThenDesiredA = 5,
+    GetDepositAmountsMinBBiggerThenDesiredB = 6,
+    GetDepositAmountsAmountABiggerThenDesiredA = 7,
+    GetDepositAmountsAmountALessThenMinA = 8,
+    GetDepositAmountsAmountBBiggerThenDesiredB = 9,
+    GetDepositAmountsAmountBLessThenMinB = 10,
+    TotalSharesEqualZero = 11,
+    DesiredAmountsBelowOrEqualZero = 12,
+    MinAmountsBelowZero = 13,
+    AssetNotInPool = 14,
+    AlreadyInitialized = 15,
+    TokenABiggerThanTokenB = 16,
+    InvalidBps = 17,
+    InvalidWeights = 18,
+
+    SwapMinReceivedBiggerThanReturn = 19,
+    TransactionAfterTimestampDeadline = 20,
+    ReferralFeeTooHigh = 21,
+    Unauthorized = 22,
+    ContractPaused = 23,
+    LowLiquidity = 24,
+    NotEnoughLiquidity = 25,
+    EmptyPool = 26,
+    SwapComputationOverflow = 27,
+}
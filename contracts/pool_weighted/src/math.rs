@@ -0,0 +1,214 @@
+use soroban_decimal::Decimal;
+
+/// One with 18 decimal places, the precision of `Decimal`
+const ONE: i128 = 1_000_000_000_000_000_000;
+/// ln(2) with 18 decimal places
+const LN_2: i128 = 693_147_180_559_945_309;
+/// e^x does not fit into a decimal above this
+const MAX_EXP_ARGUMENT: i128 = 46 * ONE;
+/// e^x rounds down to zero below this
+const MIN_EXP_ARGUMENT: i128 = -42 * ONE;
+/// Upper bound of the relative error of `pow` (1e-14), used to round its result in favour of
+/// the pool
+const MAX_POW_RELATIVE_ERROR: i128 = 10_000;
+
+/// Natural logarithm of a positive number with 18 decimal places.
+///
+/// The number is split into `m * 2^k` with `m` in `[1, 2)`, and `ln(m)` is computed with the
+/// series `2 * (z + z^3 / 3 + z^5 / 5 + ...)` where `z = (m - 1) / (m + 1)` is at most 1/3.
+fn ln(x: i128) -> i128 {
+    assert!(x > 0, "Pool Weighted: ln of a non-positive number");
+
+    let (m, k) = if x >= ONE {
+        let k = (x / ONE).ilog2() as i128;
+        (x >> k, k)
+    } else {
+        let k = (ONE / x).ilog2() as i128;
+        // x * 2^k is in [0.5, 1), one more doubling moves it to [1, 2)
+        let m = x << k;
+        if m >= ONE {
+            (m, -k)
+        } else {
+            (m << 1, -k - 1)
+        }
+    };
+
+    let z = (m - ONE) * ONE / (m + ONE);
+    let z_squared = z * z / ONE;
+
+    let mut sum = 0;
+    let mut term = z;
+    let mut n = 1;
+    while term != 0 {
+        sum += term / n;
+        term = term * z_squared / ONE;
+        n += 2;
+    }
+
+    k * LN_2 + 2 * sum
+}
+
+/// e^x with 18 decimal places, `None` when the result does not fit into a decimal.
+///
+/// The exponent is split into `k * ln(2) + r` with `r` in `[0, ln(2))`, so the Taylor series
+/// of `e^r` converges quickly and `e^x = e^r * 2^k`.
+fn exp(x: i128) -> Option<i128> {
+    if x < MIN_EXP_ARGUMENT {
+        return Some(0);
+    }
+    if x > MAX_EXP_ARGUMENT {
+        return None;
+    }
+
+    let k = x.div_euclid(LN_2);
+    let r = x - k * LN_2;
+
+    let mut sum = ONE;
+    let mut term = ONE;
+    let mut n = 1;
+    loop {
+        term = term * r / ONE / n;
+        if term == 0 {
+            break;
+        }
+        sum += term;
+        n += 1;
+    }
+
+    if k >= 0 {
+        Some(sum << k)
+    } else {
+        Some(sum >> -k)
+    }
+}
+
+/// `base^(exponent_numerator / exponent_denominator)` for a positive base, rounded up by the
+/// maximum relative error of the computation. `None` when the result does not fit into a decimal.
+///
+/// The exponent is a ratio of the pool weights, so it is applied as such and the logarithm of the
+/// base never has to be multiplied by a large decimal.
+pub fn pow_up(
+    base: Decimal,
+    exponent_numerator: i128,
+    exponent_denominator: i128,
+) -> Option<Decimal> {
+    let power = exp(ln(base.atomics()) * exponent_numerator / exponent_denominator)?;
+    let max_error =
+        power / ONE * MAX_POW_RELATIVE_ERROR + (power % ONE) * MAX_POW_RELATIVE_ERROR / ONE + 1;
+
+    Some(Decimal::new(power.checked_add(max_error)?))
+}
+
+/// The weighted geometric mean `a^weight_a * b^weight_b` of two positive amounts, with the
+/// weights in bps adding up to 10_000. It is the invariant of a weighted pool, and the amount of
+/// shares minted by the first deposit.
+pub fn weighted_geometric_mean(
+    amount_a: i128,
+    weight_a: i64,
+    amount_b: i128,
+    weight_b: i64,
+) -> i128 {
+    let weighted_ln = (ln(amount_a * ONE) * weight_a as i128
+        + ln(amount_b * ONE) * weight_b as i128)
+        / (weight_a + weight_b) as i128;
+
+    // the mean lies between the two amounts, so it always fits
+    exp(weighted_ln).unwrap() / ONE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(value: i128, expected: i128, max_relative_error: i128) {
+        let error = (value - expected).abs();
+        assert!(
+            error <= expected.abs() / max_relative_error + 1,
+            "value: {value}, expected: {expected}"
+        );
+    }
+
+    #[test]
+    fn ln_matches_known_values() {
+        assert_eq!(ln(ONE), 0);
+        assert_close(ln(2 * ONE), LN_2, 1_000_000_000_000_000);
+        // ln(10) = 2.302585092994045684
+        assert_close(
+            ln(10 * ONE),
+            2_302_585_092_994_045_684,
+            1_000_000_000_000_000,
+        );
+        // ln(0.001) = -6.907755278982137052
+        assert_close(
+            ln(ONE / 1_000),
+            -6_907_755_278_982_137_052,
+            1_000_000_000_000_000,
+        );
+        // ln(1e20) = 46.051701859880913680
+        assert_close(
+            ln(100_000_000_000_000_000_000 * ONE),
+            46_051_701_859_880_913_680,
+            1_000_000_000_000_000,
+        );
+    }
+
+    #[test]
+    fn exp_matches_known_values() {
+        assert_eq!(exp(0), Some(ONE));
+        // e = 2.718281828459045235
+        assert_close(
+            exp(ONE).unwrap(),
+            2_718_281_828_459_045_235,
+            1_000_000_000_000_000,
+        );
+        // e^-5 = 0.006737946999085467
+        assert_close(
+            exp(-5 * ONE).unwrap(),
+            6_737_946_999_085_467,
+            1_000_000_000_000_000,
+        );
+        // e^40 = 235385266837019985.4
+        assert_close(
+            exp(40 * ONE).unwrap(),
+            235_385_266_837_019_985_407_899_910_749_034_804,
+            1_000_000_000_000_000,
+        );
+        assert_eq!(exp(-50 * ONE), Some(0));
+        assert_eq!(exp(50 * ONE), None);
+    }
+
+    #[test]
+    fn pow_up_is_never_below_the_exact_power() {
+        // 0.5^4 = 0.0625
+        let power = pow_up(Decimal::percent(50), 8_000, 2_000).unwrap();
+        assert!(power.atomics() >= 62_500_000_000_000_000);
+        assert_close(power.atomics(), 62_500_000_000_000_000, 10_000_000_000_000);
+
+        // 0.5^0.25 = 0.840896415253714543
+        let power = pow_up(Decimal::percent(50), 2_000, 8_000).unwrap();
+        assert!(power.atomics() >= 840_896_415_253_714_543);
+        assert_close(power.atomics(), 840_896_415_253_714_543, 10_000_000_000_000);
+
+        // 2^1 = 2
+        let power = pow_up(Decimal::percent(200), 5_000, 5_000).unwrap();
+        assert!(power.atomics() >= 2 * ONE);
+        assert_close(power.atomics(), 2 * ONE, 10_000_000_000_000);
+
+        assert_eq!(pow_up(Decimal::percent(1_000_000), 9_900, 100), None);
+    }
+
+    #[test]
+    fn weighted_geometric_mean_of_equal_weights_is_the_square_root_of_the_product() {
+        assert_close(
+            weighted_geometric_mean(1_000_000, 5_000, 4_000_000, 5_000),
+            2_000_000,
+            1_000_000_000,
+        );
+        // 1_000_000^0.8 * 10_000^0.2 = 398107.17
+        assert_close(
+            weighted_geometric_mean(1_000_000, 8_000, 10_000, 2_000),
+            398_107,
+            100_000,
+        );
+    }
+}
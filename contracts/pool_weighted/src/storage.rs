@@ -0,0 +1,491 @@
+use soroban_sdk::{
+    contracttype, log, panic_with_error, symbol_short, xdr::ToXdr, Address, Bytes, BytesN,
+    ConversionError, Env, Symbol, TryFromVal, Val,
+};
+
+use crate::{error::ContractError, token_contract};
+use soroban_decimal::Decimal;
+
+#[derive(Clone, Copy)]
+#[repr(u32)]
+pub enum DataKey {
+    TotalShares = 0,
+    ReserveA = 1,
+    ReserveB = 2,
+    Admin = 3,
+    Initialized = 4,
+    Paused = 5,
+    Guardian = 6,
+    Factory = 7,
+}
+
+impl TryFromVal<Env, DataKey> for Val {
+    type Error = ConversionError;
+
+    fn try_from_val(_env: &Env, v: &DataKey) -> Result<Self, Self::Error> {
+        Ok((*v as u32).into())
+    }
+}
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum PairType {
+    Xyk = 0,
+    Stable = 1,
+    Concentrated = 2,
+    Weighted = 3,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Config {
+    pub token_a: Address,
+    pub token_b: Address,
+    pub share_token: Address,
+    pub stake_contract: Address,
+    pub pool_type: PairType,
+    /// The total fees (in bps) charged by a pool of this type.
+    /// In relation to the returned amount of tokens
+    pub total_fee_bps: i64,
+    pub fee_recipient: Address,
+    /// The maximum amount of slippage (in bps) that is tolerated during providing liquidity
+    pub max_allowed_slippage_bps: i64,
+    /// The maximum amount of spread (in bps) that is tolerated during swap
+    pub max_allowed_spread_bps: i64,
+    /// The maximum allowed percentage (in bps) for referral fee
+    pub max_referral_bps: i64,
+    /// Default value that will be used whenever the user hasn't specified their preferred slippage
+    pub default_slippage_bps: i64,
+    /// The share (in bps) of the swap commission that is sent to the fee recipient.
+    /// The rest of the commission stays in the pool and accrues to the liquidity providers
+    pub protocol_fee_share_bps: i64,
+    /// The weight (in bps) of token A in the pool value
+    pub weight_a_bps: i64,
+    /// The weight (in bps) of token B in the pool value, both weights add up to 10_000
+    pub weight_b_bps: i64,
+}
+const CONFIG: Symbol = symbol_short!("CONFIG");
+
+impl Config {
+    pub fn protocol_fee_rate(&self) -> Decimal {
+        Decimal::bps(self.total_fee_bps)
+    }
+
+    pub fn protocol_fee_share(&self) -> Decimal {
+        Decimal::bps(self.protocol_fee_share_bps)
+    }
+
+    pub fn max_allowed_slippage(&self) -> Decimal {
+        Decimal::bps(self.max_allowed_slippage_bps)
+    }
+}
+
+pub fn get_config(env: &Env) -> Config {
+    env.storage().persistent().get(&CONFIG).unwrap()
+}
+
+pub fn save_config(env: &Env, config: Config) {
+    env.storage().persistent().set(&CONFIG, &config);
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Asset {
+    /// Address of the asset
+    pub address: Address,
+    /// The total amount of those tokens in the pool
+    pub amount: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ComputeSwap {
+    /// The amount that will be returned to the user, after all fees and spread has been taken into
+    /// account.
+    pub return_amount: i128,
+    /// The spread amount, that is the difference between expected and actual swap amount.
+    pub spread_amount: i128,
+    /// The commision amount is the fee that is charged by the pool for the swap service.
+    pub commission_amount: i128,
+    /// The referral fee is the fee that will be given back to the referral. `0` if no referral is
+    /// set.
+    pub referral_fee_amount: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Referral {
+    /// Address of the referral
+    pub address: Address,
+    /// fee in bps, later parsed to percentage
+    pub fee_bps: i64,
+}
+
+/// This struct is used to return a query result with the total amount of LP tokens and assets in a specific pool.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PoolResponse {
+    /// The asset A in the pool together with asset amounts
+    pub asset_a: Asset,
+    /// The asset B in the pool together with asset amounts
+    pub asset_b: Asset,
+    /// The total amount of LP tokens currently issued
+    pub asset_lp_share: Asset,
+    /// The address of the Stake contract for the liquidity pool
+    pub stake_address: Address,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LiquidityPoolInfo {
+    pub pool_address: Address,
+    pub pool_response: PoolResponse,
+    pub total_fee_bps: i64,
+}
+
+/// The amounts of an executed swap, as returned by `swap`.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SwapResponse {
+    /// The amount sent to the sender, after commission and referral fee
+    pub ask_amount: i128,
+    pub commission_amount: i128,
+    /// The part of the commission sent to the fee recipient, the rest stays in the pool
+    pub protocol_fee_amount: i128,
+    pub referral_fee_amount: i128,
+    pub spread_amount: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SimulateSwapResponse {
+    pub ask_amount: i128,
+    pub commission_amount: i128,
+    /// The part of the commission sent to the fee recipient, the rest stays in the pool
+    pub protocol_fee_amount: i128,
+    pub spread_amount: i128,
+    pub total_return: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SimulateReverseSwapResponse {
+    pub offer_amount: i128,
+    pub commission_amount: i128,
+    pub spread_amount: i128,
+}
+
+pub mod utils {
+    use super::*;
+
+    pub fn deploy_token_contract(
+        e: &Env,
+        token_wasm_hash: BytesN<32>,
+        token_a: &Address,
+        token_b: &Address,
+    ) -> Address {
+        let mut salt = Bytes::new(e);
+        salt.append(&token_a.to_xdr(e));
+        salt.append(&token_b.to_xdr(e));
+        let salt = e.crypto().sha256(&salt);
+        e.deployer()
+            .with_current_contract(salt)
+            .deploy(token_wasm_hash)
+    }
+
+    pub fn deploy_stake_contract(e: &Env, stake_wasm_hash: BytesN<32>) -> Address {
+        let salt = Bytes::new(e);
+        let salt = e.crypto().sha256(&salt);
+
+        e.deployer()
+            .with_current_contract(salt)
+            .deploy(stake_wasm_hash)
+    }
+
+    pub fn save_admin(e: &Env, address: Address) {
+        e.storage().persistent().set(&DataKey::Admin, &address)
+    }
+
+    pub fn save_factory(e: &Env, address: Address) {
+        e.storage().persistent().set(&DataKey::Factory, &address)
+    }
+
+    pub fn save_guardian(e: &Env, address: Address) {
+        e.storage().persistent().set(&DataKey::Guardian, &address)
+    }
+
+    pub fn set_paused(e: &Env, paused: bool) {
+        e.storage().persistent().set(&DataKey::Paused, &paused)
+    }
+
+    pub fn save_total_shares(e: &Env, amount: i128) {
+        e.storage().persistent().set(&DataKey::TotalShares, &amount)
+    }
+
+    pub fn save_pool_balance_a(e: &Env, amount: i128) {
+        e.storage().persistent().set(&DataKey::ReserveA, &amount)
+    }
+
+    pub fn save_pool_balance_b(e: &Env, amount: i128) {
+        e.storage().persistent().set(&DataKey::ReserveB, &amount)
+    }
+
+    pub fn mint_shares(e: &Env, share_token: &Address, to: &Address, amount: i128) {
+        let total = get_total_shares(e);
+
+        token_contract::Client::new(e, share_token).mint(to, &amount);
+
+        save_total_shares(e, total + amount);
+    }
+
+    pub fn burn_shares(e: &Env, share_token: &Address, amount: i128) {
+        let total = get_total_shares(e);
+
+        token_contract::Client::new(e, share_token).burn(&e.current_contract_address(), &amount);
+
+        save_total_shares(e, total - amount);
+    }
+
+    // queries
+    pub fn get_admin(e: &Env) -> Address {
+        e.storage().persistent().get(&DataKey::Admin).unwrap()
+    }
+
+    /// `None` for pools upgraded from a version that didn't store the factory
+    pub fn get_factory(e: &Env) -> Option<Address> {
+        e.storage().persistent().get(&DataKey::Factory)
+    }
+
+    pub fn get_guardian(e: &Env) -> Option<Address> {
+        e.storage().persistent().get(&DataKey::Guardian)
+    }
+
+    pub fn is_paused(e: &Env) -> bool {
+        e.storage()
+            .persistent()
+            .get(&DataKey::Paused)
+            .unwrap_or(false)
+    }
+
+    pub fn get_total_shares(e: &Env) -> i128 {
+        e.storage().persistent().get(&DataKey::TotalShares).unwrap()
+    }
+    pub fn get_pool_balance_a(e: &Env) -> i128 {
+        e.storage().persistent().get(&DataKey::ReserveA).unwrap()
+    }
+
+    pub fn get_pool_balance_b(e: &Env) -> i128 {
+        e.storage().persistent().get(&DataKey::ReserveB).unwrap()
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn get_deposit_amounts(
+        env: &Env,
+        desired_a: i128,
+        min_a: Option<i128>,
+        desired_b: i128,
+        min_b: Option<i128>,
+        pool_balance_a: i128,
+        pool_balance_b: i128,
+        allowed_slippage: Decimal,
+    ) -> (i128, i128) {
+        if desired_a <= 0 || desired_b <= 0 {
+            log!(
+            env,
+            "Pool Weighted: Get Deposit Amounts: Desired amounts are equal or less than zero - desired_a: {}, desired_b: {}",
+            desired_a, desired_b);
+
+            panic_with_error!(env, ContractError::DesiredAmountsBelowOrEqualZero);
+        }
+
+        if let (Some(min_a), Some(min_b)) = (min_a, min_b) {
+            if min_a < 0 || min_b < 0 {
+                log!(
+                env,
+                "Pool Weighted: Get Deposit Amounts: Min amounts are less than zero - min_a: {}, min_b: {}",
+                min_a, min_b);
+
+                panic_with_error!(env, ContractError::MinAmountsBelowZero);
+            }
+        }
+
+        if pool_balance_a == 0 && pool_balance_b == 0 {
+            return (desired_a, desired_b);
+        }
+
+        if let Some(min_a) = min_a {
+            if min_a > desired_a {
+                log!(
+                    &env,
+                    "Pool Weighted: GetDepositAmounts: Critical error - minimumA is bigger than desiredA"
+                );
+                panic_with_error!(env, ContractError::GetDepositAmountsMinABiggerThenDesiredA);
+            }
+        }
+        if let Some(min_b) = min_b {
+            if min_b > desired_b {
+                log!(
+                    &env,
+                    "Pool Weighted: GetDepositAmounts: Critical error - minimumB is bigger than desiredB"
+                );
+                panic_with_error!(env, ContractError::GetDepositAmountsMinBBiggerThenDesiredB);
+            }
+        }
+
+        let amount_a = {
+            let mut amount_a = desired_b * pool_balance_a / pool_balance_b;
+            if amount_a > desired_a {
+                // If the amount is within the desired amount of slippage, we accept it
+                if Decimal::from_ratio(amount_a, desired_a) - Decimal::one() <= allowed_slippage {
+                    amount_a = desired_a;
+                } else {
+                    log!(
+                        env,
+                        "Pool Weighted: Get deposit amounts: Deposit amount for asset A ({}) is invalid. It exceeds the desired amount ({})",
+                        amount_a,
+                        desired_a,
+                    );
+                    panic_with_error!(
+                        env,
+                        ContractError::GetDepositAmountsAmountABiggerThenDesiredA
+                    );
+                }
+            };
+            if let Some(min_a) = min_a {
+                if amount_a < min_a {
+                    log!(
+                        env,
+                        "Pool Weighted: Get deposit amounts: Deposit amount for asset A ({}) is invalid. It falls below the minimum requirement ({})",
+                        amount_a,
+                        min_a
+                    );
+                    panic_with_error!(env, ContractError::GetDepositAmountsAmountALessThenMinA);
+                }
+            }
+            amount_a
+        };
+
+        let amount_b = {
+            let mut amount_b = desired_a * pool_balance_b / pool_balance_a;
+            if amount_b > desired_b {
+                // If the amount is within the set threshold of the desired amount, we accept it
+                if Decimal::from_ratio(amount_b, desired_b) - Decimal::one() <= allowed_slippage {
+                    amount_b = desired_b;
+                } else {
+                    log!(
+                env,
+                "Pool Weighted: Get deposit amounts: Deposit amount for asset B ({}) is invalid. It exceeds the desired amount ({})",
+                amount_b,
+                desired_b,
+            );
+                    panic_with_error!(
+                        env,
+                        ContractError::GetDepositAmountsAmountBBiggerThenDesiredB
+                    );
+                }
+            };
+            if let Some(min_b) = min_b {
+                if amount_b < min_b {
+                    log!(
+                env,
+                "Pool Weighted: Get deposit amounts: Deposit amount for asset B ({}) is invalid. It falls below the minimum requirement ({})",
+                amount_b,
+                min_b
+            );
+                    panic_with_error!(env, ContractError::GetDepositAmountsAmountBLessThenMinB);
+                }
+            }
+            amount_b
+        };
+
+        (amount_a, amount_b)
+    }
+
+    pub fn is_initialized(e: &Env) -> bool {
+        e.storage()
+            .persistent()
+            .get(&DataKey::Initialized)
+            .unwrap_or(false)
+    }
+
+    pub fn set_initialized(e: &Env) {
+        e.storage().persistent().set(&DataKey::Initialized, &true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use soroban_sdk::testutils::Address as _;
+
+    #[test]
+    #[should_panic]
+    fn test_get_total_shares_failure() {
+        let env = Env::default();
+        let _ = utils::get_total_shares(&env);
+    }
+
+    #[test]
+    fn test_get_deposit_amounts_pool_balances_zero() {
+        let env = Env::default();
+        let result =
+            utils::get_deposit_amounts(&env, 100, Some(50), 200, Some(50), 0, 0, Decimal::bps(100));
+        assert_eq!(result, (100, 200));
+    }
+
+    #[test]
+    fn test_get_deposit_amounts_keeps_the_pool_ratio() {
+        let env = Env::default();
+        // an 80/20 pool holds the two tokens in any ratio, deposits only have to follow it
+        let result =
+            utils::get_deposit_amounts(&env, 1000, None, 252, None, 8000, 2000, Decimal::bps(100));
+        assert_eq!(result, (1000, 250));
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #7)")]
+    fn test_get_deposit_amounts_exceeds_desired() {
+        let env = Env::default();
+        utils::get_deposit_amounts(&env, 1000, None, 2000, None, 10000, 5000, Decimal::bps(100));
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #10)")]
+    fn test_get_deposit_amounts_below_min_b() {
+        let env = Env::default();
+        utils::get_deposit_amounts(
+            &env,
+            200,
+            None,
+            5000,
+            Some(2000),
+            500,
+            1000,
+            Decimal::bps(120000),
+        );
+    }
+
+    #[test]
+    fn test_max_allowed_slippage() {
+        let env = Env::default();
+        let config = Config {
+            max_allowed_slippage_bps: 100,
+            token_a: Address::generate(&env),
+            token_b: Address::generate(&env),
+            share_token: Address::generate(&env),
+            stake_contract: Address::generate(&env),
+            pool_type: PairType::Weighted,
+            total_fee_bps: 10i64,
+            fee_recipient: Address::generate(&env),
+            max_allowed_spread_bps: 10_i64,
+            max_referral_bps: 10i64,
+            default_slippage_bps: 100i64,
+            protocol_fee_share_bps: 10_000,
+            weight_a_bps: 8_000,
+            weight_b_bps: 2_000,
+        };
+
+        assert_eq!(config.max_allowed_slippage(), Decimal::percent(1));
+    }
+}
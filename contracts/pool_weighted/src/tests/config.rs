@@ -0,0 +1,206 @@
+use soroban_sdk::{testutils::Address as _, Address, BytesN, Env, String};
+use test_case::test_case;
+
+use super::setup::{deploy_token_pair, deploy_weighted_liquidity_pool_contract};
+use crate::storage::{Config, PairType};
+use phoenix::utils::{LiquidityPoolInitInfo, StakeInitInfo, TokenInitInfo, WeightedPoolInitInfo};
+
+#[test]
+fn update_config() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let fee_recipient = Address::generate(&env);
+    let (token1, token2) = deploy_token_pair(&env, &admin);
+    let pool = deploy_weighted_liquidity_pool_contract(
+        &env,
+        admin.clone(),
+        (&token1.address, &token2.address),
+        30,
+        fee_recipient.clone(),
+        (8_000, 2_000),
+    );
+
+    assert_eq!(
+        pool.query_config(),
+        Config {
+            token_a: token1.address.clone(),
+            token_b: token2.address.clone(),
+            share_token: pool.query_share_token_address(),
+            stake_contract: pool.query_stake_contract_address(),
+            pool_type: PairType::Weighted,
+            total_fee_bps: 30,
+            fee_recipient: fee_recipient.clone(),
+            max_allowed_slippage_bps: 5_000,
+            max_allowed_spread_bps: 1_000,
+            max_referral_bps: 5_000,
+            default_slippage_bps: 100,
+            protocol_fee_share_bps: 10_000,
+            weight_a_bps: 8_000,
+            weight_b_bps: 2_000,
+        }
+    );
+
+    let new_fee_recipient = Address::generate(&env);
+    pool.update_config(
+        &None,
+        &Some(100),
+        &Some(new_fee_recipient.clone()),
+        &None,
+        &Some(500),
+        &None,
+        &Some(2_000),
+    );
+
+    let config = pool.query_config();
+    assert_eq!(config.total_fee_bps, 100);
+    assert_eq!(config.fee_recipient, new_fee_recipient);
+    assert_eq!(config.max_allowed_spread_bps, 500);
+    assert_eq!(config.protocol_fee_share_bps, 2_000);
+    assert_eq!((config.weight_a_bps, config.weight_b_bps), (8_000, 2_000));
+}
+
+#[test_case(8_000, 1_000 ; "when weights do not add up")]
+#[test_case(10_000, 0 ; "when a weight is zero")]
+#[test_case(11_000, -1_000 ; "when a weight is negative")]
+#[should_panic(
+    expected = "Pool Weighted: Initialize: Weights must be positive and add up to 10_000 bps"
+)]
+fn initialize_with_invalid_weights_fails(weight_a_bps: i64, weight_b_bps: i64) {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let (token1, token2) = deploy_token_pair(&env, &admin);
+    deploy_weighted_liquidity_pool_contract(
+        &env,
+        admin,
+        (&token1.address, &token2.address),
+        30,
+        None,
+        (weight_a_bps, weight_b_bps),
+    );
+}
+
+#[test]
+#[should_panic(
+    expected = "Pool Weighted: Initialize: First token must be alphabetically smaller than second token"
+)]
+fn initialize_with_unordered_tokens_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let (token1, token2) = deploy_token_pair(&env, &admin);
+    deploy_weighted_liquidity_pool_contract(
+        &env,
+        admin,
+        (&token2.address, &token1.address),
+        30,
+        None,
+        (5_000, 5_000),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Pool Weighted: Initialize: initializing contract twice is not allowed")]
+fn initialize_twice_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let (token1, token2) = deploy_token_pair(&env, &admin);
+    let pool = deploy_weighted_liquidity_pool_contract(
+        &env,
+        admin.clone(),
+        (&token1.address, &token2.address),
+        30,
+        None,
+        (8_000, 2_000),
+    );
+
+    let lp_init_info = LiquidityPoolInitInfo {
+        admin: admin.clone(),
+        swap_fee_bps: 30,
+        fee_recipient: admin.clone(),
+        max_allowed_slippage_bps: 5_000,
+        max_allowed_spread_bps: 1_000,
+        max_referral_bps: 5_000,
+        token_init_info: TokenInitInfo {
+            token_a: token1.address.clone(),
+            token_b: token2.address.clone(),
+        },
+        stake_init_info: StakeInitInfo {
+            min_bond: 10i128,
+            min_reward: 5i128,
+            manager: admin.clone(),
+            max_complexity: 10u32,
+        },
+    };
+    pool.initialize(
+        &BytesN::from_array(&env, &[0u8; 32]),
+        &BytesN::from_array(&env, &[0u8; 32]),
+        &lp_init_info,
+        &admin,
+        &7,
+        &String::from_str(&env, "LP_SHARE_TOKEN"),
+        &String::from_str(&env, "PHOBTCLP"),
+        &100,
+        &WeightedPoolInitInfo {
+            weight_a_bps: 5_000,
+            weight_b_bps: 5_000,
+        },
+    );
+}
+
+#[test]
+fn pause_blocks_swaps_but_not_withdrawals() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let guardian = Address::generate(&env);
+    let (token1, token2) = deploy_token_pair(&env, &admin);
+    let pool = deploy_weighted_liquidity_pool_contract(
+        &env,
+        admin.clone(),
+        (&token1.address, &token2.address),
+        30,
+        None,
+        (8_000, 2_000),
+    );
+
+    let user1 = Address::generate(&env);
+    token1.mint(&user1, &4_000_000);
+    token2.mint(&user1, &1_000_000);
+    pool.provide_liquidity(
+        &user1,
+        &Some(4_000_000),
+        &None,
+        &Some(1_000_000),
+        &None,
+        &None,
+        &None::<u64>,
+    );
+
+    pool.set_guardian(&guardian);
+    pool.pause(&guardian);
+    assert!(pool.query_paused());
+    assert_eq!(pool.query_guardian(), Some(guardian));
+
+    assert!(pool
+        .try_swap(&user1, &None, &token1.address, &1_000, &None, &None, &None)
+        .is_err());
+
+    let share_token = crate::token_contract::Client::new(&env, &pool.query_share_token_address());
+    pool.withdraw_liquidity(&user1, &share_token.balance(&user1), &1, &1, &None);
+
+    pool.unpause(&admin);
+    assert!(!pool.query_paused());
+}
@@ -0,0 +1,185 @@
+use soroban_sdk::{testutils::Address as _, Address, Env};
+
+use super::setup::{deploy_token_pair, deploy_weighted_liquidity_pool_contract};
+use crate::{storage::Asset, token_contract};
+
+#[test]
+fn provide_liquidity_mints_the_weighted_invariant() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let (token1, token2) = deploy_token_pair(&env, &admin);
+    let pool = deploy_weighted_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0,
+        None,
+        (8_000, 2_000),
+    );
+    let share_token = token_contract::Client::new(&env, &pool.query_share_token_address());
+
+    let user1 = Address::generate(&env);
+    token1.mint(&user1, &4_000_000);
+    token2.mint(&user1, &1_000_000);
+    pool.provide_liquidity(
+        &user1,
+        &Some(4_000_000),
+        &None,
+        &Some(1_000_000),
+        &None,
+        &None,
+        &None::<u64>,
+    );
+
+    // 4_000_000^0.8 * 1_000_000^0.2 = 3_031_433.1, minus the locked minimum liquidity
+    let shares = share_token.balance(&user1);
+    assert!((shares - 3_030_433).abs() <= 1);
+    assert_eq!(pool.query_total_issued_lp(), shares + 1_000);
+    assert_eq!(token1.balance(&user1), 0);
+    assert_eq!(token2.balance(&user1), 0);
+
+    let pool_info = pool.query_pool_info();
+    assert_eq!(
+        (pool_info.asset_a.amount, pool_info.asset_b.amount),
+        (4_000_000, 1_000_000)
+    );
+
+    // the next deposit follows the ratio of the reserves and mints proportional shares
+    let user2 = Address::generate(&env);
+    token1.mint(&user2, &400_000);
+    token2.mint(&user2, &101_000);
+    pool.provide_liquidity(
+        &user2,
+        &Some(400_000),
+        &None,
+        &Some(101_000),
+        &None,
+        &None,
+        &None::<u64>,
+    );
+    assert_eq!(token1.balance(&user2), 0);
+    assert_eq!(token2.balance(&user2), 1_000);
+    assert_eq!(share_token.balance(&user2), (shares + 1_000) / 10);
+}
+
+#[test]
+fn withdraw_liquidity_returns_the_share_of_both_reserves() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let (token1, token2) = deploy_token_pair(&env, &admin);
+    let pool = deploy_weighted_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0,
+        None,
+        (2_000, 8_000),
+    );
+    let share_token = token_contract::Client::new(&env, &pool.query_share_token_address());
+
+    let user1 = Address::generate(&env);
+    token1.mint(&user1, &1_000_000);
+    token2.mint(&user1, &4_000_000);
+    pool.provide_liquidity(
+        &user1,
+        &Some(1_000_000),
+        &None,
+        &Some(4_000_000),
+        &None,
+        &None,
+        &None::<u64>,
+    );
+
+    let total_shares = pool.query_total_issued_lp();
+    let shares = share_token.balance(&user1);
+    let (share_a, share_b) = pool.query_share(&(total_shares / 2));
+    let (amount_a, amount_b) = pool.withdraw_liquidity(&user1, &(total_shares / 2), &1, &1, &None);
+    assert_eq!((share_a.amount, share_b.amount), (amount_a, amount_b));
+    assert!((amount_a - 500_000).abs() <= 1);
+    assert!((amount_b - 2_000_000).abs() <= 1);
+
+    pool.withdraw_liquidity(&user1, &(shares - total_shares / 2), &1, &1, &None);
+
+    // only the share of the locked minimum liquidity stays in the pool
+    assert_eq!(share_token.balance(&user1), 0);
+    assert_eq!(pool.query_total_issued_lp(), 1_000);
+    let pool_info = pool.query_pool_info();
+    assert_eq!(
+        pool_info.asset_a,
+        Asset {
+            address: token1.address.clone(),
+            amount: token1.balance(&pool.address),
+        }
+    );
+    assert!(pool_info.asset_a.amount > 0 && pool_info.asset_a.amount < 1_000);
+    assert!(pool_info.asset_b.amount > 0 && pool_info.asset_b.amount < 4_000);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #3)")]
+fn provide_liquidity_with_a_single_token_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let (token1, token2) = deploy_token_pair(&env, &admin);
+    let pool = deploy_weighted_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0,
+        None,
+        (8_000, 2_000),
+    );
+
+    let user1 = Address::generate(&env);
+    token1.mint(&user1, &1_000_000);
+    pool.provide_liquidity(
+        &user1,
+        &Some(1_000_000),
+        &None,
+        &None,
+        &None,
+        &None,
+        &None::<u64>,
+    );
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #24)")]
+fn provide_too_little_first_liquidity_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let (token1, token2) = deploy_token_pair(&env, &admin);
+    let pool = deploy_weighted_liquidity_pool_contract(
+        &env,
+        None,
+        (&token1.address, &token2.address),
+        0,
+        None,
+        (8_000, 2_000),
+    );
+
+    let user1 = Address::generate(&env);
+    token1.mint(&user1, &1_000);
+    token2.mint(&user1, &1_000);
+    pool.provide_liquidity(
+        &user1,
+        &Some(1_000),
+        &None,
+        &Some(1_000),
+        &None,
+        &None,
+        &None::<u64>,
+    );
+}
@@ -0,0 +1,99 @@
+use soroban_sdk::{testutils::Address as _, Address, BytesN, Env, String};
+
+use crate::{
+    contract::{WeightedLiquidityPool, WeightedLiquidityPoolClient},
+    token_contract,
+};
+
+use phoenix::utils::{LiquidityPoolInitInfo, StakeInitInfo, TokenInitInfo, WeightedPoolInitInfo};
+
+pub fn deploy_token_contract<'a>(env: &Env, admin: &Address) -> token_contract::Client<'a> {
+    token_contract::Client::new(env, &env.register_stellar_asset_contract(admin.clone()))
+}
+
+/// Deploys two tokens ordered the way the pool requires them
+pub fn deploy_token_pair<'a>(
+    env: &Env,
+    admin: &Address,
+) -> (token_contract::Client<'a>, token_contract::Client<'a>) {
+    let token1 = deploy_token_contract(env, admin);
+    let token2 = deploy_token_contract(env, admin);
+    if token2.address < token1.address {
+        (token2, token1)
+    } else {
+        (token1, token2)
+    }
+}
+
+pub fn install_token_wasm(env: &Env) -> BytesN<32> {
+    soroban_sdk::contractimport!(
+        file = "../../target/wasm32-unknown-unknown/release/soroban_token_contract.wasm"
+    );
+    env.deployer().upload_contract_wasm(WASM)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn install_stake_wasm(env: &Env) -> BytesN<32> {
+    soroban_sdk::contractimport!(
+        file = "../../target/wasm32-unknown-unknown/release/phoenix_stake.wasm"
+    );
+    env.deployer().upload_contract_wasm(WASM)
+}
+
+pub fn deploy_weighted_liquidity_pool_contract<'a>(
+    env: &Env,
+    admin: impl Into<Option<Address>>,
+    token_a_b: (&Address, &Address),
+    swap_fees: i64,
+    fee_recipient: impl Into<Option<Address>>,
+    weights_bps: (i64, i64),
+) -> WeightedLiquidityPoolClient<'a> {
+    let admin = admin.into().unwrap_or(Address::generate(env));
+    let pool = WeightedLiquidityPoolClient::new(
+        env,
+        &env.register_contract(None, WeightedLiquidityPool {}),
+    );
+    let fee_recipient = fee_recipient
+        .into()
+        .unwrap_or_else(|| Address::generate(env));
+
+    let token_init_info = TokenInitInfo {
+        token_a: token_a_b.0.clone(),
+        token_b: token_a_b.1.clone(),
+    };
+    let stake_init_info = StakeInitInfo {
+        min_bond: 10i128,
+        min_reward: 5i128,
+        manager: Address::generate(env),
+        max_complexity: 10u32,
+    };
+    let stake_wasm_hash = install_stake_wasm(env);
+    let token_wasm_hash = install_token_wasm(env);
+
+    let lp_init_info = LiquidityPoolInitInfo {
+        admin,
+        swap_fee_bps: swap_fees,
+        fee_recipient,
+        max_allowed_slippage_bps: 5_000,
+        max_allowed_spread_bps: 1_000,
+        max_referral_bps: 5_000,
+        token_init_info,
+        stake_init_info,
+    };
+
+    pool.initialize(
+        &stake_wasm_hash,
+        &token_wasm_hash,
+        &lp_init_info,
+        &Address::generate(env),
+        &7u32,
+        &String::from_str(env, "Pool"),
+        &String::from_str(env, "PHOBTC"),
+        &100i64,
+        &WeightedPoolInitInfo {
+            weight_a_bps: weights_bps.0,
+            weight_b_bps: weights_bps.1,
+        },
+    );
+    pool
+}
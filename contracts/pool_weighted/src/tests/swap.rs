@@ -0,0 +1,278 @@
+use soroban_sdk::{testutils::Address as _, Address, Env};
+
+use super::setup::{deploy_token_pair, deploy_weighted_liquidity_pool_contract};
+use crate::{
+    contract::WeightedLiquidityPoolClient,
+    storage::{Referral, SimulateSwapResponse},
+    token_contract,
+};
+
+/// Deploys an 80/20 pool holding 4_000_000 of token A and 1_000_000 of token B, where one token A
+/// is worth one token B
+fn deploy_funded_pool<'a>(
+    env: &Env,
+    admin: &Address,
+    swap_fees: i64,
+    fee_recipient: impl Into<Option<Address>>,
+) -> (
+    WeightedLiquidityPoolClient<'a>,
+    token_contract::Client<'a>,
+    token_contract::Client<'a>,
+) {
+    let (token1, token2) = deploy_token_pair(env, admin);
+    let pool = deploy_weighted_liquidity_pool_contract(
+        env,
+        admin.clone(),
+        (&token1.address, &token2.address),
+        swap_fees,
+        fee_recipient,
+        (8_000, 2_000),
+    );
+
+    let provider = Address::generate(env);
+    token1.mint(&provider, &4_000_000);
+    token2.mint(&provider, &1_000_000);
+    pool.provide_liquidity(
+        &provider,
+        &Some(4_000_000),
+        &None,
+        &Some(1_000_000),
+        &None,
+        &None,
+        &None::<u64>,
+    );
+
+    (pool, token1, token2)
+}
+
+#[test]
+fn simple_swap_follows_the_weights() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let (pool, token1, token2) = deploy_funded_pool(&env, &admin, 0, None);
+
+    let simulated = pool.simulate_swap(&token1.address, &10_000);
+    let trader = Address::generate(&env);
+    token1.mint(&trader, &10_000);
+    let return_amount = pool
+        .swap(
+            &trader,
+            &None,
+            &token1.address,
+            &10_000,
+            &None,
+            &Some(100),
+            &None::<u64>,
+        )
+        .ask_amount;
+
+    // 1_000_000 * (1 - (4_000_000 / 4_010_000)^4) = 9_937.8
+    assert_eq!(return_amount, 9_937);
+    assert_eq!(token2.balance(&trader), return_amount);
+    assert_eq!(
+        simulated,
+        SimulateSwapResponse {
+            ask_amount: 9_937,
+            commission_amount: 0,
+            protocol_fee_amount: 0,
+            spread_amount: 63,
+            total_return: 10_000,
+        }
+    );
+
+    // and back, 4_010_000 * (1 - (990_063 / 1_000_063)^0.25) = 10_062.2
+    token2.mint(&trader, &63);
+    let return_amount = pool
+        .swap(
+            &trader,
+            &None,
+            &token2.address,
+            &10_000,
+            &None,
+            &Some(100),
+            &None::<u64>,
+        )
+        .ask_amount;
+    assert_eq!(return_amount, 10_062);
+
+    let pool_info = pool.query_pool_info();
+    assert_eq!(pool_info.asset_a.amount, token1.balance(&pool.address));
+    assert_eq!(pool_info.asset_b.amount, token2.balance(&pool.address));
+    assert_eq!(pool_info.asset_a.amount, 4_010_000 - 10_062);
+    assert_eq!(pool_info.asset_b.amount, 1_000_063);
+}
+
+#[test]
+fn simulate_reverse_swap_covers_the_ask_amount() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let (pool, token1, token2) = deploy_funded_pool(&env, &admin, 30, None);
+
+    for (ask_asset, offer_asset, ask_amount) in [
+        (&token2.address, &token1.address, 5_000),
+        (&token1.address, &token2.address, 5_000),
+        (&token2.address, &token1.address, 500_000),
+        (&token1.address, &token2.address, 2_000_000),
+    ] {
+        let reverse = pool.simulate_reverse_swap(ask_asset, &ask_amount);
+        let forward = pool.simulate_swap(offer_asset, &reverse.offer_amount);
+        assert!(forward.ask_amount >= ask_amount);
+        assert!(forward.ask_amount - ask_amount <= 2);
+        assert!((forward.commission_amount - reverse.commission_amount).abs() <= 1);
+    }
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #25)")]
+fn simulate_reverse_swap_beyond_the_reserves_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let (pool, _, token2) = deploy_funded_pool(&env, &admin, 30, None);
+
+    pool.simulate_reverse_swap(&token2.address, &1_000_000);
+}
+
+#[test]
+fn swap_fees_are_split_with_the_fee_recipient_and_referral() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let fee_recipient = Address::generate(&env);
+    let (pool, token1, token2) = deploy_funded_pool(&env, &admin, 1_000, fee_recipient.clone());
+    // 40% of the commission goes to the fee recipient
+    pool.update_config(&None, &None, &None, &None, &None, &None, &Some(4_000));
+
+    let trader = Address::generate(&env);
+    let referral = Address::generate(&env);
+    token1.mint(&trader, &10_000);
+    let return_amount = pool
+        .swap(
+            &trader,
+            &Some(Referral {
+                address: referral.clone(),
+                fee_bps: 1_000,
+            }),
+            &token1.address,
+            &10_000,
+            &None,
+            &None,
+            &None::<u64>,
+        )
+        .ask_amount;
+
+    // 10% commission of 9_937, 40% of it to the fee recipient, then 10% of the rest to the referral
+    assert_eq!(token2.balance(&fee_recipient), 397);
+    assert_eq!(token2.balance(&referral), 894);
+    assert_eq!(return_amount, 9_937 - 993 - 894);
+    assert_eq!(token2.balance(&trader), return_amount);
+
+    // the rest of the commission stays in the pool
+    assert_eq!(
+        pool.query_pool_info().asset_b.amount,
+        1_000_000 - return_amount - 894 - 397
+    );
+    assert_eq!(
+        pool.query_pool_info().asset_b.amount,
+        token2.balance(&pool.address)
+    );
+}
+
+#[test]
+#[should_panic(expected = "Pool Weighted: Spread exceeds maximum allowed")]
+fn swap_with_high_spread_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let (pool, token1, _) = deploy_funded_pool(&env, &admin, 0, None);
+
+    let trader = Address::generate(&env);
+    token1.mint(&trader, &500_000);
+    pool.swap(
+        &trader,
+        &None,
+        &token1.address,
+        &500_000,
+        &None,
+        &Some(100),
+        &None::<u64>,
+    );
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #19)")]
+fn swap_below_the_minimum_received_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let (pool, token1, _) = deploy_funded_pool(&env, &admin, 0, None);
+
+    let trader = Address::generate(&env);
+    token1.mint(&trader, &10_000);
+    pool.swap(
+        &trader,
+        &None,
+        &token1.address,
+        &10_000,
+        &Some(9_938),
+        &None,
+        &None::<u64>,
+    );
+}
+
+#[test]
+fn swap_against_empty_pool_fails_with_contract_error() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let admin = Address::generate(&env);
+    let (token1, token2) = deploy_token_pair(&env, &admin);
+    let pool = deploy_weighted_liquidity_pool_contract(
+        &env,
+        admin.clone(),
+        (&token1.address, &token2.address),
+        30,
+        None,
+        (8_000, 2_000),
+    );
+
+    let trader = Address::generate(&env);
+    token1.mint(&trader, &10_000);
+
+    let empty_pool = soroban_sdk::Error::from_contract_error(26);
+    assert_eq!(
+        pool.try_simulate_swap(&token1.address, &10_000),
+        Err(Ok(empty_pool))
+    );
+    assert_eq!(
+        pool.try_simulate_reverse_swap(&token2.address, &1_000),
+        Err(Ok(empty_pool))
+    );
+    assert_eq!(
+        pool.try_swap(
+            &trader,
+            &None,
+            &token1.address,
+            &10_000,
+            &None,
+            &None,
+            &None::<u64>
+        ),
+        Err(Ok(empty_pool))
+    );
+}
@@ -70,6 +70,16 @@ pub trait StakingTrait {
         token_amount: i128,
     );
 
+    // Sets the guardian, an address allowed to pause the contract next to the admin and the owner
+    fn set_guardian(env: Env, guardian: Address);
+
+    // Pauses bonding. Unbonding and withdrawing rewards stay available.
+    // Callable by the admin, the guardian or the owner
+    fn pause(env: Env, sender: Address);
+
+    // Resumes bonding. Callable by the admin only
+    fn unpause(env: Env);
+
     // QUERIES
 
     fn query_config(env: Env) -> ConfigResponse;
@@ -87,6 +97,10 @@ pub trait StakingTrait {
     fn query_distributed_rewards(env: Env, asset: Address) -> u128;
 
     fn query_undistributed_rewards(env: Env, asset: Address) -> u128;
+
+    fn query_paused(env: Env) -> bool;
+
+    fn query_guardian(env: Env) -> Option<Address>;
 }
 
 #[contractimpl]
@@ -150,6 +164,11 @@ impl StakingTrait for Staking {
     }
 
     fn bond(env: Env, sender: Address, tokens: i128) {
+        if utils::is_paused(&env) {
+            log!(&env, "Stake: Bond: The contract is paused!");
+            panic_with_error!(&env, ContractError::ContractPaused);
+        }
+
         sender.require_auth();
 
         let ledger = env.ledger();
@@ -460,6 +479,42 @@ impl StakingTrait for Staking {
             .publish(("fund_reward_distribution", "end_time"), end_time);
     }
 
+    fn set_guardian(env: Env, guardian: Address) {
+        let admin = get_admin(&env);
+        admin.require_auth();
+
+        utils::save_guardian(&env, &guardian);
+
+        env.events()
+            .publish(("set_guardian", "guardian"), &guardian);
+    }
+
+    fn pause(env: Env, sender: Address) {
+        sender.require_auth();
+
+        let is_guardian = utils::get_guardian(&env).map_or(false, |guardian| guardian == sender);
+        if sender != get_admin(&env) && sender != get_config(&env).owner && !is_guardian {
+            log!(
+                &env,
+                "Stake: Pause: You are not authorized to pause the contract!"
+            );
+            panic_with_error!(&env, ContractError::Unauthorized);
+        }
+
+        utils::set_paused(&env, true);
+
+        env.events().publish(("pause", "sender"), &sender);
+    }
+
+    fn unpause(env: Env) {
+        let admin = get_admin(&env);
+        admin.require_auth();
+
+        utils::set_paused(&env, false);
+
+        env.events().publish(("unpause", "sender"), &admin);
+    }
+
     // QUERIES
 
     fn query_config(env: Env) -> ConfigResponse {
@@ -550,6 +605,14 @@ impl StakingTrait for Staking {
         reward_token_client.balance(&env.current_contract_address()) as u128
             - distribution.withdrawable_total
     }
+
+    fn query_paused(env: Env) -> bool {
+        utils::is_paused(&env)
+    }
+
+    fn query_guardian(env: Env) -> Option<Address> {
+        utils::get_guardian(&env)
+    }
 }
 
 #[contractimpl]
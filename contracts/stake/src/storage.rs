@@ -81,6 +81,8 @@ pub mod utils {
         TotalStaked = 1,
         Distributions = 2,
         Initialized = 3,
+        Paused = 4,
+        Guardian = 5,
     }
 
     impl TryFromVal<Env, DataKey> for Val {
@@ -110,6 +112,25 @@ pub mod utils {
         e.storage().instance().get(&DataKey::Admin).unwrap()
     }
 
+    pub fn save_guardian(e: &Env, address: &Address) {
+        e.storage().instance().set(&DataKey::Guardian, address)
+    }
+
+    pub fn get_guardian(e: &Env) -> Option<Address> {
+        e.storage().instance().get(&DataKey::Guardian)
+    }
+
+    pub fn set_paused(e: &Env, paused: bool) {
+        e.storage().instance().set(&DataKey::Paused, &paused)
+    }
+
+    pub fn is_paused(e: &Env) -> bool {
+        e.storage()
+            .instance()
+            .get(&DataKey::Paused)
+            .unwrap_or(false)
+    }
+
     pub fn init_total_staked(e: &Env) {
         e.storage().instance().set(&DataKey::TotalStaked, &0i128);
     }
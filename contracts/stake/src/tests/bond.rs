@@ -410,3 +410,96 @@ fn initialize_staking_contract_should_panic_when_max_complexity_invalid() {
         &0u32,
     );
 }
+
+#[test]
+fn pause_blocks_bonding_but_not_unbonding() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let guardian = Address::generate(&env);
+    let manager = Address::generate(&env);
+    let owner = Address::generate(&env);
+    let lp_token = deploy_token_contract(&env, &admin);
+
+    let staking = deploy_staking_contract(
+        &env,
+        admin.clone(),
+        &lp_token.address,
+        &manager,
+        &owner,
+        &DEFAULT_COMPLEXITY,
+    );
+
+    lp_token.mint(&user, &10_000);
+    staking.bond(&user, &10_000);
+
+    assert!(!staking.query_paused());
+    staking.set_guardian(&guardian);
+    assert_eq!(staking.query_guardian(), Some(guardian.clone()));
+
+    staking.pause(&guardian);
+    assert!(staking.query_paused());
+
+    // stakers can always leave
+    staking.unbond(&user, &10_000, &0);
+    assert_eq!(lp_token.balance(&user), 10_000);
+    assert_eq!(staking.query_total_staked(), 0);
+
+    staking.unpause();
+    assert!(!staking.query_paused());
+    staking.bond(&user, &10_000);
+    assert_eq!(staking.query_total_staked(), 10_000);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #13)")]
+fn bond_when_paused_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let user = Address::generate(&env);
+    let manager = Address::generate(&env);
+    let owner = Address::generate(&env);
+    let lp_token = deploy_token_contract(&env, &admin);
+
+    let staking = deploy_staking_contract(
+        &env,
+        admin.clone(),
+        &lp_token.address,
+        &manager,
+        &owner,
+        &DEFAULT_COMPLEXITY,
+    );
+
+    // the owner is the factory that deployed the contract
+    staking.pause(&owner);
+
+    lp_token.mint(&user, &10_000);
+    staking.bond(&user, &10_000);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #5)")]
+fn pause_by_stranger_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let manager = Address::generate(&env);
+    let owner = Address::generate(&env);
+    let lp_token = deploy_token_contract(&env, &admin);
+
+    let staking = deploy_staking_contract(
+        &env,
+        admin.clone(),
+        &lp_token.address,
+        &manager,
+        &owner,
+        &DEFAULT_COMPLEXITY,
+    );
+
+    staking.pause(&Address::generate(&env));
+}
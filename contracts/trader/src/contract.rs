@@ -142,14 +142,17 @@ impl TraderTrait for Trader {
             token_client.balance(&sender)
         };
 
-        let amount_swapped = lp_client.swap(
-            &env.current_contract_address(),
-            &token_to_swap,
-            &amount,
-            &None,
-            &max_spread_bps,
-            &deadline,
-        );
+        let amount_swapped = lp_client
+            .swap(
+                &env.current_contract_address(),
+                &None,
+                &token_to_swap,
+                &amount,
+                &None,
+                &max_spread_bps,
+                &deadline,
+            )
+            .ask_amount;
 
         env.events()
             .publish(("Trader: Trade Token", "user: "), &sender);
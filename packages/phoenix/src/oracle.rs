@@ -0,0 +1,326 @@
+use soroban_decimal::Decimal;
+use soroban_sdk::{contracttype, Env, Vec, U256};
+
+/// Minimum number of seconds between two observations kept in the ring buffer.
+pub const OBSERVATION_INTERVAL: u64 = 60;
+/// Maximum number of observations kept in the ring buffer. Together with
+/// `OBSERVATION_INTERVAL` this allows TWAP windows of at least two hours.
+pub const OBSERVATIONS_CAPACITY: u32 = 120;
+
+/// Snapshot of the cumulative price accumulators of a two token pool.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Observation {
+    /// Ledger timestamp the accumulators are valid for
+    pub timestamp: u64,
+    /// Sum over time of the price of token A in token B (as decimal atomics) times the seconds
+    /// that price held. Wraps around on overflow, only differences between two snapshots are
+    /// meaningful
+    pub price_a_cumulative: i128,
+    /// Sum over time of the price of token B in token A (as decimal atomics) times the seconds
+    /// that price held. Wraps around on overflow, like `price_a_cumulative`
+    pub price_b_cumulative: i128,
+}
+
+impl Observation {
+    pub fn new(timestamp: u64) -> Self {
+        Observation {
+            timestamp,
+            price_a_cumulative: 0,
+            price_b_cumulative: 0,
+        }
+    }
+
+    /// Advances the accumulators to `timestamp`, assuming `prices` (price of A in B, price of B
+    /// in A) held since the last update. `prices` is `None` while the pool has no liquidity.
+    ///
+    /// The accumulators wrap around instead of overflowing, so extreme prices can never make
+    /// the update, and with it every operation of the pool, panic.
+    pub fn accumulate(&self, timestamp: u64, prices: Option<(Decimal, Decimal)>) -> Self {
+        if timestamp <= self.timestamp {
+            return self.clone();
+        }
+
+        let elapsed = (timestamp - self.timestamp) as i128;
+        let (price_a, price_b) = prices.map_or((0, 0), |(price_a, price_b)| {
+            (price_a.atomics(), price_b.atomics())
+        });
+
+        Observation {
+            timestamp,
+            price_a_cumulative: self
+                .price_a_cumulative
+                .wrapping_add(price_a.wrapping_mul(elapsed)),
+            price_b_cumulative: self
+                .price_b_cumulative
+                .wrapping_add(price_b.wrapping_mul(elapsed)),
+        }
+    }
+
+    fn cumulative(&self, price_of_a: bool) -> i128 {
+        if price_of_a {
+            self.price_a_cumulative
+        } else {
+            self.price_b_cumulative
+        }
+    }
+
+    /// Accumulated price since `earlier`, correct across a wrap-around of the accumulators as
+    /// long as the accumulated value itself fits into 128 bits.
+    fn cumulative_since(&self, earlier: &Observation, price_of_a: bool) -> u128 {
+        (self.cumulative(price_of_a) as u128).wrapping_sub(earlier.cumulative(price_of_a) as u128)
+    }
+}
+
+/// `numerator / denominator` as a decimal, saturating at the largest decimal instead of
+/// overflowing for huge or heavily skewed reserves. A zero denominator saturates as well.
+pub fn saturating_price(env: &Env, numerator: &U256, denominator: &U256) -> Decimal {
+    if *denominator == U256::from_u32(env, 0) {
+        return Decimal::raw(i128::MAX);
+    }
+
+    let atomics = numerator
+        .mul(&U256::from_u128(env, Decimal::one().atomics() as u128))
+        .div(denominator)
+        .to_u128()
+        .and_then(|atomics| i128::try_from(atomics).ok())
+        .unwrap_or(i128::MAX);
+
+    Decimal::raw(atomics)
+}
+
+/// Appends `accumulator` to the ring buffer once `OBSERVATION_INTERVAL` has passed since the
+/// newest observation, dropping the oldest one when the buffer is full.
+/// Returns whether the buffer changed.
+pub fn record_observation(observations: &mut Vec<Observation>, accumulator: &Observation) -> bool {
+    let is_due = observations.last().map_or(true, |last| {
+        accumulator.timestamp >= last.timestamp + OBSERVATION_INTERVAL
+    });
+    if !is_due {
+        return false;
+    }
+
+    observations.push_back(accumulator.clone());
+    if observations.len() > OBSERVATIONS_CAPACITY {
+        observations.pop_front();
+    }
+
+    true
+}
+
+/// Time-weighted average price of token A (`price_of_a`) or token B over the `window` seconds
+/// that end at `current`, as decimal atomics.
+///
+/// `accumulator` is the last stored state of the accumulators, `current` the same state
+/// advanced to the present. The cumulative value at the start of the window is interpolated
+/// between the two snapshots surrounding it. Returns `None` when the window is empty or
+/// reaches further back than the oldest observation.
+pub fn compute_twap(
+    observations: &Vec<Observation>,
+    accumulator: &Observation,
+    current: &Observation,
+    window: u64,
+    price_of_a: bool,
+) -> Option<i128> {
+    if window == 0 || window > current.timestamp {
+        return None;
+    }
+    let window_start = current.timestamp - window;
+
+    // walk the snapshots from the newest to the oldest
+    let mut snapshots = observations.clone();
+    if snapshots
+        .last()
+        .map_or(true, |last| accumulator.timestamp > last.timestamp)
+    {
+        snapshots.push_back(accumulator.clone());
+    }
+
+    let mut next = current.clone();
+    for snapshot in snapshots.iter().rev() {
+        if snapshot.timestamp <= window_start {
+            let span = (next.timestamp - snapshot.timestamp) as u128;
+            // the accumulated price between the snapshot and the start of the window, split
+            // into quotient and remainder so the interpolation can't overflow
+            let before_window = if span == 0 {
+                0
+            } else {
+                let accumulated = next.cumulative_since(&snapshot, price_of_a);
+                let offset = (window_start - snapshot.timestamp) as u128;
+                accumulated / span * offset + accumulated % span * offset / span
+            };
+            let in_window = current
+                .cumulative_since(&snapshot, price_of_a)
+                .wrapping_sub(before_window);
+
+            return i128::try_from(in_window / window as u128).ok();
+        }
+        next = snapshot;
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use soroban_sdk::{vec, Env};
+
+    #[test]
+    fn accumulate_adds_price_times_elapsed_time() {
+        let observation = Observation::new(100);
+
+        let accumulated =
+            observation.accumulate(110, Some((Decimal::percent(200), Decimal::percent(50))));
+
+        assert_eq!(
+            accumulated,
+            Observation {
+                timestamp: 110,
+                price_a_cumulative: 20_000_000_000_000_000_000,
+                price_b_cumulative: 5_000_000_000_000_000_000,
+            }
+        );
+        // no time passed, nothing to accumulate
+        assert_eq!(
+            accumulated.accumulate(110, Some((Decimal::one(), Decimal::one()))),
+            accumulated
+        );
+        // an empty pool has no price
+        assert_eq!(
+            accumulated.accumulate(120, None).price_a_cumulative,
+            20_000_000_000_000_000_000
+        );
+    }
+
+    #[test]
+    fn record_observation_respects_interval_and_capacity() {
+        let env = Env::default();
+        let mut observations = vec![&env];
+
+        record_observation(&mut observations, &Observation::new(0));
+        record_observation(
+            &mut observations,
+            &Observation::new(OBSERVATION_INTERVAL - 1),
+        );
+        assert_eq!(observations.len(), 1);
+
+        for i in 1..=OBSERVATIONS_CAPACITY as u64 {
+            record_observation(
+                &mut observations,
+                &Observation::new(i * OBSERVATION_INTERVAL),
+            );
+        }
+        assert_eq!(observations.len(), OBSERVATIONS_CAPACITY);
+        assert_eq!(
+            observations.first().unwrap().timestamp,
+            OBSERVATION_INTERVAL
+        );
+    }
+
+    #[test]
+    fn compute_twap_interpolates_start_of_window() {
+        let env = Env::default();
+
+        // price of A is 1 during [0, 100) and 3 during [100, 200)
+        let first = Observation::new(0);
+        let second = first.accumulate(100, Some((Decimal::one(), Decimal::one())));
+        let current = second.accumulate(200, Some((Decimal::percent(300), Decimal::one())));
+        let observations = vec![&env, first, second.clone()];
+
+        assert_eq!(
+            compute_twap(&observations, &second, &current, 200, true),
+            Some(2_000_000_000_000_000_000)
+        );
+        assert_eq!(
+            compute_twap(&observations, &second, &current, 100, true),
+            Some(3_000_000_000_000_000_000)
+        );
+        // window [50, 200): 50s at 1 and 100s at 3
+        assert_eq!(
+            compute_twap(&observations, &second, &current, 150, true),
+            Some(2_333_333_333_333_333_333)
+        );
+        assert_eq!(
+            compute_twap(&observations, &second, &current, 150, false),
+            Some(1_000_000_000_000_000_000)
+        );
+    }
+
+    #[test]
+    fn compute_twap_across_wrapped_accumulators() {
+        let env = Env::default();
+
+        // the same prices as above, but the accumulator of A wraps around after the first second
+        let first = Observation {
+            timestamp: 0,
+            price_a_cumulative: i128::MAX - 1_000_000_000_000_000_000,
+            price_b_cumulative: 0,
+        };
+        let second = first.accumulate(100, Some((Decimal::one(), Decimal::one())));
+        assert!(second.price_a_cumulative < 0);
+        let current = second.accumulate(200, Some((Decimal::percent(300), Decimal::one())));
+        let observations = vec![&env, first, second.clone()];
+
+        assert_eq!(
+            compute_twap(&observations, &second, &current, 200, true),
+            Some(2_000_000_000_000_000_000)
+        );
+        assert_eq!(
+            compute_twap(&observations, &second, &current, 150, true),
+            Some(2_333_333_333_333_333_333)
+        );
+    }
+
+    #[test]
+    fn accumulate_extreme_price_does_not_overflow() {
+        let observation = Observation::new(0);
+
+        // a saturated price held for a year
+        let price = Decimal::raw(i128::MAX);
+        let accumulated = observation.accumulate(365 * 24 * 60 * 60, Some((price, price)));
+
+        assert_eq!(accumulated.timestamp, 365 * 24 * 60 * 60);
+    }
+
+    #[test]
+    fn saturating_price_of_extreme_ratios() {
+        let env = Env::default();
+        let price = |numerator: u128, denominator: u128| {
+            saturating_price(
+                &env,
+                &U256::from_u128(&env, numerator),
+                &U256::from_u128(&env, denominator),
+            )
+        };
+
+        assert_eq!(price(3, 2), Decimal::percent(150));
+        // reserves above i128::MAX / 10^18 still give the exact price
+        assert_eq!(
+            price(10u128.pow(30), 2 * 10u128.pow(30)),
+            Decimal::percent(50)
+        );
+        assert_eq!(price(10u128.pow(30), 1), Decimal::raw(i128::MAX));
+        assert_eq!(price(1, 0), Decimal::raw(i128::MAX));
+        assert_eq!(price(1, 10u128.pow(30)), Decimal::zero());
+    }
+
+    #[test]
+    fn compute_twap_without_enough_history() {
+        let env = Env::default();
+
+        let first = Observation::new(100);
+        let current = first.accumulate(200, Some((Decimal::one(), Decimal::one())));
+        let observations = vec![&env, first.clone()];
+
+        assert_eq!(compute_twap(&observations, &first, &current, 0, true), None);
+        assert_eq!(
+            compute_twap(&observations, &first, &current, 101, true),
+            None
+        );
+        assert_eq!(
+            compute_twap(&observations, &first, &current, 100, true),
+            Some(1_000_000_000_000_000_000)
+        );
+    }
+}
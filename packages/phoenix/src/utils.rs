@@ -1,5 +1,5 @@
 use soroban_decimal::Decimal;
-use soroban_sdk::{contracttype, Address};
+use soroban_sdk::{contracttype, Address, Vec};
 
 // Validate if int value is bigger then 0
 #[macro_export]
@@ -67,17 +67,105 @@ pub struct LiquidityPoolInitInfo {
     pub stake_init_info: StakeInitInfo,
 }
 
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConcentratedPoolInitInfo {
+    /// Only ticks that are a multiple of the spacing can be used as position boundaries
+    pub tick_spacing: u32,
+    /// The tick of the starting price, where the price of token A in token B is 1.0001^tick
+    pub initial_tick: i32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WeightedPoolInitInfo {
+    /// The weight (in bps) of token A in the pool value, an 80/20 pool has 8_000 here
+    pub weight_a_bps: i64,
+    /// The weight (in bps) of token B in the pool value, both weights add up to 10_000
+    pub weight_b_bps: i64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StableMultiPoolInitInfo {
+    /// The coins of the pool after token A and token B, all coins of the pool sorted ascending
+    pub additional_tokens: Vec<Address>,
+}
+
 #[contracttype]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[repr(u32)]
 pub enum PoolType {
     Xyk = 0,
     Stable = 1,
+    Concentrated = 2,
+    Weighted = 3,
+    StableMulti = 4,
+}
+
+/// The parameters of a new pool that depend on its type
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PoolInitInfo {
+    Xyk,
+    /// The amplification parameter (AMP) of the pool
+    Stable(u64),
+    Concentrated(ConcentratedPoolInitInfo),
+    Weighted(WeightedPoolInitInfo),
+    /// The amplification parameter (AMP) and the coins of the pool after token A and token B
+    StableMulti(u64, StableMultiPoolInitInfo),
+}
+
+impl PoolInitInfo {
+    pub fn pool_type(&self) -> PoolType {
+        match self {
+            PoolInitInfo::Xyk => PoolType::Xyk,
+            PoolInitInfo::Stable(_) => PoolType::Stable,
+            PoolInitInfo::Concentrated(_) => PoolType::Concentrated,
+            PoolInitInfo::Weighted(_) => PoolType::Weighted,
+            PoolInitInfo::StableMulti(..) => PoolType::StableMulti,
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use soroban_sdk::Env;
+
+    #[test]
+    fn pool_init_info_pool_type() {
+        let env = Env::default();
+
+        assert_eq!(PoolInitInfo::Xyk.pool_type(), PoolType::Xyk);
+        assert_eq!(PoolInitInfo::Stable(10).pool_type(), PoolType::Stable);
+        assert_eq!(
+            PoolInitInfo::Concentrated(ConcentratedPoolInitInfo {
+                tick_spacing: 10,
+                initial_tick: 0,
+            })
+            .pool_type(),
+            PoolType::Concentrated
+        );
+        assert_eq!(
+            PoolInitInfo::Weighted(WeightedPoolInitInfo {
+                weight_a_bps: 8_000,
+                weight_b_bps: 2_000,
+            })
+            .pool_type(),
+            PoolType::Weighted
+        );
+        assert_eq!(
+            PoolInitInfo::StableMulti(
+                10,
+                StableMultiPoolInitInfo {
+                    additional_tokens: Vec::new(&env),
+                }
+            )
+            .pool_type(),
+            PoolType::StableMulti
+        );
+    }
 
     #[test]
     fn test_validate_int_parameters() {